@@ -0,0 +1,128 @@
+// game.rs
+use rust_platformer_engine::engine::assets::Sound;
+use rust_platformer_engine::engine::audio::Bus;
+use rust_platformer_engine::{AssetManifest, Ctx, Engine, Game, GameEvent};
+use std::sync::Arc;
+
+/// Tiled exports for the levels offered on the level-select menu, in order.
+const LEVEL_PATHS: [&str; 2] = ["assets/levels/level1.json", "assets/levels/level2.json"];
+
+/// Aseprite export describing the dino's sprite sheet frames and animations.
+const PLAYER_SHEET_PATH: &str = "assets/character/sheets/DinoSprites.json";
+
+/// The dino's texture, one of the sheet's four color variants.
+const PLAYER_TEXTURE_PATH: &str = "assets/character/sheets/DinoSprites - tard.png";
+
+/// Music looped while playing.
+const MUSIC_PATH: &str = "assets/audio/music.wav";
+
+/// Effect played where the player appears at the start of a level or after losing a life.
+const SPAWN_EFFECT: &str = "spawn_sparkle";
+
+/// Effect played where the player loses a life.
+const DESPAWN_EFFECT: &str = "despawn_poof";
+
+/// Effect played where a projectile hits something.
+const IMPACT_EFFECT: &str = "projectile_hit";
+
+/// Effect played where the player starts a swim-dash.
+const SWIM_DASH_EFFECT: &str = "swim_dash";
+
+/// Effect played where the player breathes again after holding their breath.
+const BREATH_EFFECT: &str = "air_bubbles";
+
+/// Effect played around the player as they finish a level objective.
+const OBJECTIVE_EFFECT: &str = "pickup_shine";
+
+/// Sound played when the player bounces off a jelly platform.
+const BOUNCE_SOUND_PATH: &str = "assets/audio/bounce.wav";
+
+/// Sound played when the player swim-dashes.
+const SWIM_DASH_SOUND_PATH: &str = "assets/audio/swim_dash.wav";
+
+/// Sound played on the frames where the dino's foot comes down.
+const FOOTSTEP_SOUND_PATH: &str = "assets/audio/footstep.wav";
+
+/// Sound played each second the player's breath is running low.
+const OXYGEN_WARNING_SOUND_PATH: &str = "assets/audio/oxygen_warning.wav";
+
+/// The dinosaur platformer: the dino running through its levels, with its own effects
+/// and sounds.
+pub struct Dino {
+    /// Played on bouncing off jelly platforms, if it loaded.
+    bounce_sound: Option<Arc<Sound>>,
+    /// Played on swim-dashing, if it loaded.
+    swim_dash_sound: Option<Arc<Sound>>,
+    /// Played while running out of breath, if it loaded.
+    oxygen_warning_sound: Option<Arc<Sound>>,
+    /// Played on each step, if it loaded.
+    footstep_sound: Option<Arc<Sound>>,
+}
+
+impl Dino {
+    /// Creates the game, with its sounds loaded by `init`.
+    pub fn new() -> Self {
+        Self {
+            bounce_sound: None,
+            swim_dash_sound: None,
+            oxygen_warning_sound: None,
+            footstep_sound: None,
+        }
+    }
+}
+
+impl Game for Dino {
+    fn assets(&self) -> AssetManifest {
+        AssetManifest {
+            levels: LEVEL_PATHS.iter().map(ToString::to_string).collect(),
+            music: Some(MUSIC_PATH.to_string()),
+            ..AssetManifest::new(PLAYER_SHEET_PATH, PLAYER_TEXTURE_PATH)
+        }
+    }
+
+    fn init(&mut self, engine: &mut Engine) {
+        self.bounce_sound = engine.load_sound(BOUNCE_SOUND_PATH);
+        self.swim_dash_sound = engine.load_sound(SWIM_DASH_SOUND_PATH);
+        self.oxygen_warning_sound = engine.load_sound(OXYGEN_WARNING_SOUND_PATH);
+        self.footstep_sound = engine.load_sound(FOOTSTEP_SOUND_PATH);
+    }
+
+    fn on_event(&mut self, ctx: &mut Ctx, event: GameEvent) {
+        match event {
+            GameEvent::Spawned { position } => ctx.effects.play(SPAWN_EFFECT, position.0, position.1),
+            GameEvent::LifeLost { position } => ctx.effects.play(DESPAWN_EFFECT, position.0, position.1),
+            GameEvent::ProjectileHit { position, .. } => ctx.effects.play(IMPACT_EFFECT, position.0, position.1),
+            GameEvent::Bounced => {
+                if let Some(sound) = &self.bounce_sound {
+                    ctx.audio.play(sound, Bus::Sfx, 0.8, false);
+                }
+            }
+            GameEvent::SwimDashed { position } => {
+                ctx.effects.play(SWIM_DASH_EFFECT, position.0, position.1);
+                if let Some(sound) = &self.swim_dash_sound {
+                    ctx.audio.play(sound, Bus::Sfx, 0.7, false);
+                }
+            }
+            // Louder as the last seconds run out
+            GameEvent::OxygenLow { seconds_left } => {
+                if let Some(sound) = &self.oxygen_warning_sound {
+                    let volume = if seconds_left <= 1 { 0.9 } else { 0.6 };
+                    ctx.audio.play(sound, Bus::Sfx, volume, false);
+                }
+            }
+            GameEvent::Breathed { position } => ctx.effects.play(BREATH_EFFECT, position.0, position.1),
+            // Quiet steps, and none while swimming
+            GameEvent::AnimationEvent { name: "footstep", .. } => {
+                if let (Some(sound), false) = (&self.footstep_sound, ctx.game_state.is_swimming()) {
+                    ctx.audio.play(sound, Bus::Sfx, 0.35, false);
+                }
+            }
+            GameEvent::AnimationEvent { .. } => {}
+            GameEvent::ObjectiveCompleted { .. } => {
+                let (x, y) = (ctx.game_state.player_x, ctx.game_state.player_y);
+                ctx.effects.play(OBJECTIVE_EFFECT, x, y);
+            }
+            GameEvent::ObjectiveProgressed { .. } | GameEvent::SignRead { .. } => {}
+        }
+    }
+}
@@ -0,0 +1,29 @@
+// main.rs
+mod game;
+
+use rust_platformer_engine::App;
+
+/// Runs the dinosaur platformer on the engine, from the repository's root so its
+/// assets are found, with `cargo run --example dino`. Adding `-- --seed <seed>` plays
+/// the run out from that random seed.
+fn main() {
+    env_logger::init();
+
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let app = match args.iter().map(String::as_str).collect::<Vec<_>>().as_slice() {
+        [] => App::new(game::Dino::new()),
+        ["--seed", seed] => match seed.parse() {
+            Ok(seed) => App::new(game::Dino::new()).with_seed(seed),
+            Err(e) => {
+                eprintln!("Invalid seed '{}': {}", seed, e);
+                std::process::exit(1);
+            }
+        },
+        _ => {
+            eprintln!("Usage: dino [--seed <seed>]");
+            std::process::exit(2);
+        }
+    };
+    log::info!("Starting the game loop...");
+    app.run();
+}
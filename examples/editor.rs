@@ -0,0 +1,133 @@
+// editor.rs
+//! A level inspector built on the engine: the player flies freely through the level
+//! with noclip, and the tile and objects under the cursor are listed in a panel.
+//! - The mouse wheel zooms the camera in and out.
+//! - Left-click moves the player to the cursor.
+//!
+//! Run from the repository's root, where the assets are, with
+//! `cargo run --example editor`, optionally followed by the path of a level to open.
+use rust_platformer_engine::engine::camera::CameraShot;
+use rust_platformer_engine::engine::renderer::layer::RenderLayer;
+use rust_platformer_engine::engine::renderer::text::{TextAlign, TextStyle};
+use rust_platformer_engine::engine::scene::Scene;
+use rust_platformer_engine::engine::ui::{Rect, Ui};
+use rust_platformer_engine::engine::{Camera, GameState};
+use rust_platformer_engine::{App, AssetManifest, Ctx, Engine, Game, WindowSettings};
+use winit::event::MouseButton;
+
+/// The level opened when none is given.
+const DEFAULT_LEVEL: &str = "assets/levels/level1.json";
+
+/// Aseprite export describing the player's sprite sheet frames and animations.
+const PLAYER_SHEET_PATH: &str = "assets/character/sheets/DinoSprites.json";
+
+/// The player's texture, cut into frames by the sprite sheet.
+const PLAYER_TEXTURE_PATH: &str = "assets/character/sheets/DinoSprites - tard.png";
+
+/// How much each step of the mouse wheel zooms by.
+const ZOOM_STEP: f32 = 1.1;
+
+/// Furthest out and in the camera zooms.
+const ZOOM_RANGE: (f32, f32) = (0.25, 4.0);
+
+/// The inspector, holding what it found under the cursor this frame.
+struct Editor {
+    /// Path of the level being inspected.
+    level: String,
+    /// Magnification the camera is held at.
+    zoom: f32,
+    /// Lines describing what's under the cursor.
+    inspected: Vec<String>,
+}
+
+impl Editor {
+    fn new(level: String) -> Self {
+        Self {
+            level,
+            zoom: 1.0,
+            inspected: Vec::new(),
+        }
+    }
+}
+
+impl Game for Editor {
+    fn assets(&self) -> AssetManifest {
+        AssetManifest {
+            levels: vec![self.level.clone()],
+            ..AssetManifest::new(PLAYER_SHEET_PATH, PLAYER_TEXTURE_PATH)
+        }
+    }
+
+    fn init(&mut self, engine: &mut Engine) {
+        engine.game_state.cheats.noclip = true;
+    }
+
+    fn update(&mut self, ctx: &mut Ctx, _delta_time: f32) {
+        let scroll = ctx.input.scroll();
+        if scroll != 0.0 {
+            self.zoom = (self.zoom * ZOOM_STEP.powf(scroll)).clamp(ZOOM_RANGE.0, ZOOM_RANGE.1);
+            ctx.camera.request_shot(CameraShot {
+                zoom: self.zoom,
+                focus: None,
+                duration: None,
+            });
+        }
+
+        self.inspected.clear();
+        let Some(cursor) = ctx.input.cursor_position() else {
+            return;
+        };
+        let (x, y) = ctx.camera.screen_to_world(cursor);
+        if ctx.input.is_mouse_just_pressed(MouseButton::Left) {
+            ctx.game_state.teleport(x, y);
+        }
+
+        self.inspected.push(format!("X {:.2} Y {:.2}", x, y));
+        match ctx.level.tile_map.tile_at(x, y) {
+            Some(tile) => self.inspected.push(format!("TILE {}", tile.tile_index)),
+            None => self.inspected.push("NO TILE".to_string()),
+        }
+        for object in ctx.level.objects_at(x, y) {
+            let name = if object.name.is_empty() { format!("#{}", object.id) } else { object.name.clone() };
+            self.inspected.push(format!("{} {}", object.kind.to_uppercase(), name.to_uppercase()));
+        }
+    }
+
+    fn render(&mut self, ui: &mut Ui, _game_state: &GameState, _camera: &Camera) {
+        if self.inspected.is_empty() {
+            return;
+        }
+        let style = TextStyle {
+            height: 0.05,
+            color: [1.0, 1.0, 0.7, 1.0],
+            z: RenderLayer::Hud.depth(3),
+            align: TextAlign::Left,
+        };
+        let text = self.inspected.join("\n");
+
+        // A backing panel sized to the text, in the top-right corner, in front of the
+        // engine's banners
+        let margin = 0.03;
+        let width = ui.text_renderer().width(&text, style.height) + 2.0 * margin;
+        let height = ui.text_renderer().line_spacing(style.height) * self.inspected.len() as f32 + margin;
+        let (right, top) = (0.98, 0.95);
+        ui.panel(
+            Rect::new(right - width / 2.0, top - height / 2.0, width, height),
+            RenderLayer::Hud.depth(2),
+            [0.0, 0.0, 0.0, 0.6],
+        );
+        ui.label(&text, (right - width + margin, top - margin - style.height / 2.0), &style);
+    }
+}
+
+fn main() {
+    env_logger::init();
+    let level = std::env::args().nth(1).unwrap_or_else(|| DEFAULT_LEVEL.to_string());
+    App::new(Editor::new(level.clone()))
+        .with_window(WindowSettings {
+            title: format!("Level Inspector - {}", level),
+            ..Default::default()
+        })
+        .with_initial_scene(Scene::Level(level))
+        .run();
+}
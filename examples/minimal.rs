@@ -0,0 +1,36 @@
+// minimal.rs
+//! The least a game needs: a level and a player to run through it on the engine,
+//! with no logic of its own, starting straight in the level.
+//!
+//! Run from the repository's root, where the assets are, with
+//! `cargo run --example minimal`.
+use rust_platformer_engine::engine::scene::Scene;
+use rust_platformer_engine::{App, AssetManifest, Game};
+
+/// Tiled export of the level played.
+const LEVEL_PATH: &str = "assets/levels/level1.json";
+
+/// Aseprite export describing the player's sprite sheet frames and animations.
+const PLAYER_SHEET_PATH: &str = "assets/character/sheets/DinoSprites.json";
+
+/// The player's texture, cut into frames by the sprite sheet.
+const PLAYER_TEXTURE_PATH: &str = "assets/character/sheets/DinoSprites - tard.png";
+
+/// A game adding nothing to the engine.
+struct Minimal;
+
+impl Game for Minimal {
+    fn assets(&self) -> AssetManifest {
+        AssetManifest {
+            levels: vec![LEVEL_PATH.to_string()],
+            ..AssetManifest::new(PLAYER_SHEET_PATH, PLAYER_TEXTURE_PATH)
+        }
+    }
+}
+
+fn main() {
+    env_logger::init();
+    App::new(Minimal)
+        .with_initial_scene(Scene::Level(LEVEL_PATH.to_string()))
+        .run();
+}
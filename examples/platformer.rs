@@ -0,0 +1,198 @@
+// platformer.rs
+//! A platformer adding logic of its own to the engine's through the `Game` hooks:
+//! - a ghost entity replaying the player's last life, drawn over the world,
+//! - the camera pulling back while the player runs,
+//! - a timer for the current life in the corner of the screen,
+//!
+//! and a sound on every jump, added as a `System` of its own.
+//!
+//! Run from the repository's root, where the assets are, with
+//! `cargo run --example platformer`.
+use rust_platformer_engine::engine::assets::Sound;
+use rust_platformer_engine::engine::audio::Bus;
+use rust_platformer_engine::engine::camera::CameraShot;
+use rust_platformer_engine::engine::collision::Aabb;
+use rust_platformer_engine::engine::renderer::layer::RenderLayer;
+use rust_platformer_engine::engine::renderer::text::{TextAlign, TextStyle};
+use rust_platformer_engine::engine::scene::Scene;
+use rust_platformer_engine::engine::ui::{Rect, Ui};
+use rust_platformer_engine::engine::{Camera, GameState};
+use rust_platformer_engine::{App, AssetManifest, Ctx, Engine, Game, GameEvent, System};
+use std::sync::Arc;
+
+/// Tiled export of the level played.
+const LEVEL_PATH: &str = "assets/levels/level1.json";
+
+/// Aseprite export describing the player's sprite sheet frames and animations.
+const PLAYER_SHEET_PATH: &str = "assets/character/sheets/DinoSprites.json";
+
+/// The player's texture, cut into frames by the sprite sheet.
+const PLAYER_TEXTURE_PATH: &str = "assets/character/sheets/DinoSprites - tard.png";
+
+/// Sound played as the player leaves the ground upward.
+const JUMP_SOUND_PATH: &str = "assets/audio/bounce.wav";
+
+/// Horizontal speed above which the player counts as running, between the walking
+/// and running top speeds.
+const RUN_SPEED: f32 = 1.2;
+
+/// The framing held while the player runs, zoomed out to see further ahead.
+const RUN_SHOT: CameraShot = CameraShot {
+    zoom: 0.8,
+    focus: None,
+    duration: None,
+};
+
+/// Color of the ghost, see-through so the world shows behind it.
+const GHOST_COLOR: [f32; 4] = [0.6, 0.8, 1.0, 0.35];
+
+/// The player's path through their previous life, played back alongside the current one.
+struct Ghost {
+    /// The player's bounds each frame of the recorded life.
+    path: Vec<Aabb>,
+    /// The frame of the path being shown.
+    frame: usize,
+}
+
+/// The platformer, holding the state of its own additions.
+struct Platformer {
+    /// The player's bounds each frame of the current life.
+    recording: Vec<Aabb>,
+    ghost: Option<Ghost>,
+    /// Whether the camera is pulled back for running.
+    running: bool,
+    /// Game time the current life has lasted, in seconds.
+    life_time: f32,
+    /// Whether the game was updated since it was last drawn; it isn't on the menu,
+    /// while paused or rewinding, or between scenes, and the additions are hidden then.
+    updated: bool,
+}
+
+impl Platformer {
+    fn new() -> Self {
+        Self {
+            recording: Vec::new(),
+            ghost: None,
+            running: false,
+            life_time: 0.0,
+            updated: false,
+        }
+    }
+}
+
+impl Game for Platformer {
+    fn assets(&self) -> AssetManifest {
+        AssetManifest {
+            levels: vec![LEVEL_PATH.to_string()],
+            ..AssetManifest::new(PLAYER_SHEET_PATH, PLAYER_TEXTURE_PATH)
+        }
+    }
+
+    fn update(&mut self, ctx: &mut Ctx, delta_time: f32) {
+        self.updated = true;
+        self.life_time += delta_time;
+
+        // Record the player for the next life's ghost, and step the ghost along
+        self.recording.push(ctx.game_state.bounds());
+        if let Some(ghost) = self.ghost.as_mut() {
+            ghost.frame += 1;
+        }
+
+        // Pull the camera back while running, and let it ease in again after
+        let (velocity_x, _) = ctx.game_state.velocity();
+        let running = velocity_x.abs() > RUN_SPEED;
+        if running && !self.running {
+            ctx.camera.request_shot(RUN_SHOT);
+        } else if !running && self.running {
+            ctx.camera.release_shot(&RUN_SHOT);
+        }
+        self.running = running;
+    }
+
+    fn render(&mut self, ui: &mut Ui, _game_state: &GameState, camera: &Camera) {
+        if !std::mem::take(&mut self.updated) {
+            return;
+        }
+
+        if let Some(bounds) = self.ghost.as_ref().and_then(|ghost| ghost.path.get(ghost.frame)) {
+            let center = ((bounds.min_x + bounds.max_x) / 2.0, (bounds.min_y + bounds.max_y) / 2.0);
+            let (x, y) = camera.world_to_screen(center);
+            let width = (bounds.max_x - bounds.min_x) * camera.zoom;
+            let height = (bounds.max_y - bounds.min_y) * camera.zoom;
+            // Sorted just behind the player
+            ui.panel(Rect::new(x, y, width, height), RenderLayer::Player.depth(-1), GHOST_COLOR);
+        }
+
+        let style = TextStyle {
+            height: 0.06,
+            color: [1.0, 1.0, 1.0, 1.0],
+            z: RenderLayer::Hud.depth(1),
+            align: TextAlign::Right,
+        };
+        ui.label(&format!("{:.1}", self.life_time), (0.95, -0.9), &style);
+    }
+
+    fn on_event(&mut self, ctx: &mut Ctx, event: GameEvent) {
+        match event {
+            // The life just lost is the one the ghost replays
+            GameEvent::LifeLost { .. } => {
+                self.ghost = Some(Ghost {
+                    path: std::mem::take(&mut self.recording),
+                    frame: 0,
+                });
+            }
+            GameEvent::Spawned { .. } => {
+                self.recording.clear();
+                if let Some(ghost) = self.ghost.as_mut() {
+                    ghost.frame = 0;
+                }
+                self.life_time = 0.0;
+                self.running = false;
+                ctx.camera.release_shot(&RUN_SHOT);
+            }
+            GameEvent::Bounced
+            | GameEvent::ProjectileHit { .. }
+            | GameEvent::SwimDashed { .. }
+            | GameEvent::OxygenLow { .. }
+            | GameEvent::Breathed { .. }
+            | GameEvent::AnimationEvent { .. }
+            | GameEvent::ObjectiveProgressed { .. }
+            | GameEvent::ObjectiveCompleted { .. }
+            | GameEvent::SignRead { .. } => {}
+        }
+    }
+}
+
+/// Plays a sound as the player leaves the ground upward.
+struct JumpSound {
+    sound: Option<Arc<Sound>>,
+    /// Whether the player was on the ground last frame, to catch them jumping.
+    was_grounded: bool,
+}
+
+impl System for JumpSound {
+    fn init(&mut self, engine: &mut Engine) {
+        self.sound = engine.load_sound(JUMP_SOUND_PATH);
+    }
+
+    fn update(&mut self, ctx: &mut Ctx, _delta_time: f32) {
+        let grounded = ctx.game_state.is_grounded();
+        if self.was_grounded && !grounded && ctx.game_state.velocity().1 > 0.0 {
+            if let Some(sound) = &self.sound {
+                ctx.audio.play(sound, Bus::Sfx, 0.4, false);
+            }
+        }
+        self.was_grounded = grounded;
+    }
+}
+
+fn main() {
+    env_logger::init();
+    App::new(Platformer::new())
+        .with_initial_scene(Scene::Level(LEVEL_PATH.to_string()))
+        .with_system(JumpSound {
+            sound: None,
+            was_grounded: true,
+        })
+        .run();
+}
@@ -0,0 +1,198 @@
+// app.rs
+use crate::engine::scene::Scene;
+use crate::game::{Game, System};
+use crate::game_loop;
+
+/// Directory watched for asset changes to hot-reload.
+const ASSETS_DIR: &str = "assets";
+
+/// Gameplay tuning read at startup.
+const TUNING_PATH: &str = "assets/config/tuning.toml";
+
+/// Background layers read at startup.
+const BACKGROUNDS_PATH: &str = "assets/config/backgrounds.toml";
+
+/// Particle effect presets read at startup.
+const EFFECTS_PATH: &str = "assets/config/effects.toml";
+
+/// Decorations scattered over the ground, read at startup.
+const DECORATIONS_PATH: &str = "assets/config/decorations.toml";
+
+/// Bullet patterns fired by turrets and scripts, read at startup.
+const PATTERNS_PATH: &str = "assets/config/patterns.toml";
+
+/// Events fired on the frames of the player's animations, read at startup.
+const ANIMATION_EVENTS_PATH: &str = "assets/config/animation_events.toml";
+
+/// Key and gamepad bindings for each action, written with the defaults if missing.
+const CONTROLS_PATH: &str = "assets/config/controls.toml";
+
+/// The window the game opens.
+#[derive(Clone, Debug)]
+pub struct WindowSettings {
+    pub title: String,
+    /// Initial size of the window's drawable area, in physical pixels.
+    pub width: u32,
+    pub height: u32,
+}
+
+impl Default for WindowSettings {
+    fn default() -> Self {
+        Self {
+            title: "Rust Platformer Engine".to_string(),
+            width: 800,
+            height: 600,
+        }
+    }
+}
+
+/// Where the game finds the files it reads at startup, relative to the working
+/// directory. Files that are missing or invalid fall back to the built-in defaults
+/// with a warning, as do sounds, which are simply not played.
+///
+/// The game's own files, its levels, player, and music, are named by the game; the
+/// engine's configs default to the files under `assets/config`. The engine's other
+/// textures and the files named by levels are found on their own paths, and enabled
+/// mods can override any of them.
+#[derive(Clone, Debug)]
+pub struct AssetManifest {
+    /// Directory watched for changes to hot-reload.
+    pub assets_dir: String,
+    /// Tiled exports offered on the level-select menu, in order, before any from mods.
+    pub levels: Vec<String>,
+    /// Gameplay tuning.
+    pub tuning: String,
+    /// Key and gamepad bindings, written with the defaults if missing.
+    pub controls: String,
+    /// Background layers.
+    pub backgrounds: String,
+    /// Particle effect presets.
+    pub effects: String,
+    /// Decoration sprites and the tileset surfaces they're scattered on.
+    pub decorations: String,
+    /// Bullet patterns fired by turrets and scripts.
+    pub patterns: String,
+    /// Events fired on the frames of the player's animations.
+    pub animation_events: String,
+    /// Aseprite export of the player's sprite sheet.
+    pub player_sheet: String,
+    /// The player's texture, cut into frames by the sprite sheet.
+    pub player_texture: String,
+    /// Music looped while playing, if any.
+    pub music: Option<String>,
+}
+
+impl AssetManifest {
+    /// Creates the manifest for a game's player, with the engine's own configs, no
+    /// levels on the menu, and no music.
+    ///
+    /// # Arguments
+    ///
+    /// * `player_sheet` - Aseprite export of the player's sprite sheet.
+    /// * `player_texture` - The player's texture, cut into frames by the sheet.
+    pub fn new(player_sheet: &str, player_texture: &str) -> Self {
+        Self {
+            assets_dir: ASSETS_DIR.to_string(),
+            levels: Vec::new(),
+            tuning: TUNING_PATH.to_string(),
+            controls: CONTROLS_PATH.to_string(),
+            backgrounds: BACKGROUNDS_PATH.to_string(),
+            effects: EFFECTS_PATH.to_string(),
+            decorations: DECORATIONS_PATH.to_string(),
+            patterns: PATTERNS_PATH.to_string(),
+            animation_events: ANIMATION_EVENTS_PATH.to_string(),
+            player_sheet: player_sheet.to_string(),
+            player_texture: player_texture.to_string(),
+            music: None,
+        }
+    }
+}
+
+/// A game set up to run on the engine: the game, the window it opens, the scene it
+/// starts in, and the systems run alongside it.
+///
+/// ```no_run
+/// use rust_platformer_engine::engine::scene::Scene;
+/// use rust_platformer_engine::{App, AssetManifest, Ctx, Game, System, WindowSettings};
+///
+/// struct MyGame;
+///
+/// impl Game for MyGame {
+///     fn assets(&self) -> AssetManifest {
+///         AssetManifest::new("assets/character/player.json", "assets/character/player.png")
+///     }
+/// }
+///
+/// /// Tops the player's lives back up whenever they run low.
+/// struct ExtraLives;
+///
+/// impl System for ExtraLives {
+///     fn update(&mut self, ctx: &mut Ctx, _delta_time: f32) {
+///         ctx.game_state.lives = ctx.game_state.lives.max(2);
+///     }
+/// }
+///
+/// App::new(MyGame)
+///     .with_window(WindowSettings {
+///         title: "My Platformer".to_string(),
+///         ..Default::default()
+///     })
+///     .with_initial_scene(Scene::Level("assets/levels/level1.json".to_string()))
+///     .with_system(ExtraLives)
+///     .run();
+/// ```
+pub struct App {
+    pub(crate) window: WindowSettings,
+    pub(crate) initial_scene: Scene,
+    pub(crate) seed: Option<u32>,
+    pub(crate) game: Box<dyn Game>,
+    pub(crate) systems: Vec<Box<dyn System>>,
+}
+
+impl App {
+    /// Creates the app running a game in the default window, starting at the
+    /// level-select menu.
+    ///
+    /// # Arguments
+    ///
+    /// * `game` - The game driven by the game loop.
+    pub fn new(game: impl Game + 'static) -> Self {
+        Self {
+            window: WindowSettings::default(),
+            initial_scene: Scene::Menu,
+            seed: None,
+            game: Box::new(game),
+            systems: Vec::new(),
+        }
+    }
+
+    /// Sets the window the game opens.
+    pub fn with_window(mut self, window: WindowSettings) -> Self {
+        self.window = window;
+        self
+    }
+
+    /// Sets the scene shown first, such as a level to skip the menu and start in.
+    pub fn with_initial_scene(mut self, scene: Scene) -> Self {
+        self.initial_scene = scene;
+        self
+    }
+
+    /// Sets the seed the run's random numbers all come from, to play a run out the
+    /// same way again; without one, each run picks its own and logs it.
+    pub fn with_seed(mut self, seed: u32) -> Self {
+        self.seed = Some(seed);
+        self
+    }
+
+    /// Adds a system to run alongside the game, after it and any systems added before.
+    pub fn with_system(mut self, system: impl System + 'static) -> Self {
+        self.systems.push(Box::new(system));
+        self
+    }
+
+    /// Opens the window and runs the game until it's closed.
+    pub fn run(self) {
+        game_loop::run(self);
+    }
+}
@@ -0,0 +1,297 @@
+// action.rs
+use crate::engine::config::ConfigError;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use winit::event::VirtualKeyCode;
+
+/// Something the player can do, bound to keys and gamepad buttons by an `ActionMap`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Action {
+    MoveLeft,
+    MoveRight,
+    /// Climbs ropes, reads signs, flies up while riding, and moves menu selections up.
+    Up,
+    /// Climbs down ropes, flies down while riding, and moves menu selections down.
+    Down,
+    Jump,
+    Run,
+    Crouch,
+    Kick,
+    /// Mounts a nearby mount, or dismounts the one being ridden.
+    Mount,
+    /// Bursts through the water toward the held direction while swimming.
+    Dash,
+    Pause,
+    /// Confirms the selected menu choice.
+    Confirm,
+    QuickSave,
+    QuickLoad,
+    SlowDown,
+    SpeedUp,
+    /// Steps the player back through the last few seconds while held.
+    Rewind,
+    DebugHud,
+    /// Turns low-power mode on and off.
+    LowPower,
+    /// Shows and hides the list of controls.
+    Help,
+    /// Shows and hides the developer menu. Only debug builds have it.
+    #[cfg(debug_assertions)]
+    DevMenu,
+    /// Turns noclip on and off without opening the developer menu. Only debug builds have it.
+    #[cfg(debug_assertions)]
+    Noclip,
+    /// Opens the console to type developer commands into. Only debug builds have it.
+    #[cfg(debug_assertions)]
+    Console,
+    /// Steps back through the recorded simulation while paused. Only debug builds have it.
+    #[cfg(debug_assertions)]
+    ScrubBack,
+    /// Steps forward through the recorded simulation while paused. Only debug builds have it.
+    #[cfg(debug_assertions)]
+    ScrubForward,
+}
+
+impl Action {
+    /// Returns the action's name as shown in the controls help.
+    pub fn label(self) -> &'static str {
+        match self {
+            Action::MoveLeft => "MOVE LEFT",
+            Action::MoveRight => "MOVE RIGHT",
+            Action::Up => "UP / CLIMB",
+            Action::Down => "DOWN",
+            Action::Jump => "JUMP",
+            Action::Run => "RUN",
+            Action::Crouch => "CROUCH",
+            Action::Kick => "KICK",
+            Action::Mount => "MOUNT",
+            Action::Dash => "SWIM DASH",
+            Action::Pause => "PAUSE",
+            Action::Confirm => "CONFIRM",
+            Action::QuickSave => "QUICKSAVE",
+            Action::QuickLoad => "QUICKLOAD",
+            Action::SlowDown => "SLOW DOWN",
+            Action::SpeedUp => "SPEED UP",
+            Action::Rewind => "REWIND",
+            Action::DebugHud => "DEBUG HUD",
+            Action::LowPower => "LOW POWER",
+            Action::Help => "CONTROLS",
+            #[cfg(debug_assertions)]
+            Action::DevMenu => "DEV MENU",
+            #[cfg(debug_assertions)]
+            Action::Noclip => "NOCLIP",
+            #[cfg(debug_assertions)]
+            Action::Console => "CONSOLE",
+            #[cfg(debug_assertions)]
+            Action::ScrubBack => "SCRUB BACK",
+            #[cfg(debug_assertions)]
+            Action::ScrubForward => "SCRUB FORWARD",
+        }
+    }
+}
+
+/// A gamepad button, named by its position so it reads the same across controller layouts.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GamepadButton {
+    /// The bottom face button (A on Xbox controllers).
+    South,
+    /// The left face button (X on Xbox controllers).
+    West,
+    /// The top face button (Y on Xbox controllers).
+    North,
+    LeftBumper,
+    RightBumper,
+    Select,
+    Start,
+    DPadUp,
+    DPadDown,
+    DPadLeft,
+    DPadRight,
+}
+
+impl GamepadButton {
+    /// Returns the button's name as shown in the controls help, using Xbox labels.
+    pub fn label(self) -> &'static str {
+        match self {
+            GamepadButton::South => "A",
+            GamepadButton::West => "X",
+            GamepadButton::North => "Y",
+            GamepadButton::LeftBumper => "LB",
+            GamepadButton::RightBumper => "RB",
+            GamepadButton::Select => "BACK",
+            GamepadButton::Start => "START",
+            GamepadButton::DPadUp => "D-PAD UP",
+            GamepadButton::DPadDown => "D-PAD DOWN",
+            GamepadButton::DPadLeft => "D-PAD LEFT",
+            GamepadButton::DPadRight => "D-PAD RIGHT",
+        }
+    }
+}
+
+/// The keys and gamepad buttons that trigger one action. Any of them will do.
+pub struct Binding {
+    pub action: Action,
+    pub keys: Vec<VirtualKeyCode>,
+    pub buttons: Vec<GamepadButton>,
+}
+
+/// The keys and gamepad buttons one action is bound to in the controls config.
+#[derive(Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+struct BindingConfig {
+    #[serde(default)]
+    keys: Vec<VirtualKeyCode>,
+    #[serde(default)]
+    buttons: Vec<GamepadButton>,
+}
+
+/// Maps the player's actions to the keys and gamepad buttons that trigger them.
+///
+/// Gameplay and menus read input through actions rather than raw keys, so the
+/// map is the one place bindings are defined, and what the controls help lists.
+pub struct ActionMap {
+    bindings: Vec<Binding>,
+}
+
+impl ActionMap {
+    /// Reads bindings from a TOML controls config over the defaults.
+    ///
+    /// Each table is named after an action and lists its `keys`, by winit key name,
+    /// and gamepad `buttons`. Actions the file leaves out keep their default
+    /// bindings, and unknown actions, such as debug-only ones in a release build,
+    /// are skipped with a warning.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - Path to the TOML file.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, ConfigError> {
+        let source = std::fs::read_to_string(path)?;
+        let table: toml::Table = toml::from_str(&source).map_err(|e| ConfigError::Parse(e.to_string()))?;
+
+        let mut map = Self::default();
+        for (name, value) in table {
+            let Ok(action) = Action::deserialize(toml::Value::String(name.clone())) else {
+                log::warn!("Ignoring bindings for unknown action '{}'", name);
+                continue;
+            };
+            let config: BindingConfig = value
+                .try_into()
+                .map_err(|e: toml::de::Error| ConfigError::Parse(format!("{}: {}", name, e)))?;
+            match map.bindings.iter_mut().find(|binding| binding.action == action) {
+                Some(binding) => {
+                    binding.keys = config.keys;
+                    binding.buttons = config.buttons;
+                }
+                None => map.bindings.push(Binding {
+                    action,
+                    keys: config.keys,
+                    buttons: config.buttons,
+                }),
+            }
+        }
+        Ok(map)
+    }
+
+    /// Writes every binding to a TOML controls config that `load` can read back,
+    /// creating its directory if needed.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - Where to write the file.
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), ConfigError> {
+        let mut table = toml::Table::new();
+        for binding in &self.bindings {
+            let config = BindingConfig {
+                keys: binding.keys.clone(),
+                buttons: binding.buttons.clone(),
+            };
+            let name = toml::Value::try_from(binding.action).map_err(|e| ConfigError::Parse(e.to_string()))?;
+            let value = toml::Value::try_from(config).map_err(|e| ConfigError::Parse(e.to_string()))?;
+            table.insert(name.as_str().unwrap_or_default().to_string(), value);
+        }
+        let source = toml::to_string_pretty(&table).map_err(|e| ConfigError::Parse(e.to_string()))?;
+
+        let path = path.as_ref();
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(path, source)?;
+        Ok(())
+    }
+
+    /// Returns every binding, in the order they are listed in the controls help.
+    pub fn bindings(&self) -> &[Binding] {
+        &self.bindings
+    }
+
+    /// Returns the binding for an action, if it has one.
+    pub fn binding(&self, action: Action) -> Option<&Binding> {
+        self.bindings.iter().find(|binding| binding.action == action)
+    }
+}
+
+impl Default for ActionMap {
+    /// Creates the default bindings.
+    fn default() -> Self {
+        use GamepadButton::*;
+        use VirtualKeyCode as Key;
+
+        let bind = |action, keys: &[VirtualKeyCode], buttons: &[GamepadButton]| Binding {
+            action,
+            keys: keys.to_vec(),
+            buttons: buttons.to_vec(),
+        };
+
+        #[allow(unused_mut)]
+        let mut bindings = vec![
+            bind(Action::MoveLeft, &[Key::A, Key::Left], &[DPadLeft]),
+            bind(Action::MoveRight, &[Key::D, Key::Right], &[DPadRight]),
+            bind(Action::Up, &[Key::W, Key::Up], &[DPadUp]),
+            bind(Action::Down, &[Key::S, Key::Down], &[DPadDown]),
+            bind(Action::Jump, &[Key::Space], &[South]),
+            bind(Action::Run, &[Key::LShift], &[RightBumper]),
+            bind(Action::Crouch, &[Key::LControl], &[LeftBumper]),
+            bind(Action::Kick, &[Key::E], &[West]),
+            bind(Action::Mount, &[Key::F], &[North]),
+            bind(Action::Dash, &[Key::Q], &[RightBumper]),
+            bind(Action::Pause, &[Key::Escape], &[Start]),
+            bind(Action::Confirm, &[Key::Return], &[South]),
+            bind(Action::QuickSave, &[Key::F5], &[]),
+            bind(Action::QuickLoad, &[Key::F9], &[]),
+            bind(Action::SlowDown, &[Key::LBracket], &[]),
+            bind(Action::SpeedUp, &[Key::RBracket], &[]),
+            bind(Action::Rewind, &[Key::R], &[]),
+            bind(Action::DebugHud, &[Key::F3], &[]),
+            bind(Action::LowPower, &[Key::F6], &[]),
+            bind(Action::Help, &[Key::F1], &[Select]),
+        ];
+        #[cfg(debug_assertions)]
+        bindings.extend([
+            bind(Action::DevMenu, &[Key::F2], &[]),
+            bind(Action::Noclip, &[Key::F4], &[]),
+            bind(Action::Console, &[Key::Grave], &[]),
+            bind(Action::ScrubBack, &[Key::Comma], &[]),
+            bind(Action::ScrubForward, &[Key::Period], &[]),
+        ]);
+
+        Self { bindings }
+    }
+}
+
+/// Returns a key's name as shown in the controls help.
+pub fn key_label(key: VirtualKeyCode) -> String {
+    match key {
+        VirtualKeyCode::LShift => "LEFT SHIFT".to_string(),
+        VirtualKeyCode::LControl => "LEFT CTRL".to_string(),
+        VirtualKeyCode::Return => "ENTER".to_string(),
+        VirtualKeyCode::Escape => "ESC".to_string(),
+        VirtualKeyCode::LBracket => "[".to_string(),
+        VirtualKeyCode::RBracket => "]".to_string(),
+        VirtualKeyCode::Comma => ",".to_string(),
+        VirtualKeyCode::Period => ".".to_string(),
+        VirtualKeyCode::Grave => "`".to_string(),
+        key => format!("{:?}", key).to_uppercase(),
+    }
+}
@@ -0,0 +1,36 @@
+// activation.rs
+use crate::engine::camera::Camera;
+use crate::engine::collision::Aabb;
+use crate::engine::constants::ACTIVATION_MARGIN;
+
+/// The area around the camera in which entities are updated.
+///
+/// Entities entirely outside it stop simulating until the camera comes near
+/// again, so large levels only pay for what's around the player. Each system
+/// keeps an `active` flag per entity, set from this range once per frame and
+/// checked by its update.
+///
+/// Systems keep their entities in a `Vec` in level file order and always update
+/// them in that order, so runs with the same input play out the same. Entities
+/// that go away, such as spent projectiles, are removed with order-preserving
+/// `retain`, or stay in place and are skipped once collected; nothing in the
+/// frame path iterates a `HashMap`.
+pub struct ActivationRange {
+    bounds: Aabb,
+}
+
+impl ActivationRange {
+    /// Creates the range covering the camera's view, extended by the activation margin.
+    pub fn around(camera: &Camera) -> Self {
+        // The view spans -1..1 in clip space, which is 1 / zoom world units either side
+        let half_extent = 1.0 / camera.zoom + ACTIVATION_MARGIN;
+        Self {
+            bounds: Aabb::from_center(camera.x, camera.y, 2.0 * half_extent, 2.0 * half_extent),
+        }
+    }
+
+    /// Returns `true` if any part of an entity's bounds lies within the range.
+    pub fn contains(&self, bounds: &Aabb) -> bool {
+        self.bounds.intersects(bounds)
+    }
+}
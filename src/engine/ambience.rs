@@ -0,0 +1,161 @@
+// ambience.rs
+use crate::engine::assets::load_wav;
+use crate::engine::audio::{Audio, Bus, VoiceId};
+use crate::engine::collision::Aabb;
+use crate::engine::constants::AMBIENCE_FADE_TIME;
+use crate::engine::level::Level;
+use log::{info, warn};
+use std::sync::Arc;
+use std::time::Instant;
+
+/// A trigger region where an ambient loop plays while the player is inside it.
+struct AmbienceZone {
+    bounds: Aabb,
+    /// Path of the loop to play.
+    sound: String,
+    /// Volume the loop plays at inside the zone.
+    volume: f32,
+}
+
+/// Where the level's ambient loops, such as cave drips, wind, or a waterfall, play.
+///
+/// Zones come from `ambience_zone` rectangles in the level, with these properties:
+/// - `sound`: path of the WAV file to loop.
+/// - `volume`: volume to play it at inside the zone (default 0.5).
+///
+/// Zones sharing a sound play one loop, at the loudest volume of those the player is in.
+pub struct AmbienceZones {
+    zones: Vec<AmbienceZone>,
+}
+
+impl AmbienceZones {
+    /// Creates the ambience zones placed in the level.
+    pub fn from_level(level: &Level) -> Self {
+        let zones = level
+            .objects_of_kind("ambience_zone")
+            .filter_map(|object| {
+                let Some(sound) = object.property("sound") else {
+                    warn!("Ambience zone '{}' has no sound", object.name);
+                    return None;
+                };
+                Some(AmbienceZone {
+                    bounds: object.bounds,
+                    sound: sound.to_string(),
+                    volume: object.property_f32("volume", 0.5).max(0.0),
+                })
+            })
+            .collect();
+
+        Self { zones }
+    }
+
+    /// Returns the volume each of the zones' loops should play at, in the order the
+    /// sounds first appear: the loudest zone the player is in, or silent outside them all.
+    ///
+    /// # Arguments
+    ///
+    /// * `player` - The player's bounds.
+    fn volumes(&self, player: &Aabb) -> Vec<(&str, f32)> {
+        let mut volumes: Vec<(&str, f32)> = Vec::new();
+        for zone in &self.zones {
+            let volume = if zone.bounds.intersects(player) { zone.volume } else { 0.0 };
+            match volumes.iter_mut().find(|(sound, _)| *sound == zone.sound) {
+                Some((_, loudest)) => *loudest = loudest.max(volume),
+                None => volumes.push((&zone.sound, volume)),
+            }
+        }
+        volumes
+    }
+}
+
+/// An ambient loop and how loud it's playing.
+struct AmbientLoop {
+    sound: String,
+    /// The playing voice, or `None` if the sound couldn't be loaded.
+    voice: Option<VoiceId>,
+    volume: f32,
+}
+
+/// Plays the ambient loops of the current level's zones on the ambience bus,
+/// crossfading them as the player moves between zones.
+///
+/// Loops start silently when a level with their zones is entered, fade in and out
+/// over `AMBIENCE_FADE_TIME`, and stop once faded out after their level is left, so
+/// a loop shared between levels plays on across the switch.
+pub struct Ambience {
+    loops: Vec<AmbientLoop>,
+    /// When the loops were last faded.
+    last_update: Instant,
+}
+
+impl Ambience {
+    /// Creates the ambience with nothing playing.
+    pub fn new() -> Self {
+        Self {
+            loops: Vec::new(),
+            last_update: Instant::now(),
+        }
+    }
+
+    /// Fades each loop toward the volume of the zones the player is in, by the real
+    /// time elapsed since the last update.
+    ///
+    /// # Arguments
+    ///
+    /// * `zones` - The current level's ambience zones.
+    /// * `player` - The player's bounds.
+    /// * `audio` - Plays the loops.
+    pub fn update(&mut self, zones: &AmbienceZones, player: &Aabb, audio: &Audio) {
+        let now = Instant::now();
+        let step = now.duration_since(self.last_update).as_secs_f32() / AMBIENCE_FADE_TIME.max(f32::EPSILON);
+        self.last_update = now;
+
+        let volumes = zones.volumes(player);
+
+        // Start any new loops silently, ready to fade in
+        for &(sound, _) in &volumes {
+            if self.loops.iter().all(|ambient| ambient.sound != sound) {
+                info!("Starting ambient loop '{}'", sound);
+                let voice = match load_wav(sound) {
+                    Ok(loaded) => Some(audio.play(&Arc::new(loaded), Bus::Ambience, 0.0, true)),
+                    Err(e) => {
+                        warn!("Could not load ambient loop '{}': {}", sound, e);
+                        None
+                    }
+                };
+                self.loops.push(AmbientLoop {
+                    sound: sound.to_string(),
+                    voice,
+                    volume: 0.0,
+                });
+            }
+        }
+
+        self.loops.retain_mut(|ambient| {
+            let target = volumes
+                .iter()
+                .find(|(sound, _)| *sound == ambient.sound)
+                .map(|&(_, volume)| volume);
+            let Some(voice) = ambient.voice else {
+                return target.is_some();
+            };
+
+            let target_volume = target.unwrap_or(0.0);
+            ambient.volume += (target_volume - ambient.volume).clamp(-step, step);
+            audio.set_volume(voice, ambient.volume);
+
+            // Loops left behind in another level stop once they've faded out
+            if target.is_none() && ambient.volume <= 0.0 {
+                audio.stop(voice);
+                return false;
+            }
+            true
+        });
+    }
+}
+
+impl Default for Ambience {
+    fn default() -> Self {
+        Self::new()
+    }
+}
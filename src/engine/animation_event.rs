@@ -0,0 +1,92 @@
+// animation_event.rs
+use crate::engine::config::ConfigError;
+use crate::engine::mods::resolve;
+use serde::Deserialize;
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+use std::sync::OnceLock;
+
+/// Events attached to the frames of the player's animations, as read from the
+/// animation events file, so sounds and hits land on the exact frame drawn for them.
+///
+/// Each animation maps event names to the sprite frames they fire on. An event fires
+/// as the animation moves onto one of its frames, including the first frame when the
+/// animation starts.
+#[derive(Clone, Debug, Default, Deserialize, PartialEq)]
+#[serde(transparent)]
+pub struct AnimationEvents {
+    animations: HashMap<String, BTreeMap<String, Vec<usize>>>,
+}
+
+impl AnimationEvents {
+    /// Returns the names of the events fired on a frame of an animation, in name order.
+    ///
+    /// # Arguments
+    ///
+    /// * `action` - Name of the animation playing.
+    /// * `frame` - The sprite frame it moved onto.
+    pub fn at<'a>(&'a self, action: &str, frame: usize) -> impl Iterator<Item = &'a str> + 'a {
+        self.animations
+            .get(action)
+            .into_iter()
+            .flatten()
+            .filter(move |(_, frames)| frames.contains(&frame))
+            .map(|(name, _)| name.as_str())
+    }
+
+    /// Checks the events against the animations played.
+    ///
+    /// # Arguments
+    ///
+    /// * `animations` - Each animation's name and inclusive frame range.
+    ///
+    /// # Returns
+    ///
+    /// A description of each problem found: events attached to an animation that
+    /// doesn't exist, or to a frame their animation never shows, which would never fire.
+    pub fn validate(&self, animations: &[(&str, (usize, usize))]) -> Vec<String> {
+        let mut problems = Vec::new();
+        for (action, events) in &self.animations {
+            let Some(&(_, (start, end))) = animations.iter().find(|(name, _)| name == action) else {
+                problems.push(format!("events are attached to unknown animation '{}'", action));
+                continue;
+            };
+            for (name, frames) in events {
+                for frame in frames.iter().filter(|frame| !(start..=end).contains(*frame)) {
+                    problems.push(format!(
+                        "event '{}' is on frame {}, outside animation '{}' ({}..={})",
+                        name, frame, action, start, end
+                    ));
+                }
+            }
+        }
+        problems.sort();
+        problems
+    }
+}
+
+static ANIMATION_EVENTS: OnceLock<AnimationEvents> = OnceLock::new();
+
+/// Reads animation events from a TOML file, one table per animation.
+///
+/// # Arguments
+///
+/// * `path` - Path to the TOML file.
+pub fn load_animation_events(path: impl AsRef<Path>) -> Result<AnimationEvents, ConfigError> {
+    let source = std::fs::read_to_string(resolve(path))?;
+    toml::from_str(&source).map_err(|e| ConfigError::Parse(e.to_string()))
+}
+
+/// Makes the given events the ones returned by `animation_events()`.
+///
+/// Only the first call has any effect, so this should happen at startup.
+pub fn set_animation_events(events: AnimationEvents) {
+    if ANIMATION_EVENTS.set(events).is_err() {
+        log::warn!("Animation events were already set; ignoring the new ones");
+    }
+}
+
+/// Returns the animation events in effect, or none if none have been set.
+pub fn animation_events() -> &'static AnimationEvents {
+    ANIMATION_EVENTS.get_or_init(AnimationEvents::default)
+}
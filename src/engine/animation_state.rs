@@ -0,0 +1,180 @@
+// animation_state.rs
+
+/// What the player is doing this update, which decides the animation they're in.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Motion {
+    pub mounted: bool,
+    pub kicking: bool,
+    pub jumping: bool,
+    pub crouching: bool,
+    pub moving: bool,
+    pub running: bool,
+}
+
+impl Motion {
+    /// Returns the motion with a move over, as when its animation has played out.
+    pub fn without(mut self, the_move: Condition) -> Self {
+        match the_move {
+            Condition::Mounted => self.mounted = false,
+            Condition::Kicking => self.kicking = false,
+            Condition::Jumping => self.jumping = false,
+            Condition::Crouching => self.crouching = false,
+            Condition::Moving => self.moving = false,
+            Condition::Running => self.running = false,
+        }
+        self
+    }
+}
+
+/// Something about the player's motion an animation state can depend on.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Condition {
+    Mounted,
+    Kicking,
+    Jumping,
+    Crouching,
+    Moving,
+    Running,
+}
+
+impl Condition {
+    /// Returns whether the condition holds for the player's motion.
+    pub fn holds(self, motion: &Motion) -> bool {
+        match self {
+            Condition::Mounted => motion.mounted,
+            Condition::Kicking => motion.kicking,
+            Condition::Jumping => motion.jumping,
+            Condition::Crouching => motion.crouching,
+            Condition::Moving => motion.moving,
+            Condition::Running => motion.running,
+        }
+    }
+}
+
+/// An animation the player can be in, and when they're in it.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct AnimationState {
+    /// Name of the animation played, as tagged in the sprite sheet.
+    pub action: &'static str,
+    /// Of the states the player could be in, the one with the highest priority plays,
+    /// the first listed on a tie. Only a higher priority interrupts a state playing out
+    /// its move.
+    pub priority: u8,
+    /// Conditions that must all hold for the state to play.
+    pub when: &'static [Condition],
+    /// Conditions that must all not hold for the state to play.
+    pub unless: &'static [Condition],
+    /// The move the state plays out once, ending it when its animation does, such as
+    /// a kick. `None` loops the animation while the state's conditions hold.
+    pub plays_out: Option<Condition>,
+}
+
+impl AnimationState {
+    /// Returns whether the state's conditions allow it for the player's motion.
+    fn allows(&self, motion: &Motion) -> bool {
+        self.when.iter().all(|condition| condition.holds(motion))
+            && !self.unless.iter().any(|condition| condition.holds(motion))
+    }
+}
+
+/// The player's animation states, most important first.
+pub const PLAYER_STATES: [AnimationState; 9] = [
+    state("ride_move", 60, &[Condition::Mounted, Condition::Moving], &[]),
+    state("ride_idle", 60, &[Condition::Mounted], &[Condition::Moving]),
+    AnimationState {
+        plays_out: Some(Condition::Kicking),
+        ..state("kick", 50, &[Condition::Kicking], &[])
+    },
+    state("jump", 40, &[Condition::Jumping], &[]),
+    state("crouch_walk", 30, &[Condition::Crouching, Condition::Moving], &[]),
+    state("crouch_idle", 30, &[Condition::Crouching], &[Condition::Moving]),
+    state("run", 20, &[Condition::Moving, Condition::Running], &[]),
+    state("walk", 20, &[Condition::Moving], &[Condition::Running]),
+    state("idle", 0, &[], &[]),
+];
+
+/// Creates a looping animation state.
+const fn state(
+    action: &'static str,
+    priority: u8,
+    when: &'static [Condition],
+    unless: &'static [Condition],
+) -> AnimationState {
+    AnimationState {
+        action,
+        priority,
+        when,
+        unless,
+        plays_out: None,
+    }
+}
+
+/// Picks the animation the player is in from a table of states, in place of a
+/// hand-written ladder of checks.
+///
+/// Each update, the highest-priority state whose conditions hold plays, unless the
+/// current state is still playing out its move and nothing of a higher priority
+/// interrupts it. New moves only need a state added at the right priority.
+#[derive(Clone, Debug)]
+pub struct AnimationStateMachine {
+    states: Vec<AnimationState>,
+}
+
+impl AnimationStateMachine {
+    /// Creates the machine with the player's built-in states.
+    pub fn new() -> Self {
+        Self {
+            states: PLAYER_STATES.to_vec(),
+        }
+    }
+
+    /// Adds a state, such as for a new move, after the states of its priority or higher.
+    pub fn add_state(&mut self, state: AnimationState) {
+        let index = self.states.iter().position(|other| other.priority < state.priority).unwrap_or(self.states.len());
+        self.states.insert(index, state);
+    }
+
+    /// Returns the state playing an animation, if any does.
+    pub fn state(&self, action: &str) -> Option<&AnimationState> {
+        self.states.iter().find(|state| state.action == action)
+    }
+
+    /// Returns the animation to be in next.
+    ///
+    /// # Arguments
+    ///
+    /// * `current` - The animation playing now, which keeps playing while it plays out its move.
+    /// * `motion` - What the player is doing.
+    pub fn next(&self, current: &str, motion: &Motion) -> Option<&'static str> {
+        let best = self.best(motion);
+        if let Some(current) = self.state(current).filter(|state| state.plays_out.is_some()) {
+            if best.is_none_or(|best| best.priority <= current.priority) {
+                return Some(current.action);
+            }
+        }
+        best.map(|state| state.action)
+    }
+
+    /// Returns the animation to be in once the current one has played out its move,
+    /// with that move over.
+    pub fn after(&self, motion: &Motion) -> Option<&'static str> {
+        self.best(motion).map(|state| state.action)
+    }
+
+    /// Returns the highest-priority state allowed, the first listed on a tie.
+    fn best(&self, motion: &Motion) -> Option<&AnimationState> {
+        self.states
+            .iter()
+            .filter(|state| state.allows(motion))
+            .fold(None, |best: Option<&AnimationState>, state| match best {
+                Some(best) if best.priority >= state.priority => Some(best),
+                _ => Some(state),
+            })
+    }
+}
+
+impl Default for AnimationStateMachine {
+    fn default() -> Self {
+        Self::new()
+    }
+}
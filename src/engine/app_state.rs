@@ -0,0 +1,51 @@
+// app_state.rs
+use crate::engine::level_stats::LevelSummary;
+
+/// The state of the application as a whole, above the `GameState` of the world.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum AppState {
+    /// The world updates every frame.
+    Playing,
+    /// The world is frozen but still drawn, under the pause overlay.
+    Paused,
+    /// The player has run out of lives; the world is frozen under the game-over screen
+    /// until the player picks one of its choices.
+    GameOver(GameOverChoice),
+    /// The player has reached the level's exit; the world is frozen under a summary of
+    /// the run until they go on.
+    LevelComplete(LevelSummary),
+    /// The level-select menu is shown over a backdrop with nothing to play, with the
+    /// index of the selected entry: a level, or after the levels, a mod.
+    Menu(usize),
+}
+
+/// The choices offered on the game-over screen.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GameOverChoice {
+    /// Spend a continue to return to the last checkpoint with full lives.
+    Retry,
+    /// Start the level over from the beginning, with a new game's lives and continues.
+    ReturnToStart,
+}
+
+impl GameOverChoice {
+    /// Returns the other choice, for moving the selection up or down.
+    pub fn toggle(self) -> Self {
+        match self {
+            GameOverChoice::Retry => GameOverChoice::ReturnToStart,
+            GameOverChoice::ReturnToStart => GameOverChoice::Retry,
+        }
+    }
+}
+
+impl AppState {
+    /// Switches between playing and paused. The game-over, level-complete, and menu
+    /// screens can't be paused.
+    pub fn toggle_pause(&mut self) {
+        *self = match *self {
+            AppState::Playing => AppState::Paused,
+            AppState::Paused => AppState::Playing,
+            other => other,
+        };
+    }
+}
@@ -0,0 +1,178 @@
+// aseprite.rs
+use crate::engine::assets::sprite_sheet::{FrameBox, SpriteFrame, SpriteSheet};
+use crate::engine::assets::AssetError;
+use crate::engine::mods::resolve;
+use log::{info, warn};
+use serde::Deserialize;
+use std::collections::HashMap;
+
+#[derive(Deserialize)]
+struct AsepriteExport {
+    /// An array of frames, or an object keyed by frame name ("Hash" export).
+    frames: serde_json::Value,
+    meta: AsepriteMeta,
+}
+
+#[derive(Deserialize)]
+struct AsepriteFrame {
+    frame: AsepriteRect,
+    duration: u32,
+}
+
+#[derive(Deserialize)]
+struct AsepriteRect {
+    x: u32,
+    y: u32,
+    w: u32,
+    h: u32,
+}
+
+#[derive(Deserialize)]
+struct AsepriteMeta {
+    size: AsepriteSize,
+    #[serde(default, rename = "frameTags")]
+    frame_tags: Vec<AsepriteTag>,
+    #[serde(default)]
+    slices: Vec<AsepriteSlice>,
+}
+
+#[derive(Deserialize)]
+struct AsepriteSize {
+    w: u32,
+    h: u32,
+}
+
+#[derive(Deserialize)]
+struct AsepriteTag {
+    name: String,
+    from: usize,
+    to: usize,
+}
+
+#[derive(Deserialize)]
+struct AsepriteSlice {
+    name: String,
+    keys: Vec<AsepriteSliceKey>,
+}
+
+#[derive(Deserialize)]
+struct AsepriteSliceKey {
+    frame: usize,
+    bounds: AsepriteRect,
+}
+
+/// Loads the JSON data Aseprite exports alongside a sprite sheet image.
+///
+/// Both the "Array" and "Hash" frame layouts are supported. Frame rectangles are
+/// converted to texture coordinates using the sheet size in `meta.size`, and must lie
+/// within it; frame
+/// durations are converted from milliseconds to seconds, and each frame tag
+/// becomes a named animation range.
+///
+/// Slices named `hitbox` and `hurtbox` give frames their attack and vulnerable
+/// areas. Each slice key applies from its frame until the next key, as in Aseprite,
+/// and a key with an empty rectangle turns the box off.
+///
+/// # Arguments
+/// - `path`: Path to the exported JSON file.
+///
+/// # Returns
+/// The `SpriteSheet` described by the file, or an `AssetError` if it is unreadable.
+pub fn load_aseprite_sheet(path: &str) -> Result<SpriteSheet, AssetError> {
+    info!("Loading Aseprite sprite sheet: {}", path);
+    let source = std::fs::read_to_string(resolve(path))?;
+    let export: AsepriteExport =
+        serde_json::from_str(&source).map_err(|e| AssetError::Parse(e.to_string()))?;
+
+    let frames: Vec<AsepriteFrame> = match export.frames {
+        serde_json::Value::Array(frames) => frames
+            .into_iter()
+            .map(serde_json::from_value)
+            .collect::<Result<_, _>>(),
+        serde_json::Value::Object(frames) => frames
+            .into_iter()
+            .map(|(_, frame)| serde_json::from_value(frame))
+            .collect::<Result<_, _>>(),
+        _ => return Err(AssetError::Parse("'frames' must be an array or object".to_string())),
+    }
+    .map_err(|e| AssetError::Parse(e.to_string()))?;
+
+    let (sheet_width, sheet_height) = (export.meta.size.w as f32, export.meta.size.h as f32);
+    if sheet_width == 0.0 || sheet_height == 0.0 {
+        return Err(AssetError::Parse("sheet size is zero".to_string()));
+    }
+    if let Some((i, frame)) = frames.iter().enumerate().find(|(_, frame)| {
+        frame.frame.x + frame.frame.w > export.meta.size.w || frame.frame.y + frame.frame.h > export.meta.size.h
+    }) {
+        return Err(AssetError::Parse(format!(
+            "frame {} at {},{} sized {}x{} lies outside the {}x{} sheet",
+            i, frame.frame.x, frame.frame.y, frame.frame.w, frame.frame.h, export.meta.size.w, export.meta.size.h
+        )));
+    }
+
+    let frames: Vec<SpriteFrame> = frames
+        .iter()
+        .enumerate()
+        .map(|(i, frame)| SpriteFrame {
+            uv_offset: [
+                frame.frame.x as f32 / sheet_width,
+                frame.frame.y as f32 / sheet_height,
+            ],
+            uv_scale: [
+                frame.frame.w as f32 / sheet_width,
+                frame.frame.h as f32 / sheet_height,
+            ],
+            duration: frame.duration as f32 / 1000.0,
+            hitbox: slice_box(&export.meta.slices, "hitbox", i, &frame.frame),
+            hurtbox: slice_box(&export.meta.slices, "hurtbox", i, &frame.frame),
+        })
+        .collect();
+
+    let mut tags = HashMap::new();
+    for tag in export.meta.frame_tags {
+        if tag.from > tag.to || tag.to >= frames.len() {
+            warn!(
+                "Ignoring tag '{}' with frames {}..={} in a {}-frame sheet",
+                tag.name,
+                tag.from,
+                tag.to,
+                frames.len()
+            );
+            continue;
+        }
+        tags.insert(tag.name, (tag.from, tag.to));
+    }
+
+    info!("Loaded {} frame(s) and {} tag(s) from {}", frames.len(), tags.len(), path);
+    Ok(SpriteSheet {
+        frames,
+        tags,
+        image_size: Some((export.meta.size.w, export.meta.size.h)),
+    })
+}
+
+/// Returns the box a named slice gives a frame, as fractions of the frame's size.
+///
+/// # Arguments
+/// - `slices`: The sheet's slices.
+/// - `name`: Name of the slice to look up.
+/// - `index`: Index of the frame.
+/// - `rect`: The frame's rectangle in the sheet, whose size the slice bounds are relative to.
+///
+/// # Returns
+/// The box from the slice's last key at or before the frame, or `None` if there is no
+/// such key or its rectangle is empty.
+fn slice_box(slices: &[AsepriteSlice], name: &str, index: usize, rect: &AsepriteRect) -> Option<FrameBox> {
+    let slice = slices.iter().find(|slice| slice.name == name)?;
+    let key = slice.keys.iter().filter(|key| key.frame <= index).max_by_key(|key| key.frame)?;
+    let bounds = &key.bounds;
+    if bounds.w == 0 || bounds.h == 0 || rect.w == 0 || rect.h == 0 {
+        return None;
+    }
+    Some(FrameBox {
+        x: bounds.x as f32 / rect.w as f32,
+        y: bounds.y as f32 / rect.h as f32,
+        width: bounds.w as f32 / rect.w as f32,
+        height: bounds.h as f32 / rect.h as f32,
+    })
+}
@@ -0,0 +1,39 @@
+// src/engine/assets/mod.rs
+
+pub mod aseprite;
+pub mod sound;
+pub mod sprite_sheet;
+pub mod watcher;
+
+pub use aseprite::load_aseprite_sheet;
+pub use sound::{load_wav, Sound};
+pub use sprite_sheet::SpriteSheet;
+pub use watcher::AssetWatcher;
+
+use std::fmt;
+
+/// Errors that can occur while importing an asset description.
+#[derive(Debug)]
+pub enum AssetError {
+    /// The asset file could not be read.
+    Io(std::io::Error),
+    /// The asset file is malformed.
+    Parse(String),
+}
+
+impl fmt::Display for AssetError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AssetError::Io(e) => write!(f, "failed to read asset: {}", e),
+            AssetError::Parse(msg) => write!(f, "failed to parse asset: {}", msg),
+        }
+    }
+}
+
+impl std::error::Error for AssetError {}
+
+impl From<std::io::Error> for AssetError {
+    fn from(e: std::io::Error) -> Self {
+        AssetError::Io(e)
+    }
+}
@@ -0,0 +1,59 @@
+// sound.rs
+use crate::engine::assets::AssetError;
+use crate::engine::mods::resolve;
+use log::info;
+
+/// Decoded audio samples, ready for the mixer.
+pub struct Sound {
+    /// Interleaved samples in the range -1.0..=1.0.
+    pub samples: Vec<f32>,
+    pub channels: usize,
+    pub sample_rate: u32,
+}
+
+impl Sound {
+    /// Returns the number of frames, i.e. samples per channel.
+    pub fn frames(&self) -> usize {
+        self.samples.len() / self.channels
+    }
+}
+
+/// Loads and decodes a WAV file.
+///
+/// Integer samples of any bit depth and 32-bit float samples are supported.
+///
+/// # Arguments
+/// - `path`: Path to the WAV file.
+///
+/// # Returns
+/// The decoded `Sound`, or an `AssetError` if the file is unreadable or not a supported WAV.
+pub fn load_wav(path: &str) -> Result<Sound, AssetError> {
+    info!("Loading sound: {}", path);
+    let mut reader = hound::WavReader::open(resolve(path)).map_err(|e| match e {
+        hound::Error::IoError(e) => AssetError::Io(e),
+        e => AssetError::Parse(e.to_string()),
+    })?;
+    let spec = reader.spec();
+
+    let samples: Result<Vec<f32>, _> = match spec.sample_format {
+        hound::SampleFormat::Float => reader.samples::<f32>().collect(),
+        hound::SampleFormat::Int => {
+            let scale = 1.0 / (1i64 << (spec.bits_per_sample - 1)) as f32;
+            reader
+                .samples::<i32>()
+                .map(|sample| sample.map(|sample| sample as f32 * scale))
+                .collect()
+        }
+    };
+    let samples = samples.map_err(|e| AssetError::Parse(e.to_string()))?;
+
+    if spec.channels == 0 || samples.is_empty() {
+        return Err(AssetError::Parse("sound has no samples".to_string()));
+    }
+
+    Ok(Sound {
+        samples,
+        channels: spec.channels as usize,
+        sample_rate: spec.sample_rate,
+    })
+}
@@ -0,0 +1,129 @@
+// sprite_sheet.rs
+use crate::engine::collision::Aabb;
+use std::collections::HashMap;
+
+/// A box within a frame, such as an attack's hitbox, as fractions of the frame's
+/// size measured from its top-left corner.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct FrameBox {
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+}
+
+impl FrameBox {
+    /// Places the box in the world on a sprite drawn at a position and size.
+    ///
+    /// # Arguments
+    ///
+    /// * `center` - Center of the sprite in world coordinates.
+    /// * `size` - Width and height the sprite is drawn at.
+    /// * `facing_right` - Whether the sprite is drawn as is, rather than mirrored.
+    pub fn place(&self, center: (f32, f32), size: (f32, f32), facing_right: bool) -> Aabb {
+        let left = if facing_right { self.x } else { 1.0 - self.x - self.width };
+        let min_x = center.0 + (left - 0.5) * size.0;
+        let max_y = center.1 + (0.5 - self.y) * size.1;
+        Aabb {
+            min_x,
+            min_y: max_y - self.height * size.1,
+            max_x: min_x + self.width * size.0,
+            max_y,
+        }
+    }
+}
+
+/// A single animation frame within a sprite sheet texture.
+#[derive(Clone, Copy, Debug)]
+pub struct SpriteFrame {
+    /// Top-left corner of the frame in texture coordinates.
+    pub uv_offset: [f32; 2],
+    /// Size of the frame in texture coordinates.
+    pub uv_scale: [f32; 2],
+    /// How long the frame is shown, in seconds.
+    pub duration: f32,
+    /// Area the frame's attack strikes, if it attacks.
+    pub hitbox: Option<FrameBox>,
+    /// Area the character can be hurt in; `None` leaves it to the collision box.
+    pub hurtbox: Option<FrameBox>,
+}
+
+/// Frame layout and named animation ranges for a sprite sheet texture.
+pub struct SpriteSheet {
+    pub frames: Vec<SpriteFrame>,
+    /// Inclusive frame ranges for each named animation (Aseprite tags).
+    pub tags: HashMap<String, (usize, usize)>,
+    /// Size in pixels of the image the frames were laid out on, if known.
+    pub image_size: Option<(u32, u32)>,
+}
+
+impl SpriteSheet {
+    /// Creates an untagged sheet of equally sized frames laid out left to right, top to bottom.
+    ///
+    /// # Arguments
+    ///
+    /// * `columns` - Number of frames per row.
+    /// * `rows` - Number of rows.
+    /// * `duration` - How long each frame is shown, in seconds.
+    pub fn grid(columns: usize, rows: usize, duration: f32) -> Self {
+        let uv_scale = [1.0 / columns as f32, 1.0 / rows as f32];
+        let frames = (0..columns * rows)
+            .map(|i| SpriteFrame {
+                uv_offset: [
+                    (i % columns) as f32 * uv_scale[0],
+                    (i / columns) as f32 * uv_scale[1],
+                ],
+                uv_scale,
+                duration,
+                hitbox: None,
+                hurtbox: None,
+            })
+            .collect();
+
+        Self {
+            frames,
+            tags: HashMap::new(),
+            image_size: None,
+        }
+    }
+
+    /// Checks the sheet against the texture it's drawn from and the animations played from it.
+    ///
+    /// # Arguments
+    ///
+    /// * `texture_size` - Size of the loaded texture in pixels.
+    /// * `animations` - Each animation's name and inclusive frame range.
+    ///
+    /// # Returns
+    ///
+    /// A description of each problem found: a texture of a different size than the
+    /// frames were laid out on, whose UVs would sample the wrong pixels, or an
+    /// animation using frames the sheet doesn't have.
+    pub fn validate<'a>(
+        &self,
+        texture_size: (u32, u32),
+        animations: impl IntoIterator<Item = (&'a str, (usize, usize))>,
+    ) -> Vec<String> {
+        let mut problems = Vec::new();
+        if let Some(image_size) = self.image_size {
+            if image_size != texture_size {
+                problems.push(format!(
+                    "frames are laid out on a {}x{} image, but the texture is {}x{}",
+                    image_size.0, image_size.1, texture_size.0, texture_size.1
+                ));
+            }
+        }
+        for (name, (first, last)) in animations {
+            if first > last || last >= self.frames.len() {
+                problems.push(format!(
+                    "animation '{}' uses frames {}..={}, but the sheet has {}",
+                    name,
+                    first,
+                    last,
+                    self.frames.len()
+                ));
+            }
+        }
+        problems
+    }
+}
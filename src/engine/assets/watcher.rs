@@ -0,0 +1,73 @@
+// watcher.rs
+use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{channel, Receiver};
+use log::info;
+
+/// Watches an asset directory for files changed on disk.
+///
+/// Changes are collected in the background and handed out by `changed_paths`,
+/// so the game loop can reload them between frames.
+pub struct AssetWatcher {
+    /// Kept alive so the background watch keeps running.
+    _watcher: RecommendedWatcher,
+    events: Receiver<notify::Result<Event>>,
+    /// The directory as given, used to rebuild paths in the form assets are loaded with.
+    root: PathBuf,
+    /// The directory as reported in events.
+    canonical_root: PathBuf,
+}
+
+impl AssetWatcher {
+    /// Starts watching a directory and everything under it.
+    ///
+    /// # Arguments
+    /// - `root`: The asset directory, relative to the working directory.
+    ///
+    /// # Returns
+    /// The watcher, or a `notify::Error` if the directory can't be watched.
+    pub fn new(root: &str) -> Result<Self, notify::Error> {
+        let canonical_root = Path::new(root).canonicalize()?;
+        let (sender, events) = channel();
+        let mut watcher = notify::recommended_watcher(sender)?;
+        watcher.watch(&canonical_root, RecursiveMode::Recursive)?;
+        info!("Watching '{}' for asset changes", root);
+
+        Ok(Self {
+            _watcher: watcher,
+            events,
+            root: PathBuf::from(root),
+            canonical_root,
+        })
+    }
+
+    /// Returns the files created or modified since the last call.
+    ///
+    /// Paths are relative to the working directory, e.g. `assets/levels/level1.json`,
+    /// matching the paths assets are loaded and cached under. Each path is listed once,
+    /// however many times it changed.
+    pub fn changed_paths(&self) -> Vec<String> {
+        let mut changed = BTreeSet::new();
+        for event in self.events.try_iter() {
+            let event = match event {
+                Ok(event) => event,
+                Err(e) => {
+                    log::warn!("Asset watcher error: {}", e);
+                    continue;
+                }
+            };
+            // Editors often save through a temporary file renamed over the original,
+            // which arrives as a create or a rename rather than a data modification
+            if !matches!(event.kind, EventKind::Create(_) | EventKind::Modify(_)) {
+                continue;
+            }
+            for path in event.paths {
+                if let Ok(relative) = path.strip_prefix(&self.canonical_root) {
+                    changed.insert(self.root.join(relative).to_string_lossy().replace('\\', "/"));
+                }
+            }
+        }
+        changed.into_iter().collect()
+    }
+}
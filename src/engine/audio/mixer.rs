@@ -0,0 +1,175 @@
+// mixer.rs
+use crate::engine::assets::Sound;
+use std::sync::Arc;
+
+/// A group of voices whose playback rate is controlled together.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Bus {
+    Music,
+    Sfx,
+    /// Ambient loops layered under the music, such as wind or dripping water.
+    Ambience,
+}
+
+impl Bus {
+    /// Every bus, in index order.
+    pub const ALL: [Bus; 3] = [Bus::Music, Bus::Sfx, Bus::Ambience];
+
+    fn index(self) -> usize {
+        match self {
+            Bus::Music => 0,
+            Bus::Sfx => 1,
+            Bus::Ambience => 2,
+        }
+    }
+}
+
+/// Identifies a playing sound, to change its volume or stop it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct VoiceId(u64);
+
+/// A sound being played.
+struct Voice {
+    id: VoiceId,
+    sound: Arc<Sound>,
+    /// Read position in source frames; fractional between frames.
+    position: f64,
+    volume: f32,
+    looping: bool,
+    bus: Bus,
+}
+
+/// Mixes playing sounds into an output buffer, resampling each to the output rate.
+///
+/// Each bus has a playback rate that speeds up or slows down its voices, shifting
+/// pitch and tempo together like a tape or record played at a different speed.
+pub struct Mixer {
+    voices: Vec<Voice>,
+    output_rate: u32,
+    bus_rates: [f32; 3],
+    /// Id given to the next voice played.
+    next_id: u64,
+}
+
+impl Mixer {
+    /// Creates a mixer with no voices.
+    ///
+    /// # Arguments
+    ///
+    /// * `output_rate` - Sample rate of the output buffers, in Hz.
+    pub fn new(output_rate: u32) -> Self {
+        Self {
+            voices: Vec::new(),
+            output_rate,
+            bus_rates: [1.0; 3],
+            next_id: 0,
+        }
+    }
+
+    /// Returns the number of sounds playing.
+    pub fn voice_count(&self) -> usize {
+        self.voices.len()
+    }
+
+    /// Returns the sample rate of the output buffers, in Hz.
+    pub fn output_rate(&self) -> u32 {
+        self.output_rate
+    }
+
+    /// Starts playing a sound.
+    ///
+    /// # Arguments
+    ///
+    /// * `sound` - The sound to play.
+    /// * `bus` - The bus to play it on.
+    /// * `volume` - Gain applied to the sound's samples.
+    /// * `looping` - Whether to restart the sound when it ends instead of stopping.
+    ///
+    /// # Returns
+    ///
+    /// The id of the new voice.
+    pub fn play(&mut self, sound: Arc<Sound>, bus: Bus, volume: f32, looping: bool) -> VoiceId {
+        let id = VoiceId(self.next_id);
+        self.next_id += 1;
+        self.voices.push(Voice {
+            id,
+            sound,
+            position: 0.0,
+            volume,
+            looping,
+            bus,
+        });
+        id
+    }
+
+    /// Changes the volume of a playing voice. Voices that have finished are skipped.
+    pub fn set_volume(&mut self, id: VoiceId, volume: f32) {
+        if let Some(voice) = self.voices.iter_mut().find(|voice| voice.id == id) {
+            voice.volume = volume;
+        }
+    }
+
+    /// Stops a voice straight away, if it's still playing.
+    pub fn stop(&mut self, id: VoiceId) {
+        self.voices.retain(|voice| voice.id != id);
+    }
+
+    /// Sets the playback rate of a bus, where 1.0 is normal speed and pitch.
+    pub fn set_bus_rate(&mut self, bus: Bus, rate: f32) {
+        self.bus_rates[bus.index()] = rate.max(0.0);
+    }
+
+    /// Mixes all voices into the output buffer, replacing its contents.
+    ///
+    /// Voices are resampled with linear interpolation. Mono sounds play on every
+    /// output channel; extra output channels repeat a sound's last channel.
+    /// Voices that reach their end without looping are removed.
+    ///
+    /// # Arguments
+    ///
+    /// * `output` - Interleaved output samples.
+    /// * `channels` - Number of output channels.
+    pub fn mix(&mut self, output: &mut [f32], channels: usize) {
+        output.fill(0.0);
+
+        for voice in &mut self.voices {
+            let sound = &voice.sound;
+            let frames = sound.frames();
+            let step = sound.sample_rate as f64 / self.output_rate as f64
+                * self.bus_rates[voice.bus.index()] as f64;
+
+            for frame in output.chunks_mut(channels) {
+                if voice.position >= frames as f64 {
+                    if !voice.looping {
+                        break;
+                    }
+                    voice.position %= frames as f64;
+                }
+
+                let index = voice.position as usize;
+                let t = (voice.position - index as f64) as f32;
+                let next = match index + 1 {
+                    next if next < frames => next,
+                    _ if voice.looping => 0,
+                    _ => index,
+                };
+
+                for (channel, sample) in frame.iter_mut().enumerate() {
+                    let source = channel.min(sound.channels - 1);
+                    let a = sound.samples[index * sound.channels + source];
+                    let b = sound.samples[next * sound.channels + source];
+                    *sample += (a + (b - a) * t) * voice.volume;
+                }
+
+                voice.position += step;
+            }
+        }
+
+        self.voices
+            .retain(|voice| voice.looping || voice.position < voice.sound.frames() as f64);
+
+        for sample in output.iter_mut() {
+            *sample = sample.clamp(-1.0, 1.0);
+        }
+    }
+}
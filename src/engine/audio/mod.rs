@@ -0,0 +1,186 @@
+// src/engine/audio/mod.rs
+
+pub mod mixer;
+
+pub use mixer::{Bus, Mixer, VoiceId};
+
+use crate::engine::assets::Sound;
+use crate::engine::constants::{MUSIC_FOLLOWS_TIME_SCALE, SFX_FOLLOWS_TIME_SCALE};
+use log::info;
+#[cfg(feature = "audio")]
+use log::warn;
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+
+/// Sample rate the mixer runs at when there is no output device.
+const HEADLESS_SAMPLE_RATE: u32 = 44_100;
+
+/// Slowest rate the time scale can play sound at, so hitstop doesn't silence the music.
+const MIN_PLAYBACK_RATE: f32 = 0.25;
+
+/// Plays sounds through the default output device.
+///
+/// When built without the `audio` feature, or when no device can be opened, the
+/// mixer is advanced silently instead so sounds still start and finish on time.
+pub struct Audio {
+    mixer: Arc<Mutex<Mixer>>,
+    #[cfg(feature = "audio")]
+    _stream: Option<cpal::Stream>,
+    /// Whether `update` has to advance the mixer because no device is pulling samples from it.
+    headless: bool,
+    /// When the mixer was last advanced without an output device.
+    last_update: Instant,
+    /// Buffer the mixer writes into when there is no output device.
+    scratch: Vec<f32>,
+}
+
+impl Audio {
+    /// Opens the default output device, falling back to silent mixing if there is none.
+    pub fn new() -> Self {
+        #[cfg(feature = "audio")]
+        match open_output() {
+            Ok((stream, mixer)) => {
+                info!("Opened audio output at {} Hz", mixer.lock().unwrap().output_rate());
+                return Self {
+                    mixer,
+                    _stream: Some(stream),
+                    headless: false,
+                    last_update: Instant::now(),
+                    scratch: Vec::new(),
+                };
+            }
+            Err(e) => warn!("Could not open audio output, continuing without sound: {}", e),
+        }
+
+        #[cfg(not(feature = "audio"))]
+        info!("Built without the `audio` feature; sounds are mixed silently");
+
+        Self {
+            mixer: Arc::new(Mutex::new(Mixer::new(HEADLESS_SAMPLE_RATE))),
+            #[cfg(feature = "audio")]
+            _stream: None,
+            headless: true,
+            last_update: Instant::now(),
+            scratch: Vec::new(),
+        }
+    }
+
+    /// Starts playing a sound.
+    ///
+    /// # Arguments
+    ///
+    /// * `sound` - The sound to play.
+    /// * `bus` - The bus to play it on.
+    /// * `volume` - Gain applied to the sound's samples.
+    /// * `looping` - Whether to restart the sound when it ends instead of stopping.
+    ///
+    /// # Returns
+    ///
+    /// The id of the new voice, for changing its volume or stopping it later.
+    pub fn play(&self, sound: &Arc<Sound>, bus: Bus, volume: f32, looping: bool) -> VoiceId {
+        self.mixer
+            .lock()
+            .unwrap()
+            .play(Arc::clone(sound), bus, volume, looping)
+    }
+
+    /// Changes the volume of a playing sound.
+    pub fn set_volume(&self, voice: VoiceId, volume: f32) {
+        self.mixer.lock().unwrap().set_volume(voice, volume);
+    }
+
+    /// Stops a playing sound.
+    pub fn stop(&self, voice: VoiceId) {
+        self.mixer.lock().unwrap().stop(voice);
+    }
+
+    /// Returns the number of sounds playing.
+    pub fn voice_count(&self) -> usize {
+        self.mixer.lock().unwrap().voice_count()
+    }
+
+    /// Shifts the pitch and tempo of the buses that follow the game's time scale.
+    ///
+    /// Which buses follow is set by `MUSIC_FOLLOWS_TIME_SCALE`, which ambience follows
+    /// too, and `SFX_FOLLOWS_TIME_SCALE`.
+    ///
+    /// # Arguments
+    ///
+    /// * `scale` - The factor game time is currently scaled by.
+    pub fn set_time_scale(&self, scale: f32) {
+        let mut mixer = self.mixer.lock().unwrap();
+        for bus in Bus::ALL {
+            let follows = match bus {
+                Bus::Music | Bus::Ambience => MUSIC_FOLLOWS_TIME_SCALE,
+                Bus::Sfx => SFX_FOLLOWS_TIME_SCALE,
+            };
+            mixer.set_bus_rate(bus, if follows { scale.max(MIN_PLAYBACK_RATE) } else { 1.0 });
+        }
+    }
+
+    /// Advances the mixer by the real time elapsed since the last call, if no device is doing so.
+    pub fn update(&mut self) {
+        if !self.headless {
+            return;
+        }
+
+        let now = Instant::now();
+        // Cap long gaps so a stall doesn't allocate a huge buffer
+        let elapsed = now.duration_since(self.last_update).as_secs_f32().min(1.0);
+        self.last_update = now;
+
+        let mut mixer = self.mixer.lock().unwrap();
+        let frames = (elapsed * mixer.output_rate() as f32) as usize;
+        self.scratch.resize(frames, 0.0);
+        mixer.mix(&mut self.scratch, 1);
+    }
+}
+
+impl Default for Audio {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Opens the default output device and starts a stream that pulls samples from a new mixer.
+#[cfg(feature = "audio")]
+fn open_output() -> Result<(cpal::Stream, Arc<Mutex<Mixer>>), String> {
+    use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
+
+    let device = cpal::default_host()
+        .default_output_device()
+        .ok_or("no output device")?;
+    let default_rate = device
+        .default_output_config()
+        .map_err(|e| e.to_string())?
+        .sample_rate();
+    let supported = device
+        .supported_output_configs()
+        .map_err(|e| e.to_string())?
+        .find(|config| config.sample_format() == cpal::SampleFormat::F32)
+        .ok_or("output device doesn't support f32 samples")?;
+
+    // Prefer the device's default rate when the f32 configuration allows it
+    let rate = default_rate
+        .0
+        .clamp(supported.min_sample_rate().0, supported.max_sample_rate().0);
+    let config: cpal::StreamConfig = supported.with_sample_rate(cpal::SampleRate(rate)).into();
+    let channels = config.channels as usize;
+
+    let mixer = Arc::new(Mutex::new(Mixer::new(rate)));
+    let callback_mixer = Arc::clone(&mixer);
+    let stream = device
+        .build_output_stream(
+            &config,
+            move |data: &mut [f32], _: &cpal::OutputCallbackInfo| match callback_mixer.lock() {
+                Ok(mut mixer) => mixer.mix(data, channels),
+                Err(_) => data.fill(0.0),
+            },
+            |e| warn!("Audio output error: {}", e),
+            None,
+        )
+        .map_err(|e| e.to_string())?;
+    stream.play().map_err(|e| e.to_string())?;
+
+    Ok((stream, mixer))
+}
@@ -0,0 +1,112 @@
+// background.rs
+use crate::engine::config::ConfigError;
+use crate::engine::mods::resolve;
+use serde::Deserialize;
+use std::path::Path;
+
+/// A background layer drawn behind the level, scrolling slower the farther away it is.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct BackgroundLayer {
+    /// Path of the layer's image.
+    pub image: String,
+    /// Fraction of the camera's horizontal movement the layer scrolls by.
+    pub parallax: f32,
+    /// The horizontal band of the image drawn, as fractions of its height from the top.
+    #[serde(default = "full_band")]
+    pub band: [f32; 2],
+}
+
+/// Returns the band covering a whole image.
+fn full_band() -> [f32; 2] {
+    [0.0, 1.0]
+}
+
+/// A single tall image sliced into horizontal bands, each becoming its own layer.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Bands {
+    /// Path of the image to slice.
+    image: String,
+    /// Number of equally tall bands to slice the image into.
+    count: usize,
+    /// Parallax of the top and bottom bands; the bands between are spread evenly.
+    parallax: [f32; 2],
+}
+
+/// The background layers as read from the backgrounds file.
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct BackgroundConfig {
+    /// Images to slice into layers, drawn behind `layers`.
+    bands: Option<Bands>,
+    /// Layers listed one by one, from the farthest to the nearest.
+    layers: Vec<BackgroundLayer>,
+}
+
+/// Reads the background layers from a TOML file, from the farthest to the nearest.
+///
+/// Layers are either listed one by one, or generated from a single tall image by
+/// slicing it into bands: the top of such an image shows what is farthest away,
+/// like the sky, so the top band scrolls slowest and each band below it faster.
+///
+/// # Arguments
+///
+/// * `path` - Path to the TOML file.
+pub fn load_backgrounds(path: impl AsRef<Path>) -> Result<Vec<BackgroundLayer>, ConfigError> {
+    let source = std::fs::read_to_string(resolve(path))?;
+    let config: BackgroundConfig = toml::from_str(&source).map_err(|e| ConfigError::Parse(e.to_string()))?;
+
+    let mut layers = match &config.bands {
+        Some(bands) => slice_bands(&bands.image, bands.count, bands.parallax),
+        None => Vec::new(),
+    };
+    layers.extend(config.layers);
+    Ok(layers)
+}
+
+/// Generates the layers for an image sliced into equally tall horizontal bands,
+/// from the top band, scrolling slowest, to the bottom one.
+///
+/// # Arguments
+///
+/// * `image` - Path of the image to slice.
+/// * `count` - Number of bands.
+/// * `parallax` - Parallax of the top and bottom bands.
+pub fn slice_bands(image: &str, count: usize, parallax: [f32; 2]) -> Vec<BackgroundLayer> {
+    (0..count)
+        .map(|i| {
+            let t = if count > 1 { i as f32 / (count - 1) as f32 } else { 0.0 };
+            let layer = BackgroundLayer {
+                image: image.to_string(),
+                parallax: parallax[0] + (parallax[1] - parallax[0]) * t,
+                band: [i as f32 / count as f32, (i + 1) as f32 / count as f32],
+            };
+            // Logged in the backgrounds file's own format, to copy out and fine-tune
+            log::info!(
+                "Generated background layer: image = \"{}\", parallax = {:.3}, band = [{:.3}, {:.3}]",
+                layer.image,
+                layer.parallax,
+                layer.band[0],
+                layer.band[1]
+            );
+            layer
+        })
+        .collect()
+}
+
+/// Returns the layers drawn when the backgrounds file can't be read.
+pub fn default_backgrounds() -> Vec<BackgroundLayer> {
+    [
+        ("assets/tileset/BG1.png", 0.05),
+        ("assets/tileset/BG2.png", 0.2),
+        ("assets/tileset/BG3.png", 0.6),
+    ]
+    .into_iter()
+    .map(|(image, parallax)| BackgroundLayer {
+        image: image.to_string(),
+        parallax,
+        band: full_band(),
+    })
+    .collect()
+}
@@ -0,0 +1,146 @@
+// camera.rs
+use crate::engine::collision::Aabb;
+use crate::engine::config::tuning;
+use crate::engine::tween::Timer;
+
+/// Half the width of the visible area in world units (the view spans -1..1).
+const VIEW_HALF_WIDTH: f32 = 1.0;
+
+/// A temporary framing requested by a trigger or script, such as zooming out
+/// over a boss arena or in on a dialogue speaker.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct CameraShot {
+    /// Magnification to ease to; above 1.0 zooms in, below 1.0 zooms out.
+    pub zoom: f32,
+    /// World position to center on instead of the player, if any.
+    pub focus: Option<(f32, f32)>,
+    /// Seconds before the camera restores itself, or `None` to hold until released.
+    pub duration: Option<f32>,
+}
+
+/// A 2D camera that scrolls the world horizontally to follow a target.
+///
+/// World positions are converted to view space by subtracting the camera
+/// position and scaling by the zoom. The camera can be confined to a region
+/// (such as the current room) so it never shows anything outside of it.
+#[derive(Clone)]
+pub struct Camera {
+    /// Horizontal position of the view center in the game world.
+    pub x: f32,
+    /// Vertical position of the view center in the game world.
+    pub y: f32,
+    /// Current magnification; above 1.0 zooms in.
+    pub zoom: f32,
+    bounds: Option<Aabb>,
+    shot: Option<CameraShot>,
+    /// Counts down a shot with a duration.
+    shot_timer: Option<Timer>,
+    /// How far the view has moved from the follow target toward the focus, from 0.0 to 1.0.
+    focus_blend: f32,
+    /// The last focus point, kept so the camera can ease back from it after a shot ends.
+    focus: (f32, f32),
+}
+
+impl Camera {
+    /// Creates a camera centered on the world origin with no bounds.
+    pub fn new() -> Self {
+        Self {
+            x: 0.0,
+            y: 0.0,
+            zoom: 1.0,
+            bounds: None,
+            shot: None,
+            shot_timer: None,
+            focus_blend: 0.0,
+            focus: (0.0, 0.0),
+        }
+    }
+
+    /// Confines the camera to the given region, or removes the limit with `None`.
+    pub fn set_bounds(&mut self, bounds: Option<Aabb>) {
+        self.bounds = bounds;
+    }
+
+    /// Starts easing toward a shot, replacing any shot already in progress.
+    pub fn request_shot(&mut self, shot: CameraShot) {
+        self.shot = Some(shot);
+        self.shot_timer = shot.duration.map(Timer::new);
+    }
+
+    /// Eases back to following the player if the given shot is the one in progress.
+    pub fn release_shot(&mut self, shot: &CameraShot) {
+        if self.shot.as_ref() == Some(shot) {
+            self.shot = None;
+            self.shot_timer = None;
+        }
+    }
+
+    /// Counts down the current shot and eases the zoom and focus toward it.
+    ///
+    /// # Arguments
+    ///
+    /// * `delta_time` - Time elapsed since the last frame.
+    pub fn update(&mut self, delta_time: f32) {
+        if self.shot_timer.as_mut().is_some_and(|timer| timer.tick(delta_time)) {
+            self.shot = None;
+            self.shot_timer = None;
+        }
+
+        let (target_zoom, target_blend) = match self.shot {
+            Some(CameraShot { zoom, focus: Some(focus), .. }) => {
+                self.focus = focus;
+                (zoom, 1.0)
+            }
+            Some(CameraShot { zoom, focus: None, .. }) => (zoom, 0.0),
+            None => (1.0, 0.0),
+        };
+
+        // Exponential easing, independent of the frame rate
+        let t = 1.0 - (-tuning().camera_ease_speed * delta_time).exp();
+        self.zoom += (target_zoom - self.zoom) * t;
+        self.focus_blend += (target_blend - self.focus_blend) * t;
+    }
+
+    /// Returns the area of the world in view.
+    pub fn view_bounds(&self) -> Aabb {
+        let half_extent = VIEW_HALF_WIDTH / self.zoom;
+        Aabb::from_center(self.x, self.y, 2.0 * half_extent, 2.0 * half_extent)
+    }
+
+    /// Converts a point in screen coordinates (-1..1, y up), such as the cursor
+    /// position, to the world position drawn there.
+    #[cfg_attr(not(debug_assertions), allow(dead_code))]
+    pub fn screen_to_world(&self, (x, y): (f32, f32)) -> (f32, f32) {
+        (x / self.zoom + self.x, y / self.zoom + self.y)
+    }
+
+    /// Converts a world position to the screen coordinates (-1..1, y up) it's drawn
+    /// at, such as to place UI over something in the world.
+    pub fn world_to_screen(&self, (x, y): (f32, f32)) -> (f32, f32) {
+        ((x - self.x) * self.zoom, (y - self.y) * self.zoom)
+    }
+
+    /// Centers the camera horizontally on the target, clamped to the current bounds.
+    ///
+    /// While a shot has a focus, the view is pulled from the target toward it.
+    /// If the bounds are narrower than the view, the camera centers on them instead.
+    pub fn follow(&mut self, target_x: f32) {
+        let target_x = target_x + (self.focus.0 - target_x) * self.focus_blend;
+        let half_width = VIEW_HALF_WIDTH / self.zoom;
+
+        self.x = match self.bounds {
+            Some(bounds) if bounds.max_x - bounds.min_x <= half_width * 2.0 => {
+                (bounds.min_x + bounds.max_x) / 2.0
+            }
+            Some(bounds) => target_x.clamp(bounds.min_x + half_width, bounds.max_x - half_width),
+            None => target_x,
+        };
+        self.y = self.focus.1 * self.focus_blend;
+    }
+}
+
+impl Default for Camera {
+    fn default() -> Self {
+        Self::new()
+    }
+}
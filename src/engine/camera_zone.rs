@@ -0,0 +1,80 @@
+// camera_zone.rs
+use crate::engine::camera::{Camera, CameraShot};
+use crate::engine::collision::Aabb;
+use crate::engine::game_state::GameState;
+use crate::engine::level::Level;
+use log::{info, warn};
+
+/// A trigger region that reframes the camera while the player is inside it.
+struct CameraZone {
+    name: String,
+    bounds: Aabb,
+    shot: CameraShot,
+    /// Whether the player was inside last frame, so the shot is only requested on entry.
+    occupied: bool,
+}
+
+/// Zooms and refocuses the camera as the player walks through trigger regions.
+///
+/// Zones come from `camera_zone` rectangles in the level, with these properties:
+/// - `zoom`: magnification to ease to (default 1.0); below 1.0 zooms out.
+/// - `focus`: name of another level object whose center the camera looks at.
+/// - `duration`: seconds the shot lasts after the player enters. Without it the
+///   shot holds until the player leaves the zone.
+pub struct CameraZones {
+    zones: Vec<CameraZone>,
+}
+
+impl CameraZones {
+    /// Creates the camera zones placed in the level.
+    pub fn from_level(level: &Level) -> Self {
+        let zones = level
+            .objects_of_kind("camera_zone")
+            .map(|object| {
+                let focus = object.property("focus").and_then(|name| {
+                    let target = level.object_named(name);
+                    if target.is_none() {
+                        warn!("Camera zone '{}' focuses on unknown object '{}'", object.name, name);
+                    }
+                    target.map(|target| target.center())
+                });
+
+                CameraZone {
+                    name: object.name.clone(),
+                    bounds: object.bounds,
+                    shot: CameraShot {
+                        zoom: object.property_f32("zoom", 1.0).max(0.1),
+                        focus,
+                        duration: object
+                            .property("duration")
+                            .map(|_| object.property_f32("duration", 0.0)),
+                    },
+                    occupied: false,
+                }
+            })
+            .collect();
+
+        Self { zones }
+    }
+
+    /// Requests a zone's shot when the player enters it, and releases held shots when they leave.
+    ///
+    /// # Arguments
+    ///
+    /// * `game_state` - Provides the player's position.
+    /// * `camera` - The camera to reframe.
+    pub fn update(&mut self, game_state: &GameState, camera: &mut Camera) {
+        let player = game_state.bounds();
+
+        for zone in &mut self.zones {
+            let inside = zone.bounds.intersects(&player);
+            if inside && !zone.occupied {
+                info!("Entered camera zone '{}'", zone.name);
+                camera.request_shot(zone.shot);
+            } else if !inside && zone.occupied && zone.shot.duration.is_none() {
+                camera.release_shot(&zone.shot);
+            }
+            zone.occupied = inside;
+        }
+    }
+}
@@ -0,0 +1,62 @@
+// checkpoint.rs
+use crate::engine::collision::Aabb;
+use crate::engine::game_state::GameState;
+use crate::engine::level::Level;
+use log::info;
+
+/// Effect played on reaching a checkpoint without an `effect` property.
+const DEFAULT_EFFECT: &str = "pickup_shine";
+
+/// A place the player returns to after losing a life.
+pub struct Checkpoint {
+    name: String,
+    pub bounds: Aabb,
+    /// Name of the effect preset played when the checkpoint is reached.
+    pub effect: String,
+}
+
+/// Records the checkpoints the player reaches.
+///
+/// Checkpoints come from `checkpoint` rectangles in the level. Each is reached once:
+/// it is marked as collected, so it stays reached across saves. The `effect`
+/// property names the effect played on reaching it.
+pub struct Checkpoints {
+    checkpoints: Vec<Checkpoint>,
+}
+
+impl Checkpoints {
+    /// Creates the checkpoints placed in the level.
+    pub fn from_level(level: &Level) -> Self {
+        let checkpoints = level
+            .objects_of_kind("checkpoint")
+            .map(|object| Checkpoint {
+                name: object.name.clone(),
+                bounds: object.bounds,
+                effect: object.property("effect").unwrap_or(DEFAULT_EFFECT).to_string(),
+            })
+            .collect();
+
+        Self { checkpoints }
+    }
+
+    /// Marks any checkpoint the player is inside as reached.
+    ///
+    /// # Arguments
+    ///
+    /// * `game_state` - Provides the player's position, and records reached checkpoints as collected.
+    ///
+    /// # Returns
+    ///
+    /// The checkpoint the player reached for the first time this frame, if any.
+    pub fn update(&self, game_state: &mut GameState) -> Option<&Checkpoint> {
+        let player = game_state.bounds();
+        let mut reached = None;
+        for checkpoint in &self.checkpoints {
+            if checkpoint.bounds.intersects(&player) && game_state.collect(&checkpoint.name) {
+                info!("Checkpoint '{}' reached", checkpoint.name);
+                reached = Some(checkpoint);
+            }
+        }
+        reached
+    }
+}
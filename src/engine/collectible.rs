@@ -0,0 +1,141 @@
+// collectible.rs
+use crate::engine::collision::Aabb;
+use crate::engine::game_state::GameState;
+use crate::engine::level::Level;
+use log::info;
+
+/// Points a coin is worth without a `value` property.
+const COIN_VALUE: u32 = 10;
+
+/// Points a gem is worth without a `value` property.
+const GEM_VALUE: u32 = 50;
+
+/// How much a lantern widens the player's light without a `light` property, in world units.
+const LANTERN_LIGHT: f32 = 0.25;
+
+/// Effect played on picking up a collectible without an `effect` property.
+const DEFAULT_EFFECT: &str = "pickup_shine";
+
+/// A coin, gem, or lantern the player picks up.
+pub struct Collectible {
+    /// The collectible's identity, under which it is marked as collected.
+    name: String,
+    pub bounds: Aabb,
+    /// Points added to the score on picking it up.
+    value: u32,
+    /// How much picking it up widens the player's light, in world units.
+    light: f32,
+    /// Index of the tileset tile drawn for the collectible.
+    pub tile_index: usize,
+    /// Columns and rows of tileset tiles making up its art.
+    pub tile_span: (usize, usize),
+    /// Name of the effect preset played when it is picked up.
+    pub effect: String,
+    /// How far its art is turned about its center, counterclockwise in radians.
+    pub angle: f32,
+    /// How fast its art spins, in radians per second.
+    spin: f32,
+}
+
+impl Collectible {
+    /// Returns the collectible's identity, under which it is marked as collected.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns whether the player has already picked up the collectible.
+    pub fn is_collected(&self, game_state: &GameState) -> bool {
+        game_state.has_collected(&self.name)
+    }
+}
+
+/// The level's coins and gems, which add to the score when the player touches them,
+/// and lanterns, which widen the light the player carries through the dark.
+///
+/// Collectibles come from `coin` and `gem` objects in the level, worth 10 and 50
+/// points unless their `value` property says otherwise, and `lantern` objects, worth
+/// nothing, whose `light` property says how much further the player's light reaches
+/// in the level once picked up (default 0.25 world units). The `tile` property picks
+/// the tile drawn for them and `effect` the effect played on picking them up. Their
+/// art can be tilted by a `rotation` property and spun by a `spin` property, in
+/// degrees and degrees per second counterclockwise. Each is picked up once: it is
+/// marked as collected, so it stays gone across saves.
+pub struct Collectibles {
+    pub collectibles: Vec<Collectible>,
+}
+
+impl Collectibles {
+    /// Creates the collectibles placed in the level.
+    pub fn from_level(level: &Level) -> Self {
+        let coins = level.objects_of_kind("coin").map(|object| (object, COIN_VALUE));
+        let gems = level.objects_of_kind("gem").map(|object| (object, GEM_VALUE));
+        let lanterns = level.objects_of_kind("lantern").map(|object| (object, 0));
+        let collectibles = coins
+            .chain(gems)
+            .chain(lanterns)
+            .map(|(object, value)| {
+                let light = match object.kind.as_str() {
+                    "lantern" => object.property_f32("light", LANTERN_LIGHT).max(0.0),
+                    _ => 0.0,
+                };
+                let (angle, spin) = object.art_rotation();
+                Collectible {
+                    // Collectibles are rarely named, so they are told apart by id
+                    name: level.object_id(object).to_string(),
+                    bounds: object.bounds,
+                    value: object.property_f32("value", value as f32) as u32,
+                    light,
+                    tile_index: object.tile_index(),
+                    tile_span: object.tile_span(),
+                    effect: object.property("effect").unwrap_or(DEFAULT_EFFECT).to_string(),
+                    angle,
+                    spin,
+                }
+            })
+            .collect();
+
+        Self { collectibles }
+    }
+
+    /// Picks up any collectible the player touches, adding its value to the score.
+    ///
+    /// # Arguments
+    ///
+    /// * `game_state` - Provides the player's position, and records the score and what was collected.
+    ///
+    /// # Returns
+    ///
+    /// The collectibles picked up this frame.
+    pub fn update(&self, game_state: &mut GameState) -> Vec<&Collectible> {
+        let player = game_state.bounds();
+        let mut picked_up = Vec::new();
+        for collectible in &self.collectibles {
+            if collectible.bounds.intersects(&player) && game_state.collect(&collectible.name) {
+                game_state.score += collectible.value;
+                info!("Picked up {} for {} (score {})", collectible.name, collectible.value, game_state.score);
+                picked_up.push(collectible);
+            }
+        }
+        picked_up
+    }
+
+    /// Returns how much the lanterns the player has picked up widen their light.
+    pub fn light_gained(&self, game_state: &GameState) -> f32 {
+        self.collectibles
+            .iter()
+            .filter(|collectible| collectible.is_collected(game_state))
+            .map(|collectible| collectible.light)
+            .sum()
+    }
+
+    /// Spins the collectibles' art by a frame.
+    ///
+    /// # Arguments
+    ///
+    /// * `delta_time` - Game time the frame took, in seconds.
+    pub fn animate(&mut self, delta_time: f32) {
+        for collectible in &mut self.collectibles {
+            collectible.angle = (collectible.angle + collectible.spin * delta_time) % std::f32::consts::TAU;
+        }
+    }
+}
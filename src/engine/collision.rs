@@ -0,0 +1,122 @@
+// collision.rs
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// How far apart two box edges may be and still count as touching, in world units,
+/// allowing for rounding in where tiles are placed.
+const SEAM_TOLERANCE: f32 = 1e-4;
+
+/// Box overlap tests made since the count was last taken.
+static COLLISION_TESTS: AtomicUsize = AtomicUsize::new(0);
+
+/// Returns the number of box overlap tests made since the last call, and starts counting again.
+pub fn take_collision_tests() -> usize {
+    COLLISION_TESTS.swap(0, Ordering::Relaxed)
+}
+
+/// An axis-aligned bounding box in world coordinates.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Aabb {
+    pub min_x: f32,
+    pub min_y: f32,
+    pub max_x: f32,
+    pub max_y: f32,
+}
+
+impl Aabb {
+    /// Creates a box from its center point and full size.
+    pub fn from_center(x: f32, y: f32, width: f32, height: f32) -> Self {
+        Self {
+            min_x: x - width / 2.0,
+            min_y: y - height / 2.0,
+            max_x: x + width / 2.0,
+            max_y: y + height / 2.0,
+        }
+    }
+
+    /// Returns `true` if the point lies within the box, edges included.
+    pub fn contains(&self, x: f32, y: f32) -> bool {
+        x >= self.min_x && x <= self.max_x && y >= self.min_y && y <= self.max_y
+    }
+
+    /// Returns `true` if the two boxes overlap.
+    ///
+    /// Each call counts towards the frame's collision tests in the engine stats.
+    pub fn intersects(&self, other: &Aabb) -> bool {
+        COLLISION_TESTS.fetch_add(1, Ordering::Relaxed);
+        self.min_x < other.max_x
+            && self.max_x > other.min_x
+            && self.min_y < other.max_y
+            && self.max_y > other.min_y
+    }
+}
+
+/// Merges boxes into fewer, larger ones covering the same area.
+///
+/// Boxes sharing a top and bottom are first joined side by side into runs, then runs
+/// sharing a left and right edge are stacked. Tiles laid out on a grid become a few
+/// rectangles this way, with no seams between neighbors to catch on. Boxes that
+/// overlap or only touch at a corner are kept apart.
+///
+/// # Arguments
+///
+/// * `boxes` - The boxes to merge, in any order.
+pub fn merge_boxes(mut boxes: Vec<Aabb>) -> Vec<Aabb> {
+    // Edges are compared in steps of the tolerance, so rounding doesn't split a row
+    let snap = |value: f32| (value / SEAM_TOLERANCE).round() as i64;
+
+    boxes.sort_by_key(|b| (snap(b.min_y), snap(b.max_y), snap(b.min_x)));
+    let mut runs: Vec<Aabb> = Vec::with_capacity(boxes.len());
+    for b in boxes {
+        match runs.last_mut() {
+            Some(run)
+                if snap(run.min_y) == snap(b.min_y)
+                    && snap(run.max_y) == snap(b.max_y)
+                    && (b.min_x - run.max_x).abs() <= SEAM_TOLERANCE =>
+            {
+                run.max_x = b.max_x;
+            }
+            _ => runs.push(b),
+        }
+    }
+
+    runs.sort_by_key(|run| (snap(run.min_x), snap(run.max_x), snap(run.min_y)));
+    let mut merged: Vec<Aabb> = Vec::with_capacity(runs.len());
+    for run in runs {
+        match merged.last_mut() {
+            Some(stack)
+                if snap(stack.min_x) == snap(run.min_x)
+                    && snap(stack.max_x) == snap(run.max_x)
+                    && (run.min_y - stack.max_y).abs() <= SEAM_TOLERANCE =>
+            {
+                stack.max_y = run.max_y;
+            }
+            _ => merged.push(run),
+        }
+    }
+    merged
+}
+
+/// A slope filling the lower half of a box, its surface running diagonally from one
+/// bottom corner to the opposite top corner.
+///
+/// The box's shape sets the incline: a square box is a 45° slope, and one wider than
+/// it is tall a shallower one.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Slope {
+    pub bounds: Aabb,
+    /// Whether the surface climbs from left to right, rather than right to left.
+    pub rises_right: bool,
+}
+
+impl Slope {
+    /// Returns the height of the surface at a horizontal position, or `None` past either end.
+    pub fn surface_y(&self, x: f32) -> Option<f32> {
+        let width = self.bounds.max_x - self.bounds.min_x;
+        if width <= 0.0 || x < self.bounds.min_x || x > self.bounds.max_x {
+            return None;
+        }
+        let t = (x - self.bounds.min_x) / width;
+        let rise = if self.rises_right { t } else { 1.0 - t };
+        Some(self.bounds.min_y + (self.bounds.max_y - self.bounds.min_y) * rise)
+    }
+}
@@ -0,0 +1,159 @@
+// collision_checks.rs
+use crate::engine::collision::{Aabb, Slope};
+use crate::engine::constants::GROUND_LEVEL;
+use crate::engine::game_state::GameState;
+use crate::engine::input::InputHandler;
+use crate::engine::level::{Level, LevelObject};
+use crate::engine::renderer::tile::TileMap;
+use crate::engine::slope::Slopes;
+use std::collections::HashMap;
+
+/// Time step the player is simulated at, as at 60 frames per second.
+const STEP: f32 = 1.0 / 60.0;
+
+/// Most steps a walk is simulated for before the player counts as stuck.
+const MAX_STEPS: usize = 600;
+
+/// World size of the tiles in the tile seam check, as the levels are loaded with.
+const TILE_SIZE: f32 = 0.3;
+
+// Each scenario walks the player, or slides a point, across a seam and fails with why
+// if they register a collision or lose their footing there.
+
+/// Walks up a slope from the ground and across a hairline gap onto the next slope.
+#[test]
+fn slope_seam() -> Result<(), String> {
+    let lower = slope(0.0, GROUND_LEVEL, 0.59, 0.3, true);
+    let upper = slope(0.605, GROUND_LEVEL + 0.3, 0.6, 0.3, true);
+    walk_right(&[lower, upper], (-0.3, GROUND_LEVEL), 1.1)
+}
+
+/// Walks down a slope whose low end stops just short of the ground, and on along the ground.
+#[test]
+fn slope_to_ground() -> Result<(), String> {
+    let downhill = slope(0.0, GROUND_LEVEL + 0.02, 0.6, 0.3, false);
+    let start_x = 0.05;
+    let start_y = downhill.surface_y(start_x).unwrap_or(GROUND_LEVEL);
+    walk_right(&[downhill], (start_x, start_y), 1.0)
+}
+
+/// Slides points through a block of tiles along the seams between them and just
+/// over its top, which only the block itself should collide with.
+#[test]
+fn tile_seams() -> Result<(), String> {
+    let (columns, rows) = (10, 2);
+    let tile_map = TileMap::from_layers(
+        TILE_SIZE,
+        TILE_SIZE,
+        1,
+        1,
+        columns,
+        rows,
+        &[("ground".to_string(), vec![Some(0); columns * rows])],
+    );
+    let colliders = tile_map.layers[0].colliders();
+    let [block] = colliders else {
+        return Err(format!("{} tiles were merged into {} boxes, not 1", columns * rows, colliders.len()));
+    };
+
+    let samples = 1000;
+    for sample in 0..=samples {
+        let x = block.min_x + (block.max_x - block.min_x) * sample as f32 / samples as f32;
+        // Between the two rows, and just inside and outside the top
+        let seam_y = block.min_y + TILE_SIZE;
+        if !tile_map.is_solid_at(x, seam_y) || !tile_map.is_solid_at(x, block.max_y - 0.001) {
+            return Err(format!(
+                "a point inside the tiles at x = {:.3} passed through a seam",
+                x
+            ));
+        }
+        if tile_map.is_solid_at(x, block.max_y + 0.001) {
+            return Err(format!(
+                "a point over the tiles at x = {:.3} collided with them",
+                x
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Creates a slope filling a box.
+///
+/// # Arguments
+///
+/// * `min_x`, `min_y` - The bottom-left corner of its box.
+/// * `width`, `height` - The size of its box.
+/// * `rises_right` - Whether it climbs from left to right.
+fn slope(min_x: f32, min_y: f32, width: f32, height: f32, rises_right: bool) -> Slope {
+    Slope {
+        bounds: Aabb {
+            min_x,
+            min_y,
+            max_x: min_x + width,
+            max_y: min_y + height,
+        },
+        rises_right,
+    }
+}
+
+/// Walks the player right over flat ground and slopes, checking they're on their
+/// feet after every step.
+///
+/// # Arguments
+///
+/// * `slopes` - The slopes placed on the ground, as a level's `slope` objects would be.
+/// * `start` - Where the player starts: the horizontal position and the height of their feet.
+/// * `end_x` - How far right the player walks.
+fn walk_right(slopes: &[Slope], start: (f32, f32), end_x: f32) -> Result<(), String> {
+    let mut level = Level::flat_ground(TILE_SIZE, TILE_SIZE, 1, 1);
+    level.objects = slopes
+        .iter()
+        .enumerate()
+        .map(|(index, slope)| LevelObject {
+            id: index as u32 + 1,
+            name: format!("slope {}", index + 1),
+            kind: "slope".to_string(),
+            bounds: slope.bounds,
+            tile: None,
+            properties: HashMap::from([(
+                "rise".to_string(),
+                if slope.rises_right { "right" } else { "left" }.to_string(),
+            )]),
+        })
+        .collect();
+    let mut slopes = Slopes::from_level(&level);
+
+    let mut game_state = GameState::new();
+    let half_height = game_state.player_y - game_state.bounds().min_y;
+    game_state.teleport(start.0, start.1 + half_height);
+    let mut input_handler = InputHandler::new();
+
+    // Let the player settle onto where they start before walking
+    for _ in 0..MAX_STEPS {
+        game_state.update(&input_handler, STEP);
+        slopes.update(&mut game_state);
+        if game_state.is_grounded() {
+            break;
+        }
+    }
+    input_handler.handle_gamepad_stick_x(1.0);
+
+    for _ in 0..MAX_STEPS {
+        game_state.update(&input_handler, STEP);
+        slopes.update(&mut game_state);
+        if !game_state.is_grounded() {
+            return Err(format!(
+                "the player lost their footing at x = {:.3}, feet at {:.3}",
+                game_state.player_x,
+                game_state.bounds().min_y
+            ));
+        }
+        if game_state.player_x >= end_x {
+            return Ok(());
+        }
+    }
+    Err(format!(
+        "the player stopped at x = {:.3}",
+        game_state.player_x
+    ))
+}
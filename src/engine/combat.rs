@@ -0,0 +1,55 @@
+// combat.rs
+use crate::engine::collision::Aabb;
+
+/// Damage one hit of the player's kick deals.
+pub const KICK_DAMAGE: u32 = 1;
+
+/// Damage one of the player's projectiles deals.
+pub const PROJECTILE_DAMAGE: u32 = 1;
+
+/// An attack in progress, remembering what it has struck so each attack damages a
+/// target once however many frames its hitbox overlaps the target's hurtbox.
+///
+/// Attacks come from sprite frames with a hitbox, such as the kick's: an attack
+/// lasts while its frames keep a hitbox out, and ends on the first frame without one.
+#[derive(Clone, Default)]
+pub struct Attack {
+    /// Indices of the targets struck since the attack began.
+    struck: Vec<usize>,
+}
+
+impl Attack {
+    /// Resolves the attacker's hitbox against the targets' hurtboxes.
+    ///
+    /// # Arguments
+    ///
+    /// * `hitbox` - The attacker's hitbox this frame. `None` ends the attack, so the
+    ///   next one can strike the same targets again.
+    /// * `hurtboxes` - Where each target can be hurt, or `None` for targets that
+    ///   can't be hurt right now.
+    ///
+    /// # Returns
+    ///
+    /// Indices of the targets struck for the first time this attack.
+    pub fn strike(
+        &mut self,
+        hitbox: Option<Aabb>,
+        hurtboxes: impl IntoIterator<Item = Option<Aabb>>,
+    ) -> Vec<usize> {
+        let Some(hitbox) = hitbox else {
+            self.struck.clear();
+            return Vec::new();
+        };
+
+        let hits: Vec<usize> = hurtboxes
+            .into_iter()
+            .enumerate()
+            .filter(|(index, hurtbox)| {
+                !self.struck.contains(index) && hurtbox.is_some_and(|hurtbox| hitbox.intersects(&hurtbox))
+            })
+            .map(|(index, _)| index)
+            .collect();
+        self.struck.extend(&hits);
+        hits
+    }
+}
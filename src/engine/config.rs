@@ -0,0 +1,216 @@
+// config.rs
+use crate::engine::constants::{
+    ANIMATION_SPEED, BOUNCE_HITSTOP_TIME, CAMERA_EASE_SPEED, COYOTE_TIME, DAMAGE_FLASH, DAMAGE_FLASH_INTENSITY, GRAVITY,
+    PHOTOSENSITIVE_MODE,
+    JELLY_BOUNCE, JUMP_BUFFER_TIME, JUMP_FORCE, PLAYER_SPEED, PORTAL_COOLDOWN, ROPE_CLIMB_SPEED, ROPE_SWING_FORCE,
+    SECRET_FADE_SPEED, STICK_RESPONSE_EXPONENT, STICK_RUN_THRESHOLD, TARGET_FPS, TERMINAL_VELOCITY, WATER_REFLECTIONS, MIPMAPS, MSAA_SAMPLES, GRAPHICS_BACKEND,
+    GPU_PREFERENCE, LOW_POWER, PAUSE_WHEN_HIDDEN, VIRTUAL_RESOLUTION, POST_PROCESSING, VIGNETTE, SATURATION, CONTRAST,
+    LIGHTING, TILE_SHADING, SWIM_GRAVITY_SCALE, SWIM_STROKE_FORCE, SWIM_DASH_SPEED, SWIM_DASH_COOLDOWN, OXYGEN_TIME,
+};
+use crate::engine::mods::resolve;
+use serde::Deserialize;
+use std::fmt;
+use std::path::Path;
+use std::sync::OnceLock;
+
+/// Errors that can occur while loading the tuning config.
+#[derive(Debug)]
+pub enum ConfigError {
+    /// The config file could not be read.
+    Io(std::io::Error),
+    /// The config file is malformed.
+    Parse(String),
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigError::Io(e) => write!(f, "failed to read config: {}", e),
+            ConfigError::Parse(msg) => write!(f, "failed to parse config: {}", msg),
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+impl From<std::io::Error> for ConfigError {
+    fn from(e: std::io::Error) -> Self {
+        ConfigError::Io(e)
+    }
+}
+
+/// Graphics API the renderer asks wgpu for.
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum GraphicsBackend {
+    /// Whichever API wgpu finds best on this platform.
+    Auto,
+    Vulkan,
+    Dx12,
+    Metal,
+    Gl,
+}
+
+impl GraphicsBackend {
+    /// Returns the wgpu backends to create the instance with.
+    pub fn backends(self) -> wgpu::Backends {
+        match self {
+            GraphicsBackend::Auto => wgpu::Backends::all(),
+            GraphicsBackend::Vulkan => wgpu::Backends::VULKAN,
+            GraphicsBackend::Dx12 => wgpu::Backends::DX12,
+            GraphicsBackend::Metal => wgpu::Backends::METAL,
+            GraphicsBackend::Gl => wgpu::Backends::GL,
+        }
+    }
+}
+
+/// Which GPU the renderer prefers on machines with more than one, such as
+/// laptops with both integrated and discrete graphics.
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum GpuPreference {
+    HighPerformance,
+    LowPower,
+}
+
+impl GpuPreference {
+    /// Returns the wgpu power preference to request the adapter with.
+    pub fn power_preference(self) -> wgpu::PowerPreference {
+        match self {
+            GpuPreference::HighPerformance => wgpu::PowerPreference::HighPerformance,
+            GpuPreference::LowPower => wgpu::PowerPreference::LowPower,
+        }
+    }
+}
+
+/// When the game saves power.
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum LowPowerMode {
+    Off,
+    On,
+    /// While the machine is running on battery.
+    Battery,
+}
+
+/// Gameplay values that can be tuned without recompiling.
+///
+/// Each field defaults to the constant of the same name, so a config file only
+/// needs to list the values it changes.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Tuning {
+    pub player_speed: f32,
+    pub gravity: f32,
+    pub terminal_velocity: f32,
+    pub jump_force: f32,
+    pub stick_response_exponent: f32,
+    pub stick_run_threshold: f32,
+    pub coyote_time: f32,
+    pub jump_buffer_time: f32,
+    pub animation_speed: f32,
+    pub secret_fade_speed: f32,
+    pub portal_cooldown: f32,
+    pub camera_ease_speed: f32,
+    pub rope_swing_force: f32,
+    pub rope_climb_speed: f32,
+    pub jelly_bounce: f32,
+    pub bounce_hitstop_time: f32,
+    pub swim_gravity_scale: f32,
+    pub swim_stroke_force: f32,
+    pub swim_dash_speed: f32,
+    pub swim_dash_cooldown: f32,
+    pub oxygen_time: f32,
+    pub damage_flash: bool,
+    pub damage_flash_intensity: f32,
+    pub photosensitive_mode: bool,
+    pub target_fps: f32,
+    pub water_reflections: bool,
+    pub mipmaps: bool,
+    pub msaa_samples: u32,
+    pub virtual_resolution: [u32; 2],
+    pub post_processing: bool,
+    pub vignette: f32,
+    pub saturation: f32,
+    pub contrast: f32,
+    pub lighting: bool,
+    pub tile_shading: f32,
+    pub graphics_backend: GraphicsBackend,
+    pub gpu_preference: GpuPreference,
+    pub low_power: LowPowerMode,
+    pub pause_when_hidden: bool,
+}
+
+impl Default for Tuning {
+    fn default() -> Self {
+        Self {
+            player_speed: PLAYER_SPEED,
+            gravity: GRAVITY,
+            terminal_velocity: TERMINAL_VELOCITY,
+            jump_force: JUMP_FORCE,
+            stick_response_exponent: STICK_RESPONSE_EXPONENT,
+            stick_run_threshold: STICK_RUN_THRESHOLD,
+            coyote_time: COYOTE_TIME,
+            jump_buffer_time: JUMP_BUFFER_TIME,
+            animation_speed: ANIMATION_SPEED,
+            secret_fade_speed: SECRET_FADE_SPEED,
+            portal_cooldown: PORTAL_COOLDOWN,
+            camera_ease_speed: CAMERA_EASE_SPEED,
+            rope_swing_force: ROPE_SWING_FORCE,
+            rope_climb_speed: ROPE_CLIMB_SPEED,
+            jelly_bounce: JELLY_BOUNCE,
+            bounce_hitstop_time: BOUNCE_HITSTOP_TIME,
+            swim_gravity_scale: SWIM_GRAVITY_SCALE,
+            swim_stroke_force: SWIM_STROKE_FORCE,
+            swim_dash_speed: SWIM_DASH_SPEED,
+            swim_dash_cooldown: SWIM_DASH_COOLDOWN,
+            oxygen_time: OXYGEN_TIME,
+            damage_flash: DAMAGE_FLASH,
+            damage_flash_intensity: DAMAGE_FLASH_INTENSITY,
+            photosensitive_mode: PHOTOSENSITIVE_MODE,
+            target_fps: TARGET_FPS,
+            water_reflections: WATER_REFLECTIONS,
+            mipmaps: MIPMAPS,
+            msaa_samples: MSAA_SAMPLES,
+            virtual_resolution: VIRTUAL_RESOLUTION,
+            post_processing: POST_PROCESSING,
+            vignette: VIGNETTE,
+            saturation: SATURATION,
+            contrast: CONTRAST,
+            lighting: LIGHTING,
+            tile_shading: TILE_SHADING,
+            graphics_backend: GRAPHICS_BACKEND,
+            gpu_preference: GPU_PREFERENCE,
+            low_power: LOW_POWER,
+            pause_when_hidden: PAUSE_WHEN_HIDDEN,
+        }
+    }
+}
+
+/// The tuning in effect, set once at startup.
+static TUNING: OnceLock<Tuning> = OnceLock::new();
+
+/// Reads tuning values from a TOML file.
+///
+/// # Arguments
+///
+/// * `path` - Path to the TOML file.
+pub fn load_tuning(path: impl AsRef<Path>) -> Result<Tuning, ConfigError> {
+    let source = std::fs::read_to_string(resolve(path))?;
+    toml::from_str(&source).map_err(|e| ConfigError::Parse(e.to_string()))
+}
+
+/// Makes the given tuning the one returned by `tuning()`.
+///
+/// Only the first call has any effect, so this should happen at startup before
+/// anything reads the tuning.
+pub fn set_tuning(tuning: Tuning) {
+    if TUNING.set(tuning).is_err() {
+        log::warn!("Tuning was already set; ignoring the new values");
+    }
+}
+
+/// Returns the tuning in effect, or the defaults if none has been set.
+pub fn tuning() -> &'static Tuning {
+    TUNING.get_or_init(Tuning::default)
+}
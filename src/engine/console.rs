@@ -0,0 +1,113 @@
+// console.rs
+use crate::engine::dev::DevAction;
+
+/// Most characters a command line holds.
+const MAX_LINE: usize = 40;
+
+/// The commands the console runs, by name, with what each does.
+const COMMANDS: [(&str, DevAction); 5] = [
+    ("god", DevAction::ToggleGodMode),
+    ("noclip", DevAction::ToggleNoclip),
+    ("jumps", DevAction::ToggleInfiniteJumps),
+    ("unlock", DevAction::UnlockDoors),
+    ("skip", DevAction::SkipLevel),
+];
+
+/// A line to type developer commands into, such as `noclip` to fly through the level.
+/// Only debug builds have it.
+///
+/// The console action opens it; while it's open the keyboard types into it rather than
+/// playing. Enter runs the line as the same choice the developer menu would make, and
+/// the console action or Escape closes it again.
+pub struct Console {
+    open: bool,
+    /// The command being typed.
+    line: String,
+    /// What the console said about the last command run.
+    reply: String,
+}
+
+impl Console {
+    /// Creates a closed console.
+    pub fn new() -> Self {
+        Self {
+            open: false,
+            line: String::new(),
+            reply: String::new(),
+        }
+    }
+
+    /// Returns whether the console is open, taking the keyboard's typing.
+    pub fn is_open(&self) -> bool {
+        self.open
+    }
+
+    /// Opens the console with an empty line, or closes it.
+    pub fn toggle(&mut self) {
+        self.open = !self.open;
+        self.line.clear();
+    }
+
+    /// Returns the command being typed.
+    pub fn line(&self) -> &str {
+        &self.line
+    }
+
+    /// Returns what the console said about the last command run.
+    pub fn reply(&self) -> &str {
+        &self.reply
+    }
+
+    /// Types characters into the line, running it on Enter and closing on Escape or the
+    /// console key's backquote.
+    ///
+    /// # Arguments
+    ///
+    /// * `typed` - The characters typed this frame, in order.
+    ///
+    /// # Returns
+    ///
+    /// The choice the command run makes, if one was run and is known.
+    pub fn update(&mut self, typed: &str) -> Option<DevAction> {
+        let mut action = None;
+        for character in typed.chars() {
+            match character {
+                '\r' | '\n' => action = self.run().or(action),
+                '\u{8}' => {
+                    self.line.pop();
+                }
+                '\u{1b}' | '`' => {
+                    self.toggle();
+                    break;
+                }
+                character if !character.is_control() && self.line.len() < MAX_LINE => self.line.push(character),
+                _ => {}
+            }
+        }
+        action
+    }
+
+    /// Runs the line as a command and clears it.
+    fn run(&mut self) -> Option<DevAction> {
+        let line = std::mem::take(&mut self.line);
+        let command = line.trim().to_lowercase();
+        if command.is_empty() {
+            return None;
+        }
+        let action = COMMANDS.iter().find(|(name, _)| *name == command).map(|&(_, action)| action);
+        self.reply = match action {
+            Some(_) => format!("> {}", command),
+            None => {
+                let names: Vec<&str> = COMMANDS.iter().map(|(name, _)| *name).collect();
+                format!("UNKNOWN '{}'; TRY {}", command, names.join(", "))
+            }
+        };
+        action
+    }
+}
+
+impl Default for Console {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -1,4 +1,9 @@
 // constants.rs
+//
+// Constants that also appear in `Tuning` (config.rs) are only defaults: the
+// tuning config loaded at startup can override them.
+
+use crate::engine::config::{GpuPreference, GraphicsBackend, LowPowerMode};
 
 /// Width of the sprite used for the player and other objects.
 pub const SPRITE_WIDTH: f32 = 0.3;
@@ -15,8 +20,219 @@ pub const PLAYER_SPEED: f32 = 1.0;
 /// Gravity applied to the player.
 pub const GRAVITY: f32 = -9.8;
 
+/// Fastest the player can fall.
+pub const TERMINAL_VELOCITY: f32 = 12.0;
+
 /// Force applied when the player jumps.
 pub const JUMP_FORCE: f32 = 5.0;
 
+/// Exponent of the stick's response curve: 1.0 moves in proportion to its tilt,
+/// and higher values give finer control at small tilts.
+pub const STICK_RESPONSE_EXPONENT: f32 = 1.5;
+
+/// How far the stick must be tilted sideways to run, from 0.0 to 1.0.
+pub const STICK_RUN_THRESHOLD: f32 = 0.9;
+
+/// How long after walking off a ledge the player can still jump, in seconds.
+pub const COYOTE_TIME: f32 = 0.1;
+
+/// How long a jump pressed in the air is remembered, firing on landing, in seconds.
+pub const JUMP_BUFFER_TIME: f32 = 0.12;
+
 /// Animation speed for frame transitions.
 pub const ANIMATION_SPEED: f32 = 0.1;
+
+/// How quickly secret-area covers fade, in opacity per second.
+pub const SECRET_FADE_SPEED: f32 = 3.0;
+
+/// Duration of the portal fade out and back in, in seconds.
+pub const PORTAL_TRANSITION_TIME: f32 = 0.4;
+
+/// Default time after arriving through a portal before portals can be used again, in seconds.
+pub const PORTAL_COOLDOWN: f32 = 1.0;
+
+/// How quickly the camera eases toward a requested zoom and focus; higher is snappier.
+pub const CAMERA_EASE_SPEED: f32 = 3.0;
+
+/// Time for the cutscene letterbox bars to slide fully in or out, in seconds.
+pub const LETTERBOX_TRANSITION_TIME: f32 = 0.4;
+
+/// Time for the level intro banner to slide in, in seconds.
+pub const BANNER_SLIDE_TIME: f32 = 0.6;
+
+/// Time the level intro banner stays fully visible, in seconds.
+pub const BANNER_HOLD_TIME: f32 = 2.0;
+
+/// Time for the level intro banner to fade out, in seconds.
+pub const BANNER_FADE_TIME: f32 = 0.8;
+
+/// Time for the screen to fade to black, and again to fade back in, when switching scenes, in seconds.
+pub const SCENE_FADE_TIME: f32 = 0.4;
+
+/// Lives the player starts with, and gets back when continuing after a game over.
+pub const STARTING_LIVES: u32 = 3;
+
+/// Times the player can continue from a checkpoint after running out of lives.
+pub const STARTING_CONTINUES: u32 = 2;
+
+/// Whether the screen edges flash red when the player loses a life.
+/// Players sensitive to flashing can turn this off in the tuning config.
+pub const DAMAGE_FLASH: bool = true;
+
+/// Peak opacity of the damage flash, from 0.0 (invisible) to 1.0.
+pub const DAMAGE_FLASH_INTENSITY: f32 = 0.6;
+
+/// Whether full-screen flashes are held to a safe rate and strength, for players
+/// sensitive to flashing light.
+pub const PHOTOSENSITIVE_MODE: bool = false;
+
+/// Most full-screen flashes that start in any one second in photosensitive mode,
+/// below the three per second that can trigger seizures.
+pub const SAFE_FLASHES_PER_SECOND: usize = 2;
+
+/// Strongest a full-screen flash gets in photosensitive mode, as its peak opacity.
+pub const SAFE_FLASH_INTENSITY: f32 = 0.25;
+
+/// Frames per second the game loop is held to. Zero runs uncapped, without vsync.
+pub const TARGET_FPS: f32 = 60.0;
+
+/// Whether water reflects the scene above it, drawing the scene offscreen first.
+/// Slower GPUs can turn this off in the tuning config.
+pub const WATER_REFLECTIONS: bool = true;
+
+/// Whether sprites drawn smaller than their pixels, such as backgrounds in a small
+/// window, sample filtered mipmaps rather than shimmering as they move. Off keeps
+/// every pixel crisp, at the cost of shimmer when scaled down.
+pub const MIPMAPS: bool = false;
+
+/// Samples per pixel frames are drawn with, smoothing the edges of rotated and
+/// scaled sprites: 1 (off), 2, or 4. Counts the GPU doesn't support fall back to 1.
+pub const MSAA_SAMPLES: u32 = 1;
+
+/// Size in pixels of the canvas frames are drawn into before being scaled into
+/// the window, with bars where the shapes differ. Zero in either dimension draws
+/// at the window's size instead, stretching the view to its shape.
+pub const VIRTUAL_RESOLUTION: [u32; 2] = [800, 600];
+
+/// Whether frames are drawn offscreen and finished by a post-processing pass that
+/// grades their color, adds a vignette, and fades the screen between scenes. Off
+/// draws straight to the window, fading with a panel over the screen instead.
+pub const POST_PROCESSING: bool = true;
+
+/// How much the post-processing pass darkens the corners of the screen, from 0.0 to 1.0.
+pub const VIGNETTE: f32 = 0.2;
+
+/// Color the post-processing pass keeps, from 0.0 (grey) to 1.0; above 1.0 makes
+/// colors more vivid.
+pub const SATURATION: f32 = 1.0;
+
+/// How far the post-processing pass spreads colors from mid-grey; 1.0 leaves them
+/// as they are, and lower flattens them.
+pub const CONTRAST: f32 = 1.0;
+
+/// Whether levels with an ambient light darker than white are drawn in the dark, lit
+/// by their lights. Off draws every level fully lit.
+pub const LIGHTING: bool = true;
+
+/// How dark the shading baked into tiles gets, deep in the ground and just under
+/// ceilings, from 0.0 (off) to 1.0 (black).
+pub const TILE_SHADING: f32 = 0.35;
+
+/// Graphics API the renderer prefers; `Auto` lets wgpu pick the best available.
+pub const GRAPHICS_BACKEND: GraphicsBackend = GraphicsBackend::Auto;
+
+/// Whether the renderer prefers a discrete GPU or a power-saving integrated one.
+pub const GPU_PREFERENCE: GpuPreference = GpuPreference::HighPerformance;
+
+/// When to save power by lowering the frame rate, preferring the integrated GPU,
+/// and skipping post-processing.
+pub const LOW_POWER: LowPowerMode = LowPowerMode::Battery;
+
+/// Frames per second held to while saving power.
+pub const LOW_POWER_FPS: f32 = 30.0;
+
+/// Whether the game pauses while its window is minimized or hidden, rather than
+/// running on without drawing.
+pub const PAUSE_WHEN_HIDDEN: bool = true;
+
+/// Time for the damage flash to fade out, in seconds.
+pub const DAMAGE_FLASH_TIME: f32 = 0.4;
+
+/// Speed the player flies at with noclip on, in world units per second. Running doubles it.
+pub const NOCLIP_SPEED: f32 = 2.0;
+
+/// How far beyond the edges of the view entities keep updating, in world units.
+pub const ACTIVATION_MARGIN: f32 = 2.0;
+
+/// Duration of the hop onto a mount, in seconds.
+pub const MOUNT_TRANSITION_TIME: f32 = 0.3;
+
+/// Number of constraint passes per frame when simulating ropes; more passes make ropes stiffer.
+pub const ROPE_ITERATIONS: usize = 12;
+
+/// Acceleration the player applies to a rope while swinging on it.
+pub const ROPE_SWING_FORCE: f32 = 8.0;
+
+/// Speed at which the player climbs along a rope, in segments per second.
+pub const ROPE_CLIMB_SPEED: f32 = 6.0;
+
+/// Time after letting go of a rope before any rope can be grabbed again, in seconds.
+pub const ROPE_REGRAB_DELAY: f32 = 0.3;
+
+/// Fraction of the landing speed a jelly platform bounces the player back up with.
+pub const JELLY_BOUNCE: f32 = 0.35;
+
+/// How long game time freezes when the player bounces hard off a jelly platform, in seconds.
+pub const BOUNCE_HITSTOP_TIME: f32 = 0.06;
+
+/// Fraction of gravity that pulls the player down in water.
+pub const SWIM_GRAVITY_SCALE: f32 = 0.3;
+
+/// Fastest the player sinks in water.
+pub const SWIM_SINK_SPEED: f32 = 1.0;
+
+/// Fraction of the player's top speed they swim at.
+pub const SWIM_SPEED_SCALE: f32 = 0.75;
+
+/// Upward velocity of a swim stroke, taken with each jump pressed in water.
+pub const SWIM_STROKE_FORCE: f32 = 2.0;
+
+/// Speed of a swim-dash, a burst through the water toward the held direction.
+pub const SWIM_DASH_SPEED: f32 = 3.5;
+
+/// How long a swim-dash lasts, in seconds.
+pub const SWIM_DASH_TIME: f32 = 0.2;
+
+/// Seconds after a swim-dash before the player can dash again.
+pub const SWIM_DASH_COOLDOWN: f32 = 0.8;
+
+/// Seconds the player can hold their breath underwater before drowning. Zero or less
+/// lets them breathe underwater.
+pub const OXYGEN_TIME: f32 = 10.0;
+
+/// How much of the player's recent movement can be rewound, in seconds of game time.
+pub const REWIND_DURATION: f32 = 5.0;
+
+/// How many times faster than the game runs the recording plays backward while rewinding.
+pub const REWIND_SPEED: f32 = 2.0;
+
+/// How quickly the screen fades to grey when rewinding starts and back when it stops,
+/// as the fraction of the fade per second.
+pub const REWIND_FADE_SPEED: f32 = 5.0;
+
+/// Time for an ambient loop to fade fully in or out as the player enters or leaves its zone, in seconds.
+pub const AMBIENCE_FADE_TIME: f32 = 1.5;
+
+/// Whether music speeds up and slows down, shifting pitch, with the game's time scale.
+pub const MUSIC_FOLLOWS_TIME_SCALE: bool = true;
+
+/// Whether sound effects speed up and slow down, shifting pitch, with the game's time scale.
+pub const SFX_FOLLOWS_TIME_SCALE: bool = true;
+
+/// Game time the player's movement is simulated in per step, in seconds. Frames run
+/// as many steps as their time adds up to, at any frame rate.
+pub const SIMULATION_STEP: f32 = 1.0 / 60.0;
+
+/// Most simulation steps run in one frame; time beyond them is dropped, so a long
+/// stall doesn't have later frames catching up on it.
+pub const MAX_SIMULATION_STEPS: u32 = 8;
@@ -0,0 +1,143 @@
+// cutscene.rs
+use crate::engine::camera::{Camera, CameraShot};
+use crate::engine::collision::Aabb;
+use crate::engine::constants::LETTERBOX_TRANSITION_TIME;
+use crate::engine::game_state::GameState;
+use crate::engine::level::Level;
+use crate::engine::tween::{ease_in_out, Timer};
+use log::{info, warn};
+
+/// A non-interactive sequence played the first time the player enters its trigger.
+struct Cutscene {
+    name: String,
+    bounds: Aabb,
+    /// Framing held for the length of the cutscene.
+    shot: CameraShot,
+    duration: f32,
+    lock_input: bool,
+    played: bool,
+}
+
+/// The cutscene currently playing.
+struct ActiveCutscene {
+    index: usize,
+    timer: Timer,
+}
+
+/// Plays cutscenes, framing them with the camera and letterbox bars.
+///
+/// Cutscenes come from `cutscene` rectangles in the level, and each plays once,
+/// when the player first enters it. Properties:
+/// - `duration`: length of the cutscene in seconds (default 3.0).
+/// - `zoom`: camera magnification held during the cutscene (default 1.0).
+/// - `focus`: name of a level object for the camera to look at.
+/// - `lock_input`: set to `false` to let the player keep moving (default `true`).
+pub struct Cutscenes {
+    cutscenes: Vec<Cutscene>,
+    active: Option<ActiveCutscene>,
+    /// How far the letterbox bars have slid in, from 0.0 (hidden) to 1.0 (shown).
+    letterbox: f32,
+    /// Whether a script is playing a cutscene of its own.
+    scripted: bool,
+}
+
+impl Cutscenes {
+    /// Creates the cutscenes placed in the level, none of them played yet.
+    pub fn from_level(level: &Level) -> Self {
+        let cutscenes = level
+            .objects_of_kind("cutscene")
+            .map(|object| {
+                let focus = object.property("focus").and_then(|name| {
+                    let target = level.object_named(name);
+                    if target.is_none() {
+                        warn!("Cutscene '{}' focuses on unknown object '{}'", object.name, name);
+                    }
+                    target.map(|target| target.center())
+                });
+
+                Cutscene {
+                    name: object.name.clone(),
+                    bounds: object.bounds,
+                    shot: CameraShot {
+                        zoom: object.property_f32("zoom", 1.0).max(0.1),
+                        focus,
+                        duration: None,
+                    },
+                    duration: object.property_f32("duration", 3.0),
+                    lock_input: object.property("lock_input") != Some("false"),
+                    played: false,
+                }
+            })
+            .collect();
+
+        Self {
+            cutscenes,
+            active: None,
+            letterbox: 0.0,
+            scripted: false,
+        }
+    }
+
+    /// Starts a cutscene when the player enters an unplayed one, ends it when its time is up,
+    /// and slides the letterbox bars in or out.
+    ///
+    /// # Arguments
+    ///
+    /// * `game_state` - Provides the player's position.
+    /// * `camera` - The camera to frame the cutscene with.
+    /// * `delta_time` - Time elapsed since the last frame.
+    pub fn update(&mut self, game_state: &GameState, camera: &mut Camera, delta_time: f32) {
+        match self.active.as_mut() {
+            Some(active) => {
+                if active.timer.tick(delta_time) {
+                    camera.release_shot(&self.cutscenes[active.index].shot);
+                    self.active = None;
+                }
+            }
+            None => {
+                let player = game_state.bounds();
+                let triggered = self
+                    .cutscenes
+                    .iter()
+                    .position(|cutscene| !cutscene.played && cutscene.bounds.intersects(&player));
+                if let Some(index) = triggered {
+                    let cutscene = &mut self.cutscenes[index];
+                    info!("Playing cutscene '{}'", cutscene.name);
+                    cutscene.played = true;
+                    camera.request_shot(cutscene.shot);
+                    self.active = Some(ActiveCutscene {
+                        index,
+                        timer: Timer::new(cutscene.duration),
+                    });
+                }
+            }
+        }
+
+        let step = delta_time / LETTERBOX_TRANSITION_TIME;
+        self.letterbox = if self.active.is_some() || self.scripted {
+            (self.letterbox + step).min(1.0)
+        } else {
+            (self.letterbox - step).max(0.0)
+        };
+    }
+
+    /// Shows the letterbox bars and takes control from the player while a script
+    /// plays a cutscene, as set before each update.
+    pub fn set_scripted(&mut self, scripted: bool) {
+        self.scripted = scripted;
+    }
+
+    /// Returns whether the playing cutscene has taken control away from the player.
+    pub fn input_locked(&self) -> bool {
+        self.scripted
+            || self
+                .active
+                .as_ref()
+                .is_some_and(|active| self.cutscenes[active.index].lock_input)
+    }
+
+    /// Returns how far the letterbox bars cover the screen, eased, from 0.0 (hidden) to 1.0 (shown).
+    pub fn letterbox(&self) -> f32 {
+        ease_in_out(self.letterbox)
+    }
+}
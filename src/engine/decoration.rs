@@ -0,0 +1,303 @@
+// decoration.rs
+use crate::engine::collision::Aabb;
+use crate::engine::config::ConfigError;
+use crate::engine::level::bundle::TILESET_PATH;
+use crate::engine::level::Level;
+use crate::engine::mods::resolve;
+use crate::engine::renderer::tile::{Tile, TileMap};
+use crate::engine::rng::{hash_str, Rng};
+use serde::Deserialize;
+use std::collections::HashSet;
+use std::path::Path;
+use std::sync::OnceLock;
+
+/// Sheet the built-in decorations are cut from.
+const DEFAULT_SHEET: &str = "assets/tileset/Decors.png";
+
+/// Pixels across one tileset tile, which decoration sprites are scaled against so
+/// their pixels match the tiles'.
+const TILE_PIXEL_SIZE: f32 = 16.0;
+
+/// Object classes marking out areas or spots, such as rooms and spawn points, rather than
+/// things drawn in the world; decorations may overlap them.
+const MARKER_KINDS: [&str; 13] = [
+    "room",
+    "room_entrance",
+    "challenge_goal",
+    "camera_zone",
+    "ambience_zone",
+    "secret_area",
+    "cutscene",
+    "script",
+    "air_bubbles",
+    "objective",
+    "player_spawn",
+    "light",
+    "torch",
+];
+
+/// Pixels a decoration is sunk into the surface it stands on, so it doesn't float
+/// above the grass.
+const SINK_PIXELS: f32 = 1.0;
+
+/// A sprite cut out of the decoration sheet.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct DecorationSprite {
+    pub name: String,
+    /// The sprite's area of the sheet in pixels, as `[x, y, width, height]` from the top-left.
+    pub rect: [u32; 4],
+    /// How often the sprite is picked relative to the others.
+    #[serde(default = "default_weight")]
+    pub weight: f32,
+}
+
+/// Weight of sprites without one of their own.
+fn default_weight() -> f32 {
+    1.0
+}
+
+/// How a tileset's surfaces are decorated.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TilesetDecoration {
+    /// Path of the tileset image the settings apply to.
+    pub image: String,
+    /// Chance of each exposed surface tile getting a decoration, from 0.0 to 1.0.
+    pub density: f32,
+    /// Tileset tiles with a top that decorations can stand on, such as grass.
+    pub surfaces: Vec<usize>,
+}
+
+/// The decoration sprites and where they're scattered, as read from the decorations file.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct DecorationConfig {
+    /// Path of the sprite sheet the sprites are cut from.
+    pub sheet: String,
+    pub sprites: Vec<DecorationSprite>,
+    pub tilesets: Vec<TilesetDecoration>,
+    /// Width and height of the sheet in pixels, read from the image.
+    #[serde(skip)]
+    pub sheet_size: (u32, u32),
+}
+
+impl Default for DecorationConfig {
+    /// The bushes and rocks of the built-in sheet, on the grass of the built-in tileset.
+    fn default() -> Self {
+        let sprite = |name: &str, rect, weight| DecorationSprite {
+            name: name.to_string(),
+            rect,
+            weight,
+        };
+        Self {
+            sheet: DEFAULT_SHEET.to_string(),
+            sprites: vec![sprite("bush", [114, 96, 35, 16], 3.0), sprite("rock", [177, 92, 27, 20], 2.0)],
+            tilesets: vec![TilesetDecoration {
+                image: TILESET_PATH.to_string(),
+                density: 0.3,
+                surfaces: vec![0, 1, 2, 21],
+            }],
+            sheet_size: (0, 0),
+        }
+        .with_sheet_size()
+    }
+}
+
+impl DecorationConfig {
+    /// Returns the settings for a tileset image, if it's decorated.
+    pub fn tileset(&self, image: &str) -> Option<&TilesetDecoration> {
+        self.tilesets.iter().find(|tileset| tileset.image == image)
+    }
+
+    /// Reads the size of the sheet, without which no sprite can be drawn.
+    fn with_sheet_size(mut self) -> Self {
+        match image::image_dimensions(resolve(&self.sheet)) {
+            Ok(size) => self.sheet_size = size,
+            Err(e) => log::warn!("Could not read decoration sheet '{}': {}", self.sheet, e),
+        }
+        self
+    }
+}
+
+static DECORATIONS: OnceLock<DecorationConfig> = OnceLock::new();
+
+/// Reads the decoration sprites and their tilesets from a TOML file.
+///
+/// # Arguments
+///
+/// * `path` - Path to the TOML file.
+pub fn load_decorations(path: impl AsRef<Path>) -> Result<DecorationConfig, ConfigError> {
+    let source = std::fs::read_to_string(resolve(path))?;
+    let config: DecorationConfig = toml::from_str(&source).map_err(|e| ConfigError::Parse(e.to_string()))?;
+    Ok(config.with_sheet_size())
+}
+
+/// Makes the given decorations the ones returned by `decorations()`.
+///
+/// Only the first call has any effect, so this should happen at startup before the
+/// renderer loads the sheet.
+pub fn set_decorations(config: DecorationConfig) {
+    if DECORATIONS.set(config).is_err() {
+        log::warn!("Decorations were already set; ignoring the new ones");
+    }
+}
+
+/// Returns the decorations in effect, or the built-in ones if none have been set.
+pub fn decorations() -> &'static DecorationConfig {
+    DECORATIONS.get_or_init(DecorationConfig::default)
+}
+
+/// A decoration scattered onto the level.
+pub struct Decoration {
+    /// Center in world coordinates.
+    pub position: (f32, f32),
+    /// Width and height in world units.
+    pub size: (f32, f32),
+    /// The sprite's area of the sheet, as `(offset, scale)` in UV coordinates.
+    pub uv: ([f32; 2], [f32; 2]),
+    /// Whether the sprite is mirrored.
+    pub flipped: bool,
+}
+
+/// Grass, bushes, and rocks scattered along the tops of the level's ground, so levels
+/// don't look bare without every decoration being placed by hand.
+///
+/// Decorations stand on the tileset's surface tiles wherever nothing solid is on top
+/// of them, in tile layers without a `decorate` property set to `false`, and stay
+/// clear of the level's objects so they never hide a hazard or an exit. Each cell draws
+/// from a stream of the run's random numbers of its own, named by its position, so the
+/// same seed always decorates a map the same way and editing one part of it leaves the
+/// rest as it was. A level's `decoration_seed` property rerolls its decorations, and its
+/// `decoration_density` property overrides the tileset's density.
+pub struct Decorations {
+    pub decorations: Vec<Decoration>,
+}
+
+impl Decorations {
+    /// Scatters decorations over the level's tile map.
+    ///
+    /// # Arguments
+    ///
+    /// * `level` - The level to decorate.
+    /// * `config` - The sprites, and which tiles of the level's tileset they stand on.
+    /// * `tileset` - Path of the tileset image the level is drawn with.
+    /// * `seed` - The run's seed.
+    pub fn scatter(level: &Level, config: &DecorationConfig, tileset: &str, seed: u32) -> Self {
+        let Some(settings) = config.tileset(tileset) else {
+            return Self { decorations: Vec::new() };
+        };
+        let total_weight: f32 = config.sprites.iter().map(|sprite| sprite.weight.max(0.0)).sum();
+        if config.sheet_size.0 == 0 || config.sheet_size.1 == 0 || total_weight <= 0.0 {
+            return Self { decorations: Vec::new() };
+        }
+        let density = level
+            .property("decoration_density")
+            .and_then(|value| value.parse().ok())
+            .unwrap_or(settings.density);
+        // Levels decorate differently from each other under the same run's seed
+        let seed = seed
+            ^ level
+                .property("decoration_seed")
+                .and_then(|value| value.parse().ok())
+                .unwrap_or_else(|| hash_str(&level.path));
+
+        let tile_map = &level.tile_map;
+        let (tile_width, tile_height) = (tile_map.tile_width, tile_map.tile_height);
+        let pixel = tile_width / TILE_PIXEL_SIZE;
+        // Cells counted from the first tile, so positions off the grid's origin round the same way
+        let origin = tile_map.tiles().next().map_or((0.0, 0.0), |tile| tile.position);
+        let cell = |position: (f32, f32)| {
+            (
+                ((position.0 - origin.0) / tile_width).round() as i32,
+                ((position.1 - origin.1) / tile_height).round() as i32,
+            )
+        };
+
+        // Surface tiles with open air above them, by cell
+        let surfaces: Vec<&Tile> = tile_map
+            .layers
+            .iter()
+            .filter(|layer| layer.solid && layer.decorate)
+            .flat_map(|layer| &layer.tiles)
+            .filter(|tile| tile.span == (1, 1) && settings.surfaces.contains(&tile.tile_index))
+            .filter(|tile| !tile_map.is_solid_at(tile.position.0, tile.position.1 + tile_height))
+            .collect();
+        let open: HashSet<(i32, i32)> = surfaces.iter().map(|tile| cell(tile.position)).collect();
+
+        let mut claimed = HashSet::new();
+        let mut decorations = Vec::new();
+        for tile in surfaces {
+            let (column, row) = cell(tile.position);
+            let mut rng = Rng::stream(seed, &format!("decorations {},{}", column, row));
+            if !rng.chance(density) {
+                continue;
+            }
+
+            // Pick a sprite by weight
+            let mut pick = rng.random() * total_weight;
+            let Some(sprite) = config.sprites.iter().find(|sprite| {
+                pick -= sprite.weight.max(0.0);
+                pick < 0.0
+            }) else {
+                continue;
+            };
+            let [x, y, width, height] = sprite.rect;
+            let size = (width as f32 * pixel, height as f32 * pixel);
+
+            // Wide sprites need the surface to carry on either side, and nothing
+            // solid or gameplay-related in the space they take up
+            let reach = ((size.0 - tile_width) / 2.0 / tile_width).ceil().max(0.0) as i32;
+            let columns = column - reach..=column + reach;
+            if columns.clone().any(|column| !open.contains(&(column, row)) || claimed.contains(&(column, row))) {
+                continue;
+            }
+            let slack = (tile_width - size.0).max(0.0);
+            let center_x = tile.position.0 + rng.range(-0.5, 0.5) * slack;
+            let bottom = tile.position.1 + tile_height / 2.0 - SINK_PIXELS * pixel;
+            let bounds = Aabb::from_center(center_x, bottom + size.1 / 2.0, size.0, size.1);
+            let blocked = level
+                .objects
+                .iter()
+                .filter(|object| !MARKER_KINDS.contains(&object.kind.as_str()))
+                .any(|object| object.bounds.intersects(&bounds))
+                || solid_in(tile_map, &bounds, tile_height);
+            if blocked {
+                continue;
+            }
+            claimed.extend(columns.map(|column| (column, row)));
+
+            let (sheet_width, sheet_height) = (config.sheet_size.0 as f32, config.sheet_size.1 as f32);
+            decorations.push(Decoration {
+                position: (center_x, bottom + size.1 / 2.0),
+                size,
+                uv: (
+                    [x as f32 / sheet_width, y as f32 / sheet_height],
+                    [width as f32 / sheet_width, height as f32 / sheet_height],
+                ),
+                flipped: rng.chance(0.5),
+            });
+        }
+
+        Self { decorations }
+    }
+
+    /// Returns the decorations overlapping an area, such as the camera's view.
+    pub fn in_view<'a>(&'a self, view: &'a Aabb) -> impl Iterator<Item = &'a Decoration> {
+        self.decorations.iter().filter(|decoration| {
+            let bounds = Aabb::from_center(decoration.position.0, decoration.position.1, decoration.size.0, decoration.size.1);
+            bounds.min_x < view.max_x && bounds.max_x > view.min_x && bounds.min_y < view.max_y && bounds.max_y > view.min_y
+        })
+    }
+}
+
+/// Returns whether any solid tile reaches into an area above a surface, sampling a
+/// point per tile row across the area's corners and middle.
+fn solid_in(tile_map: &TileMap, bounds: &Aabb, tile_height: f32) -> bool {
+    let rows = ((bounds.max_y - bounds.min_y) / tile_height).ceil().max(1.0) as usize;
+    (0..rows).any(|row| {
+        let y = (bounds.min_y + (row as f32 + 0.5) * tile_height).min(bounds.max_y);
+        let middle = (bounds.min_x + bounds.max_x) / 2.0;
+        [bounds.min_x, middle, bounds.max_x].into_iter().any(|x| tile_map.is_solid_at(x, y))
+    })
+}
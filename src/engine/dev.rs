@@ -0,0 +1,30 @@
+// dev.rs
+
+/// Cheats that bend the game's rules while testing a level.
+///
+/// They are all off unless turned on from the developer menu or the console,
+/// which only debug builds include.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Cheats {
+    /// Hazards don't cost the player lives.
+    pub god_mode: bool,
+    /// The player flies freely with the movement actions, passing through the ground,
+    /// surfaces, closed doors, hazards, and portals, and out of the current room.
+    pub noclip: bool,
+    /// The player can jump again at any time, including in mid-air.
+    pub infinite_jumps: bool,
+}
+
+/// A choice made from the developer menu or the console.
+#[cfg(debug_assertions)]
+#[derive(Clone, Copy, Debug)]
+pub enum DevAction {
+    ToggleGodMode,
+    ToggleNoclip,
+    ToggleInfiniteJumps,
+    /// Opens every door in the level, as if all their switches were flipped. This is
+    /// the engine's give-all-keys: there are no key items, and doors open by switches.
+    UnlockDoors,
+    /// Starts the level named by the current level's `next_level` property.
+    SkipLevel,
+}
@@ -0,0 +1,69 @@
+// door.rs
+use crate::engine::collision::Aabb;
+use crate::engine::game_state::GameState;
+use crate::engine::level::{Level, ObjectId};
+
+/// A door that blocks the player until it is switched on.
+pub struct Door {
+    /// The door's identity, which switches refer to and its state is saved under.
+    pub id: ObjectId,
+    /// Area the closed door blocks, in world coordinates.
+    pub bounds: Aabb,
+    /// Index of the tileset tile repeated along the door.
+    pub tile_index: usize,
+    /// Columns and rows of tileset tiles making up each repeat of its art.
+    pub tile_span: (usize, usize),
+}
+
+/// The level's doors.
+///
+/// Doors come from `door` rectangles in the level and are opened by the switches
+/// targeting them, which may be in another room or level.
+pub struct Doors {
+    pub doors: Vec<Door>,
+}
+
+impl Doors {
+    /// Creates the doors placed in the level.
+    pub fn from_level(level: &Level) -> Self {
+        let doors = level
+            .objects_of_kind("door")
+            .map(|object| Door {
+                id: level.object_id(object),
+                bounds: object.bounds,
+                tile_index: object.tile_index(),
+                tile_span: object.tile_span(),
+            })
+            .collect();
+
+        Self { doors }
+    }
+
+    /// Pushes the player out of any closed door, back to the side they came from.
+    /// Doors don't block the player while noclip is on.
+    ///
+    /// # Arguments
+    ///
+    /// * `game_state` - The player to keep out of closed doors.
+    pub fn update(&self, game_state: &mut GameState) {
+        if game_state.cheats.noclip {
+            return;
+        }
+        for door in &self.doors {
+            let player = game_state.bounds();
+            if game_state.is_object_on(&door.id) || !door.bounds.intersects(&player) {
+                continue;
+            }
+
+            let half_width = (player.max_x - player.min_x) / 2.0;
+            let door_center = (door.bounds.min_x + door.bounds.max_x) / 2.0;
+            let x = if game_state.player_x < door_center {
+                door.bounds.min_x - half_width
+            } else {
+                door.bounds.max_x + half_width
+            };
+            // Horizontal velocity is recomputed from input every frame, so only the position needs fixing
+            game_state.player_x = x;
+        }
+    }
+}
@@ -0,0 +1,294 @@
+// effects.rs
+use crate::engine::config::ConfigError;
+use crate::engine::mods::resolve;
+use crate::engine::renderer::gpu_particles::GpuParticle;
+use crate::engine::rng::Rng;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::path::Path;
+
+/// Number of particles the pool holds; effects played while it's full spawn fewer particles.
+const POOL_SIZE: usize = 256;
+
+/// How far past the edges of the view weather is scattered, in world units; matches
+/// `VIEW_MARGIN` in particles.wgsl, where it respawns.
+const WEATHER_MARGIN: f32 = 0.2;
+
+/// How an effect's particles burst out, move, and fade, as read from the effects file.
+///
+/// Ranges are `[min, max]` and each particle picks a random value within them.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct EffectPreset {
+    /// Number of particles spawned each time the effect plays.
+    pub count: usize,
+    /// How long each particle lives, in seconds.
+    pub lifetime: [f32; 2],
+    /// Speed particles burst out at, in world units per second.
+    pub speed: [f32; 2],
+    /// Angle particles burst out at, in degrees counterclockwise from the right; 90 is up.
+    pub direction: f32,
+    /// Width of the spray around `direction`, in degrees; 360 bursts out all around.
+    pub spread: f32,
+    /// Vertical acceleration of the particles; negative pulls them down.
+    pub gravity: f32,
+    /// Particle size at the start and end of its life, in world units.
+    pub size: [f32; 2],
+    /// Particle height relative to its width, for streaks like rain.
+    pub aspect: f32,
+    /// Particle color at the start of its life.
+    pub color: [f32; 4],
+    /// Particle color at the end of its life; defaults to `color` faded out.
+    pub end_color: Option<[f32; 4]>,
+    /// Whether the particles are simulated on the GPU, for heavy effects that nothing
+    /// in the game needs to react to. Falls back to the CPU without compute support.
+    pub gpu: bool,
+}
+
+impl EffectPreset {
+    /// Returns the particle color at the end of its life.
+    fn end_color(&self) -> [f32; 4] {
+        self.end_color.unwrap_or([self.color[0], self.color[1], self.color[2], 0.0])
+    }
+}
+
+impl Default for EffectPreset {
+    fn default() -> Self {
+        Self {
+            count: 8,
+            lifetime: [0.5, 0.5],
+            speed: [0.5, 0.5],
+            direction: 90.0,
+            spread: 360.0,
+            gravity: 0.0,
+            size: [0.04, 0.0],
+            aspect: 1.0,
+            color: [1.0, 1.0, 1.0, 1.0],
+            end_color: None,
+            gpu: false,
+        }
+    }
+}
+
+/// Reads effect presets from a TOML file, one table per preset named by its key.
+///
+/// # Arguments
+///
+/// * `path` - Path to the TOML file.
+pub fn load_effects(path: impl AsRef<Path>) -> Result<HashMap<String, EffectPreset>, ConfigError> {
+    let source = std::fs::read_to_string(resolve(path))?;
+    toml::from_str(&source).map_err(|e| ConfigError::Parse(e.to_string()))
+}
+
+/// A single particle of a playing effect.
+struct Particle {
+    position: (f32, f32),
+    velocity: (f32, f32),
+    gravity: f32,
+    age: f32,
+    lifetime: f32,
+    size: [f32; 2],
+    aspect: f32,
+    color: [f32; 4],
+    end_color: [f32; 4],
+}
+
+/// How to draw a particle this frame.
+pub struct ParticleSprite {
+    /// Position in world coordinates, as `(x, y)`.
+    pub position: (f32, f32),
+    /// Width and height in world units.
+    pub size: (f32, f32),
+    pub color: [f32; 4],
+}
+
+/// Plays particle effects, such as the sparkle when the player spawns, by preset name.
+///
+/// Particles are kept in a pool allocated up front, in the order they were spawned:
+/// playing an effect appends to it, and particles that have lived out their lifetime
+/// are removed without reordering the rest, so particles always draw oldest first
+/// and overlapping ones don't swap places as others expire. Presets marked
+/// `gpu` instead queue their particles for the renderer's GPU particles.
+pub struct Effects {
+    presets: HashMap<String, EffectPreset>,
+    pool: Vec<Particle>,
+    /// Whether the renderer can simulate particles on the GPU.
+    gpu: bool,
+    /// GPU particles spawned since they were last taken.
+    gpu_spawns: Vec<GpuParticle>,
+    /// Scatters the particles.
+    rng: Rng,
+}
+
+impl Effects {
+    /// Creates the particle pool, with nothing playing.
+    ///
+    /// # Arguments
+    ///
+    /// * `presets` - The effects that can be played, by name.
+    /// * `gpu` - Whether the renderer can simulate particles on the GPU.
+    /// * `rng` - Scatters the particles.
+    pub fn new(presets: HashMap<String, EffectPreset>, gpu: bool, rng: Rng) -> Self {
+        Self {
+            presets,
+            pool: Vec::with_capacity(POOL_SIZE),
+            gpu,
+            gpu_spawns: Vec::new(),
+            rng,
+        }
+    }
+
+    /// Scatters particles with another generator from now on, such as when the run's
+    /// seed changes.
+    pub fn set_rng(&mut self, rng: Rng) {
+        self.rng = rng;
+    }
+
+    /// Returns the generator scattering the particles, as far as it has got.
+    pub fn rng(&self) -> Rng {
+        self.rng
+    }
+
+    /// Bursts the named effect's particles out from a point.
+    ///
+    /// Unknown effects are skipped with a warning.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The preset to play.
+    /// * `x` - Horizontal position of the burst in world coordinates.
+    /// * `y` - Vertical position of the burst in world coordinates.
+    pub fn play(&mut self, name: &str, x: f32, y: f32) {
+        let Some(preset) = self.presets.get(name).cloned() else {
+            log::warn!("Unknown effect '{}'", name);
+            return;
+        };
+        let end_color = preset.end_color();
+
+        for _ in 0..preset.count {
+            let (velocity, lifetime) = self.scatter(&preset);
+            if preset.gpu && self.gpu {
+                self.gpu_spawns.push(gpu_particle(&preset, (x, y), velocity, lifetime, false));
+                continue;
+            }
+
+            if self.pool.len() >= POOL_SIZE {
+                return;
+            }
+            self.pool.push(Particle {
+                position: (x, y),
+                velocity,
+                gravity: preset.gravity,
+                age: 0.0,
+                lifetime,
+                size: preset.size,
+                aspect: preset.aspect,
+                color: preset.color,
+                end_color,
+            });
+        }
+    }
+
+    /// Creates weather that keeps falling over the view, such as rain, from the named
+    /// preset: `count` particles scattered across the view at random points in their
+    /// lives, which respawn along its top on the GPU as they expire or fall out of it.
+    ///
+    /// Unknown effects are skipped with a warning.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The preset to make weather from.
+    /// * `camera_x` - Horizontal position of the camera.
+    /// * `camera_y` - Vertical position of the camera.
+    /// * `zoom` - The camera magnification, deciding how much of the world is in view.
+    pub fn weather(&mut self, name: &str, camera_x: f32, camera_y: f32, zoom: f32) -> Vec<GpuParticle> {
+        let Some(preset) = self.presets.get(name).cloned() else {
+            log::warn!("Unknown effect '{}'", name);
+            return Vec::new();
+        };
+
+        let extent = 1.0 / zoom + WEATHER_MARGIN;
+        (0..preset.count)
+            .map(|_| {
+                let (velocity, lifetime) = self.scatter(&preset);
+                let x = camera_x + self.rng.range(-extent, extent);
+                let y = camera_y + self.rng.range(-extent, extent);
+                let mut particle = gpu_particle(&preset, (x, y), velocity, lifetime, true);
+                particle.age = self.rng.range(0.0, lifetime);
+                particle
+            })
+            .collect()
+    }
+
+    /// Returns the GPU particles spawned since the last call, for the renderer to simulate.
+    pub fn take_gpu_spawns(&mut self) -> Vec<GpuParticle> {
+        std::mem::take(&mut self.gpu_spawns)
+    }
+
+    /// Moves the particles and despawns those that have lived out their lifetime.
+    ///
+    /// # Arguments
+    ///
+    /// * `delta_time` - Time elapsed since the last frame.
+    pub fn update(&mut self, delta_time: f32) {
+        // `retain_mut` keeps the survivors in spawn order
+        self.pool.retain_mut(|particle| {
+            particle.age += delta_time;
+            if particle.age >= particle.lifetime {
+                return false;
+            }
+            particle.velocity.1 += particle.gravity * delta_time;
+            particle.position.0 += particle.velocity.0 * delta_time;
+            particle.position.1 += particle.velocity.1 * delta_time;
+            true
+        });
+    }
+
+    /// Returns how to draw each particle, oldest first, blending its size and color over its life.
+    pub fn sprites(&self) -> impl Iterator<Item = ParticleSprite> + '_ {
+        self.pool.iter().map(|particle| {
+            let t = particle.age / particle.lifetime;
+            let lerp = |a: f32, b: f32| a + (b - a) * t;
+            let size = lerp(particle.size[0], particle.size[1]);
+            ParticleSprite {
+                position: particle.position,
+                size: (size, size * particle.aspect),
+                color: [0, 1, 2, 3].map(|channel| lerp(particle.color[channel], particle.end_color[channel])),
+            }
+        })
+    }
+
+    /// Returns a random velocity and lifetime for a new particle of the preset.
+    fn scatter(&mut self, preset: &EffectPreset) -> ((f32, f32), f32) {
+        let angle = (preset.direction + self.rng.range(-0.5, 0.5) * preset.spread).to_radians();
+        let speed = self.rng.range(preset.speed[0], preset.speed[1]);
+        let lifetime = self.rng.range(preset.lifetime[0], preset.lifetime[1]);
+        ((angle.cos() * speed, angle.sin() * speed), lifetime)
+    }
+}
+
+/// Creates a GPU particle of the preset, starting out at the beginning of its life.
+///
+/// # Arguments
+///
+/// * `preset` - The preset deciding the particle's size, gravity, and colors.
+/// * `position` - Where the particle starts, in world coordinates.
+/// * `velocity` - The particle's starting velocity.
+/// * `lifetime` - How long the particle lives, in seconds.
+/// * `looping` - Whether the particle respawns along the top of the view, as weather.
+fn gpu_particle(preset: &EffectPreset, position: (f32, f32), velocity: (f32, f32), lifetime: f32, looping: bool) -> GpuParticle {
+    GpuParticle {
+        position: [position.0, position.1],
+        velocity: [velocity.0, velocity.1],
+        start_velocity: [velocity.0, velocity.1],
+        age: 0.0,
+        lifetime,
+        size: preset.size,
+        gravity: preset.gravity,
+        looping: if looping { 1.0 } else { 0.0 },
+        color: preset.color,
+        end_color: preset.end_color(),
+        aspect: preset.aspect,
+        _padding: [0.0; 3],
+    }
+}
@@ -0,0 +1,52 @@
+// exit.rs
+use crate::engine::collision::Aabb;
+use crate::engine::game_state::GameState;
+use crate::engine::level::Level;
+
+/// A doorway out of the level that takes the player on to another.
+pub struct Exit {
+    /// Area the player walks into to leave, in world coordinates.
+    pub bounds: Aabb,
+    /// Index of the tileset tile repeated along the exit.
+    pub tile_index: usize,
+    /// Columns and rows of tileset tiles making up each repeat of its art.
+    pub tile_span: (usize, usize),
+    /// Path of the level the exit leads to, or `None` to return to the menu.
+    pub destination: Option<String>,
+}
+
+/// The level's exits.
+///
+/// Exits come from `exit` rectangles in the level, leading to the level named by
+/// their `level` property, or else by the map's `next_level` property. An exit with
+/// neither returns to the menu. Like hazards, they are drawn by repeating their
+/// `tile` along their length.
+pub struct LevelExits {
+    pub exits: Vec<Exit>,
+}
+
+impl LevelExits {
+    /// Creates the exits placed in the level.
+    pub fn from_level(level: &Level) -> Self {
+        let exits = level
+            .objects_of_kind("exit")
+            .map(|object| Exit {
+                bounds: object.bounds,
+                tile_index: object.tile_index(),
+                tile_span: object.tile_span(),
+                destination: object
+                    .property("level")
+                    .or_else(|| level.property("next_level"))
+                    .map(str::to_string),
+            })
+            .collect();
+
+        Self { exits }
+    }
+
+    /// Returns the exit the player is touching, if any.
+    pub fn reached(&self, game_state: &GameState) -> Option<&Exit> {
+        let player = game_state.bounds();
+        self.exits.iter().find(|exit| exit.bounds.intersects(&player))
+    }
+}
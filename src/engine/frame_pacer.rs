@@ -0,0 +1,68 @@
+// frame_pacer.rs
+use std::time::{Duration, Instant};
+
+/// How long before a frame's deadline to stop sleeping and spin instead. Sleeps can
+/// overshoot by the OS timer granularity, which is up to a couple of milliseconds on
+/// most systems.
+const SPIN_MARGIN: Duration = Duration::from_millis(2);
+
+/// Holds frames to a steady rate by sleeping for most of the wait and spinning,
+/// yielding to other threads, for the last moment before each frame is due.
+///
+/// Deadlines are kept on a fixed schedule rather than measured from the end of the
+/// last frame, so a frame that runs a little late doesn't push back every frame
+/// after it. A frame late by more than a whole frame starts the schedule afresh.
+pub struct FramePacer {
+    /// Time between frames, or `None` to run uncapped.
+    frame_duration: Option<Duration>,
+    /// When the next frame is due.
+    next_frame: Instant,
+}
+
+impl FramePacer {
+    /// Creates a pacer whose first frame is due one frame from now.
+    ///
+    /// # Arguments
+    ///
+    /// * `target_fps` - Frames per second to hold to; zero or less runs uncapped.
+    pub fn new(target_fps: f32) -> Self {
+        let frame_duration = (target_fps > 0.0).then(|| Duration::from_secs_f32(1.0 / target_fps));
+        Self {
+            frame_duration,
+            next_frame: Instant::now() + frame_duration.unwrap_or_default(),
+        }
+    }
+
+    /// Changes the frame rate to hold to, starting from the next frame.
+    ///
+    /// # Arguments
+    ///
+    /// * `target_fps` - Frames per second to hold to; zero or less runs uncapped.
+    pub fn set_target_fps(&mut self, target_fps: f32) {
+        *self = Self::new(target_fps);
+    }
+
+    /// Waits until the next frame is due. Returns straight away when uncapped.
+    pub fn wait(&mut self) {
+        let Some(frame_duration) = self.frame_duration else {
+            return;
+        };
+
+        // Sleep through most of the wait, then spin for the rest to wake on time
+        let now = Instant::now();
+        if let Some(remaining) = self.next_frame.checked_duration_since(now) {
+            if remaining > SPIN_MARGIN {
+                std::thread::sleep(remaining - SPIN_MARGIN);
+            }
+            while Instant::now() < self.next_frame {
+                std::thread::yield_now();
+            }
+        }
+
+        self.next_frame += frame_duration;
+        let now = Instant::now();
+        if now > self.next_frame {
+            self.next_frame = now + frame_duration;
+        }
+    }
+}
@@ -1,8 +1,168 @@
+use crate::engine::action::Action;
+use crate::engine::animation_state::{AnimationStateMachine, Condition, Motion};
+use crate::engine::assets::sprite_sheet::FrameBox;
+use crate::engine::assets::SpriteSheet;
+use crate::engine::collision::Aabb;
+use crate::engine::dev::Cheats;
 use crate::engine::input::InputHandler;
-use crate::engine::renderer::Renderer;
-use crate::engine::constants::{SPRITE_WIDTH, SPRITE_HEIGHT, GROUND_LEVEL, PLAYER_SPEED, GRAVITY, JUMP_FORCE, ANIMATION_SPEED};
-use winit::event::VirtualKeyCode;
-use std::collections::HashMap;
+use crate::engine::level::{Level, ObjectId};
+use crate::engine::rng::{time_seed, Rng};
+use crate::engine::save::{SaveData, SaveError};
+use crate::engine::config::tuning;
+use crate::engine::constants::{
+    SPRITE_WIDTH, SPRITE_HEIGHT, GROUND_LEVEL, MAX_SIMULATION_STEPS, NOCLIP_SPEED, SIMULATION_STEP, STARTING_CONTINUES,
+    STARTING_LIVES, SWIM_DASH_TIME, SWIM_SINK_SPEED, SWIM_SPEED_SCALE,
+};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::path::Path;
+
+/// How the player moves and collides, either on foot or while riding a mount.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct MovementProfile {
+    /// Top horizontal speed, before the running bonus.
+    pub speed: f32,
+    /// How quickly horizontal speed changes, in units per second squared.
+    /// `None` changes speed instantly.
+    pub acceleration: Option<f32>,
+    /// Upward velocity applied when jumping. Zero disables jumping.
+    pub jump_force: f32,
+    /// Whether up and down input move the player directly instead of gravity.
+    pub flying: bool,
+    /// Size of the player's collision box as `(width, height)`.
+    pub hitbox: (f32, f32),
+    /// Whether the player is riding, which swaps in the `ride_idle` and
+    /// `ride_move` animations and disables crouching and kicking.
+    pub mounted: bool,
+}
+
+impl MovementProfile {
+    /// Returns the player's own movement when not riding anything.
+    pub fn on_foot() -> Self {
+        let tuning = tuning();
+        Self {
+            speed: tuning.player_speed,
+            acceleration: None,
+            jump_force: tuning.jump_force,
+            flying: false,
+            hitbox: (SPRITE_WIDTH, SPRITE_HEIGHT),
+            mounted: false,
+        }
+    }
+}
+
+/// The physics the player moves under: the tuned values, unless the level
+/// overrides them.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Physics {
+    /// Downward acceleration, negative to pull the player down.
+    pub gravity: f32,
+    /// Fastest the player can fall.
+    pub terminal_velocity: f32,
+    /// How quickly horizontal speed changes, in units per second squared, for movement
+    /// that otherwise changes speed instantly. `None` keeps it instant.
+    pub friction: Option<f32>,
+}
+
+impl Physics {
+    /// Returns the physics from the tuning config.
+    pub fn tuned() -> Self {
+        let tuning = tuning();
+        Self {
+            gravity: tuning.gravity,
+            terminal_velocity: tuning.terminal_velocity,
+            friction: None,
+        }
+    }
+
+    /// Returns the physics of a level, such as low gravity on the moon or slow,
+    /// floaty movement underwater.
+    ///
+    /// The level's `gravity`, `terminal_velocity`, and `friction` properties override
+    /// the tuned values; those it leaves out keep them.
+    ///
+    /// # Arguments
+    ///
+    /// * `level` - The level whose properties to read.
+    pub fn from_level(level: &Level) -> Self {
+        let tuned = Self::tuned();
+        let property = |name: &str| level.property(name).and_then(|value| value.parse::<f32>().ok());
+        Self {
+            gravity: property("gravity").unwrap_or(tuned.gravity),
+            terminal_velocity: property("terminal_velocity").unwrap_or(tuned.terminal_velocity).max(0.0),
+            friction: property("friction").map(|friction| friction.max(0.0)).or(tuned.friction),
+        }
+    }
+}
+
+/// Slowest and fastest an animation that follows the player's speed plays, relative to normal.
+const ANIMATION_RATE_RANGE: (f32, f32) = (0.25, 2.0);
+
+/// Names the game's own stream of the run's random numbers.
+const GAME_STREAM: &str = "game";
+
+/// How one of the player's actions is animated.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Animation {
+    /// First and last sprite frame of the animation, inclusive.
+    pub frames: (usize, usize),
+    /// How long each frame is shown, in milliseconds. `None` uses the sprite sheet's
+    /// own frame durations, or the tuned `animation_speed` for frames without one.
+    pub frame_ms: Option<u32>,
+    /// Whether the animation plays faster or slower with the player's horizontal speed,
+    /// at its normal rate when moving at the current top speed before running.
+    pub follows_speed: bool,
+}
+
+impl Animation {
+    /// Creates an animation shown at a fixed rate.
+    ///
+    /// # Arguments
+    ///
+    /// * `frames` - First and last sprite frame, inclusive.
+    /// * `frame_ms` - How long each frame is shown, in milliseconds.
+    fn fixed(frames: (usize, usize), frame_ms: u32) -> Self {
+        Self {
+            frames,
+            frame_ms: Some(frame_ms),
+            follows_speed: false,
+        }
+    }
+
+    /// Creates an animation whose rate follows the player's horizontal speed.
+    ///
+    /// # Arguments
+    ///
+    /// * `frames` - First and last sprite frame, inclusive.
+    /// * `frame_ms` - How long each frame is shown at the normal rate, in milliseconds.
+    fn moving(frames: (usize, usize), frame_ms: u32) -> Self {
+        Self {
+            follows_speed: true,
+            ..Self::fixed(frames, frame_ms)
+        }
+    }
+}
+
+/// The player's moment-to-moment state at one point in time: where they are, how
+/// they're moving, and how they're drawn. Progress such as score and lives isn't
+/// included, so restoring a snapshot moves the player without undoing it.
+#[derive(Clone)]
+pub struct PlayerSnapshot {
+    position: (f32, f32),
+    velocity: (f32, f32),
+    is_jumping: bool,
+    grounded: bool,
+    platform_velocity: (f32, f32),
+    carried_velocity_x: f32,
+    swim_dash_time: f32,
+    swim_dash_velocity: (f32, f32),
+    is_crouching: bool,
+    is_running: bool,
+    is_kicking: bool,
+    facing_right: bool,
+    sprite_index: usize,
+    frame_time: f32,
+    current_action: String,
+}
 
 /// Represents the state of the game, including the player's position,
 /// actions, and physics-related properties.
@@ -13,102 +173,373 @@ pub struct GameState {
     pub player_y: f32,
     player_velocity_x: f32,
     player_velocity_y: f32,
+    /// Player's position before the last update, which drawing eases on from.
+    previous_position: (f32, f32),
+    /// Game time `fixed_steps` has yet to hand out as steps, short of a whole one, in seconds.
+    unsimulated_time: f32,
 
     // Player state
     is_jumping: bool,
+    /// Whether the player was standing on the ground or a surface at the end of the last update.
+    grounded: bool,
+    /// Time left to jump after leaving the ground, in seconds.
+    coyote_timer: f32,
+    /// Time left for a jump pressed in the air to fire on landing, in seconds.
+    jump_buffer_timer: f32,
+    /// Velocity of the moving platform the player stood on at the end of the last update.
+    platform_velocity: (f32, f32),
+    /// Horizontal velocity carried over from a moving platform, kept after jumping or
+    /// walking off it until the player lands again.
+    carried_velocity_x: f32,
+    /// Whether the player is in water, swimming instead of falling, as set before each update.
+    swimming: bool,
+    /// Time left in the current swim-dash, in seconds, and the velocity it carries the player at.
+    swim_dash_time: f32,
+    swim_dash_velocity: (f32, f32),
+    /// Time left before the player can swim-dash again, in seconds.
+    swim_dash_cooldown: f32,
+    /// Whether a swim-dash started in the last update.
+    swim_dash_started: bool,
     is_crouching: bool,
     is_running: bool,
     is_kicking: bool,
     pub facing_right: bool,
+    movement: MovementProfile,
+    physics: Physics,
+    /// Cheats turned on from the developer menu.
+    pub cheats: Cheats,
+
+    /// Points earned by the player.
+    pub score: u32,
+    /// Lives left before the game is over.
+    pub lives: u32,
+    /// Times the player can still continue after the game is over.
+    pub continues: u32,
+    /// Path of the level the player is in.
+    pub level: String,
+    /// Names of the level objects the player has collected, such as discovered
+    /// secrets and completed challenges, so they stay collected across saves.
+    collected: BTreeSet<String>,
+    /// Level objects switched on, such as flipped switches and opened doors. Objects
+    /// are identified across levels, so a switch can open a door in another level.
+    switched_on: BTreeSet<ObjectId>,
+    /// Seed of the run, which its random numbers all come from.
+    seed: u32,
+    /// The run's streams of random numbers drawn from so far, by name.
+    streams: BTreeMap<String, Rng>,
 
     // Animation
     pub sprite_index: usize,
     frame_time: f32,
     current_action: String,
-    actions: HashMap<String, (usize, usize)>,
+    /// Whether the animation moved onto a new sprite frame in the last update.
+    frame_entered: bool,
+    actions: HashMap<String, Animation>,
+    /// Picks the animation to play from what the player is doing.
+    animation_states: AnimationStateMachine,
+    /// What the player was doing as of the last update, which chose the animation.
+    motion: Motion,
+    /// How long each sprite frame is shown, in seconds, for animations without their own
+    /// `frame_ms`. Frames without an entry use the tuned `animation_speed`.
+    frame_durations: Vec<f32>,
+    /// Area each sprite frame's attack strikes, for frames that attack.
+    hitboxes: Vec<Option<FrameBox>>,
+    /// Area the player can be hurt in on each sprite frame, where the sheet gives one.
+    hurtboxes: Vec<Option<FrameBox>>,
 }
 
 impl GameState {
     /// Creates a new `GameState` instance with default values.
     pub fn new() -> Self {
         let mut actions = HashMap::new();
-        actions.insert("idle".to_string(), (0, 0));
-        actions.insert("walk".to_string(), (1, 10));
-        actions.insert("kick".to_string(), (11, 13));
-        actions.insert("hurt".to_string(), (14, 16));
-        actions.insert("run".to_string(), (17, 23));
-        actions.insert("jump".to_string(), (6, 8));
-        actions.insert("crouch_walk".to_string(), (19, 23));
-        actions.insert("crouch_idle".to_string(), (18, 18));
+        actions.insert("idle".to_string(), Animation::fixed((0, 0), 100));
+        actions.insert("walk".to_string(), Animation::moving((1, 10), 100));
+        actions.insert("kick".to_string(), Animation::fixed((11, 13), 80));
+        actions.insert("hurt".to_string(), Animation::fixed((14, 16), 120));
+        actions.insert("run".to_string(), Animation::moving((17, 23), 100));
+        actions.insert("jump".to_string(), Animation::fixed((6, 8), 100));
+        actions.insert("crouch_walk".to_string(), Animation::moving((19, 23), 140));
+        actions.insert("crouch_idle".to_string(), Animation::fixed((18, 18), 100));
+        actions.insert("ride_idle".to_string(), Animation::fixed((18, 18), 100));
+        actions.insert("ride_move".to_string(), Animation::moving((19, 23), 100));
+        let seed = time_seed();
+
+        let player_y = GROUND_LEVEL + (SPRITE_HEIGHT / 2.0);
 
         Self {
             player_x: 0.0,
-            player_y: GROUND_LEVEL + (SPRITE_HEIGHT / 2.0),
+            player_y,
             player_velocity_x: 0.0,
             player_velocity_y: 0.0,
+            previous_position: (0.0, player_y),
+            unsimulated_time: 0.0,
             is_jumping: false,
+            grounded: false,
+            coyote_timer: 0.0,
+            jump_buffer_timer: 0.0,
+            platform_velocity: (0.0, 0.0),
+            carried_velocity_x: 0.0,
+            swimming: false,
+            swim_dash_time: 0.0,
+            swim_dash_velocity: (0.0, 0.0),
+            swim_dash_cooldown: 0.0,
+            swim_dash_started: false,
             is_crouching: false,
             is_running: false,
             is_kicking: false,
             facing_right: true,
+            movement: MovementProfile::on_foot(),
+            physics: Physics::tuned(),
+            cheats: Cheats::default(),
+            score: 0,
+            lives: STARTING_LIVES,
+            continues: STARTING_CONTINUES,
+            level: String::new(),
+            collected: BTreeSet::new(),
+            switched_on: BTreeSet::new(),
+            seed,
+            streams: BTreeMap::new(),
             sprite_index: 0,
             frame_time: 0.0,
             current_action: "idle".to_string(),
+            frame_entered: false,
             actions,
+            animation_states: AnimationStateMachine::new(),
+            motion: Motion::default(),
+            frame_durations: Vec::new(),
+            hitboxes: Vec::new(),
+            hurtboxes: Vec::new(),
+        }
+    }
+
+    /// Creates a new `GameState` whose animations come from a sprite sheet's tags and frame durations.
+    ///
+    /// Tagged actions take their frames and frame durations from the sheet, keeping
+    /// whether they follow the player's speed. Actions without a matching tag in the
+    /// sheet keep their default animations.
+    ///
+    /// # Arguments
+    ///
+    /// * `sprite_sheet` - The player's sprite sheet, typically imported from Aseprite.
+    pub fn with_sprite_sheet(sprite_sheet: &SpriteSheet) -> Self {
+        let mut state = Self::new();
+        for (name, &frames) in &sprite_sheet.tags {
+            let animation = state.actions.entry(name.clone()).or_insert(Animation {
+                frames,
+                frame_ms: None,
+                follows_speed: false,
+            });
+            animation.frames = frames;
+            animation.frame_ms = None;
+        }
+        state.frame_durations = sprite_sheet.frames.iter().map(|frame| frame.duration).collect();
+        state.hitboxes = sprite_sheet.frames.iter().map(|frame| frame.hitbox).collect();
+        state.hurtboxes = sprite_sheet.frames.iter().map(|frame| frame.hurtbox).collect();
+        state
+    }
+
+    /// Returns each animation's name and inclusive frame range, sorted by name.
+    pub fn animation_frames(&self) -> Vec<(&str, (usize, usize))> {
+        let mut animations: Vec<_> = self
+            .actions
+            .iter()
+            .map(|(name, animation)| (name.as_str(), animation.frames))
+            .collect();
+        animations.sort_unstable();
+        animations
+    }
+
+    /// Adds a frame's game time to the time left to simulate, and returns how many
+    /// fixed steps of `SIMULATION_STEP` to run for it, so the player and the level move
+    /// the same at any frame rate. The game loop runs each step through `update` and
+    /// the level's features.
+    ///
+    /// Time short of a whole step carries over to the next frame, and `drawn_position`
+    /// eases the player on by it. Time beyond `MAX_SIMULATION_STEPS` is dropped.
+    ///
+    /// # Arguments
+    ///
+    /// * `delta_time` - Game time the frame took, in seconds.
+    pub fn fixed_steps(&mut self, delta_time: f32) -> u32 {
+        self.unsimulated_time += delta_time;
+        let steps = ((self.unsimulated_time / SIMULATION_STEP) as u32).min(MAX_SIMULATION_STEPS);
+        self.unsimulated_time -= steps as f32 * SIMULATION_STEP;
+        if steps == MAX_SIMULATION_STEPS {
+            self.unsimulated_time %= SIMULATION_STEP;
         }
+        steps
+    }
+
+    /// Returns where to draw the player: partway from where they were before the last
+    /// update to where they are now, by how far the game time `fixed_steps` hasn't yet
+    /// handed out is into the next step. Drawing a step behind like this keeps movement
+    /// smooth when frames and steps don't line up.
+    pub fn drawn_position(&self) -> (f32, f32) {
+        let t = self.unsimulated_time / SIMULATION_STEP;
+        let (x, y) = self.previous_position;
+        (x + (self.player_x - x) * t, y + (self.player_y - y) * t)
     }
 
-    /// Updates the game state, including handling player input,
-    /// physics (gravity), and animations.
+    /// Updates the game state by a step, including handling player input, physics
+    /// (gravity), and animations.
     ///
     /// # Arguments
     ///
     /// * `input_handler` - Provides the state of input keys.
-    /// * `delta_time` - Time elapsed since the last frame.
+    /// * `delta_time` - Game time the step covers, `SIMULATION_STEP` in the game loop.
     pub fn update(&mut self, input_handler: &InputHandler, delta_time: f32) {
-        self.player_velocity_x = 0.0;
+        self.previous_position = (self.player_x, self.player_y);
+        self.frame_entered = false;
+        if self.cheats.noclip {
+            self.update_noclip(input_handler, delta_time);
+            return;
+        }
+
+        let movement = self.movement;
+
+        // Keys and the d-pad move at full speed. The stick's tilt goes through the response
+        // curve up to the run threshold, so a partly tilted stick moves (and animates)
+        // slower, and tilting it past the threshold runs.
+        let tuning = tuning();
+        let (direction, stick_running) = match input_handler.analog_movement_x() {
+            Some(tilt) => {
+                let threshold = tuning.stick_run_threshold;
+                let response = (tilt.abs() / threshold).min(1.0).powf(tuning.stick_response_exponent);
+                (response.copysign(tilt), tilt.abs() >= threshold)
+            }
+            None => (input_handler.movement_x(), false),
+        };
 
         // Handle running
-        self.is_running = input_handler.is_key_pressed(VirtualKeyCode::LShift);
+        self.is_running = input_handler.is_action_pressed(Action::Run) || stick_running;
+        let speed = if self.is_running { movement.speed * 1.5 } else { movement.speed };
+        let swimming = self.swimming && !movement.mounted && !movement.flying;
+        let speed = if swimming { speed * SWIM_SPEED_SCALE } else { speed };
 
         // Handle horizontal movement
-        let mut is_moving = false;
-        if input_handler.is_key_pressed(VirtualKeyCode::A) {
-            self.player_velocity_x -= if self.is_running { PLAYER_SPEED * 1.5 } else { PLAYER_SPEED };
-            self.facing_right = false;
-            is_moving = true;
+        let is_moving = direction != 0.0;
+        let target_velocity_x = direction * speed;
+        if is_moving {
+            self.facing_right = direction > 0.0;
         }
-        if input_handler.is_key_pressed(VirtualKeyCode::D) {
-            self.player_velocity_x += if self.is_running { PLAYER_SPEED * 1.5 } else { PLAYER_SPEED };
-            self.facing_right = true;
-            is_moving = true;
+        // The level's friction slows speed changes only for movement without its own acceleration
+        self.player_velocity_x = match movement.acceleration.or(self.physics.friction) {
+            Some(acceleration) => {
+                let step = acceleration * delta_time;
+                self.player_velocity_x
+                    + (target_velocity_x - self.player_velocity_x).clamp(-step, step)
+            }
+            None => target_velocity_x,
+        };
+
+        // Handle crouching and kicking, which aren't possible while riding
+        self.is_crouching = !movement.mounted && input_handler.is_action_pressed(Action::Crouch);
+        self.is_kicking = !movement.mounted && input_handler.is_action_pressed(Action::Kick);
+
+        // Burst through the water with a swim-dash, toward the held direction or else ahead
+        self.swim_dash_started = false;
+        self.swim_dash_time = (self.swim_dash_time - delta_time).max(0.0);
+        self.swim_dash_cooldown = (self.swim_dash_cooldown - delta_time).max(0.0);
+        if swimming && self.swim_dash_cooldown <= 0.0 && input_handler.is_action_just_pressed(Action::Dash) {
+            let mut aim_y = 0.0;
+            if input_handler.is_action_pressed(Action::Up) {
+                aim_y += 1.0;
+            }
+            if input_handler.is_action_pressed(Action::Down) {
+                aim_y -= 1.0;
+            }
+            let aim_x = if direction != 0.0 {
+                direction.signum()
+            } else if aim_y != 0.0 {
+                0.0
+            } else if self.facing_right {
+                1.0
+            } else {
+                -1.0
+            };
+            let length = f32::hypot(aim_x, aim_y);
+            self.swim_dash_velocity = (
+                aim_x / length * tuning.swim_dash_speed,
+                aim_y / length * tuning.swim_dash_speed,
+            );
+            self.swim_dash_time = SWIM_DASH_TIME;
+            self.swim_dash_cooldown = tuning.swim_dash_cooldown;
+            self.swim_dash_started = true;
         }
 
-        // Handle crouching
-        self.is_crouching = input_handler.is_key_pressed(VirtualKeyCode::LControl);
+        if movement.flying {
+            // Steer vertically instead of falling
+            self.player_velocity_y = 0.0;
+            if input_handler.is_action_pressed(Action::Up) {
+                self.player_velocity_y += movement.speed;
+            }
+            if input_handler.is_action_pressed(Action::Down) {
+                self.player_velocity_y -= movement.speed;
+            }
+        } else if self.swim_dash_time > 0.0 {
+            // Dashing carries the player straight along, gravity or not, even out of the water
+            (self.player_velocity_x, self.player_velocity_y) = self.swim_dash_velocity;
+        } else if swimming {
+            // Stroke upward with each jump, and otherwise sink slowly
+            if input_handler.is_action_just_pressed(Action::Jump) && !self.is_crouching && movement.jump_force > 0.0 {
+                self.player_velocity_y = self.player_velocity_y.max(tuning.swim_stroke_force);
+                self.is_jumping = true;
+            }
+            self.player_velocity_y = (self.player_velocity_y
+                + self.physics.gravity * tuning.swim_gravity_scale * delta_time)
+                .max(-SWIM_SINK_SPEED.min(self.physics.terminal_velocity));
+        } else {
+            // Jumps still work shortly after leaving a ledge, and a jump pressed just
+            // before landing is remembered until the player can take it
+            if self.grounded {
+                self.coyote_timer = tuning.coyote_time;
+            } else {
+                self.coyote_timer = (self.coyote_timer - delta_time).max(0.0);
+            }
+            if input_handler.is_action_just_pressed(Action::Jump) {
+                self.jump_buffer_timer = tuning.jump_buffer_time;
+            } else {
+                self.jump_buffer_timer = (self.jump_buffer_timer - delta_time).max(0.0);
+            }
 
-        // Handle kicking
-        self.is_kicking = input_handler.is_key_pressed(VirtualKeyCode::E);
+            // Handle jumping
+            if self.jump_buffer_timer > 0.0
+                && (self.coyote_timer > 0.0 || self.cheats.infinite_jumps)
+                && !self.is_crouching
+                && movement.jump_force > 0.0
+            {
+                // Jumping off a rising platform adds its speed to the jump
+                self.player_velocity_y = movement.jump_force + self.platform_velocity.1.max(0.0);
+                self.is_jumping = true;
+                self.jump_buffer_timer = 0.0;
+                self.coyote_timer = 0.0;
+            }
 
-        // Handle jumping
-        if input_handler.is_key_pressed(VirtualKeyCode::Space) && !self.is_jumping && !self.is_crouching {
-            self.player_velocity_y = JUMP_FORCE;
-            self.is_jumping = true;
+            // Apply gravity, up to the fastest the player can fall
+            self.player_velocity_y = (self.player_velocity_y + self.physics.gravity * delta_time)
+                .max(-self.physics.terminal_velocity);
         }
 
-        // Apply gravity
-        self.player_velocity_y += GRAVITY * delta_time;
-
-        // Update position
-        self.player_x += self.player_velocity_x * delta_time;
+        // Update position; standing on something is found again below, or by `land_on`.
+        // A platform carries the player along while they stand on it, and its horizontal
+        // velocity stays with them after leaving it
+        if self.grounded {
+            self.carried_velocity_x = self.platform_velocity.0;
+        }
+        self.platform_velocity = (0.0, 0.0);
+        self.grounded = false;
+        self.player_x += (self.player_velocity_x + self.carried_velocity_x) * delta_time;
         self.player_y += self.player_velocity_y * delta_time;
 
         // Ground collision
-        let player_bottom = self.player_y - (SPRITE_HEIGHT / 2.0);
+        let half_height = movement.hitbox.1 / 2.0;
+        let player_bottom = self.player_y - half_height;
         if player_bottom <= GROUND_LEVEL {
-            self.player_y = GROUND_LEVEL + (SPRITE_HEIGHT / 2.0);
+            self.player_y = GROUND_LEVEL + half_height;
             self.player_velocity_y = 0.0;
             self.is_jumping = false;
+            self.grounded = true;
+            self.carried_velocity_x = 0.0;
         }
 
         // Update action
@@ -118,30 +549,418 @@ impl GameState {
         self.update_animation(delta_time);
     }
 
+    /// Flies the player freely with the movement actions while noclip is on, ignoring
+    /// gravity and the ground. Running flies faster.
+    ///
+    /// # Arguments
+    ///
+    /// * `input_handler` - Provides the state of input keys.
+    /// * `delta_time` - Time elapsed since the last frame.
+    fn update_noclip(&mut self, input_handler: &InputHandler, delta_time: f32) {
+        let mut direction_y = 0.0;
+        if input_handler.is_action_pressed(Action::Up) {
+            direction_y += 1.0;
+        }
+        if input_handler.is_action_pressed(Action::Down) {
+            direction_y -= 1.0;
+        }
+        let direction_x = input_handler.movement_x();
+
+        self.is_running = input_handler.is_action_pressed(Action::Run);
+        let speed = if self.is_running { NOCLIP_SPEED * 2.0 } else { NOCLIP_SPEED };
+        self.player_velocity_x = direction_x * speed;
+        self.player_velocity_y = direction_y * speed;
+        self.player_x += self.player_velocity_x * delta_time;
+        self.player_y += self.player_velocity_y * delta_time;
+
+        let is_moving = direction_x != 0.0;
+        if is_moving {
+            self.facing_right = direction_x > 0.0;
+        }
+        self.is_jumping = false;
+        self.is_crouching = false;
+        self.is_kicking = false;
+        self.grounded = false;
+        self.platform_velocity = (0.0, 0.0);
+        self.swim_dash_time = 0.0;
+        self.carried_velocity_x = 0.0;
+
+        self.update_action(is_moving);
+        self.update_animation(delta_time);
+    }
+
+    /// Moves the player to the given position and stops all movement.
+    ///
+    /// # Arguments
+    ///
+    /// * `x` - The new horizontal position.
+    /// * `y` - The new vertical position.
+    pub fn teleport(&mut self, x: f32, y: f32) {
+        self.player_x = x;
+        self.player_y = y;
+        self.previous_position = (x, y);
+        self.player_velocity_x = 0.0;
+        self.player_velocity_y = 0.0;
+        self.platform_velocity = (0.0, 0.0);
+        self.carried_velocity_x = 0.0;
+        self.swim_dash_time = 0.0;
+    }
+
+    /// Returns whether the player was standing on the ground or a surface at the end
+    /// of the last update.
+    pub fn is_grounded(&self) -> bool {
+        self.grounded
+    }
+
+    /// Stands the player on a surface other than the ground, such as a rope bridge.
+    /// Does nothing while noclip is on, so the player can pass through the surface.
+    ///
+    /// # Arguments
+    ///
+    /// * `surface_y` - Height of the surface the player's feet rest on.
+    pub fn land_on(&mut self, surface_y: f32) {
+        if self.cheats.noclip {
+            return;
+        }
+        self.player_y = surface_y + self.movement.hitbox.1 / 2.0;
+        self.player_velocity_y = 0.0;
+        self.is_jumping = false;
+        self.grounded = true;
+        self.carried_velocity_x = 0.0;
+    }
+
+    /// Stands the player on a moving platform, which carries them along in the next
+    /// update and lends its velocity to jumps off it.
+    ///
+    /// # Arguments
+    ///
+    /// * `surface_y` - Height of the platform's top.
+    /// * `velocity` - The platform's velocity as `(x, y)`.
+    pub fn ride(&mut self, surface_y: f32, velocity: (f32, f32)) {
+        if self.cheats.noclip {
+            return;
+        }
+        self.land_on(surface_y);
+        self.platform_velocity = velocity;
+    }
+
+    /// Sets whether the player is in water, which they swim through instead of falling
+    /// in the next update.
+    pub fn set_swimming(&mut self, swimming: bool) {
+        self.swimming = swimming;
+    }
+
+    /// Returns whether the player was in water at the last update.
+    pub fn is_swimming(&self) -> bool {
+        self.swimming
+    }
+
+    /// Returns whether the player started a swim-dash in the last update.
+    pub fn swim_dash_started(&self) -> bool {
+        self.swim_dash_started
+    }
+
+    /// Returns the name of the animation playing.
+    pub fn current_action(&self) -> &str {
+        &self.current_action
+    }
+
+    /// Returns whether the animation moved onto a new sprite frame in the last update,
+    /// which fires the frame's animation events.
+    pub fn frame_entered(&self) -> bool {
+        self.frame_entered
+    }
+
+    /// Returns the player's current velocity as `(x, y)`.
+    pub fn velocity(&self) -> (f32, f32) {
+        (self.player_velocity_x, self.player_velocity_y)
+    }
+
+    /// Sets the player's velocity.
+    ///
+    /// The vertical component carries over into following frames, under gravity. The
+    /// horizontal one eases back toward the speed the input asks for at the movement's
+    /// acceleration, or the level's friction where the movement has none, so a push
+    /// carries over and dies away. With neither, it's replaced by the input's speed on
+    /// the next update.
+    ///
+    /// # Arguments
+    ///
+    /// * `x` - The new horizontal velocity.
+    /// * `y` - The new vertical velocity.
+    pub fn set_velocity(&mut self, x: f32, y: f32) {
+        self.player_velocity_x = x;
+        self.player_velocity_y = y;
+    }
+
+    /// Returns the player's collision box for the current movement profile.
+    pub fn bounds(&self) -> Aabb {
+        let (width, height) = self.movement.hitbox;
+        Aabb::from_center(self.player_x, self.player_y, width, height)
+    }
+
+    /// Returns the player's collision box around where they're drawn, such as to cast
+    /// their shadow from.
+    pub fn drawn_bounds(&self) -> Aabb {
+        let (x, y) = self.drawn_position();
+        let (width, height) = self.movement.hitbox;
+        Aabb::from_center(x, y, width, height)
+    }
+
+    /// Returns the area the player's current sprite frame strikes, if it attacks.
+    ///
+    /// Frames only attack on foot, since riding draws the player elsewhere.
+    pub fn hitbox(&self) -> Option<Aabb> {
+        if self.movement.mounted {
+            return None;
+        }
+        let frame_box = self.hitboxes.get(self.sprite_index).copied().flatten()?;
+        Some(self.place_frame_box(&frame_box))
+    }
+
+    /// Returns the area the player can be hurt in: the current sprite frame's hurtbox,
+    /// or the collision box for frames without one and while riding.
+    pub fn hurtbox(&self) -> Aabb {
+        match self.hurtboxes.get(self.sprite_index).copied().flatten() {
+            Some(frame_box) if !self.movement.mounted => self.place_frame_box(&frame_box),
+            _ => self.bounds(),
+        }
+    }
+
+    /// Places a box from the player's sprite frame in the world, where the sprite is drawn.
+    fn place_frame_box(&self, frame_box: &FrameBox) -> Aabb {
+        frame_box.place((self.player_x, self.player_y), (SPRITE_WIDTH, SPRITE_HEIGHT), self.facing_right)
+    }
+
+    /// Returns the profile currently controlling the player's movement.
+    pub fn movement(&self) -> &MovementProfile {
+        &self.movement
+    }
+
+    /// Replaces the player's movement profile, e.g. when mounting or dismounting.
+    ///
+    /// # Arguments
+    ///
+    /// * `movement` - The new movement profile.
+    pub fn set_movement(&mut self, movement: MovementProfile) {
+        self.movement = movement;
+    }
+
+    /// Returns the physics the player moves under.
+    pub fn physics(&self) -> &Physics {
+        &self.physics
+    }
+
+    /// Replaces the physics the player moves under, when a level with its own is
+    /// entered or left.
+    ///
+    /// # Arguments
+    ///
+    /// * `physics` - The new physics.
+    pub fn set_physics(&mut self, physics: Physics) {
+        self.physics = physics;
+    }
+
+    /// Marks a level object as collected.
+    ///
+    /// # Returns
+    ///
+    /// `true` if the object had not been collected before.
+    pub fn collect(&mut self, name: &str) -> bool {
+        self.collected.insert(name.to_string())
+    }
+
+    /// Returns whether a level object has been collected.
+    pub fn has_collected(&self, name: &str) -> bool {
+        self.collected.contains(name)
+    }
+
+    /// Switches a level object on or off.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - The object to switch, which may be in any level.
+    /// * `on` - Whether the object is now on.
+    pub fn set_object_on(&mut self, id: &ObjectId, on: bool) {
+        if on {
+            self.switched_on.insert(id.clone());
+        } else {
+            self.switched_on.remove(id);
+        }
+    }
+
+    /// Returns whether a level object is switched on. Objects start switched off.
+    pub fn is_object_on(&self, id: &ObjectId) -> bool {
+        self.switched_on.contains(id)
+    }
+
+    /// Returns whether the player is kicking.
+    pub fn is_kicking(&self) -> bool {
+        self.is_kicking
+    }
+
+    /// Returns the seed of the run, which its random numbers all come from.
+    pub fn seed(&self) -> u32 {
+        self.seed
+    }
+
+    /// Starts the run's random numbers over from a seed, so the run plays out the same
+    /// way as any other from the same seed.
+    pub fn reseed(&mut self, seed: u32) {
+        self.seed = seed;
+        self.streams.clear();
+    }
+
+    /// Returns one of the run's streams of random numbers, starting it from the seed
+    /// the first time it's drawn from. Each system draws from a stream of its own, and
+    /// the streams are saved with the game to carry on from.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - Names the system drawing from the stream, such as `"props"`.
+    pub fn stream(&mut self, name: &str) -> &mut Rng {
+        let seed = self.seed;
+        self.streams.entry(name.to_string()).or_insert_with(|| Rng::stream(seed, name))
+    }
+
+    /// Returns the game's random numbers, for its rules and AI to draw on so they
+    /// follow the run's seed, such as to scatter turrets' aim.
+    pub fn rng(&mut self) -> &mut Rng {
+        self.stream(GAME_STREAM)
+    }
+
+    /// Writes the player's progress to a save file, creating its directory if needed.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - Where to write the save file.
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), SaveError> {
+        let data = SaveData {
+            level: self.level.clone(),
+            player: (self.player_x, self.player_y),
+            score: self.score,
+            lives: self.lives,
+            continues: self.continues,
+            collected: self.collected.clone(),
+            switched_on: self.switched_on.clone(),
+            seed: Some(self.seed),
+            streams: self.streams.clone(),
+        };
+        let json = serde_json::to_string_pretty(&data).map_err(|e| SaveError::Parse(e.to_string()))?;
+
+        let path = path.as_ref();
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(path, json)?;
+        Ok(())
+    }
+
+    /// Restores the player's progress from a save file.
+    ///
+    /// The player is placed back on foot, standing still, at the saved position.
+    /// The caller is responsible for loading the saved level.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The save file to read.
+    pub fn load(&mut self, path: impl AsRef<Path>) -> Result<(), SaveError> {
+        let source = std::fs::read_to_string(path)?;
+        let data: SaveData =
+            serde_json::from_str(&source).map_err(|e| SaveError::Parse(e.to_string()))?;
+
+        self.level = data.level;
+        self.score = data.score;
+        self.lives = data.lives;
+        self.continues = data.continues;
+        self.collected = data.collected;
+        self.switched_on = data.switched_on;
+        if let Some(seed) = data.seed {
+            self.seed = seed;
+            self.streams = data.streams;
+        }
+        self.movement = MovementProfile::on_foot();
+        self.is_jumping = false;
+        self.teleport(data.player.0, data.player.1);
+        Ok(())
+    }
+
+    /// Captures the player's moment-to-moment state, to return to with `restore`.
+    pub fn snapshot(&self) -> PlayerSnapshot {
+        PlayerSnapshot {
+            position: (self.player_x, self.player_y),
+            velocity: (self.player_velocity_x, self.player_velocity_y),
+            is_jumping: self.is_jumping,
+            grounded: self.grounded,
+            platform_velocity: self.platform_velocity,
+            carried_velocity_x: self.carried_velocity_x,
+            swim_dash_time: self.swim_dash_time,
+            swim_dash_velocity: self.swim_dash_velocity,
+            is_crouching: self.is_crouching,
+            is_running: self.is_running,
+            is_kicking: self.is_kicking,
+            facing_right: self.facing_right,
+            sprite_index: self.sprite_index,
+            frame_time: self.frame_time,
+            current_action: self.current_action.clone(),
+        }
+    }
+
+    /// Puts the player back as they were when a snapshot was taken.
+    ///
+    /// # Arguments
+    ///
+    /// * `snapshot` - The state to return to.
+    pub fn restore(&mut self, snapshot: &PlayerSnapshot) {
+        (self.player_x, self.player_y) = snapshot.position;
+        self.previous_position = snapshot.position;
+        (self.player_velocity_x, self.player_velocity_y) = snapshot.velocity;
+        self.is_jumping = snapshot.is_jumping;
+        self.grounded = snapshot.grounded;
+        self.platform_velocity = snapshot.platform_velocity;
+        self.carried_velocity_x = snapshot.carried_velocity_x;
+        self.swim_dash_time = snapshot.swim_dash_time;
+        self.swim_dash_velocity = snapshot.swim_dash_velocity;
+        self.is_crouching = snapshot.is_crouching;
+        self.is_running = snapshot.is_running;
+        self.is_kicking = snapshot.is_kicking;
+        self.facing_right = snapshot.facing_right;
+        self.sprite_index = snapshot.sprite_index;
+        self.frame_time = snapshot.frame_time;
+        self.current_action.clone_from(&snapshot.current_action);
+    }
+
     /// Updates the player's current action based on their state and movement.
     ///
     /// # Arguments
     ///
     /// * `is_moving` - Whether the player is currently moving.
     fn update_action(&mut self, is_moving: bool) {
-        if self.is_kicking {
-            self.set_action("kick");
-        } else if self.is_jumping {
-            self.set_action("jump");
-        } else if self.is_crouching {
-            if is_moving {
-                self.set_action("crouch_walk");
-            } else {
-                self.set_action("crouch_idle");
-            }
-        } else if is_moving {
-            if self.is_running {
-                self.set_action("run");
-            } else {
-                self.set_action("walk");
-            }
-        } else {
-            self.set_action("idle");
+        self.motion = Motion {
+            mounted: self.movement.mounted,
+            kicking: self.is_kicking,
+            jumping: self.is_jumping,
+            crouching: self.is_crouching,
+            moving: is_moving,
+            running: self.is_running,
+        };
+        if let Some(action) = self.animation_states.next(&self.current_action, &self.motion) {
+            self.set_action(action);
+        }
+    }
+
+    /// Returns the machine picking the player's animations, to add states for new moves to.
+    pub fn animation_states_mut(&mut self) -> &mut AnimationStateMachine {
+        &mut self.animation_states
+    }
+
+    /// Ends a move whose animation has played out, such as a kick.
+    fn end_move(&mut self, the_move: Condition) {
+        match the_move {
+            Condition::Kicking => self.is_kicking = false,
+            Condition::Jumping => self.is_jumping = false,
+            Condition::Crouching => self.is_crouching = false,
+            Condition::Mounted | Condition::Moving | Condition::Running => {}
         }
     }
 
@@ -152,10 +971,11 @@ impl GameState {
     /// * `action` - The name of the action to set.
     fn set_action(&mut self, action: &str) {
         if self.current_action != action {
-            if let Some(&(start_frame, _)) = self.actions.get(action) {
+            if let Some(&Animation { frames: (start_frame, _), .. }) = self.actions.get(action) {
                 self.current_action = action.to_string();
                 self.sprite_index = start_frame;
                 self.frame_time = 0.0;
+                self.frame_entered = true;
             } else {
                 eprintln!("Action '{}' not found in actions HashMap", action);
             }
@@ -168,26 +988,65 @@ impl GameState {
     ///
     /// * `delta_time` - Time elapsed since the last frame.
     fn update_animation(&mut self, delta_time: f32) {
-        self.frame_time += delta_time;
+        let animation = self.actions[&self.current_action];
+        self.frame_time += delta_time * self.animation_rate(&animation);
 
-        if self.frame_time >= ANIMATION_SPEED {
-            let (start_frame, end_frame) = self.actions[&self.current_action];
+        let frame_duration = match animation.frame_ms {
+            Some(frame_ms) => frame_ms as f32 / 1000.0,
+            None => self
+                .frame_durations
+                .get(self.sprite_index)
+                .copied()
+                .unwrap_or(tuning().animation_speed),
+        };
+
+        if self.frame_time >= frame_duration {
+            let (start_frame, end_frame) = animation.frames;
 
             if start_frame == end_frame {
                 self.sprite_index = start_frame;
             } else {
                 self.sprite_index += 1;
                 if self.sprite_index > end_frame {
-                    if self.current_action == "kick" {
-                        self.is_kicking = false;
-                        self.set_action("idle");
-                    } else {
-                        self.sprite_index = start_frame;
+                    let played_out = self.animation_states.state(&self.current_action).and_then(|state| state.plays_out);
+                    match played_out {
+                        Some(the_move) => {
+                            self.end_move(the_move);
+                            self.motion = self.motion.without(the_move);
+                            if let Some(action) = self.animation_states.after(&self.motion) {
+                                self.set_action(action);
+                            }
+                        }
+                        None => self.sprite_index = start_frame,
                     }
                 }
+                self.frame_entered = true;
             }
 
             self.frame_time = 0.0;
         }
     }
+
+    /// Returns how fast an animation plays relative to its normal rate.
+    ///
+    /// Animations that follow the player's speed play at their normal rate at the
+    /// top speed before running, so running speeds them up and moving slower, such
+    /// as with the stick partly tilted, slows them down.
+    ///
+    /// # Arguments
+    ///
+    /// * `animation` - The animation being played.
+    fn animation_rate(&self, animation: &Animation) -> f32 {
+        if !animation.follows_speed || self.movement.speed <= 0.0 {
+            return 1.0;
+        }
+        let (min, max) = ANIMATION_RATE_RANGE;
+        (self.player_velocity_x.abs() / self.movement.speed).clamp(min, max)
+    }
+}
+
+impl Default for GameState {
+    fn default() -> Self {
+        Self::new()
+    }
 }
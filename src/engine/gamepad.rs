@@ -0,0 +1,121 @@
+// gamepad.rs
+use crate::engine::input::InputHandler;
+use log::info;
+#[cfg(feature = "gamepad")]
+use crate::engine::action::GamepadButton;
+#[cfg(feature = "gamepad")]
+use log::warn;
+
+/// How far the left stick must be pushed up or down to count as pressing the d-pad.
+#[cfg(feature = "gamepad")]
+const STICK_THRESHOLD: f32 = 0.5;
+
+/// How far the left stick can drift from center without moving the player.
+#[cfg(feature = "gamepad")]
+const STICK_DEAD_ZONE: f32 = 0.15;
+
+/// Reads connected gamepads into the input handler, so their buttons trigger actions.
+///
+/// When built without the `gamepad` feature, or when gamepads can't be read,
+/// only the keyboard and mouse are used.
+pub struct Gamepads {
+    #[cfg(feature = "gamepad")]
+    gilrs: Option<gilrs::Gilrs>,
+}
+
+impl Gamepads {
+    /// Starts reading gamepads, including ones connected later.
+    pub fn new() -> Self {
+        #[cfg(feature = "gamepad")]
+        {
+            let gilrs = match gilrs::Gilrs::new() {
+                Ok(gilrs) => {
+                    info!("Reading gamepads; {} connected", gilrs.gamepads().count());
+                    Some(gilrs)
+                }
+                Err(e) => {
+                    warn!("Could not read gamepads, continuing with the keyboard only: {}", e);
+                    None
+                }
+            };
+            Self { gilrs }
+        }
+
+        #[cfg(not(feature = "gamepad"))]
+        {
+            info!("Built without the `gamepad` feature; only the keyboard and mouse are read");
+            Self {}
+        }
+    }
+
+    /// Passes the button presses and stick movements since the last call to the input handler.
+    ///
+    /// The left stick moves the player by how far it is tilted sideways, and counts
+    /// as the d-pad up and down.
+    ///
+    /// # Arguments
+    ///
+    /// * `input_handler` - The input handler to update.
+    #[cfg_attr(not(feature = "gamepad"), allow(unused_variables))]
+    pub fn update(&mut self, input_handler: &mut InputHandler) {
+        #[cfg(feature = "gamepad")]
+        if let Some(gilrs) = self.gilrs.as_mut() {
+            while let Some(gilrs::Event { event, .. }) = gilrs.next_event() {
+                match event {
+                    gilrs::EventType::ButtonPressed(button, _) => {
+                        if let Some(button) = gamepad_button(button) {
+                            input_handler.handle_gamepad_button(button, true);
+                        }
+                    }
+                    gilrs::EventType::ButtonReleased(button, _) => {
+                        if let Some(button) = gamepad_button(button) {
+                            input_handler.handle_gamepad_button(button, false);
+                        }
+                    }
+                    gilrs::EventType::AxisChanged(gilrs::Axis::LeftStickX, value, _) => {
+                        input_handler.handle_gamepad_stick_x(remove_dead_zone(value));
+                    }
+                    gilrs::EventType::AxisChanged(gilrs::Axis::LeftStickY, value, _) => {
+                        input_handler.handle_gamepad_button(GamepadButton::DPadDown, value < -STICK_THRESHOLD);
+                        input_handler.handle_gamepad_button(GamepadButton::DPadUp, value > STICK_THRESHOLD);
+                    }
+                    gilrs::EventType::Disconnected => input_handler.release_gamepad_buttons(),
+                    _ => {}
+                }
+            }
+        }
+    }
+}
+
+impl Default for Gamepads {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Rescales a stick axis so the dead zone around center reads as zero and the
+/// rest of its travel still covers the full range.
+#[cfg(feature = "gamepad")]
+fn remove_dead_zone(value: f32) -> f32 {
+    let magnitude = ((value.abs() - STICK_DEAD_ZONE) / (1.0 - STICK_DEAD_ZONE)).max(0.0);
+    magnitude.copysign(value)
+}
+
+/// Maps a gilrs button to the buttons actions can be bound to.
+#[cfg(feature = "gamepad")]
+fn gamepad_button(button: gilrs::Button) -> Option<GamepadButton> {
+    match button {
+        gilrs::Button::South => Some(GamepadButton::South),
+        gilrs::Button::West => Some(GamepadButton::West),
+        gilrs::Button::North => Some(GamepadButton::North),
+        gilrs::Button::LeftTrigger => Some(GamepadButton::LeftBumper),
+        gilrs::Button::RightTrigger => Some(GamepadButton::RightBumper),
+        gilrs::Button::Select => Some(GamepadButton::Select),
+        gilrs::Button::Start => Some(GamepadButton::Start),
+        gilrs::Button::DPadUp => Some(GamepadButton::DPadUp),
+        gilrs::Button::DPadDown => Some(GamepadButton::DPadDown),
+        gilrs::Button::DPadLeft => Some(GamepadButton::DPadLeft),
+        gilrs::Button::DPadRight => Some(GamepadButton::DPadRight),
+        _ => None,
+    }
+}
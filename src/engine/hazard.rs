@@ -0,0 +1,67 @@
+// hazard.rs
+use crate::engine::collision::Aabb;
+use crate::engine::game_state::GameState;
+use crate::engine::level::Level;
+
+/// An area that costs the player a life on contact, such as a row of spikes.
+pub struct Hazard {
+    /// Area that hurts the player, in world coordinates.
+    pub bounds: Aabb,
+    /// Index of the tileset tile repeated along the hazard.
+    pub tile_index: usize,
+    /// Columns and rows of tileset tiles making up each repeat of its art.
+    pub tile_span: (usize, usize),
+    /// How far each repeat of its art is turned about its center, counterclockwise in radians.
+    pub angle: f32,
+    /// How fast its art spins, in radians per second.
+    spin: f32,
+}
+
+/// The level's hazards.
+///
+/// Hazards come from `hazard` rectangles in the level, drawn by repeating the
+/// tileset tile named by their `tile` property along their length. Each repeat can
+/// be tilted by a `rotation` property and spun like a saw blade by a `spin`
+/// property, in degrees and degrees per second counterclockwise; the area that
+/// hurts stays the rectangle.
+pub struct Hazards {
+    pub hazards: Vec<Hazard>,
+}
+
+impl Hazards {
+    /// Creates the hazards placed in the level.
+    pub fn from_level(level: &Level) -> Self {
+        let hazards = level
+            .objects_of_kind("hazard")
+            .map(|object| {
+                let (angle, spin) = object.art_rotation();
+                Hazard {
+                    bounds: object.bounds,
+                    tile_index: object.tile_index(),
+                    tile_span: object.tile_span(),
+                    angle,
+                    spin,
+                }
+            })
+            .collect();
+
+        Self { hazards }
+    }
+
+    /// Spins the hazards' art by a frame.
+    ///
+    /// # Arguments
+    ///
+    /// * `delta_time` - Game time the frame took, in seconds.
+    pub fn animate(&mut self, delta_time: f32) {
+        for hazard in &mut self.hazards {
+            hazard.angle = (hazard.angle + hazard.spin * delta_time) % std::f32::consts::TAU;
+        }
+    }
+
+    /// Returns `true` if any hazard touches the player's hurtbox.
+    pub fn touching(&self, game_state: &GameState) -> bool {
+        let player = game_state.hurtbox();
+        self.hazards.iter().any(|hazard| hazard.bounds.intersects(&player))
+    }
+}
@@ -1,18 +1,67 @@
+use crate::engine::action::{Action, ActionMap, GamepadButton};
 use std::collections::HashSet;
-use winit::event::{ElementState, KeyboardInput, VirtualKeyCode};
+use winit::dpi::{PhysicalPosition, PhysicalSize};
+use winit::event::{ElementState, KeyboardInput, MouseButton, MouseScrollDelta, VirtualKeyCode};
+
+/// Pixels of touchpad scrolling counted as one line of mouse wheel scrolling.
+const PIXELS_PER_LINE: f32 = 40.0;
 
 pub struct InputHandler {
     keys_pressed: HashSet<VirtualKeyCode>,
+    mouse_buttons_pressed: HashSet<MouseButton>,
+    /// Mouse buttons held at the end of the last frame, to tell new clicks from held buttons.
+    previous_mouse_buttons: HashSet<MouseButton>,
+    /// Lines scrolled by the mouse wheel this frame; positive is up.
+    scroll: f32,
+    /// Cursor position in screen coordinates (-1..1, y up), or `None` while it's outside the window.
+    cursor: Option<(f32, f32)>,
+    gamepad_buttons_pressed: HashSet<GamepadButton>,
+    /// Horizontal position of the gamepad's left stick, from -1 (left) to 1 (right).
+    stick_x: f32,
+    /// The bindings actions are read through.
+    action_map: ActionMap,
+    /// Actions held at the end of the last frame, to tell new presses from held ones.
+    previous_actions: HashSet<Action>,
+    /// Actions pressed since the simulation last stepped, for its next step to see.
+    unstepped_presses: HashSet<Action>,
+    /// While the simulation steps, the actions pressed since its last step, which are
+    /// what count as just pressed then.
+    step_presses: Option<HashSet<Action>>,
+    /// Whether the keyboard is typing text, such as into the console, rather than
+    /// playing; its keys don't count toward actions then.
+    typing: bool,
+    /// Characters typed this frame while typing.
+    typed: String,
 }
 
 impl InputHandler {
     pub fn new() -> Self {
+        Self::with_action_map(ActionMap::default())
+    }
+
+    /// Creates an input handler reading actions through the given bindings.
+    pub fn with_action_map(action_map: ActionMap) -> Self {
         Self {
             keys_pressed: HashSet::new(),
+            mouse_buttons_pressed: HashSet::new(),
+            previous_mouse_buttons: HashSet::new(),
+            scroll: 0.0,
+            cursor: None,
+            gamepad_buttons_pressed: HashSet::new(),
+            stick_x: 0.0,
+            action_map,
+            previous_actions: HashSet::new(),
+            unstepped_presses: HashSet::new(),
+            step_presses: None,
+            typing: false,
+            typed: String::new(),
         }
     }
 
     pub fn handle_keyboard_input(&mut self, input: KeyboardInput) {
+        if self.typing {
+            return;
+        }
         if let Some(key) = input.virtual_keycode {
             match input.state {
                 ElementState::Pressed => {
@@ -25,7 +74,190 @@ impl InputHandler {
         }
     }
 
+    /// Records a character typed, while typing.
+    pub fn handle_received_character(&mut self, character: char) {
+        if self.typing {
+            self.typed.push(character);
+        }
+    }
+
+    /// Starts or stops typing text, releasing every key so none stays held.
+    pub fn set_typing(&mut self, typing: bool) {
+        self.typing = typing;
+        self.keys_pressed.clear();
+        self.typed.clear();
+    }
+
+    /// Returns the characters typed this frame while typing, in order.
+    pub fn typed(&self) -> &str {
+        &self.typed
+    }
+
+    pub fn handle_mouse_input(&mut self, state: ElementState, button: MouseButton) {
+        match state {
+            ElementState::Pressed => {
+                self.mouse_buttons_pressed.insert(button);
+            }
+            ElementState::Released => {
+                self.mouse_buttons_pressed.remove(&button);
+            }
+        }
+    }
+
+    /// Adds up the mouse wheel's movement, counting pixel deltas from touchpads in lines.
+    pub fn handle_mouse_wheel(&mut self, delta: MouseScrollDelta) {
+        self.scroll += match delta {
+            MouseScrollDelta::LineDelta(_, y) => y,
+            MouseScrollDelta::PixelDelta(position) => position.y as f32 / PIXELS_PER_LINE,
+        };
+    }
+
+    /// Records the cursor's position, converting it from pixels within the area drawn
+    /// to (the whole window, unless letterboxed) to screen coordinates.
+    pub fn handle_cursor_moved(&mut self, position: PhysicalPosition<f64>, viewport_size: PhysicalSize<u32>) {
+        let x = position.x as f32 / viewport_size.width as f32 * 2.0 - 1.0;
+        let y = 1.0 - position.y as f32 / viewport_size.height as f32 * 2.0;
+        self.cursor = Some((x, y));
+    }
+
+    pub fn handle_cursor_left(&mut self) {
+        self.cursor = None;
+    }
+
+    #[cfg_attr(not(feature = "gamepad"), allow(dead_code))]
+    pub fn handle_gamepad_button(&mut self, button: GamepadButton, pressed: bool) {
+        if pressed {
+            self.gamepad_buttons_pressed.insert(button);
+        } else {
+            self.gamepad_buttons_pressed.remove(&button);
+        }
+    }
+
+    /// Records the horizontal position of the gamepad's left stick, with its dead zone already removed.
+    #[cfg_attr(not(feature = "gamepad"), allow(dead_code))]
+    pub fn handle_gamepad_stick_x(&mut self, x: f32) {
+        self.stick_x = x.clamp(-1.0, 1.0);
+    }
+
+    /// Releases every gamepad button and centers the stick, such as when the gamepad
+    /// is disconnected mid-press.
+    #[cfg_attr(not(feature = "gamepad"), allow(dead_code))]
+    pub fn release_gamepad_buttons(&mut self) {
+        self.gamepad_buttons_pressed.clear();
+        self.stick_x = 0.0;
+    }
+
+    /// Remembers which actions and mouse buttons are held, so the next frame can tell
+    /// new presses apart, and clears the frame's scrolling and typing. Call once at the end of every frame.
+    pub fn end_frame(&mut self) {
+        self.previous_mouse_buttons = self.mouse_buttons_pressed.clone();
+        self.scroll = 0.0;
+        self.typed.clear();
+        self.previous_actions = self
+            .action_map
+            .bindings()
+            .iter()
+            .map(|binding| binding.action)
+            .filter(|&action| self.is_action_pressed(action))
+            .collect();
+    }
+
+    /// Keeps the actions pressed this frame for the simulation's next step. Call once a
+    /// frame in which the simulation runs, before its steps.
+    pub fn queue_step_presses(&mut self) {
+        let pressed: Vec<Action> = self
+            .action_map
+            .bindings()
+            .iter()
+            .map(|binding| binding.action)
+            .filter(|&action| self.is_action_just_pressed(action))
+            .collect();
+        self.unstepped_presses.extend(pressed);
+    }
+
+    /// Starts a fixed simulation step, in which the actions pressed since the last step
+    /// count as just pressed. A press in a frame too short to run a step is kept for
+    /// the next step, and one in a frame running several is seen by the first alone.
+    pub fn begin_step(&mut self) {
+        self.step_presses = Some(std::mem::take(&mut self.unstepped_presses));
+    }
+
+    /// Ends a simulation step, going back to counting presses by frame.
+    pub fn end_step(&mut self) {
+        self.step_presses = None;
+    }
+
     pub fn is_key_pressed(&self, key: VirtualKeyCode) -> bool {
         self.keys_pressed.contains(&key)
     }
+
+    /// Returns whether any key or gamepad button bound to the action is held.
+    pub fn is_action_pressed(&self, action: Action) -> bool {
+        self.action_map.binding(action).is_some_and(|binding| {
+            binding.keys.iter().any(|&key| self.is_key_pressed(key))
+                || binding.buttons.iter().any(|button| self.gamepad_buttons_pressed.contains(button))
+        })
+    }
+
+    /// Returns how far to move horizontally, from -1 (full speed left) to 1 (full speed right).
+    ///
+    /// The move actions give full speed, and holding both cancels out. Without them,
+    /// the gamepad's left stick gives a speed in between.
+    pub fn movement_x(&self) -> f32 {
+        let left = self.is_action_pressed(Action::MoveLeft);
+        let right = self.is_action_pressed(Action::MoveRight);
+        if left || right {
+            right as i32 as f32 - left as i32 as f32
+        } else {
+            self.stick_x
+        }
+    }
+
+    /// Returns the gamepad's left stick tilt, from -1 to 1, while it alone is moving the player.
+    ///
+    /// `None` while a move action is held or the stick is centered, so movement is all or nothing.
+    pub fn analog_movement_x(&self) -> Option<f32> {
+        let digital = self.is_action_pressed(Action::MoveLeft) || self.is_action_pressed(Action::MoveRight);
+        (!digital && self.stick_x != 0.0).then_some(self.stick_x)
+    }
+
+    /// Returns whether the action was pressed this frame, having not been held the frame
+    /// before, or during a simulation step, since the last step.
+    pub fn is_action_just_pressed(&self, action: Action) -> bool {
+        match &self.step_presses {
+            Some(presses) => presses.contains(&action),
+            None => self.is_action_pressed(action) && !self.previous_actions.contains(&action),
+        }
+    }
+
+    /// Returns the bindings actions are read through.
+    pub fn action_map(&self) -> &ActionMap {
+        &self.action_map
+    }
+
+    pub fn is_mouse_pressed(&self, button: MouseButton) -> bool {
+        self.mouse_buttons_pressed.contains(&button)
+    }
+
+    /// Returns whether the mouse button was pressed this frame, having not been held the frame before.
+    #[cfg_attr(not(debug_assertions), allow(dead_code))]
+    pub fn is_mouse_just_pressed(&self, button: MouseButton) -> bool {
+        self.is_mouse_pressed(button) && !self.previous_mouse_buttons.contains(&button)
+    }
+
+    /// Returns how many lines the mouse wheel scrolled this frame; positive is up.
+    pub fn scroll(&self) -> f32 {
+        self.scroll
+    }
+
+    /// Returns the cursor position in screen coordinates, or `None` if it's outside the window.
+    pub fn cursor_position(&self) -> Option<(f32, f32)> {
+        self.cursor
+    }
+}
+
+impl Default for InputHandler {
+    fn default() -> Self {
+        Self::new()
+    }
 }
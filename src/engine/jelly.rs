@@ -0,0 +1,204 @@
+// jelly.rs
+use crate::engine::activation::ActivationRange;
+use crate::engine::collision::Aabb;
+use crate::engine::config::tuning;
+use crate::engine::game_state::GameState;
+use crate::engine::level::Level;
+
+/// How strongly each surface point is pulled back to its resting height.
+const STIFFNESS: f32 = 120.0;
+
+/// How strongly each surface point is pulled toward its neighbors, spreading dents as ripples.
+const SPREAD: f32 = 60.0;
+
+/// How quickly the surface's wobble dies down.
+const DAMPING: f32 = 6.0;
+
+/// Downward acceleration the player's weight applies to the surface while standing on it.
+const PLAYER_WEIGHT: f32 = 8.0;
+
+/// Landing speed below which the player settles onto the surface instead of bouncing.
+const BOUNCE_THRESHOLD: f32 = 1.5;
+
+/// How many surface points on each side of the player are pushed by a landing or by standing.
+const CONTACT_RADIUS: f32 = 3.0;
+
+/// How far below the surface the player's feet can be and still land on it.
+const LANDING_DEPTH: f32 = 0.15;
+
+/// A point on a jelly platform's top edge, displaced vertically from rest.
+#[derive(Clone)]
+struct SurfacePoint {
+    offset: f32,
+    velocity: f32,
+}
+
+/// A platform whose top surface is a row of springs that dent and wobble.
+#[derive(Clone)]
+pub struct JellyPlatform {
+    /// The platform's resting shape in world coordinates.
+    pub bounds: Aabb,
+    points: Vec<SurfacePoint>,
+    /// Index of the tileset tile stretched over the platform.
+    pub tile_index: usize,
+    /// Whether the platform is near enough to the camera to wobble and be landed on.
+    active: bool,
+}
+
+impl JellyPlatform {
+    /// Returns the points along the platform's top edge, from left to right.
+    pub fn surface(&self) -> impl Iterator<Item = (f32, f32)> + '_ {
+        self.points.iter().enumerate().map(|(index, point)| {
+            (self.point_x(index), self.bounds.max_y + point.offset)
+        })
+    }
+
+    /// Returns the points along the platform's bottom edge, which wobble less than the top.
+    pub fn base(&self) -> impl Iterator<Item = (f32, f32)> + '_ {
+        self.points.iter().enumerate().map(|(index, point)| {
+            (self.point_x(index), self.bounds.min_y + point.offset * 0.3)
+        })
+    }
+
+    /// Returns the horizontal position of a surface point.
+    fn point_x(&self, index: usize) -> f32 {
+        let t = index as f32 / (self.points.len() - 1) as f32;
+        self.bounds.min_x + (self.bounds.max_x - self.bounds.min_x) * t
+    }
+
+    /// Returns the position along the surface, in points from the left edge, above the given x.
+    fn position_at(&self, x: f32) -> f32 {
+        let t = (x - self.bounds.min_x) / (self.bounds.max_x - self.bounds.min_x);
+        t.clamp(0.0, 1.0) * (self.points.len() - 1) as f32
+    }
+
+    /// Returns the height of the surface at a position along it.
+    fn height_at(&self, position: f32) -> f32 {
+        let index = (position as usize).min(self.points.len() - 2);
+        let t = position - index as f32;
+        let offset = self.points[index].offset + (self.points[index + 1].offset - self.points[index].offset) * t;
+        self.bounds.max_y + offset
+    }
+
+    /// Changes the velocity of the points around a position, fading out with distance.
+    fn push(&mut self, position: f32, amount: f32) {
+        for (index, point) in self.points.iter_mut().enumerate() {
+            let falloff = 1.0 - (index as f32 - position).abs() / CONTACT_RADIUS;
+            if falloff > 0.0 {
+                point.velocity += amount * falloff;
+            }
+        }
+    }
+
+    /// Advances the springs by one step.
+    fn simulate(&mut self, delta_time: f32) {
+        let offsets: Vec<f32> = self.points.iter().map(|point| point.offset).collect();
+        for (index, point) in self.points.iter_mut().enumerate() {
+            // Points past the edges are treated as resting
+            let left = if index > 0 { offsets[index - 1] } else { 0.0 };
+            let right = offsets.get(index + 1).copied().unwrap_or(0.0);
+
+            let acceleration = -STIFFNESS * point.offset
+                + SPREAD * (left + right - 2.0 * point.offset)
+                - DAMPING * point.velocity;
+            point.velocity += acceleration * delta_time;
+            point.offset += point.velocity * delta_time;
+        }
+    }
+}
+
+/// Jelly platforms that dent under the player and bounce them back up.
+///
+/// Platforms come from `jelly` objects in the level. The `segments` property sets
+/// how many springs make up the surface, and `tile` the tileset tile stretched over
+/// it. Like bridges, they can be jumped through from below and stood on from above.
+#[derive(Clone)]
+pub struct JellyPlatforms {
+    pub platforms: Vec<JellyPlatform>,
+}
+
+impl JellyPlatforms {
+    /// Creates the jelly platforms placed in the level, at rest.
+    pub fn from_level(level: &Level) -> Self {
+        let platforms = level
+            .objects_of_kind("jelly")
+            .map(|object| {
+                let segments = object.property_f32("segments", 12.0).max(1.0) as usize;
+                JellyPlatform {
+                    bounds: object.bounds,
+                    points: (0..=segments)
+                        .map(|_| SurfacePoint {
+                            offset: 0.0,
+                            velocity: 0.0,
+                        })
+                        .collect(),
+                    tile_index: object.tile_index(),
+                    active: true,
+                }
+            })
+            .collect();
+
+        Self { platforms }
+    }
+
+    /// Activates the platforms within range of the camera and deactivates the rest.
+    ///
+    /// # Returns
+    ///
+    /// The number of platforms left active.
+    pub fn activate(&mut self, range: &ActivationRange) -> usize {
+        for platform in &mut self.platforms {
+            platform.active = range.contains(&platform.bounds);
+        }
+        self.platforms.iter().filter(|platform| platform.active).count()
+    }
+
+    /// Wobbles the active platforms, and lands or bounces the player on them.
+    ///
+    /// # Arguments
+    ///
+    /// * `game_state` - The player state to stand on or bounce off the platforms.
+    /// * `delta_time` - Time elapsed since the last frame.
+    ///
+    /// # Returns
+    ///
+    /// `true` if the player bounced off a platform this frame.
+    pub fn update(&mut self, game_state: &mut GameState, delta_time: f32) -> bool {
+        let mut bounced = false;
+        // Long frames would make the springs unstable
+        let delta_time = delta_time.min(1.0 / 30.0);
+
+        for platform in self.platforms.iter_mut().filter(|platform| platform.active) {
+            platform.simulate(delta_time);
+
+            let player = game_state.bounds();
+            let (_, velocity_y) = game_state.velocity();
+            if game_state.cheats.noclip
+                || game_state.player_x < platform.bounds.min_x
+                || game_state.player_x > platform.bounds.max_x
+                || velocity_y > 0.0
+            {
+                continue;
+            }
+
+            let position = platform.position_at(game_state.player_x);
+            let surface = platform.height_at(position);
+            if player.min_y > surface || player.min_y < surface - LANDING_DEPTH {
+                continue;
+            }
+
+            let impact = -velocity_y;
+            game_state.land_on(surface);
+            if impact > BOUNCE_THRESHOLD {
+                // Dent the surface and spring the player back up
+                platform.push(position, -impact * 0.5);
+                game_state.set_velocity(0.0, impact * tuning().jelly_bounce);
+                bounced = true;
+            } else {
+                platform.push(position, -PLAYER_WEIGHT * delta_time);
+            }
+        }
+
+        bounced
+    }
+}
@@ -0,0 +1,493 @@
+// bundle.rs
+use crate::engine::config::ConfigError;
+use crate::engine::constants::{SPRITE_HEIGHT, SPRITE_WIDTH};
+use crate::engine::level::tiled::parse_tiled_map;
+use crate::engine::level::Level;
+use crate::engine::mods::{resolve, write_manifest};
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use image::{imageops, GenericImageView, RgbaImage};
+use log::info;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::io::{Cursor, Read, Write};
+use std::path::{Component, Path, PathBuf};
+
+/// Extension given to level bundle files.
+pub const BUNDLE_EXTENSION: &str = "levelpack";
+
+/// Bytes every bundle starts with, once decompressed.
+const MAGIC: &[u8; 4] = b"PLVL";
+
+/// Version of the bundle layout written by this build. Bundles of other versions
+/// are rejected rather than misread.
+const FORMAT_VERSION: u32 = 1;
+
+/// Entry holding the bundle's `BundleManifest`.
+const MANIFEST_ENTRY: &str = "manifest.json";
+
+/// Entry holding the bundle's thumbnail.
+const THUMBNAIL_ENTRY: &str = "thumbnail.png";
+
+/// Largest a bundle may be once decompressed, so a malicious file can't exhaust memory.
+const MAX_BUNDLE_SIZE: u64 = 256 * 1024 * 1024;
+
+/// Largest width or height of a thumbnail, in pixels.
+const THUMBNAIL_SIZE: u32 = 256;
+
+/// The tileset levels are drawn with, which a bundle may replace with its own.
+pub const TILESET_PATH: &str = "assets/tileset/Tileset.png";
+
+/// Width and height of a tileset tile, in pixels.
+const TILE_PIXEL_SIZE: u32 = 16;
+
+/// Errors that can occur while exporting or importing a level bundle.
+#[derive(Debug)]
+pub enum BundleError {
+    /// A file could not be read or written.
+    Io(std::io::Error),
+    /// The bundle, or the level being exported, is malformed.
+    Invalid(String),
+}
+
+impl fmt::Display for BundleError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BundleError::Io(e) => write!(f, "failed to access level bundle: {}", e),
+            BundleError::Invalid(msg) => write!(f, "invalid level bundle: {}", msg),
+        }
+    }
+}
+
+impl std::error::Error for BundleError {}
+
+impl From<std::io::Error> for BundleError {
+    fn from(e: std::io::Error) -> Self {
+        BundleError::Io(e)
+    }
+}
+
+impl From<ConfigError> for BundleError {
+    fn from(e: ConfigError) -> Self {
+        match e {
+            ConfigError::Io(e) => BundleError::Io(e),
+            ConfigError::Parse(msg) => BundleError::Invalid(msg),
+        }
+    }
+}
+
+/// Describes a bundle's level, stored as its `manifest.json`.
+#[derive(Serialize, Deserialize)]
+pub struct BundleManifest {
+    /// Name of the level, shown in the mods list once imported.
+    pub name: String,
+    #[serde(default)]
+    pub author: String,
+    #[serde(default)]
+    pub description: String,
+    /// Entry holding the level's Tiled map, `level.json` or `level.tmx`.
+    pub level: String,
+    /// Entry holding a PNG preview of the level.
+    pub thumbnail: String,
+    /// Entries holding the files the level uses, at the paths it refers to them by,
+    /// such as `assets/tileset/BG1.png`.
+    #[serde(default)]
+    pub assets: Vec<String>,
+}
+
+/// A level packaged into a single file to share: its map, the files it uses, a
+/// thumbnail, and a manifest describing it.
+///
+/// Bundles are gzip-compressed. Decompressed, they hold the bytes `PLVL`, the format
+/// version and the number of entries as little-endian `u32`s, and then each entry as
+/// its name's length (`u16`), its name, its data's length (`u32`), and its data. The
+/// manifest is the first entry.
+///
+/// Importing a bundle installs it as a mod adding its level to the level-select
+/// menu. Like any mod's files, its assets override the game's at the same paths
+/// while the mod is enabled.
+pub struct LevelBundle {
+    pub manifest: BundleManifest,
+    /// Every entry but the manifest, by name.
+    files: BTreeMap<String, Vec<u8>>,
+}
+
+impl LevelBundle {
+    /// Packages a level with the files it uses and a thumbnail drawn from its tiles.
+    ///
+    /// Any string in the map, such as a property, that names an existing file is
+    /// bundled as an asset; other levels it links to are left out. The name, author,
+    /// and description come from the map's `name`, `author`, and `description`
+    /// properties, with the file's name standing in for a missing name.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The level's Tiled map, e.g. `assets/levels/level1.json`.
+    pub fn export(path: &str) -> Result<Self, BundleError> {
+        info!("Exporting level bundle of '{}'", path);
+        let source = std::fs::read_to_string(resolve(path))?;
+        let tmx = Path::new(path).extension().and_then(|ext| ext.to_str()) == Some("tmx");
+
+        let tileset = image::open(resolve(TILESET_PATH))
+            .map_err(|e| BundleError::Invalid(format!("could not load the tileset: {}", e)))?
+            .to_rgba8();
+        let (columns, rows) = tileset_grid(tileset.dimensions());
+        let level = parse_tiled_map(path, &source, SPRITE_WIDTH, SPRITE_HEIGHT, columns, rows)
+            .map_err(|e| BundleError::Invalid(e.to_string()))?;
+
+        let level_entry = if tmx { "level.tmx" } else { "level.json" };
+        let assets = referenced_files(&source, tmx);
+        let mut files = BTreeMap::new();
+        for asset in &assets {
+            files.insert(asset.clone(), std::fs::read(resolve(asset))?);
+        }
+        files.insert(level_entry.to_string(), source.into_bytes());
+        files.insert(THUMBNAIL_ENTRY.to_string(), draw_thumbnail(&level, &tileset)?);
+
+        let property = |name: &str| level.property(name).unwrap_or_default().to_string();
+        let name = level.property("name").map(str::to_string).unwrap_or_else(|| {
+            Path::new(path)
+                .file_stem()
+                .map(|stem| stem.to_string_lossy().into_owned())
+                .unwrap_or_default()
+        });
+        Ok(Self {
+            manifest: BundleManifest {
+                name,
+                author: property("author"),
+                description: property("description"),
+                level: level_entry.to_string(),
+                thumbnail: THUMBNAIL_ENTRY.to_string(),
+                assets,
+            },
+            files,
+        })
+    }
+
+    /// Writes the bundle to a file.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The file to write, conventionally ending in `.levelpack`.
+    pub fn write(&self, path: &Path) -> Result<(), BundleError> {
+        let manifest = serde_json::to_vec_pretty(&self.manifest).map_err(|e| BundleError::Invalid(e.to_string()))?;
+        let entries = std::iter::once((MANIFEST_ENTRY, manifest.as_slice()))
+            .chain(self.files.iter().map(|(name, data)| (name.as_str(), data.as_slice())));
+
+        let mut encoder = GzEncoder::new(std::fs::File::create(path)?, Compression::default());
+        encoder.write_all(MAGIC)?;
+        encoder.write_all(&FORMAT_VERSION.to_le_bytes())?;
+        encoder.write_all(&(self.files.len() as u32 + 1).to_le_bytes())?;
+        for (name, data) in entries {
+            encoder.write_all(&(name.len() as u16).to_le_bytes())?;
+            encoder.write_all(name.as_bytes())?;
+            encoder.write_all(&(data.len() as u32).to_le_bytes())?;
+            encoder.write_all(data)?;
+        }
+        encoder.finish()?;
+        info!("Wrote level bundle '{}'", path.display());
+        Ok(())
+    }
+
+    /// Reads a bundle from a file and checks it before anything is installed: its
+    /// format, that every entry has a safe relative path and is listed in the
+    /// manifest, that every listed entry is present, that the thumbnail is a PNG,
+    /// and that the level parses.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The bundle file.
+    pub fn read(path: &Path) -> Result<Self, BundleError> {
+        info!("Reading level bundle '{}'", path.display());
+        let mut data = Vec::new();
+        GzDecoder::new(std::fs::File::open(path)?)
+            .take(MAX_BUNDLE_SIZE + 1)
+            .read_to_end(&mut data)
+            .map_err(|e| BundleError::Invalid(format!("corrupt data: {}", e)))?;
+        if data.len() as u64 > MAX_BUNDLE_SIZE {
+            return Err(BundleError::Invalid(format!("larger than {} bytes", MAX_BUNDLE_SIZE)));
+        }
+
+        let mut reader = EntryReader { data: &data };
+        if reader.take(MAGIC.len())? != MAGIC {
+            return Err(BundleError::Invalid("not a level bundle".to_string()));
+        }
+        let version = reader.u32()?;
+        if version != FORMAT_VERSION {
+            return Err(BundleError::Invalid(format!(
+                "format version {} is not supported (expected {})",
+                version, FORMAT_VERSION
+            )));
+        }
+
+        let count = reader.u32()?;
+        let mut files = BTreeMap::new();
+        for _ in 0..count {
+            let name_length = reader.u16()? as usize;
+            let name = std::str::from_utf8(reader.take(name_length)?)
+                .map_err(|_| BundleError::Invalid("an entry's name is not UTF-8".to_string()))?
+                .to_string();
+            if !is_safe_path(&name) {
+                return Err(BundleError::Invalid(format!("entry '{}' is not a safe relative path", name)));
+            }
+            let data_length = reader.u32()? as usize;
+            let data = reader.take(data_length)?.to_vec();
+            if files.insert(name.clone(), data).is_some() {
+                return Err(BundleError::Invalid(format!("entry '{}' appears twice", name)));
+            }
+        }
+        if !reader.data.is_empty() {
+            return Err(BundleError::Invalid("data after the last entry".to_string()));
+        }
+
+        let manifest = files
+            .remove(MANIFEST_ENTRY)
+            .ok_or_else(|| BundleError::Invalid(format!("missing '{}'", MANIFEST_ENTRY)))?;
+        let manifest: BundleManifest = serde_json::from_slice(&manifest)
+            .map_err(|e| BundleError::Invalid(format!("malformed manifest: {}", e)))?;
+        let bundle = Self { manifest, files };
+        bundle.validate()?;
+        Ok(bundle)
+    }
+
+    /// Installs the bundle as a mod in its own folder of the mods directory, adding
+    /// its level to the level-select menu from the next start.
+    ///
+    /// # Arguments
+    ///
+    /// * `mods_dir` - The mods directory.
+    ///
+    /// # Returns
+    ///
+    /// The mod's folder. Fails without writing anything if the folder already exists.
+    pub fn install(&self, mods_dir: &Path) -> Result<PathBuf, BundleError> {
+        let root = mods_dir.join(folder_name(&self.manifest.name));
+        if root.exists() {
+            return Err(BundleError::Invalid(format!("'{}' already exists", root.display())));
+        }
+        for (name, data) in &self.files {
+            let path = root.join(name);
+            if let Some(parent) = path.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+            std::fs::write(path, data)?;
+        }
+        write_manifest(&root, &self.manifest.name, vec![self.manifest.level.clone()])?;
+        info!("Installed level bundle '{}' into '{}'", self.manifest.name, root.display());
+        Ok(root)
+    }
+
+    /// Checks that the manifest and the entries agree and that the level and
+    /// thumbnail can be loaded.
+    fn validate(&self) -> Result<(), BundleError> {
+        let manifest = &self.manifest;
+        if manifest.name.trim().is_empty() {
+            return Err(BundleError::Invalid("the level has no name".to_string()));
+        }
+        let entry = |name: &str| {
+            self.files
+                .get(name)
+                .ok_or_else(|| BundleError::Invalid(format!("missing '{}'", name)))
+        };
+
+        let thumbnail = image::load_from_memory_with_format(entry(&manifest.thumbnail)?, image::ImageFormat::Png)
+            .map_err(|e| BundleError::Invalid(format!("unreadable thumbnail: {}", e)))?;
+        if thumbnail.width() > THUMBNAIL_SIZE || thumbnail.height() > THUMBNAIL_SIZE {
+            return Err(BundleError::Invalid(format!(
+                "thumbnail is larger than {}x{}",
+                THUMBNAIL_SIZE, THUMBNAIL_SIZE
+            )));
+        }
+
+        for asset in &manifest.assets {
+            entry(asset)?;
+        }
+        let listed = |name: &String| {
+            *name == manifest.level || *name == manifest.thumbnail || manifest.assets.contains(name)
+        };
+        if let Some(unlisted) = self.files.keys().find(|name| !listed(name)) {
+            return Err(BundleError::Invalid(format!("entry '{}' is not in the manifest", unlisted)));
+        }
+
+        // Parsed against the tileset the level will be drawn with: its own if bundled
+        let (columns, rows) = match self.files.get(TILESET_PATH) {
+            Some(tileset) => image::load_from_memory(tileset)
+                .map_err(|e| BundleError::Invalid(format!("unreadable tileset: {}", e)))?
+                .dimensions(),
+            None => image::image_dimensions(resolve(TILESET_PATH))
+                .map_err(|e| BundleError::Invalid(format!("could not load the tileset: {}", e)))?,
+        };
+        let (columns, rows) = tileset_grid((columns, rows));
+        let source = std::str::from_utf8(entry(&manifest.level)?)
+            .map_err(|_| BundleError::Invalid("the level is not UTF-8".to_string()))?;
+        parse_tiled_map(&manifest.level, source, SPRITE_WIDTH, SPRITE_HEIGHT, columns, rows)
+            .map_err(|e| BundleError::Invalid(e.to_string()))?;
+        Ok(())
+    }
+}
+
+/// Reads a bundle's entries, failing on truncated data.
+struct EntryReader<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> EntryReader<'a> {
+    fn take(&mut self, length: usize) -> Result<&'a [u8], BundleError> {
+        if length > self.data.len() {
+            return Err(BundleError::Invalid("truncated data".to_string()));
+        }
+        let (taken, rest) = self.data.split_at(length);
+        self.data = rest;
+        Ok(taken)
+    }
+
+    fn u16(&mut self) -> Result<u16, BundleError> {
+        let bytes = self.take(2)?;
+        Ok(u16::from_le_bytes([bytes[0], bytes[1]]))
+    }
+
+    fn u32(&mut self) -> Result<u32, BundleError> {
+        let bytes = self.take(4)?;
+        Ok(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    }
+}
+
+/// Returns whether a path stays inside the folder it's relative to: not absolute,
+/// and without `..` or `.` parts.
+fn is_safe_path(path: &str) -> bool {
+    !path.is_empty()
+        && !path.contains('\\')
+        && Path::new(path)
+            .components()
+            .all(|component| matches!(component, Component::Normal(_)))
+}
+
+/// Returns the columns and rows of tiles in a tileset of the given size.
+fn tileset_grid((width, height): (u32, u32)) -> (usize, usize) {
+    ((width / TILE_PIXEL_SIZE) as usize, (height / TILE_PIXEL_SIZE) as usize)
+}
+
+/// Returns the files a Tiled map refers to: every string in it, such as a property,
+/// that is a safe relative path to an existing file other than a level.
+fn referenced_files(source: &str, tmx: bool) -> Vec<String> {
+    let mut strings = Vec::new();
+    if tmx {
+        if let Ok(document) = roxmltree::Document::parse(source) {
+            for node in document.descendants() {
+                strings.extend(node.attributes().map(|attribute| attribute.value().to_string()));
+                strings.extend(node.text().map(str::to_string));
+            }
+        }
+    } else if let Ok(value) = serde_json::from_str::<serde_json::Value>(source) {
+        collect_strings(&value, &mut strings);
+    }
+
+    let is_level = |path: &str| {
+        matches!(
+            Path::new(path).extension().and_then(|ext| ext.to_str()),
+            Some("json" | "tmj" | "tmx")
+        )
+    };
+    let mut files: Vec<String> = strings
+        .into_iter()
+        .filter(|string| is_safe_path(string) && !is_level(string) && resolve(string).is_file())
+        .collect();
+    files.sort();
+    files.dedup();
+    files
+}
+
+/// Gathers every string value in a JSON document.
+fn collect_strings(value: &serde_json::Value, strings: &mut Vec<String>) {
+    match value {
+        serde_json::Value::String(string) => strings.push(string.clone()),
+        serde_json::Value::Array(values) => values.iter().for_each(|value| collect_strings(value, strings)),
+        serde_json::Value::Object(fields) => fields.values().for_each(|value| collect_strings(value, strings)),
+        _ => {}
+    }
+}
+
+/// Draws a level's tiles from the tileset and shrinks the result to a thumbnail.
+///
+/// # Returns
+///
+/// The thumbnail, encoded as a PNG.
+fn draw_thumbnail(level: &Level, tileset: &RgbaImage) -> Result<Vec<u8>, BundleError> {
+    let tile_map = &level.tile_map;
+    let tiles = || tile_map.layers.iter().flat_map(|layer| &layer.tiles);
+    let (mut min_x, mut max_x, mut min_y, mut max_y) = (f32::MAX, f32::MIN, f32::MAX, f32::MIN);
+    for tile in tiles() {
+        let bounds = tile.bounds();
+        min_x = min_x.min(bounds.min_x);
+        max_x = max_x.max(bounds.max_x);
+        min_y = min_y.min(bounds.min_y);
+        max_y = max_y.max(bounds.max_y);
+    }
+
+    // One tileset pixel per pixel, from the top-left of the tiles
+    let to_pixels = |distance: f32, tile_size: f32| (distance / tile_size * TILE_PIXEL_SIZE as f32).round();
+    let canvas_size = |distance: f32, tile_size: f32| (to_pixels(distance, tile_size) as u32).max(1);
+    let mut canvas = if min_x <= max_x {
+        RgbaImage::new(
+            canvas_size(max_x - min_x, tile_map.tile_width),
+            canvas_size(max_y - min_y, tile_map.tile_height),
+        )
+    } else {
+        RgbaImage::new(1, 1)
+    };
+    for tile in tiles() {
+        let column = (tile.tile_index % tile_map.tileset_columns.max(1)) as u32;
+        let row = (tile.tile_index / tile_map.tileset_columns.max(1)) as u32;
+        let (span_columns, span_rows) = (tile.span.0 as u32, tile.span.1 as u32);
+        if (column + span_columns) * TILE_PIXEL_SIZE > tileset.width()
+            || (row + span_rows) * TILE_PIXEL_SIZE > tileset.height()
+        {
+            continue;
+        }
+        let art = tileset
+            .view(
+                column * TILE_PIXEL_SIZE,
+                row * TILE_PIXEL_SIZE,
+                span_columns * TILE_PIXEL_SIZE,
+                span_rows * TILE_PIXEL_SIZE,
+            )
+            .to_image();
+        let art = imageops::resize(
+            &art,
+            canvas_size(tile.size.0, tile_map.tile_width),
+            canvas_size(tile.size.1, tile_map.tile_height),
+            imageops::FilterType::Nearest,
+        );
+        let bounds = tile.bounds();
+        imageops::overlay(
+            &mut canvas,
+            &art,
+            to_pixels(bounds.min_x - min_x, tile_map.tile_width) as i64,
+            to_pixels(max_y - bounds.max_y, tile_map.tile_height) as i64,
+        );
+    }
+
+    let thumbnail = image::DynamicImage::ImageRgba8(canvas).thumbnail(THUMBNAIL_SIZE, THUMBNAIL_SIZE);
+    let mut png = Vec::new();
+    thumbnail
+        .write_to(&mut Cursor::new(&mut png), image::ImageOutputFormat::Png)
+        .map_err(|e| BundleError::Invalid(format!("could not encode the thumbnail: {}", e)))?;
+    Ok(png)
+}
+
+/// Returns the mods directory folder to install a level under: its name in lowercase,
+/// with anything but letters and digits replaced by underscores.
+fn folder_name(name: &str) -> String {
+    let folder: String = name
+        .trim()
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c.to_ascii_lowercase() } else { '_' })
+        .collect();
+    if folder.is_empty() {
+        "level".to_string()
+    } else {
+        folder
+    }
+}
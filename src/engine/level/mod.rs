@@ -0,0 +1,229 @@
+// src/engine/level/mod.rs
+
+pub mod bundle;
+pub mod tiled;
+
+pub use tiled::load_tiled_map;
+
+use crate::engine::collision::Aabb;
+use crate::engine::renderer::tile::TileMap;
+use log::warn;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt;
+
+/// A loaded level: its tiles plus the objects placed in the editor.
+pub struct Level {
+    /// Path the level was loaded from, empty for generated levels.
+    pub path: String,
+    pub tile_map: TileMap,
+    /// Tiles drawn in front of the player, such as secret-area covers.
+    pub foreground: TileMap,
+    /// Cells filled with water, drawn with the water material.
+    pub water: TileMap,
+    pub objects: Vec<LevelObject>,
+    /// Custom properties set on the map itself, such as its `name`.
+    pub properties: HashMap<String, String>,
+}
+
+/// An object placed on one of the map's object layers (spawn points, triggers, rooms...).
+pub struct LevelObject {
+    /// The object's Tiled id, unique within its map and kept as the map is edited.
+    pub id: u32,
+    /// Name given to the object in the editor.
+    pub name: String,
+    /// The object's class (Tiled's `type`/`class` field), used to decide what it spawns.
+    pub kind: String,
+    /// Area covered by the object in world coordinates. Point objects have zero size.
+    pub bounds: Aabb,
+    /// Index of the tileset tile shown by a tile object, placed in the editor by
+    /// dragging a tile onto an object layer. `None` for other objects.
+    pub tile: Option<usize>,
+    /// Custom properties, stored as their string representation.
+    pub properties: HashMap<String, String>,
+}
+
+impl LevelObject {
+    /// Returns a custom property as a string, if present.
+    pub fn property(&self, name: &str) -> Option<&str> {
+        self.properties.get(name).map(String::as_str)
+    }
+
+    /// Returns a custom property parsed as a number, or `default` if it is missing or invalid.
+    pub fn property_f32(&self, name: &str, default: f32) -> f32 {
+        self.property(name)
+            .and_then(|value| value.parse().ok())
+            .unwrap_or(default)
+    }
+
+    /// Returns the index of the tileset tile the object is drawn with: the tile of a
+    /// tile object, or else its `tile` property, defaulting to the first tile.
+    pub fn tile_index(&self) -> usize {
+        self.tile.unwrap_or_else(|| self.property_f32("tile", 0.0) as usize)
+    }
+
+    /// Returns the columns and rows of tileset tiles making up the object's art, from
+    /// its `tile_columns` and `tile_rows` properties. Art bigger than one tile starts
+    /// at `tile_index` and extends right and down the tileset.
+    pub fn tile_span(&self) -> (usize, usize) {
+        let count = |name: &str| (self.property_f32(name, 1.0) as usize).max(1);
+        (count("tile_columns"), count("tile_rows"))
+    }
+
+    /// Returns how the object's art is turned, counterclockwise: its tilt from its
+    /// `rotation` property in degrees, and how fast it spins from its `spin` property
+    /// in degrees per second, as radians and radians per second.
+    pub fn art_rotation(&self) -> (f32, f32) {
+        (
+            self.property_f32("rotation", 0.0).to_radians(),
+            self.property_f32("spin", 0.0).to_radians(),
+        )
+    }
+
+    /// Returns the center of the object's area.
+    pub fn center(&self) -> (f32, f32) {
+        (
+            (self.bounds.min_x + self.bounds.max_x) / 2.0,
+            (self.bounds.min_y + self.bounds.max_y) / 2.0,
+        )
+    }
+}
+
+impl Level {
+    /// Creates a level with only a flat strip of ground tiles and no objects.
+    pub fn flat_ground(
+        tile_width: f32,
+        tile_height: f32,
+        tileset_columns: usize,
+        tileset_rows: usize,
+    ) -> Self {
+        let empty = || {
+            TileMap::from_layers(tile_width, tile_height, tileset_columns, tileset_rows, 0, 0, &[])
+        };
+
+        Self {
+            tile_map: TileMap::new_ground(tile_width, tile_height, tileset_columns, tileset_rows),
+            foreground: empty(),
+            water: empty(),
+            path: String::new(),
+            objects: Vec::new(),
+            properties: HashMap::new(),
+        }
+    }
+
+    /// Returns a custom property of the map, if present.
+    pub fn property(&self, name: &str) -> Option<&str> {
+        self.properties.get(name).map(String::as_str)
+    }
+
+    /// Returns all objects of the given class, in file order.
+    pub fn objects_of_kind<'a>(&'a self, kind: &'a str) -> impl Iterator<Item = &'a LevelObject> {
+        self.objects.iter().filter(move |object| object.kind == kind)
+    }
+
+    /// Returns the objects covering a point in world coordinates, in file order.
+    #[cfg_attr(not(debug_assertions), allow(dead_code))]
+    pub fn objects_at(&self, x: f32, y: f32) -> impl Iterator<Item = &LevelObject> {
+        self.objects.iter().filter(move |object| object.bounds.contains(x, y))
+    }
+
+    /// Returns the first object with the given name, of any class.
+    pub fn object_named(&self, name: &str) -> Option<&LevelObject> {
+        self.objects.iter().find(|object| object.name == name)
+    }
+
+    /// Returns the object with the given Tiled id.
+    pub fn object(&self, id: u32) -> Option<&LevelObject> {
+        self.objects.iter().find(|object| object.id == id)
+    }
+
+    /// Returns the identity of one of this level's objects across levels and saves.
+    pub fn object_id(&self, object: &LevelObject) -> ObjectId {
+        ObjectId {
+            level: self.path.clone(),
+            object: object.id,
+        }
+    }
+
+    /// Resolves a property of an object that refers to another object.
+    ///
+    /// The property holds either a Tiled object id, as written by Tiled's object
+    /// properties, or an object name, both referring to this level. Objects in
+    /// other levels are written as `path#id`, such as `assets/levels/level2.json#7`.
+    ///
+    /// # Arguments
+    ///
+    /// * `object` - The object whose property to read.
+    /// * `name` - The property holding the reference.
+    ///
+    /// # Returns
+    ///
+    /// The referenced object's identity, or `None` if the property is missing or names
+    /// no object in this level.
+    pub fn reference(&self, object: &LevelObject, name: &str) -> Option<ObjectId> {
+        let value = object.property(name)?;
+        if value.contains('#') {
+            return value
+                .parse()
+                .map_err(|e| warn!("Object '{}' has an invalid reference: {}", object.name, e))
+                .ok();
+        }
+
+        let target = match value.parse::<u32>() {
+            Ok(id) => self.object(id),
+            Err(_) => self.object_named(value),
+        };
+        if target.is_none() {
+            warn!("Object '{}' refers to missing object '{}'", object.name, value);
+        }
+        target.map(|target| self.object_id(target))
+    }
+}
+
+/// Identifies a level object across levels and saves: the path of its level
+/// plus its Tiled id, written as `path#id`.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(into = "String", try_from = "String")]
+pub struct ObjectId {
+    /// Path of the level the object is placed in.
+    pub level: String,
+    /// The object's Tiled id within that level.
+    pub object: u32,
+}
+
+impl fmt::Display for ObjectId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}#{}", self.level, self.object)
+    }
+}
+
+impl std::str::FromStr for ObjectId {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let (level, object) = value
+            .rsplit_once('#')
+            .ok_or_else(|| format!("object id '{}' is missing a '#'", value))?;
+        let object = object
+            .parse()
+            .map_err(|_| format!("object id '{}' must end in a number", value))?;
+        Ok(Self {
+            level: level.to_string(),
+            object,
+        })
+    }
+}
+
+impl From<ObjectId> for String {
+    fn from(id: ObjectId) -> Self {
+        id.to_string()
+    }
+}
+
+impl TryFrom<String> for ObjectId {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
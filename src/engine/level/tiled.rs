@@ -0,0 +1,607 @@
+// tiled.rs
+use crate::engine::collision::Aabb;
+use crate::engine::level::{Level, LevelObject};
+use crate::engine::mods::resolve;
+use crate::engine::renderer::autotile::AutotileRules;
+use crate::engine::renderer::tile::{Tile, TileLayer, TileMap};
+use log::{info, warn};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::fmt;
+use std::path::Path;
+
+/// Tiled stores flip/rotation flags in the top bits of every global tile id.
+const GID_FLAGS_MASK: u32 = 0xF000_0000;
+
+/// Errors that can occur while loading a Tiled map.
+#[derive(Debug)]
+pub enum LevelError {
+    /// The map file could not be read.
+    Io(std::io::Error),
+    /// The map file is not valid Tiled JSON or TMX.
+    Parse(String),
+    /// The map uses a Tiled feature this loader does not handle.
+    Unsupported(String),
+}
+
+impl fmt::Display for LevelError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LevelError::Io(e) => write!(f, "failed to read map: {}", e),
+            LevelError::Parse(msg) => write!(f, "failed to parse map: {}", msg),
+            LevelError::Unsupported(msg) => write!(f, "unsupported map feature: {}", msg),
+        }
+    }
+}
+
+impl std::error::Error for LevelError {}
+
+impl From<std::io::Error> for LevelError {
+    fn from(e: std::io::Error) -> Self {
+        LevelError::Io(e)
+    }
+}
+
+/// A tile layer as it appears in the map file, before gids are resolved.
+struct RawLayer {
+    name: String,
+    gids: Vec<u32>,
+    properties: HashMap<String, String>,
+}
+
+/// An object as it appears in the map file, in pixel coordinates from the top-left.
+struct RawObject {
+    id: u32,
+    name: String,
+    kind: String,
+    /// Global tile id shown by a tile object, flags included.
+    gid: Option<u32>,
+    x: f32,
+    y: f32,
+    width: f32,
+    height: f32,
+    properties: HashMap<String, String>,
+}
+
+/// The parts of a Tiled map needed to build a `Level`.
+struct RawMap {
+    width: usize,
+    height: usize,
+    tile_pixel_width: f32,
+    tile_pixel_height: f32,
+    first_gid: u32,
+    layers: Vec<RawLayer>,
+    objects: Vec<RawObject>,
+    properties: HashMap<String, String>,
+}
+
+/// Loads a Tiled map export (`.json`/`.tmj` or `.tmx`) into a `Level`.
+///
+/// All visible tile layers (including those nested in group layers) become
+/// named layers of the tile map, kept in file order so later layers draw over
+/// earlier ones. Tile ids are resolved against the
+/// map's first tileset, which is expected to be the renderer's tileset image.
+/// Tile layers with a `foreground` property set to `true` go into the level's
+/// foreground map, which is drawn in front of the player, and layers with a
+/// `water` property go into its water map. A layer's `z` property draws it at
+/// that depth instead of stacked at its map's, and `solid` set to `false` makes
+/// it decoration that projectiles pass through. A layer's `autotile` property
+/// names the `AutotileRules` its terrain tiles are repicked by, so painting with
+/// any of a terrain's tiles gets the right edges and corners, and `decorate` set to
+/// `false` keeps scattered decorations off its surfaces. Objects from visible
+/// object layers are converted to world coordinates and keep their Tiled ids,
+/// which stay the same as the map is edited, and the map's own custom
+/// properties become the level's properties.
+///
+/// Tile objects without a class are added to the tile map as oversized tiles,
+/// such as large decorations or 2x2 blocks, stretched over the object's area and
+/// solid across all of it; their art spans `tile_columns` by `tile_rows` tileset
+/// tiles. With `foreground` set they go into the foreground map instead. Tile
+/// objects with a class spawn entities that are drawn with the placed tile.
+///
+/// # Arguments
+/// - `path`: Path to the map file; the extension selects the format.
+/// - `tile_width`, `tile_height`: World-space size of a single tile.
+/// - `tileset_columns`, `tileset_rows`: Grid size of the loaded tileset texture.
+///
+/// # Returns
+/// The populated `Level`, or a `LevelError` describing why the map was rejected.
+pub fn load_tiled_map(
+    path: &str,
+    tile_width: f32,
+    tile_height: f32,
+    tileset_columns: usize,
+    tileset_rows: usize,
+) -> Result<Level, LevelError> {
+    info!("Loading Tiled map: {}", path);
+    let source = std::fs::read_to_string(resolve(path))?;
+    parse_tiled_map(path, &source, tile_width, tile_height, tileset_columns, tileset_rows)
+}
+
+/// Builds a `Level` from a Tiled map's contents, as `load_tiled_map` does after
+/// reading the file, such as for a level held in a bundle.
+///
+/// # Arguments
+/// - `path`: Path the level is known by; the extension selects the format.
+/// - `source`: The map file's contents.
+/// - `tile_width`, `tile_height`: World-space size of a single tile.
+/// - `tileset_columns`, `tileset_rows`: Grid size of the loaded tileset texture.
+pub fn parse_tiled_map(
+    path: &str,
+    source: &str,
+    tile_width: f32,
+    tile_height: f32,
+    tileset_columns: usize,
+    tileset_rows: usize,
+) -> Result<Level, LevelError> {
+    let raw = match Path::new(path).extension().and_then(|ext| ext.to_str()) {
+        Some("tmx") => parse_tmx(source)?,
+        _ => parse_json(source)?,
+    };
+
+    let tile_count = tileset_columns * tileset_rows;
+    let mut layers = Vec::with_capacity(raw.layers.len());
+    let mut foreground_layers = Vec::new();
+    let mut water_layers = Vec::new();
+
+    for layer in &raw.layers {
+        if layer.gids.len() != raw.width * raw.height {
+            return Err(LevelError::Parse(format!(
+                "layer '{}' has {} cells, expected {}",
+                layer.name,
+                layer.gids.len(),
+                raw.width * raw.height
+            )));
+        }
+
+        let cells = layer
+            .gids
+            .iter()
+            .map(|&gid| {
+                let gid = gid & !GID_FLAGS_MASK;
+                if gid == 0 {
+                    return None;
+                }
+                let index = gid.checked_sub(raw.first_gid)? as usize;
+                if index >= tile_count {
+                    warn!("Tile id {} in layer '{}' is outside the tileset", gid, layer.name);
+                    return None;
+                }
+                Some(index)
+            })
+            .collect();
+
+        let flag = |name: &str| layer.properties.get(name).map(String::as_str) == Some("true");
+        if flag("foreground") {
+            foreground_layers.push((layer, cells));
+        } else if flag("water") {
+            water_layers.push((layer, cells));
+        } else {
+            layers.push((layer, cells));
+        }
+    }
+
+    // The map is centered horizontally with its bottom edge on the bottom of the screen
+    let left = -(raw.width as f32 * tile_width) / 2.0 - tile_width / 2.0;
+    let top = -1.0 + raw.height as f32 * tile_height;
+    let to_world_x = |px: f32| left + px / raw.tile_pixel_width * tile_width;
+    let to_world_y = |py: f32| top - py / raw.tile_pixel_height * tile_height;
+
+    let objects = raw
+        .objects
+        .into_iter()
+        .map(|object| {
+            let tile = object.gid.and_then(|gid| {
+                let index = (gid & !GID_FLAGS_MASK).checked_sub(raw.first_gid)? as usize;
+                if index >= tile_count {
+                    warn!("Tile object {} shows tile id {}, outside the tileset", object.id, gid);
+                    return None;
+                }
+                Some(index)
+            });
+            // Tile objects are positioned by their bottom-left corner
+            let top = if object.gid.is_some() { object.y - object.height } else { object.y };
+            LevelObject {
+                id: object.id,
+                bounds: Aabb {
+                    min_x: to_world_x(object.x),
+                    min_y: to_world_y(top + object.height),
+                    max_x: to_world_x(object.x + object.width),
+                    max_y: to_world_y(top),
+                },
+                tile,
+                name: object.name,
+                kind: object.kind,
+                properties: object.properties,
+            }
+        })
+        .collect::<Vec<_>>();
+
+    let build = |layers: Vec<(&RawLayer, Vec<Option<usize>>)>| {
+        let (sources, grids): (Vec<_>, Vec<_>) = layers
+            .into_iter()
+            .map(|(source, cells)| (source, (source.name.clone(), cells)))
+            .unzip();
+        let mut tile_map = TileMap::from_layers(
+            tile_width,
+            tile_height,
+            tileset_columns,
+            tileset_rows,
+            raw.width,
+            raw.height,
+            &grids,
+        );
+        // Give each layer the depth, solidity, autotiling, and decoration set on it in the editor
+        for (layer, source) in tile_map.layers.iter_mut().zip(sources) {
+            layer.z = source.properties.get("z").and_then(|z| z.parse().ok());
+            layer.solid = source.properties.get("solid").map(String::as_str) != Some("false");
+            layer.decorate = source.properties.get("decorate").map(String::as_str) != Some("false");
+            layer.autotile = source.properties.get("autotile").and_then(|name| {
+                let rules = AutotileRules::named(name);
+                if rules.is_none() {
+                    warn!("Layer '{}' asks for unknown autotile rules '{}'", source.name, name);
+                }
+                rules
+            });
+        }
+        tile_map.autotile();
+        tile_map
+    };
+
+    let mut tile_map = build(layers);
+    let mut foreground = build(foreground_layers);
+
+    // Oversized tiles placed as objects go in a layer of their own, in front of the rest
+    let mut tile_objects = Vec::new();
+    let mut foreground_tile_objects = Vec::new();
+    for object in objects.iter().filter(|object| object.kind.is_empty()) {
+        let Some(tile_index) = object.tile else {
+            continue;
+        };
+        let tile = Tile {
+            tile_index,
+            position: object.center(),
+            size: (object.bounds.max_x - object.bounds.min_x, object.bounds.max_y - object.bounds.min_y),
+            span: object.tile_span(),
+            shade: 1.0,
+        };
+        if object.property("foreground") == Some("true") {
+            foreground_tile_objects.push(tile);
+        } else {
+            tile_objects.push(tile);
+        }
+    }
+    for (map, tiles) in [(&mut tile_map, tile_objects), (&mut foreground, foreground_tile_objects)] {
+        if !tiles.is_empty() {
+            map.layers.push(TileLayer::new("tile objects", tiles));
+        }
+    }
+
+    let water = build(water_layers);
+    let layer_names: Vec<&str> = [&tile_map, &foreground, &water]
+        .into_iter()
+        .flat_map(|map| map.layers.iter().map(|layer| layer.name.as_str()))
+        .collect();
+    let solid_layers = || tile_map.layers.iter().filter(|layer| layer.solid);
+    info!(
+        "Loaded tile layers [{}] and {} object(s) from {}; {} solid tile(s) merged into {} collision box(es)",
+        layer_names.join(", "),
+        objects.len(),
+        path,
+        solid_layers().map(|layer| layer.tiles.len()).sum::<usize>(),
+        solid_layers().map(|layer| layer.colliders().len()).sum::<usize>()
+    );
+
+    Ok(Level {
+        path: path.to_string(),
+        tile_map,
+        foreground,
+        water,
+        objects,
+        properties: raw.properties,
+    })
+}
+
+#[derive(Deserialize)]
+struct JsonMap {
+    width: usize,
+    height: usize,
+    tilewidth: f32,
+    tileheight: f32,
+    #[serde(default)]
+    infinite: bool,
+    layers: Vec<JsonLayer>,
+    tilesets: Vec<JsonTileset>,
+    #[serde(default)]
+    properties: Vec<JsonProperty>,
+}
+
+#[derive(Deserialize)]
+struct JsonLayer {
+    #[serde(default)]
+    name: String,
+    #[serde(rename = "type")]
+    kind: String,
+    #[serde(default = "default_visible")]
+    visible: bool,
+    #[serde(default)]
+    data: Option<serde_json::Value>,
+    #[serde(default)]
+    layers: Vec<JsonLayer>,
+    #[serde(default)]
+    objects: Vec<JsonObject>,
+    #[serde(default)]
+    properties: Vec<JsonProperty>,
+}
+
+#[derive(Deserialize)]
+struct JsonObject {
+    #[serde(default)]
+    id: u32,
+    #[serde(default)]
+    name: String,
+    // Tiled 1.9 renamed `type` to `class`; accept either.
+    #[serde(default, rename = "type")]
+    kind: String,
+    #[serde(default)]
+    class: String,
+    #[serde(default)]
+    gid: Option<u32>,
+    x: f32,
+    y: f32,
+    #[serde(default)]
+    width: f32,
+    #[serde(default)]
+    height: f32,
+    #[serde(default)]
+    properties: Vec<JsonProperty>,
+}
+
+#[derive(Deserialize)]
+struct JsonProperty {
+    name: String,
+    value: serde_json::Value,
+}
+
+#[derive(Deserialize)]
+struct JsonTileset {
+    firstgid: u32,
+}
+
+fn default_visible() -> bool {
+    true
+}
+
+/// Parses a map exported with Tiled's JSON format.
+fn parse_json(source: &str) -> Result<RawMap, LevelError> {
+    let map: JsonMap =
+        serde_json::from_str(source).map_err(|e| LevelError::Parse(e.to_string()))?;
+
+    if map.infinite {
+        return Err(LevelError::Unsupported("infinite maps".to_string()));
+    }
+
+    let mut layers = Vec::new();
+    let mut objects = Vec::new();
+    collect_json_layers(&map.layers, &mut layers, &mut objects)?;
+
+    Ok(RawMap {
+        width: map.width,
+        height: map.height,
+        tile_pixel_width: map.tilewidth,
+        tile_pixel_height: map.tileheight,
+        first_gid: first_gid(map.tilesets.iter().map(|t| t.firstgid)),
+        layers,
+        objects,
+        properties: json_properties(&map.properties),
+    })
+}
+
+/// Flattens visible tile and object layers, descending into group layers.
+fn collect_json_layers(
+    source: &[JsonLayer],
+    layers: &mut Vec<RawLayer>,
+    objects: &mut Vec<RawObject>,
+) -> Result<(), LevelError> {
+    for layer in source.iter().filter(|layer| layer.visible) {
+        match layer.kind.as_str() {
+            "tilelayer" => {
+                let gids = match &layer.data {
+                    Some(serde_json::Value::Array(values)) => values
+                        .iter()
+                        .map(|v| v.as_u64().map(|gid| gid as u32))
+                        .collect::<Option<Vec<_>>>()
+                        .ok_or_else(|| {
+                            LevelError::Parse(format!("layer '{}' has non-numeric tile data", layer.name))
+                        })?,
+                    _ => {
+                        return Err(LevelError::Unsupported(format!(
+                            "layer '{}' must use CSV (array) encoding",
+                            layer.name
+                        )))
+                    }
+                };
+                layers.push(RawLayer {
+                    name: layer.name.clone(),
+                    gids,
+                    properties: json_properties(&layer.properties),
+                });
+            }
+            "objectgroup" => {
+                objects.extend(layer.objects.iter().map(|object| RawObject {
+                    id: object.id,
+                    name: object.name.clone(),
+                    kind: if object.class.is_empty() {
+                        object.kind.clone()
+                    } else {
+                        object.class.clone()
+                    },
+                    gid: object.gid,
+                    x: object.x,
+                    y: object.y,
+                    width: object.width,
+                    height: object.height,
+                    properties: json_properties(&object.properties),
+                }));
+            }
+            "group" => collect_json_layers(&layer.layers, layers, objects)?,
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+/// Converts Tiled JSON custom properties to their string representation.
+fn json_properties(properties: &[JsonProperty]) -> HashMap<String, String> {
+    properties
+        .iter()
+        .map(|property| {
+            let value = match &property.value {
+                serde_json::Value::String(value) => value.clone(),
+                value => value.to_string(),
+            };
+            (property.name.clone(), value)
+        })
+        .collect()
+}
+
+/// Parses a map saved in Tiled's native TMX (XML) format.
+fn parse_tmx(source: &str) -> Result<RawMap, LevelError> {
+    let document =
+        roxmltree::Document::parse(source).map_err(|e| LevelError::Parse(e.to_string()))?;
+    let map = document.root_element();
+
+    if map.attribute("infinite") == Some("1") {
+        return Err(LevelError::Unsupported("infinite maps".to_string()));
+    }
+
+    let width = parse_attribute(&map, "width")?;
+    let height = parse_attribute(&map, "height")?;
+    let tile_pixel_width = parse_attribute(&map, "tilewidth")?;
+    let tile_pixel_height = parse_attribute(&map, "tileheight")?;
+
+    let first_gids = map
+        .children()
+        .filter(|node| node.has_tag_name("tileset"))
+        .map(|node| parse_attribute::<u32>(&node, "firstgid"))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let mut layers = Vec::new();
+    let mut objects = Vec::new();
+    collect_tmx_layers(map, &mut layers, &mut objects)?;
+
+    Ok(RawMap {
+        width,
+        height,
+        tile_pixel_width,
+        tile_pixel_height,
+        first_gid: first_gid(first_gids.into_iter()),
+        layers,
+        objects,
+        properties: tmx_properties(&map),
+    })
+}
+
+/// Flattens visible `<layer>` and `<objectgroup>` elements, descending into `<group>` elements.
+fn collect_tmx_layers(
+    parent: roxmltree::Node,
+    layers: &mut Vec<RawLayer>,
+    objects: &mut Vec<RawObject>,
+) -> Result<(), LevelError> {
+    for node in parent.children().filter(|node| node.is_element()) {
+        if node.attribute("visible") == Some("0") {
+            continue;
+        }
+
+        match node.tag_name().name() {
+            "layer" => {
+                let name = node.attribute("name").unwrap_or_default().to_string();
+                let data = node
+                    .children()
+                    .find(|child| child.has_tag_name("data"))
+                    .ok_or_else(|| LevelError::Parse(format!("layer '{}' has no data", name)))?;
+
+                if data.attribute("encoding") != Some("csv") {
+                    return Err(LevelError::Unsupported(format!(
+                        "layer '{}' must use CSV encoding",
+                        name
+                    )));
+                }
+
+                let gids = data
+                    .text()
+                    .unwrap_or_default()
+                    .split(',')
+                    .map(str::trim)
+                    .filter(|value| !value.is_empty())
+                    .map(|value| value.parse::<u32>())
+                    .collect::<Result<Vec<_>, _>>()
+                    .map_err(|e| LevelError::Parse(format!("layer '{}': {}", name, e)))?;
+
+                layers.push(RawLayer {
+                    name,
+                    gids,
+                    properties: tmx_properties(&node),
+                });
+            }
+            "objectgroup" => {
+                for object in node.children().filter(|child| child.has_tag_name("object")) {
+                    let properties = tmx_properties(&object);
+
+                    objects.push(RawObject {
+                        id: object.attribute("id").and_then(|v| v.parse().ok()).unwrap_or(0),
+                        name: object.attribute("name").unwrap_or_default().to_string(),
+                        kind: object
+                            .attribute("class")
+                            .or_else(|| object.attribute("type"))
+                            .unwrap_or_default()
+                            .to_string(),
+                        gid: object.attribute("gid").and_then(|v| v.parse().ok()),
+                        x: parse_attribute(&object, "x")?,
+                        y: parse_attribute(&object, "y")?,
+                        width: object.attribute("width").and_then(|v| v.parse().ok()).unwrap_or(0.0),
+                        height: object.attribute("height").and_then(|v| v.parse().ok()).unwrap_or(0.0),
+                        properties,
+                    });
+                }
+            }
+            "group" => collect_tmx_layers(node, layers, objects)?,
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+/// Reads the `<properties>` child of a TMX element into a map of strings.
+fn tmx_properties(node: &roxmltree::Node) -> HashMap<String, String> {
+    node.children()
+        .filter(|child| child.has_tag_name("properties"))
+        .flat_map(|properties| properties.children())
+        .filter(|property| property.has_tag_name("property"))
+        .filter_map(|property| {
+            let value = property.attribute("value").or_else(|| property.text())?;
+            Some((property.attribute("name")?.to_string(), value.to_string()))
+        })
+        .collect()
+}
+
+fn parse_attribute<T: std::str::FromStr>(node: &roxmltree::Node, name: &str) -> Result<T, LevelError> {
+    node.attribute(name)
+        .and_then(|value| value.parse().ok())
+        .ok_or_else(|| {
+            LevelError::Parse(format!(
+                "<{}> is missing a valid '{}' attribute",
+                node.tag_name().name(),
+                name
+            ))
+        })
+}
+
+/// Returns the first gid of the first tileset, warning when extra tilesets are ignored.
+fn first_gid(mut first_gids: impl Iterator<Item = u32>) -> u32 {
+    let first = first_gids.next().unwrap_or(1);
+    if first_gids.next().is_some() {
+        warn!("Map references multiple tilesets; only the first is used");
+    }
+    first
+}
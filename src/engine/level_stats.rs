@@ -0,0 +1,164 @@
+// level_stats.rs
+use crate::engine::save::SaveError;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::Path;
+
+/// What the player has done in a level, over every attempt at it.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct LevelStats {
+    /// Fastest the level has been finished, in seconds of game time, if it has been.
+    #[serde(default)]
+    pub best_time: Option<f32>,
+    /// Lives lost in the level.
+    #[serde(default)]
+    pub deaths: u32,
+    /// Names of the level's collectibles picked up on any attempt.
+    #[serde(default)]
+    pub found: BTreeSet<String>,
+    /// Collectibles placed in the level, as of the last time it was played.
+    #[serde(default)]
+    pub collectibles: usize,
+}
+
+impl LevelStats {
+    /// Returns whether the level has been finished.
+    pub fn completed(&self) -> bool {
+        self.best_time.is_some()
+    }
+
+    /// Returns how much of the level has been done, from 0.0 to 1.0, with finishing it
+    /// counting as much as each collectible found.
+    pub fn completion(&self) -> f32 {
+        let done = self.completed() as usize + self.found.len().min(self.collectibles);
+        done as f32 / (1 + self.collectibles) as f32
+    }
+}
+
+/// The current run through a level, from its start to its exit.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct LevelAttempt {
+    /// Game time spent in the level, in seconds, including lives lost.
+    pub time: f32,
+    /// Lives lost in the level on this run.
+    pub deaths: u32,
+    /// Whether the run was picked up partway through from a save, so its time can't
+    /// be the level's best.
+    pub resumed: bool,
+}
+
+/// What the level-complete screen shows of a finished run.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct LevelSummary {
+    /// Game time the run took, in seconds.
+    pub time: f32,
+    /// Fastest the level has been finished, counting this run.
+    pub best_time: f32,
+    /// Whether this run set the best time.
+    pub new_best: bool,
+    /// Lives lost on this run.
+    pub deaths: u32,
+    /// The level's collectibles found on any attempt, and how many there are.
+    pub found: usize,
+    pub collectibles: usize,
+    /// How much of the level has been done, from 0.0 to 1.0.
+    pub completion: f32,
+}
+
+/// The statistics of every level played, by level path, kept in their own save file
+/// so that loading an older save doesn't take back what was done since.
+#[derive(Default, Serialize, Deserialize)]
+pub struct LevelRecords {
+    levels: BTreeMap<String, LevelStats>,
+}
+
+impl LevelRecords {
+    /// Reads the records from a save file.
+    ///
+    /// # Returns
+    ///
+    /// The records, or empty ones if no file has been written yet.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, SaveError> {
+        let source = match std::fs::read_to_string(path) {
+            Ok(source) => source,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Self::default()),
+            Err(e) => return Err(e.into()),
+        };
+        serde_json::from_str(&source).map_err(|e| SaveError::Parse(e.to_string()))
+    }
+
+    /// Writes the records to a save file, creating its directory if needed.
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), SaveError> {
+        let json = serde_json::to_string_pretty(self).map_err(|e| SaveError::Parse(e.to_string()))?;
+
+        let path = path.as_ref();
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(path, json)?;
+        Ok(())
+    }
+
+    /// Returns a level's statistics, if it has been played.
+    pub fn get(&self, level: &str) -> Option<&LevelStats> {
+        self.levels.get(level)
+    }
+
+    /// Notes how many collectibles a level has, as it starts.
+    ///
+    /// # Arguments
+    ///
+    /// * `level` - Path of the level.
+    /// * `collectibles` - Collectibles placed in the level.
+    pub fn start(&mut self, level: &str, collectibles: usize) {
+        self.entry(level).collectibles = collectibles;
+    }
+
+    /// Counts a life lost in a level.
+    pub fn record_death(&mut self, level: &str) {
+        self.entry(level).deaths += 1;
+    }
+
+    /// Notes a collectible picked up in a level.
+    ///
+    /// # Arguments
+    ///
+    /// * `level` - Path of the level.
+    /// * `name` - The collectible's name.
+    pub fn record_found(&mut self, level: &str, name: &str) {
+        self.entry(level).found.insert(name.to_string());
+    }
+
+    /// Records a level as finished by a run, keeping its time if it's the fastest and
+    /// the run wasn't resumed from a save.
+    ///
+    /// # Arguments
+    ///
+    /// * `level` - Path of the level.
+    /// * `attempt` - The run that reached the exit.
+    ///
+    /// # Returns
+    ///
+    /// The run's summary, for the level-complete screen.
+    pub fn record_completion(&mut self, level: &str, attempt: &LevelAttempt) -> LevelSummary {
+        let stats = self.entry(level);
+        let new_best = !attempt.resumed && stats.best_time.is_none_or(|best| attempt.time < best);
+        if new_best {
+            stats.best_time = Some(attempt.time);
+        }
+        LevelSummary {
+            time: attempt.time,
+            best_time: stats.best_time.unwrap_or(attempt.time),
+            new_best,
+            deaths: attempt.deaths,
+            found: stats.found.len(),
+            collectibles: stats.collectibles,
+            completion: stats.completion(),
+        }
+    }
+
+    /// Returns a level's statistics, starting them if it hasn't been played.
+    fn entry(&mut self, level: &str) -> &mut LevelStats {
+        self.levels.entry(level.to_string()).or_default()
+    }
+}
@@ -0,0 +1,134 @@
+// lighting.rs
+use crate::engine::level::{Level, LevelObject};
+use log::warn;
+
+/// Radius of a light without a `radius` property, in world units.
+const DEFAULT_RADIUS: f32 = 1.0;
+
+/// Light reaching everywhere in a dark level without an `ambient_light` property: none,
+/// so nothing outside the lights can be seen.
+const DARK_AMBIENT: [f32; 3] = [0.0; 3];
+
+/// Radius of the light the player carries through a dark level without a `player_light`
+/// property, in world units.
+const DARK_PLAYER_LIGHT: f32 = 0.6;
+
+/// Radius, color, and flicker of a torch without its own, a small warm flame.
+const TORCH_RADIUS: f32 = 0.8;
+const TORCH_COLOR: [f32; 3] = [1.0, 0.45, 0.12];
+const TORCH_FLICKER: f32 = 0.6;
+
+/// A light placed in the level, brightening a circle around it.
+pub struct PointLight {
+    /// The center of the light, in world coordinates.
+    pub position: (f32, f32),
+    /// How far the light reaches, fading out towards its edge.
+    pub radius: f32,
+    /// The light's color, scaled by its intensity, in linear space.
+    pub color: [f32; 3],
+    /// How strongly the light flickers, from 0.0 (steady) to 1.0.
+    pub flicker: f32,
+}
+
+/// How the level is lit, for caves and nighttime levels.
+///
+/// The level's `ambient_light` property is the color of the light reaching
+/// everywhere, as a Tiled color such as `#203050`. Without one the level is fully
+/// lit and its lights aren't drawn. Lights come from `light` objects, centered on the
+/// object, with these properties:
+/// - `radius`: how far the light reaches, in world units (default 1.0).
+/// - `color`: the light's color (default white).
+/// - `intensity`: how bright it is, scaling its color (default 1.0).
+/// - `flicker`: how strongly it flickers like a flame, from 0.0 to 1.0 (default 0.0).
+///
+/// `torch` objects are lights placed on walls and floors, defaulting to a flickering
+/// warm flame with a radius of 0.8.
+///
+/// A `player_light` property on the level gives the player a light of that radius
+/// to carry through the dark, which lanterns picked up in the level widen.
+///
+/// A level whose `dark` property is `true` is pitch black outside its lights unless
+/// it has its own `ambient_light`, and the player carries a light of radius 0.6
+/// unless it has its own `player_light`, so only what's around them and the
+/// torches can be seen.
+pub struct Lighting {
+    /// The light reaching everywhere, in linear space, or `None` if the level is fully lit.
+    pub ambient: Option<[f32; 3]>,
+    pub lights: Vec<PointLight>,
+    /// Radius of the light carried by the player, or 0.0 for none.
+    pub player_light: f32,
+}
+
+impl Lighting {
+    /// Finds the level's ambient light and the lights placed in it.
+    pub fn from_level(level: &Level) -> Self {
+        let dark = level.property("dark") == Some("true");
+        let ambient = level
+            .property("ambient_light")
+            .and_then(|value| {
+                let color = parse_color(value);
+                if color.is_none() {
+                    warn!("Level has an invalid ambient light '{}'", value);
+                }
+                color
+            })
+            .or(dark.then_some(DARK_AMBIENT));
+
+        let lights = level.objects_of_kind("light").map(|object| light(object, DEFAULT_RADIUS, [1.0; 3], 0.0));
+        let torches = level.objects_of_kind("torch").map(|object| light(object, TORCH_RADIUS, TORCH_COLOR, TORCH_FLICKER));
+
+        Self {
+            ambient,
+            lights: lights.chain(torches).collect(),
+            player_light: level
+                .property("player_light")
+                .and_then(|value| value.parse::<f32>().ok())
+                .unwrap_or(if dark { DARK_PLAYER_LIGHT } else { 0.0 })
+                .max(0.0),
+        }
+    }
+}
+
+/// Creates the light a `light` or `torch` object places, with the defaults for its kind.
+///
+/// # Arguments
+///
+/// * `object` - The object, centered on the light.
+/// * `radius`, `color`, `flicker` - What the light has without its own properties.
+fn light(object: &LevelObject, radius: f32, color: [f32; 3], flicker: f32) -> PointLight {
+    let color = match object.property("color") {
+        Some(value) => parse_color(value).unwrap_or_else(|| {
+            warn!("Light '{}' has an invalid color '{}'", object.name, value);
+            color
+        }),
+        None => color,
+    };
+    let intensity = object.property_f32("intensity", 1.0).max(0.0);
+    PointLight {
+        position: object.center(),
+        radius: object.property_f32("radius", radius).max(0.0),
+        color: color.map(|channel| channel * intensity),
+        flicker: object.property_f32("flicker", flicker).clamp(0.0, 1.0),
+    }
+}
+
+/// Parses a color written as `#RRGGBB`, or as Tiled writes color properties,
+/// `#AARRGGBB`, ignoring the alpha.
+///
+/// # Returns
+///
+/// The color in linear space, as lights add up, or `None` if it isn't a valid color.
+fn parse_color(value: &str) -> Option<[f32; 3]> {
+    let hex = value.strip_prefix('#')?;
+    let rgb = match hex.len() {
+        6 => hex,
+        8 => &hex[2..],
+        _ => return None,
+    };
+    let channel = |index: usize| {
+        let value = u8::from_str_radix(rgb.get(index * 2..index * 2 + 2)?, 16).ok()?;
+        // Colors are picked in sRGB; an approximate gamma is close enough for light
+        Some((value as f32 / 255.0).powf(2.2))
+    };
+    Some([channel(0)?, channel(1)?, channel(2)?])
+}
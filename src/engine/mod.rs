@@ -1,12 +1,82 @@
 // src/engine/mod.rs
 
+pub mod action;
+pub mod ambience;
+pub mod animation_event;
+pub mod animation_state;
+pub mod activation;
+pub mod app_state;
+pub mod assets;
+pub mod audio;
+pub mod background;
+pub mod camera;
+pub mod camera_zone;
+pub mod checkpoint;
+pub mod collectible;
+pub mod collision;
+/// Fixed collision scenarios, run by `cargo test` without a window, guarding against
+/// the player catching on the seams where slopes and tiles meet. They use the built-in
+/// tuning, so the results depend only on the game's code.
+#[cfg(test)]
+mod collision_checks;
+pub mod combat;
+pub mod config;
+#[cfg(debug_assertions)]
+pub mod console;
+pub mod cutscene;
+pub mod decoration;
+pub mod dev;
+pub mod door;
+pub mod effects;
+pub mod exit;
+pub mod frame_pacer;
+pub mod gamepad;
 pub mod game_state;
+pub mod hazard;
 pub mod input;
+pub mod jelly;
+pub mod level;
+pub mod level_stats;
+pub mod lighting;
+pub mod mods;
+pub mod mount;
+pub mod objective;
+pub mod oxygen;
+pub mod pattern;
+pub mod platform;
+pub mod portal;
+pub mod power;
+pub mod projectile;
+pub mod props;
 pub mod renderer;
+pub mod rewind;
+pub mod room;
+pub mod rng;
+pub mod rope;
+pub mod save;
+pub mod scene;
+pub mod scheduler;
+pub mod script;
+#[cfg(debug_assertions)]
+pub mod scrubber;
+pub mod secret;
+pub mod shadow;
+pub mod sign;
+pub mod slope;
+pub mod stats;
+pub mod switch;
+pub mod time_scale;
+pub mod tween;
+pub mod ui;
+pub mod water;
 pub mod constants;
 
+pub use app_state::AppState;
 pub use game_state::GameState;
+pub use camera::Camera;
 pub use input::InputHandler;
 pub use renderer::Renderer;
 pub use renderer::tile::TileMap;
-pub use constants::{SPRITE_WIDTH, SPRITE_HEIGHT, GROUND_LEVEL, PLAYER_SPEED, GRAVITY, JUMP_FORCE, ANIMATION_SPEED};
\ No newline at end of file
+pub use room::RoomManager;
+pub use secret::SecretAreas;
+pub use constants::{SPRITE_WIDTH, SPRITE_HEIGHT};
\ No newline at end of file
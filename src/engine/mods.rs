@@ -0,0 +1,248 @@
+// mods.rs
+use crate::engine::config::ConfigError;
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+/// Directory searched for mods at startup, one folder per mod.
+pub const MODS_DIR: &str = "mods";
+
+/// File in each mod's folder describing it.
+const MANIFEST_FILE: &str = "mod.toml";
+
+/// File in the mods directory listing the mods switched off in the mods list.
+const SETTINGS_FILE: &str = "settings.toml";
+
+/// A mod's `mod.toml`.
+#[derive(Serialize, Deserialize)]
+struct Manifest {
+    /// Name shown in the mods list; the folder's name if missing.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    name: Option<String>,
+    /// Mods with a higher priority override those with a lower one.
+    #[serde(default)]
+    priority: i32,
+    /// Levels the mod adds to the level-select menu, relative to its folder.
+    #[serde(default)]
+    levels: Vec<String>,
+}
+
+/// The mods switched off, by folder name.
+#[derive(Default, Serialize, Deserialize)]
+struct ModSettings {
+    #[serde(default)]
+    disabled: Vec<String>,
+}
+
+/// A mod found in the mods directory.
+#[derive(Clone)]
+pub struct Mod {
+    /// Name shown in the mods list.
+    pub name: String,
+    /// Name of the mod's folder, which identifies it in the settings.
+    folder: String,
+    /// The mod's folder, laid out like the game's own, e.g. `mods/night/assets/tileset/Tileset.png`.
+    root: PathBuf,
+    priority: i32,
+    /// Levels the mod adds, relative to its folder.
+    levels: Vec<String>,
+    pub enabled: bool,
+}
+
+/// The mods found in the mods directory, which can override the game's files and add
+/// levels.
+///
+/// Each mod is a folder holding a `mod.toml` with these optional keys:
+/// - `name`: name to show in the mods list (default the folder's name).
+/// - `priority`: mods with a higher priority win where two override the same file
+///   (default 0). Ties go to the folder whose name sorts first.
+/// - `levels`: paths of levels within the folder to add to the level-select menu.
+///
+/// Any other file in the folder replaces the game's file at the same path, so an
+/// asset pack holding `assets/tileset/Tileset.png` reskins every level. Enabled mods
+/// all apply at once, above the game's own files. The default list has no mods.
+#[derive(Clone, Default)]
+pub struct ModList {
+    /// The mods from the highest priority to the lowest.
+    mods: Vec<Mod>,
+    /// Where the mods switched off are saved.
+    settings_path: PathBuf,
+}
+
+impl ModList {
+    /// Finds the mods in a directory, with those switched off in its settings disabled.
+    ///
+    /// Folders without a readable manifest are skipped with a warning. A missing
+    /// directory just means no mods.
+    ///
+    /// # Arguments
+    ///
+    /// * `dir` - The mods directory, one folder per mod.
+    pub fn discover(dir: impl AsRef<Path>) -> Self {
+        let dir = dir.as_ref();
+        let settings_path = dir.join(SETTINGS_FILE);
+        let Ok(entries) = std::fs::read_dir(dir) else {
+            info!("No mods directory at '{}'", dir.display());
+            return Self {
+                mods: Vec::new(),
+                settings_path,
+            };
+        };
+
+        let settings: ModSettings = match std::fs::read_to_string(&settings_path) {
+            Ok(source) => toml::from_str(&source).unwrap_or_else(|e| {
+                warn!("Could not parse mod settings '{}': {}", settings_path.display(), e);
+                ModSettings::default()
+            }),
+            Err(_) => ModSettings::default(),
+        };
+
+        let mut mods: Vec<Mod> = entries
+            .filter_map(Result::ok)
+            .filter(|entry| entry.path().is_dir())
+            .filter_map(|entry| {
+                let root = entry.path();
+                let folder = entry.file_name().to_string_lossy().into_owned();
+                let manifest_path = root.join(MANIFEST_FILE);
+                let source = std::fs::read_to_string(&manifest_path).ok()?;
+                let manifest: Manifest = match toml::from_str(&source) {
+                    Ok(manifest) => manifest,
+                    Err(e) => {
+                        warn!("Could not parse mod manifest '{}': {}", manifest_path.display(), e);
+                        return None;
+                    }
+                };
+                Some(Mod {
+                    name: manifest.name.unwrap_or_else(|| folder.clone()),
+                    enabled: !settings.disabled.contains(&folder),
+                    folder,
+                    root,
+                    priority: manifest.priority,
+                    levels: manifest.levels,
+                })
+            })
+            .collect();
+        mods.sort_by(|a, b| b.priority.cmp(&a.priority).then_with(|| a.folder.cmp(&b.folder)));
+
+        for mod_ in &mods {
+            info!(
+                "Found mod '{}' (priority {}, {})",
+                mod_.name,
+                mod_.priority,
+                if mod_.enabled { "enabled" } else { "disabled" }
+            );
+        }
+        Self { mods, settings_path }
+    }
+
+    /// Returns the mods, from the highest priority to the lowest.
+    pub fn mods(&self) -> &[Mod] {
+        &self.mods
+    }
+
+    /// Returns the path to read a game file from: the copy in the highest-priority
+    /// enabled mod that has one, or else the game's own.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The file's path relative to the working directory, e.g. `assets/levels/level1.json`.
+    pub fn resolve(&self, path: &Path) -> PathBuf {
+        if path.is_relative() {
+            let overridden = self
+                .mods
+                .iter()
+                .filter(|mod_| mod_.enabled)
+                .map(|mod_| mod_.root.join(path))
+                .find(|candidate| candidate.is_file());
+            if let Some(overridden) = overridden {
+                return overridden;
+            }
+        }
+        path.to_path_buf()
+    }
+
+    /// Returns the paths of the levels the enabled mods add, by mod priority and then
+    /// in the order each lists them.
+    pub fn levels(&self) -> Vec<String> {
+        self.mods
+            .iter()
+            .filter(|mod_| mod_.enabled)
+            .flat_map(|mod_| mod_.levels.iter().map(|level| mod_.root.join(level).to_string_lossy().into_owned()))
+            .collect()
+    }
+
+    /// Switches a mod on or off and saves the change for the next time the game starts.
+    ///
+    /// # Arguments
+    ///
+    /// * `index` - The mod's index in `mods()`.
+    pub fn toggle(&mut self, index: usize) {
+        let Some(mod_) = self.mods.get_mut(index) else {
+            return;
+        };
+        mod_.enabled = !mod_.enabled;
+        if let Err(e) = self.save() {
+            warn!("Could not save mod settings '{}': {}", self.settings_path.display(), e);
+        }
+    }
+
+    /// Writes which mods are switched off to the settings file.
+    fn save(&self) -> Result<(), ConfigError> {
+        let settings = ModSettings {
+            disabled: self
+                .mods
+                .iter()
+                .filter(|mod_| !mod_.enabled)
+                .map(|mod_| mod_.folder.clone())
+                .collect(),
+        };
+        let source = toml::to_string(&settings).map_err(|e| ConfigError::Parse(e.to_string()))?;
+        std::fs::write(&self.settings_path, source)?;
+        Ok(())
+    }
+}
+
+/// Writes the manifest of a new mod adding levels, creating its folder if needed.
+///
+/// # Arguments
+///
+/// * `root` - The mod's folder, e.g. `mods/night`.
+/// * `name` - Name to show in the mods list.
+/// * `levels` - Levels the mod adds, relative to its folder.
+pub fn write_manifest(root: &Path, name: &str, levels: Vec<String>) -> Result<(), ConfigError> {
+    let manifest = Manifest {
+        name: Some(name.to_string()),
+        priority: 0,
+        levels,
+    };
+    let source = toml::to_string(&manifest).map_err(|e| ConfigError::Parse(e.to_string()))?;
+    std::fs::create_dir_all(root)?;
+    std::fs::write(root.join(MANIFEST_FILE), source)?;
+    Ok(())
+}
+
+/// The mods in effect, set once at startup.
+static MODS: OnceLock<ModList> = OnceLock::new();
+
+/// Makes the given mods the ones game files are resolved through.
+///
+/// Only the first call has any effect, so this should happen at startup before
+/// anything is loaded; mods switched on or off later apply from the next start.
+pub fn set_mods(mods: ModList) {
+    if MODS.set(mods).is_err() {
+        warn!("Mods were already set; ignoring the new list");
+    }
+}
+
+/// Returns the path to read a game file from, through the mods in effect.
+///
+/// # Arguments
+///
+/// * `path` - The file's path relative to the working directory.
+pub fn resolve(path: impl AsRef<Path>) -> PathBuf {
+    match MODS.get() {
+        Some(mods) => mods.resolve(path.as_ref()),
+        None => path.as_ref().to_path_buf(),
+    }
+}
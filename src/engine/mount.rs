@@ -0,0 +1,252 @@
+// mount.rs
+use crate::engine::action::Action;
+use crate::engine::activation::ActivationRange;
+use crate::engine::collision::Aabb;
+use crate::engine::config::tuning;
+use crate::engine::constants::{GROUND_LEVEL, MOUNT_TRANSITION_TIME, SPRITE_HEIGHT};
+use crate::engine::game_state::{GameState, MovementProfile};
+use crate::engine::input::InputHandler;
+use crate::engine::level::Level;
+use log::warn;
+
+/// The kinds of mount the player can ride.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum MountKind {
+    /// Rolls along the ground, building up speed slowly but reaching a high top speed.
+    Minecart,
+    /// Floats freely in both directions, ignoring gravity.
+    HoverPlatform,
+}
+
+impl MountKind {
+    /// Returns the movement profile the player takes on while riding a mount of this kind.
+    fn movement(self, size: (f32, f32)) -> MovementProfile {
+        match self {
+            MountKind::Minecart => MovementProfile {
+                speed: 2.0,
+                acceleration: Some(2.5),
+                jump_force: tuning().jump_force * 0.6,
+                flying: false,
+                hitbox: size,
+                mounted: true,
+            },
+            MountKind::HoverPlatform => MovementProfile {
+                speed: 0.8,
+                acceleration: Some(4.0),
+                jump_force: 0.0,
+                flying: true,
+                hitbox: size,
+                mounted: true,
+            },
+        }
+    }
+}
+
+/// An entity the player can ride.
+#[derive(Clone)]
+pub struct Mount {
+    kind: MountKind,
+    /// Center of the mount in world coordinates.
+    pub position: (f32, f32),
+    /// Size of the mount as `(width, height)`, used for both its sprite and collision box.
+    pub size: (f32, f32),
+    /// Index of the tileset tile drawn for the mount.
+    pub tile_index: usize,
+    /// Whether the mount is near enough to the camera to be mounted.
+    active: bool,
+}
+
+/// The player hopping onto a mount.
+#[derive(Clone)]
+struct MountTransition {
+    mount: usize,
+    elapsed: f32,
+    start: (f32, f32),
+}
+
+/// Mounts placed in the level, and which of them the player is riding.
+///
+/// Mounts come from `mount` objects in the level. The `type` property picks the
+/// kind (`minecart` or `hover`), and the `tile` property the tileset tile used to
+/// draw it. The mount action while touching a mount hops onto it; while riding, the
+/// mount's movement profile and collision box replace the player's own, and
+/// the mount action again hops off.
+#[derive(Clone)]
+pub struct Mounts {
+    pub mounts: Vec<Mount>,
+    ridden: Option<usize>,
+    transition: Option<MountTransition>,
+    /// Time used to animate hovering mounts, in seconds.
+    time: f32,
+}
+
+impl Mounts {
+    /// Creates the mounts placed in the level, skipping any of an unknown type.
+    pub fn from_level(level: &Level) -> Self {
+        let mounts = level
+            .objects_of_kind("mount")
+            .filter_map(|object| {
+                let kind = match object.property("type") {
+                    Some("minecart") => MountKind::Minecart,
+                    Some("hover") => MountKind::HoverPlatform,
+                    other => {
+                        warn!("Mount '{}' has unknown type {:?}", object.name, other);
+                        return None;
+                    }
+                };
+
+                let size = (
+                    object.bounds.max_x - object.bounds.min_x,
+                    object.bounds.max_y - object.bounds.min_y,
+                );
+                let (x, mut y) = object.center();
+                if kind == MountKind::Minecart {
+                    // Minecarts start out resting on the ground
+                    y = GROUND_LEVEL + size.1 / 2.0;
+                }
+
+                Some(Mount {
+                    kind,
+                    position: (x, y),
+                    size,
+                    tile_index: object.tile_index(),
+                    active: true,
+                })
+            })
+            .collect();
+
+        Self {
+            mounts,
+            ridden: None,
+            transition: None,
+            time: 0.0,
+        }
+    }
+
+    /// Activates the mounts within range of the camera and deactivates the rest.
+    ///
+    /// The mount being ridden or hopped onto always stays active.
+    ///
+    /// # Returns
+    ///
+    /// The number of mounts left active.
+    pub fn activate(&mut self, range: &ActivationRange) -> usize {
+        let in_use = self.ridden.or(self.transition.as_ref().map(|transition| transition.mount));
+        for (index, mount) in self.mounts.iter_mut().enumerate() {
+            let bounds = Aabb::from_center(mount.position.0, mount.position.1, mount.size.0, mount.size.1);
+            mount.active = in_use == Some(index) || range.contains(&bounds);
+        }
+        self.mounts.iter().filter(|mount| mount.active).count()
+    }
+
+    /// Handles mounting and dismounting, and keeps the ridden mount under the player.
+    ///
+    /// # Arguments
+    ///
+    /// * `game_state` - The player state whose movement profile is swapped.
+    /// * `input_handler` - Provides the state of the mount action.
+    /// * `delta_time` - Time elapsed since the last frame.
+    pub fn update(&mut self, game_state: &mut GameState, input_handler: &InputHandler, delta_time: f32) {
+        self.time += delta_time;
+
+        // Holding the mount action doesn't toggle repeatedly
+        let toggled = input_handler.is_action_just_pressed(Action::Mount);
+
+        if let Some(transition) = self.transition.as_mut() {
+            transition.elapsed += delta_time;
+            let mount = &self.mounts[transition.mount];
+
+            if transition.elapsed >= MOUNT_TRANSITION_TIME {
+                game_state.set_movement(mount.kind.movement(mount.size));
+                game_state.teleport(mount.position.0, mount.position.1);
+                self.ridden = Some(transition.mount);
+                self.transition = None;
+            } else {
+                // Hop along an arc from where the player stood to the seat
+                let t = transition.elapsed / MOUNT_TRANSITION_TIME;
+                let (start_x, start_y) = transition.start;
+                let (seat_x, seat_y) = seat_position(mount);
+                let arc = (t * std::f32::consts::PI).sin() * SPRITE_HEIGHT;
+                game_state.teleport(
+                    start_x + (seat_x - start_x) * t,
+                    start_y + (seat_y - start_y) * t + arc,
+                );
+            }
+            return;
+        }
+
+        match self.ridden {
+            Some(index) => {
+                let mount = &mut self.mounts[index];
+                mount.position = (game_state.player_x, game_state.player_y);
+
+                if toggled {
+                    // Hop off from the seat, leaving the mount where it is
+                    let (seat_x, seat_y) = seat_position(mount);
+                    game_state.set_movement(MovementProfile::on_foot());
+                    game_state.teleport(seat_x, seat_y);
+                    game_state.set_velocity(0.0, tuning().jump_force * 0.6);
+                    self.ridden = None;
+                }
+            }
+            None if toggled => {
+                let player = game_state.bounds();
+                let touching = self.mounts.iter().position(|mount| {
+                    mount.active
+                        && Aabb::from_center(mount.position.0, mount.position.1, mount.size.0, mount.size.1)
+                            .intersects(&player)
+                });
+                if let Some(index) = touching {
+                    self.transition = Some(MountTransition {
+                        mount: index,
+                        elapsed: 0.0,
+                        start: (game_state.player_x, game_state.player_y),
+                    });
+                }
+            }
+            None => {}
+        }
+    }
+
+    /// Returns where to draw a mount, including the bobbing of hovering mounts. The
+    /// ridden mount carries the player, so it's drawn where they are.
+    pub fn draw_position(&self, index: usize, game_state: &GameState) -> (f32, f32) {
+        let mount = &self.mounts[index];
+        let (x, y) = if self.ridden == Some(index) {
+            game_state.drawn_position()
+        } else {
+            mount.position
+        };
+        match mount.kind {
+            MountKind::HoverPlatform => (x, y + (self.time * 3.0 + index as f32).sin() * 0.02),
+            MountKind::Minecart => (x, y),
+        }
+    }
+
+    /// Returns whether the player is riding one of the mounts.
+    pub fn is_riding(&self) -> bool {
+        self.ridden.is_some()
+    }
+
+    /// Returns where to draw the player sprite, which sits in the seat while riding.
+    pub fn rider_position(&self, game_state: &GameState) -> (f32, f32) {
+        match self.ridden {
+            Some(index) => {
+                let (x, y) = self.draw_position(index, game_state);
+                (x, y + seat_offset(&self.mounts[index]))
+            }
+            None => game_state.drawn_position(),
+        }
+    }
+}
+
+/// Returns the height of the rider's center above the mount's center.
+fn seat_offset(mount: &Mount) -> f32 {
+    // Sink the rider's feet slightly into the mount
+    mount.size.1 / 2.0 + SPRITE_HEIGHT * 0.3
+}
+
+/// Returns the position of the rider's center when seated on the mount.
+fn seat_position(mount: &Mount) -> (f32, f32) {
+    (mount.position.0, mount.position.1 + seat_offset(mount))
+}
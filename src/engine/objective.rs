@@ -0,0 +1,202 @@
+// objective.rs
+use crate::engine::collectible::Collectibles;
+use crate::engine::collision::Aabb;
+use crate::engine::game_state::GameState;
+use crate::engine::level::Level;
+use crate::engine::projectile::Projectiles;
+use log::{info, warn};
+
+/// What an objective asks of the player.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Goal {
+    /// Pick up the level's collectibles.
+    Collect,
+    /// Get to the objective's area.
+    Reach,
+    /// Shoot down the level's turrets.
+    Defeat,
+}
+
+/// Something the player has to do in the level.
+pub struct Objective {
+    /// The objective's identity, under which a reached area is marked as collected.
+    name: String,
+    /// Text shown for it on the tracker.
+    pub label: String,
+    goal: Goal,
+    bounds: Aabb,
+    /// How much of it is done, out of `required`.
+    pub progress: u32,
+    pub required: u32,
+    /// Whether the level can be left without it.
+    pub optional: bool,
+}
+
+impl Objective {
+    /// Returns whether the objective is done.
+    pub fn is_complete(&self) -> bool {
+        self.progress >= self.required
+    }
+}
+
+/// A change to an objective in an update.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ObjectiveUpdate {
+    /// More of the objective was done, which has this index in the level's objectives.
+    Progressed { objective: usize },
+    /// The objective with this index was finished.
+    Completed { objective: usize },
+}
+
+/// How much the player has done in the level, which objectives count.
+struct Done {
+    /// Collectibles picked up.
+    collected: u32,
+    /// Turrets shot down.
+    defeated: u32,
+}
+
+impl Done {
+    fn count(game_state: &GameState, collectibles: &Collectibles, projectiles: &Projectiles) -> Self {
+        Self {
+            collected: collectibles
+                .collectibles
+                .iter()
+                .filter(|collectible| collectible.is_collected(game_state))
+                .count() as u32,
+            defeated: projectiles
+                .turrets
+                .iter()
+                .filter(|turret| turret.is_destroyed(game_state))
+                .count() as u32,
+        }
+    }
+
+    /// Returns how much of an objective is done.
+    fn progress(&self, objective: &Objective, game_state: &GameState) -> u32 {
+        let progress = match objective.goal {
+            Goal::Collect => self.collected,
+            Goal::Reach => game_state.has_collected(&objective.name) as u32,
+            Goal::Defeat => self.defeated,
+        };
+        progress.min(objective.required)
+    }
+}
+
+/// The level's objectives, tracked on screen and holding its exits shut until done.
+///
+/// Objectives come from `objective` objects in the level, whose `goal` property says
+/// what to do:
+/// - `collect`: pick up `count` of the level's collectibles (default all of them).
+/// - `reach`: get to the objective's own area.
+/// - `defeat`: shoot down `count` of the level's turrets (default all of them).
+///
+/// The `text` property labels an objective on the tracker, and `optional` set to
+/// `true` lets the player leave the level without it. Progress is counted from what
+/// the player has collected, so it carries across lives and saves.
+pub struct Objectives {
+    objectives: Vec<Objective>,
+}
+
+impl Objectives {
+    /// Creates the level's objectives, with the progress already made on them.
+    ///
+    /// # Arguments
+    ///
+    /// * `level` - The level the objectives are placed in.
+    /// * `game_state` - Provides what the player has already collected.
+    /// * `collectibles` - The level's collectibles, which collect objectives count by default.
+    /// * `projectiles` - Holds the level's turrets, which defeat objectives count by default.
+    pub fn from_level(
+        level: &Level,
+        game_state: &GameState,
+        collectibles: &Collectibles,
+        projectiles: &Projectiles,
+    ) -> Self {
+        let done = Done::count(game_state, collectibles, projectiles);
+        let objectives = level
+            .objects_of_kind("objective")
+            .filter_map(|object| {
+                let (goal, available, default_label) = match object.property("goal") {
+                    Some("collect") => (Goal::Collect, collectibles.collectibles.len(), "COLLECT".to_string()),
+                    Some("reach") => (Goal::Reach, 1, format!("REACH {}", object.name.to_uppercase())),
+                    Some("defeat") => (Goal::Defeat, projectiles.turrets.len(), "DEFEAT TURRETS".to_string()),
+                    goal => {
+                        warn!("Objective '{}' has unknown goal {:?}", object.name, goal);
+                        return None;
+                    }
+                };
+                let required = match goal {
+                    Goal::Reach => 1,
+                    _ => object.property_f32("count", available as f32).max(1.0) as u32,
+                };
+                if required as usize > available {
+                    warn!("Objective '{}' needs {} but the level only has {}", object.name, required, available);
+                }
+                let mut objective = Objective {
+                    name: level.object_id(object).to_string(),
+                    label: object.property("text").map_or(default_label, str::to_uppercase),
+                    goal,
+                    bounds: object.bounds,
+                    progress: 0,
+                    required,
+                    optional: object.property("optional") == Some("true"),
+                };
+                objective.progress = done.progress(&objective, game_state);
+                Some(objective)
+            })
+            .collect();
+
+        Self { objectives }
+    }
+
+    /// Counts the progress made on each objective, marking reached areas as collected.
+    ///
+    /// # Arguments
+    ///
+    /// * `game_state` - Provides the player's position and what they've collected.
+    /// * `collectibles` - The level's collectibles.
+    /// * `projectiles` - Holds the level's turrets.
+    ///
+    /// # Returns
+    ///
+    /// The objectives that moved on this frame, in level file order.
+    pub fn update(
+        &mut self,
+        game_state: &mut GameState,
+        collectibles: &Collectibles,
+        projectiles: &Projectiles,
+    ) -> Vec<ObjectiveUpdate> {
+        let player = game_state.bounds();
+        let done = Done::count(game_state, collectibles, projectiles);
+        let mut updates = Vec::new();
+        for (index, objective) in self.objectives.iter_mut().enumerate() {
+            if objective.goal == Goal::Reach && objective.bounds.intersects(&player) {
+                game_state.collect(&objective.name);
+            }
+            let progress = done.progress(objective, game_state);
+            if progress > objective.progress {
+                objective.progress = progress;
+                if objective.is_complete() {
+                    info!("Objective '{}' complete", objective.label);
+                    updates.push(ObjectiveUpdate::Completed { objective: index });
+                } else {
+                    updates.push(ObjectiveUpdate::Progressed { objective: index });
+                }
+            }
+        }
+        updates
+    }
+
+    /// Returns the level's objectives, in level file order.
+    pub fn objectives(&self) -> &[Objective] {
+        &self.objectives
+    }
+
+    /// Returns whether every objective needed to leave the level is done.
+    pub fn exit_open(&self) -> bool {
+        self.objectives
+            .iter()
+            .all(|objective| objective.optional || objective.is_complete())
+    }
+}
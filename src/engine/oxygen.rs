@@ -0,0 +1,196 @@
+// oxygen.rs
+use crate::engine::collision::Aabb;
+use crate::engine::config::tuning;
+use crate::engine::game_state::GameState;
+use crate::engine::level::Level;
+use crate::engine::tween::Timer;
+use crate::engine::water::Water;
+
+/// Fraction of a full breath below which the player is warned that it's running out.
+const WARNING_FRACTION: f32 = 0.3;
+
+/// Seconds an empty breath takes to fill back up out of the water.
+const REFILL_TIME: f32 = 0.6;
+
+/// Height of the player's mouth above the center of their collision box, as a fraction
+/// of its height; they hold their breath while it's underwater.
+const MOUTH_HEIGHT: f32 = 0.3;
+
+/// Seconds between puffs of bubbles rising from air bubbles.
+const PUFF_INTERVAL: f32 = 0.5;
+
+/// Effect puffed by air bubbles without an `effect` property.
+const DEFAULT_EFFECT: &str = "air_bubbles";
+
+/// What happened to the player's breath in an update.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Breath {
+    /// The breath is running low, with this many whole seconds left. Reported once
+    /// each second until the player breathes or drowns.
+    Low { seconds_left: u32 },
+    /// The player breathed again, at the surface or from air bubbles, after holding it.
+    Breathed,
+    /// The breath ran out underwater.
+    Drowned,
+}
+
+/// A spot underwater where the player can breathe.
+pub struct AirBubble {
+    pub bounds: Aabb,
+    /// Name of the effect preset puffed from it, showing where it is.
+    pub effect: String,
+}
+
+/// The level's air bubbles, refilling the player's breath while they're inside one.
+///
+/// Air bubbles come from `air_bubbles` rectangles in the level, each puffing its
+/// `effect` property's effect from its center every so often.
+pub struct AirBubbles {
+    bubbles: Vec<AirBubble>,
+    /// Counts the time between puffs, starting due so the first comes straight away.
+    puff_timer: Timer,
+}
+
+impl AirBubbles {
+    /// Creates the air bubbles placed in the level.
+    pub fn from_level(level: &Level) -> Self {
+        let bubbles = level
+            .objects_of_kind("air_bubbles")
+            .map(|object| AirBubble {
+                bounds: object.bounds,
+                effect: object.property("effect").unwrap_or(DEFAULT_EFFECT).to_string(),
+            })
+            .collect();
+
+        Self {
+            bubbles,
+            puff_timer: Timer::repeating(PUFF_INTERVAL).with_elapsed(PUFF_INTERVAL),
+        }
+    }
+
+    /// Returns whether an area overlaps any air bubble.
+    pub fn touching(&self, bounds: &Aabb) -> bool {
+        self.bubbles.iter().any(|bubble| bubble.bounds.intersects(bounds))
+    }
+
+    /// Advances the puffs of bubbles.
+    ///
+    /// # Returns
+    ///
+    /// The effect and center of each air bubble to puff this frame.
+    pub fn update(&mut self, delta_time: f32) -> Vec<(&str, f32, f32)> {
+        if !self.puff_timer.tick(delta_time) {
+            return Vec::new();
+        }
+        self.bubbles
+            .iter()
+            .map(|bubble| {
+                let bounds = bubble.bounds;
+                (bubble.effect.as_str(), (bounds.min_x + bounds.max_x) / 2.0, (bounds.min_y + bounds.max_y) / 2.0)
+            })
+            .collect()
+    }
+}
+
+/// The breath the player holds underwater.
+///
+/// It runs down while the player's mouth is underwater and fills back up quickly at
+/// the surface or inside air bubbles. A tuned `oxygen_time` of zero or less lets the
+/// player breathe underwater.
+pub struct Oxygen {
+    /// Seconds of breath left.
+    air: f32,
+    /// Whether the breath ran down in the last update.
+    holding: bool,
+    /// Whole seconds left when the breath was last reported low.
+    warned: Option<u32>,
+}
+
+impl Oxygen {
+    /// Creates a full breath.
+    pub fn new() -> Self {
+        Self {
+            air: tuning().oxygen_time.max(0.0),
+            holding: false,
+            warned: None,
+        }
+    }
+
+    /// Runs the breath down or fills it back up, by where the player's mouth is.
+    ///
+    /// # Arguments
+    ///
+    /// * `game_state` - Provides the player's position.
+    /// * `water` - The level's water.
+    /// * `air_bubbles` - The level's air bubbles, which the player breathes from.
+    /// * `delta_time` - Time elapsed since the last frame.
+    ///
+    /// # Returns
+    ///
+    /// What happened to the breath, if it ran low, was taken again, or ran out.
+    pub fn update(
+        &mut self,
+        game_state: &GameState,
+        water: &Water,
+        air_bubbles: &AirBubbles,
+        delta_time: f32,
+    ) -> Option<Breath> {
+        let capacity = tuning().oxygen_time;
+        if capacity <= 0.0 {
+            return None;
+        }
+
+        let bounds = game_state.bounds();
+        let mouth_y = game_state.player_y + (bounds.max_y - bounds.min_y) * MOUTH_HEIGHT;
+        let holding = water.contains(game_state.player_x, mouth_y) && !air_bubbles.touching(&bounds);
+        let was_holding = std::mem::replace(&mut self.holding, holding);
+
+        if !holding {
+            self.air = (self.air + capacity / REFILL_TIME * delta_time).min(capacity);
+            self.warned = None;
+            return was_holding.then_some(Breath::Breathed);
+        }
+
+        let had_air = self.air > 0.0;
+        self.air = (self.air - delta_time).max(0.0);
+        if had_air && self.air <= 0.0 {
+            return Some(Breath::Drowned);
+        }
+        let seconds_left = self.air.ceil() as u32;
+        if self.is_low() && self.warned != Some(seconds_left) && self.air > 0.0 {
+            self.warned = Some(seconds_left);
+            return Some(Breath::Low { seconds_left });
+        }
+        None
+    }
+
+    /// Returns the seconds of breath left.
+    pub fn air_left(&self) -> f32 {
+        self.air
+    }
+
+    /// Returns how much of a full breath is left, from 0.0 to 1.0.
+    pub fn fraction(&self) -> f32 {
+        let capacity = tuning().oxygen_time;
+        if capacity <= 0.0 {
+            return 1.0;
+        }
+        (self.air / capacity).clamp(0.0, 1.0)
+    }
+
+    /// Returns whether the breath is running low enough to warn about.
+    pub fn is_low(&self) -> bool {
+        self.fraction() < WARNING_FRACTION
+    }
+
+    /// Returns whether the meter is worth showing: the breath isn't full.
+    pub fn shown(&self) -> bool {
+        self.fraction() < 1.0
+    }
+}
+
+impl Default for Oxygen {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,178 @@
+// pattern.rs
+use crate::engine::config::ConfigError;
+use crate::engine::mods::resolve;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::OnceLock;
+
+/// How a pattern lays out each volley's shots.
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum PatternKind {
+    /// A fan of shots spread evenly across `arc` degrees around the emitter's direction.
+    #[default]
+    Spread,
+    /// Shots spaced evenly all the way around, starting at the emitter's direction.
+    Ring,
+    /// A fan like `Spread`, centered on wherever the player is as each volley fires.
+    Aimed,
+    /// A ring that turns `turn` degrees further with each volley.
+    Spiral,
+}
+
+/// Degrees a spiral turns between volleys when its pattern doesn't say.
+const DEFAULT_SPIRAL_TURN: f32 = 15.0;
+
+/// A bullet pattern, as read from the patterns file: volleys of projectiles fired
+/// one after another, each laid out by the pattern's kind.
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct BulletPattern {
+    pub kind: PatternKind,
+    /// Shots in each volley.
+    pub count: u32,
+    /// Degrees a spread or aimed volley fans out across.
+    pub arc: f32,
+    /// Speed of the shots, in tiles per second.
+    pub speed: f32,
+    /// Volleys fired.
+    pub volleys: u32,
+    /// Time between volleys, in seconds.
+    pub interval: f32,
+    /// Degrees each volley turns from the last, counterclockwise; spirals turn 15 if unset.
+    pub turn: Option<f32>,
+    /// Degrees each shot may stray from its direction either way, drawn from the run's
+    /// random numbers.
+    pub scatter: f32,
+}
+
+impl Default for BulletPattern {
+    /// A single shot straight ahead.
+    fn default() -> Self {
+        Self {
+            kind: PatternKind::Spread,
+            count: 1,
+            arc: 0.0,
+            speed: 4.0,
+            volleys: 1,
+            interval: 0.2,
+            turn: None,
+            scatter: 0.0,
+        }
+    }
+}
+
+impl BulletPattern {
+    /// Returns the directions of one volley's shots, in degrees counterclockwise from the right.
+    ///
+    /// # Arguments
+    ///
+    /// * `direction` - The direction the volley is centered on or starts from.
+    /// * `volley` - How many volleys have already been fired, which turns the pattern.
+    pub fn volley(&self, direction: f32, volley: u32) -> Vec<f32> {
+        let turn = match (self.turn, self.kind) {
+            (Some(turn), _) => turn,
+            (None, PatternKind::Spiral) => DEFAULT_SPIRAL_TURN,
+            (None, _) => 0.0,
+        };
+        let start = direction + turn * volley as f32;
+        let count = self.count.max(1);
+        match self.kind {
+            PatternKind::Spread | PatternKind::Aimed => {
+                if count == 1 {
+                    return vec![start];
+                }
+                let step = self.arc / (count - 1) as f32;
+                (0..count).map(|shot| start - self.arc / 2.0 + step * shot as f32).collect()
+            }
+            PatternKind::Ring | PatternKind::Spiral => {
+                let step = 360.0 / count as f32;
+                (0..count).map(|shot| start + step * shot as f32).collect()
+            }
+        }
+    }
+}
+
+/// A pattern partway through its volleys.
+#[derive(Clone)]
+pub struct Barrage {
+    pub pattern: BulletPattern,
+    /// Where the shots come from, in world coordinates.
+    pub origin: (f32, f32),
+    /// Direction the pattern is fired in, in degrees counterclockwise from the right.
+    direction: f32,
+    /// Volleys fired so far.
+    fired: u32,
+    /// Time until the next volley, in seconds.
+    timer: f32,
+}
+
+impl Barrage {
+    /// Starts a pattern, firing its first volley on the next update.
+    pub fn new(pattern: BulletPattern, origin: (f32, f32), direction: f32) -> Self {
+        Self {
+            pattern,
+            origin,
+            direction,
+            fired: 0,
+            timer: 0.0,
+        }
+    }
+
+    /// Fires the volleys that are due.
+    ///
+    /// # Arguments
+    ///
+    /// * `target` - Where the player is, for aimed patterns to fire at.
+    /// * `delta_time` - Time elapsed since the last frame.
+    ///
+    /// # Returns
+    ///
+    /// The directions of the shots fired, in degrees counterclockwise from the right.
+    pub fn update(&mut self, target: (f32, f32), delta_time: f32) -> Vec<f32> {
+        let mut shots = Vec::new();
+        self.timer -= delta_time;
+        while self.timer <= 0.0 && !self.is_finished() {
+            let direction = match self.pattern.kind {
+                PatternKind::Aimed => (target.1 - self.origin.1).atan2(target.0 - self.origin.0).to_degrees(),
+                _ => self.direction,
+            };
+            shots.extend(self.pattern.volley(direction, self.fired));
+            self.fired += 1;
+            self.timer += self.pattern.interval.max(0.01);
+        }
+        shots
+    }
+
+    /// Returns whether every volley has been fired.
+    pub fn is_finished(&self) -> bool {
+        self.fired >= self.pattern.volleys
+    }
+}
+
+static PATTERNS: OnceLock<HashMap<String, BulletPattern>> = OnceLock::new();
+
+/// Reads bullet patterns from a TOML file, one table per pattern, keyed by name.
+///
+/// # Arguments
+///
+/// * `path` - Path to the TOML file.
+pub fn load_patterns(path: impl AsRef<Path>) -> Result<HashMap<String, BulletPattern>, ConfigError> {
+    let source = std::fs::read_to_string(resolve(path))?;
+    toml::from_str(&source).map_err(|e| ConfigError::Parse(e.to_string()))
+}
+
+/// Makes the given patterns the ones returned by `patterns()`.
+///
+/// Only the first call has any effect, so this should happen at startup.
+pub fn set_patterns(patterns: HashMap<String, BulletPattern>) {
+    if PATTERNS.set(patterns).is_err() {
+        log::warn!("Bullet patterns were already set; ignoring the new ones");
+    }
+}
+
+/// Returns the bullet patterns in effect, or none if none have been set.
+pub fn patterns() -> &'static HashMap<String, BulletPattern> {
+    PATTERNS.get_or_init(HashMap::new)
+}
@@ -0,0 +1,170 @@
+// platform.rs
+use crate::engine::activation::ActivationRange;
+use crate::engine::collision::Aabb;
+use crate::engine::game_state::GameState;
+use crate::engine::level::Level;
+use crate::engine::tween::Timer;
+
+/// How far a platform travels when its `distance` property isn't set, in tiles.
+const DEFAULT_DISTANCE: f32 = 4.0;
+
+/// How fast a platform moves when its `speed` property isn't set, in tiles per second.
+const DEFAULT_SPEED: f32 = 2.0;
+
+/// How far below the top of a platform the player's feet can be and still land on it.
+const LANDING_DEPTH: f32 = 0.15;
+
+/// A platform that moves back and forth along a straight path, carrying the player.
+#[derive(Clone)]
+pub struct MovingPlatform {
+    /// The platform's current shape in world coordinates.
+    pub bounds: Aabb,
+    /// Where the platform was placed in the level, the start of its path.
+    origin: Aabb,
+    /// Offset from the start of the path to its end.
+    travel: (f32, f32),
+    /// Time to travel the path one way, in seconds.
+    leg_time: f32,
+    /// Counts out and back along the path, starting over on each return.
+    timer: Timer,
+    /// The platform's velocity over the last update.
+    velocity: (f32, f32),
+    /// Index of the tileset tile repeated along the platform.
+    pub tile_index: usize,
+    /// Columns and rows of tileset tiles making up each repeat of its art.
+    pub tile_span: (usize, usize),
+    /// Whether the player stood on the platform at the end of the last update.
+    carrying: bool,
+    /// Whether the platform is near enough to the camera to move and be stood on.
+    active: bool,
+}
+
+impl MovingPlatform {
+    /// Moves the platform along its path, turning around at either end.
+    fn advance(&mut self, delta_time: f32) {
+        if self.leg_time <= 0.0 {
+            return;
+        }
+        self.timer.tick(delta_time);
+
+        // Out along the first leg, then back along the second
+        let progress = self.timer.elapsed() / self.leg_time;
+        let t = if progress <= 1.0 { progress } else { 2.0 - progress };
+        let (dx, dy) = (self.travel.0 * t, self.travel.1 * t);
+        let bounds = Aabb {
+            min_x: self.origin.min_x + dx,
+            min_y: self.origin.min_y + dy,
+            max_x: self.origin.max_x + dx,
+            max_y: self.origin.max_y + dy,
+        };
+
+        if delta_time > 0.0 {
+            self.velocity = (
+                (bounds.min_x - self.bounds.min_x) / delta_time,
+                (bounds.min_y - self.bounds.min_y) / delta_time,
+            );
+        }
+        self.bounds = bounds;
+    }
+
+    /// Returns the area the platform sweeps over along its whole path.
+    fn path_bounds(&self) -> Aabb {
+        Aabb {
+            min_x: self.origin.min_x + self.travel.0.min(0.0),
+            min_y: self.origin.min_y + self.travel.1.min(0.0),
+            max_x: self.origin.max_x + self.travel.0.max(0.0),
+            max_y: self.origin.max_y + self.travel.1.max(0.0),
+        }
+    }
+}
+
+/// Platforms that move back and forth and carry the player standing on them.
+///
+/// Platforms come from `platform` objects in the level, placed at the start of their
+/// path. The `axis` property is `horizontal` (the default) or `vertical`, `distance`
+/// sets how far they travel along it in tiles, negative to go left or down, and
+/// `speed` how fast in tiles per second. Like bridges, they can be jumped through
+/// from below and stood on from above.
+#[derive(Clone)]
+pub struct MovingPlatforms {
+    pub platforms: Vec<MovingPlatform>,
+}
+
+impl MovingPlatforms {
+    /// Creates the moving platforms placed in the level, at the start of their paths.
+    pub fn from_level(level: &Level) -> Self {
+        let (tile_width, tile_height) = (level.tile_map.tile_width, level.tile_map.tile_height);
+        let platforms = level
+            .objects_of_kind("platform")
+            .map(|object| {
+                let distance = object.property_f32("distance", DEFAULT_DISTANCE);
+                let speed = object.property_f32("speed", DEFAULT_SPEED).abs();
+                let travel = match object.property("axis") {
+                    Some("vertical") => (0.0, distance * tile_height),
+                    Some("horizontal") | None => (distance * tile_width, 0.0),
+                    Some(other) => {
+                        log::warn!("Unknown platform axis '{}' on '{}'", other, object.name);
+                        (distance * tile_width, 0.0)
+                    }
+                };
+                let leg_time = if speed > 0.0 {
+                    distance.abs() / speed
+                } else {
+                    0.0
+                };
+                MovingPlatform {
+                    bounds: object.bounds,
+                    origin: object.bounds,
+                    travel,
+                    leg_time,
+                    timer: Timer::repeating(2.0 * leg_time),
+                    velocity: (0.0, 0.0),
+                    tile_index: object.tile_index(),
+                    tile_span: object.tile_span(),
+                    carrying: false,
+                    active: true,
+                }
+            })
+            .collect();
+
+        Self { platforms }
+    }
+
+    /// Activates the platforms whose path lies within range of the camera and deactivates the rest.
+    ///
+    /// # Returns
+    ///
+    /// The number of platforms left active.
+    pub fn activate(&mut self, range: &ActivationRange) -> usize {
+        for platform in &mut self.platforms {
+            platform.active = range.contains(&platform.path_bounds());
+        }
+        self.platforms.iter().filter(|platform| platform.active).count()
+    }
+
+    /// Moves the active platforms, and lands the player on them or carries them along.
+    ///
+    /// A player already standing on a platform follows it up and down, and the
+    /// player's physics step carries them along with its horizontal movement.
+    ///
+    /// # Arguments
+    ///
+    /// * `game_state` - The player state to stand on or carry along with the platforms.
+    /// * `delta_time` - Time elapsed since the last frame.
+    pub fn update(&mut self, game_state: &mut GameState, delta_time: f32) {
+        for platform in self.platforms.iter_mut().filter(|platform| platform.active) {
+            platform.advance(delta_time);
+
+            let player = game_state.bounds();
+            let (_, velocity_y) = game_state.velocity();
+            let surface = platform.bounds.max_y;
+            let over = game_state.player_x >= platform.bounds.min_x && game_state.player_x <= platform.bounds.max_x;
+            let landing = player.min_y <= surface && player.min_y >= surface - LANDING_DEPTH;
+            // Jumping off or walking past the edge lets go of the player
+            platform.carrying = over && velocity_y <= 0.0 && (platform.carrying || landing);
+            if platform.carrying {
+                game_state.ride(surface, platform.velocity);
+            }
+        }
+    }
+}
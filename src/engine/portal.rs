@@ -0,0 +1,174 @@
+// portal.rs
+use crate::engine::collision::Aabb;
+use crate::engine::config::tuning;
+use crate::engine::constants::PORTAL_TRANSITION_TIME;
+use crate::engine::game_state::GameState;
+use crate::engine::level::Level;
+use log::warn;
+
+/// Glow color of portals, passed to the portal material as the instance tint.
+pub const PORTAL_TINT: [f32; 4] = [0.6, 0.3, 1.0, 0.9];
+
+/// How the player's velocity is carried out of a portal.
+#[derive(Clone, Copy)]
+enum ExitVelocity {
+    /// Keep the velocity the player entered with.
+    Preserve,
+    /// Launch the player upward with their entry speed.
+    Up,
+    /// Send the player downward with their entry speed.
+    Down,
+}
+
+/// A portal linked to another portal.
+pub struct Portal {
+    /// Area that triggers the portal, in world coordinates.
+    pub bounds: Aabb,
+    target: usize,
+    exit: ExitVelocity,
+    /// Time before any portal can be used again after arriving here, in seconds.
+    cooldown: f32,
+}
+
+/// A teleport in progress: fading out, moving, then fading back in.
+struct Transition {
+    elapsed: f32,
+    destination: usize,
+    entry_velocity: (f32, f32),
+    entry_position: (f32, f32),
+    arrived: bool,
+}
+
+/// Linked portals that teleport the player between them.
+///
+/// Portals come from `portal` objects in the level. Each names its destination
+/// in a `target` property; the destination's optional `exit` property
+/// (`preserve`, `up`, or `down`) decides the velocity the player leaves with,
+/// and its optional `cooldown` property overrides the tuned `portal_cooldown`. After
+/// arriving, the player has to wait out the cooldown and step out of the portal
+/// before any portal triggers again.
+pub struct Portals {
+    pub portals: Vec<Portal>,
+    cooldown: f32,
+    armed: bool,
+    transition: Option<Transition>,
+}
+
+impl Portals {
+    /// Creates the portals placed in the level, skipping any whose target doesn't exist.
+    pub fn from_level(level: &Level) -> Self {
+        let objects: Vec<_> = level.objects_of_kind("portal").collect();
+
+        let portals = objects
+            .iter()
+            .filter_map(|object| {
+                let target_name = object.property("target");
+                let target = objects
+                    .iter()
+                    .position(|other| Some(other.name.as_str()) == target_name);
+                if target.is_none() {
+                    warn!("Portal '{}' has unknown target {:?}", object.name, target_name);
+                }
+
+                Some(Portal {
+                    bounds: object.bounds,
+                    target: target?,
+                    exit: match object.property("exit") {
+                        Some("up") => ExitVelocity::Up,
+                        Some("down") => ExitVelocity::Down,
+                        _ => ExitVelocity::Preserve,
+                    },
+                    cooldown: object.property_f32("cooldown", tuning().portal_cooldown),
+                })
+            })
+            .collect();
+
+        Self {
+            portals,
+            cooldown: 0.0,
+            armed: true,
+            transition: None,
+        }
+    }
+
+    /// Starts a teleport when the player touches a portal, and advances any teleport in progress.
+    ///
+    /// # Arguments
+    ///
+    /// * `game_state` - The player state to move.
+    /// * `delta_time` - Time elapsed since the last frame.
+    pub fn update(&mut self, game_state: &mut GameState, delta_time: f32) {
+        if let Some(transition) = self.transition.as_mut() {
+            transition.elapsed += delta_time;
+
+            if !transition.arrived {
+                // Hold the player in place while fading out
+                let (x, y) = transition.entry_position;
+                game_state.teleport(x, y);
+
+                if transition.elapsed >= PORTAL_TRANSITION_TIME / 2.0 {
+                    let destination = &self.portals[transition.destination];
+                    let (vx, vy) = transition.entry_velocity;
+                    let speed = (vx * vx + vy * vy).sqrt();
+
+                    game_state.teleport(
+                        (destination.bounds.min_x + destination.bounds.max_x) / 2.0,
+                        (destination.bounds.min_y + destination.bounds.max_y) / 2.0,
+                    );
+                    match destination.exit {
+                        ExitVelocity::Preserve => game_state.set_velocity(vx, vy),
+                        ExitVelocity::Up => game_state.set_velocity(0.0, speed),
+                        ExitVelocity::Down => game_state.set_velocity(0.0, -speed),
+                    }
+
+                    transition.arrived = true;
+                    self.cooldown = destination.cooldown;
+                }
+            } else if transition.elapsed >= PORTAL_TRANSITION_TIME {
+                self.transition = None;
+            }
+            return;
+        }
+
+        self.cooldown = (self.cooldown - delta_time).max(0.0);
+
+        let player = game_state.bounds();
+        // Noclip flies straight through portals
+        let touching = self
+            .portals
+            .iter()
+            .position(|portal| portal.bounds.intersects(&player))
+            .filter(|_| !game_state.cheats.noclip);
+
+        match touching {
+            Some(index) if self.armed && self.cooldown <= 0.0 => {
+                self.armed = false;
+                self.transition = Some(Transition {
+                    elapsed: 0.0,
+                    destination: self.portals[index].target,
+                    entry_velocity: game_state.velocity(),
+                    entry_position: (game_state.player_x, game_state.player_y),
+                    arrived: false,
+                });
+            }
+            Some(_) => {}
+            None => self.armed = true,
+        }
+    }
+
+    /// Returns the player's opacity for the transition effect: fading out, then back in.
+    pub fn player_alpha(&self) -> f32 {
+        match &self.transition {
+            Some(transition) => {
+                let half = PORTAL_TRANSITION_TIME / 2.0;
+                let t = if transition.arrived {
+                    transition.elapsed - half
+                } else {
+                    half - transition.elapsed
+                };
+                (t / half).clamp(0.0, 1.0)
+            }
+            None => 1.0,
+        }
+    }
+}
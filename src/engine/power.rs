@@ -0,0 +1,112 @@
+// power.rs
+use crate::engine::config::LowPowerMode;
+use crate::engine::constants::LOW_POWER_FPS;
+use crate::engine::tween::Timer;
+
+/// How often to check whether the machine is running on battery, in seconds.
+const BATTERY_CHECK_INTERVAL: f32 = 5.0;
+
+/// Decides when to save power: holding to a lower frame rate and skipping
+/// post-processing, on battery or when the player asks.
+///
+/// Only rendering changes; the game still advances by real time elapsed, so
+/// it plays at the same speed either way.
+pub struct PowerSaver {
+    mode: LowPowerMode,
+    /// Whether the machine was on battery at the last check.
+    on_battery: bool,
+    /// Counts the time between battery checks.
+    check_timer: Timer,
+    /// Low-power mode as toggled by the player, overriding the configured mode.
+    toggled: Option<bool>,
+}
+
+impl PowerSaver {
+    /// Creates the power saver, checking the battery straight away.
+    ///
+    /// # Arguments
+    ///
+    /// * `mode` - When to save power, from the tuning config.
+    pub fn new(mode: LowPowerMode) -> Self {
+        Self {
+            mode,
+            on_battery: mode == LowPowerMode::Battery && on_battery(),
+            check_timer: Timer::repeating(BATTERY_CHECK_INTERVAL),
+            toggled: None,
+        }
+    }
+
+    /// Returns whether power is being saved.
+    pub fn is_low_power(&self) -> bool {
+        self.toggled.unwrap_or(match self.mode {
+            LowPowerMode::Off => false,
+            LowPowerMode::On => true,
+            LowPowerMode::Battery => self.on_battery,
+        })
+    }
+
+    /// Switches low-power mode the other way, until toggled back.
+    pub fn toggle(&mut self) {
+        self.toggled = Some(!self.is_low_power());
+    }
+
+    /// Checks the battery every few seconds when following it.
+    ///
+    /// # Arguments
+    ///
+    /// * `delta_time` - Real time elapsed since the last update.
+    ///
+    /// # Returns
+    ///
+    /// Whether the machine was plugged in or unplugged since the last check.
+    pub fn update(&mut self, delta_time: f32) -> bool {
+        if self.mode != LowPowerMode::Battery || !self.check_timer.tick(delta_time) {
+            return false;
+        }
+
+        let was_on_battery = self.on_battery;
+        self.on_battery = on_battery();
+        self.on_battery != was_on_battery
+    }
+
+    /// Returns the frame rate to hold to: at most `LOW_POWER_FPS` while saving power.
+    ///
+    /// # Arguments
+    ///
+    /// * `target_fps` - The configured frame rate; zero or less is uncapped.
+    pub fn target_fps(&self, target_fps: f32) -> f32 {
+        if self.is_low_power() && (target_fps <= 0.0 || target_fps > LOW_POWER_FPS) {
+            LOW_POWER_FPS
+        } else {
+            target_fps
+        }
+    }
+}
+
+/// Returns `true` if the machine is running on battery: it has a battery and
+/// no mains adapter is online. Always `false` where this can't be told.
+#[cfg(target_os = "linux")]
+fn on_battery() -> bool {
+    let Ok(supplies) = std::fs::read_dir("/sys/class/power_supply") else {
+        return false;
+    };
+    let read = |path: &std::path::Path, name: &str| {
+        std::fs::read_to_string(path.join(name)).map(|value| value.trim().to_string()).unwrap_or_default()
+    };
+
+    let mut has_battery = false;
+    for supply in supplies.flatten() {
+        let path = supply.path();
+        match read(&path, "type").as_str() {
+            "Mains" if read(&path, "online") == "1" => return false,
+            "Battery" => has_battery = true,
+            _ => {}
+        }
+    }
+    has_battery
+}
+
+#[cfg(not(target_os = "linux"))]
+fn on_battery() -> bool {
+    false
+}
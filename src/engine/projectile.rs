@@ -0,0 +1,376 @@
+// projectile.rs
+use crate::engine::activation::ActivationRange;
+use crate::engine::collision::Aabb;
+use crate::engine::combat::{Attack, KICK_DAMAGE, PROJECTILE_DAMAGE};
+use crate::engine::game_state::GameState;
+use crate::engine::level::Level;
+use crate::engine::pattern::{patterns, Barrage};
+use crate::engine::renderer::tile::TileMap;
+use crate::engine::tween::Timer;
+use log::{info, warn};
+
+/// Number of frames in the projectile sprite strip: two for the player's
+/// projectiles, then two for hostile ones.
+pub const PROJECTILE_FRAMES: usize = 4;
+
+/// Time each frame of a projectile's spin is shown, in seconds.
+const FRAME_TIME: f32 = 0.1;
+
+/// Width and height of a projectile, in world units.
+pub const PROJECTILE_SIZE: f32 = 0.12;
+
+/// Speed of the projectile the player kicks out, in world units per second.
+const KICK_SPEED: f32 = 2.5;
+
+/// How long a projectile flies before fizzling out, in seconds.
+const LIFETIME: f32 = 1.5;
+
+/// Time between a turret's shots when its `interval` property isn't set, in seconds.
+const DEFAULT_INTERVAL: f32 = 2.0;
+
+/// Speed of a turret's shots when its `speed` property isn't set, in tiles per second.
+const DEFAULT_SPEED: f32 = 4.0;
+
+/// Points a turret is worth when the player shoots it down, without a `value` property.
+const TURRET_VALUE: u32 = 100;
+
+/// Damage a turret takes before it's shot down, without a `health` property.
+const TURRET_HEALTH: u32 = 1;
+
+/// Who fired a projectile, deciding what it can hit.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Team {
+    /// Kicked out by the player; hits turrets.
+    Player,
+    /// Fired by a turret; hits the player.
+    Hostile,
+}
+
+/// A shot flying in a straight line until it hits something or fizzles out.
+#[derive(Clone)]
+pub struct Projectile {
+    /// Center of the projectile in world coordinates.
+    pub position: (f32, f32),
+    velocity: (f32, f32),
+    /// Time since the projectile was fired, in seconds.
+    age: f32,
+    pub team: Team,
+}
+
+impl Projectile {
+    /// Returns the area the projectile hits things with.
+    pub fn bounds(&self) -> Aabb {
+        Aabb::from_center(self.position.0, self.position.1, PROJECTILE_SIZE, PROJECTILE_SIZE)
+    }
+
+    /// Returns the frame of the projectile sprite strip to draw.
+    pub fn frame(&self) -> usize {
+        let first = match self.team {
+            Team::Player => 0,
+            Team::Hostile => 2,
+        };
+        first + (self.age / FRAME_TIME) as usize % 2
+    }
+
+    /// Returns `true` if the projectile flies to the right.
+    pub fn facing_right(&self) -> bool {
+        self.velocity.0 >= 0.0
+    }
+}
+
+/// A stationary shooter that fires at intervals until the player shoots it down.
+#[derive(Clone)]
+pub struct Turret {
+    /// The turret's identity, under which it is marked as destroyed.
+    name: String,
+    pub bounds: Aabb,
+    /// Velocity of its shots.
+    velocity: (f32, f32),
+    /// Direction it fires in, in degrees counterclockwise from the right.
+    direction: f32,
+    /// Name of the bullet pattern it fires instead of single shots, if any.
+    pattern: Option<String>,
+    /// Counts the time between shots.
+    fire_timer: Timer,
+    /// Points added to the score on shooting it down.
+    value: u32,
+    /// Damage the turret can still take before it's shot down.
+    health: u32,
+    /// Index of the tileset tile repeated along the turret.
+    pub tile_index: usize,
+    /// Columns and rows of tileset tiles making up each repeat of its art.
+    pub tile_span: (usize, usize),
+    /// Whether the turret is near enough to the camera to fire.
+    active: bool,
+    /// Whether the turret's script fires for it instead of its interval.
+    scripted: bool,
+}
+
+impl Turret {
+    /// Returns whether the player has already shot the turret down.
+    pub fn is_destroyed(&self, game_state: &GameState) -> bool {
+        game_state.has_collected(&self.name)
+    }
+
+    /// Returns the center of the turret.
+    fn center(&self) -> (f32, f32) {
+        ((self.bounds.min_x + self.bounds.max_x) / 2.0, (self.bounds.min_y + self.bounds.max_y) / 2.0)
+    }
+
+    /// Deals damage to the turret, shooting it down and adding its value to the
+    /// score once its health runs out.
+    fn hurt(&mut self, damage: u32, game_state: &mut GameState) {
+        self.health = self.health.saturating_sub(damage);
+        if self.health == 0 && game_state.collect(&self.name) {
+            game_state.score += self.value;
+            info!("Shot down {} for {} (score {})", self.name, self.value, game_state.score);
+        }
+    }
+}
+
+/// What a projectile ran into.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Target {
+    /// A solid tile of the level.
+    Tile,
+    /// A hostile projectile struck the player.
+    Player,
+    /// The player's kick or projectile struck a turret.
+    Turret,
+}
+
+/// Where a projectile hit something this update, and what it hit.
+pub struct Impact {
+    pub position: (f32, f32),
+    pub target: Target,
+}
+
+/// Projectiles kicked out by the player and fired by turrets.
+///
+/// Each kick sends a projectile flying the way the player faces. Turrets come from
+/// `turret` objects in the level: they fire every `interval` seconds towards
+/// `direction`, in degrees counterclockwise from the right (180, to the left, by
+/// default), at `speed` tiles per second. Projectiles stop at solid tiles. Turrets
+/// take `health` hits from the player's projectiles or kick, and a shot-down turret
+/// is worth `value` points and stays destroyed across saves. A turret's `pattern`
+/// property names a bullet pattern from the patterns file to fire each time instead of
+/// a single shot. Turrets with a `script` property fire only when their script says to.
+#[derive(Clone)]
+pub struct Projectiles {
+    pub projectiles: Vec<Projectile>,
+    pub turrets: Vec<Turret>,
+    /// Bullet patterns partway through their volleys, with the turret firing each, if one is.
+    barrages: Vec<(Option<usize>, Barrage)>,
+    /// World units per tile, which pattern speeds are given in.
+    tile_width: f32,
+    /// Whether the player was already kicking last frame, so each kick fires once.
+    was_kicking: bool,
+    /// The player's kick, striking turrets its hitbox reaches.
+    kick: Attack,
+}
+
+impl Projectiles {
+    /// Creates the turrets placed in the level, with no projectiles in flight.
+    pub fn from_level(level: &Level) -> Self {
+        let tile_width = level.tile_map.tile_width;
+        let turrets = level
+            .objects_of_kind("turret")
+            .map(|object| {
+                let direction = object.property_f32("direction", 180.0);
+                let speed = object.property_f32("speed", DEFAULT_SPEED) * tile_width;
+                let interval = object.property_f32("interval", DEFAULT_INTERVAL).max(0.1);
+                let pattern = object.property("pattern").map(str::to_string);
+                if let Some(pattern) = pattern.as_ref().filter(|pattern| !patterns().contains_key(*pattern)) {
+                    warn!("Turret '{}' fires unknown bullet pattern '{}'", object.name, pattern);
+                }
+                Turret {
+                    name: level.object_id(object).to_string(),
+                    bounds: object.bounds,
+                    velocity: (direction.to_radians().cos() * speed, direction.to_radians().sin() * speed),
+                    direction,
+                    pattern,
+                    fire_timer: Timer::repeating(interval),
+                    value: object.property_f32("value", TURRET_VALUE as f32) as u32,
+                    health: object.property_f32("health", TURRET_HEALTH as f32).max(1.0) as u32,
+                    tile_index: object.tile_index(),
+                    tile_span: object.tile_span(),
+                    active: true,
+                    scripted: object.property("script").is_some(),
+                }
+            })
+            .collect();
+
+        Self {
+            projectiles: Vec::new(),
+            turrets,
+            barrages: Vec::new(),
+            tile_width,
+            was_kicking: false,
+            kick: Attack::default(),
+        }
+    }
+
+    /// Activates the turrets within range of the camera and deactivates the rest.
+    ///
+    /// # Returns
+    ///
+    /// The number of turrets left active plus the projectiles in flight, which always move.
+    pub fn activate(&mut self, range: &ActivationRange) -> usize {
+        for turret in &mut self.turrets {
+            turret.active = range.contains(&turret.bounds);
+        }
+        self.turrets.iter().filter(|turret| turret.active).count() + self.projectiles.len()
+    }
+
+    /// Fires a hostile projectile, such as for a script.
+    ///
+    /// # Arguments
+    ///
+    /// * `position` - Where the projectile starts, in world coordinates.
+    /// * `velocity` - Its velocity, in world units per second.
+    pub fn fire(&mut self, position: (f32, f32), velocity: (f32, f32)) {
+        self.projectiles.push(Projectile {
+            position,
+            velocity,
+            age: 0.0,
+            team: Team::Hostile,
+        });
+    }
+
+    /// Starts firing a bullet pattern, such as for a script.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The pattern to fire, from the patterns file.
+    /// * `origin` - Where the shots come from, in world coordinates.
+    /// * `direction` - Direction to fire the pattern in, in degrees counterclockwise from the right.
+    pub fn emit(&mut self, name: &str, origin: (f32, f32), direction: f32) {
+        match patterns().get(name) {
+            Some(pattern) => self.barrages.push((None, Barrage::new(pattern.clone(), origin, direction))),
+            None => warn!("Unknown bullet pattern '{}'", name),
+        }
+    }
+
+    /// Fires new projectiles, moves them, and resolves what they and the player's kick hit.
+    ///
+    /// Shooting down a turret adds its value to the score; a hostile projectile
+    /// reaching the player's hurtbox is left to the caller to act on.
+    ///
+    /// # Arguments
+    ///
+    /// * `game_state` - Provides the player's position and kick, and records the score and destroyed turrets.
+    /// * `tile_map` - The solid tiles that stop projectiles.
+    /// * `delta_time` - Time elapsed since the last frame.
+    ///
+    /// # Returns
+    ///
+    /// Where projectiles hit something this frame.
+    pub fn update(&mut self, game_state: &mut GameState, tile_map: &TileMap, delta_time: f32) -> Vec<Impact> {
+        let kicked = game_state.is_kicking() && !self.was_kicking;
+        self.was_kicking = game_state.is_kicking();
+        if kicked {
+            let player = game_state.bounds();
+            let direction = if game_state.facing_right { 1.0 } else { -1.0 };
+            self.projectiles.push(Projectile {
+                position: (game_state.player_x, (player.min_y + player.max_y) / 2.0),
+                velocity: (direction * KICK_SPEED, 0.0),
+                age: 0.0,
+                team: Team::Player,
+            });
+        }
+
+        for (index, turret) in self.turrets.iter_mut().enumerate() {
+            if !turret.active || turret.scripted || game_state.has_collected(&turret.name) {
+                continue;
+            }
+            if turret.fire_timer.tick(delta_time) {
+                match turret.pattern.as_ref().and_then(|name| patterns().get(name)) {
+                    Some(pattern) => {
+                        let barrage = Barrage::new(pattern.clone(), turret.center(), turret.direction);
+                        self.barrages.push((Some(index), barrage));
+                    }
+                    None => self.projectiles.push(Projectile {
+                        position: turret.center(),
+                        velocity: turret.velocity,
+                        age: 0.0,
+                        team: Team::Hostile,
+                    }),
+                }
+            }
+        }
+
+        // Fire the volleys of patterns that are due, dropping those of shot-down turrets
+        let target = game_state.hurtbox();
+        let target = ((target.min_x + target.max_x) / 2.0, (target.min_y + target.max_y) / 2.0);
+        let turrets = &self.turrets;
+        self.barrages.retain(|(owner, _)| owner.is_none_or(|owner| !turrets[owner].is_destroyed(game_state)));
+        for (_, barrage) in &mut self.barrages {
+            let speed = barrage.pattern.speed * self.tile_width;
+            let scatter = barrage.pattern.scatter.abs();
+            for direction in barrage.update(target, delta_time) {
+                let direction = (direction + game_state.rng().range(-scatter, scatter)).to_radians();
+                self.projectiles.push(Projectile {
+                    position: barrage.origin,
+                    velocity: (direction.cos() * speed, direction.sin() * speed),
+                    age: 0.0,
+                    team: Team::Hostile,
+                });
+            }
+        }
+        self.barrages.retain(|(_, barrage)| !barrage.is_finished());
+
+        // Strike the turrets the kick's hitbox reaches
+        let hurtboxes = self
+            .turrets
+            .iter()
+            .map(|turret| (!turret.is_destroyed(game_state)).then_some(turret.bounds))
+            .collect::<Vec<_>>();
+        let mut impacts = Vec::new();
+        for index in self.kick.strike(game_state.hitbox(), hurtboxes) {
+            let turret = &mut self.turrets[index];
+            turret.hurt(KICK_DAMAGE, game_state);
+            impacts.push(Impact {
+                position: turret.center(),
+                target: Target::Turret,
+            });
+        }
+
+        let player = game_state.hurtbox();
+        let turrets = &mut self.turrets;
+        self.projectiles.retain_mut(|projectile| {
+            projectile.age += delta_time;
+            projectile.position.0 += projectile.velocity.0 * delta_time;
+            projectile.position.1 += projectile.velocity.1 * delta_time;
+            if projectile.age >= LIFETIME {
+                return false;
+            }
+
+            let bounds = projectile.bounds();
+            let target = match projectile.team {
+                Team::Hostile if bounds.intersects(&player) => Some(Target::Player),
+                Team::Player => turrets
+                    .iter_mut()
+                    .find(|turret| !turret.is_destroyed(game_state) && bounds.intersects(&turret.bounds))
+                    .map(|turret| {
+                        turret.hurt(PROJECTILE_DAMAGE, game_state);
+                        Target::Turret
+                    }),
+                Team::Hostile => None,
+            };
+            let (x, y) = projectile.position;
+            let target = target.or_else(|| tile_map.is_solid_at(x, y).then_some(Target::Tile));
+
+            match target {
+                Some(target) => {
+                    impacts.push(Impact {
+                        position: projectile.position,
+                        target,
+                    });
+                    false
+                }
+                None => true,
+            }
+        });
+        impacts
+    }
+}
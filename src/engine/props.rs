@@ -0,0 +1,237 @@
+// props.rs
+use crate::engine::camera::Camera;
+use crate::engine::rng::Rng;
+use crate::engine::scheduler::Scheduler;
+
+/// Number of props kept in the pool; nothing new spawns while all of them are in use.
+const POOL_SIZE: usize = 24;
+
+/// How far past the edge of the view props spawn and despawn, in view units.
+const EDGE_MARGIN: f32 = 0.3;
+
+/// Number of frames in the prop sprite strip.
+pub const PROP_FRAMES: usize = 4;
+
+/// Time each frame of a bird's wing flap is shown, in seconds.
+const FLAP_TIME: f32 = 0.15;
+
+/// The kinds of decorative prop.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum PropKind {
+    /// Drifts slowly across the far background layers.
+    Cloud,
+    /// Flaps across the nearest background layer.
+    Bird,
+}
+
+impl PropKind {
+    /// Every kind, in spawn timer order.
+    const ALL: [PropKind; 2] = [PropKind::Cloud, PropKind::Bird];
+
+    /// Returns the range of seconds between spawns, as `(min, max)`.
+    fn spawn_interval(self) -> (f32, f32) {
+        match self {
+            PropKind::Cloud => (2.5, 6.0),
+            PropKind::Bird => (4.0, 10.0),
+        }
+    }
+
+    /// Returns the layers props of this kind are drawn on, as `(depth, parallax)`.
+    ///
+    /// Depths sit between the background images, and farther layers scroll a
+    /// smaller fraction of the camera's movement.
+    fn layers(self) -> &'static [(f32, f32)] {
+        match self {
+            PropKind::Cloud => &[(0.9, 0.1), (0.7, 0.3)],
+            PropKind::Bird => &[(0.65, 0.5)],
+        }
+    }
+}
+
+/// A decorative sprite moving across one of the background layers.
+struct Prop {
+    kind: PropKind,
+    /// Horizontal position in layer space, before the layer's parallax scroll.
+    x: f32,
+    y: f32,
+    velocity_x: f32,
+    depth: f32,
+    /// Fraction of the camera's movement the prop's layer scrolls by.
+    parallax: f32,
+    size: f32,
+    /// First frame of the prop's sprite in the strip.
+    first_frame: usize,
+    /// Time since the prop spawned, used to animate it.
+    age: f32,
+    active: bool,
+}
+
+/// How to draw a prop this frame.
+pub struct PropSprite {
+    /// Position in view space, as `(x, y)`.
+    pub position: (f32, f32),
+    pub depth: f32,
+    pub size: f32,
+    /// Frame of the prop sprite strip to draw.
+    pub frame: usize,
+    pub facing_right: bool,
+}
+
+/// Spawns clouds and birds that drift across the background, purely for decoration.
+///
+/// Props are kept in a fixed pool: spawning reuses an inactive slot, and props
+/// are deactivated once they have moved off the far side of the view.
+pub struct Props {
+    pool: Vec<Prop>,
+    /// The next spawn of each kind, due after a random delay.
+    spawns: Scheduler<PropKind>,
+    /// Varies the props.
+    rng: Rng,
+}
+
+impl Props {
+    /// Creates the prop pool with a few clouds already in view.
+    ///
+    /// The same generator always places the same props, such as for the rendering
+    /// regression tests.
+    ///
+    /// # Arguments
+    ///
+    /// * `camera` - The camera, to place the first clouds relative to.
+    /// * `rng` - Varies the props.
+    pub fn new(camera: &Camera, rng: Rng) -> Self {
+        let mut props = Self {
+            pool: (0..POOL_SIZE)
+                .map(|_| Prop {
+                    kind: PropKind::Cloud,
+                    x: 0.0,
+                    y: 0.0,
+                    velocity_x: 0.0,
+                    depth: 0.0,
+                    parallax: 0.0,
+                    size: 0.0,
+                    first_frame: 0,
+                    age: 0.0,
+                    active: false,
+                })
+                .collect(),
+            spawns: Scheduler::new(),
+            rng,
+        };
+
+        for kind in PropKind::ALL {
+            let delay = props.spawn_delay(kind);
+            props.spawns.after(delay, kind);
+        }
+        for _ in 0..3 {
+            let view_x = props.rng.range(-1.0, 1.0);
+            props.spawn(PropKind::Cloud, camera, Some(view_x));
+        }
+
+        props
+    }
+
+    /// Varies the props from now on with another generator, such as when the run's
+    /// seed changes.
+    pub fn set_rng(&mut self, rng: Rng) {
+        self.rng = rng;
+    }
+
+    /// Returns the generator varying the props, as far as it has got.
+    pub fn rng(&self) -> Rng {
+        self.rng
+    }
+
+    /// Moves and animates the props, despawns those that left the view, and spawns new ones.
+    ///
+    /// # Arguments
+    ///
+    /// * `camera` - The camera, whose scroll decides which props are in view.
+    /// * `delta_time` - Time elapsed since the last frame.
+    pub fn update(&mut self, camera: &Camera, delta_time: f32) {
+        let edge = 1.0 + EDGE_MARGIN;
+        for prop in self.pool.iter_mut().filter(|prop| prop.active) {
+            prop.x += prop.velocity_x * delta_time;
+            prop.age += delta_time;
+
+            // Despawn props past the edge they are heading away from, sparing ones
+            // that just spawned on the other side unless the camera left them far behind
+            let view_x = prop.x - camera.x * prop.parallax;
+            let passed = (prop.velocity_x > 0.0 && view_x > edge) || (prop.velocity_x < 0.0 && view_x < -edge);
+            if passed || view_x.abs() > edge + 1.0 {
+                prop.active = false;
+            }
+        }
+
+        for kind in self.spawns.update(delta_time) {
+            self.spawn(kind, camera, None);
+            let delay = self.spawn_delay(kind);
+            self.spawns.after(delay, kind);
+        }
+    }
+
+    /// Returns how to draw each active prop.
+    ///
+    /// # Arguments
+    ///
+    /// * `camera` - The camera, whose scroll offsets each layer.
+    pub fn sprites<'a>(&'a self, camera: &'a Camera) -> impl Iterator<Item = PropSprite> + 'a {
+        self.pool.iter().filter(|prop| prop.active).map(|prop| PropSprite {
+            position: (prop.x - camera.x * prop.parallax, prop.y),
+            depth: prop.depth,
+            size: prop.size,
+            frame: match prop.kind {
+                PropKind::Cloud => prop.first_frame,
+                PropKind::Bird => prop.first_frame + (prop.age / FLAP_TIME) as usize % 2,
+            },
+            facing_right: prop.velocity_x >= 0.0,
+        })
+    }
+
+    /// Activates a pooled prop of the given kind, unless the pool is exhausted.
+    ///
+    /// Without a view position the prop starts just off the edge it moves away from.
+    fn spawn(&mut self, kind: PropKind, camera: &Camera, view_x: Option<f32>) {
+        let direction = if self.rng.chance(0.5) { -1.0 } else { 1.0 };
+        let view_x = view_x.unwrap_or(-direction * (1.0 + EDGE_MARGIN));
+        let layers = kind.layers();
+        let (depth, parallax) = layers[self.rng.index(layers.len())];
+
+        let (speed, y, size, first_frame) = match kind {
+            PropKind::Cloud => (
+                self.rng.range(0.03, 0.08),
+                self.rng.range(0.3, 0.9),
+                self.rng.range(0.3, 0.5),
+                if self.rng.chance(0.5) { 0 } else { 1 },
+            ),
+            PropKind::Bird => (
+                self.rng.range(0.3, 0.5),
+                self.rng.range(0.1, 0.7),
+                self.rng.range(0.12, 0.16),
+                2,
+            ),
+        };
+
+        let Some(prop) = self.pool.iter_mut().find(|prop| !prop.active) else {
+            return;
+        };
+        *prop = Prop {
+            kind,
+            x: view_x + camera.x * parallax,
+            y,
+            velocity_x: speed * direction,
+            depth,
+            parallax,
+            size,
+            first_frame,
+            age: 0.0,
+            active: true,
+        };
+    }
+
+    /// Returns a random delay before the next spawn of the given kind.
+    fn spawn_delay(&mut self, kind: PropKind) -> f32 {
+        let (min, max) = kind.spawn_interval();
+        self.rng.range(min, max)
+    }
+}
@@ -0,0 +1,100 @@
+// autotile.rs
+use crate::engine::renderer::tile::Tile;
+use std::collections::HashSet;
+
+/// Neighbor bits of a cell, set where the cell in that direction holds a tile of the
+/// same terrain.
+const UP: usize = 1;
+const RIGHT: usize = 2;
+const DOWN: usize = 4;
+const LEFT: usize = 8;
+
+/// Picks which tile of a terrain to draw in each cell, so its edges, corners, and
+/// interior match up whatever shape it's painted in.
+///
+/// A tile belongs to the terrain if it's any of the terrain's tiles; which one is
+/// drawn then depends only on which of its four neighbors also belong.
+pub struct AutotileRules {
+    /// Name the rules are chosen by, in a tile layer's `autotile` property.
+    pub name: &'static str,
+    /// The tile to draw for each combination of neighbor bits.
+    tiles: [usize; 16],
+}
+
+/// Grass-topped dirt from the top-left of the tileset: a 3x3 block of corners, edges,
+/// and interior, plus a lone rounded tile.
+pub const GROUND: AutotileRules = AutotileRules {
+    name: "ground",
+    tiles: [
+        21, // On its own
+        17, // UP: the bottom of a column
+        0,  // RIGHT
+        16, // UP | RIGHT: bottom-left corner
+        1,  // DOWN: the top of a column
+        9,  // UP | DOWN
+        0,  // RIGHT | DOWN: top-left corner
+        8,  // UP | RIGHT | DOWN: left edge
+        2,  // LEFT
+        18, // UP | LEFT: bottom-right corner
+        1,  // LEFT | RIGHT: a strip one tile high
+        17, // UP | LEFT | RIGHT: bottom edge
+        2,  // LEFT | DOWN: top-right corner
+        10, // UP | LEFT | DOWN: right edge
+        1,  // LEFT | RIGHT | DOWN: top edge
+        9,  // Surrounded: interior
+    ],
+};
+
+/// Every set of rules, for looking them up by name.
+const RULES: [&AutotileRules; 1] = [&GROUND];
+
+impl AutotileRules {
+    /// Returns the rules with the given name, if there are any.
+    pub fn named(name: &str) -> Option<&'static AutotileRules> {
+        RULES.into_iter().find(|rules| rules.name == name)
+    }
+
+    /// Returns whether a tile belongs to the terrain.
+    fn contains(&self, tile_index: usize) -> bool {
+        self.tiles.contains(&tile_index)
+    }
+
+    /// Repicks the tile of every cell of the terrain among the tiles, from its neighbors.
+    ///
+    /// Tiles not of the terrain, and oversized ones, are left alone and don't count
+    /// as neighbors. Call again after adding or removing tiles to fix up the cells
+    /// around them.
+    ///
+    /// # Arguments
+    ///
+    /// * `tiles` - The tiles of one layer, each a grid cell apart.
+    /// * `tile_width`, `tile_height` - Size of one grid cell in world units.
+    pub fn apply(&self, tiles: &mut [Tile], tile_width: f32, tile_height: f32) {
+        let Some(origin) = tiles.first().map(|tile| tile.position) else {
+            return;
+        };
+        let is_terrain = |tile: &Tile| tile.span == (1, 1) && self.contains(tile.tile_index);
+        // Cells counted from the first tile, so positions off the grid's origin round the same way
+        let cell = |tile: &Tile| {
+            (
+                ((tile.position.0 - origin.0) / tile_width).round() as i32,
+                ((tile.position.1 - origin.1) / tile_height).round() as i32,
+            )
+        };
+
+        let filled: HashSet<(i32, i32)> = tiles.iter().filter(|tile| is_terrain(tile)).map(cell).collect();
+        for tile in tiles.iter_mut().filter(|tile| is_terrain(tile)) {
+            let (column, row) = cell(tile);
+            let neighbors = [
+                (UP, (column, row + 1)),
+                (RIGHT, (column + 1, row)),
+                (DOWN, (column, row - 1)),
+                (LEFT, (column - 1, row)),
+            ]
+            .into_iter()
+            .filter(|(_, neighbor)| filled.contains(neighbor))
+            .fold(0, |bits, (bit, _)| bits | bit);
+            tile.tile_index = self.tiles[neighbors];
+        }
+    }
+}
@@ -0,0 +1,98 @@
+// batcher.rs
+use crate::engine::renderer::instance::InstanceData;
+use crate::engine::renderer::sprite_array::SpriteLayer;
+use std::ops::Range;
+
+/// Gathers a frame's instances into one list, so they reach the instance buffer in a
+/// single upload.
+///
+/// Each group pushed is a batch drawn with one material. Batches are laid out back to
+/// back in the order they're pushed, and each is drawn from its range of the buffer;
+/// consecutive sprite batches can be drawn together, as they all sample the sprite
+/// array. The list is kept between frames to reuse its allocation.
+pub struct InstanceBatcher {
+    instances: Vec<InstanceData>,
+    /// Where each batch sits in the instance buffer, in the order they were pushed.
+    ranges: Vec<Range<u32>>,
+}
+
+impl InstanceBatcher {
+    /// Creates an empty batcher.
+    pub fn new() -> Self {
+        Self {
+            instances: Vec::new(),
+            ranges: Vec::new(),
+        }
+    }
+
+    /// Empties the batcher for the next frame.
+    pub fn clear(&mut self) {
+        self.instances.clear();
+        self.ranges.clear();
+    }
+
+    /// Appends a batch after those already pushed. Empty batches still get a range,
+    /// so the ranges line up with the pushes.
+    ///
+    /// # Arguments
+    /// - `group`: The instances drawn together with one material.
+    /// - `layer`: The sprite array layer the instances sample, set on each of them, or
+    ///   `None` for materials that don't sample the sprite array.
+    pub fn push(&mut self, group: &[InstanceData], layer: Option<SpriteLayer>) {
+        let start = self.instances.len() as u32;
+        self.instances.extend_from_slice(group);
+        if let Some(layer) = layer {
+            for instance in &mut self.instances[start as usize..] {
+                instance.layer = layer.index();
+            }
+        }
+        self.ranges.push(start..self.instances.len() as u32);
+    }
+
+    /// Returns the number of instances in all the batches pushed.
+    pub fn len(&self) -> usize {
+        self.instances.len()
+    }
+
+    /// Returns whether no instances have been pushed.
+    pub fn is_empty(&self) -> bool {
+        self.instances.is_empty()
+    }
+
+    /// Writes every batch into the instance buffer at once.
+    ///
+    /// The buffer should first be grown to hold them all with `Renderer::reserve_instances`.
+    /// Instances past the end of a buffer that's still too small are left out, with a
+    /// warning, rather than overflowing it.
+    ///
+    /// # Arguments
+    /// - `queue`: The queue to write with.
+    /// - `buffer`: The instance buffer.
+    ///
+    /// # Returns
+    /// The number of bytes written.
+    pub fn upload(&self, queue: &wgpu::Queue, buffer: &wgpu::Buffer) -> u64 {
+        if self.instances.is_empty() {
+            return 0;
+        }
+        let mut bytes: &[u8] = bytemuck::cast_slice(&self.instances);
+        if bytes.len() as u64 > buffer.size() {
+            let fits = buffer.size() as usize / std::mem::size_of::<InstanceData>();
+            log::warn!("Only {} of {} instances fit in the instance buffer", fits, self.instances.len());
+            bytes = &bytes[..fits * std::mem::size_of::<InstanceData>()];
+        }
+        queue.write_buffer(buffer, 0, bytes);
+        bytes.len() as u64
+    }
+
+    /// Returns where each batch sits in the instance buffer, in the order they were pushed.
+    pub fn ranges(&self) -> &[Range<u32>] {
+        &self.ranges
+    }
+}
+
+impl Default for InstanceBatcher {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,45 @@
+// globals.rs
+use bytemuck::{Pod, Zeroable};
+use log::info;
+
+/// Per-frame values shared by material shaders, bound as a uniform buffer.
+#[repr(C)]
+#[derive(Copy, Clone, Pod, Zeroable)]
+pub struct Globals {
+    pub time: f32,       // Seconds since the game started
+    pub camera_x: f32,   // Camera position, to recover world positions in shaders
+    pub camera_y: f32,
+    pub zoom: f32,       // Camera magnification, to undo it when recovering world positions
+    pub reflection: f32, // Strength of water reflections; zero without the scene texture to reflect
+    pub saturation: f32, // Color kept by the last pass of the frame; zero is grey
+    pub vignette: f32,   // How much the last pass darkens the screen's corners
+    pub contrast: f32,   // Contrast the last pass grades the color to; one leaves it as it is
+    pub fade: f32,       // How far the last pass fades the frame to black
+    pub _padding: [f32; 3],
+}
+
+/// Creates a bind group layout for the globals uniform buffer.
+///
+/// The buffer is bound at binding 0 and visible to both vertex and fragment stages.
+///
+/// # Arguments
+/// - `device`: The `wgpu::Device` used to create the bind group layout.
+///
+/// # Returns
+/// A `wgpu::BindGroupLayout` for a single uniform buffer.
+pub fn create_globals_bind_group_layout(device: &wgpu::Device) -> wgpu::BindGroupLayout {
+    info!("Creating globals bind group layout");
+    device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+        label: Some("Globals Bind Group Layout"),
+        entries: &[wgpu::BindGroupLayoutEntry {
+            binding: 0,
+            visibility: wgpu::ShaderStages::VERTEX | wgpu::ShaderStages::FRAGMENT,
+            ty: wgpu::BindingType::Buffer {
+                ty: wgpu::BufferBindingType::Uniform,
+                has_dynamic_offset: false,
+                min_binding_size: None,
+            },
+            count: None,
+        }],
+    })
+}
@@ -0,0 +1,337 @@
+// gpu_particles.rs
+use bytemuck::{Pod, Zeroable};
+use log::info;
+use wgpu::util::DeviceExt;
+
+use crate::engine::renderer::layer::RenderLayer;
+use crate::engine::renderer::vertex::Vertex;
+
+/// Particle slots reserved for looping weather, at the start of the particle buffer.
+pub const WEATHER_CAPACITY: u32 = 8192;
+
+/// Particle slots for bursts, after the weather slots. Bursts reuse them in turn,
+/// overwriting the oldest particles once all are in use.
+pub const BURST_CAPACITY: u32 = 8192;
+
+/// Invocations per compute workgroup; matches `@workgroup_size` in the shader.
+const WORKGROUP_SIZE: u32 = 64;
+
+/// One particle as laid out in the particle buffer; matches `Particle` in particles.wgsl.
+#[repr(C)]
+#[derive(Copy, Clone, Pod, Zeroable)]
+pub struct GpuParticle {
+    pub position: [f32; 2],
+    pub velocity: [f32; 2],
+    pub start_velocity: [f32; 2], // Velocity looping particles respawn with
+    pub age: f32,
+    pub lifetime: f32,             // Zero for unused slots
+    pub size: [f32; 2],            // Size at the start and end of its life
+    pub gravity: f32,
+    pub looping: f32,              // 1.0 for weather that respawns along the top of the view
+    pub color: [f32; 4],           // Color at the start of its life
+    pub end_color: [f32; 4],       // Color at the end of its life
+    pub aspect: f32,               // Height relative to width, for streaks like rain
+    pub _padding: [f32; 3],
+    // Total size: 96 bytes (aligned to 16 bytes)
+}
+
+/// Per-frame simulation values; matches `Params` in particles.wgsl.
+#[repr(C)]
+#[derive(Copy, Clone, Pod, Zeroable)]
+struct Params {
+    delta_time: f32,
+    seed: u32,      // Drawn from the run's random numbers each frame, to vary respawn positions
+    camera_x: f32,
+    camera_y: f32,
+    zoom: f32,
+    depth: f32,     // Depth the particles are drawn at
+    _padding: [f32; 2],
+}
+
+/// Particles simulated by a compute shader and drawn straight from the GPU buffer
+/// it updates, for heavy effects like weather and large explosions.
+///
+/// The CPU only writes particles when they are spawned; after that they live
+/// entirely on the GPU, so nothing in the game can react to them. Gameplay-relevant
+/// particles belong in the CPU effects instead.
+pub struct GpuParticles {
+    particle_buffer: wgpu::Buffer,
+    params_buffer: wgpu::Buffer,
+    compute_bind_group: wgpu::BindGroup,
+    render_bind_group: wgpu::BindGroup,
+    compute_pipeline: wgpu::ComputePipeline,
+    render_pipeline: wgpu::RenderPipeline,
+    /// Slot the next burst particle is written to, counted from the first burst slot.
+    burst_cursor: u32,
+}
+
+impl GpuParticles {
+    /// Creates the particle buffer, with every slot unused, and the pipelines that
+    /// simulate and draw it.
+    ///
+    /// # Arguments
+    /// - `device`: The `wgpu::Device` used to create GPU resources.
+    /// - `config`: The surface configuration, for the color format drawn to.
+    /// - `sample_count`: Samples per pixel of the targets drawn to.
+    pub fn new(device: &wgpu::Device, config: &wgpu::SurfaceConfiguration, sample_count: u32) -> Self {
+        info!("Creating GPU particles");
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Particles Shader"),
+            source: wgpu::ShaderSource::Wgsl(include_str!("shaders/particles.wgsl").into()),
+        });
+
+        let capacity = (WEATHER_CAPACITY + BURST_CAPACITY) as usize;
+        let particle_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Particle Buffer"),
+            contents: bytemuck::cast_slice(&vec![GpuParticle::zeroed(); capacity]),
+            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+        });
+        let params_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Particle Params Buffer"),
+            contents: bytemuck::bytes_of(&Params::zeroed()),
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        });
+
+        // The simulation reads and writes the particles, so it binds them as storage. Drawing
+        // reads them as instances instead, since a pass can't use a buffer both ways, and
+        // only binds the params
+        let params_entry = wgpu::BindGroupLayoutEntry {
+            binding: 0,
+            visibility: wgpu::ShaderStages::COMPUTE | wgpu::ShaderStages::VERTEX,
+            ty: wgpu::BindingType::Buffer {
+                ty: wgpu::BufferBindingType::Uniform,
+                has_dynamic_offset: false,
+                min_binding_size: None,
+            },
+            count: None,
+        };
+        let compute_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Particles Compute Bind Group Layout"),
+            entries: &[
+                params_entry,
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Storage { read_only: false },
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+            ],
+        });
+        let render_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Particles Render Bind Group Layout"),
+            entries: &[params_entry],
+        });
+        let compute_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Particles Compute Bind Group"),
+            layout: &compute_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: params_buffer.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: particle_buffer.as_entire_binding(),
+                },
+            ],
+        });
+        let render_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Particles Render Bind Group"),
+            layout: &render_layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: params_buffer.as_entire_binding(),
+            }],
+        });
+
+        let compute_pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+            label: Some("Particles Compute Pipeline"),
+            layout: Some(&device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+                label: Some("Particles Compute Pipeline Layout"),
+                bind_group_layouts: &[&compute_layout],
+                push_constant_ranges: &[],
+            })),
+            module: &shader,
+            entry_point: "cs_main",
+        });
+
+        // Each particle is an instance of the quad, read from the particle buffer
+        let particle_attributes = [
+            wgpu::VertexAttribute {
+                offset: 0, // position
+                shader_location: 2,
+                format: wgpu::VertexFormat::Float32x2,
+            },
+            wgpu::VertexAttribute {
+                offset: 24, // age
+                shader_location: 3,
+                format: wgpu::VertexFormat::Float32,
+            },
+            wgpu::VertexAttribute {
+                offset: 28, // lifetime
+                shader_location: 4,
+                format: wgpu::VertexFormat::Float32,
+            },
+            wgpu::VertexAttribute {
+                offset: 32, // size
+                shader_location: 5,
+                format: wgpu::VertexFormat::Float32x2,
+            },
+            wgpu::VertexAttribute {
+                offset: 48, // color
+                shader_location: 6,
+                format: wgpu::VertexFormat::Float32x4,
+            },
+            wgpu::VertexAttribute {
+                offset: 64, // end_color
+                shader_location: 7,
+                format: wgpu::VertexFormat::Float32x4,
+            },
+            wgpu::VertexAttribute {
+                offset: 80, // aspect
+                shader_location: 8,
+                format: wgpu::VertexFormat::Float32,
+            },
+        ];
+        let vertex_layouts = [
+            Vertex::descriptor(),
+            wgpu::VertexBufferLayout {
+                array_stride: std::mem::size_of::<GpuParticle>() as wgpu::BufferAddress,
+                step_mode: wgpu::VertexStepMode::Instance,
+                attributes: &particle_attributes,
+            },
+        ];
+
+        let render_pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Particles Render Pipeline"),
+            layout: Some(&device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+                label: Some("Particles Render Pipeline Layout"),
+                bind_group_layouts: &[&render_layout],
+                push_constant_ranges: &[],
+            })),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: "vs_main",
+                buffers: &vertex_layouts,
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: "fs_main",
+                targets: &[Some(wgpu::ColorTargetState {
+                    format: config.format,
+                    blend: Some(wgpu::BlendState::ALPHA_BLENDING),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+            }),
+            primitive: wgpu::PrimitiveState::default(),
+            // Particles overlap heavily, so they test depth without writing it
+            depth_stencil: Some(wgpu::DepthStencilState {
+                format: wgpu::TextureFormat::Depth32Float,
+                depth_write_enabled: false,
+                depth_compare: wgpu::CompareFunction::Less,
+                stencil: wgpu::StencilState::default(),
+                bias: wgpu::DepthBiasState::default(),
+            }),
+            multisample: wgpu::MultisampleState {
+                count: sample_count,
+                ..Default::default()
+            },
+            multiview: None,
+        });
+
+        Self {
+            particle_buffer,
+            params_buffer,
+            compute_bind_group,
+            render_bind_group,
+            compute_pipeline,
+            render_pipeline,
+            burst_cursor: 0,
+        }
+    }
+
+    /// Replaces the looping weather particles, clearing the weather slots they don't fill.
+    ///
+    /// # Arguments
+    /// - `queue`: The queue to write the particles with.
+    /// - `particles`: The weather particles; any past `WEATHER_CAPACITY` are dropped.
+    pub fn set_weather(&self, queue: &wgpu::Queue, particles: &[GpuParticle]) {
+        let mut slots = vec![GpuParticle::zeroed(); WEATHER_CAPACITY as usize];
+        let count = particles.len().min(slots.len());
+        slots[..count].copy_from_slice(&particles[..count]);
+        queue.write_buffer(&self.particle_buffer, 0, bytemuck::cast_slice(&slots));
+    }
+
+    /// Writes newly spawned burst particles into the next burst slots.
+    ///
+    /// # Arguments
+    /// - `queue`: The queue to write the particles with.
+    /// - `particles`: The particles to spawn; any past `BURST_CAPACITY` are dropped.
+    pub fn spawn(&mut self, queue: &wgpu::Queue, particles: &[GpuParticle]) {
+        let particle_size = std::mem::size_of::<GpuParticle>() as wgpu::BufferAddress;
+        let mut remaining = &particles[..particles.len().min(BURST_CAPACITY as usize)];
+        while !remaining.is_empty() {
+            // Write up to the end of the burst slots, then wrap around to the first
+            let room = (BURST_CAPACITY - self.burst_cursor) as usize;
+            let (now, later) = remaining.split_at(room.min(remaining.len()));
+            let offset = (WEATHER_CAPACITY + self.burst_cursor) as wgpu::BufferAddress * particle_size;
+            queue.write_buffer(&self.particle_buffer, offset, bytemuck::cast_slice(now));
+            self.burst_cursor = (self.burst_cursor + now.len() as u32) % BURST_CAPACITY;
+            remaining = later;
+        }
+    }
+
+    /// Uploads the values the next simulation step and draw read.
+    ///
+    /// # Arguments
+    /// - `queue`: The queue to write the values with.
+    /// - `delta_time`: Game time to advance the particles by; zero holds them still.
+    /// - `seed`: A fresh random number from the run's seed, varying where particles respawn.
+    /// - `camera_x`, `camera_y`: The camera position.
+    /// - `zoom`: The camera magnification.
+    pub fn update(&self, queue: &wgpu::Queue, delta_time: f32, seed: u32, camera_x: f32, camera_y: f32, zoom: f32) {
+        let params = Params {
+            delta_time,
+            seed,
+            camera_x,
+            camera_y,
+            zoom,
+            // In front of the CPU particles
+            depth: RenderLayer::Particles.depth(1),
+            _padding: [0.0; 2],
+        };
+        queue.write_buffer(&self.params_buffer, 0, bytemuck::bytes_of(&params));
+    }
+
+    /// Records the compute pass advancing every particle by the uploaded time step.
+    ///
+    /// # Arguments
+    /// - `encoder`: The command encoder to record the pass into, before the render pass.
+    pub fn simulate(&self, encoder: &mut wgpu::CommandEncoder) {
+        let mut compute_pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+            label: Some("Particles Compute Pass"),
+        });
+        compute_pass.set_pipeline(&self.compute_pipeline);
+        compute_pass.set_bind_group(0, &self.compute_bind_group, &[]);
+        let capacity = WEATHER_CAPACITY + BURST_CAPACITY;
+        compute_pass.dispatch_workgroups(capacity.div_ceil(WORKGROUP_SIZE), 1, 1);
+    }
+
+    /// Draws every particle slot as an instance of the quad; unused slots are clipped away.
+    ///
+    /// # Arguments
+    /// - `render_pass`: The active render pass, with the quad's index buffer bound.
+    /// - `vertex_buffer`: The quad's vertices.
+    /// - `num_indices`: Number of indices in the quad's index buffer.
+    pub fn draw<'a>(&'a self, render_pass: &mut wgpu::RenderPass<'a>, vertex_buffer: &'a wgpu::Buffer, num_indices: u32) {
+        render_pass.set_pipeline(&self.render_pipeline);
+        render_pass.set_bind_group(0, &self.render_bind_group, &[]);
+        render_pass.set_vertex_buffer(0, vertex_buffer.slice(..));
+        render_pass.set_vertex_buffer(1, self.particle_buffer.slice(..));
+        render_pass.draw_indexed(0..num_indices, 0, 0..WEATHER_CAPACITY + BURST_CAPACITY);
+    }
+}
@@ -0,0 +1,165 @@
+// gpu_timer.rs
+use std::cell::RefCell;
+use std::sync::atomic::{AtomicU8, Ordering};
+use std::sync::Arc;
+
+/// Most passes timed in one frame; spans begun past this many aren't timed.
+const MAX_SPANS: u32 = 8;
+
+/// Bytes each timestamp is resolved into.
+const TIMESTAMP_SIZE: u64 = std::mem::size_of::<u64>() as u64;
+
+/// Where the readback of the last timed frame has got to.
+const PENDING: u8 = 0;
+const MAPPED: u8 = 1;
+const FAILED: u8 = 2;
+
+/// What the timer has recorded so far, changed while a frame is drawn through a
+/// shared renderer.
+#[derive(Default)]
+struct TimerState {
+    /// Whether frames are timed at all.
+    enabled: bool,
+    /// Names of the spans begun in the frame being recorded, in order.
+    spans: Vec<&'static str>,
+    /// Names of the spans of the frame being read back, if one is.
+    in_flight: Option<Vec<&'static str>>,
+}
+
+/// Times passes of a frame on the GPU with timestamp queries, for the debug HUD.
+///
+/// Timestamps are written around each span as the frame is recorded, resolved into a
+/// buffer once it's finished, and read back a frame or more later, without waiting on
+/// the GPU. Frames recorded while the last one's times are still being read back
+/// aren't timed. Only exists where the GPU supports timestamp queries.
+pub struct GpuTimer {
+    query_set: wgpu::QuerySet,
+    resolve_buffer: wgpu::Buffer,
+    readback_buffer: wgpu::Buffer,
+    /// Nanoseconds per timestamp tick.
+    period: f32,
+    state: RefCell<TimerState>,
+    readback: Arc<AtomicU8>,
+}
+
+impl GpuTimer {
+    /// Creates the timer, initially off.
+    ///
+    /// # Arguments
+    ///
+    /// * `device` - A device created with `Features::TIMESTAMP_QUERY`.
+    /// * `queue` - The queue frames are submitted to, whose timestamp period is used.
+    pub fn new(device: &wgpu::Device, queue: &wgpu::Queue) -> Self {
+        let size = (MAX_SPANS * 2) as u64 * TIMESTAMP_SIZE;
+        Self {
+            query_set: device.create_query_set(&wgpu::QuerySetDescriptor {
+                label: Some("GPU Timer Queries"),
+                ty: wgpu::QueryType::Timestamp,
+                count: MAX_SPANS * 2,
+            }),
+            resolve_buffer: device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some("GPU Timer Resolve Buffer"),
+                size,
+                usage: wgpu::BufferUsages::QUERY_RESOLVE | wgpu::BufferUsages::COPY_SRC,
+                mapped_at_creation: false,
+            }),
+            readback_buffer: device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some("GPU Timer Readback Buffer"),
+                size,
+                usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+                mapped_at_creation: false,
+            }),
+            period: queue.get_timestamp_period(),
+            state: RefCell::new(TimerState::default()),
+            readback: Arc::new(AtomicU8::new(PENDING)),
+        }
+    }
+
+    /// Turns timing on or off, such as along with the debug HUD.
+    pub fn set_enabled(&self, enabled: bool) {
+        self.state.borrow_mut().enabled = enabled;
+    }
+
+    /// Writes the timestamp starting a span, outside of any pass.
+    ///
+    /// # Returns
+    ///
+    /// The span's index to end it with, or `None` if this frame isn't being timed.
+    pub fn begin(&self, encoder: &mut wgpu::CommandEncoder, name: &'static str) -> Option<u32> {
+        let mut state = self.state.borrow_mut();
+        let index = state.spans.len() as u32;
+        if !state.enabled || state.in_flight.is_some() || index >= MAX_SPANS {
+            return None;
+        }
+        state.spans.push(name);
+        encoder.write_timestamp(&self.query_set, index * 2);
+        Some(index)
+    }
+
+    /// Writes the timestamp ending a span begun with `begin`, outside of any pass.
+    pub fn end(&self, encoder: &mut wgpu::CommandEncoder, span: u32) {
+        encoder.write_timestamp(&self.query_set, span * 2 + 1);
+    }
+
+    /// Copies the frame's timestamps to where they're read back from, as the last
+    /// command of the frame.
+    pub fn resolve(&self, encoder: &mut wgpu::CommandEncoder) {
+        let mut state = self.state.borrow_mut();
+        if state.spans.is_empty() {
+            return;
+        }
+        let queries = state.spans.len() as u32 * 2;
+        encoder.resolve_query_set(&self.query_set, 0..queries, &self.resolve_buffer, 0);
+        encoder.copy_buffer_to_buffer(&self.resolve_buffer, 0, &self.readback_buffer, 0, queries as u64 * TIMESTAMP_SIZE);
+        state.in_flight = Some(std::mem::take(&mut state.spans));
+    }
+
+    /// Starts reading back the frame's timestamps, once it's been submitted.
+    pub fn read_back(&self) {
+        if self.state.borrow().in_flight.is_none() || self.readback.load(Ordering::Acquire) != PENDING {
+            return;
+        }
+        let readback = Arc::clone(&self.readback);
+        self.readback_buffer.slice(..).map_async(wgpu::MapMode::Read, move |result| {
+            readback.store(if result.is_ok() { MAPPED } else { FAILED }, Ordering::Release);
+        });
+    }
+
+    /// Returns the times of the last timed frame's spans, if they've been read back
+    /// since this was last called, without waiting for the GPU.
+    ///
+    /// # Returns
+    ///
+    /// Each span's name and how long the GPU took over it, in seconds, in the order
+    /// they were begun.
+    pub fn collect(&self, device: &wgpu::Device) -> Option<Vec<(&'static str, f32)>> {
+        let mut state = self.state.borrow_mut();
+        state.in_flight.as_ref()?;
+        device.poll(wgpu::Maintain::Poll);
+
+        let times = match self.readback.load(Ordering::Acquire) {
+            PENDING => return None,
+            MAPPED => {
+                let spans = state.in_flight.as_deref().unwrap_or_default();
+                let times = {
+                    let data = self.readback_buffer.slice(..).get_mapped_range();
+                    let timestamps: &[u64] = bytemuck::cast_slice(&data);
+                    spans
+                        .iter()
+                        .zip(timestamps.chunks_exact(2))
+                        .map(|(&name, span)| {
+                            let ticks = span[1].saturating_sub(span[0]);
+                            (name, ticks as f32 * self.period / 1e9)
+                        })
+                        .collect()
+                };
+                self.readback_buffer.unmap();
+                Some(times)
+            }
+            _ => None,
+        };
+        state.in_flight = None;
+        self.readback.store(PENDING, Ordering::Release);
+        times
+    }
+}
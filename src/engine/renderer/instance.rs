@@ -6,9 +6,48 @@ use bytemuck::{Pod, Zeroable};
 pub struct InstanceData {
     pub transform: [[f32; 4]; 4], // 64 bytes
     pub sprite_index: f32,        // 4 bytes
-    pub _padding1: f32,           // 4 bytes padding
+    pub layer: f32,               // 4 bytes, sprite array layer to sample, set when batched
     pub sprite_size: [f32; 2],    // 8 bytes
     pub uv_offset: [f32; 2],      // 8 bytes
     pub uv_scale: [f32; 2],       // 8 bytes
-    // Total size: 96 bytes (aligned to 16 bytes)
+    pub color: [f32; 4],          // 16 bytes, multiplied with the sampled texel
+    // Total size: 112 bytes (aligned to 16 bytes)
+}
+
+impl InstanceData {
+    /// Turns the instance about its position, as placed by its transform.
+    ///
+    /// # Arguments
+    ///
+    /// * `angle` - Counterclockwise rotation in radians, applied after the transform's scale.
+    pub fn rotated(mut self, angle: f32) -> Self {
+        let (sin, cos) = angle.sin_cos();
+        for column in &mut self.transform[..2] {
+            let (x, y) = (column[0], column[1]);
+            column[0] = cos * x - sin * y;
+            column[1] = sin * x + cos * y;
+        }
+        self
+    }
+
+    pub fn descriptor<'a>() -> wgpu::VertexBufferLayout<'a> {
+        use wgpu::vertex_attr_array;
+        const ATTRIBUTES: [wgpu::VertexAttribute; 10] = vertex_attr_array![
+            2 => Float32x4,  // transform, by column
+            3 => Float32x4,
+            4 => Float32x4,
+            5 => Float32x4,
+            6 => Float32,    // sprite_index
+            7 => Float32,    // layer
+            8 => Float32x2,  // sprite_size
+            9 => Float32x2,  // uv_offset
+            10 => Float32x2, // uv_scale
+            11 => Float32x4  // color
+        ];
+        wgpu::VertexBufferLayout {
+            array_stride: std::mem::size_of::<InstanceData>() as wgpu::BufferAddress,
+            step_mode: wgpu::VertexStepMode::Instance,
+            attributes: &ATTRIBUTES,
+        }
+    }
 }
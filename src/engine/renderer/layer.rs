@@ -0,0 +1,71 @@
+// layer.rs
+
+/// Depth range each layer is given; the background images and their props are drawn
+/// deeper than every layer, from 0.6 back.
+const LAYER_SPAN: f32 = 0.025;
+
+/// Depth between successive sort keys within a layer, fitting every key of an `i8`
+/// inside the layer's span.
+const ORDER_STEP: f32 = LAYER_SPAN / 256.0;
+
+/// What something is drawn as, deciding what it's drawn in front of. Layers are
+/// listed from the back to the front, so a new kind of object only has to be placed
+/// between the layers it belongs between.
+///
+/// The renderer only deals in depths, where smaller is nearer; `depth` converts a
+/// layer and a sort key within it into one.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum RenderLayer {
+    /// The level's tile layers and the tile-drawn objects: hazards, exits, turrets,
+    /// switches, and doors.
+    Tiles,
+    /// Grass, bushes, and rocks scattered on top of the tiles.
+    Decorations,
+    Jelly,
+    /// Moving platforms.
+    Platforms,
+    /// The blob shadows on the ground under characters and items.
+    Shadows,
+    Portals,
+    Ropes,
+    Mounts,
+    Projectiles,
+    Player,
+    Particles,
+    Water,
+    /// The level's foreground tile layers, drawn over everything in the world.
+    Foreground,
+    /// Black bars covering the screen outside a cutscene's frame.
+    Letterbox,
+    /// The colored border flashed on taking damage.
+    Flash,
+    /// Banners and the game's own overlay drawn over the world.
+    Hud,
+    /// The pause, game-over, and main menu screens.
+    Pause,
+    /// The developer menu, over the pause screens.
+    Menu,
+    /// The controls help.
+    Help,
+    /// The black panel faded in and out while switching scenes.
+    Fade,
+    /// The debug HUD, over everything.
+    Debug,
+}
+
+impl RenderLayer {
+    /// The frontmost layer.
+    const FRONT: RenderLayer = RenderLayer::Debug;
+
+    /// Returns the depth to draw at on this layer.
+    ///
+    /// # Arguments
+    ///
+    /// * `order` - Sort key within the layer; higher keys are drawn in front of lower
+    ///   ones, and 0 is the layer's own depth. Every key stays behind the layers in
+    ///   front and in front of the layers behind.
+    pub fn depth(self, order: i8) -> f32 {
+        let layers_in_front = (Self::FRONT as usize - self as usize) as f32;
+        (layers_in_front + 0.5) * LAYER_SPAN - order as f32 * ORDER_STEP
+    }
+}
@@ -0,0 +1,187 @@
+// letterbox.rs
+use log::info;
+use std::sync::Arc;
+
+use crate::engine::renderer::texture::{create_texture_bind_group, SamplerPreset, Texture};
+
+/// Color of the bars either side of the canvas when its shape doesn't match the window's.
+const BAR_COLOR: wgpu::Color = wgpu::Color::BLACK;
+
+/// A canvas of fixed size the frame is drawn into, then scaled as large as fits into
+/// the window and centered, with bars filling the rest.
+///
+/// Drawing at a virtual resolution keeps the view the same shape whatever the window's,
+/// so sprites aren't stretched when it's resized. Pixels scale with the nearest
+/// filter to stay crisp. As the frame's last pass, the copy also applies the
+/// post-processing effects by the globals' values, in place of `PostEffects`.
+pub struct Letterbox {
+    canvas: Texture,
+    bind_group: wgpu::BindGroup,
+    pipeline: wgpu::RenderPipeline,
+    /// Where the canvas is drawn in the window: x, y, width, and height in pixels.
+    viewport: [f32; 4],
+}
+
+impl Letterbox {
+    /// Creates the canvas and the pipeline scaling it into the window.
+    ///
+    /// # Arguments
+    /// - `device`: The `wgpu::Device` used to create GPU resources.
+    /// - `config`: The surface configuration, whose format the canvas matches and whose
+    ///   size the canvas is fitted into.
+    /// - `size`: The virtual resolution, in pixels.
+    /// - `texture_bind_group_layout`: The layout the canvas is bound with.
+    /// - `globals_bind_group_layout`: The layout of the globals the effects are read from.
+    pub fn new(
+        device: &wgpu::Device,
+        config: &wgpu::SurfaceConfiguration,
+        size: (u32, u32),
+        texture_bind_group_layout: &wgpu::BindGroupLayout,
+        globals_bind_group_layout: &wgpu::BindGroupLayout,
+    ) -> Self {
+        info!("Rendering at a virtual resolution of {}x{}", size.0, size.1);
+        let canvas = create_canvas_texture(device, config.format, size);
+        let bind_group = create_texture_bind_group(device, texture_bind_group_layout, &canvas);
+
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Letterbox Shader"),
+            source: wgpu::ShaderSource::Wgsl(include_str!("shaders/blit.wgsl").into()),
+        });
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Letterbox Pipeline Layout"),
+            bind_group_layouts: &[texture_bind_group_layout, globals_bind_group_layout],
+            push_constant_ranges: &[],
+        });
+        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Letterbox Pipeline"),
+            layout: Some(&pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: "vs_main",
+                buffers: &[],
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: "fs_post",
+                targets: &[Some(wgpu::ColorTargetState {
+                    format: config.format,
+                    blend: None,
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+            }),
+            primitive: wgpu::PrimitiveState::default(),
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState::default(),
+            multiview: None,
+        });
+
+        let mut letterbox = Self {
+            canvas,
+            bind_group,
+            pipeline,
+            viewport: [0.0; 4],
+        };
+        letterbox.resize(config.width, config.height);
+        letterbox
+    }
+
+    /// Fits the canvas into a new window size.
+    ///
+    /// # Arguments
+    /// - `width`, `height`: The window's inner size in pixels.
+    pub fn resize(&mut self, width: u32, height: u32) {
+        let (width, height) = (width as f32, height as f32);
+        let scale = (width / self.canvas.width as f32).min(height / self.canvas.height as f32);
+        // Whole pixels, so the canvas edges don't blur into the bars
+        let fitted_width = (self.canvas.width as f32 * scale).floor().max(1.0);
+        let fitted_height = (self.canvas.height as f32 * scale).floor().max(1.0);
+        self.viewport = [
+            ((width - fitted_width) / 2.0).floor(),
+            ((height - fitted_height) / 2.0).floor(),
+            fitted_width,
+            fitted_height,
+        ];
+    }
+
+    /// Returns the virtual resolution, in pixels.
+    pub fn size(&self) -> (u32, u32) {
+        (self.canvas.width, self.canvas.height)
+    }
+
+    /// Returns where the canvas is drawn in the window: x, y, width, and height in pixels.
+    pub fn viewport(&self) -> [f32; 4] {
+        self.viewport
+    }
+
+    /// Returns the view the frame is drawn into.
+    pub fn canvas_view(&self) -> &wgpu::TextureView {
+        &self.canvas.view
+    }
+
+    /// Clears the window to the bar color and draws the canvas into it.
+    ///
+    /// # Arguments
+    /// - `encoder`: The encoder to record the pass into, after the frame's own passes.
+    /// - `view`: The window surface's view.
+    /// - `globals_bind_group`: The globals the effects are read from.
+    pub fn present(
+        &self,
+        encoder: &mut wgpu::CommandEncoder,
+        view: &wgpu::TextureView,
+        globals_bind_group: &wgpu::BindGroup,
+    ) {
+        let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("Letterbox Pass"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Clear(BAR_COLOR),
+                    store: true,
+                },
+            })],
+            depth_stencil_attachment: None,
+        });
+        let [x, y, width, height] = self.viewport;
+        render_pass.set_viewport(x, y, width, height, 0.0, 1.0);
+        render_pass.set_pipeline(&self.pipeline);
+        render_pass.set_bind_group(0, &self.bind_group, &[]);
+        render_pass.set_bind_group(1, globals_bind_group, &[]);
+        render_pass.draw(0..3, 0..1);
+    }
+}
+
+/// Creates a texture of the virtual resolution that frames can be drawn into and sampled from.
+///
+/// # Arguments
+/// - `device`: The `wgpu::Device` used to create the texture.
+/// - `format`: The surface format to match.
+/// - `size`: The virtual resolution, in pixels.
+fn create_canvas_texture(device: &wgpu::Device, format: wgpu::TextureFormat, (width, height): (u32, u32)) -> Texture {
+    let texture = Arc::new(device.create_texture(&wgpu::TextureDescriptor {
+        label: Some("Canvas Texture"),
+        size: wgpu::Extent3d {
+            width,
+            height,
+            depth_or_array_layers: 1,
+        },
+        mip_level_count: 1,
+        sample_count: 1,
+        dimension: wgpu::TextureDimension::D2,
+        format,
+        usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING,
+        view_formats: &[],
+    }));
+
+    let view = Arc::new(texture.create_view(&wgpu::TextureViewDescriptor::default()));
+    let sampler = Arc::new(SamplerPreset::PIXEL_ART.create_sampler(device, "Canvas Texture Sampler"));
+
+    Texture {
+        texture,
+        view,
+        sampler,
+        preset: SamplerPreset::PIXEL_ART,
+        width,
+        height,
+    }
+}
@@ -0,0 +1,208 @@
+// light_map.rs
+use crate::engine::renderer::instance::InstanceData;
+use crate::engine::renderer::post::create_offscreen_texture;
+use crate::engine::renderer::texture::{create_texture_bind_group, Texture};
+use crate::engine::renderer::vertex::Vertex;
+use log::info;
+
+/// Format of the light map; floating point, so dim light doesn't band and lights
+/// can add up past white.
+const LIGHT_MAP_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba16Float;
+
+/// How the scene is lit: a texture the size of the frame holding the light reaching
+/// each pixel, which the scene is multiplied by once it's drawn.
+///
+/// Each frame the map is cleared to the level's ambient light and the lights are
+/// added onto it, one instance each, drawn with the light material. The scene is then
+/// darkened by it before the overlay is drawn, so the overlay stays fully lit.
+pub struct LightMap {
+    map: Texture,
+    bind_group: wgpu::BindGroup,
+    /// Adds each light instance onto the map; reads the globals to flicker.
+    pub light_pipeline: wgpu::RenderPipeline,
+    composite_pipeline: wgpu::RenderPipeline,
+}
+
+impl LightMap {
+    /// Creates the light map at the size frames are drawn at, and the pipelines drawing
+    /// lights onto it and multiplying the scene by it.
+    ///
+    /// # Arguments
+    /// - `device`: The `wgpu::Device` used to create GPU resources.
+    /// - `config`: The configuration frames are drawn with, whose size the map matches.
+    /// - `sample_count`: Samples per pixel of the scene the map is multiplied into.
+    /// - `texture_bind_group_layout`: The layout the map is bound with.
+    /// - `globals_bind_group_layout`: The layout of the globals the lights flicker by.
+    pub fn new(
+        device: &wgpu::Device,
+        config: &wgpu::SurfaceConfiguration,
+        sample_count: u32,
+        texture_bind_group_layout: &wgpu::BindGroupLayout,
+        globals_bind_group_layout: &wgpu::BindGroupLayout,
+    ) -> Self {
+        info!("Creating light map");
+        let map = create_light_map(device, config);
+        let bind_group = create_texture_bind_group(device, texture_bind_group_layout, &map);
+
+        let light_shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Light Shader"),
+            source: wgpu::ShaderSource::Wgsl(include_str!("shaders/lights.wgsl").into()),
+        });
+        let light_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Light Pipeline Layout"),
+            bind_group_layouts: &[globals_bind_group_layout],
+            push_constant_ranges: &[],
+        });
+        let additive = wgpu::BlendComponent {
+            src_factor: wgpu::BlendFactor::One,
+            dst_factor: wgpu::BlendFactor::One,
+            operation: wgpu::BlendOperation::Add,
+        };
+        let light_pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Light Pipeline"),
+            layout: Some(&light_layout),
+            vertex: wgpu::VertexState {
+                module: &light_shader,
+                entry_point: "vs_main",
+                buffers: &[Vertex::descriptor(), InstanceData::descriptor()],
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &light_shader,
+                entry_point: "fs_main",
+                targets: &[Some(wgpu::ColorTargetState {
+                    format: LIGHT_MAP_FORMAT,
+                    blend: Some(wgpu::BlendState {
+                        color: additive,
+                        alpha: additive,
+                    }),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+            }),
+            primitive: wgpu::PrimitiveState::default(),
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState::default(),
+            multiview: None,
+        });
+
+        // The scene is multiplied by the map by blending, so the copy only samples it
+        let blit_shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Light Composite Shader"),
+            source: wgpu::ShaderSource::Wgsl(include_str!("shaders/blit.wgsl").into()),
+        });
+        let composite_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Light Composite Pipeline Layout"),
+            bind_group_layouts: &[texture_bind_group_layout],
+            push_constant_ranges: &[],
+        });
+        let composite_pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Light Composite Pipeline"),
+            layout: Some(&composite_layout),
+            vertex: wgpu::VertexState {
+                module: &blit_shader,
+                entry_point: "vs_main",
+                buffers: &[],
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &blit_shader,
+                entry_point: "fs_main",
+                targets: &[Some(wgpu::ColorTargetState {
+                    format: config.format,
+                    blend: Some(wgpu::BlendState {
+                        color: wgpu::BlendComponent {
+                            src_factor: wgpu::BlendFactor::Dst,
+                            dst_factor: wgpu::BlendFactor::Zero,
+                            operation: wgpu::BlendOperation::Add,
+                        },
+                        alpha: wgpu::BlendComponent {
+                            src_factor: wgpu::BlendFactor::Zero,
+                            dst_factor: wgpu::BlendFactor::One,
+                            operation: wgpu::BlendOperation::Add,
+                        },
+                    }),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+            }),
+            primitive: wgpu::PrimitiveState::default(),
+            // Leave the scene's depth in place for what is drawn over it
+            depth_stencil: Some(wgpu::DepthStencilState {
+                format: wgpu::TextureFormat::Depth32Float,
+                depth_write_enabled: false,
+                depth_compare: wgpu::CompareFunction::Always,
+                stencil: wgpu::StencilState::default(),
+                bias: wgpu::DepthBiasState::default(),
+            }),
+            multisample: wgpu::MultisampleState {
+                count: sample_count,
+                ..Default::default()
+            },
+            multiview: None,
+        });
+
+        Self {
+            map,
+            bind_group,
+            light_pipeline,
+            composite_pipeline,
+        }
+    }
+
+    /// Recreates the light map for a new frame size.
+    ///
+    /// # Arguments
+    /// - `device`: The `wgpu::Device` used to create GPU resources.
+    /// - `config`: The reconfigured surface configuration.
+    /// - `texture_bind_group_layout`: The layout the map is bound with.
+    pub fn resize(
+        &mut self,
+        device: &wgpu::Device,
+        config: &wgpu::SurfaceConfiguration,
+        texture_bind_group_layout: &wgpu::BindGroupLayout,
+    ) {
+        self.map = create_light_map(device, config);
+        self.bind_group = create_texture_bind_group(device, texture_bind_group_layout, &self.map);
+    }
+
+    /// Begins a render pass drawing onto the light map, cleared to the ambient light.
+    ///
+    /// # Arguments
+    /// - `encoder`: The encoder to record the pass into, before the scene's passes.
+    /// - `ambient`: The light reaching everywhere, in linear space.
+    pub fn begin_pass<'a>(&'a self, encoder: &'a mut wgpu::CommandEncoder, ambient: [f32; 3]) -> wgpu::RenderPass<'a> {
+        encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("Light Pass"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view: &self.map.view,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Clear(wgpu::Color {
+                        r: ambient[0] as f64,
+                        g: ambient[1] as f64,
+                        b: ambient[2] as f64,
+                        a: 1.0,
+                    }),
+                    store: true,
+                },
+            })],
+            depth_stencil_attachment: None,
+        })
+    }
+
+    /// Multiplies everything drawn so far by the light map.
+    ///
+    /// # Arguments
+    /// - `render_pass`: A render pass drawing the scene, with the scene's depth loaded.
+    pub fn composite<'a>(&'a self, render_pass: &mut wgpu::RenderPass<'a>) {
+        render_pass.set_pipeline(&self.composite_pipeline);
+        render_pass.set_bind_group(0, &self.bind_group, &[]);
+        render_pass.draw(0..3, 0..1);
+    }
+}
+
+/// Creates a light map the size frames are drawn at.
+fn create_light_map(device: &wgpu::Device, config: &wgpu::SurfaceConfiguration) -> Texture {
+    let config = wgpu::SurfaceConfiguration {
+        format: LIGHT_MAP_FORMAT,
+        ..config.clone()
+    };
+    create_offscreen_texture(device, &config, "Light Map")
+}
@@ -0,0 +1,69 @@
+// mesh.rs
+use crate::engine::renderer::vertex::Vertex;
+
+/// Geometry built on the CPU each frame, for shapes the shared quad can't draw.
+///
+/// Meshes are drawn with the sprite pipeline and a single instance, so vertex
+/// positions are final scene positions and UVs index the bound texture directly.
+pub struct Mesh {
+    pub vertices: Vec<Vertex>,
+    pub indices: Vec<u16>,
+}
+
+impl Mesh {
+    /// Creates an empty mesh.
+    pub fn new() -> Self {
+        Self {
+            vertices: Vec::new(),
+            indices: Vec::new(),
+        }
+    }
+
+    /// Appends a strip of quads between matching points on a top and bottom edge.
+    ///
+    /// The texture region is stretched across the whole strip, left to right and top to bottom.
+    ///
+    /// # Arguments
+    /// - `top`: Points along the top edge, from left to right.
+    /// - `bottom`: Points along the bottom edge, one for each top point.
+    /// - `uv_offset`: Top-left corner of the texture region.
+    /// - `uv_scale`: Size of the texture region.
+    pub fn add_strip(
+        &mut self,
+        top: &[(f32, f32)],
+        bottom: &[(f32, f32)],
+        uv_offset: [f32; 2],
+        uv_scale: [f32; 2],
+    ) {
+        let columns = top.len().min(bottom.len());
+        if columns < 2 {
+            return;
+        }
+
+        let base = self.vertices.len() as u16;
+        for (index, (top, bottom)) in top.iter().zip(bottom).enumerate() {
+            let u = uv_offset[0] + uv_scale[0] * index as f32 / (columns - 1) as f32;
+            self.vertices.push(Vertex {
+                position: [top.0, top.1, 0.0],
+                uv: [u, uv_offset[1]],
+            });
+            self.vertices.push(Vertex {
+                position: [bottom.0, bottom.1, 0.0],
+                uv: [u, uv_offset[1] + uv_scale[1]],
+            });
+        }
+
+        for column in 0..columns as u16 - 1 {
+            let top_left = base + column * 2;
+            let (bottom_left, top_right, bottom_right) = (top_left + 1, top_left + 2, top_left + 3);
+            self.indices
+                .extend_from_slice(&[bottom_left, bottom_right, top_right, top_right, top_left, bottom_left]);
+        }
+    }
+}
+
+impl Default for Mesh {
+    fn default() -> Self {
+        Self::new()
+    }
+}
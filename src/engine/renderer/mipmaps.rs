@@ -0,0 +1,138 @@
+// mipmaps.rs
+use crate::engine::renderer::texture::create_texture_bind_group_layout;
+use log::info;
+
+/// Returns how many mip levels a texture of the given size has, down to 1x1.
+///
+/// # Arguments
+/// - `width`, `height`: Size of the full-resolution level, in pixels.
+pub fn mip_level_count(width: u32, height: u32) -> u32 {
+    32 - width.max(height).max(1).leading_zeros()
+}
+
+/// Fills in a texture's mipmaps by drawing each level, filtered, at half the size into
+/// the next, so textures drawn smaller than their pixels don't shimmer as they move.
+pub struct MipmapGenerator {
+    pipeline: wgpu::RenderPipeline,
+    bind_group_layout: wgpu::BindGroupLayout,
+    /// Linear filter, so each pixel of a level averages the four it covers in the one above.
+    sampler: wgpu::Sampler,
+}
+
+impl MipmapGenerator {
+    /// Creates the pipeline drawing one level into the next.
+    ///
+    /// # Arguments
+    /// - `device`: The `wgpu::Device` used to create GPU resources.
+    /// - `format`: The format of the textures to generate mipmaps for.
+    pub fn new(device: &wgpu::Device, format: wgpu::TextureFormat) -> Self {
+        info!("Creating mipmap generator");
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Mipmap Shader"),
+            source: wgpu::ShaderSource::Wgsl(include_str!("shaders/blit.wgsl").into()),
+        });
+        let bind_group_layout = create_texture_bind_group_layout(device);
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Mipmap Pipeline Layout"),
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[],
+        });
+        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Mipmap Pipeline"),
+            layout: Some(&pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: "vs_main",
+                buffers: &[],
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: "fs_main",
+                targets: &[Some(wgpu::ColorTargetState {
+                    format,
+                    blend: None,
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+            }),
+            primitive: wgpu::PrimitiveState::default(),
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState::default(),
+            multiview: None,
+        });
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some("Mipmap Sampler"),
+            address_mode_u: wgpu::AddressMode::ClampToEdge,
+            address_mode_v: wgpu::AddressMode::ClampToEdge,
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            ..Default::default()
+        });
+
+        Self {
+            pipeline,
+            bind_group_layout,
+            sampler,
+        }
+    }
+
+    /// Redraws every level below the full-resolution one from the level above it.
+    ///
+    /// # Arguments
+    /// - `device`: The `wgpu::Device` used to record the draws.
+    /// - `queue`: The `wgpu::Queue` used to submit them.
+    /// - `texture`: The texture, created with `RENDER_ATTACHMENT` and `TEXTURE_BINDING`
+    ///   usage in the format the generator was created for.
+    pub fn generate(&self, device: &wgpu::Device, queue: &wgpu::Queue, texture: &wgpu::Texture) {
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("Mipmap Encoder"),
+        });
+        for layer in 0..texture.depth_or_array_layers() {
+            let level_view = |level: u32| {
+                texture.create_view(&wgpu::TextureViewDescriptor {
+                    label: Some("Mipmap Level View"),
+                    dimension: Some(wgpu::TextureViewDimension::D2),
+                    base_mip_level: level,
+                    mip_level_count: Some(1),
+                    base_array_layer: layer,
+                    array_layer_count: Some(1),
+                    ..Default::default()
+                })
+            };
+            for level in 1..texture.mip_level_count() {
+                let source = level_view(level - 1);
+                let target = level_view(level);
+                let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+                    label: Some("Mipmap Bind Group"),
+                    layout: &self.bind_group_layout,
+                    entries: &[
+                        wgpu::BindGroupEntry {
+                            binding: 0,
+                            resource: wgpu::BindingResource::TextureView(&source),
+                        },
+                        wgpu::BindGroupEntry {
+                            binding: 1,
+                            resource: wgpu::BindingResource::Sampler(&self.sampler),
+                        },
+                    ],
+                });
+
+                let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                    label: Some("Mipmap Pass"),
+                    color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                        view: &target,
+                        resolve_target: None,
+                        ops: wgpu::Operations {
+                            load: wgpu::LoadOp::Clear(wgpu::Color::TRANSPARENT),
+                            store: true,
+                        },
+                    })],
+                    depth_stencil_attachment: None,
+                });
+                render_pass.set_pipeline(&self.pipeline);
+                render_pass.set_bind_group(0, &bind_group, &[]);
+                render_pass.draw(0..3, 0..1);
+            }
+        }
+        queue.submit(Some(encoder.finish()));
+    }
+}
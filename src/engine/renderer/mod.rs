@@ -1,7 +1,24 @@
 pub mod vertex;
 pub mod pipeline;
+#[allow(clippy::module_inception)]
 pub mod renderer;
+pub mod text;
 pub mod texture;
 pub use renderer::Renderer;
 pub mod tile;
-pub mod instance;
\ No newline at end of file
+pub mod layer;
+pub mod autotile;
+pub mod shading;
+pub mod instance;
+pub mod batcher;
+pub mod globals;
+pub mod mesh;
+pub mod mipmaps;
+pub mod gpu_particles;
+pub mod gpu_timer;
+pub mod letterbox;
+pub mod light_map;
+pub mod post;
+pub mod snapshot;
+pub mod splash;
+pub mod sprite_array;
@@ -2,106 +2,120 @@
 use crate::engine::renderer::vertex::Vertex;
 use crate::engine::renderer::instance::InstanceData;
 
+/// The directory of the shaders as checked out, watched in debug builds so edits to
+/// the sprite shader show up without rebuilding.
+#[cfg(debug_assertions)]
+pub const SHADERS_DIR: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/src/engine/renderer/shaders");
+
+/// The sprite shader's file in `SHADERS_DIR`.
+#[cfg(debug_assertions)]
+pub const SHADER_FILE: &str = "shader.wgsl";
+
 /// Creates a render pipeline for rendering textured instances with depth testing.
 ///
 /// This pipeline includes support for:
 /// - Vertex attributes for position and texture coordinates.
-/// - Instance attributes for transform matrices, sprite indices, and UV offsets.
+/// - Instance attributes for transform matrices, sprite indices, UV offsets, and color tints.
 /// - Alpha blending for semi-transparent textures.
 /// - Depth testing for proper layer ordering.
 ///
 /// # Arguments
 /// - `device`: The `wgpu::Device` used to create GPU resources.
 /// - `config`: The surface configuration that specifies rendering settings like format and size.
-/// - `texture_bind_group_layout`: The bind group layout for textures, specifying bindings for texture views and samplers.
+/// - `sample_count`: Samples per pixel of the targets drawn to; above 1 smooths edges with MSAA.
+/// - `sprite_array_bind_group_layout`: The bind group layout for the sprite array, specifying bindings for its texture view, sampler, and layer extents.
 ///
 /// # Returns
 /// A `wgpu::RenderPipeline` configured with the specified attributes, shaders, blending, and depth testing.
 ///
 /// # Notes
 /// - Ensure the vertex and instance attributes match the shader definitions.
+/// - The shader is built into the executable; debug builds can rebuild the pipeline
+///   from the file on disk with `reload_pipeline`.
 pub fn create_pipeline(
     device: &wgpu::Device,
     config: &wgpu::SurfaceConfiguration,
-    texture_bind_group_layout: &wgpu::BindGroupLayout,
+    sample_count: u32,
+    sprite_array_bind_group_layout: &wgpu::BindGroupLayout,
 ) -> wgpu::RenderPipeline {
-    // Load the shader module from a WGSL shader file
+    create_material_pipeline(
+        device,
+        config,
+        sample_count,
+        &[sprite_array_bind_group_layout],
+        "Sprite",
+        include_str!("shaders/shader.wgsl"),
+    )
+}
+
+/// Rebuilds the sprite pipeline from the shader file in `SHADERS_DIR`, as it is on disk.
+///
+/// # Arguments
+/// - `device`: The `wgpu::Device` used to create GPU resources.
+/// - `config`: The surface configuration that specifies rendering settings like format and size.
+/// - `sample_count`: Samples per pixel of the targets drawn to.
+/// - `sprite_array_bind_group_layout`: The bind group layout for the sprite array.
+///
+/// # Returns
+/// The pipeline, or why the shader couldn't be read or compiled. Compile errors are
+/// caught rather than aborting, so a half-finished edit can be fixed and saved again.
+#[cfg(debug_assertions)]
+pub fn reload_pipeline(
+    device: &wgpu::Device,
+    config: &wgpu::SurfaceConfiguration,
+    sample_count: u32,
+    sprite_array_bind_group_layout: &wgpu::BindGroupLayout,
+) -> Result<wgpu::RenderPipeline, String> {
+    let path = std::path::Path::new(SHADERS_DIR).join(SHADER_FILE);
+    let source = std::fs::read_to_string(&path).map_err(|e| e.to_string())?;
+
+    device.push_error_scope(wgpu::ErrorFilter::Validation);
+    let pipeline = create_material_pipeline(device, config, sample_count, &[sprite_array_bind_group_layout], "Sprite", &source);
+    match pollster::block_on(device.pop_error_scope()) {
+        // The description says where the shader went wrong, not just that it did
+        Some(wgpu::Error::Validation { description, .. }) => Err(description),
+        Some(e) => Err(e.to_string()),
+        None => Ok(pipeline),
+    }
+}
+
+/// Creates a render pipeline for a custom material shader.
+///
+/// Materials share the sprite pipeline's vertex and instance layouts, blending,
+/// and depth testing, so any instance batch can be drawn with any material. The
+/// shader must provide `vs_main` and `fs_main` entry points; it may ignore
+/// instance attributes it doesn't need.
+///
+/// # Arguments
+/// - `device`: The `wgpu::Device` used to create GPU resources.
+/// - `config`: The surface configuration that specifies rendering settings like format and size.
+/// - `sample_count`: Samples per pixel of the targets drawn to.
+/// - `bind_group_layouts`: The bind group layouts used by the shader, in group order.
+/// - `label`: Name used to label the shader and pipeline in debugging tools.
+/// - `source`: The WGSL source of the material shader.
+///
+/// # Returns
+/// A `wgpu::RenderPipeline` that draws instances with the given shader.
+pub fn create_material_pipeline(
+    device: &wgpu::Device,
+    config: &wgpu::SurfaceConfiguration,
+    sample_count: u32,
+    bind_group_layouts: &[&wgpu::BindGroupLayout],
+    label: &str,
+    source: &str,
+) -> wgpu::RenderPipeline {
+    // Load the shader module from WGSL source
     let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
-        label: Some("Shader"),
-        source: wgpu::ShaderSource::Wgsl(include_str!("shaders/shader.wgsl").into()),
+        label: Some(&format!("{} Shader", label)),
+        source: wgpu::ShaderSource::Wgsl(source.into()),
     });
 
     // Define vertex and instance buffer layouts
     let vertex_layouts = [
         // Layout for vertex attributes
-        wgpu::VertexBufferLayout {
-            array_stride: std::mem::size_of::<Vertex>() as wgpu::BufferAddress,
-            step_mode: wgpu::VertexStepMode::Vertex,
-            attributes: &[
-                wgpu::VertexAttribute {
-                    offset: 0,
-                    shader_location: 0,
-                    format: wgpu::VertexFormat::Float32x3,
-                },
-                wgpu::VertexAttribute {
-                    offset: 12,
-                    shader_location: 1,
-                    format: wgpu::VertexFormat::Float32x2,
-                },
-            ],
-        },
+        Vertex::descriptor(),
         // Layout for instance attributes
-        wgpu::VertexBufferLayout {
-            array_stride: std::mem::size_of::<InstanceData>() as wgpu::BufferAddress,
-            step_mode: wgpu::VertexStepMode::Instance,
-            attributes: &[
-                wgpu::VertexAttribute {
-                    offset: 0,
-                    shader_location: 2,
-                    format: wgpu::VertexFormat::Float32x4,
-                },
-                wgpu::VertexAttribute {
-                    offset: 16,
-                    shader_location: 3,
-                    format: wgpu::VertexFormat::Float32x4,
-                },
-                wgpu::VertexAttribute {
-                    offset: 32,
-                    shader_location: 4,
-                    format: wgpu::VertexFormat::Float32x4,
-                },
-                wgpu::VertexAttribute {
-                    offset: 48,
-                    shader_location: 5,
-                    format: wgpu::VertexFormat::Float32x4,
-                },
-                wgpu::VertexAttribute {
-                    offset: 64,
-                    shader_location: 6,
-                    format: wgpu::VertexFormat::Float32,
-                },
-                wgpu::VertexAttribute {
-                    offset: 68,
-                    shader_location: 7,
-                    format: wgpu::VertexFormat::Float32,
-                },
-                wgpu::VertexAttribute {
-                    offset: 72,
-                    shader_location: 8,
-                    format: wgpu::VertexFormat::Float32x2,
-                },
-                wgpu::VertexAttribute {
-                    offset: 80,
-                    shader_location: 9,
-                    format: wgpu::VertexFormat::Float32x2,
-                },
-                wgpu::VertexAttribute {
-                    offset: 88,
-                    shader_location: 10,
-                    format: wgpu::VertexFormat::Float32x2,
-                },
-            ],
-        },
+        InstanceData::descriptor(),
     ];
 
     // Configure the depth stencil state
@@ -115,14 +129,14 @@ pub fn create_pipeline(
 
     // Create the pipeline layout
     let render_pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
-        label: Some("Render Pipeline Layout"),
-        bind_group_layouts: &[texture_bind_group_layout],
+        label: Some(&format!("{} Pipeline Layout", label)),
+        bind_group_layouts,
         push_constant_ranges: &[],
     });
 
     // Create the render pipeline
     device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
-        label: Some("Render Pipeline"),
+        label: Some(&format!("{} Pipeline", label)),
         layout: Some(&render_pipeline_layout),
         vertex: wgpu::VertexState {
             module: &shader,
@@ -139,14 +153,11 @@ pub fn create_pipeline(
             })],
         }),
         primitive: wgpu::PrimitiveState::default(),
-        depth_stencil: Some(wgpu::DepthStencilState {
-            format: wgpu::TextureFormat::Depth32Float,
-            depth_write_enabled: true,
-            depth_compare: wgpu::CompareFunction::Less,
-            stencil: wgpu::StencilState::default(),
-            bias: wgpu::DepthBiasState::default(),
-        }),
-        multisample: wgpu::MultisampleState::default(),
+        depth_stencil: Some(depth_stencil),
+        multisample: wgpu::MultisampleState {
+            count: sample_count,
+            ..Default::default()
+        },
         multiview: None,
     })
 }
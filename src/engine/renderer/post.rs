@@ -0,0 +1,273 @@
+// post.rs
+use log::info;
+use std::sync::Arc;
+
+use crate::engine::renderer::texture::{create_texture_bind_group, SamplerPreset, Texture};
+
+/// The start of the post-processing chain: the scene is drawn into an offscreen
+/// texture, which is then copied onto the screen before the rest of the frame.
+///
+/// Everything drawn after the copy can sample the scene behind it, as water does to
+/// reflect it.
+pub struct PostProcess {
+    scene: Texture,
+    /// Binds the scene texture and its sampler with the texture bind group layout.
+    pub scene_bind_group: wgpu::BindGroup,
+    blit_pipeline: wgpu::RenderPipeline,
+}
+
+impl PostProcess {
+    /// Creates the scene texture at the size of the surface, and the pipeline copying it to the screen.
+    ///
+    /// # Arguments
+    /// - `device`: The `wgpu::Device` used to create GPU resources.
+    /// - `config`: The surface configuration, whose size and format the scene texture matches.
+    /// - `sample_count`: Samples per pixel of the targets the scene is copied to.
+    /// - `texture_bind_group_layout`: The layout the scene texture is bound with.
+    pub fn new(
+        device: &wgpu::Device,
+        config: &wgpu::SurfaceConfiguration,
+        sample_count: u32,
+        texture_bind_group_layout: &wgpu::BindGroupLayout,
+    ) -> Self {
+        info!("Creating post-processing scene texture");
+        let scene = create_offscreen_texture(device, config, "Scene Texture");
+        let scene_bind_group = create_texture_bind_group(device, texture_bind_group_layout, &scene);
+
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Blit Shader"),
+            source: wgpu::ShaderSource::Wgsl(include_str!("shaders/blit.wgsl").into()),
+        });
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Blit Pipeline Layout"),
+            bind_group_layouts: &[texture_bind_group_layout],
+            push_constant_ranges: &[],
+        });
+        let blit_pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Blit Pipeline"),
+            layout: Some(&pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: "vs_main",
+                buffers: &[],
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: "fs_main",
+                targets: &[Some(wgpu::ColorTargetState {
+                    format: config.format,
+                    blend: None,
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+            }),
+            primitive: wgpu::PrimitiveState::default(),
+            // Leave the scene's depth in place for what is drawn over it
+            depth_stencil: Some(wgpu::DepthStencilState {
+                format: wgpu::TextureFormat::Depth32Float,
+                depth_write_enabled: false,
+                depth_compare: wgpu::CompareFunction::Always,
+                stencil: wgpu::StencilState::default(),
+                bias: wgpu::DepthBiasState::default(),
+            }),
+            multisample: wgpu::MultisampleState {
+                count: sample_count,
+                ..Default::default()
+            },
+            multiview: None,
+        });
+
+        Self {
+            scene,
+            scene_bind_group,
+            blit_pipeline,
+        }
+    }
+
+    /// Recreates the scene texture for a new surface size.
+    ///
+    /// # Arguments
+    /// - `device`: The `wgpu::Device` used to create GPU resources.
+    /// - `config`: The reconfigured surface configuration.
+    /// - `texture_bind_group_layout`: The layout the scene texture is bound with.
+    pub fn resize(
+        &mut self,
+        device: &wgpu::Device,
+        config: &wgpu::SurfaceConfiguration,
+        texture_bind_group_layout: &wgpu::BindGroupLayout,
+    ) {
+        self.scene = create_offscreen_texture(device, config, "Scene Texture");
+        self.scene_bind_group = create_texture_bind_group(device, texture_bind_group_layout, &self.scene);
+    }
+
+    /// Returns the view the scene is drawn into.
+    pub fn scene_view(&self) -> &wgpu::TextureView {
+        &self.scene.view
+    }
+
+    /// Copies the scene onto the screen, covering whatever the pass started with.
+    ///
+    /// # Arguments
+    /// - `render_pass`: A render pass drawing to the screen, with the scene's depth loaded.
+    pub fn blit<'a>(&'a self, render_pass: &mut wgpu::RenderPass<'a>) {
+        render_pass.set_pipeline(&self.blit_pipeline);
+        render_pass.set_bind_group(0, &self.scene_bind_group, &[]);
+        render_pass.draw(0..3, 0..1);
+    }
+}
+
+/// The end of the post-processing chain: the frame is drawn into an offscreen
+/// texture, then copied onto the window through the effects read from the globals,
+/// grading its color, darkening the edges with a vignette, and fading it to black.
+///
+/// With a virtual resolution the letterbox's canvas takes its place, and the
+/// letterbox applies the same effects as it scales the canvas into the window.
+pub struct PostEffects {
+    frame: Texture,
+    bind_group: wgpu::BindGroup,
+    pipeline: wgpu::RenderPipeline,
+}
+
+impl PostEffects {
+    /// Creates the frame texture at the size of the surface, and the pipeline copying
+    /// it onto the window through the effects.
+    ///
+    /// # Arguments
+    /// - `device`: The `wgpu::Device` used to create GPU resources.
+    /// - `config`: The surface configuration, whose size and format the frame texture matches.
+    /// - `texture_bind_group_layout`: The layout the frame texture is bound with.
+    /// - `globals_bind_group_layout`: The layout of the globals the effects are read from.
+    pub fn new(
+        device: &wgpu::Device,
+        config: &wgpu::SurfaceConfiguration,
+        texture_bind_group_layout: &wgpu::BindGroupLayout,
+        globals_bind_group_layout: &wgpu::BindGroupLayout,
+    ) -> Self {
+        info!("Creating post-processing frame texture");
+        let frame = create_offscreen_texture(device, config, "Frame Texture");
+        let bind_group = create_texture_bind_group(device, texture_bind_group_layout, &frame);
+
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Post Effects Shader"),
+            source: wgpu::ShaderSource::Wgsl(include_str!("shaders/blit.wgsl").into()),
+        });
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Post Effects Pipeline Layout"),
+            bind_group_layouts: &[texture_bind_group_layout, globals_bind_group_layout],
+            push_constant_ranges: &[],
+        });
+        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Post Effects Pipeline"),
+            layout: Some(&pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: "vs_main",
+                buffers: &[],
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: "fs_post",
+                targets: &[Some(wgpu::ColorTargetState {
+                    format: config.format,
+                    blend: None,
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+            }),
+            primitive: wgpu::PrimitiveState::default(),
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState::default(),
+            multiview: None,
+        });
+
+        Self {
+            frame,
+            bind_group,
+            pipeline,
+        }
+    }
+
+    /// Recreates the frame texture for a new surface size.
+    ///
+    /// # Arguments
+    /// - `device`: The `wgpu::Device` used to create GPU resources.
+    /// - `config`: The reconfigured surface configuration.
+    /// - `texture_bind_group_layout`: The layout the frame texture is bound with.
+    pub fn resize(
+        &mut self,
+        device: &wgpu::Device,
+        config: &wgpu::SurfaceConfiguration,
+        texture_bind_group_layout: &wgpu::BindGroupLayout,
+    ) {
+        self.frame = create_offscreen_texture(device, config, "Frame Texture");
+        self.bind_group = create_texture_bind_group(device, texture_bind_group_layout, &self.frame);
+    }
+
+    /// Returns the view the frame is drawn into.
+    pub fn frame_view(&self) -> &wgpu::TextureView {
+        &self.frame.view
+    }
+
+    /// Copies the frame onto the window through the effects.
+    ///
+    /// # Arguments
+    /// - `encoder`: The encoder to record the pass into, after the frame's own passes.
+    /// - `view`: The window surface's view.
+    /// - `globals_bind_group`: The globals the effects are read from.
+    pub fn present(
+        &self,
+        encoder: &mut wgpu::CommandEncoder,
+        view: &wgpu::TextureView,
+        globals_bind_group: &wgpu::BindGroup,
+    ) {
+        let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("Post Effects Pass"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+                    store: true,
+                },
+            })],
+            depth_stencil_attachment: None,
+        });
+        render_pass.set_pipeline(&self.pipeline);
+        render_pass.set_bind_group(0, &self.bind_group, &[]);
+        render_pass.set_bind_group(1, globals_bind_group, &[]);
+        render_pass.draw(0..3, 0..1);
+    }
+}
+
+/// Creates a texture matching the surface that frames can be drawn into and sampled from.
+///
+/// # Arguments
+/// - `device`: The `wgpu::Device` used to create the texture.
+/// - `config`: The surface configuration whose size and format to match.
+/// - `label`: The texture's debug label.
+pub fn create_offscreen_texture(device: &wgpu::Device, config: &wgpu::SurfaceConfiguration, label: &str) -> Texture {
+    let texture = Arc::new(device.create_texture(&wgpu::TextureDescriptor {
+        label: Some(label),
+        size: wgpu::Extent3d {
+            width: config.width,
+            height: config.height,
+            depth_or_array_layers: 1,
+        },
+        mip_level_count: 1,
+        sample_count: 1,
+        dimension: wgpu::TextureDimension::D2,
+        format: config.format,
+        usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING,
+        view_formats: &[],
+    }));
+
+    let view = Arc::new(texture.create_view(&wgpu::TextureViewDescriptor::default()));
+    let sampler = Arc::new(SamplerPreset::SMOOTH.create_sampler(device, &format!("{} Sampler", label)));
+
+    Texture {
+        texture,
+        view,
+        sampler,
+        preset: SamplerPreset::SMOOTH,
+        width: config.width,
+        height: config.height,
+    }
+}
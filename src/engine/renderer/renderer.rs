@@ -2,95 +2,370 @@
 use crate::engine::renderer::vertex::{Vertex, VERTICES, INDICES};
 
 use crate::engine::renderer::texture::{
-    create_texture_bind_group, create_texture_bind_group_layout, create_depth_texture, load_texture, Texture,
+    create_texture_bind_group, create_texture_bind_group_layout, create_depth_texture, create_multisample_view,
+    create_shadow_texture, create_solid_texture, load_texture, SamplerPreset, Texture,
 };
 use crate::engine::renderer::instance::InstanceData;
+use crate::engine::renderer::globals::{create_globals_bind_group_layout, Globals};
+use crate::engine::background::BackgroundLayer;
+use crate::engine::config::tuning;
+use crate::engine::decoration::decorations;
+use crate::engine::level::bundle::TILESET_PATH;
+use crate::engine::renderer::gpu_particles::GpuParticles;
+use crate::engine::renderer::gpu_timer::GpuTimer;
+use crate::engine::renderer::letterbox::Letterbox;
+use crate::engine::renderer::light_map::LightMap;
+use crate::engine::renderer::mesh::Mesh;
+use crate::engine::renderer::post::{PostEffects, PostProcess};
+use crate::engine::renderer::splash::present_splash;
+use crate::engine::renderer::sprite_array::{create_sprite_array_bind_group_layout, SpriteArray};
+use crate::engine::renderer::text::TextRenderer;
 
 use wgpu::util::DeviceExt;
 use winit::window::Window;
 
-use super::pipeline::create_pipeline;
+use super::pipeline::{create_material_pipeline, create_pipeline};
+#[cfg(debug_assertions)]
+use super::pipeline::{reload_pipeline, SHADER_FILE};
+
+/// Instances the instance buffer holds to begin with, before any frame needs more.
+const INITIAL_INSTANCE_CAPACITY: usize = 1024;
 
 pub struct Renderer {
-    pub surface: wgpu::Surface,
+    pub surface: Option<wgpu::Surface>,      // The window's surface, or `None` when rendering headlessly
     pub device: wgpu::Device,
     pub queue: wgpu::Queue,
     pub config: wgpu::SurfaceConfiguration,
     pub pipeline: wgpu::RenderPipeline,
+    #[cfg(debug_assertions)]
+    sprite_array_bind_group_layout: wgpu::BindGroupLayout, // Kept to rebuild the pipeline when its shader changes
     pub vertex_buffer: wgpu::Buffer,
     pub index_buffer: wgpu::Buffer,
     pub num_indices: u32,
-    pub texture_bind_group: wgpu::BindGroup,
-    pub tileset_texture: Texture,
-    pub tileset_bind_group: wgpu::BindGroup,
+    pub texture_bind_group_layout: wgpu::BindGroupLayout, // Kept to rebind the scene texture on resize
+    pub sprites: SpriteArray,                 // Every sprite texture, drawn from with one bind group
+    pub texture_size: (u32, u32),             // Pixel size of the character sprite sheet
     pub tileset_columns: usize,
     pub tileset_rows: usize,
     pub instance_buffer: wgpu::Buffer,
     pub depth_texture: wgpu::Texture, // Depth texture field
+    pub sample_count: u32,                    // Samples per pixel frames are drawn with
+    pub multisample_view: Option<wgpu::TextureView>, // Target drawn into and resolved from with MSAA on
     pub background_textures: Vec<Texture>, // Store textures for background layers
-    pub background_bind_groups: Vec<wgpu::BindGroup>, // Bind groups for the backgrounds
+    pub water_pipeline: wgpu::RenderPipeline, // Animated material for water tiles
+    pub portal_pipeline: wgpu::RenderPipeline, // Animated material for portals
+    pub globals_buffer: wgpu::Buffer,         // Uniform buffer holding `Globals`
+    pub globals_bind_group: wgpu::BindGroup,
+    pub solid_bind_group: wgpu::BindGroup,    // White texture, the blank reflection for water without post-processing
+    pub text: TextRenderer,                   // Glyph atlas and layout for UI text
+    pub mesh_vertex_buffer: wgpu::Buffer,     // Per-frame geometry built on the CPU
+    pub mesh_index_buffer: wgpu::Buffer,
+    pub particles: Option<GpuParticles>,     // Compute-simulated particles, if the GPU supports compute
+    pub post: Option<PostProcess>,           // Offscreen scene for water to reflect, if reflections are on
+    pub letterbox: Option<Letterbox>,        // Fixed-size canvas scaled into the window, if a virtual resolution is set
+    pub effects: Option<PostEffects>,        // Offscreen frame finished by the effects, if post-processing is on without a canvas
+    pub light_map: Option<LightMap>,         // Light reaching each pixel of dark levels, if lighting is on
+    pub gpu_timer: Option<GpuTimer>,         // Times passes for the debug HUD, if the GPU supports timestamps
+    pub adapter_info: wgpu::AdapterInfo,     // The GPU and driver rendering the game
+    pub low_power: bool,                     // Skips post-processing to save power
+    minimized: bool,                         // Whether the window has no area to draw to
+    occluded: bool,                          // Whether the window is fully hidden by others
+}
+
+/// Returns the graphics APIs to look for an adapter on, in order: the configured ones,
+/// then any available, for when the configured ones have no adapter.
+pub fn backend_choices() -> [wgpu::Backends; 2] {
+    [tuning().graphics_backend.backends(), wgpu::Backends::all()]
+}
+
+/// Requests an adapter that can present to the surface, if there is one.
+///
+/// # Arguments
+/// - `instance`: The instance to look for adapters on.
+/// - `surface`: The window's surface, or `None` to render offscreen only.
+/// - `power_preference`: Whether to prefer a discrete or an integrated GPU.
+///
+/// # Returns
+/// The adapter, or `None` if the instance's backends have no suitable adapter.
+async fn request_adapter(
+    instance: &wgpu::Instance,
+    surface: Option<&wgpu::Surface>,
+    power_preference: wgpu::PowerPreference,
+) -> Option<wgpu::Adapter> {
+    instance
+        .request_adapter(&wgpu::RequestAdapterOptions {
+            power_preference,
+            compatible_surface: surface,
+            force_fallback_adapter: false,
+        })
+        .await
+}
+
+/// Picks the samples per pixel to draw with, falling back to one sample where the
+/// GPU can't multisample the frame's color and depth formats as many times.
+///
+/// # Arguments
+/// - `adapter`: The adapter frames are drawn with.
+/// - `features`: The features the device was created with; counts other than 1 and 4
+///   need `TEXTURE_ADAPTER_SPECIFIC_FORMAT_FEATURES`.
+/// - `format`: The color format frames are drawn in.
+/// - `requested`: The tuned sample count.
+fn supported_sample_count(
+    adapter: &wgpu::Adapter,
+    features: wgpu::Features,
+    format: wgpu::TextureFormat,
+    requested: u32,
+) -> u32 {
+    if requested <= 1 {
+        return 1;
+    }
+    let adapter_specific = features.contains(wgpu::Features::TEXTURE_ADAPTER_SPECIFIC_FORMAT_FEATURES);
+    let supports = |format: wgpu::TextureFormat| {
+        let flags = adapter.get_texture_format_features(format).flags;
+        flags.sample_count_supported(requested) && (adapter_specific || requested == 4)
+    };
+    if supports(format) && supports(wgpu::TextureFormat::Depth32Float) {
+        log::info!("Drawing with {}x MSAA", requested);
+        requested
+    } else {
+        log::warn!("{}x MSAA is unsupported on this GPU; drawing without it", requested);
+        1
+    }
+}
+
+/// Creates the buffer every frame's instances are uploaded into.
+///
+/// # Arguments
+/// - `device`: The device to create the buffer on.
+/// - `capacity`: The number of instances it holds.
+fn create_instance_buffer(device: &wgpu::Device, capacity: usize) -> wgpu::Buffer {
+    device.create_buffer(&wgpu::BufferDescriptor {
+        label: Some("Instance Buffer"),
+        size: (capacity * std::mem::size_of::<InstanceData>()) as wgpu::BufferAddress,
+        usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+        mapped_at_creation: false,
+    })
 }
 
 impl Renderer {
-    pub async fn new(window: &Window) -> Self {
-        // Initialize GPU resources
-        let instance = wgpu::Instance::default();
-
-        let surface = unsafe { instance.create_surface(window) }.unwrap();
-        let adapter = instance
-            .request_adapter(&wgpu::RequestAdapterOptions {
-                power_preference: wgpu::PowerPreference::HighPerformance,
-                compatible_surface: Some(&surface),
-                force_fallback_adapter: false,
-            })
-            .await
-            .unwrap();
+    /// Creates an instance for some graphics APIs and a surface for the window on it.
+    ///
+    /// Some platforms, such as macOS, only allow surfaces to be made on the main
+    /// thread, so this is called there; the rest of the renderer can then be built
+    /// on another with `new`.
+    ///
+    /// # Arguments
+    /// - `window`: The window to render to.
+    /// - `backends`: The graphics APIs to create the instance for.
+    ///
+    /// # Returns
+    /// The instance and the surface, or `None` if the surface couldn't be created.
+    pub fn create_surface(window: &Window, backends: wgpu::Backends) -> Option<(wgpu::Instance, wgpu::Surface)> {
+        let instance = wgpu::Instance::new(wgpu::InstanceDescriptor {
+            backends,
+            ..Default::default()
+        });
+        let surface = unsafe { instance.create_surface(window) }.ok()?;
+        Some((instance, surface))
+    }
 
+    /// Creates a renderer presenting to a window's surface, made by `create_surface`.
+    ///
+    /// # Arguments
+    /// - `instance`: The instance the surface was created on.
+    /// - `surface`: The window's surface.
+    /// - `size`: The width and height of the window, in pixels.
+    /// - `player_texture`: The player's texture, cut into frames by their sprite sheet.
+    /// - `backgrounds`: The background layers whose textures it loads.
+    /// - `low_power`: Whether to start out saving power, preferring the integrated GPU.
+    ///
+    /// # Returns
+    /// The renderer, or `None` if the instance has no adapter that can present to the surface.
+    pub async fn new(
+        instance: &wgpu::Instance,
+        surface: wgpu::Surface,
+        size: (u32, u32),
+        player_texture: &str,
+        backgrounds: &[BackgroundLayer],
+        low_power: bool,
+    ) -> Option<Self> {
+        Self::create(instance, Some(surface), size, player_texture, backgrounds, low_power).await
+    }
+
+    /// Creates a renderer without a window, drawing only into offscreen targets, such
+    /// as for the rendering regression tests.
+    ///
+    /// # Arguments
+    /// - `size`: The width and height of the frames it draws, in pixels.
+    /// - `player_texture`: The player's texture, cut into frames by their sprite sheet.
+    /// - `backgrounds`: The background layers whose textures it loads.
+    ///
+    /// # Returns
+    /// The renderer, or `None` if no graphics adapter is available.
+    pub async fn headless(size: (u32, u32), player_texture: &str, backgrounds: &[BackgroundLayer]) -> Option<Self> {
+        for (attempt, backends) in backend_choices().into_iter().enumerate() {
+            let instance = wgpu::Instance::new(wgpu::InstanceDescriptor {
+                backends,
+                ..Default::default()
+            });
+            if let Some(renderer) = Self::create(&instance, None, size, player_texture, backgrounds, false).await {
+                return Some(renderer);
+            }
+            if attempt == 0 {
+                log::warn!(
+                    "No {:?} adapter found; falling back to any available backend",
+                    tuning().graphics_backend
+                );
+            }
+        }
+        None
+    }
+
+    /// Creates the renderer for a window's surface, or for offscreen targets of the
+    /// given size without one.
+    async fn create(
+        instance: &wgpu::Instance,
+        surface: Option<wgpu::Surface>,
+        (width, height): (u32, u32),
+        player_texture: &str,
+        backgrounds: &[BackgroundLayer],
+        low_power: bool,
+    ) -> Option<Self> {
+        // Saving power prefers the integrated GPU
+        let tuning = tuning();
+        let power_preference = if low_power {
+            wgpu::PowerPreference::LowPower
+        } else {
+            tuning.gpu_preference.power_preference()
+        };
+        let adapter = request_adapter(instance, surface.as_ref(), power_preference).await?;
+
+        // Log which GPU was picked, to help diagnose rendering issues on other machines
+        let adapter_info = adapter.get_info();
+        log::info!(
+            "Using {} ({:?}, {:?}), driver {} {}",
+            adapter_info.name,
+            adapter_info.device_type,
+            adapter_info.backend,
+            adapter_info.driver,
+            adapter_info.driver_info
+        );
+
+        // Sample counts beyond the guaranteed ones are only allowed with the adapter's
+        // own format features; passes are timed where timestamps are supported
+        let features = adapter.features()
+            & (wgpu::Features::TEXTURE_ADAPTER_SPECIFIC_FORMAT_FEATURES | wgpu::Features::TIMESTAMP_QUERY);
         let (device, queue) = adapter
-            .request_device(&wgpu::DeviceDescriptor::default(), None)
+            .request_device(
+                &wgpu::DeviceDescriptor {
+                    features,
+                    ..Default::default()
+                },
+                None,
+            )
             .await
             .unwrap();
 
-        // Configure the surface
-        let capabilities = surface.get_capabilities(&adapter);
+        // Configure the surface; offscreen frames are drawn in the sprites' format
+        let format = match &surface {
+            Some(surface) => surface.get_capabilities(&adapter).formats[0],
+            None => wgpu::TextureFormat::Rgba8UnormSrgb,
+        };
         let config = wgpu::SurfaceConfiguration {
             usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
-            format: capabilities.formats[0],
-            width: window.inner_size().width,
-            height: window.inner_size().height,
-            present_mode: wgpu::PresentMode::Fifo,
+            format,
+            width,
+            height,
+            // Uncapped frame rates turn off vsync too, where the surface allows it
+            present_mode: if tuning.target_fps > 0.0 {
+                wgpu::PresentMode::Fifo
+            } else {
+                wgpu::PresentMode::AutoNoVsync
+            },
             alpha_mode: wgpu::CompositeAlphaMode::Auto,
             view_formats: vec![],
         };
-        surface.configure(&device, &config);
+        // Show the splash screen while the pipelines compile and the textures upload
+        if let Some(surface) = &surface {
+            surface.configure(&device, &config);
+            present_splash(surface, &device, &queue);
+        }
 
-        // Create the depth texture
-        let depth_texture = create_depth_texture(&device, &config);
+        // Draw at the virtual resolution, if there is one, and scale that into the window
+        let virtual_size = match tuning.virtual_resolution {
+            [0, _] | [_, 0] => None,
+            [width, height] => Some((width, height)),
+        };
+        let render_config = match virtual_size {
+            Some((width, height)) => wgpu::SurfaceConfiguration {
+                width,
+                height,
+                ..config.clone()
+            },
+            None => config.clone(),
+        };
+
+        // Create the depth texture, and the multisampled target resolved into each frame
+        let sample_count = supported_sample_count(&adapter, features, format, tuning.msaa_samples);
+        let depth_texture = create_depth_texture(&device, &render_config, sample_count);
+        let multisample_view = create_multisample_view(&device, &render_config, sample_count);
 
-        // Load the character texture
-        let texture = load_texture(&device, &queue, "assets/character/sheets/DinoSprites - tard.png").await;
+        // Load the player's texture
+        let texture = load_texture(&device, &queue, player_texture, SamplerPreset::PIXEL_ART).await;
 
-        // Create texture bind group layout and bind group for the character
+        // Create the texture bind group layout for the offscreen canvases
         let texture_bind_group_layout = create_texture_bind_group_layout(&device);
-        let texture_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
-            layout: &texture_bind_group_layout,
-            entries: &[
-                wgpu::BindGroupEntry {
-                    binding: 0, // Matches binding(0) in shader
-                    resource: wgpu::BindingResource::TextureView(&texture.view),
-                },
-                wgpu::BindGroupEntry {
-                    binding: 1, // Matches binding(1) in shader
-                    resource: wgpu::BindingResource::Sampler(&texture.sampler),
-                },
-            ],
-            label: Some("Texture Bind Group"),
-        });
 
         // Load the tileset texture
-        let tileset_texture = load_texture(&device, &queue, "assets/tileset/Tileset.png").await;
-        let tileset_bind_group =
-            create_texture_bind_group(&device, &texture_bind_group_layout, &tileset_texture);
+        let tileset_texture = load_texture(&device, &queue, TILESET_PATH, SamplerPreset::PIXEL_ART).await;
+
+        // Create a white texture for drawing solid colored quads
+        let solid_texture = create_solid_texture(&device, &queue, [255, 255, 255, 255]);
+        let solid_bind_group =
+            create_texture_bind_group(&device, &texture_bind_group_layout, &solid_texture);
+
+        // Load the sprite strip for decorative props like clouds and birds
+        let props_texture = load_texture(&device, &queue, "assets/props/Props.png", SamplerPreset::PIXEL_ART).await;
+
+        // Load the sprite strip for the player's and turrets' projectiles
+        let projectiles_texture = load_texture(&device, &queue, "assets/props/Projectiles.png", SamplerPreset::PIXEL_ART).await;
+
+        // Load the sheet the decorations scattered over the ground are cut from
+        let decorations_texture = load_texture(&device, &queue, &decorations().sheet, SamplerPreset::PIXEL_ART).await;
+
+        // Load the glyph atlas for UI text
+        let text = TextRenderer::new(&device, &queue, &render_config).await;
+
+        // Create the soft ellipse shadows are drawn with
+        let shadow_texture = create_shadow_texture(&device, &queue);
+
+        // Load background textures, one per layer; bands of the same image share its texture
+        let mut background_textures: Vec<Texture> = Vec::new();
+        for (i, layer) in backgrounds.iter().enumerate() {
+            let texture = match backgrounds[..i].iter().position(|other| other.image == layer.image) {
+                Some(loaded) => background_textures[loaded].clone(),
+                None => load_texture(&device, &queue, &layer.image, SamplerPreset::BACKGROUND).await,
+            };
+            background_textures.push(texture);
+        }
+
+        // Copy every sprite texture into one array, in `SpriteLayer` order
+        let sprite_array_bind_group_layout = create_sprite_array_bind_group_layout(&device);
+        let sources = [
+            &texture,
+            &tileset_texture,
+            &solid_texture,
+            &props_texture,
+            &projectiles_texture,
+            &decorations_texture,
+            &text.texture,
+            &shadow_texture,
+        ]
+        .into_iter()
+        .chain(&background_textures)
+        .cloned()
+        .collect();
+        let sprites = SpriteArray::new(&device, &queue, &sprite_array_bind_group_layout, sources, tuning.mipmaps);
 
         // Calculate tileset dimensions
         let tile_pixel_size = 16; // Each tile is 16x16 pixels
@@ -101,7 +376,8 @@ impl Renderer {
         let pipeline = create_pipeline(
             &device,
             &config,
-            &texture_bind_group_layout,
+            sample_count,
+            &sprite_array_bind_group_layout,
         );
 
         // Create vertex and index buffers
@@ -117,55 +393,332 @@ impl Renderer {
         });
         let num_indices = INDICES.len() as u32;
 
-        let max_instances = 1000; // Adjust as needed
-        let instance_buffer_size = max_instances * std::mem::size_of::<InstanceData>() as wgpu::BufferAddress;
-
-        // Create the instance buffer
-        let instance_buffer = device.create_buffer(&wgpu::BufferDescriptor {
-            label: Some("Instance Buffer"),
-            size: instance_buffer_size,
-            usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
-            mapped_at_creation: false,
+        // Create the globals uniform and the water material that reads it
+        let globals_bind_group_layout = create_globals_bind_group_layout(&device);
+        let globals_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Globals Buffer"),
+            contents: bytemuck::bytes_of(&Globals {
+                time: 0.0,
+                camera_x: 0.0,
+                camera_y: 0.0,
+                zoom: 1.0,
+                reflection: 0.0,
+                saturation: 1.0,
+                vignette: 0.0,
+                contrast: 1.0,
+                fade: 0.0,
+                _padding: [0.0; 3],
+            }),
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        });
+        let globals_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Globals Bind Group"),
+            layout: &globals_bind_group_layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: globals_buffer.as_entire_binding(),
+            }],
         });
+        let water_pipeline = create_material_pipeline(
+            &device,
+            &config,
+            sample_count,
+            &[&globals_bind_group_layout, &texture_bind_group_layout],
+            "Water",
+            include_str!("shaders/water.wgsl"),
+        );
+        let portal_pipeline = create_material_pipeline(
+            &device,
+            &config,
+            sample_count,
+            &[&globals_bind_group_layout],
+            "Portal",
+            include_str!("shaders/portal.wgsl"),
+        );
+
+        // Create the instance buffer, grown by `reserve_instances` for frames with more
+        let instance_buffer = create_instance_buffer(&device, INITIAL_INSTANCE_CAPACITY);
 
-    // Load background textures
-    let background_paths = vec![
-        "assets/tileset/BG1.png", // Far background
-        "assets/tileset/BG2.png", // Middle background
-        "assets/tileset/BG3.png", // Near background
-    ];
+        // Simulate heavy particle effects on the GPU where compute shaders are available
+        let particles = if adapter
+            .get_downlevel_capabilities()
+            .flags
+            .contains(wgpu::DownlevelFlags::COMPUTE_SHADERS)
+        {
+            Some(GpuParticles::new(&device, &config, sample_count))
+        } else {
+            log::warn!("Compute shaders are unsupported; GPU particle effects are off");
+            None
+        };
 
-    let mut background_textures = Vec::new();
-    let mut background_bind_groups = Vec::new();
+        // Time passes on the GPU for the debug HUD where it can
+        let gpu_timer = if features.contains(wgpu::Features::TIMESTAMP_QUERY) {
+            Some(GpuTimer::new(&device, &queue))
+        } else {
+            log::info!("Timestamp queries are unsupported; GPU pass times are off");
+            None
+        };
 
-    for path in background_paths {
-        let texture = load_texture(&device, &queue, path).await;
-        let bind_group = create_texture_bind_group(&device, &texture_bind_group_layout, &texture);
+        // Draw the scene offscreen first when water reflects it
+        let post = tuning
+            .water_reflections
+            .then(|| PostProcess::new(&device, &render_config, sample_count, &texture_bind_group_layout));
+        // Whichever copies onto the surface last applies the effects: the letterbox, or
+        // else the post-processing pass
+        let letterbox = virtual_size.map(|size| {
+            Letterbox::new(&device, &config, size, &texture_bind_group_layout, &globals_bind_group_layout)
+        });
+        let effects = (tuning.post_processing && virtual_size.is_none()).then(|| {
+            PostEffects::new(&device, &config, &texture_bind_group_layout, &globals_bind_group_layout)
+        });
+        let light_map = tuning.lighting.then(|| {
+            LightMap::new(&device, &render_config, sample_count, &texture_bind_group_layout, &globals_bind_group_layout)
+        });
 
-        background_textures.push(texture);
-        background_bind_groups.push(bind_group);
-    }
-    
+        // Create the buffers for per-frame meshes
+        let max_mesh_vertices = 4096;
+        let mesh_vertex_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Mesh Vertex Buffer"),
+            size: max_mesh_vertices * std::mem::size_of::<Vertex>() as wgpu::BufferAddress,
+            usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let mesh_index_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Mesh Index Buffer"),
+            size: max_mesh_vertices * 3 * std::mem::size_of::<u16>() as wgpu::BufferAddress,
+            usage: wgpu::BufferUsages::INDEX | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
 
-    Self {
+    Some(Self {
         surface,
         device,
         queue,
         config,
         pipeline,
+        #[cfg(debug_assertions)]
+        sprite_array_bind_group_layout,
         vertex_buffer,
         index_buffer,
         num_indices,
-        texture_bind_group,
-        tileset_texture,
-        tileset_bind_group,
+        texture_bind_group_layout,
+        sprites,
+        texture_size: (texture.width, texture.height),
         tileset_columns,
         tileset_rows,
         instance_buffer,
         depth_texture,
+        sample_count,
+        multisample_view,
         background_textures,
-        background_bind_groups, // Include depth texture
+        water_pipeline,
+        portal_pipeline,
+        globals_buffer,
+        globals_bind_group,
+        solid_bind_group,
+        text,
+        mesh_vertex_buffer,
+        mesh_index_buffer,
+        particles,
+        post,
+        letterbox,
+        effects,
+        light_map,
+        gpu_timer,
+        adapter_info,
+        low_power,
+        minimized: false,
+        occluded: false,
+    })
+}
+
+/// Rebuilds the sprite pipeline from its shader file on disk, keeping the current
+/// pipeline if the shader can't be read or doesn't compile.
+#[cfg(debug_assertions)]
+pub fn reload_shader(&mut self) {
+    match reload_pipeline(&self.device, &self.config, self.sample_count, &self.sprite_array_bind_group_layout) {
+        Ok(pipeline) => {
+            self.pipeline = pipeline;
+            log::info!("Shader reloaded: {}", SHADER_FILE);
+        }
+        Err(e) => log::warn!("Could not reload shader '{}': {}", SHADER_FILE, e),
+    }
+}
+
+/// Reconfigures the surface, depth texture, multisampled target, and scene texture for
+/// a new window size.
+///
+/// With a virtual resolution only the surface changes, and the canvas is fitted
+/// into it again. Zero-sized windows (e.g. when minimized) are only marked as not
+/// visible, since a surface cannot be configured with no area.
+///
+/// # Arguments
+/// - `new_size`: The new inner size of the window in physical pixels.
+pub fn resize(&mut self, new_size: winit::dpi::PhysicalSize<u32>) {
+    self.minimized = new_size.width == 0 || new_size.height == 0;
+    if self.minimized {
+        return;
+    }
+
+    self.config.width = new_size.width;
+    self.config.height = new_size.height;
+    if let Some(surface) = &self.surface {
+        surface.configure(&self.device, &self.config);
+    }
+    if let Some(letterbox) = &mut self.letterbox {
+        letterbox.resize(new_size.width, new_size.height);
+        return;
+    }
+    self.depth_texture = create_depth_texture(&self.device, &self.config, self.sample_count);
+    self.multisample_view = create_multisample_view(&self.device, &self.config, self.sample_count);
+    if let Some(post) = &mut self.post {
+        post.resize(&self.device, &self.config, &self.texture_bind_group_layout);
+    }
+    if let Some(effects) = &mut self.effects {
+        effects.resize(&self.device, &self.config, &self.texture_bind_group_layout);
+    }
+    if let Some(light_map) = &mut self.light_map {
+        light_map.resize(&self.device, &self.config, &self.texture_bind_group_layout);
     }
+    self.text.resize(new_size.width, new_size.height);
+}
+
+/// Grows the instance buffer to hold at least the given number of instances, to
+/// the next power of two, so busy frames don't overflow it. The buffer never shrinks.
+///
+/// # Arguments
+/// - `count`: The number of instances about to be uploaded.
+pub fn reserve_instances(&mut self, count: usize) {
+    let needed = (count * std::mem::size_of::<InstanceData>()) as wgpu::BufferAddress;
+    if needed <= self.instance_buffer.size() {
+        return;
+    }
+    let capacity = count.next_power_of_two();
+    log::debug!("Growing the instance buffer to {} instances", capacity);
+    self.instance_buffer = create_instance_buffer(&self.device, capacity);
+}
+
+/// Uploads a mesh into the mesh buffers for drawing this frame.
+///
+/// Meshes too large for the buffers are skipped with a warning.
+///
+/// # Arguments
+/// - `mesh`: The mesh to upload. Its index count must be even, as strips always are.
+///
+/// # Returns
+/// The number of indices to draw from `mesh_index_buffer`.
+pub fn upload_mesh(&self, mesh: &Mesh) -> u32 {
+    let vertex_bytes: &[u8] = bytemuck::cast_slice(&mesh.vertices);
+    let index_bytes: &[u8] = bytemuck::cast_slice(&mesh.indices);
+    if index_bytes.is_empty() {
+        return 0;
+    }
+    if vertex_bytes.len() as u64 > self.mesh_vertex_buffer.size()
+        || index_bytes.len() as u64 > self.mesh_index_buffer.size()
+    {
+        log::warn!(
+            "Skipping mesh with {} vertices and {} indices: too large for the mesh buffers",
+            mesh.vertices.len(),
+            mesh.indices.len()
+        );
+        return 0;
+    }
+
+    self.queue.write_buffer(&self.mesh_vertex_buffer, 0, vertex_bytes);
+    self.queue.write_buffer(&self.mesh_index_buffer, 0, index_bytes);
+    mesh.indices.len() as u32
+}
+
+/// Returns the size frames are drawn at, in pixels: the virtual resolution if there
+/// is one, or else the window's.
+pub fn render_size(&self) -> (u32, u32) {
+    match &self.letterbox {
+        Some(letterbox) => letterbox.size(),
+        None => (self.config.width, self.config.height),
+    }
+}
+
+/// Returns where frames are drawn in the window: x, y, width, and height in pixels.
+/// This is the whole window unless a virtual resolution letterboxes it.
+pub fn viewport(&self) -> [f32; 4] {
+    match &self.letterbox {
+        Some(letterbox) => letterbox.viewport(),
+        None => [0.0, 0.0, self.config.width as f32, self.config.height as f32],
+    }
+}
+
+/// Records whether the window is fully hidden behind other windows.
+pub fn set_occluded(&mut self, occluded: bool) {
+    self.occluded = occluded;
+}
+
+/// Returns whether anything drawn would be seen: the window is neither minimized
+/// nor hidden. Frames aren't drawn while it isn't.
+pub fn is_visible(&self) -> bool {
+    !self.minimized && !self.occluded
+}
+
+/// Writes the timestamp starting a span of the frame timed on the GPU, outside of any pass.
+///
+/// # Returns
+///
+/// The span to end with `end_timing`, or `None` if frames aren't being timed.
+pub fn begin_timing(&self, encoder: &mut wgpu::CommandEncoder, name: &'static str) -> Option<u32> {
+    self.gpu_timer.as_ref()?.begin(encoder, name)
+}
+
+/// Writes the timestamp ending a span begun with `begin_timing`, if it was timed.
+pub fn end_timing(&self, encoder: &mut wgpu::CommandEncoder, span: Option<u32>) {
+    if let (Some(timer), Some(span)) = (&self.gpu_timer, span) {
+        timer.end(encoder, span);
+    }
+}
+
+/// Returns the post-processing to draw the frame through, or `None` if it's off or
+/// skipped to save power.
+pub fn active_post(&self) -> Option<&PostProcess> {
+    self.post.as_ref().filter(|_| !self.low_power)
+}
+
+/// Returns the post-processing pass to finish the frame with, or `None` if it's off,
+/// skipped to save power, or left to the letterbox.
+pub fn active_effects(&self) -> Option<&PostEffects> {
+    self.effects.as_ref().filter(|_| !self.low_power)
+}
+
+/// Returns whether the frame's last pass applies the post-processing effects: they're
+/// on and not skipped to save power. When they aren't, screen fades are left to the caller.
+pub fn effects_active(&self) -> bool {
+    tuning().post_processing && !self.low_power
+}
+
+/// Uploads the per-frame values read by material shaders.
+///
+/// # Arguments
+/// - `time`: Seconds since the game started, used to animate materials.
+/// - `camera_x`, `camera_y`: The camera position, so shaders can work in world space.
+/// - `zoom`: The camera magnification applied to world instances.
+/// - `saturation`: Color the frame keeps, from 0.0 (grey) to 1.0, applied by its last
+///   pass: the letterbox, or else the post-processing pass.
+/// - `fade`: How far the post-processing pass fades the frame to black, from 0.0 to 1.0.
+pub fn update_globals(&self, time: f32, camera_x: f32, camera_y: f32, zoom: f32, saturation: f32, fade: f32) {
+    // Effects left out keep their neutral values, as the letterbox applies them regardless
+    let tuning = tuning();
+    let effects = self.effects_active();
+    let globals = Globals {
+        time,
+        camera_x,
+        camera_y,
+        zoom,
+        reflection: if self.active_post().is_some() { 1.0 } else { 0.0 },
+        saturation: if effects { saturation * tuning.saturation } else { saturation },
+        vignette: if effects { tuning.vignette.clamp(0.0, 1.0) } else { 0.0 },
+        contrast: if effects { tuning.contrast } else { 1.0 },
+        fade: if effects { fade } else { 0.0 },
+        _padding: [0.0; 3],
+    };
+    self.queue
+        .write_buffer(&self.globals_buffer, 0, bytemuck::bytes_of(&globals));
 }
 
 pub fn create_transform_matrix(
@@ -182,4 +735,34 @@ pub fn create_transform_matrix(
         [x,      y,       z,      1.0],
     ]
 }
+
+/// Creates a transformation matrix that also rotates the sprite about a pivot.
+///
+/// # Arguments
+/// - `x`, `y`, `z`: Position of the pivot.
+/// - `scale_x`, `scale_y`: Size of the sprite before rotation.
+/// - `angle`: Counterclockwise rotation in radians.
+/// - `pivot`: The point the sprite turns about, from its center as a fraction of its
+///   size: `(0.0, 0.0)` turns it about its center, `(0.0, 0.5)` about its top edge.
+pub fn create_rotated_transform_matrix(
+    x: f32,
+    y: f32,
+    z: f32,
+    scale_x: f32,
+    scale_y: f32,
+    angle: f32,
+    pivot: (f32, f32),
+) -> [[f32; 4]; 4] {
+    let (sin, cos) = angle.sin_cos();
+    // Move the pivot to the origin before turning, then out to the position
+    let (pivot_x, pivot_y) = (pivot.0 * scale_x, pivot.1 * scale_y);
+    let center_x = x - (cos * pivot_x - sin * pivot_y);
+    let center_y = y - (sin * pivot_x + cos * pivot_y);
+    [
+        [cos * scale_x,  sin * scale_x, 0.0, 0.0],
+        [-sin * scale_y, cos * scale_y, 0.0, 0.0],
+        [0.0,            0.0,           1.0, 0.0],
+        [center_x,       center_y,      z,   1.0],
+    ]
+}
 }
@@ -0,0 +1,62 @@
+// Blit: copies the offscreen scene texture onto the screen with a single
+// triangle covering the whole view. As the last pass of the frame, the copy
+// also applies the post-processing effects by the globals' values.
+
+@group(0) @binding(0)
+var scene: texture_2d<f32>;
+@group(0) @binding(1)
+var scene_sampler: sampler;
+
+struct Globals {
+    time: f32,
+    camera_x: f32,
+    camera_y: f32,
+    zoom: f32,
+    reflection: f32,
+    saturation: f32,
+    vignette: f32,
+    contrast: f32,
+    fade: f32,
+};
+
+@group(1) @binding(0)
+var<uniform> globals: Globals;
+
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+    @location(0) uv: vec2<f32>,
+};
+
+@vertex
+fn vs_main(@builtin(vertex_index) index: u32) -> VertexOutput {
+    var output: VertexOutput;
+
+    // Corners at (-1, -1), (3, -1), and (-1, 3) cover the view
+    let corner = vec2<f32>(f32((index << 1u) & 2u), f32(index & 2u));
+    output.position = vec4<f32>(corner * 2.0 - 1.0, 0.0, 1.0);
+    output.uv = vec2<f32>(corner.x, 1.0 - corner.y);
+    return output;
+}
+
+@fragment
+fn fs_main(input: VertexOutput) -> @location(0) vec4<f32> {
+    return textureSample(scene, scene_sampler, input.uv);
+}
+
+@fragment
+fn fs_post(input: VertexOutput) -> @location(0) vec4<f32> {
+    let color = textureSample(scene, scene_sampler, input.uv);
+
+    // Grade the color: drain or boost it around its grey, then spread it from mid-grey
+    let luma = dot(color.rgb, vec3<f32>(0.299, 0.587, 0.114));
+    let saturated = mix(vec3<f32>(luma), color.rgb, globals.saturation);
+    var graded = clamp((saturated - 0.5) * globals.contrast + 0.5, vec3<f32>(0.0), vec3<f32>(1.0));
+
+    // Darken towards the edges, from nothing in the middle to the full vignette in the corners
+    let offset = (input.uv - 0.5) * 2.0;
+    let edge = dot(offset, offset) / 2.0;
+    graded *= 1.0 - globals.vignette * smoothstep(0.2, 1.0, edge);
+
+    // Fade to black over everything
+    return vec4<f32>(graded * (1.0 - globals.fade), color.a);
+}
@@ -0,0 +1,66 @@
+// Lights: each instance adds a soft circle of light to the light map, fading from
+// its center to its edge.
+//
+// Instances use `color.rgb` as the light's color, `color.a` as how strongly it
+// flickers, and `sprite_index` to offset its flicker from the other lights'.
+
+struct Globals {
+    time: f32,
+    camera_x: f32,
+    camera_y: f32,
+    zoom: f32,
+};
+
+@group(0) @binding(0)
+var<uniform> globals: Globals;
+
+struct VertexInput {
+    @location(0) position: vec3<f32>,
+    @location(1) uv: vec2<f32>,
+
+    // Instance data
+    @location(2) transform0: vec4<f32>,
+    @location(3) transform1: vec4<f32>,
+    @location(4) transform2: vec4<f32>,
+    @location(5) transform3: vec4<f32>,
+    @location(6) sprite_index: f32,
+    @location(11) color: vec4<f32>,
+};
+
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+    @location(0) uv: vec2<f32>,
+    @location(1) color: vec4<f32>,
+    @location(2) phase: f32,
+};
+
+@vertex
+fn vs_main(input: VertexInput) -> VertexOutput {
+    var output: VertexOutput;
+
+    let transform = mat4x4<f32>(
+        input.transform0,
+        input.transform1,
+        input.transform2,
+        input.transform3,
+    );
+    output.position = transform * vec4<f32>(input.position, 1.0);
+    output.uv = input.uv;
+    output.color = input.color;
+    output.phase = input.sprite_index * 1.7;
+    return output;
+}
+
+@fragment
+fn fs_main(input: VertexOutput) -> @location(0) vec4<f32> {
+    // Distance from the center, in 0..1 at the edge
+    let p = input.uv * 2.0 - 1.0;
+    let falloff = clamp(1.0 - dot(p, p), 0.0, 1.0);
+
+    // Two waves out of step flicker less regularly than one
+    let t = globals.time + input.phase;
+    let wave = 0.5 + 0.25 * sin(t * 11.0) + 0.25 * sin(t * 17.3);
+    let flicker = 1.0 - input.color.a * 0.4 * wave;
+
+    return vec4<f32>(input.color.rgb * falloff * falloff * flicker, 1.0);
+}
@@ -0,0 +1,131 @@
+// GPU particles: simulated by a compute shader and drawn straight from the same buffer.
+//
+// Particles with a lifetime of zero are unused slots. Looping particles (weather)
+// respawn along the top of the view when they expire or fall out of it, and wrap
+// around horizontally to stay in view as the camera moves.
+
+struct Particle {
+    position: vec2<f32>,
+    velocity: vec2<f32>,
+    start_velocity: vec2<f32>,
+    age: f32,
+    lifetime: f32,
+    size: vec2<f32>,
+    gravity: f32,
+    looping: f32,
+    color: vec4<f32>,
+    end_color: vec4<f32>,
+    aspect: f32,
+    _padding0: f32,
+    _padding1: f32,
+    _padding2: f32,
+};
+
+struct Params {
+    delta_time: f32,
+    seed: u32,
+    camera_x: f32,
+    camera_y: f32,
+    zoom: f32,
+    depth: f32,
+    _padding0: f32,
+    _padding1: f32,
+};
+
+// How far past the edges of the view looping particles respawn and wrap, in world units
+const VIEW_MARGIN: f32 = 0.2;
+
+@group(0) @binding(0)
+var<uniform> params: Params;
+
+@group(0) @binding(1)
+var<storage, read_write> particles: array<Particle>;
+
+// Returns a pseudo-random number in 0..1 from a seed (PCG hash)
+fn random(seed: u32) -> f32 {
+    var state = seed * 747796405u + 2891336453u;
+    let word = ((state >> ((state >> 28u) + 4u)) ^ state) * 277803737u;
+    return f32((word >> 22u) ^ word) / 4294967295.0;
+}
+
+@compute @workgroup_size(64)
+fn cs_main(@builtin(global_invocation_id) id: vec3<u32>) {
+    let index = id.x;
+    if index >= arrayLength(&particles) {
+        return;
+    }
+    var particle = particles[index];
+    if particle.lifetime <= 0.0 || (particle.age >= particle.lifetime && particle.looping < 0.5) {
+        return;
+    }
+
+    particle.age += params.delta_time;
+    particle.velocity.y += particle.gravity * params.delta_time;
+    particle.position += particle.velocity * params.delta_time;
+
+    if particle.looping > 0.5 {
+        let extent = 1.0 / params.zoom + VIEW_MARGIN;
+        let left = params.camera_x - extent;
+
+        // Keep within the view horizontally as the camera moves
+        particle.position.x = left + ((particle.position.x - left) % (2.0 * extent) + 2.0 * extent) % (2.0 * extent);
+
+        // Start over along the top of the view once expired or fallen out of it
+        if particle.age >= particle.lifetime || particle.position.y < params.camera_y - extent {
+            let seed = index ^ params.seed;
+            particle.position = vec2<f32>(left + random(seed) * 2.0 * extent, params.camera_y + extent);
+            particle.velocity = particle.start_velocity;
+            particle.age = 0.0;
+        }
+    }
+
+    particles[index] = particle;
+}
+
+struct VertexInput {
+    @location(0) position: vec3<f32>,
+    @location(1) uv: vec2<f32>,
+
+    // Particle data
+    @location(2) particle_position: vec2<f32>,
+    @location(3) age: f32,
+    @location(4) lifetime: f32,
+    @location(5) size: vec2<f32>,
+    @location(6) color: vec4<f32>,
+    @location(7) end_color: vec4<f32>,
+    @location(8) aspect: f32,
+};
+
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+    @location(0) color: vec4<f32>,
+};
+
+@vertex
+fn vs_main(input: VertexInput) -> VertexOutput {
+    var output: VertexOutput;
+
+    // Move unused and expired particles outside the clip volume
+    if input.lifetime <= 0.0 || input.age >= input.lifetime {
+        output.position = vec4<f32>(2.0, 2.0, 2.0, 1.0);
+        output.color = vec4<f32>(0.0);
+        return output;
+    }
+
+    let t = input.age / input.lifetime;
+    let size = mix(input.size.x, input.size.y, t);
+    let world = input.particle_position + input.position.xy * vec2<f32>(size, size * input.aspect);
+    let view = (world - vec2<f32>(params.camera_x, params.camera_y)) * params.zoom;
+
+    output.position = vec4<f32>(view, params.depth, 1.0);
+    output.color = mix(input.color, input.end_color, t);
+    return output;
+}
+
+@fragment
+fn fs_main(input: VertexOutput) -> @location(0) vec4<f32> {
+    if input.color.a <= 0.0 {
+        discard;
+    }
+    return input.color;
+}
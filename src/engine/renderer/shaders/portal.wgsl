@@ -0,0 +1,65 @@
+// Portal material: a glowing, swirling oval drawn without a texture.
+//
+// Instances use `color` as the glow color.
+
+struct Globals {
+    time: f32,
+    camera_x: f32,
+    camera_y: f32,
+    zoom: f32,
+};
+
+@group(0) @binding(0)
+var<uniform> globals: Globals;
+
+struct VertexInput {
+    @location(0) position: vec3<f32>,
+    @location(1) uv: vec2<f32>,
+
+    // Instance data
+    @location(2) transform0: vec4<f32>,
+    @location(3) transform1: vec4<f32>,
+    @location(4) transform2: vec4<f32>,
+    @location(5) transform3: vec4<f32>,
+    @location(11) color: vec4<f32>,
+};
+
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+    @location(0) uv: vec2<f32>,
+    @location(1) color: vec4<f32>,
+};
+
+@vertex
+fn vs_main(input: VertexInput) -> VertexOutput {
+    var output: VertexOutput;
+
+    let transform = mat4x4<f32>(
+        input.transform0,
+        input.transform1,
+        input.transform2,
+        input.transform3,
+    );
+    output.position = transform * vec4<f32>(input.position, 1.0);
+    output.uv = input.uv;
+    output.color = input.color;
+    return output;
+}
+
+@fragment
+fn fs_main(input: VertexOutput) -> @location(0) vec4<f32> {
+    // Position relative to the center, in -1..1
+    let p = input.uv * 2.0 - 1.0;
+    let radius = length(p);
+    if radius > 1.0 {
+        discard;
+    }
+
+    // Spiral arms rotating over time, brightest toward the rim
+    let angle = atan2(p.y, p.x);
+    let swirl = 0.5 + 0.5 * sin(angle * 3.0 + radius * 8.0 - globals.time * 4.0);
+    let rim = smoothstep(0.5, 1.0, radius);
+    let intensity = mix(swirl * 0.6, 1.0, rim) * (1.0 - smoothstep(0.9, 1.0, radius));
+
+    return vec4<f32>(input.color.rgb * (0.4 + intensity), input.color.a * intensity);
+}
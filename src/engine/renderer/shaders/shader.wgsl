@@ -1,8 +1,12 @@
-// Texture bindings
+// Texture bindings: every sprite texture, one per layer of an array
 @group(0) @binding(0)
-var sprite_sheet: texture_2d<f32>;
+var sprites: texture_2d_array<f32>;
 @group(0) @binding(1)
 var sprite_sampler: sampler;
+// How much of each layer its texture covers, in xy; textures sit in the top-left corner.
+// z is 1.0 if the texture is filtered linearly, and w is 1.0 if it repeats
+@group(0) @binding(2)
+var<uniform> extents: array<vec4<f32>, 32>;
 
 // Vertex input and output structures
 struct VertexInput {
@@ -15,10 +19,11 @@ struct VertexInput {
     @location(4) transform2: vec4<f32>,
     @location(5) transform3: vec4<f32>,
     @location(6) sprite_index: f32,
-    @location(7) _padding1: f32,
+    @location(7) layer: f32,
     @location(8) sprite_size: vec2<f32>,
     @location(9) uv_offset: vec2<f32>,
     @location(10) uv_scale: vec2<f32>,
+    @location(11) color: vec4<f32>,
 };
 
 struct VertexOutput {
@@ -27,6 +32,8 @@ struct VertexOutput {
     @location(1) sprite_index: f32,
     @location(2) sprite_size: vec2<f32>,
     @location(3) depth: f32, // Depth for the fragment shader
+    @location(4) color: vec4<f32>,
+    @location(5) @interpolate(flat) layer: i32,
 };
 
 // Vertex shader
@@ -55,6 +62,8 @@ fn vs_main(input: VertexInput) -> VertexOutput {
     // Pass through instance data to fragment shader
     output.sprite_index = input.sprite_index;
     output.sprite_size = input.sprite_size;
+    output.color = input.color;
+    output.layer = i32(input.layer);
 
     return output;
 }
@@ -84,6 +93,33 @@ fn fs_main(input: VertexOutput) -> @location(0) vec4<f32> {
         adjusted_uv = input.tex_coords;
     }
 
-    // Sample the texture outside of the conditional using textureSampleLevel
-    return textureSampleLevel(sprite_sheet, sprite_sampler, adjusted_uv, 0.0);
+    // Wrap repeating textures, then scale into the part of the layer the texture
+    // covers, keeping to its own texels as clamping to its edge would
+    let settings = extents[input.layer];
+    let extent = settings.xy;
+    let wrapped_uv = select(adjusted_uv, fract(adjusted_uv), settings.w > 0.5);
+    let texels = vec2<f32>(textureDimensions(sprites));
+    let half_texel = 0.5 / texels;
+    let layer_uv = clamp(wrapped_uv * extent, half_texel, extent - half_texel);
+
+    // The sampler filters linearly, so layers filtered to the nearest texel sample at
+    // its center. The mip level is still picked from how the unsnapped UVs change
+    // across neighboring pixels
+    let nearest_uv = (floor(layer_uv * texels) + 0.5) / texels;
+    let sample_uv = select(nearest_uv, layer_uv, settings.z > 0.5);
+    let color = textureSampleGrad(
+        sprites,
+        sprite_sampler,
+        sample_uv,
+        input.layer,
+        dpdx(layer_uv),
+        dpdy(layer_uv),
+    ) * input.color;
+
+    // Skip fully transparent fragments so they don't write depth
+    if color.a <= 0.0 {
+        discard;
+    }
+
+    return color;
 }
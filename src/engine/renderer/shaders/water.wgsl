@@ -0,0 +1,113 @@
+// Water material: a translucent, animated fill for water tiles.
+//
+// Instances use `color` as the water tint and `sprite_index` as a flag:
+// 1.0 marks a surface tile, whose top edge is displaced by a sine wave.
+// `uv_offset.x` is the height of the water's surface, about which the scene
+// texture is mirrored to reflect the scene above it.
+
+struct Globals {
+    time: f32,
+    camera_x: f32,
+    camera_y: f32,
+    zoom: f32,
+    reflection: f32,
+};
+
+@group(0) @binding(0)
+var<uniform> globals: Globals;
+
+// The scene drawn so far, behind the water
+@group(1) @binding(0)
+var scene: texture_2d<f32>;
+@group(1) @binding(1)
+var scene_sampler: sampler;
+
+// Wave shape along the surface, in world units and radians per second
+const WAVE_AMPLITUDE: f32 = 0.02;
+const WAVE_FREQUENCY: f32 = 6.0;
+const WAVE_SPEED: f32 = 2.5;
+
+// How strongly the surface reflects, how far below it the reflection fades out,
+// and how far ripples shift it sideways, in world units
+const REFLECTION_STRENGTH: f32 = 0.45;
+const REFLECTION_DEPTH: f32 = 0.5;
+const REFLECTION_RIPPLE: f32 = 0.008;
+
+struct VertexInput {
+    @location(0) position: vec3<f32>,
+    @location(1) uv: vec2<f32>,
+
+    // Instance data
+    @location(2) transform0: vec4<f32>,
+    @location(3) transform1: vec4<f32>,
+    @location(4) transform2: vec4<f32>,
+    @location(5) transform3: vec4<f32>,
+    @location(6) sprite_index: f32,
+    @location(9) uv_offset: vec2<f32>,
+    @location(11) color: vec4<f32>,
+};
+
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+    @location(0) uv: vec2<f32>,
+    @location(1) world: vec2<f32>,
+    @location(2) color: vec4<f32>,
+    @location(3) surface: f32,
+    @location(4) surface_y: f32,
+};
+
+@vertex
+fn vs_main(input: VertexInput) -> VertexOutput {
+    var output: VertexOutput;
+
+    let transform = mat4x4<f32>(
+        input.transform0,
+        input.transform1,
+        input.transform2,
+        input.transform3,
+    );
+    var position = transform * vec4<f32>(input.position, 1.0);
+    let world_x = position.x / globals.zoom + globals.camera_x;
+
+    // Ripple the top edge of surface tiles
+    if input.sprite_index > 0.5 && input.position.y > 0.0 {
+        position.y += sin(world_x * WAVE_FREQUENCY + globals.time * WAVE_SPEED) * WAVE_AMPLITUDE * globals.zoom;
+    }
+
+    output.position = position;
+    output.uv = input.uv;
+    output.world = vec2<f32>(world_x, position.y / globals.zoom + globals.camera_y);
+    output.color = input.color;
+    output.surface = input.sprite_index;
+    output.surface_y = input.uv_offset.x;
+    return output;
+}
+
+@fragment
+fn fs_main(input: VertexOutput) -> @location(0) vec4<f32> {
+    var color = input.color;
+
+    // Subtle shifting bands below the surface
+    let distortion = sin(input.world.x * 20.0 + input.world.y * 14.0 + globals.time * 1.5)
+        * sin(input.world.y * 9.0 - globals.time);
+    color = vec4<f32>(color.rgb + distortion * 0.04, color.a);
+
+    // Mirror the scene above the surface, rippling and fading out with depth
+    if globals.reflection > 0.0 {
+        let depth = max(input.surface_y - input.world.y, 0.0);
+        let ripple = sin(input.world.y * 40.0 - globals.time * 3.0) * REFLECTION_RIPPLE;
+        let mirrored = vec2<f32>(input.world.x + ripple, input.surface_y + depth);
+        let view = (mirrored - vec2<f32>(globals.camera_x, globals.camera_y)) * globals.zoom;
+        let uv = vec2<f32>(view.x * 0.5 + 0.5, 0.5 - view.y * 0.5);
+        let reflected = textureSampleLevel(scene, scene_sampler, uv, 0.0).rgb;
+        let strength = globals.reflection * REFLECTION_STRENGTH * (1.0 - min(depth / REFLECTION_DEPTH, 1.0));
+        color = vec4<f32>(mix(color.rgb, reflected, strength), color.a);
+    }
+
+    // Light foam line along the surface
+    if input.surface > 0.5 && input.uv.y < 0.12 {
+        color = vec4<f32>(mix(color.rgb, vec3<f32>(0.85, 0.95, 1.0), 0.6), min(color.a + 0.2, 1.0));
+    }
+
+    return color;
+}
@@ -0,0 +1,91 @@
+// shading.rs
+use crate::engine::renderer::tile::TileMap;
+use std::collections::{HashMap, HashSet, VecDeque};
+
+/// Tiles into the ground over which its shading deepens to full strength.
+const DEPTH: u32 = 3;
+
+/// Tiles below a ceiling over which the shade it casts fades out.
+const CEILING_REACH: i32 = 3;
+
+/// Bakes edge shading into a tile map's tiles, giving its ground depth without any
+/// lighting: solid tiles darken the farther they are from open space, and the tiles
+/// of other layers darken the closer they are under a solid ceiling.
+///
+/// Space above the top of the ground counts as open, while space past its other
+/// edges counts as more ground, so the bottom and sides of a level stay dark.
+/// Oversized tiles block out every cell they cover but are left unshaded.
+///
+/// # Arguments
+///
+/// * `tile_map` - The map to shade, whose tiles' `shade` is overwritten.
+/// * `strength` - How dark the deepest shading gets, from 0.0 (off) to 1.0 (black).
+pub fn bake(tile_map: &mut TileMap, strength: f32) {
+    let strength = strength.clamp(0.0, 1.0);
+    let Some(origin) = tile_map.tiles().next().map(|tile| tile.position) else {
+        return;
+    };
+    let (tile_width, tile_height) = (tile_map.tile_width, tile_map.tile_height);
+    // Cells counted from the first tile, so positions off the grid's origin round the same way
+    let cell = |x: f32, y: f32| {
+        (
+            ((x - origin.0) / tile_width).round() as i32,
+            ((y - origin.1) / tile_height).round() as i32,
+        )
+    };
+
+    // Every cell the solid layers cover
+    let mut solid = HashSet::new();
+    for tile in tile_map.layers.iter().filter(|layer| layer.solid).flat_map(|layer| &layer.tiles) {
+        let bounds = tile.bounds();
+        let (first_column, first_row) = cell(bounds.min_x + tile_width / 2.0, bounds.min_y + tile_height / 2.0);
+        let (last_column, last_row) = cell(bounds.max_x - tile_width / 2.0, bounds.max_y - tile_height / 2.0);
+        for column in first_column..=last_column.max(first_column) {
+            for row in first_row..=last_row.max(first_row) {
+                solid.insert((column, row));
+            }
+        }
+    }
+
+    // Steps from each solid cell to open space, spreading inward from the cells beside it
+    let mut depths: HashMap<(i32, i32), u32> = HashMap::new();
+    if let (Some(&(min_column, _)), Some(&(max_column, _))) =
+        (solid.iter().min_by_key(|cell| cell.0), solid.iter().max_by_key(|cell| cell.0))
+    {
+        let min_row = solid.iter().map(|cell| cell.1).min().unwrap_or_default();
+        let max_row = solid.iter().map(|cell| cell.1).max().unwrap_or_default();
+        let mut queue: VecDeque<((i32, i32), u32)> = (min_column..=max_column)
+            .flat_map(|column| (min_row..=max_row + 1).map(move |row| (column, row)))
+            .filter(|cell| !solid.contains(cell))
+            .map(|cell| (cell, 0))
+            .collect();
+        while let Some(((column, row), depth)) = queue.pop_front() {
+            for neighbor in [(column, row + 1), (column + 1, row), (column, row - 1), (column - 1, row)] {
+                if solid.contains(&neighbor) && !depths.contains_key(&neighbor) {
+                    depths.insert(neighbor, depth + 1);
+                    queue.push_back((neighbor, depth + 1));
+                }
+            }
+        }
+    }
+
+    for layer in &mut tile_map.layers {
+        for tile in &mut layer.tiles {
+            if tile.span != (1, 1) {
+                tile.shade = 1.0;
+                continue;
+            }
+            let (column, row) = cell(tile.position.0, tile.position.1);
+            let darkness = if layer.solid {
+                // Ground sealed off from open space is as deep as it gets
+                let depth = depths.get(&(column, row)).copied().unwrap_or(DEPTH + 1);
+                (depth.saturating_sub(1).min(DEPTH)) as f32 / DEPTH as f32
+            } else {
+                (1..=CEILING_REACH)
+                    .find(|above| solid.contains(&(column, row + above)))
+                    .map_or(0.0, |above| 1.0 - (above - 1) as f32 / CEILING_REACH as f32)
+            };
+            tile.shade = 1.0 - strength * darkness;
+        }
+    }
+}
@@ -0,0 +1,147 @@
+// snapshot.rs
+use image::{Rgba, RgbaImage};
+use log::info;
+
+/// A texture frames are drawn into instead of a window's surface, and read back from
+/// as images, such as for the rendering regression tests.
+pub struct OffscreenTarget {
+    texture: wgpu::Texture,
+    /// The view to draw frames into.
+    pub view: wgpu::TextureView,
+}
+
+impl OffscreenTarget {
+    /// Creates the target.
+    ///
+    /// # Arguments
+    /// - `device`: The `wgpu::Device` used to create GPU resources.
+    /// - `config`: The renderer's configuration, whose size the target matches. Its
+    ///   format must be `Rgba8UnormSrgb`, as a headless renderer's is.
+    pub fn new(device: &wgpu::Device, config: &wgpu::SurfaceConfiguration) -> Self {
+        info!("Creating a {}x{} offscreen target", config.width, config.height);
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Offscreen Target"),
+            size: wgpu::Extent3d {
+                width: config.width,
+                height: config.height,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: config.format,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::COPY_SRC,
+            view_formats: &[],
+        });
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+        Self { texture, view }
+    }
+
+    /// Copies what was drawn into the target back from the GPU, waiting for the copy.
+    ///
+    /// # Arguments
+    /// - `device`: The `wgpu::Device` used to record the copy and wait for it.
+    /// - `queue`: The `wgpu::Queue` used to submit it.
+    pub fn read(&self, device: &wgpu::Device, queue: &wgpu::Queue) -> RgbaImage {
+        let (width, height) = (self.texture.width(), self.texture.height());
+        // Rows are copied at the alignment the GPU requires, then trimmed
+        let row_bytes = width * 4;
+        let alignment = wgpu::COPY_BYTES_PER_ROW_ALIGNMENT;
+        let padded_row_bytes = row_bytes.div_ceil(alignment) * alignment;
+        let buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Offscreen Readback Buffer"),
+            size: (padded_row_bytes * height) as wgpu::BufferAddress,
+            usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+            mapped_at_creation: false,
+        });
+
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("Offscreen Readback Encoder"),
+        });
+        encoder.copy_texture_to_buffer(
+            self.texture.as_image_copy(),
+            wgpu::ImageCopyBuffer {
+                buffer: &buffer,
+                layout: wgpu::ImageDataLayout {
+                    offset: 0,
+                    bytes_per_row: Some(padded_row_bytes),
+                    rows_per_image: Some(height),
+                },
+            },
+            self.texture.size(),
+        );
+        queue.submit(Some(encoder.finish()));
+
+        let slice = buffer.slice(..);
+        slice.map_async(wgpu::MapMode::Read, |result| {
+            if let Err(e) = result {
+                log::error!("Failed to read back the offscreen target: {:?}", e);
+            }
+        });
+        device.poll(wgpu::Maintain::Wait);
+
+        let padded = slice.get_mapped_range();
+        let pixels = padded
+            .chunks(padded_row_bytes as usize)
+            .flat_map(|row| &row[..row_bytes as usize])
+            .copied()
+            .collect();
+        RgbaImage::from_raw(width, height, pixels).expect("Readback holds every row of the target.")
+    }
+}
+
+/// How far an image strays from its reference.
+pub struct ImageDifference {
+    /// Pixels with a channel further from the reference than the tolerance.
+    pub differing_pixels: usize,
+    /// The largest difference in any channel of any pixel, from 0 to 255.
+    pub max_difference: u8,
+    /// The reference with each differing pixel painted magenta, to show where they are.
+    pub highlighted: RgbaImage,
+}
+
+impl ImageDifference {
+    /// Returns the share of the image's pixels that differ, from 0.0 to 1.0.
+    pub fn differing_share(&self) -> f32 {
+        let (width, height) = self.highlighted.dimensions();
+        self.differing_pixels as f32 / (width * height).max(1) as f32
+    }
+}
+
+/// Compares an image against a reference, pixel by pixel.
+///
+/// # Arguments
+/// - `image`: The image to check.
+/// - `reference`: The image it's expected to match.
+/// - `tolerance`: How far a channel may stray before its pixel counts as differing,
+///   allowing for small differences between GPUs and drivers.
+///
+/// # Returns
+/// How the images differ, or `None` if they aren't the same size.
+pub fn compare_images(image: &RgbaImage, reference: &RgbaImage, tolerance: u8) -> Option<ImageDifference> {
+    if image.dimensions() != reference.dimensions() {
+        return None;
+    }
+    let mut highlighted = reference.clone();
+    let mut differing_pixels = 0;
+    let mut max_difference = 0;
+    for ((pixel, expected), marked) in image.pixels().zip(reference.pixels()).zip(highlighted.pixels_mut()) {
+        let difference = pixel
+            .0
+            .iter()
+            .zip(expected.0)
+            .map(|(&channel, expected)| channel.abs_diff(expected))
+            .max()
+            .unwrap_or(0);
+        max_difference = max_difference.max(difference);
+        if difference > tolerance {
+            differing_pixels += 1;
+            *marked = Rgba([255, 0, 255, 255]);
+        }
+    }
+    Some(ImageDifference {
+        differing_pixels,
+        max_difference,
+        highlighted,
+    })
+}
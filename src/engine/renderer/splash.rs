@@ -0,0 +1,45 @@
+// splash.rs
+
+/// Color the window is cleared to while the renderer warms up.
+pub const SPLASH_COLOR: wgpu::Color = wgpu::Color {
+    r: 0.05,
+    g: 0.08,
+    b: 0.12,
+    a: 1.0,
+};
+
+/// Clears the surface to the splash color and presents it, so the window shows
+/// something other than a blank or frozen frame while pipelines compile and
+/// textures upload.
+///
+/// # Arguments
+/// - `surface`: The configured window surface.
+/// - `device`: The `wgpu::Device` the surface was configured with.
+/// - `queue`: The queue to submit the clear to.
+pub fn present_splash(surface: &wgpu::Surface, device: &wgpu::Device, queue: &wgpu::Queue) {
+    let output = match surface.get_current_texture() {
+        Ok(output) => output,
+        Err(e) => {
+            log::warn!("Could not show the splash screen: {:?}", e);
+            return;
+        }
+    };
+    let view = output.texture.create_view(&wgpu::TextureViewDescriptor::default());
+    let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+        label: Some("Splash Encoder"),
+    });
+    encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+        label: Some("Splash Pass"),
+        color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+            view: &view,
+            resolve_target: None,
+            ops: wgpu::Operations {
+                load: wgpu::LoadOp::Clear(SPLASH_COLOR),
+                store: true,
+            },
+        })],
+        depth_stencil_attachment: None,
+    });
+    queue.submit(Some(encoder.finish()));
+    output.present();
+}
@@ -0,0 +1,265 @@
+// sprite_array.rs
+use crate::engine::renderer::mipmaps::{mip_level_count, MipmapGenerator};
+use crate::engine::renderer::texture::Texture;
+use log::{info, warn};
+use wgpu::util::DeviceExt;
+
+/// Most layers the sprite array holds, matching the extents array in `shader.wgsl`.
+pub const MAX_SPRITE_LAYERS: usize = 32;
+
+/// The layer each of the renderer's textures is copied into, in the order they're
+/// given to `SpriteArray::new`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SpriteLayer {
+    /// The character sprite sheet.
+    Player,
+    Tileset,
+    /// White, tinted by instances into solid colored quads.
+    Solid,
+    /// The sprite strip for decorative props.
+    Props,
+    /// The sprite strip for projectiles.
+    Projectiles,
+    /// The sheet decorations are cut from.
+    Decorations,
+    /// The glyph atlas for UI text.
+    Font,
+    /// A soft ellipse, tinted into the shadows under characters and items.
+    Shadow,
+    /// A background layer, by its index from the farthest.
+    Background(usize),
+}
+
+impl SpriteLayer {
+    /// Returns the layer's index in the array, as instances store it.
+    pub fn index(self) -> f32 {
+        let index = match self {
+            SpriteLayer::Player => 0,
+            SpriteLayer::Tileset => 1,
+            SpriteLayer::Solid => 2,
+            SpriteLayer::Props => 3,
+            SpriteLayer::Projectiles => 4,
+            SpriteLayer::Decorations => 5,
+            SpriteLayer::Font => 6,
+            SpriteLayer::Shadow => 7,
+            SpriteLayer::Background(background) => 8 + background,
+        };
+        index as f32
+    }
+}
+
+/// The textures sprites are drawn from, copied into the layers of one 2D texture
+/// array so every sprite batch can be drawn with the same bind group.
+///
+/// Layers are as large as the largest texture, and smaller textures sit in the
+/// top-left corner of theirs. Instances pick their layer with `InstanceData::layer`,
+/// and the shader scales their UVs to the part of the layer their texture covers.
+/// Each layer is sampled as its texture's `SamplerPreset` asks: nearest or linear,
+/// and clamped or wrapped within its part of the layer.
+///
+/// With mipmaps on, sprites drawn smaller than their pixels blend between filtered
+/// mip levels. Pixels drawn larger stay crisp either way.
+pub struct SpriteArray {
+    texture: wgpu::Texture,
+    pub bind_group: wgpu::BindGroup,
+    /// The textures copied into each layer, in layer order.
+    sources: Vec<Texture>,
+    /// Regenerates the mipmaps after the layers are copied, if mipmaps are on.
+    mipmaps: Option<MipmapGenerator>,
+}
+
+impl SpriteArray {
+    /// Creates the array and copies the textures into it.
+    ///
+    /// # Arguments
+    /// - `device`: The `wgpu::Device` used to create GPU resources.
+    /// - `queue`: The `wgpu::Queue` used to submit the copies.
+    /// - `layout`: The layout from `create_sprite_array_bind_group_layout`.
+    /// - `sources`: The textures in `SpriteLayer` order, each created with `COPY_SRC`
+    ///   usage in the sprite format. Any past `MAX_SPRITE_LAYERS` are left out.
+    /// - `mipmaps`: Whether to generate mipmaps and sample them trilinearly.
+    pub fn new(
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        layout: &wgpu::BindGroupLayout,
+        mut sources: Vec<Texture>,
+        mipmaps: bool,
+    ) -> Self {
+        if sources.len() > MAX_SPRITE_LAYERS {
+            warn!(
+                "Only {} of {} textures fit in the sprite array; the rest won't be drawn",
+                MAX_SPRITE_LAYERS,
+                sources.len()
+            );
+            sources.truncate(MAX_SPRITE_LAYERS);
+        }
+        let width = sources.iter().map(|source| source.width).max().unwrap_or(1);
+        let height = sources.iter().map(|source| source.height).max().unwrap_or(1);
+        info!("Creating a {}x{} sprite array of {} layers", width, height, sources.len());
+
+        let format = wgpu::TextureFormat::Rgba8UnormSrgb;
+        let (mip_level_count, usage) = if mipmaps {
+            (mip_level_count(width, height), wgpu::TextureUsages::RENDER_ATTACHMENT)
+        } else {
+            (1, wgpu::TextureUsages::empty())
+        };
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Sprite Array"),
+            size: wgpu::Extent3d {
+                width,
+                height,
+                depth_or_array_layers: sources.len().max(1) as u32,
+            },
+            mip_level_count,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format,
+            usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST | usage,
+            view_formats: &[],
+        });
+        let view = texture.create_view(&wgpu::TextureViewDescriptor {
+            dimension: Some(wgpu::TextureViewDimension::D2Array),
+            ..Default::default()
+        });
+        // Linear, with the shader snapping to texel centers for layers filtered to the
+        // nearest texel; a texture can't be sampled with two samplers on every backend.
+        // Trilinear when scaled down with mipmaps
+        let mipmap_filter = if mipmaps { wgpu::FilterMode::Linear } else { wgpu::FilterMode::Nearest };
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some("Sprite Array Sampler"),
+            address_mode_u: wgpu::AddressMode::ClampToEdge,
+            address_mode_v: wgpu::AddressMode::ClampToEdge,
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            mipmap_filter,
+            ..Default::default()
+        });
+
+        // The part of its layer each texture covers, then whether it's filtered
+        // linearly and whether it repeats, as 0.0 or 1.0
+        let mut extents = [[0.0f32; 4]; MAX_SPRITE_LAYERS];
+        for (extent, source) in extents.iter_mut().zip(&sources) {
+            let flag = |on: bool| if on { 1.0 } else { 0.0 };
+            *extent = [
+                source.width as f32 / width as f32,
+                source.height as f32 / height as f32,
+                flag(source.preset.filter == wgpu::FilterMode::Linear),
+                flag(source.preset.repeat),
+            ];
+        }
+        let extents_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Sprite Array Extents"),
+            contents: bytemuck::cast_slice(&extents),
+            usage: wgpu::BufferUsages::UNIFORM,
+        });
+
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Sprite Array Bind Group"),
+            layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(&view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::Sampler(&sampler),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: extents_buffer.as_entire_binding(),
+                },
+            ],
+        });
+
+        let array = Self {
+            texture,
+            bind_group,
+            sources,
+            mipmaps: mipmaps.then(|| MipmapGenerator::new(device, format)),
+        };
+        array.refresh(device, queue);
+        array
+    }
+
+    /// Copies every texture into its layer again, picking up any reloaded since, and
+    /// regenerates the mipmaps.
+    ///
+    /// # Arguments
+    /// - `device`: The `wgpu::Device` used to record the copies.
+    /// - `queue`: The `wgpu::Queue` used to submit them.
+    pub fn refresh(&self, device: &wgpu::Device, queue: &wgpu::Queue) {
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("Sprite Array Copy Encoder"),
+        });
+        for (layer, source) in self.sources.iter().enumerate() {
+            encoder.copy_texture_to_texture(
+                source.texture.as_image_copy(),
+                wgpu::ImageCopyTexture {
+                    texture: &self.texture,
+                    mip_level: 0,
+                    origin: wgpu::Origin3d {
+                        x: 0,
+                        y: 0,
+                        z: layer as u32,
+                    },
+                    aspect: wgpu::TextureAspect::All,
+                },
+                wgpu::Extent3d {
+                    width: source.width,
+                    height: source.height,
+                    depth_or_array_layers: 1,
+                },
+            );
+        }
+        queue.submit(Some(encoder.finish()));
+
+        if let Some(mipmaps) = &self.mipmaps {
+            mipmaps.generate(device, queue, &self.texture);
+        }
+    }
+}
+
+/// Creates the bind group layout for the sprite array.
+///
+/// This layout specifies three bindings:
+/// 1. A 2D texture array.
+/// 2. A sampler for filtering and addressing modes.
+/// 3. A uniform array of how much of each layer its texture covers and how it's sampled.
+///
+/// # Arguments
+/// - `device`: The `wgpu::Device` used to create the bind group layout.
+pub fn create_sprite_array_bind_group_layout(device: &wgpu::Device) -> wgpu::BindGroupLayout {
+    info!("Creating sprite array bind group layout");
+    device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+        label: Some("Sprite Array Bind Group Layout"),
+        entries: &[
+            wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Texture {
+                    multisampled: false,
+                    sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                    view_dimension: wgpu::TextureViewDimension::D2Array,
+                },
+                count: None,
+            },
+            wgpu::BindGroupLayoutEntry {
+                binding: 1,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                count: None,
+            },
+            wgpu::BindGroupLayoutEntry {
+                binding: 2,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None,
+            },
+        ],
+    })
+}
@@ -0,0 +1,159 @@
+// text.rs
+use crate::engine::renderer::instance::InstanceData;
+use crate::engine::renderer::texture::{load_texture, SamplerPreset, Texture};
+use crate::engine::renderer::Renderer;
+
+/// Glyph atlas covering printable ASCII.
+const FONT_PATH: &str = "assets/ui/Font.png";
+
+/// Glyph columns in the font atlas.
+const FONT_COLUMNS: usize = 16;
+
+/// Glyph rows in the font atlas.
+const FONT_ROWS: usize = 6;
+
+/// Character of the atlas's first glyph; the rest follow in ASCII order.
+const FIRST_CHAR: char = ' ';
+
+/// Width of a glyph cell relative to its height, spacing included.
+const GLYPH_ASPECT: f32 = 6.0 / 8.0;
+
+/// Distance between the centers of successive lines, relative to the text height.
+const LINE_SPACING: f32 = 1.4;
+
+/// Which part of each line is placed at the anchor.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TextAlign {
+    /// Lines extend to the right of the anchor.
+    Left,
+    /// Lines are centered on the anchor.
+    Center,
+    /// Lines end at the anchor.
+    Right,
+}
+
+/// How text is drawn.
+#[derive(Clone, Copy, Debug)]
+pub struct TextStyle {
+    /// Height of a line in screen coordinates (the screen spans -1..1); glyphs scale with it.
+    pub height: f32,
+    pub color: [f32; 4],
+    pub z: f32,
+    pub align: TextAlign,
+}
+
+/// Lays out strings from a bitmap font atlas as instanced quads, one per glyph.
+///
+/// The instances it builds are drawn with the standard pipeline from the sprite array,
+/// into which the font atlas is copied.
+/// Positions are in screen coordinates, and glyphs keep their shape whatever the
+/// window's aspect ratio. Characters the font doesn't cover are drawn as `?`.
+pub struct TextRenderer {
+    /// The font atlas.
+    pub texture: Texture,
+    /// Width of the screen over its height.
+    screen_aspect: f32,
+}
+
+impl TextRenderer {
+    /// Loads the font atlas.
+    ///
+    /// # Arguments
+    /// - `device`: The `wgpu::Device` used to create the GPU resources.
+    /// - `queue`: The `wgpu::Queue` used to upload the atlas.
+    /// - `config`: The surface configuration, giving the screen size.
+    pub async fn new(
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        config: &wgpu::SurfaceConfiguration,
+    ) -> Self {
+        Self {
+            texture: load_texture(device, queue, FONT_PATH, SamplerPreset::PIXEL_ART).await,
+            screen_aspect: config.width as f32 / config.height as f32,
+        }
+    }
+
+    /// Updates the screen size text is laid out for.
+    ///
+    /// # Arguments
+    /// - `width`: The new screen width in pixels.
+    /// - `height`: The new screen height in pixels.
+    pub fn resize(&mut self, width: u32, height: u32) {
+        self.screen_aspect = width as f32 / height as f32;
+    }
+
+    /// Returns the width of the widest line of some text, in screen coordinates.
+    ///
+    /// # Arguments
+    /// - `text`: The text to measure; lines are separated by `\n`.
+    /// - `height`: Height of a line in screen coordinates.
+    pub fn width(&self, text: &str, height: f32) -> f32 {
+        let longest = text.lines().map(|line| line.chars().count()).max().unwrap_or(0);
+        self.glyph_width(height) * longest as f32
+    }
+
+    /// Lays out text as one instance per glyph.
+    ///
+    /// # Arguments
+    /// - `text`: The text to draw; lines are separated by `\n` and stack downward.
+    /// - `anchor`: Where the first line is placed, in screen coordinates. The vertical
+    ///   position is the line's center; the horizontal one depends on `style.align`.
+    /// - `style`: Size, color, depth, and alignment of the text.
+    ///
+    /// # Returns
+    /// The glyph instances, to draw from the font's layer of the sprite array.
+    pub fn layout(&self, text: &str, anchor: (f32, f32), style: &TextStyle) -> Vec<InstanceData> {
+        let glyph_width = self.glyph_width(style.height);
+
+        text.lines()
+            .enumerate()
+            .flat_map(|(row, line)| {
+                let line_width = glyph_width * line.chars().count() as f32;
+                let left = match style.align {
+                    TextAlign::Left => anchor.0,
+                    TextAlign::Center => anchor.0 - line_width / 2.0,
+                    TextAlign::Right => anchor.0 - line_width,
+                };
+                let y = anchor.1 - row as f32 * style.height * LINE_SPACING;
+
+                line.chars()
+                    .enumerate()
+                    .filter(|(_, c)| *c != ' ')
+                    .map(move |(index, c)| InstanceData {
+                        transform: Renderer::create_transform_matrix(
+                            left + glyph_width * (index as f32 + 0.5),
+                            y,
+                            style.z,
+                            glyph_width,
+                            style.height,
+                        ),
+                        sprite_index: glyph_index(c) as f32,
+                        layer: 0.0,
+                        sprite_size: [1.0 / FONT_COLUMNS as f32, 1.0 / FONT_ROWS as f32],
+                        uv_offset: [0.0, 0.0],
+                        uv_scale: [1.0, 1.0],
+                        color: style.color,
+                    })
+            })
+            .collect()
+    }
+
+    /// Returns the distance between successive lines of text of the given height.
+    pub fn line_spacing(&self, height: f32) -> f32 {
+        height * LINE_SPACING
+    }
+
+    /// Returns the width of a glyph cell for text of the given height.
+    fn glyph_width(&self, height: f32) -> f32 {
+        height * GLYPH_ASPECT / self.screen_aspect
+    }
+}
+
+/// Returns the atlas index of a character's glyph.
+fn glyph_index(c: char) -> usize {
+    let glyph_count = FONT_COLUMNS * FONT_ROWS;
+    match (c as usize).checked_sub(FIRST_CHAR as usize) {
+        Some(index) if index < glyph_count => index,
+        _ => '?' as usize - FIRST_CHAR as usize,
+    }
+}
@@ -1,21 +1,78 @@
 //texture.rs
-use wgpu::util::DeviceExt;
+use crate::engine::mods::resolve;
 use image::GenericImageView;
 use std::collections::HashMap;
-use std::path::Path;
 use std::sync::{Arc, Mutex};
 use log::{info, warn};
 
+/// Size in pixels of the generated blob shadow texture, twice as wide as it's tall.
+const SHADOW_TEXTURE_SIZE: (u32, u32) = (64, 32);
+
+/// How a texture is filtered and addressed when sampled, picked per texture.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SamplerPreset {
+    /// How texels are blended when drawn larger or smaller than they are.
+    pub filter: wgpu::FilterMode,
+    /// Whether UVs past the edges wrap around to repeat the texture, rather than
+    /// clamping to its edge texels.
+    pub repeat: bool,
+}
+
+impl SamplerPreset {
+    /// Crisp texels clamped at the edges, for pixel-art sprites, tiles, and text.
+    pub const PIXEL_ART: Self = Self {
+        filter: wgpu::FilterMode::Nearest,
+        repeat: false,
+    };
+
+    /// Smoothly filtered and repeating, for backgrounds scaled and tiled across the screen.
+    pub const BACKGROUND: Self = Self {
+        filter: wgpu::FilterMode::Linear,
+        repeat: true,
+    };
+
+    /// Smoothly filtered and clamped at the edges, for offscreen scenes copied to the screen.
+    pub const SMOOTH: Self = Self {
+        filter: wgpu::FilterMode::Linear,
+        repeat: false,
+    };
+
+    /// Creates a sampler with the preset's filtering and addressing.
+    ///
+    /// # Arguments
+    /// - `device`: The `wgpu::Device` used to create the sampler.
+    /// - `label`: The sampler's debug label.
+    pub fn create_sampler(self, device: &wgpu::Device, label: &str) -> wgpu::Sampler {
+        let address_mode = if self.repeat {
+            wgpu::AddressMode::Repeat
+        } else {
+            wgpu::AddressMode::ClampToEdge
+        };
+        device.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some(label),
+            address_mode_u: address_mode,
+            address_mode_v: address_mode,
+            address_mode_w: address_mode,
+            mag_filter: self.filter,
+            min_filter: self.filter,
+            mipmap_filter: wgpu::FilterMode::Nearest,
+            ..Default::default()
+        })
+    }
+}
+
 /// Represents a texture along with its view and sampler.
 /// 
 /// This structure encapsulates:
 /// - The GPU texture object.
 /// - A texture view for rendering.
-/// - A sampler for filtering and addressing.
+/// - A sampler for filtering and addressing, created from its preset.
 pub struct Texture {
     pub texture: Arc<wgpu::Texture>,
     pub view: Arc<wgpu::TextureView>,
     pub sampler: Arc<wgpu::Sampler>,
+    /// How the texture is sampled, including when drawn from the sprite array.
+    pub preset: SamplerPreset,
     pub width: u32,  // Add width
     pub height: u32, // Add height
 }
@@ -26,13 +83,14 @@ impl Clone for Texture {
             texture: Arc::clone(&self.texture),
             view: Arc::clone(&self.view),
             sampler: Arc::clone(&self.sampler),
+            preset: self.preset,
             width: self.width,
             height: self.height,
         }
     }
 }
 
-/// A global texture cache to avoid reloading the same texture multiple times.
+// A global texture cache to avoid reloading the same texture multiple times.
 lazy_static::lazy_static! {
     static ref TEXTURE_CACHE: Mutex<HashMap<String, Texture>> = Mutex::new(HashMap::new());
 }
@@ -43,29 +101,39 @@ lazy_static::lazy_static! {
 /// - `device`: The `wgpu::Device` used to create the GPU resources.
 /// - `queue`: The `wgpu::Queue` used to upload texture data to the GPU.
 /// - `path`: The file path to the texture image.
+/// - `preset`: How the texture is filtered and addressed, e.g. `SamplerPreset::PIXEL_ART`.
 /// 
 /// # Returns
 /// A `Texture` structure containing the loaded texture, its view, and sampler.
 /// 
 /// # Notes
 /// This function uses a global texture cache to avoid redundant loading.
-/// If the texture is already cached, it will be returned directly.
+/// If the texture is already cached, it will be returned directly, with a sampler
+/// of its own if it was cached with another preset.
 pub async fn load_texture(
     device: &wgpu::Device,
     queue: &wgpu::Queue,
     path: &str,
+    preset: SamplerPreset,
 ) -> Texture {
     let mut cache = TEXTURE_CACHE.lock().unwrap();
 
     // Check if the texture is already in the cache
     if let Some(texture) = cache.get(path) {
         info!("Using cached texture: {}", path);
-        return texture.clone();
+        if texture.preset == preset {
+            return texture.clone();
+        }
+        return Texture {
+            sampler: Arc::new(preset.create_sampler(device, "Texture Sampler")),
+            preset,
+            ..texture.clone()
+        };
     }
 
     // Load the image using the `image` crate
     info!("Loading texture from file: {}", path);
-    let img = image::open(Path::new(path)).expect("Failed to load texture");
+    let img = image::open(resolve(path)).expect("Failed to load texture");
     let rgba = img.to_rgba8();
     let dimensions = img.dimensions();
 
@@ -83,43 +151,21 @@ pub async fn load_texture(
         sample_count: 1,    // No multisampling
         dimension: wgpu::TextureDimension::D2,
         format: wgpu::TextureFormat::Rgba8UnormSrgb, // sRGB texture format
-        usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+        usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST | wgpu::TextureUsages::COPY_SRC,
         view_formats: &[],
     }));
 
     // Upload pixel data to the GPU texture
-    queue.write_texture(
-        wgpu::ImageCopyTexture {
-            texture: &texture,
-            mip_level: 0,
-            origin: wgpu::Origin3d::ZERO,
-            aspect: wgpu::TextureAspect::All,
-        },
-        &rgba,
-        wgpu::ImageDataLayout {
-            offset: 0,
-            bytes_per_row: Some(4 * dimensions.0), // 4 bytes per pixel * width
-            rows_per_image: Some(dimensions.1),
-        },
-        size,
-    );
+    write_pixels(queue, &texture, &rgba, dimensions);
 
     // Create a texture view and sampler
     let view = Arc::new(texture.create_view(&wgpu::TextureViewDescriptor::default()));
-    let sampler = Arc::new(device.create_sampler(&wgpu::SamplerDescriptor {
-        label: Some("Texture Sampler"),
-        address_mode_u: wgpu::AddressMode::ClampToEdge,
-        address_mode_v: wgpu::AddressMode::ClampToEdge,
-        address_mode_w: wgpu::AddressMode::ClampToEdge,
-        mag_filter: wgpu::FilterMode::Nearest, // Nearest-neighbor filtering
-        min_filter: wgpu::FilterMode::Nearest,
-        mipmap_filter: wgpu::FilterMode::Nearest,
-        ..Default::default()
-    }));
+    let sampler = Arc::new(preset.create_sampler(device, "Texture Sampler"));
     let texture = Texture { 
         texture, 
         view, 
         sampler, 
+        preset,
         width: dimensions.0, 
         height: dimensions.1 
     };
@@ -131,6 +177,172 @@ pub async fn load_texture(
     texture
 }
 
+/// Reloads a cached texture from its file, replacing its pixels in place.
+///
+/// The GPU texture is reused, so bind groups created for it show the new image
+/// without being rebuilt. Images whose size has changed can't be swapped in this way
+/// and are skipped, as are textures that were never loaded.
+///
+/// # Arguments
+/// - `queue`: The `wgpu::Queue` used to upload the new pixels to the GPU.
+/// - `path`: The file path the texture was loaded from.
+///
+/// # Returns
+/// Whether the texture was reloaded.
+pub fn reload_texture(queue: &wgpu::Queue, path: &str) -> bool {
+    let cache = TEXTURE_CACHE.lock().unwrap();
+    let Some(texture) = cache.get(path) else {
+        return false;
+    };
+
+    // The file may still be mid-write, in which case a later change event retries
+    let img = match image::open(resolve(path)) {
+        Ok(img) => img,
+        Err(e) => {
+            warn!("Could not reload texture '{}': {}", path, e);
+            return false;
+        }
+    };
+    let dimensions = img.dimensions();
+    if dimensions != (texture.width, texture.height) {
+        warn!(
+            "Could not reload texture '{}': size changed from {}x{} to {}x{}; restart to pick it up",
+            path, texture.width, texture.height, dimensions.0, dimensions.1
+        );
+        return false;
+    }
+
+    write_pixels(queue, &texture.texture, &img.to_rgba8(), dimensions);
+    info!("Texture reloaded: {}", path);
+    true
+}
+
+/// Uploads RGBA pixels covering the whole of a texture.
+fn write_pixels(queue: &wgpu::Queue, texture: &wgpu::Texture, rgba: &[u8], dimensions: (u32, u32)) {
+    queue.write_texture(
+        wgpu::ImageCopyTexture {
+            texture,
+            mip_level: 0,
+            origin: wgpu::Origin3d::ZERO,
+            aspect: wgpu::TextureAspect::All,
+        },
+        rgba,
+        wgpu::ImageDataLayout {
+            offset: 0,
+            bytes_per_row: Some(4 * dimensions.0), // 4 bytes per pixel * width
+            rows_per_image: Some(dimensions.1),
+        },
+        wgpu::Extent3d {
+            width: dimensions.0,
+            height: dimensions.1,
+            depth_or_array_layers: 1,
+        },
+    );
+}
+
+/// Creates a 1x1 texture of a single color.
+///
+/// Drawing it with an instance tint gives solid colored quads, such as UI panels.
+///
+/// # Arguments
+/// - `device`: The `wgpu::Device` used to create the GPU resources.
+/// - `queue`: The `wgpu::Queue` used to upload the pixel to the GPU.
+/// - `color`: The RGBA color of the pixel.
+///
+/// # Returns
+/// A `Texture` containing the single pixel, its view, and sampler.
+pub fn create_solid_texture(device: &wgpu::Device, queue: &wgpu::Queue, color: [u8; 4]) -> Texture {
+    create_pixel_texture(device, queue, "Solid Texture", (1, 1), &color, SamplerPreset::PIXEL_ART)
+}
+
+/// Creates a texture of a soft white ellipse filling it, fading out towards its edge.
+///
+/// Drawing it with a dark instance tint gives the blob shadows under characters and items.
+///
+/// # Arguments
+/// - `device`: The `wgpu::Device` used to create the GPU resources.
+/// - `queue`: The `wgpu::Queue` used to upload the pixels to the GPU.
+///
+/// # Returns
+/// A smoothly filtered `Texture` of the ellipse, its view, and sampler.
+pub fn create_shadow_texture(device: &wgpu::Device, queue: &wgpu::Queue) -> Texture {
+    let (width, height) = SHADOW_TEXTURE_SIZE;
+    let pixels: Vec<u8> = (0..height)
+        .flat_map(|row| (0..width).map(move |column| (column, row)))
+        .flat_map(|(column, row)| {
+            // Distance from the center, in 0..1 at the ellipse's edge
+            let x = (column as f32 + 0.5) / width as f32 * 2.0 - 1.0;
+            let y = (row as f32 + 0.5) / height as f32 * 2.0 - 1.0;
+            let falloff = (1.0 - (x * x + y * y)).clamp(0.0, 1.0);
+            [255, 255, 255, (falloff.sqrt() * 255.0) as u8]
+        })
+        .collect();
+    create_pixel_texture(device, queue, "Shadow Texture", SHADOW_TEXTURE_SIZE, &pixels, SamplerPreset::SMOOTH)
+}
+
+/// Creates a texture from RGBA pixels generated in code.
+///
+/// # Arguments
+/// - `device`: The `wgpu::Device` used to create the GPU resources.
+/// - `queue`: The `wgpu::Queue` used to upload the pixels to the GPU.
+/// - `label`: The texture's debug label.
+/// - `(width, height)`: Size of the texture in pixels.
+/// - `pixels`: The RGBA pixels, row by row from the top.
+/// - `preset`: How the texture is sampled.
+fn create_pixel_texture(
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    label: &str,
+    (width, height): (u32, u32),
+    pixels: &[u8],
+    preset: SamplerPreset,
+) -> Texture {
+    let size = wgpu::Extent3d {
+        width,
+        height,
+        depth_or_array_layers: 1,
+    };
+
+    let texture = Arc::new(device.create_texture(&wgpu::TextureDescriptor {
+        label: Some(label),
+        size,
+        mip_level_count: 1,
+        sample_count: 1,
+        dimension: wgpu::TextureDimension::D2,
+        format: wgpu::TextureFormat::Rgba8UnormSrgb,
+        usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST | wgpu::TextureUsages::COPY_SRC,
+        view_formats: &[],
+    }));
+
+    queue.write_texture(
+        wgpu::ImageCopyTexture {
+            texture: &texture,
+            mip_level: 0,
+            origin: wgpu::Origin3d::ZERO,
+            aspect: wgpu::TextureAspect::All,
+        },
+        pixels,
+        wgpu::ImageDataLayout {
+            offset: 0,
+            bytes_per_row: Some(4 * width),
+            rows_per_image: Some(height),
+        },
+        size,
+    );
+
+    let view = Arc::new(texture.create_view(&wgpu::TextureViewDescriptor::default()));
+    let sampler = Arc::new(preset.create_sampler(device, &format!("{} Sampler", label)));
+
+    Texture {
+        texture,
+        view,
+        sampler,
+        preset,
+        width,
+        height,
+    }
+}
+
 /// Creates a bind group layout for textures.
 /// 
 /// This layout specifies two bindings:
@@ -204,7 +416,13 @@ pub fn create_texture_bind_group(
         ],
     })
 }
-pub fn create_depth_texture(device: &wgpu::Device, config: &wgpu::SurfaceConfiguration) -> wgpu::Texture {
+pub fn create_depth_texture(device: &wgpu::Device, config: &wgpu::SurfaceConfiguration, sample_count: u32) -> wgpu::Texture {
+    // Multisampled textures can't be sampled on every backend, such as GLES 3.0
+    let usage = if sample_count > 1 {
+        wgpu::TextureUsages::RENDER_ATTACHMENT
+    } else {
+        wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING
+    };
     device.create_texture(&wgpu::TextureDescriptor {
         label: Some("Depth Texture"),
         size: wgpu::Extent3d {
@@ -213,10 +431,45 @@ pub fn create_depth_texture(device: &wgpu::Device, config: &wgpu::SurfaceConfigu
             depth_or_array_layers: 1,
         },
         mip_level_count: 1,
-        sample_count: 1,
+        sample_count,
         dimension: wgpu::TextureDimension::D2,
         format: wgpu::TextureFormat::Depth32Float,
-        usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING,
+        usage,
         view_formats: &[],
     })
-}
\ No newline at end of file
+}
+/// Creates the color target frames are drawn into with several samples per pixel,
+/// resolved into the frame's own target at the end of each pass.
+///
+/// # Arguments
+/// - `device`: The `wgpu::Device` used to create GPU resources.
+/// - `config`: The configuration frames are drawn with, whose size and format to match.
+/// - `sample_count`: Samples per pixel.
+///
+/// # Returns
+/// A view of the target, or `None` with one sample per pixel, when frames are drawn
+/// straight into their own target.
+pub fn create_multisample_view(
+    device: &wgpu::Device,
+    config: &wgpu::SurfaceConfiguration,
+    sample_count: u32,
+) -> Option<wgpu::TextureView> {
+    if sample_count <= 1 {
+        return None;
+    }
+    let texture = device.create_texture(&wgpu::TextureDescriptor {
+        label: Some("Multisample Texture"),
+        size: wgpu::Extent3d {
+            width: config.width,
+            height: config.height,
+            depth_or_array_layers: 1,
+        },
+        mip_level_count: 1,
+        sample_count,
+        dimension: wgpu::TextureDimension::D2,
+        format: config.format,
+        usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+        view_formats: &[],
+    });
+    Some(texture.create_view(&wgpu::TextureViewDescriptor::default()))
+}
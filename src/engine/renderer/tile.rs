@@ -1,11 +1,139 @@
 // tile.rs
+use crate::engine::collision::{merge_boxes, Aabb};
+use crate::engine::renderer::autotile::{AutotileRules, GROUND};
+use crate::engine::renderer::layer::RenderLayer;
+use std::collections::BTreeMap;
+
+/// Width and height of the squares a layer's tiles are grouped into for culling, in
+/// world units: 16 tiles of the usual size.
+const CHUNK_SIZE: f32 = 4.8;
+
 pub struct Tile {
+    /// Index of the tileset tile at the top-left of the tile's art.
     pub tile_index: usize,
+    /// Center of the tile in world coordinates.
     pub position: (f32, f32),
+    /// Width and height in world units: one grid cell, or more for oversized tiles.
+    pub size: (f32, f32),
+    /// Columns and rows of tileset tiles making up its art, stretched over `size`.
+    pub span: (usize, usize),
+    /// Brightness its color is multiplied by, baked from its surroundings; 1.0 is unshaded.
+    pub shade: f32,
 }
 
-pub struct TileMap {
+impl Tile {
+    /// Returns the area the tile covers, every grid cell of it for oversized tiles.
+    pub fn bounds(&self) -> Aabb {
+        Aabb::from_center(self.position.0, self.position.1, self.size.0, self.size.1)
+    }
+}
+
+/// A square of a layer's tiles, drawn only while it's in view.
+struct TileChunk {
+    /// Area the chunk's tiles cover, including oversized tiles spilling out of it.
+    bounds: Aabb,
+    /// Indices of the chunk's tiles in the layer, in layer order.
+    tiles: Vec<usize>,
+}
+
+/// One named layer of a tile map, such as background decoration, collision, or a
+/// foreground overlay.
+///
+/// The tiles are grouped into square chunks by position, so drawing a large level
+/// only goes through the chunks in view, and merged into larger boxes for collision.
+/// Call `rechunk` after moving, adding, or removing tiles; changing their tile
+/// indices needs nothing more.
+pub struct TileLayer {
+    pub name: String,
     pub tiles: Vec<Tile>,
+    chunks: Vec<TileChunk>,
+    /// The area the tiles cover, merged into as few boxes as `merge_boxes` finds.
+    colliders: Vec<Aabb>,
+    /// Depth the layer is drawn at, or `None` to draw it just in front of the layers
+    /// before it, on the map's render layer.
+    pub z: Option<f32>,
+    /// Whether its tiles are solid, stopping projectiles; decoration layers aren't.
+    pub solid: bool,
+    /// Rules repicking its terrain tiles from their neighbors, if it's autotiled.
+    pub autotile: Option<&'static AutotileRules>,
+    /// Whether decorations are scattered on its surfaces, if it's solid.
+    pub decorate: bool,
+}
+
+impl TileLayer {
+    /// Creates a solid layer drawn on the map's render layer.
+    pub fn new(name: &str, tiles: Vec<Tile>) -> Self {
+        let mut layer = Self {
+            name: name.to_string(),
+            tiles,
+            chunks: Vec::new(),
+            colliders: Vec::new(),
+            z: None,
+            solid: true,
+            autotile: None,
+            decorate: true,
+        };
+        layer.rechunk();
+        layer
+    }
+
+    /// Regroups the tiles into chunks by the square their center falls in, and merges
+    /// them into collision boxes again. Tiles in the same chunk keep their order in
+    /// the layer.
+    pub fn rechunk(&mut self) {
+        self.colliders = merge_boxes(self.tiles.iter().map(Tile::bounds).collect());
+
+        // Sorted by square, so chunks are always drawn in the same order
+        let mut chunks: BTreeMap<(i32, i32), TileChunk> = BTreeMap::new();
+        for (index, tile) in self.tiles.iter().enumerate() {
+            let key = (
+                (tile.position.0 / CHUNK_SIZE).floor() as i32,
+                (tile.position.1 / CHUNK_SIZE).floor() as i32,
+            );
+            let bounds = tile.bounds();
+            let chunk = chunks.entry(key).or_insert(TileChunk {
+                bounds,
+                tiles: Vec::new(),
+            });
+            chunk.bounds = Aabb {
+                min_x: chunk.bounds.min_x.min(bounds.min_x),
+                min_y: chunk.bounds.min_y.min(bounds.min_y),
+                max_x: chunk.bounds.max_x.max(bounds.max_x),
+                max_y: chunk.bounds.max_y.max(bounds.max_y),
+            };
+            chunk.tiles.push(index);
+        }
+        self.chunks = chunks.into_values().collect();
+    }
+
+    /// Returns the boxes the layer's tiles are merged into for collision.
+    pub fn colliders(&self) -> &[Aabb] {
+        &self.colliders
+    }
+
+    /// Returns the tiles of the chunks overlapping an area, such as the camera's view.
+    /// Tiles of other chunks near the area may be included too.
+    ///
+    /// # Arguments
+    ///
+    /// * `view` - The area to draw, in world coordinates.
+    pub fn tiles_in<'a>(&'a self, view: &'a Aabb) -> impl Iterator<Item = &'a Tile> {
+        // Compared directly rather than with `Aabb::intersects`, as culling isn't collision
+        self.chunks
+            .iter()
+            .filter(|chunk| {
+                chunk.bounds.min_x < view.max_x
+                    && chunk.bounds.max_x > view.min_x
+                    && chunk.bounds.min_y < view.max_y
+                    && chunk.bounds.max_y > view.min_y
+            })
+            .flat_map(|chunk| chunk.tiles.iter().map(|&index| &self.tiles[index]))
+    }
+}
+
+pub struct TileMap {
+    /// Layers in render order, from the back to the front.
+    pub layers: Vec<TileLayer>,
     pub tile_width: f32,
     pub tile_height: f32,
     pub tileset_columns: usize,
@@ -38,11 +166,135 @@ impl TileMap {
                     start_x + i as f32 * tile_width, // Adjusted x position
                     -1.0 + tile_height / 2.0,        // Adjust y position as needed
                 ),
+                size: (tile_width, tile_height),
+                span: (1, 1),
+                shade: 1.0,
             });
         }
     
+        let mut ground = TileLayer::new("ground", tiles);
+        ground.autotile = Some(&GROUND);
+        let mut tile_map = Self {
+            layers: vec![ground],
+            tile_width,
+            tile_height,
+            tileset_columns,
+            tileset_rows,
+        };
+        tile_map.autotile();
+        tile_map
+    }
+
+    /// Repicks the terrain tiles of every autotiled layer to match their neighbors.
+    /// Call after building the map or changing an autotiled layer's tiles.
+    pub fn autotile(&mut self) {
+        for layer in &mut self.layers {
+            if let Some(rules) = layer.autotile {
+                rules.apply(&mut layer.tiles, self.tile_width, self.tile_height);
+            }
+        }
+    }
+
+    /// Returns the tiles of every layer, from the back layer to the front.
+    pub fn tiles(&self) -> impl Iterator<Item = &Tile> {
+        self.layers.iter().flat_map(|layer| &layer.tiles)
+    }
+
+    /// Returns each layer with the depth it's drawn at.
+    ///
+    /// # Arguments
+    ///
+    /// * `render_layer` - The map's render layer, which layers without a depth of their
+    ///   own are stacked on in order, each sorted in front of the ones before it.
+    pub fn layer_depths(&self, render_layer: RenderLayer) -> impl Iterator<Item = (&TileLayer, f32)> {
+        self.layers.iter().enumerate().map(move |(index, layer)| {
+            let order = index.min(i8::MAX as usize) as i8;
+            (layer, layer.z.unwrap_or(render_layer.depth(order)))
+        })
+    }
+
+    /// Returns the solid tile covering a point in world coordinates, if any, counting
+    /// every cell an oversized tile covers. Where tiles overlap, the frontmost is returned.
+    #[cfg_attr(not(debug_assertions), allow(dead_code))]
+    pub fn tile_at(&self, x: f32, y: f32) -> Option<&Tile> {
+        self.layers
+            .iter()
+            .rev()
+            .filter(|layer| layer.solid)
+            .find_map(|layer| layer.tiles.iter().rev().find(|tile| tile.bounds().contains(x, y)))
+    }
+
+    /// Returns whether a point in world coordinates is inside a solid tile, testing the
+    /// solid layers' merged collision boxes rather than each of their tiles.
+    pub fn is_solid_at(&self, x: f32, y: f32) -> bool {
+        self.layers
+            .iter()
+            .filter(|layer| layer.solid)
+            .flat_map(TileLayer::colliders)
+            .any(|collider| collider.contains(x, y))
+    }
+
+    /// Casts a ray straight down from a point in world coordinates to the first solid
+    /// surface it meets, testing the solid layers' merged collision boxes.
+    ///
+    /// # Returns
+    ///
+    /// The height of the surface's top, or `None` if nothing solid is below the point.
+    pub fn ground_below(&self, x: f32, y: f32) -> Option<f32> {
+        self.layers
+            .iter()
+            .filter(|layer| layer.solid)
+            .flat_map(TileLayer::colliders)
+            .filter(|collider| collider.min_x <= x && x <= collider.max_x && collider.max_y <= y + f32::EPSILON)
+            .map(|collider| collider.max_y)
+            .reduce(f32::max)
+    }
+
+    /// Builds a tile map from one or more named grids of tileset indices.
+    ///
+    /// Each layer is a row-major grid of `map_width * map_height` cells, with
+    /// row 0 at the top of the map. Empty cells are `None`. Layers are kept in
+    /// order, so later layers are drawn over earlier ones; all start out solid and
+    /// at the map's depth. The map is centered horizontally and its bottom row rests
+    /// on the bottom edge of the screen, matching the placement used by `new_ground`.
+    pub fn from_layers(
+        tile_width: f32,
+        tile_height: f32,
+        tileset_columns: usize,
+        tileset_rows: usize,
+        map_width: usize,
+        map_height: usize,
+        layers: &[(String, Vec<Option<usize>>)],
+    ) -> Self {
+        let start_x = -(map_width as f32 * tile_width) / 2.0;
+        let bottom_y = -1.0 + tile_height / 2.0;
+
+        let mut tile_layers = Vec::with_capacity(layers.len());
+        for (name, cells) in layers {
+            let mut tiles = Vec::new();
+            for (cell, tile_index) in cells.iter().enumerate() {
+                let Some(tile_index) = *tile_index else {
+                    continue;
+                };
+                let column = cell % map_width;
+                let row = cell / map_width;
+
+                tiles.push(Tile {
+                    tile_index,
+                    position: (
+                        start_x + column as f32 * tile_width,
+                        bottom_y + (map_height - 1 - row) as f32 * tile_height,
+                    ),
+                    size: (tile_width, tile_height),
+                    span: (1, 1),
+                    shade: 1.0,
+                });
+            }
+            tile_layers.push(TileLayer::new(name, tiles));
+        }
+
         Self {
-            tiles,
+            layers: tile_layers,
             tile_width,
             tile_height,
             tileset_columns,
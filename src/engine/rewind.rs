@@ -0,0 +1,113 @@
+// rewind.rs
+use crate::engine::constants::{REWIND_DURATION, REWIND_FADE_SPEED, REWIND_SPEED};
+use crate::engine::game_state::{GameState, PlayerSnapshot};
+use std::collections::VecDeque;
+use std::time::Instant;
+
+/// Lets the player step back through the last few seconds of their movement.
+///
+/// The player's state is recorded every frame game time passes, keeping the most
+/// recent `REWIND_DURATION` seconds. Holding the rewind action plays the recording
+/// backward at `REWIND_SPEED` times the game's time scale, while the rest of the
+/// world holds still, and fades the screen to grey.
+pub struct Rewind {
+    /// Recorded states, oldest first, each with the game time the frame it ended took.
+    history: VecDeque<(f32, PlayerSnapshot)>,
+    /// Game time the history covers, in seconds.
+    recorded: f32,
+    /// Game time to rewind that hasn't yet added up to a whole recorded frame, in seconds.
+    pending: f32,
+    /// How far the screen has faded to grey, from 0.0 to 1.0.
+    desaturation: f32,
+    /// When the fade was last updated.
+    last_fade: Instant,
+}
+
+impl Rewind {
+    /// Creates a rewind with nothing recorded.
+    pub fn new() -> Self {
+        Self {
+            history: VecDeque::new(),
+            recorded: 0.0,
+            pending: 0.0,
+            desaturation: 0.0,
+            last_fade: Instant::now(),
+        }
+    }
+
+    /// Records the player's state at the end of a frame, forgetting anything older
+    /// than can be rewound.
+    ///
+    /// # Arguments
+    ///
+    /// * `game_state` - The player to record.
+    /// * `delta_time` - Game time the frame took. Frames where none passed aren't recorded.
+    pub fn record(&mut self, game_state: &GameState, delta_time: f32) {
+        self.pending = 0.0;
+        if delta_time <= 0.0 {
+            return;
+        }
+        self.history.push_back((delta_time, game_state.snapshot()));
+        self.recorded += delta_time;
+        while self.recorded > REWIND_DURATION {
+            let Some((oldest, _)) = self.history.pop_front() else {
+                break;
+            };
+            self.recorded -= oldest;
+        }
+    }
+
+    /// Returns whether anything has been recorded to rewind.
+    pub fn can_rewind(&self) -> bool {
+        !self.history.is_empty()
+    }
+
+    /// Steps the player back through the recording by one frame's worth of time,
+    /// holding them at the oldest recorded state once it's reached.
+    ///
+    /// # Arguments
+    ///
+    /// * `game_state` - The player to move back.
+    /// * `delta_time` - Game time the frame took; that much times `REWIND_SPEED` is undone.
+    pub fn rewind(&mut self, game_state: &mut GameState, delta_time: f32) {
+        // Drop whole frames, keeping the oldest, then return to the state before the last one dropped
+        self.pending += delta_time * REWIND_SPEED;
+        while let Some(&(frame, _)) = self.history.back() {
+            if self.pending < frame || self.history.len() == 1 {
+                break;
+            }
+            self.pending -= frame;
+            self.recorded -= frame;
+            self.history.pop_back();
+        }
+        if let Some((_, snapshot)) = self.history.back() {
+            game_state.restore(snapshot);
+        }
+    }
+
+    /// Fades the screen toward grey while rewinding, and back to color otherwise, by
+    /// the real time elapsed since the last update, so the fade runs through slow
+    /// motion and pauses.
+    ///
+    /// # Arguments
+    ///
+    /// * `rewinding` - Whether the player rewound this frame.
+    pub fn update_fade(&mut self, rewinding: bool) {
+        let now = Instant::now();
+        let step = REWIND_FADE_SPEED * now.duration_since(self.last_fade).as_secs_f32();
+        self.last_fade = now;
+        let target = if rewinding { 1.0 } else { 0.0 };
+        self.desaturation += (target - self.desaturation).clamp(-step, step);
+    }
+
+    /// Returns how much color the screen keeps, from 0.0 (grey) to 1.0 (full color).
+    pub fn saturation(&self) -> f32 {
+        1.0 - self.desaturation
+    }
+}
+
+impl Default for Rewind {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,233 @@
+// room.rs
+use crate::engine::camera::Camera;
+use crate::engine::collision::Aabb;
+use crate::engine::constants::SPRITE_WIDTH;
+use crate::engine::game_state::GameState;
+use crate::engine::level::Level;
+use log::{info, warn};
+
+/// A region of the level that the player and camera are confined to.
+struct Room {
+    name: String,
+    bounds: Aabb,
+    challenge: Option<Challenge>,
+}
+
+/// Makes a room a timed challenge: reach the goal before time runs out to earn the reward.
+struct Challenge {
+    time_limit: f32,
+    reward: u32,
+    goal: Option<Aabb>,
+    completed: bool,
+}
+
+/// A trigger zone that moves the player into another room.
+struct Entrance {
+    bounds: Aabb,
+    target: usize,
+    /// Cleared after use so the player has to step out before it can fire again.
+    armed: bool,
+}
+
+/// The challenge currently being played, and where to send the player afterwards.
+struct ActiveChallenge {
+    room: usize,
+    remaining: f32,
+    return_room: usize,
+    return_point: (f32, f32),
+}
+
+/// Tracks which room the player is in and runs timed challenge rooms.
+///
+/// Rooms, entrances, and goals are read from level objects:
+/// - `room`: a rectangle named after the room. Adding a `time_limit` property
+///   (seconds) turns it into a challenge room, with an optional `reward` in points.
+/// - `room_entrance`: a trigger rectangle whose `room` property names the target room.
+/// - `challenge_goal`: a rectangle whose `room` property names the challenge it completes.
+///
+/// When a challenge ends, by reaching the goal or running out of time, the player
+/// is returned to the entrance they came through.
+pub struct RoomManager {
+    rooms: Vec<Room>,
+    entrances: Vec<Entrance>,
+    current: Option<usize>,
+    active: Option<ActiveChallenge>,
+}
+
+impl RoomManager {
+    /// Builds the rooms described by the level's objects and finds the player's starting room.
+    pub fn from_level(level: &Level, game_state: &GameState) -> Self {
+        let mut rooms: Vec<Room> = level
+            .objects_of_kind("room")
+            .map(|object| Room {
+                name: object.name.clone(),
+                bounds: object.bounds,
+                challenge: object.property("time_limit").map(|_| Challenge {
+                    time_limit: object.property_f32("time_limit", 0.0),
+                    reward: object.property_f32("reward", 0.0) as u32,
+                    goal: None,
+                    completed: false,
+                }),
+            })
+            .collect();
+
+        let find_room = |rooms: &[Room], name: Option<&str>| {
+            let index = rooms.iter().position(|room| Some(room.name.as_str()) == name);
+            if index.is_none() {
+                warn!("Level object references unknown room {:?}", name);
+            }
+            index
+        };
+
+        for object in level.objects_of_kind("challenge_goal") {
+            let index = find_room(&rooms, object.property("room"));
+            if let Some(challenge) = index.and_then(|i| rooms[i].challenge.as_mut()) {
+                challenge.goal = Some(object.bounds);
+            }
+        }
+
+        let entrances = level
+            .objects_of_kind("room_entrance")
+            .filter_map(|object| {
+                Some(Entrance {
+                    bounds: object.bounds,
+                    target: find_room(&rooms, object.property("room"))?,
+                    armed: true,
+                })
+            })
+            .collect();
+
+        let mut manager = Self {
+            rooms,
+            entrances,
+            current: None,
+            active: None,
+        };
+        manager.current = manager.room_at(game_state.player_x, game_state.player_y);
+        manager
+    }
+
+    /// Runs entrance triggers and the active challenge timer, then confines the
+    /// player and camera to the current room. With noclip on, entrances are ignored
+    /// and the player can fly out of the room, leaving only the camera confined.
+    ///
+    /// # Arguments
+    ///
+    /// * `game_state` - The player state to move between rooms and reward.
+    /// * `camera` - The camera to confine to the current room.
+    /// * `delta_time` - Time elapsed since the last frame.
+    pub fn update(&mut self, game_state: &mut GameState, camera: &mut Camera, delta_time: f32) {
+        let player = game_state.bounds();
+
+        if self.active.is_some() {
+            self.update_challenge(game_state, &player, delta_time);
+        } else {
+            // Pick up teleports from elsewhere (e.g. portals) that left the current room
+            let inside = self.current.is_some_and(|index| {
+                self.rooms[index]
+                    .bounds
+                    .intersects(&Aabb::from_center(game_state.player_x, game_state.player_y, 0.0, 0.0))
+            });
+            if !inside {
+                if let Some(index) = self.room_at(game_state.player_x, game_state.player_y) {
+                    self.current = Some(index);
+                }
+            }
+
+            if !game_state.cheats.noclip {
+                self.update_entrances(game_state, &player);
+            }
+        }
+
+        let bounds = self.current.map(|index| self.rooms[index].bounds);
+        if let Some(bounds) = bounds.filter(|_| !game_state.cheats.noclip) {
+            let half_width = game_state.movement().hitbox.0 / 2.0;
+            game_state.player_x = game_state
+                .player_x
+                .clamp(bounds.min_x + half_width, bounds.max_x - half_width);
+        }
+        camera.set_bounds(bounds);
+    }
+
+    /// Returns the index of the room containing the given point.
+    fn room_at(&self, x: f32, y: f32) -> Option<usize> {
+        let point = Aabb::from_center(x, y, 0.0, 0.0);
+        self.rooms
+            .iter()
+            .position(|room| room.bounds.intersects(&point))
+    }
+
+    /// Moves the player through any entrance they are standing in.
+    fn update_entrances(&mut self, game_state: &mut GameState, player: &Aabb) {
+        for entrance in &mut self.entrances {
+            if !entrance.bounds.intersects(player) {
+                entrance.armed = true;
+                continue;
+            }
+            if !entrance.armed {
+                continue;
+            }
+
+            let room = &self.rooms[entrance.target];
+            let completed = room.challenge.as_ref().is_some_and(|challenge| challenge.completed)
+                || game_state.has_collected(&room.name);
+            if completed {
+                continue;
+            }
+
+            entrance.armed = false;
+            let return_point = (game_state.player_x, game_state.player_y);
+            let return_room = self.current;
+
+            // Enter on the left side of the target room
+            game_state.teleport(room.bounds.min_x + SPRITE_WIDTH, game_state.player_y);
+
+            if let Some(challenge) = &room.challenge {
+                info!(
+                    "Challenge '{}' started: {:.0}s to reach the goal",
+                    room.name, challenge.time_limit
+                );
+                self.active = Some(ActiveChallenge {
+                    room: entrance.target,
+                    remaining: challenge.time_limit,
+                    return_room: return_room.unwrap_or(entrance.target),
+                    return_point,
+                });
+            }
+            self.current = Some(entrance.target);
+            break;
+        }
+    }
+
+    /// Counts down the active challenge and ends it on success or timeout.
+    fn update_challenge(&mut self, game_state: &mut GameState, player: &Aabb, delta_time: f32) {
+        let Some(active) = self.active.as_mut() else {
+            return;
+        };
+        active.remaining -= delta_time;
+
+        let room = &mut self.rooms[active.room];
+        let Some(challenge) = room.challenge.as_mut() else {
+            return;
+        };
+
+        if challenge.goal.is_some_and(|goal| goal.intersects(player)) {
+            challenge.completed = true;
+            game_state.collect(&room.name);
+            game_state.score += challenge.reward;
+            info!(
+                "Challenge '{}' completed with {:.1}s left: +{} (score {})",
+                room.name, active.remaining, challenge.reward, game_state.score
+            );
+        } else if active.remaining <= 0.0 {
+            info!("Challenge '{}' failed: time ran out", room.name);
+        } else {
+            return;
+        }
+
+        let (x, y) = active.return_point;
+        game_state.teleport(x, y);
+        self.current = Some(active.return_room);
+        self.active = None;
+    }
+}
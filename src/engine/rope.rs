@@ -0,0 +1,401 @@
+// rope.rs
+use crate::engine::action::Action;
+use crate::engine::activation::ActivationRange;
+use crate::engine::collision::Aabb;
+use crate::engine::config::tuning;
+use crate::engine::constants::{ROPE_ITERATIONS, ROPE_REGRAB_DELAY, SPRITE_HEIGHT};
+use crate::engine::game_state::GameState;
+use crate::engine::input::InputHandler;
+use crate::engine::level::Level;
+
+/// How much heavier than a rope point the player is when hanging from or standing on a rope.
+const PLAYER_WEIGHT: f32 = 3.0;
+
+/// How far below a bridge's surface the player's feet can be and still land on it.
+const BRIDGE_LANDING_DEPTH: f32 = 0.15;
+
+/// How much longer a bridge is than the gap it spans, which makes it sag.
+const BRIDGE_SLACK: f32 = 1.05;
+
+/// The kinds of rope a level can contain.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum RopeKind {
+    /// A light rope hanging from its top end that the player can swing on.
+    Rope,
+    /// A heavier hanging chain that swings more sluggishly.
+    Chain,
+    /// A sagging bridge pinned at both ends that the player can walk across.
+    Bridge,
+}
+
+impl RopeKind {
+    /// Returns the thickness of the rope's segment sprites.
+    fn thickness(self) -> f32 {
+        match self {
+            RopeKind::Rope => 0.04,
+            RopeKind::Chain => 0.06,
+            RopeKind::Bridge => 0.08,
+        }
+    }
+
+    /// Returns the fraction of velocity points keep from one frame to the next.
+    fn damping(self) -> f32 {
+        match self {
+            RopeKind::Rope => 0.995,
+            RopeKind::Chain => 0.98,
+            RopeKind::Bridge => 0.95,
+        }
+    }
+}
+
+/// A point of a rope, moved by verlet integration.
+#[derive(Clone)]
+struct RopePoint {
+    position: (f32, f32),
+    /// Position on the previous step; the difference to `position` is the point's velocity.
+    previous: (f32, f32),
+    /// Whether the point is fixed in place, like the anchor of a hanging rope.
+    pinned: bool,
+}
+
+/// A rope simulated as a chain of points kept a fixed distance apart.
+#[derive(Clone)]
+pub struct Rope {
+    kind: RopeKind,
+    points: Vec<RopePoint>,
+    segment_length: f32,
+    /// Index of the tileset tile drawn along each segment.
+    pub tile_index: usize,
+    /// Whether the rope is near enough to the camera to be simulated and touched.
+    active: bool,
+}
+
+impl Rope {
+    /// Returns the box around the rope's points in their current positions.
+    fn bounds(&self) -> Aabb {
+        let first = self.points[0].position;
+        self.points.iter().fold(
+            Aabb {
+                min_x: first.0,
+                min_y: first.1,
+                max_x: first.0,
+                max_y: first.1,
+            },
+            |bounds, point| Aabb {
+                min_x: bounds.min_x.min(point.position.0),
+                min_y: bounds.min_y.min(point.position.1),
+                max_x: bounds.max_x.max(point.position.0),
+                max_y: bounds.max_y.max(point.position.1),
+            },
+        )
+    }
+
+    /// Returns the thickness of the rope's segment sprites.
+    pub fn thickness(&self) -> f32 {
+        self.kind.thickness()
+    }
+
+    /// Returns the end points of each segment, from the rope's anchor onward.
+    pub fn segments(&self) -> impl Iterator<Item = ((f32, f32), (f32, f32))> + '_ {
+        self.points
+            .windows(2)
+            .map(|pair| (pair[0].position, pair[1].position))
+    }
+
+    /// Adds an acceleration to a point for this step.
+    fn accelerate(&mut self, index: usize, acceleration: (f32, f32), delta_time: f32) {
+        let point = &mut self.points[index];
+        if !point.pinned {
+            point.previous.0 -= acceleration.0 * delta_time * delta_time;
+            point.previous.1 -= acceleration.1 * delta_time * delta_time;
+        }
+    }
+
+    /// Adds an acceleration spread over the two points around a position along the rope.
+    fn accelerate_at(&mut self, position: f32, acceleration: (f32, f32), delta_time: f32) {
+        let (index, t) = self.split(position);
+        self.accelerate(index, (acceleration.0 * (1.0 - t), acceleration.1 * (1.0 - t)), delta_time);
+        self.accelerate(index + 1, (acceleration.0 * t, acceleration.1 * t), delta_time);
+    }
+
+    /// Splits a position along the rope, in segments from the anchor, into a segment index and the fraction along it.
+    fn split(&self, position: f32) -> (usize, f32) {
+        let position = position.clamp(0.0, (self.points.len() - 1) as f32);
+        let index = (position as usize).min(self.points.len() - 2);
+        (index, position - index as f32)
+    }
+
+    /// Returns the world position at a position along the rope, in segments from the anchor.
+    fn point_at(&self, position: f32) -> (f32, f32) {
+        let (index, t) = self.split(position);
+        let (a, b) = (self.points[index].position, self.points[index + 1].position);
+        (a.0 + (b.0 - a.0) * t, a.1 + (b.1 - a.1) * t)
+    }
+
+    /// Returns the velocity at a position along the rope, in segments from the anchor.
+    fn velocity_at(&self, position: f32, delta_time: f32) -> (f32, f32) {
+        let (index, _) = self.split(position);
+        let point = &self.points[index + 1];
+        (
+            (point.position.0 - point.previous.0) / delta_time,
+            (point.position.1 - point.previous.1) / delta_time,
+        )
+    }
+
+    /// Advances the rope one step: integrates gravity, then pulls the points back to their segment length.
+    fn simulate(&mut self, gravity: f32, delta_time: f32) {
+        let damping = self.kind.damping();
+        for point in self.points.iter_mut().filter(|point| !point.pinned) {
+            let velocity = (
+                (point.position.0 - point.previous.0) * damping,
+                (point.position.1 - point.previous.1) * damping,
+            );
+            point.previous = point.position;
+            point.position.0 += velocity.0;
+            point.position.1 += velocity.1 + gravity * delta_time * delta_time;
+        }
+
+        for _ in 0..ROPE_ITERATIONS {
+            for index in 0..self.points.len() - 1 {
+                let (a, b) = (self.points[index].position, self.points[index + 1].position);
+                let (dx, dy) = (b.0 - a.0, b.1 - a.1);
+                let distance = (dx * dx + dy * dy).sqrt().max(f32::EPSILON);
+                let error = (distance - self.segment_length) / distance;
+
+                // Pinned points don't move, so their neighbor takes the whole correction
+                let (share_a, share_b) = match (self.points[index].pinned, self.points[index + 1].pinned) {
+                    (true, true) => continue,
+                    (true, false) => (0.0, 1.0),
+                    (false, true) => (1.0, 0.0),
+                    (false, false) => (0.5, 0.5),
+                };
+                let a = &mut self.points[index].position;
+                a.0 += dx * error * share_a;
+                a.1 += dy * error * share_a;
+                let b = &mut self.points[index + 1].position;
+                b.0 -= dx * error * share_b;
+                b.1 -= dy * error * share_b;
+            }
+        }
+    }
+}
+
+/// The player hanging from a rope.
+#[derive(Clone)]
+struct Grab {
+    rope: usize,
+    /// Where the player holds the rope, in segments from its anchor.
+    position: f32,
+}
+
+/// Hanging ropes, chains, and bridges simulated with verlet integration.
+///
+/// Ropes come from `rope` objects in the level. The `type` property picks the
+/// kind (`rope`, `chain`, or `bridge`), `segments` the number of segments, and
+/// `tile` the tileset tile drawn along each segment. Ropes and chains hang from
+/// the top center of their rectangle down its height; bridges span its top edge.
+///
+/// The player brushes hanging ropes aside, and can grab one with the up action
+/// while touching it. While hanging, moving left and right swings, up and down
+/// climb, and jumping lets go. Bridges hold the player up and sag under their weight.
+#[derive(Clone)]
+pub struct Ropes {
+    pub ropes: Vec<Rope>,
+    grab: Option<Grab>,
+    /// Time left before a rope can be grabbed again after letting go, in seconds.
+    regrab_timer: f32,
+}
+
+impl Ropes {
+    /// Creates the ropes placed in the level, at rest in their starting shape.
+    pub fn from_level(level: &Level) -> Self {
+        let ropes = level
+            .objects_of_kind("rope")
+            .map(|object| {
+                let kind = match object.property("type") {
+                    Some("chain") => RopeKind::Chain,
+                    Some("bridge") => RopeKind::Bridge,
+                    _ => RopeKind::Rope,
+                };
+                let segments = object.property_f32("segments", 8.0).max(1.0) as usize;
+                let bounds = object.bounds;
+
+                let (start, end, segment_length) = match kind {
+                    RopeKind::Bridge => {
+                        let width = bounds.max_x - bounds.min_x;
+                        (
+                            (bounds.min_x, bounds.max_y),
+                            (bounds.max_x, bounds.max_y),
+                            width / segments as f32 * BRIDGE_SLACK,
+                        )
+                    }
+                    RopeKind::Rope | RopeKind::Chain => {
+                        let (center_x, _) = object.center();
+                        let height = bounds.max_y - bounds.min_y;
+                        (
+                            (center_x, bounds.max_y),
+                            (center_x, bounds.min_y),
+                            height / segments as f32,
+                        )
+                    }
+                };
+
+                let points = (0..=segments)
+                    .map(|index| {
+                        let t = index as f32 / segments as f32;
+                        let position = (start.0 + (end.0 - start.0) * t, start.1 + (end.1 - start.1) * t);
+                        RopePoint {
+                            position,
+                            previous: position,
+                            pinned: index == 0 || (kind == RopeKind::Bridge && index == segments),
+                        }
+                    })
+                    .collect();
+
+                Rope {
+                    kind,
+                    points,
+                    segment_length,
+                    tile_index: object.tile_index(),
+                    active: true,
+                }
+            })
+            .collect();
+
+        Self {
+            ropes,
+            grab: None,
+            regrab_timer: 0.0,
+        }
+    }
+
+    /// Activates the ropes within range of the camera and deactivates the rest.
+    ///
+    /// The rope the player is hanging from always stays active.
+    ///
+    /// # Returns
+    ///
+    /// The number of ropes left active.
+    pub fn activate(&mut self, range: &ActivationRange) -> usize {
+        let grabbed = self.grab.as_ref().map(|grab| grab.rope);
+        for (index, rope) in self.ropes.iter_mut().enumerate() {
+            rope.active = grabbed == Some(index) || range.contains(&rope.bounds());
+        }
+        self.ropes.iter().filter(|rope| rope.active).count()
+    }
+
+    /// Simulates the active ropes and resolves the player's contact with them.
+    ///
+    /// # Arguments
+    ///
+    /// * `game_state` - The player state to hang from ropes and stand on bridges.
+    /// * `input_handler` - Provides the grab, climb, swing, and release keys.
+    /// * `delta_time` - Time elapsed since the last frame.
+    pub fn update(&mut self, game_state: &mut GameState, input_handler: &InputHandler, delta_time: f32) {
+        if self.ropes.is_empty() {
+            return;
+        }
+
+        // Long frames would make the simulation unstable
+        let delta_time = delta_time.min(1.0 / 30.0);
+        let gravity = game_state.physics().gravity;
+        self.regrab_timer = (self.regrab_timer - delta_time).max(0.0);
+
+        if let Some(grab) = self.grab.as_mut() {
+            let rope = &mut self.ropes[grab.rope];
+            let last = (rope.points.len() - 1) as f32;
+
+            if input_handler.is_action_pressed(Action::Up) {
+                grab.position -= tuning().rope_climb_speed * delta_time;
+            }
+            if input_handler.is_action_pressed(Action::Down) {
+                grab.position += tuning().rope_climb_speed * delta_time;
+            }
+            grab.position = grab.position.clamp(1.0, last);
+
+            let swing = input_handler.movement_x() * tuning().rope_swing_force;
+            rope.accelerate_at(grab.position, (swing, gravity * PLAYER_WEIGHT), delta_time);
+
+            if input_handler.is_action_pressed(Action::Jump) {
+                // Let go, keeping the rope's swing and hopping up a little
+                let (vx, vy) = rope.velocity_at(grab.position, delta_time);
+                game_state.set_velocity(vx, vy.max(0.0) + tuning().jump_force * 0.6);
+                self.grab = None;
+                self.regrab_timer = ROPE_REGRAB_DELAY;
+            }
+        }
+
+        for rope in self.ropes.iter_mut().filter(|rope| rope.active) {
+            rope.simulate(gravity, delta_time);
+        }
+
+        if let Some(grab) = &self.grab {
+            // Hang from the rope by the hands
+            let (x, y) = self.ropes[grab.rope].point_at(grab.position);
+            game_state.teleport(x, y - SPRITE_HEIGHT * 0.3);
+            return;
+        }
+
+        let player = game_state.bounds();
+        let player_x = game_state.player_x;
+        let (_, velocity_y) = game_state.velocity();
+        let can_grab = self.regrab_timer <= 0.0
+            && !game_state.movement().mounted
+            && !game_state.cheats.noclip
+            && input_handler.is_action_pressed(Action::Up);
+
+        for (rope_index, rope) in self.ropes.iter_mut().enumerate() {
+            if !rope.active {
+                continue;
+            }
+            match rope.kind {
+                RopeKind::Bridge => {
+                    // Find the segment under the player and stand on it while falling onto it
+                    let support = rope.points.windows(2).position(|pair| {
+                        let (a, b) = (pair[0].position.0, pair[1].position.0);
+                        a.min(b) <= player_x && player_x <= a.max(b)
+                    });
+                    let Some(index) = support else { continue };
+
+                    let (a, b) = (rope.points[index].position, rope.points[index + 1].position);
+                    let t = if b.0 != a.0 { (player_x - a.0) / (b.0 - a.0) } else { 0.0 };
+                    let surface = a.1 + (b.1 - a.1) * t + rope.thickness() / 2.0;
+
+                    if velocity_y <= 0.0
+                        && !game_state.cheats.noclip
+                        && player.min_y <= surface
+                        && player.min_y >= surface - BRIDGE_LANDING_DEPTH
+                    {
+                        game_state.land_on(surface);
+                        rope.accelerate_at(index as f32 + t, (0.0, game_state.physics().gravity * PLAYER_WEIGHT), delta_time);
+                    }
+                }
+                RopeKind::Rope | RopeKind::Chain => {
+                    let touching = rope.points.iter().position(|point| {
+                        !point.pinned
+                            && point.position.0 > player.min_x
+                            && point.position.0 < player.max_x
+                            && point.position.1 > player.min_y
+                            && point.position.1 < player.max_y
+                    });
+
+                    if let Some(index) = touching.filter(|_| can_grab && self.grab.is_none()) {
+                        self.grab = Some(Grab {
+                            rope: rope_index,
+                            position: index as f32,
+                        });
+                        continue;
+                    }
+
+                    // Push the rope out of the player's way
+                    for point in rope.points.iter_mut().filter(|point| !point.pinned) {
+                        let (x, y) = point.position;
+                        if x > player.min_x && x < player.max_x && y > player.min_y && y < player.max_y {
+                            point.position.0 = if x < player_x { player.min_x } else { player.max_x };
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,70 @@
+// save.rs
+use crate::engine::constants::{STARTING_CONTINUES, STARTING_LIVES};
+use crate::engine::level::ObjectId;
+use serde::{Deserialize, Serialize};
+use crate::engine::rng::Rng;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+
+/// Errors that can occur while writing or reading a save file.
+#[derive(Debug)]
+pub enum SaveError {
+    /// The save file could not be written or read.
+    Io(std::io::Error),
+    /// The save file is malformed.
+    Parse(String),
+}
+
+impl fmt::Display for SaveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SaveError::Io(e) => write!(f, "failed to access save file: {}", e),
+            SaveError::Parse(msg) => write!(f, "failed to parse save file: {}", msg),
+        }
+    }
+}
+
+impl std::error::Error for SaveError {}
+
+impl From<std::io::Error> for SaveError {
+    fn from(e: std::io::Error) -> Self {
+        SaveError::Io(e)
+    }
+}
+
+/// The progress stored in a save file, written as JSON.
+#[derive(Serialize, Deserialize)]
+pub struct SaveData {
+    /// Path of the level the player was in.
+    pub level: String,
+    /// Player position as `(x, y)` in world coordinates.
+    pub player: (f32, f32),
+    pub score: u32,
+    #[serde(default = "default_lives")]
+    pub lives: u32,
+    #[serde(default = "default_continues")]
+    pub continues: u32,
+    /// Names of the level objects the player has collected.
+    pub collected: BTreeSet<String>,
+    /// Level objects switched on, written as `level#id`.
+    #[serde(default)]
+    pub switched_on: BTreeSet<ObjectId>,
+    /// Seed of the run, to carry on from; saves written before seeds were saved keep
+    /// the current run's.
+    #[serde(default)]
+    pub seed: Option<u32>,
+    /// Where each of the run's random number streams had got to, by name; streams left
+    /// out start over from the seed.
+    #[serde(default)]
+    pub streams: BTreeMap<String, Rng>,
+}
+
+/// Lives for saves written before lives were saved.
+fn default_lives() -> u32 {
+    STARTING_LIVES
+}
+
+/// Continues for saves written before continues were saved.
+fn default_continues() -> u32 {
+    STARTING_CONTINUES
+}
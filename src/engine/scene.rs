@@ -0,0 +1,125 @@
+// scene.rs
+use crate::engine::constants::SCENE_FADE_TIME;
+use crate::engine::tween::{ease_in_out, progress, Timer};
+
+/// What the game is showing: the level-select menu or one of the levels.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Scene {
+    /// The level-select menu, drawn over a backdrop without the player.
+    Menu,
+    /// A level, by the path of its Tiled map.
+    Level(String),
+}
+
+/// A switch from one scene to another, fading the screen out and back in.
+struct Transition {
+    /// The scene being switched to.
+    to: Scene,
+    /// Real time through the fade out and back in.
+    timer: Timer,
+    /// Whether the new scene has been handed out to load.
+    arrived: bool,
+}
+
+/// Switches between the menu and the levels, above the `GameState` of the world.
+///
+/// A switch fades the screen to black, hands out the new scene for the game loop
+/// to load once the old one is hidden, then fades back in over it. The old scene's
+/// world is unloaded by the game loop replacing it with the new one.
+pub struct SceneManager {
+    current: Scene,
+    transition: Option<Transition>,
+}
+
+impl SceneManager {
+    /// Creates the manager in its first scene, fading in from black.
+    ///
+    /// The first scene is loaded by the caller rather than handed out by `arrive`.
+    ///
+    /// # Arguments
+    ///
+    /// * `scene` - The scene to start in.
+    pub fn new(scene: Scene) -> Self {
+        Self {
+            current: scene.clone(),
+            transition: Some(Transition {
+                to: scene,
+                timer: Timer::new(2.0 * SCENE_FADE_TIME).with_elapsed(SCENE_FADE_TIME),
+                arrived: true,
+            }),
+        }
+    }
+
+    /// Returns the scene being shown.
+    pub fn current(&self) -> &Scene {
+        &self.current
+    }
+
+    /// Starts fading out to another scene. Ignored while already fading out.
+    ///
+    /// # Arguments
+    ///
+    /// * `scene` - The scene to switch to.
+    pub fn switch_to(&mut self, scene: Scene) {
+        if self.leaving() {
+            return;
+        }
+        log::info!("Switching to {:?}", scene);
+
+        // Fade back out from however far the current scene has faded in
+        let elapsed = self
+            .transition
+            .as_ref()
+            .map_or(0.0, |transition| 2.0 * SCENE_FADE_TIME - transition.timer.elapsed());
+        self.transition = Some(Transition {
+            to: scene,
+            timer: Timer::new(2.0 * SCENE_FADE_TIME).with_elapsed(elapsed),
+            arrived: false,
+        });
+    }
+
+    /// Advances the fade.
+    ///
+    /// # Arguments
+    ///
+    /// * `delta_time` - Real time elapsed since the last frame, so the fade runs
+    ///   at the same pace whatever the game speed.
+    pub fn update(&mut self, delta_time: f32) {
+        if let Some(transition) = self.transition.as_mut() {
+            transition.timer.tick(delta_time);
+            if transition.arrived && transition.timer.finished() {
+                self.transition = None;
+            }
+        }
+    }
+
+    /// Returns the scene to load once the screen has faded out, once per switch.
+    pub fn arrive(&mut self) -> Option<Scene> {
+        let transition = self.transition.as_mut()?;
+        if transition.arrived || transition.timer.elapsed() < SCENE_FADE_TIME {
+            return None;
+        }
+        transition.arrived = true;
+        self.current = transition.to.clone();
+        Some(self.current.clone())
+    }
+
+    /// Returns `true` while fading out of the current scene, which holds still meanwhile.
+    pub fn leaving(&self) -> bool {
+        self.transition.as_ref().is_some_and(|transition| !transition.arrived)
+    }
+
+    /// Returns the opacity of the black drawn over the screen, from 0.0 to 1.0.
+    pub fn fade_alpha(&self) -> f32 {
+        self.transition.as_ref().map_or(0.0, |transition| {
+            let elapsed = transition.timer.elapsed();
+            let fade_out = ease_in_out(progress(elapsed, 0.0, SCENE_FADE_TIME));
+            let fade_in = ease_in_out(progress(elapsed, SCENE_FADE_TIME, SCENE_FADE_TIME));
+            if transition.arrived {
+                1.0 - fade_in
+            } else {
+                fade_out
+            }
+        })
+    }
+}
@@ -0,0 +1,126 @@
+// scheduler.rs
+use crate::engine::tween::Timer;
+
+/// Identifies a task given to a `Scheduler`, to cancel it or see how long it has left.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct TaskId(u64);
+
+/// A task waiting for its timer to come round.
+struct ScheduledTask<T> {
+    id: TaskId,
+    timer: Timer,
+    task: T,
+}
+
+/// Holds tasks to be done after a delay or over and over at an interval, counted in
+/// game time as the game loop updates it, in place of hand-kept countdowns.
+///
+/// A task is whatever the owner wants handed back when its time comes: a value it
+/// matches on, such as which kind of thing to spawn, or a function to call. Each
+/// update returns the tasks that came due, so the owner carries them out with all
+/// its own state at hand.
+pub struct Scheduler<T> {
+    tasks: Vec<ScheduledTask<T>>,
+    next_id: u64,
+}
+
+impl<T> Scheduler<T> {
+    /// Creates a scheduler with nothing scheduled.
+    pub fn new() -> Self {
+        Self {
+            tasks: Vec::new(),
+            next_id: 0,
+        }
+    }
+
+    /// Schedules a task to come due once, after a delay.
+    ///
+    /// # Arguments
+    ///
+    /// * `delay` - Time until the task comes due, in seconds.
+    /// * `task` - What to hand back then.
+    pub fn after(&mut self, delay: f32, task: T) -> TaskId {
+        self.schedule(Timer::new(delay), task)
+    }
+
+    /// Schedules a task to come due every time an interval passes, until cancelled.
+    ///
+    /// # Arguments
+    ///
+    /// * `interval` - Time between each time the task comes due, in seconds.
+    /// * `task` - What to hand back each time.
+    pub fn every(&mut self, interval: f32, task: T) -> TaskId {
+        self.schedule(Timer::repeating(interval), task)
+    }
+
+    fn schedule(&mut self, timer: Timer, task: T) -> TaskId {
+        let id = TaskId(self.next_id);
+        self.next_id += 1;
+        self.tasks.push(ScheduledTask { id, timer, task });
+        id
+    }
+
+    /// Cancels a task before it comes due, or stops one that repeats.
+    ///
+    /// # Returns
+    ///
+    /// The task, or `None` if it had already come due or been cancelled.
+    pub fn cancel(&mut self, id: TaskId) -> Option<T> {
+        let index = self.tasks.iter().position(|scheduled| scheduled.id == id)?;
+        Some(self.tasks.remove(index).task)
+    }
+
+    /// Returns the time until a task next comes due, in seconds, or `None` if it's no
+    /// longer scheduled.
+    pub fn time_left(&self, id: TaskId) -> Option<f32> {
+        let scheduled = self.tasks.iter().find(|scheduled| scheduled.id == id)?;
+        Some((scheduled.timer.duration() - scheduled.timer.elapsed()).max(0.0))
+    }
+
+    /// Returns whether nothing is scheduled.
+    pub fn is_empty(&self) -> bool {
+        self.tasks.is_empty()
+    }
+
+    /// Cancels every task.
+    pub fn clear(&mut self) {
+        self.tasks.clear();
+    }
+
+    /// Counts down every task by a frame.
+    ///
+    /// # Arguments
+    ///
+    /// * `delta_time` - Game time the frame took, in seconds.
+    ///
+    /// # Returns
+    ///
+    /// The tasks that came due this frame, in the order they were scheduled. Tasks due
+    /// once are removed; repeating ones are handed back as a copy and stay scheduled,
+    /// coming due at most once a frame.
+    pub fn update(&mut self, delta_time: f32) -> Vec<T>
+    where
+        T: Clone,
+    {
+        let mut due = Vec::new();
+        let mut index = 0;
+        while index < self.tasks.len() {
+            let scheduled = &mut self.tasks[index];
+            if !scheduled.timer.tick(delta_time) {
+                index += 1;
+            } else if scheduled.timer.finished() {
+                due.push(self.tasks.remove(index).task);
+            } else {
+                due.push(scheduled.task.clone());
+                index += 1;
+            }
+        }
+        due
+    }
+}
+
+impl<T> Default for Scheduler<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,581 @@
+// script.rs
+use crate::engine::activation::ActivationRange;
+use crate::engine::camera::{Camera, CameraShot};
+use crate::engine::collision::Aabb;
+use crate::engine::effects::Effects;
+use crate::engine::game_state::GameState;
+use crate::engine::level::{Level, LevelObject};
+use crate::engine::mods::resolve;
+use crate::engine::projectile::Projectiles;
+use crate::engine::scheduler::Scheduler;
+use log::{info, warn};
+use rhai::module_resolvers::DummyModuleResolver;
+use rhai::{Dynamic, Engine, EvalAltResult, FnPtr, Map, ParseError, Scope, AST, FLOAT};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fmt;
+use std::path::Path;
+use std::rc::Rc;
+
+/// Directory the level's `script` properties name files in.
+pub const SCRIPTS_DIR: &str = "assets/scripts";
+
+/// Most operations a script runs in one call before it's stopped, so a runaway loop
+/// can't hang the game.
+const MAX_OPERATIONS: u64 = 100_000;
+
+/// Errors that can occur while loading a script.
+#[derive(Debug)]
+pub enum ScriptError {
+    /// The script file could not be read.
+    Io(std::io::Error),
+    /// The script isn't valid Rhai.
+    Parse(ParseError),
+}
+
+impl fmt::Display for ScriptError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ScriptError::Io(e) => write!(f, "failed to read script: {}", e),
+            ScriptError::Parse(e) => write!(f, "failed to parse script: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for ScriptError {}
+
+impl From<std::io::Error> for ScriptError {
+    fn from(e: std::io::Error) -> Self {
+        ScriptError::Io(e)
+    }
+}
+
+/// When a script starts running.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Trigger {
+    /// The first time the player enters its area.
+    Enter,
+    /// As soon as its area first comes near the camera, running only while it's near.
+    Always,
+}
+
+/// Something a script asked the game to do, carried out once the script returns.
+#[derive(Clone)]
+enum Command {
+    /// Calls a function once, after a number of seconds.
+    After(f32, FnPtr),
+    /// Calls a function every time a number of seconds passes, until the script stops.
+    Every(f32, FnPtr),
+    /// Ends the script, dropping the functions it scheduled.
+    Stop,
+    /// Plays an effect preset at a position.
+    Effect { name: String, x: f32, y: f32 },
+    /// Fires a hostile projectile from a position, in a direction in degrees
+    /// counterclockwise from the right, at a speed in tiles per second.
+    Fire { x: f32, y: f32, direction: f32, speed: f32 },
+    /// Fires a bullet pattern from a position, in a direction in degrees
+    /// counterclockwise from the right.
+    Pattern { name: String, x: f32, y: f32, direction: f32 },
+    /// Frames the camera on a position, at a zoom, until released.
+    Focus { x: f32, y: f32, zoom: f32 },
+    /// Lets the camera go back to following the player.
+    Release,
+    /// Starts or ends a cutscene, taking control from the player behind letterbox bars.
+    Cutscene(bool),
+    /// Adds points to the score.
+    Score(u32),
+}
+
+/// What the running script can see of the game, and what it has asked for, shared
+/// with the functions registered on the engine.
+#[derive(Default)]
+struct Host {
+    /// The player's state, as scripts read it with `player()`.
+    player: Map,
+    /// Seconds since the running script started.
+    time: f32,
+    /// What the running script has asked for so far.
+    commands: Vec<Command>,
+}
+
+/// A script partway through running.
+struct Run {
+    /// Time since the script started, in seconds.
+    time: f32,
+    /// Functions the script scheduled, handed back as they come due.
+    tasks: Scheduler<FnPtr>,
+    /// Whether the script called `stop`.
+    stopped: bool,
+    /// Framing the script asked the camera for, released when it ends.
+    shot: Option<CameraShot>,
+    /// Whether the script is playing a cutscene.
+    cutscene: bool,
+}
+
+/// A level object running a script.
+struct ScriptObject {
+    name: String,
+    bounds: Aabb,
+    script: Rc<AST>,
+    trigger: Trigger,
+    /// Identity of the turret the script drives, which stops it once shot down.
+    turret: Option<String>,
+    /// Whether the script has been started, so it runs only once.
+    started: bool,
+    run: Option<Run>,
+    /// Whether the object is near enough to the camera to run an `Always` script.
+    active: bool,
+}
+
+/// What a script can change in the game while it runs.
+struct Api<'a> {
+    game_state: &'a mut GameState,
+    camera: &'a mut Camera,
+    effects: &'a mut Effects,
+    projectiles: &'a mut Projectiles,
+    /// World units per tile, which scripts give speeds in.
+    tile_width: f32,
+}
+
+impl Run {
+    fn new() -> Self {
+        Self {
+            time: 0.0,
+            tasks: Scheduler::new(),
+            stopped: false,
+            shot: None,
+            cutscene: false,
+        }
+    }
+
+    /// Returns whether the script has nothing left to run.
+    fn ended(&self) -> bool {
+        self.stopped || self.tasks.is_empty()
+    }
+
+    /// Carries out something the script asked for.
+    fn apply(&mut self, command: Command, api: &mut Api) {
+        match command {
+            Command::After(delay, task) => {
+                self.tasks.after(delay, task);
+            }
+            Command::Every(interval, task) => {
+                self.tasks.every(interval, task);
+            }
+            Command::Stop => {
+                self.tasks.clear();
+                self.stopped = true;
+            }
+            Command::Effect { name, x, y } => api.effects.play(&name, x, y),
+            Command::Fire { x, y, direction, speed } => {
+                let direction = direction.to_radians();
+                let speed = speed * api.tile_width;
+                api.projectiles.fire((x, y), (direction.cos() * speed, direction.sin() * speed));
+            }
+            Command::Pattern { name, x, y, direction } => api.projectiles.emit(&name, (x, y), direction),
+            Command::Focus { x, y, zoom } => {
+                let shot = CameraShot {
+                    zoom: zoom.max(0.1),
+                    focus: Some((x, y)),
+                    duration: None,
+                };
+                api.camera.request_shot(shot);
+                self.shot = Some(shot);
+            }
+            Command::Release => {
+                if let Some(shot) = self.shot.take() {
+                    api.camera.release_shot(&shot);
+                }
+            }
+            Command::Cutscene(on) => self.cutscene = on,
+            Command::Score(points) => api.game_state.score = api.game_state.score.saturating_add(points),
+        }
+    }
+
+    /// Lets go of the camera as the script ends.
+    fn finish(self, camera: &mut Camera) {
+        if let Some(shot) = self.shot {
+            camera.release_shot(&shot);
+        }
+    }
+}
+
+/// Runs the level's scripts, which drive triggers, enemies, and cutscenes from
+/// [Rhai](https://rhai.rs) script files loaded with the level.
+///
+/// Scripts come from `script` rectangles in the level, whose `script` property names
+/// a file in the scripts directory, and whose `trigger` property picks when it starts:
+/// `enter` (the default) the first time the player enters the rectangle, or `always`
+/// as soon as it comes near the camera. Turrets with a `script` property fire only as
+/// their script says, running it while near the camera until they're shot down.
+///
+/// A script's top level runs once when it starts, and carries on over later frames
+/// through the functions it schedules; it ends when it calls `stop()` or has nothing
+/// left scheduled. Scripts see their object's center as the constants `self_x` and
+/// `self_y` and the world size of a tile as `tile`, and call:
+/// - `after(seconds, f)` and `every(seconds, f)`: call a function or closure once
+///   after a delay, or over and over until the script stops.
+/// - `stop()`: ends the script.
+/// - `player()`: the player's `x`, `y`, `vx`, `vy`, `grounded`, `swimming`,
+///   `facing_right`, `lives`, and `score`, as a map.
+/// - `time()`: the seconds since the script started.
+/// - `effect(preset, x, y)`: plays an effect.
+/// - `fire(x, y, direction, speed)`: fires a hostile projectile, in degrees
+///   counterclockwise from the right and tiles per second.
+/// - `pattern(name, x, y, direction)`: fires a bullet pattern from the patterns file.
+/// - `focus(x, y)`, `focus(x, y, zoom)`, and `release()`: frame the camera on a spot
+///   and let it go.
+/// - `cutscene(on)`: takes control from the player behind letterbox bars, or gives it back.
+/// - `score(points)`: adds points to the score.
+/// - `print(text)`: writes the text to the log.
+///
+/// Scripts can't reach files or import modules. A script that fails, or runs too
+/// many operations in one call, is stopped with a warning.
+pub struct Scripts {
+    engine: Engine,
+    host: Rc<RefCell<Host>>,
+    objects: Vec<ScriptObject>,
+    tile_width: f32,
+}
+
+impl Scripts {
+    /// Loads the scripts of the level's script objects and scripted turrets, none of them started yet.
+    pub fn from_level(level: &Level) -> Self {
+        let host = Rc::new(RefCell::new(Host::default()));
+        let engine = create_engine(&host);
+
+        // Scripts shared between objects are loaded once
+        let mut loaded = HashMap::new();
+        let mut objects = Vec::new();
+        for object in level.objects_of_kind("script") {
+            let trigger = match object.property("trigger") {
+                None | Some("enter") => Trigger::Enter,
+                Some("always") => Trigger::Always,
+                Some(other) => {
+                    warn!("Script '{}' has unknown trigger '{}'; using 'enter'", object.name, other);
+                    Trigger::Enter
+                }
+            };
+            if object.property("script").is_none() {
+                warn!("Script '{}' has no `script` file", object.name);
+            }
+            if let Some(script) = load(&engine, &mut loaded, object) {
+                objects.push(ScriptObject::new(object, script, trigger, None));
+            }
+        }
+        for object in level.objects_of_kind("turret") {
+            if let Some(script) = load(&engine, &mut loaded, object) {
+                let turret = Some(level.object_id(object).to_string());
+                objects.push(ScriptObject::new(object, script, Trigger::Always, turret));
+            }
+        }
+
+        Self {
+            engine,
+            host,
+            objects,
+            tile_width: level.tile_map.tile_width,
+        }
+    }
+
+    /// Activates the scripts whose objects are within range of the camera and deactivates the rest.
+    ///
+    /// # Returns
+    ///
+    /// The number of scripts left active.
+    pub fn activate(&mut self, range: &ActivationRange) -> usize {
+        for object in &mut self.objects {
+            object.active = range.contains(&object.bounds);
+        }
+        self.objects.iter().filter(|object| object.active).count()
+    }
+
+    /// Starts the scripts whose trigger has been met and runs the functions those
+    /// started have scheduled, stopping the scripts of shot-down turrets.
+    ///
+    /// # Arguments
+    ///
+    /// * `game_state` - Provides the player's state, and records points the scripts add.
+    /// * `camera` - The camera scripts frame things with.
+    /// * `effects` - Plays the effects scripts ask for.
+    /// * `projectiles` - Takes the projectiles scripts fire.
+    /// * `delta_time` - Time elapsed since the last frame.
+    pub fn update(
+        &mut self,
+        game_state: &mut GameState,
+        camera: &mut Camera,
+        effects: &mut Effects,
+        projectiles: &mut Projectiles,
+        delta_time: f32,
+    ) {
+        let player = game_state.bounds();
+        self.host.borrow_mut().player = player_map(game_state);
+        let mut api = Api {
+            game_state,
+            camera,
+            effects,
+            projectiles,
+            tile_width: self.tile_width,
+        };
+        for object in &mut self.objects {
+            if object.turret.as_ref().is_some_and(|turret| api.game_state.has_collected(turret)) {
+                if let Some(run) = object.run.take() {
+                    run.finish(api.camera);
+                }
+                continue;
+            }
+
+            let triggered = match object.trigger {
+                Trigger::Enter => object.bounds.intersects(&player),
+                Trigger::Always => object.active,
+            };
+            let starting = !object.started && triggered;
+            if starting {
+                info!("Running script of '{}'", object.name);
+                object.started = true;
+                object.run = Some(Run::new());
+            }
+            // Scripts always running only run near the camera, like the entities they drive
+            if object.trigger == Trigger::Always && !object.active {
+                continue;
+            }
+
+            let Some(run) = object.run.as_mut() else {
+                continue;
+            };
+            let result = if starting {
+                let center = ((object.bounds.min_x + object.bounds.max_x) / 2.0, (object.bounds.min_y + object.bounds.max_y) / 2.0);
+                let mut scope = Scope::new();
+                scope.push_constant("self_x", center.0 as FLOAT);
+                scope.push_constant("self_y", center.1 as FLOAT);
+                scope.push_constant("tile", self.tile_width as FLOAT);
+                call(&self.host, run, &mut api, || self.engine.run_ast_with_scope(&mut scope, &object.script))
+            } else {
+                run.time += delta_time;
+                run.tasks.update(delta_time).into_iter().try_for_each(|task| {
+                    // A task stopping the script drops the rest due this frame
+                    if run.stopped {
+                        return Ok(());
+                    }
+                    call(&self.host, run, &mut api, || {
+                        task.call::<Dynamic>(&self.engine, &object.script, ()).map(drop)
+                    })
+                })
+            };
+            if let Err(e) = &result {
+                warn!("Stopped the script of '{}': {}", object.name, e);
+            }
+            if result.is_err() || run.ended() {
+                if let Some(run) = object.run.take() {
+                    run.finish(api.camera);
+                }
+            }
+        }
+    }
+
+    /// Returns whether a script is playing a cutscene, taking control from the player.
+    pub fn in_cutscene(&self) -> bool {
+        self.objects
+            .iter()
+            .any(|object| object.run.as_ref().is_some_and(|run| run.cutscene))
+    }
+}
+
+impl ScriptObject {
+    fn new(object: &LevelObject, script: Rc<AST>, trigger: Trigger, turret: Option<String>) -> Self {
+        Self {
+            name: object.name.clone(),
+            bounds: object.bounds,
+            script,
+            trigger,
+            turret,
+            started: false,
+            run: None,
+            active: true,
+        }
+    }
+}
+
+/// Runs part of a script, then carries out what it asked for. Nothing it asked for
+/// is carried out if it fails.
+///
+/// # Arguments
+///
+/// * `host` - Collects what the script asks for.
+/// * `run` - The script's run.
+/// * `api` - What the script can change.
+/// * `part` - Runs the part of the script.
+fn call(
+    host: &RefCell<Host>,
+    run: &mut Run,
+    api: &mut Api,
+    part: impl FnOnce() -> Result<(), Box<EvalAltResult>>,
+) -> Result<(), Box<EvalAltResult>> {
+    host.borrow_mut().time = run.time;
+    let result = part();
+    let commands = std::mem::take(&mut host.borrow_mut().commands);
+    result?;
+    for command in commands {
+        run.apply(command, api);
+    }
+    Ok(())
+}
+
+/// Creates the engine scripts run on, with only the functions the game gives them.
+///
+/// # Arguments
+///
+/// * `host` - Shared with the registered functions, which read the game from it and
+///   queue what scripts ask for on it.
+fn create_engine(host: &Rc<RefCell<Host>>) -> Engine {
+    let mut engine = Engine::new();
+    engine.set_module_resolver(DummyModuleResolver::new());
+    engine.set_max_operations(MAX_OPERATIONS);
+    engine.on_print(|text| info!("Script: {}", text));
+
+    // What the script can read
+    let player = Rc::clone(host);
+    engine.register_fn("player", move || player.borrow().player.clone());
+    let time = Rc::clone(host);
+    engine.register_fn("time", move || time.borrow().time as FLOAT);
+
+    // What the script can ask for, carried out once it returns
+    let queue = |host: &Rc<RefCell<Host>>| {
+        let host = Rc::clone(host);
+        move |command: Command| host.borrow_mut().commands.push(command)
+    };
+    let push = queue(host);
+    engine.register_fn("after", move |delay: Dynamic, task: FnPtr| -> Result<(), Box<EvalAltResult>> {
+        push(Command::After(number(delay)?.max(0.0), task));
+        Ok(())
+    });
+    let push = queue(host);
+    engine.register_fn("every", move |interval: Dynamic, task: FnPtr| -> Result<(), Box<EvalAltResult>> {
+        push(Command::Every(number(interval)?.max(0.0), task));
+        Ok(())
+    });
+    let push = queue(host);
+    engine.register_fn("stop", move || push(Command::Stop));
+    let push = queue(host);
+    engine.register_fn("effect", move |name: &str, x: Dynamic, y: Dynamic| -> Result<(), Box<EvalAltResult>> {
+        push(Command::Effect {
+            name: name.to_string(),
+            x: number(x)?,
+            y: number(y)?,
+        });
+        Ok(())
+    });
+    let push = queue(host);
+    engine.register_fn(
+        "fire",
+        move |x: Dynamic, y: Dynamic, direction: Dynamic, speed: Dynamic| -> Result<(), Box<EvalAltResult>> {
+            push(Command::Fire {
+                x: number(x)?,
+                y: number(y)?,
+                direction: number(direction)?,
+                speed: number(speed)?,
+            });
+            Ok(())
+        },
+    );
+    let push = queue(host);
+    engine.register_fn(
+        "pattern",
+        move |name: &str, x: Dynamic, y: Dynamic, direction: Dynamic| -> Result<(), Box<EvalAltResult>> {
+            push(Command::Pattern {
+                name: name.to_string(),
+                x: number(x)?,
+                y: number(y)?,
+                direction: number(direction)?,
+            });
+            Ok(())
+        },
+    );
+    let push = queue(host);
+    engine.register_fn("focus", move |x: Dynamic, y: Dynamic| -> Result<(), Box<EvalAltResult>> {
+        push(Command::Focus {
+            x: number(x)?,
+            y: number(y)?,
+            zoom: 1.0,
+        });
+        Ok(())
+    });
+    let push = queue(host);
+    engine.register_fn("focus", move |x: Dynamic, y: Dynamic, zoom: Dynamic| -> Result<(), Box<EvalAltResult>> {
+        push(Command::Focus {
+            x: number(x)?,
+            y: number(y)?,
+            zoom: number(zoom)?,
+        });
+        Ok(())
+    });
+    let push = queue(host);
+    engine.register_fn("release", move || push(Command::Release));
+    let push = queue(host);
+    engine.register_fn("cutscene", move |on: bool| push(Command::Cutscene(on)));
+    let push = queue(host);
+    engine.register_fn("score", move |points: Dynamic| -> Result<(), Box<EvalAltResult>> {
+        push(Command::Score(number(points)?.max(0.0) as u32));
+        Ok(())
+    });
+
+    engine
+}
+
+/// Reads a number a script passed, whether it was written as an integer or not.
+fn number(value: Dynamic) -> Result<f32, Box<EvalAltResult>> {
+    match (value.as_float(), value.as_int()) {
+        (Ok(number), _) => Ok(number as f32),
+        (_, Ok(number)) => Ok(number as f32),
+        _ => Err(format!("expected a number, found {}", value.type_name()).into()),
+    }
+}
+
+/// Returns the player's state as scripts read it from `player()`.
+fn player_map(game_state: &GameState) -> Map {
+    let (vx, vy) = game_state.velocity();
+    let values: [(&str, Dynamic); 9] = [
+        ("x", (game_state.player_x as FLOAT).into()),
+        ("y", (game_state.player_y as FLOAT).into()),
+        ("vx", (vx as FLOAT).into()),
+        ("vy", (vy as FLOAT).into()),
+        ("grounded", game_state.is_grounded().into()),
+        ("swimming", game_state.is_swimming().into()),
+        ("facing_right", game_state.facing_right.into()),
+        ("lives", (game_state.lives as rhai::INT).into()),
+        ("score", (game_state.score as rhai::INT).into()),
+    ];
+    values.into_iter().map(|(name, value)| (name.into(), value)).collect()
+}
+
+/// Loads the script an object's `script` property names, once for all the objects naming it.
+///
+/// # Returns
+///
+/// The script, or `None` if the object has none or it couldn't be loaded.
+fn load<'a>(
+    engine: &Engine,
+    loaded: &mut HashMap<&'a str, Option<Rc<AST>>>,
+    object: &'a LevelObject,
+) -> Option<Rc<AST>> {
+    let file = object.property("script")?;
+    loaded
+        .entry(file)
+        .or_insert_with(|| match compile(engine, resolve(Path::new(SCRIPTS_DIR).join(file))) {
+            Ok(mut script) => {
+                script.set_source(file);
+                Some(Rc::new(script))
+            }
+            Err(e) => {
+                warn!("Couldn't load script '{}' for '{}': {}", file, object.name, e);
+                None
+            }
+        })
+        .clone()
+}
+
+/// Reads and compiles a script file.
+fn compile(engine: &Engine, path: impl AsRef<Path>) -> Result<AST, ScriptError> {
+    let source = std::fs::read_to_string(path)?;
+    engine.compile(source).map_err(ScriptError::Parse)
+}
@@ -0,0 +1,74 @@
+// scrubber.rs
+use std::collections::VecDeque;
+
+/// How many simulated frames are kept to scrub through, about five seconds at 60 frames per second.
+const HISTORY_FRAMES: usize = 300;
+
+/// A rolling history of whole simulation states, for stepping backward and forward
+/// through the last few seconds while paused to catch intermittent physics bugs in
+/// the act. Only debug builds have it.
+///
+/// Every simulated frame's state is recorded, dropping the oldest past
+/// `HISTORY_FRAMES`. Stepping moves a cursor through the history, starting from the
+/// newest state; recording again after stepping back forgets the states after the
+/// cursor, so the game carries on from the one being looked at.
+pub struct Scrubber<T> {
+    /// Recorded states, oldest first.
+    history: VecDeque<T>,
+    /// Index of the state being looked at, or `None` while the game runs live.
+    cursor: Option<usize>,
+}
+
+impl<T> Scrubber<T> {
+    /// Creates a scrubber with nothing recorded.
+    pub fn new() -> Self {
+        Self {
+            history: VecDeque::with_capacity(HISTORY_FRAMES),
+            cursor: None,
+        }
+    }
+
+    /// Records the state at the end of a simulated frame.
+    ///
+    /// # Arguments
+    ///
+    /// * `state` - The whole simulation state the frame ended in.
+    pub fn record(&mut self, state: T) {
+        if let Some(cursor) = self.cursor.take() {
+            self.history.truncate(cursor + 1);
+        }
+        self.history.push_back(state);
+        while self.history.len() > HISTORY_FRAMES {
+            self.history.pop_front();
+        }
+    }
+
+    /// Moves through the history by a number of frames, stopping at either end.
+    ///
+    /// # Arguments
+    ///
+    /// * `frames` - How many frames to move; negative steps back toward the oldest.
+    ///
+    /// # Returns
+    ///
+    /// The state now being looked at, or `None` if nothing has been recorded.
+    pub fn step(&mut self, frames: isize) -> Option<&T> {
+        let newest = self.history.len().checked_sub(1)?;
+        let cursor = self.cursor.unwrap_or(newest);
+        let cursor = cursor.saturating_add_signed(frames).min(newest);
+        self.cursor = Some(cursor);
+        self.history.get(cursor)
+    }
+
+    /// Returns the frame being looked at, counting from 1 for the oldest, and how
+    /// many are recorded, or `None` while the game runs live.
+    pub fn position(&self) -> Option<(usize, usize)> {
+        self.cursor.map(|cursor| (cursor + 1, self.history.len()))
+    }
+}
+
+impl<T> Default for Scrubber<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,76 @@
+// secret.rs
+use crate::engine::collision::Aabb;
+use crate::engine::config::tuning;
+use crate::engine::game_state::GameState;
+use crate::engine::level::Level;
+use log::info;
+
+/// A hidden region covered by foreground tiles that fade out while the player is inside.
+struct SecretArea {
+    name: String,
+    bounds: Aabb,
+    /// Current opacity of the cover, from 0.0 (revealed) to 1.0 (hidden).
+    alpha: f32,
+    discovered: bool,
+}
+
+/// Fades foreground "secret cover" tiles in and out as the player walks behind them.
+///
+/// Areas come from `secret_area` rectangles in the level. Any foreground tile whose
+/// center lies inside an area takes on that area's opacity.
+pub struct SecretAreas {
+    areas: Vec<SecretArea>,
+}
+
+impl SecretAreas {
+    /// Creates the secret areas placed in the level, all initially hidden.
+    pub fn from_level(level: &Level) -> Self {
+        let areas = level
+            .objects_of_kind("secret_area")
+            .map(|object| SecretArea {
+                name: object.name.clone(),
+                bounds: object.bounds,
+                alpha: 1.0,
+                discovered: false,
+            })
+            .collect();
+
+        Self { areas }
+    }
+
+    /// Fades each area toward revealed while the player overlaps it, and back to hidden otherwise.
+    ///
+    /// # Arguments
+    ///
+    /// * `game_state` - Provides the player's position, and records discovered areas as collected.
+    /// * `delta_time` - Time elapsed since the last frame.
+    pub fn update(&mut self, game_state: &mut GameState, delta_time: f32) {
+        let player = game_state.bounds();
+        let step = tuning().secret_fade_speed * delta_time;
+
+        for area in &mut self.areas {
+            if area.bounds.intersects(&player) {
+                area.alpha = (area.alpha - step).max(0.0);
+                if !area.discovered {
+                    area.discovered = true;
+                    // Areas found before the game was saved aren't announced again
+                    if game_state.collect(&area.name) {
+                        info!("Secret area '{}' discovered", area.name);
+                    }
+                }
+            } else {
+                area.alpha = (area.alpha + step).min(1.0);
+            }
+        }
+    }
+
+    /// Returns the opacity for a foreground tile centered at the given position.
+    pub fn alpha_at(&self, x: f32, y: f32) -> f32 {
+        let point = Aabb::from_center(x, y, 0.0, 0.0);
+        self.areas
+            .iter()
+            .filter(|area| area.bounds.intersects(&point))
+            .map(|area| area.alpha)
+            .fold(1.0, f32::min)
+    }
+}
@@ -0,0 +1,59 @@
+// shadow.rs
+use crate::engine::collision::Aabb;
+use crate::engine::renderer::tile::TileMap;
+
+/// Highest a caster can be above the ground and still cast a shadow, in world units;
+/// the shadow shrinks and fades out towards it.
+const MAX_SHADOW_HEIGHT: f32 = 0.8;
+
+/// How dark a shadow is right under a caster standing on the ground, from 0.0 to 1.0.
+const SHADOW_OPACITY: f32 = 0.45;
+
+/// A shadow's width relative to its caster's, and its height relative to its width.
+const SHADOW_WIDTH_SCALE: f32 = 0.9;
+const SHADOW_ASPECT: f32 = 0.25;
+
+/// Smallest a shadow shrinks to relative to its full size, at the highest a caster casts one.
+const MIN_SHADOW_SCALE: f32 = 0.4;
+
+/// A soft elliptical shadow on the ground under something, grounding it visually.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Shadow {
+    /// The center of the shadow, on the ground's surface.
+    pub position: (f32, f32),
+    /// Width and height of the shadow's ellipse.
+    pub size: (f32, f32),
+    /// How dark the shadow is, from 0.0 (invisible) to 1.0.
+    pub opacity: f32,
+}
+
+impl Shadow {
+    /// Projects a shadow for something onto the nearest ground straight below it,
+    /// smaller and fainter the higher above the ground it is.
+    ///
+    /// # Arguments
+    ///
+    /// * `tile_map` - The level's tiles, whose solid tiles the shadow falls on.
+    /// * `bounds` - The area taken up by the thing casting the shadow.
+    ///
+    /// # Returns
+    ///
+    /// The shadow, or `None` if there's no ground close enough below to cast one on.
+    pub fn cast(tile_map: &TileMap, bounds: &Aabb) -> Option<Self> {
+        let x = (bounds.min_x + bounds.max_x) / 2.0;
+        let ground = tile_map.ground_below(x, bounds.min_y)?;
+        let height = bounds.min_y - ground;
+        if height >= MAX_SHADOW_HEIGHT {
+            return None;
+        }
+
+        let closeness = 1.0 - height / MAX_SHADOW_HEIGHT;
+        let width = (bounds.max_x - bounds.min_x) * SHADOW_WIDTH_SCALE;
+        let scale = MIN_SHADOW_SCALE + (1.0 - MIN_SHADOW_SCALE) * closeness;
+        Some(Self {
+            position: (x, ground),
+            size: (width * scale, width * scale * SHADOW_ASPECT),
+            opacity: SHADOW_OPACITY * closeness * closeness,
+        })
+    }
+}
@@ -0,0 +1,132 @@
+// sign.rs
+use crate::engine::action::Action;
+use crate::engine::collision::Aabb;
+use crate::engine::game_state::GameState;
+use crate::engine::input::InputHandler;
+use crate::engine::level::Level;
+use crate::engine::tween::{ease_out_cubic, Tween};
+use log::warn;
+
+/// How long a sign's panel takes to pop up, in seconds.
+const POP_UP_TIME: f32 = 0.15;
+
+/// A sign with a few lines of text, such as a tutorial hint or a scrap of lore.
+pub struct Sign {
+    /// Area the player must be touching to read the sign, in world coordinates.
+    pub bounds: Aabb,
+    /// Heading shown above the sign's text, if any.
+    pub title: Option<String>,
+    /// Text on the sign's panel, a line per line of the `text` property.
+    pub text: String,
+    /// Whether the panel shows as soon as the player touches the sign, rather than
+    /// when they read it.
+    auto: bool,
+    /// Index of the tileset tile repeated along the sign.
+    pub tile_index: usize,
+    /// Columns and rows of tileset tiles making up each repeat of its art.
+    pub tile_span: (usize, usize),
+}
+
+/// How a sign's panel is drawn this frame.
+pub struct SignPanel<'a> {
+    pub title: Option<&'a str>,
+    pub text: &'a str,
+    /// How far the panel has popped up, from 0.0 to 1.0.
+    pub scale: f32,
+}
+
+/// The level's signs, each popping up a small panel of text while the player reads it.
+///
+/// Signs come from `sign` objects in the level, with the panel's text in their `text`
+/// property and an optional heading in `title`. The player reads a sign by pressing
+/// up while touching it, and again or walking away puts it down. A sign whose `auto`
+/// property is `true` shows its panel whenever the player is touching it instead,
+/// for hints the player shouldn't miss.
+pub struct Signs {
+    pub signs: Vec<Sign>,
+    /// The sign the player is touching, if any.
+    nearby: Option<usize>,
+    /// The sign whose panel is shown, and its panel popping up.
+    open: Option<(usize, Tween<f32>)>,
+}
+
+impl Signs {
+    /// Creates the signs placed in the level.
+    pub fn from_level(level: &Level) -> Self {
+        let signs = level
+            .objects_of_kind("sign")
+            .filter_map(|object| {
+                let Some(text) = object.property("text") else {
+                    warn!("Sign '{}' has no text", object.name);
+                    return None;
+                };
+                Some(Sign {
+                    bounds: object.bounds,
+                    title: object.property("title").map(str::to_uppercase),
+                    text: text.to_uppercase(),
+                    auto: object.property("auto") == Some("true"),
+                    tile_index: object.tile_index(),
+                    tile_span: object.tile_span(),
+                })
+            })
+            .collect();
+
+        Self {
+            signs,
+            nearby: None,
+            open: None,
+        }
+    }
+
+    /// Opens and closes sign panels as the player reads signs and walks away from them.
+    ///
+    /// # Arguments
+    ///
+    /// * `game_state` - Provides the player's position.
+    /// * `input_handler` - Provides the state of the up action, which reads signs.
+    /// * `delta_time` - Time elapsed since the last frame.
+    ///
+    /// # Returns
+    ///
+    /// The index of the sign whose panel opened this frame, if one did.
+    pub fn update(&mut self, game_state: &GameState, input_handler: &InputHandler, delta_time: f32) -> Option<usize> {
+        let player = game_state.bounds();
+        self.nearby = self.signs.iter().position(|sign| sign.bounds.intersects(&player));
+        let read = input_handler.is_action_just_pressed(Action::Up);
+
+        if let Some((index, pop_up)) = self.open.as_mut() {
+            let put_down = read && !self.signs[*index].auto;
+            if self.nearby == Some(*index) && !put_down {
+                pop_up.update(delta_time);
+                return None;
+            }
+            self.open = None;
+            if put_down {
+                return None;
+            }
+        }
+
+        let index = self.nearby?;
+        if !(read || self.signs[index].auto) {
+            return None;
+        }
+        self.open = Some((index, Tween::new(0.0, 1.0, POP_UP_TIME, ease_out_cubic)));
+        Some(index)
+    }
+
+    /// Returns whether the player is touching a sign they can read but haven't.
+    pub fn can_read(&self) -> bool {
+        self.open.is_none() && self.nearby.is_some_and(|index| !self.signs[index].auto)
+    }
+
+    /// Returns how to draw the open sign's panel, or `None` if no sign is being read.
+    pub fn panel(&self) -> Option<SignPanel<'_>> {
+        let (index, pop_up) = self.open.as_ref()?;
+        let sign = &self.signs[*index];
+        Some(SignPanel {
+            title: sign.title.as_deref(),
+            text: &sign.text,
+            scale: pop_up.value(),
+        })
+    }
+}
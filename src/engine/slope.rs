@@ -0,0 +1,114 @@
+// slope.rs
+use crate::engine::collision::Slope;
+use crate::engine::constants::GROUND_LEVEL;
+use crate::engine::game_state::GameState;
+use crate::engine::level::Level;
+
+/// How far below a slope's surface the player's feet can be and still land on it.
+const LANDING_DEPTH: f32 = 0.15;
+
+/// How far above a slope's surface the player's feet can be and still be kept on it
+/// while walking downhill, rather than stepping off into the air each frame.
+const SNAP_HEIGHT: f32 = 0.05;
+
+/// How far past a slope's end the player can be and still stand on it, about a pixel,
+/// bridging the hairline gaps between slopes placed end to end.
+const SEAM_WIDTH: f32 = 0.02;
+
+/// Slopes the player walks up and down, following the incline instead of a flat top.
+///
+/// Slopes come from `slope` objects in the level, drawn over slope tiles. The surface
+/// runs corner to corner across the object: the `rise` property is `right` (the
+/// default) for one climbing from left to right, or `left`. A square object makes a
+/// 45° slope and one wider than it is tall a shallower one. Where slopes meet, the
+/// player stands on the higher surface.
+///
+/// The player is carried across the seams between slopes and from a slope's low end
+/// onto the ground, so a hairline gap or a slope ending a little above the ground
+/// never bumps them into the air. Like bridges, they can be jumped through from below
+/// and stood on from above.
+pub struct Slopes {
+    slopes: Vec<Slope>,
+    /// Whether the player stood on a slope at the end of the last update.
+    standing: bool,
+}
+
+impl Slopes {
+    /// Finds the slopes placed in the level.
+    pub fn from_level(level: &Level) -> Self {
+        let slopes = level
+            .objects_of_kind("slope")
+            .map(|object| {
+                let rises_right = match object.property("rise") {
+                    Some("right") | None => true,
+                    Some("left") => false,
+                    Some(other) => {
+                        log::warn!("Unknown slope rise '{}' on '{}'", other, object.name);
+                        true
+                    }
+                };
+                Slope {
+                    bounds: object.bounds,
+                    rises_right,
+                }
+            })
+            .collect();
+
+        Self {
+            slopes,
+            standing: false,
+        }
+    }
+
+    /// Lands the player on the slope under them, or keeps them on it as they walk.
+    ///
+    /// Walking uphill pushes the player's feet into the surface and walking downhill
+    /// lifts them off it, so a player already standing on a slope is moved back onto
+    /// the surface either way.
+    ///
+    /// # Arguments
+    ///
+    /// * `game_state` - The player state to stand on the slopes.
+    pub fn update(&mut self, game_state: &mut GameState) {
+        let (_, velocity_y) = game_state.velocity();
+        let feet = game_state.bounds().min_y;
+        let surface = self.surface_at(game_state.player_x);
+
+        // Jumping off or walking past the end of a slope lets go of the player
+        let was_standing = self.standing;
+        let reach = if was_standing { SNAP_HEIGHT } else { 0.0 };
+        self.standing = match surface {
+            Some(surface) => {
+                velocity_y <= 0.0
+                    && !game_state.cheats.noclip
+                    && feet <= surface + reach
+                    && feet >= surface - LANDING_DEPTH
+            }
+            None => false,
+        };
+        if let Some(surface) = surface.filter(|_| self.standing) {
+            game_state.land_on(surface);
+        } else if was_standing
+            && velocity_y <= 0.0
+            && !game_state.cheats.noclip
+            && feet - GROUND_LEVEL <= SNAP_HEIGHT
+        {
+            // Stepping off a slope's low end onto the ground
+            game_state.land_on(GROUND_LEVEL);
+        }
+    }
+
+    /// Returns the height of the highest slope surface at a horizontal position,
+    /// extending each slope's ends by `SEAM_WIDTH`.
+    fn surface_at(&self, x: f32) -> Option<f32> {
+        self.slopes
+            .iter()
+            .filter_map(|slope| {
+                let clamped = x.clamp(slope.bounds.min_x, slope.bounds.max_x);
+                ((clamped - x).abs() <= SEAM_WIDTH)
+                    .then(|| slope.surface_y(clamped))
+                    .flatten()
+            })
+            .reduce(f32::max)
+    }
+}
@@ -0,0 +1,85 @@
+// stats.rs
+use std::time::Instant;
+
+/// Counts of the work the engine did for one frame.
+///
+/// Filled in by the game loop as the frame runs, then handed to the debug HUD.
+/// Comparing them against a budget with `over_budget` flags frames that did more
+/// work than the game can afford.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct EngineStats {
+    /// Entities simulated: those within the activation range, plus projectiles in flight.
+    pub entities_updated: usize,
+    /// Instances uploaded to the instance buffer.
+    pub instances: usize,
+    /// Draw calls issued.
+    pub draw_calls: usize,
+    /// Bytes written to the instance and mesh buffers.
+    pub bytes_uploaded: u64,
+    /// Box overlap tests made.
+    pub collision_tests: usize,
+    /// Sounds playing at the end of the frame.
+    pub audio_voices: usize,
+}
+
+/// The most work a frame should do, comfortably within what a low-end laptop
+/// manages at 60 FPS.
+pub const FRAME_BUDGET: EngineStats = EngineStats {
+    entities_updated: 256,
+    instances: 8192,
+    draw_calls: 64,
+    bytes_uploaded: 1 << 20,
+    collision_tests: 20_000,
+    audio_voices: 32,
+};
+
+impl EngineStats {
+    /// Returns the names of the counters that went over budget, empty if none did.
+    ///
+    /// # Arguments
+    ///
+    /// * `budget` - The most of each counter allowed.
+    pub fn over_budget(&self, budget: &EngineStats) -> Vec<&'static str> {
+        let counters = [
+            ("ENTITIES", self.entities_updated as u64, budget.entities_updated as u64),
+            ("INSTANCES", self.instances as u64, budget.instances as u64),
+            ("DRAW CALLS", self.draw_calls as u64, budget.draw_calls as u64),
+            ("UPLOAD", self.bytes_uploaded, budget.bytes_uploaded),
+            ("COLLISIONS", self.collision_tests as u64, budget.collision_tests as u64),
+            ("VOICES", self.audio_voices as u64, budget.audio_voices as u64),
+        ];
+        counters
+            .into_iter()
+            .filter(|(_, value, limit)| value > limit)
+            .map(|(name, _, _)| name)
+            .collect()
+    }
+}
+
+/// Where one frame's time went, span by span, to show on the debug HUD.
+///
+/// CPU spans are timed around the steps of the game loop as they run. GPU spans come
+/// from the renderer's timer, a frame or more late, and are left empty on frames
+/// whose times haven't been read back yet.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct FrameTimings {
+    /// Name and CPU time of each timed step, in seconds, in the order they ran.
+    pub cpu: Vec<(&'static str, f32)>,
+    /// Name and GPU time of each timed pass, in seconds, in the order they were drawn.
+    pub gpu: Vec<(&'static str, f32)>,
+}
+
+impl FrameTimings {
+    /// Runs a step of the frame, timing it on the CPU.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - Name the step is shown by.
+    /// * `step` - The work to time.
+    pub fn time<T>(&mut self, name: &'static str, step: impl FnOnce() -> T) -> T {
+        let start = Instant::now();
+        let result = step();
+        self.cpu.push((name, start.elapsed().as_secs_f32()));
+        result
+    }
+}
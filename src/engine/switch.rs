@@ -0,0 +1,84 @@
+// switch.rs
+use crate::engine::collision::Aabb;
+use crate::engine::game_state::GameState;
+use crate::engine::level::{Level, ObjectId};
+use log::info;
+
+/// A switch the player flips by kicking it.
+pub struct Switch {
+    /// The switch's own identity, under which its state is saved.
+    pub id: ObjectId,
+    /// Area the player must be touching to flip the switch, in world coordinates.
+    pub bounds: Aabb,
+    /// The object the switch turns on and off, which may be in another level.
+    target: Option<ObjectId>,
+    /// Index of the tileset tile drawn while the switch is off.
+    pub tile_index: usize,
+    /// Index of the tileset tile drawn while the switch is on.
+    pub tile_on_index: usize,
+    /// Columns and rows of tileset tiles making up each repeat of its art.
+    pub tile_span: (usize, usize),
+}
+
+/// The level's switches.
+///
+/// Switches come from `switch` rectangles in the level. Their `target` property
+/// names the object they control, such as a door, by id or name, or as `path#id`
+/// for an object in another level. Switch states are kept in the game state, so
+/// they persist across saves and levels.
+pub struct Switches {
+    pub switches: Vec<Switch>,
+    /// Whether the player was already kicking last frame, so each kick flips once.
+    was_kicking: bool,
+}
+
+impl Switches {
+    /// Creates the switches placed in the level.
+    pub fn from_level(level: &Level) -> Self {
+        let switches = level
+            .objects_of_kind("switch")
+            .map(|object| {
+                let tile_index = object.tile_index();
+                Switch {
+                    id: level.object_id(object),
+                    bounds: object.bounds,
+                    target: level.reference(object, "target"),
+                    tile_index,
+                    tile_on_index: object.property_f32("tile_on", tile_index as f32) as usize,
+                    tile_span: object.tile_span(),
+                }
+            })
+            .collect();
+
+        Self {
+            switches,
+            was_kicking: false,
+        }
+    }
+
+    /// Flips any switch the player kicks, along with the object it controls.
+    ///
+    /// # Arguments
+    ///
+    /// * `game_state` - Provides the player's position and kick, and records switch states.
+    pub fn update(&mut self, game_state: &mut GameState) {
+        let kicked = game_state.is_kicking() && !self.was_kicking;
+        self.was_kicking = game_state.is_kicking();
+        if !kicked {
+            return;
+        }
+
+        let player = game_state.bounds();
+        for switch in &self.switches {
+            if !switch.bounds.intersects(&player) {
+                continue;
+            }
+            let on = !game_state.is_object_on(&switch.id);
+            game_state.set_object_on(&switch.id, on);
+            if let Some(target) = &switch.target {
+                game_state.set_object_on(target, on);
+            }
+            info!("Switch {} turned {}", switch.id, if on { "on" } else { "off" });
+        }
+    }
+}
@@ -0,0 +1,64 @@
+// time_scale.rs
+
+/// Scales the passage of game time, for hitstop and changes in game speed.
+pub struct TimeScale {
+    /// Factor applied to time outside of hitstop.
+    speed: f32,
+    /// Real time left in the current hitstop, in seconds.
+    hitstop: f32,
+}
+
+impl TimeScale {
+    /// Creates a time scale running at normal speed.
+    pub fn new() -> Self {
+        Self {
+            speed: 1.0,
+            hitstop: 0.0,
+        }
+    }
+
+    /// Freezes game time briefly, to give an impact weight.
+    ///
+    /// # Arguments
+    ///
+    /// * `duration` - How long to freeze for, in real seconds.
+    pub fn hitstop(&mut self, duration: f32) {
+        self.hitstop = self.hitstop.max(duration);
+    }
+
+    /// Returns the speed of game time outside of hitstop.
+    pub fn speed(&self) -> f32 {
+        self.speed
+    }
+
+    /// Sets the speed of game time outside of hitstop; below 1.0 is slow motion.
+    pub fn set_speed(&mut self, speed: f32) {
+        self.speed = speed.max(0.0);
+    }
+
+    /// Returns the factor game time is currently scaled by.
+    pub fn scale(&self) -> f32 {
+        if self.hitstop > 0.0 {
+            0.0
+        } else {
+            self.speed
+        }
+    }
+
+    /// Advances any hitstop by real time and returns the matching game time step.
+    ///
+    /// # Arguments
+    ///
+    /// * `real_delta` - Real time elapsed since the last frame.
+    pub fn update(&mut self, real_delta: f32) -> f32 {
+        let delta_time = real_delta * self.scale();
+        self.hitstop = (self.hitstop - real_delta).max(0.0);
+        delta_time
+    }
+}
+
+impl Default for TimeScale {
+    fn default() -> Self {
+        Self::new()
+    }
+}
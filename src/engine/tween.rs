@@ -0,0 +1,251 @@
+// tween.rs
+
+/// An easing curve, mapping progress through a span of time from 0.0..1.0 to how
+/// far the animated value has gone, from 0.0 to 1.0.
+pub type Easing = fn(f32) -> f32;
+
+/// Linearly interpolates from `from` to `to` as `t` goes from 0.0 to 1.0.
+pub fn lerp(from: f32, to: f32, t: f32) -> f32 {
+    from + (to - from) * t
+}
+
+/// Returns how far `elapsed` is through a span of time, clamped to 0.0..1.0.
+///
+/// # Arguments
+///
+/// * `elapsed` - Time since the animation started.
+/// * `start` - When the span begins.
+/// * `duration` - Length of the span.
+pub fn progress(elapsed: f32, start: f32, duration: f32) -> f32 {
+    if duration <= 0.0 {
+        return if elapsed >= start { 1.0 } else { 0.0 };
+    }
+    ((elapsed - start) / duration).clamp(0.0, 1.0)
+}
+
+/// Moves at a constant rate.
+pub fn linear(t: f32) -> f32 {
+    t.clamp(0.0, 1.0)
+}
+
+/// Eases in and out along a smoothstep curve, starting and stopping gently.
+pub fn ease_in_out(t: f32) -> f32 {
+    let t = t.clamp(0.0, 1.0);
+    t * t * (3.0 - 2.0 * t)
+}
+
+/// Starts slowly and accelerates into the end.
+pub fn ease_in_cubic(t: f32) -> f32 {
+    let t = t.clamp(0.0, 1.0);
+    t * t * t
+}
+
+/// Starts fast and decelerates into the end.
+pub fn ease_out_cubic(t: f32) -> f32 {
+    let inverse = 1.0 - t.clamp(0.0, 1.0);
+    1.0 - inverse * inverse * inverse
+}
+
+/// A value that can be interpolated, and so tweened.
+pub trait Lerp: Copy {
+    /// Interpolates from `self` to `to` as `t` goes from 0.0 to 1.0.
+    fn lerp(self, to: Self, t: f32) -> Self;
+}
+
+impl Lerp for f32 {
+    fn lerp(self, to: Self, t: f32) -> Self {
+        lerp(self, to, t)
+    }
+}
+
+/// A position or size.
+impl Lerp for (f32, f32) {
+    fn lerp(self, to: Self, t: f32) -> Self {
+        (lerp(self.0, to.0, t), lerp(self.1, to.1, t))
+    }
+}
+
+/// An RGBA color, interpolated channel by channel.
+impl Lerp for [f32; 4] {
+    fn lerp(self, to: Self, t: f32) -> Self {
+        [0, 1, 2, 3].map(|channel| lerp(self[channel], to[channel], t))
+    }
+}
+
+/// Counts time up to a duration, once or over and over.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Timer {
+    duration: f32,
+    /// Time counted since the timer started, or since it last came round if it repeats.
+    elapsed: f32,
+    repeating: bool,
+    /// Whether a timer that runs once has reached its duration, on a tick.
+    finished: bool,
+}
+
+impl Timer {
+    /// Creates a timer that runs once, stopping at its duration.
+    ///
+    /// # Arguments
+    ///
+    /// * `duration` - Time to count up to, in seconds.
+    pub fn new(duration: f32) -> Self {
+        Self {
+            duration,
+            elapsed: 0.0,
+            repeating: false,
+            finished: false,
+        }
+    }
+
+    /// Creates a timer that starts over each time it reaches its duration.
+    ///
+    /// # Arguments
+    ///
+    /// * `duration` - Time each round lasts, in seconds.
+    pub fn repeating(duration: f32) -> Self {
+        Self {
+            repeating: true,
+            ..Self::new(duration)
+        }
+    }
+
+    /// Starts the timer partway through, such as to pick up where another left off.
+    ///
+    /// # Arguments
+    ///
+    /// * `elapsed` - Time already counted, in seconds.
+    pub fn with_elapsed(mut self, elapsed: f32) -> Self {
+        self.elapsed = elapsed.clamp(0.0, self.duration.max(0.0));
+        self
+    }
+
+    /// Counts time, wrapping round if the timer repeats.
+    ///
+    /// # Arguments
+    ///
+    /// * `delta_time` - Time elapsed since the last frame.
+    ///
+    /// # Returns
+    ///
+    /// `true` if the timer reached its duration during this tick.
+    pub fn tick(&mut self, delta_time: f32) -> bool {
+        if self.finished {
+            return false;
+        }
+        self.elapsed += delta_time;
+        if self.elapsed < self.duration {
+            return false;
+        }
+        if self.repeating && self.duration > 0.0 {
+            self.elapsed %= self.duration;
+        } else {
+            self.elapsed = self.duration.max(0.0);
+            self.finished = !self.repeating;
+        }
+        true
+    }
+
+    /// Returns the time counted, in seconds.
+    pub fn elapsed(&self) -> f32 {
+        self.elapsed
+    }
+
+    /// Returns the time each round lasts, in seconds.
+    pub fn duration(&self) -> f32 {
+        self.duration
+    }
+
+    /// Returns how far through the duration the timer is, from 0.0 to 1.0.
+    pub fn progress(&self) -> f32 {
+        progress(self.elapsed, 0.0, self.duration)
+    }
+
+    /// Returns `true` once a timer that runs once has reached its duration, even one
+    /// of no duration only once it has been ticked; one that repeats never finishes.
+    pub fn finished(&self) -> bool {
+        self.finished
+    }
+
+    /// Starts the timer over from zero.
+    pub fn reset(&mut self) {
+        self.elapsed = 0.0;
+        self.finished = false;
+    }
+}
+
+/// Animates a value from one end to another over a span of time along an easing
+/// curve, optionally after a delay, calling back once when it gets there.
+pub struct Tween<T: Lerp> {
+    from: T,
+    to: T,
+    /// Time before the value starts moving, in seconds.
+    delay: f32,
+    /// Counts the delay and the movement together.
+    timer: Timer,
+    easing: Easing,
+    on_complete: Option<Box<dyn FnOnce()>>,
+}
+
+impl<T: Lerp> Tween<T> {
+    /// Creates a tween starting at `from` straight away.
+    ///
+    /// # Arguments
+    ///
+    /// * `from` - The value at the start.
+    /// * `to` - The value at the end.
+    /// * `duration` - Time to move from one to the other, in seconds.
+    /// * `easing` - The curve the value moves along.
+    pub fn new(from: T, to: T, duration: f32, easing: Easing) -> Self {
+        Self {
+            from,
+            to,
+            delay: 0.0,
+            timer: Timer::new(duration),
+            easing,
+            on_complete: None,
+        }
+    }
+
+    /// Holds the value at `from` for a while before it starts moving.
+    ///
+    /// # Arguments
+    ///
+    /// * `delay` - Time to wait, in seconds.
+    pub fn delayed(mut self, delay: f32) -> Self {
+        self.timer = Timer::new(delay + self.timer.duration() - self.delay);
+        self.delay = delay;
+        self
+    }
+
+    /// Calls `callback` once, on the update the tween reaches its end.
+    pub fn on_complete(mut self, callback: impl FnOnce() + 'static) -> Self {
+        self.on_complete = Some(Box::new(callback));
+        self
+    }
+
+    /// Advances the tween, calling back if it reaches its end.
+    ///
+    /// # Arguments
+    ///
+    /// * `delta_time` - Time elapsed since the last frame.
+    pub fn update(&mut self, delta_time: f32) {
+        if self.timer.tick(delta_time) {
+            if let Some(callback) = self.on_complete.take() {
+                callback();
+            }
+        }
+    }
+
+    /// Returns the value at the current point of the tween.
+    pub fn value(&self) -> T {
+        let duration = self.timer.duration() - self.delay;
+        let t = (self.easing)(progress(self.timer.elapsed(), self.delay, duration));
+        self.from.lerp(self.to, t)
+    }
+
+    /// Returns `true` once the tween has reached its end.
+    pub fn finished(&self) -> bool {
+        self.timer.finished()
+    }
+}
@@ -0,0 +1,62 @@
+// banner.rs
+use crate::engine::constants::{BANNER_FADE_TIME, BANNER_HOLD_TIME, BANNER_SLIDE_TIME};
+use crate::engine::level::Level;
+use crate::engine::tween::{ease_in_out, ease_out_cubic, Tween};
+
+/// Where the banner starts sliding in from, in screen coordinates.
+const SLIDE_START_X: f32 = -2.0;
+
+/// A banner announcing the level as it starts.
+///
+/// It slides in from the left, holds for a moment, then fades out. The text
+/// comes from the map's `name` property, with its `number` property shown
+/// above it as "LEVEL n".
+pub struct LevelBanner {
+    pub title: String,
+    pub subtitle: Option<String>,
+    /// Horizontal offset of the banner, sliding in.
+    slide: Tween<f32>,
+    /// Opacity of the banner, fading out after the hold.
+    fade: Tween<f32>,
+}
+
+/// How the banner is drawn this frame.
+pub struct BannerFrame {
+    /// Horizontal offset of the banner from the center of the screen.
+    pub offset_x: f32,
+    /// Opacity of the banner, from 0.0 to 1.0.
+    pub alpha: f32,
+}
+
+impl LevelBanner {
+    /// Creates the banner for a level, or `None` if the level has no name.
+    pub fn from_level(level: &Level) -> Option<Self> {
+        Some(Self {
+            title: level.property("name")?.to_string(),
+            subtitle: level.property("number").map(|number| format!("LEVEL {}", number)),
+            slide: Tween::new(SLIDE_START_X, 0.0, BANNER_SLIDE_TIME, ease_out_cubic),
+            fade: Tween::new(1.0, 0.0, BANNER_FADE_TIME, ease_in_out).delayed(BANNER_SLIDE_TIME + BANNER_HOLD_TIME),
+        })
+    }
+
+    /// Advances the banner's animation.
+    ///
+    /// # Arguments
+    ///
+    /// * `delta_time` - Time elapsed since the last frame.
+    pub fn update(&mut self, delta_time: f32) {
+        self.slide.update(delta_time);
+        self.fade.update(delta_time);
+    }
+
+    /// Returns how to draw the banner, or `None` once it has faded out.
+    pub fn frame(&self) -> Option<BannerFrame> {
+        if self.fade.finished() {
+            return None;
+        }
+        Some(BannerFrame {
+            offset_x: self.slide.value(),
+            alpha: self.fade.value(),
+        })
+    }
+}
@@ -0,0 +1,66 @@
+// damage_flash.rs
+use crate::engine::config::tuning;
+use crate::engine::constants::DAMAGE_FLASH_TIME;
+use crate::engine::ui::flash_limiter::FlashLimiter;
+use crate::engine::tween::{ease_in_out, Tween};
+
+/// A red vignette that flashes around the screen edges when the player is hurt.
+///
+/// It can be turned off, or toned down, with the tuned `damage_flash` and
+/// `damage_flash_intensity`, and goes through the `FlashLimiter` like every
+/// full-screen flash.
+pub struct DamageFlash {
+    /// Strength of the flash fading out, or `None` if it isn't showing.
+    fade: Option<Tween<f32>>,
+}
+
+impl DamageFlash {
+    /// Creates the flash, initially not showing.
+    pub fn new() -> Self {
+        Self { fade: None }
+    }
+
+    /// Starts the flash over at full strength, unless it has been turned off or the
+    /// limiter holds it back.
+    ///
+    /// # Arguments
+    ///
+    /// * `limiter` - Counts the flash against the safe rate of flashes.
+    pub fn trigger(&mut self, limiter: &mut FlashLimiter) {
+        if tuning().damage_flash && limiter.allow() {
+            self.fade = Some(Tween::new(1.0, 0.0, DAMAGE_FLASH_TIME, ease_in_out));
+        }
+    }
+
+    /// Fades the flash out.
+    ///
+    /// # Arguments
+    ///
+    /// * `delta_time` - Real time elapsed since the last frame, so the flash fades
+    ///   at the same pace whatever the game speed.
+    pub fn update(&mut self, delta_time: f32) {
+        if let Some(fade) = self.fade.as_mut() {
+            fade.update(delta_time);
+        }
+        if self.fade.as_ref().is_some_and(Tween::finished) {
+            self.fade = None;
+        }
+    }
+
+    /// Returns the opacity of the flash at the screen edges, from 0.0 to 1.0.
+    ///
+    /// # Arguments
+    ///
+    /// * `limiter` - Caps the flash's strength.
+    pub fn alpha(&self, limiter: &FlashLimiter) -> f32 {
+        self.fade.as_ref().map_or(0.0, |fade| {
+            limiter.limit(fade.value() * tuning().damage_flash_intensity.clamp(0.0, 1.0))
+        })
+    }
+}
+
+impl Default for DamageFlash {
+    fn default() -> Self {
+        Self::new()
+    }
+}
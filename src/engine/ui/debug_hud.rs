@@ -0,0 +1,143 @@
+// debug_hud.rs
+use crate::engine::stats::{EngineStats, FrameTimings, FRAME_BUDGET};
+
+/// How often the frame rate and frame time shown are refreshed, in seconds.
+const REFRESH_TIME: f32 = 0.5;
+
+/// Times of each span summed over a window, with how many frames each was timed on.
+type SpanTotals = Vec<(&'static str, f32, u32)>;
+
+/// A toggleable readout of the frame rate, frame time, engine stats, where the frame's
+/// time went on the CPU and GPU, and the GPU in use.
+///
+/// The frame rate and times are averaged over short windows so the
+/// numbers stay readable instead of flickering every frame.
+pub struct DebugHud {
+    visible: bool,
+    /// Frames and time counted in the current window.
+    window_frames: u32,
+    window_time: f32,
+    fps: f32,
+    frame_time: f32,
+    /// CPU and GPU span times summed in the current window, then averaged over the last.
+    window_cpu: SpanTotals,
+    window_gpu: SpanTotals,
+    cpu_times: Vec<(&'static str, f32)>,
+    gpu_times: Vec<(&'static str, f32)>,
+    stats: EngineStats,
+    /// The GPU and graphics API rendering the game, e.g. for bug reports.
+    adapter: String,
+}
+
+impl DebugHud {
+    /// Creates the HUD, initially hidden.
+    pub fn new() -> Self {
+        Self {
+            visible: false,
+            window_frames: 0,
+            window_time: 0.0,
+            fps: 0.0,
+            frame_time: 0.0,
+            window_cpu: Vec::new(),
+            window_gpu: Vec::new(),
+            cpu_times: Vec::new(),
+            gpu_times: Vec::new(),
+            stats: EngineStats::default(),
+            adapter: String::new(),
+        }
+    }
+
+    /// Sets the description of the GPU shown under the stats.
+    ///
+    /// # Arguments
+    ///
+    /// * `info` - The adapter the renderer is using.
+    pub fn set_adapter(&mut self, info: &wgpu::AdapterInfo) {
+        self.adapter = format!("GPU {} ({:?})\nDRIVER {} {}", info.name, info.backend, info.driver, info.driver_info)
+            .trim_end()
+            .to_uppercase();
+    }
+
+    /// Shows or hides the HUD.
+    pub fn toggle(&mut self) {
+        self.visible = !self.visible;
+    }
+
+    /// Returns whether the HUD is shown.
+    pub fn visible(&self) -> bool {
+        self.visible
+    }
+
+    /// Records a finished frame.
+    ///
+    /// # Arguments
+    ///
+    /// * `frame_time` - Real time the frame took, including any frame limiting, in seconds.
+    /// * `stats` - The work the engine did for the frame.
+    /// * `timings` - Where the frame's time went, and the GPU's times if any were read back.
+    pub fn record_frame(&mut self, frame_time: f32, stats: EngineStats, timings: &FrameTimings) {
+        self.stats = stats;
+        self.window_frames += 1;
+        self.window_time += frame_time;
+        add_spans(&mut self.window_cpu, &timings.cpu);
+        add_spans(&mut self.window_gpu, &timings.gpu);
+        if self.window_time >= REFRESH_TIME {
+            self.fps = self.window_frames as f32 / self.window_time;
+            self.frame_time = self.window_time / self.window_frames as f32;
+            self.cpu_times = average_spans(&mut self.window_cpu);
+            // GPU times only arrive on some frames, so the last ones stay up until more do
+            if !self.window_gpu.is_empty() {
+                self.gpu_times = average_spans(&mut self.window_gpu);
+            }
+            self.window_frames = 0;
+            self.window_time = 0.0;
+        }
+    }
+
+    /// Returns the lines of text the HUD shows, from top to bottom.
+    pub fn lines(&self) -> Vec<String> {
+        let mut lines = vec![
+            format!("FPS {:.0}", self.fps),
+            format!("FRAME {:.2} MS", self.frame_time * 1000.0),
+            format!("INSTANCES {}", self.stats.instances),
+            format!("DRAW CALLS {}", self.stats.draw_calls),
+            format!("UPLOAD {:.1} KB", self.stats.bytes_uploaded as f32 / 1024.0),
+            format!("ENTITIES {}", self.stats.entities_updated),
+            format!("COLLISIONS {}", self.stats.collision_tests),
+            format!("VOICES {}", self.stats.audio_voices),
+        ];
+        let over_budget = self.stats.over_budget(&FRAME_BUDGET);
+        if !over_budget.is_empty() {
+            lines.push(format!("OVER BUDGET {}", over_budget.join(", ")));
+        }
+        for (unit, times) in [("CPU", &self.cpu_times), ("GPU", &self.gpu_times)] {
+            lines.extend(times.iter().map(|(name, time)| format!("{} {} {:.2} MS", unit, name, time * 1000.0)));
+        }
+        lines.extend(self.adapter.lines().map(str::to_string));
+        lines
+    }
+}
+
+/// Adds a frame's span times to a window's totals, by name.
+fn add_spans(totals: &mut SpanTotals, spans: &[(&'static str, f32)]) {
+    for &(name, time) in spans {
+        match totals.iter_mut().find(|(total_name, _, _)| *total_name == name) {
+            Some((_, total, frames)) => {
+                *total += time;
+                *frames += 1;
+            }
+            None => totals.push((name, time, 1)),
+        }
+    }
+}
+
+/// Returns the average time of each span in a window's totals, emptying them for the next.
+fn average_spans(totals: &mut SpanTotals) -> Vec<(&'static str, f32)> {
+    totals.drain(..).map(|(name, total, frames)| (name, total / frames as f32)).collect()
+}
+
+impl Default for DebugHud {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,68 @@
+// flash_limiter.rs
+use crate::engine::config::tuning;
+use crate::engine::constants::{SAFE_FLASH_INTENSITY, SAFE_FLASHES_PER_SECOND};
+
+/// Keeps full-screen flashes, such as the damage flash, safe for players sensitive
+/// to flashing light.
+///
+/// Every effect that flashes the whole screen asks the limiter before starting a
+/// flash and has its strength capped by it, so the limits hold however many effects
+/// flash at once. With the tuned `photosensitive_mode` on, no more than
+/// `SAFE_FLASHES_PER_SECOND` flashes start in any second, and none is stronger
+/// than `SAFE_FLASH_INTENSITY`; with it off, flashes pass through untouched.
+pub struct FlashLimiter {
+    /// Real time since each flash started within the last second, oldest first.
+    recent: Vec<f32>,
+}
+
+impl FlashLimiter {
+    /// Creates the limiter with no flashes yet.
+    pub fn new() -> Self {
+        Self { recent: Vec::new() }
+    }
+
+    /// Asks to start a flash, counting it if allowed.
+    ///
+    /// # Returns
+    ///
+    /// Whether the flash may start; `false` if too many started in the last second.
+    pub fn allow(&mut self) -> bool {
+        if tuning().photosensitive_mode && self.recent.len() >= SAFE_FLASHES_PER_SECOND {
+            return false;
+        }
+        self.recent.push(0.0);
+        true
+    }
+
+    /// Caps a flash's opacity.
+    ///
+    /// # Arguments
+    ///
+    /// * `alpha` - The opacity the effect would flash at, from 0.0 to 1.0.
+    pub fn limit(&self, alpha: f32) -> f32 {
+        if tuning().photosensitive_mode {
+            alpha.min(SAFE_FLASH_INTENSITY)
+        } else {
+            alpha
+        }
+    }
+
+    /// Forgets flashes that started over a second ago.
+    ///
+    /// # Arguments
+    ///
+    /// * `delta_time` - Real time elapsed since the last frame, so the limits hold
+    ///   whatever the game speed.
+    pub fn update(&mut self, delta_time: f32) {
+        for age in &mut self.recent {
+            *age += delta_time;
+        }
+        self.recent.retain(|age| *age < 1.0);
+    }
+}
+
+impl Default for FlashLimiter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,179 @@
+// immediate.rs
+use crate::engine::input::InputHandler;
+use crate::engine::renderer::instance::InstanceData;
+use crate::engine::renderer::text::{TextAlign, TextRenderer, TextStyle};
+use crate::engine::renderer::Renderer;
+use winit::event::MouseButton;
+
+/// Fill of a button that isn't hovered or selected.
+const BUTTON_COLOR: [f32; 4] = [0.2, 0.2, 0.28, 0.95];
+
+/// Fill of a hovered or selected button.
+const BUTTON_HIGHLIGHT_COLOR: [f32; 4] = [0.35, 0.3, 0.15, 0.95];
+
+/// Label color of a button that can be clicked.
+const BUTTON_TEXT_COLOR: [f32; 4] = [0.95, 0.95, 0.95, 1.0];
+
+/// Label color of a disabled button.
+const BUTTON_DISABLED_TEXT_COLOR: [f32; 4] = [0.5, 0.5, 0.5, 1.0];
+
+/// How far in front of a widget's background its label is drawn.
+const LABEL_DEPTH_OFFSET: f32 = 0.005;
+
+/// A rectangle in screen coordinates (-1..1, y up), given by its center and size.
+#[derive(Clone, Copy, Debug)]
+pub struct Rect {
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+}
+
+impl Rect {
+    /// Creates a rectangle from its center point and full size.
+    pub fn new(x: f32, y: f32, width: f32, height: f32) -> Self {
+        Self { x, y, width, height }
+    }
+
+    /// Returns `true` if the point lies inside the rectangle.
+    pub fn contains(&self, (x, y): (f32, f32)) -> bool {
+        (x - self.x).abs() <= self.width / 2.0 && (y - self.y).abs() <= self.height / 2.0
+    }
+}
+
+/// The mouse as seen by the UI for one frame.
+pub struct Mouse {
+    position: Option<(f32, f32)>,
+    held: bool,
+    clicked: bool,
+}
+
+impl Mouse {
+    /// Creates the mouse state, with the cursor outside the window.
+    pub fn new() -> Self {
+        Self {
+            position: None,
+            held: false,
+            clicked: false,
+        }
+    }
+
+    /// Reads this frame's cursor position and left button from the input handler.
+    ///
+    /// A click is registered only on the frame the button goes down.
+    ///
+    /// # Arguments
+    ///
+    /// * `input_handler` - Provides the cursor position and mouse buttons.
+    pub fn update(&mut self, input_handler: &InputHandler) {
+        let held = input_handler.is_mouse_pressed(MouseButton::Left);
+        self.clicked = held && !self.held;
+        self.held = held;
+        self.position = input_handler.cursor_position();
+    }
+
+    /// Returns `true` if the cursor is over the rectangle.
+    fn hovers(&self, rect: &Rect) -> bool {
+        self.position.is_some_and(|position| rect.contains(position))
+    }
+}
+
+impl Default for Mouse {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// A clickable button.
+pub struct Button<'a> {
+    pub label: &'a str,
+    pub rect: Rect,
+    pub z: f32,
+    /// Whether the button is highlighted as if hovered, e.g. when chosen with the keyboard.
+    pub selected: bool,
+    /// Whether the button can be clicked; disabled buttons are greyed out.
+    pub enabled: bool,
+}
+
+/// An immediate-mode UI drawn in screen space over the world.
+///
+/// Widgets are declared afresh every frame, and each call both draws the widget
+/// and reports how the mouse interacted with it, so menus and HUDs are plain code
+/// rather than retained widget trees. The collected instances are drawn with the
+/// standard pipeline: `quads` with the white texture, then `text` with the font.
+pub struct Ui<'a> {
+    text_renderer: &'a TextRenderer,
+    mouse: &'a Mouse,
+    quads: Vec<InstanceData>,
+    text: Vec<InstanceData>,
+}
+
+impl<'a> Ui<'a> {
+    /// Starts building a frame's UI.
+    ///
+    /// # Arguments
+    ///
+    /// * `text_renderer` - Lays out labels.
+    /// * `mouse` - This frame's mouse state, for hit-testing.
+    pub fn new(text_renderer: &'a TextRenderer, mouse: &'a Mouse) -> Self {
+        Self {
+            text_renderer,
+            mouse,
+            quads: Vec::new(),
+            text: Vec::new(),
+        }
+    }
+
+    /// Draws a solid colored rectangle.
+    pub fn panel(&mut self, rect: Rect, z: f32, color: [f32; 4]) {
+        self.quads.push(InstanceData {
+            transform: Renderer::create_transform_matrix(rect.x, rect.y, z, rect.width, rect.height),
+            sprite_index: 0.0,
+            layer: 0.0,
+            sprite_size: [0.0, 0.0],
+            uv_offset: [0.0, 0.0],
+            uv_scale: [1.0, 1.0],
+            color,
+        });
+    }
+
+    /// Draws text; see `TextRenderer::layout` for how it is placed.
+    pub fn label(&mut self, text: &str, anchor: (f32, f32), style: &TextStyle) {
+        self.text.extend(self.text_renderer.layout(text, anchor, style));
+    }
+
+    /// Draws a button with its label centered on it.
+    ///
+    /// # Returns
+    ///
+    /// `true` if the button is enabled and was clicked this frame.
+    pub fn button(&mut self, button: &Button) -> bool {
+        let hovered = self.mouse.hovers(&button.rect);
+        let color = if button.enabled && (hovered || button.selected) {
+            BUTTON_HIGHLIGHT_COLOR
+        } else {
+            BUTTON_COLOR
+        };
+        self.panel(button.rect, button.z, color);
+
+        let style = TextStyle {
+            height: button.rect.height * 0.5,
+            color: if button.enabled { BUTTON_TEXT_COLOR } else { BUTTON_DISABLED_TEXT_COLOR },
+            z: button.z - LABEL_DEPTH_OFFSET,
+            align: TextAlign::Center,
+        };
+        self.label(button.label, (button.rect.x, button.rect.y), &style);
+
+        button.enabled && hovered && self.mouse.clicked
+    }
+
+    /// Returns the text renderer, for measuring text.
+    pub fn text_renderer(&self) -> &TextRenderer {
+        self.text_renderer
+    }
+
+    /// Finishes the frame's UI, returning its solid quads and text glyphs.
+    pub fn finish(self) -> (Vec<InstanceData>, Vec<InstanceData>) {
+        (self.quads, self.text)
+    }
+}
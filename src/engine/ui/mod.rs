@@ -0,0 +1,13 @@
+// src/engine/ui/mod.rs
+
+pub mod banner;
+pub mod damage_flash;
+pub mod debug_hud;
+pub mod flash_limiter;
+pub mod immediate;
+
+pub use banner::LevelBanner;
+pub use damage_flash::DamageFlash;
+pub use debug_hud::DebugHud;
+pub use flash_limiter::FlashLimiter;
+pub use immediate::{Button, Mouse, Rect, Ui};
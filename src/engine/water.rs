@@ -0,0 +1,72 @@
+// water.rs
+use crate::engine::collision::Aabb;
+use crate::engine::renderer::tile::TileMap;
+
+/// Color and opacity of water, passed to the water material as the instance tint.
+pub const WATER_TINT: [f32; 4] = [0.2, 0.45, 0.85, 0.55];
+
+/// Tint applied to sprites whose center is underwater.
+pub const SUBMERGED_TINT: [f32; 4] = [0.6, 0.8, 1.0, 0.75];
+
+/// A single cell of water.
+pub struct WaterCell {
+    /// Center of the cell in world coordinates.
+    pub position: (f32, f32),
+    /// Whether this cell is on the surface, with no water directly above it.
+    pub surface: bool,
+    /// Height of the surface of the water this cell is in, the top of its column.
+    pub surface_y: f32,
+}
+
+/// The water cells of a level, used for rendering and for submersion checks.
+pub struct Water {
+    pub cells: Vec<WaterCell>,
+    pub cell_width: f32,
+    pub cell_height: f32,
+}
+
+impl Water {
+    /// Builds the water cells from a level's water tile map.
+    ///
+    /// A cell is on the surface when no other water cell sits directly above it.
+    pub fn from_tile_map(tile_map: &TileMap) -> Self {
+        let (width, height) = (tile_map.tile_width, tile_map.tile_height);
+        let covered = |x: f32, y: f32| {
+            tile_map.tiles().any(|other| {
+                (other.position.0 - x).abs() < width / 2.0 && (other.position.1 - (y + height)).abs() < height / 2.0
+            })
+        };
+        let cells = tile_map
+            .tiles()
+            .map(|tile| {
+                let (x, y) = tile.position;
+
+                // Climb the column to the cell on the surface
+                let mut top = y;
+                while covered(x, top) {
+                    top += height;
+                }
+                WaterCell {
+                    position: (x, y),
+                    surface: top == y,
+                    surface_y: top + height / 2.0,
+                }
+            })
+            .collect();
+
+        Self {
+            cells,
+            cell_width: width,
+            cell_height: height,
+        }
+    }
+
+    /// Returns `true` if the given point is inside any water cell.
+    pub fn contains(&self, x: f32, y: f32) -> bool {
+        let point = Aabb::from_center(x, y, 0.0, 0.0);
+        self.cells.iter().any(|cell| {
+            Aabb::from_center(cell.position.0, cell.position.1, self.cell_width, self.cell_height)
+                .intersects(&point)
+        })
+    }
+}
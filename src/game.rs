@@ -0,0 +1,151 @@
+// game.rs
+use crate::app::AssetManifest;
+use crate::engine::assets::{load_wav, Sound};
+use crate::engine::audio::Audio;
+use crate::engine::effects::Effects;
+use crate::engine::level::Level;
+use crate::engine::projectile::Target;
+use crate::engine::ui::Ui;
+use crate::engine::{Camera, GameState, InputHandler};
+use std::sync::Arc;
+
+/// Something that happened in the game this frame, for the game's own reactions
+/// such as effects and sounds.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum GameEvent {
+    /// The player appeared at the start of a level, or back at a checkpoint.
+    Spawned { position: (f32, f32) },
+    /// The player lost a life where they stood, to a hazard, a projectile, or drowning.
+    LifeLost { position: (f32, f32) },
+    /// The player bounced off a jelly platform.
+    Bounced,
+    /// A projectile hit something.
+    ProjectileHit { position: (f32, f32), target: Target },
+    /// The player burst through the water with a swim-dash, from where they started it.
+    SwimDashed { position: (f32, f32) },
+    /// The player's breath is running low underwater, with this many whole seconds
+    /// left. Sent once each second until they breathe or drown.
+    OxygenLow { seconds_left: u32 },
+    /// The player breathed again where they are, at the surface or from air bubbles.
+    Breathed { position: (f32, f32) },
+    /// The player's animation moved onto a sprite frame with an event attached, such
+    /// as a footstep, named as in the animation events file, where the player is.
+    AnimationEvent { name: &'static str, frame: usize, position: (f32, f32) },
+    /// More of a level objective was done, which has this index in the level's objectives.
+    ObjectiveProgressed { objective: usize, progress: u32, required: u32 },
+    /// A level objective was finished.
+    ObjectiveCompleted { objective: usize },
+    /// The player opened a sign's panel, which has this index in the level's signs,
+    /// where the sign is.
+    SignRead { sign: usize, position: (f32, f32) },
+}
+
+/// What a game can reach while it starts up, before the first scene is shown.
+pub struct Engine<'a> {
+    /// Plays sounds and music.
+    pub audio: &'a Audio,
+    /// The player, with no level loaded yet.
+    pub game_state: &'a mut GameState,
+}
+
+impl Engine<'_> {
+    /// Loads a WAV sound to play later.
+    ///
+    /// # Returns
+    ///
+    /// The sound, or `None` with a warning if it couldn't be loaded, in which case
+    /// the game carries on without it.
+    pub fn load_sound(&self, path: &str) -> Option<Arc<Sound>> {
+        match load_wav(path) {
+            Ok(sound) => Some(Arc::new(sound)),
+            Err(e) => {
+                log::warn!("Could not load sound '{}': {}", path, e);
+                None
+            }
+        }
+    }
+}
+
+/// What a game can reach while the engine updates it.
+pub struct Ctx<'a> {
+    /// The level being played, or the menu's backdrop.
+    pub level: &'a Level,
+    /// The player and their progress, to read or change.
+    pub game_state: &'a mut GameState,
+    /// The input the player is moved by; locked during cutscenes.
+    pub input: &'a InputHandler,
+    pub camera: &'a mut Camera,
+    /// Plays the particle effects named in the effects config.
+    pub effects: &'a mut Effects,
+    /// Plays sounds and music.
+    pub audio: &'a Audio,
+}
+
+/// A game built on the engine, driven by the game loop.
+///
+/// The engine runs the levels, the player, and the menus; the game names its own
+/// levels, player, and music, and adds its own logic through the other hooks, each of
+/// which does nothing unless implemented.
+pub trait Game {
+    /// Returns where the game finds the files it reads at startup: its levels, its
+    /// player's sprite sheet and texture, its music, and the engine's configs.
+    fn assets(&self) -> AssetManifest;
+
+    /// Sets the game up, once, after the window opens and before the first scene.
+    fn init(&mut self, _engine: &mut Engine) {}
+
+    /// Updates the game by a frame, after the engine has moved the player and the
+    /// level's features. Only called while a level is being played, not while the
+    /// game is paused, rewinding, or fading between scenes.
+    ///
+    /// # Arguments
+    ///
+    /// * `ctx` - The level, the player, and the engine's effects and audio.
+    /// * `delta_time` - Game time the frame took, in seconds; 0.0 during hitstop.
+    fn update(&mut self, _ctx: &mut Ctx, _delta_time: f32) {}
+
+    /// Draws the game's own overlay over the engine's, such as extra HUD elements,
+    /// every frame, including on the menu.
+    ///
+    /// # Arguments
+    ///
+    /// * `ui` - The UI the engine's overlay was drawn with, in screen coordinates.
+    /// * `game_state` - The player and their progress.
+    /// * `camera` - The view of the world, to place things drawn over it.
+    fn render(&mut self, _ui: &mut Ui, _game_state: &GameState, _camera: &Camera) {}
+
+    /// Reacts to something that happened this frame. Events are sent in the order
+    /// they happened, after the frame's update.
+    fn on_event(&mut self, _ctx: &mut Ctx, _event: GameEvent) {}
+}
+
+/// A self-contained piece of logic added to the game loop with `App::with_system`,
+/// such as a feature shared between games.
+///
+/// Systems have the same hooks as the game, less its assets, and are run right after
+/// it, in the order they were added. Each hook does nothing unless implemented.
+pub trait System {
+    /// Sets the system up, once, right after the game's own `init`.
+    fn init(&mut self, _engine: &mut Engine) {}
+
+    /// Updates the system by a frame, right after the game. Only called while a level
+    /// is being played, as with `Game::update`.
+    ///
+    /// # Arguments
+    ///
+    /// * `ctx` - The level, the player, and the engine's effects and audio.
+    /// * `delta_time` - Game time the frame took, in seconds; 0.0 during hitstop.
+    fn update(&mut self, _ctx: &mut Ctx, _delta_time: f32) {}
+
+    /// Draws the system's own overlay over the game's, every frame.
+    ///
+    /// # Arguments
+    ///
+    /// * `ui` - The UI the overlay was drawn with, in screen coordinates.
+    /// * `game_state` - The player and their progress.
+    /// * `camera` - The view of the world, to place things drawn over it.
+    fn render(&mut self, _ui: &mut Ui, _game_state: &GameState, _camera: &Camera) {}
+
+    /// Reacts to something that happened this frame, after the game has.
+    fn on_event(&mut self, _ctx: &mut Ctx, _event: GameEvent) {}
+}
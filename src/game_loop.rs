@@ -1,300 +1,3781 @@
-use crate::engine::{GameState, InputHandler, Renderer};
-use crate::engine::renderer::tile::TileMap;
+use crate::app::{App, AssetManifest};
+use crate::game::{Ctx, Engine, Game, GameEvent, System};
+use crate::engine::{AppState, Camera, GameState, InputHandler, Renderer, RoomManager, SecretAreas, TileMap, SPRITE_WIDTH, SPRITE_HEIGHT};
+use crate::engine::renderer::tile::Tile;
+use crate::engine::oxygen::{AirBubbles, Breath, Oxygen};
+use crate::engine::water::{Water, SUBMERGED_TINT, WATER_TINT};
+use crate::engine::action::{key_label, Action, ActionMap};
+use crate::engine::ambience::{Ambience, AmbienceZones};
+use crate::engine::animation_event::{animation_events, load_animation_events, set_animation_events};
+use crate::engine::assets::{load_aseprite_sheet, load_wav, AssetWatcher, Sound, SpriteSheet};
+use crate::engine::audio::{Audio, Bus};
+use crate::engine::background::{default_backgrounds, load_backgrounds, BackgroundLayer};
+use crate::engine::activation::ActivationRange;
+use crate::engine::app_state::GameOverChoice;
+use crate::engine::camera_zone::CameraZones;
+use crate::engine::rewind::Rewind;
+use crate::engine::game_state::Physics;
+#[cfg(debug_assertions)]
+use crate::engine::game_state::PlayerSnapshot;
+use crate::engine::checkpoint::Checkpoints;
+use crate::engine::collectible::Collectibles;
+use crate::engine::collision::{take_collision_tests, Aabb};
+use crate::engine::cutscene::Cutscenes;
+use crate::engine::decoration::{decorations, load_decorations, set_decorations, Decorations};
+#[cfg(debug_assertions)]
+use crate::engine::console::Console;
+#[cfg(debug_assertions)]
+use crate::engine::dev::{Cheats, DevAction};
+use crate::engine::door::Doors;
+use crate::engine::effects::{load_effects, Effects};
+use crate::engine::exit::LevelExits;
+use crate::engine::frame_pacer::FramePacer;
+use crate::engine::gamepad::Gamepads;
+use crate::engine::config::{load_tuning, set_tuning, tuning, ConfigError};
+use crate::engine::mods::{set_mods, ModList, MODS_DIR};
+use crate::engine::constants::{GROUND_LEVEL, SIMULATION_STEP, STARTING_LIVES};
+use crate::engine::hazard::Hazards;
+use crate::engine::level::bundle::TILESET_PATH;
+use crate::engine::level::{load_tiled_map, Level};
+use crate::engine::level_stats::{LevelAttempt, LevelRecords};
+use crate::engine::jelly::JellyPlatforms;
+use crate::engine::lighting::{Lighting, PointLight};
+use crate::engine::mount::Mounts;
+use crate::engine::objective::{ObjectiveUpdate, Objectives};
+use crate::engine::pattern::{load_patterns, set_patterns};
+use crate::engine::platform::MovingPlatforms;
+use crate::engine::scene::{Scene, SceneManager};
+use crate::engine::script::Scripts;
+#[cfg(debug_assertions)]
+use crate::engine::scrubber::Scrubber;
+use crate::engine::shadow::Shadow;
+use crate::engine::sign::Signs;
+use crate::engine::slope::Slopes;
+use crate::engine::stats::{EngineStats, FrameTimings};
+use crate::engine::portal::{Portals, PORTAL_TINT};
+use crate::engine::power::PowerSaver;
+use crate::engine::projectile::{Projectiles, Target, PROJECTILE_FRAMES, PROJECTILE_SIZE};
+use crate::engine::props::{Props, PROP_FRAMES};
+use crate::engine::rng::{time_seed, Rng};
+use crate::engine::rope::Ropes;
+use crate::engine::switch::Switches;
+use crate::engine::renderer::batcher::InstanceBatcher;
 use crate::engine::renderer::instance::InstanceData;
+use crate::engine::renderer::layer::RenderLayer;
+use crate::engine::renderer::mesh::Mesh;
+use crate::engine::renderer::renderer::backend_choices;
+#[cfg(debug_assertions)]
+use crate::engine::renderer::pipeline::{SHADERS_DIR, SHADER_FILE};
+use crate::engine::renderer::shading;
+use crate::engine::renderer::snapshot::{compare_images, OffscreenTarget};
+use crate::engine::renderer::sprite_array::SpriteLayer;
+use crate::engine::renderer::text::{TextAlign, TextStyle};
+use crate::engine::renderer::texture::reload_texture;
+use crate::engine::time_scale::TimeScale;
+use crate::engine::ui::{Button, DamageFlash, DebugHud, FlashLimiter, LevelBanner, Mouse, Rect, Ui};
 use winit::{
     event::{Event, WindowEvent},
     event_loop::{ControlFlow, EventLoop},
-    window::WindowBuilder,
+    platform::run_return::EventLoopExtRunReturn,
+    window::{Window, WindowBuilder},
 };
 use pollster::block_on;
+use std::ops::Range;
+use std::sync::Arc;
+
+/// Save file written by quicksave and read by quickload.
+const SAVE_PATH: &str = "saves/quicksave.json";
+
+/// Save file written when the player reaches a checkpoint, and read when they lose a life.
+const CHECKPOINT_SAVE_PATH: &str = "saves/checkpoint.json";
+
+/// Save file of every level's statistics, kept across games and written as they change.
+const LEVEL_RECORDS_PATH: &str = "saves/records.json";
+
+/// How much the debug speed keys change the game speed by per press.
+const GAME_SPEED_STEP: f32 = 0.25;
+
+/// Slowest and fastest game speeds the debug speed keys allow.
+const GAME_SPEED_RANGE: (f32, f32) = (0.25, 2.0);
+
+/// Levels drawn by the rendering regression tests, and offered on their menu.
+const RENDER_TEST_LEVELS: [&str; 2] = ["assets/levels/level1.json", "assets/levels/level2.json"];
+
+/// Sprite sheet and texture of the player drawn by the rendering regression tests.
+const RENDER_TEST_PLAYER: (&str, &str) = (
+    "assets/character/sheets/DinoSprites.json",
+    "assets/character/sheets/DinoSprites - tard.png",
+);
+
+/// Scenes drawn by the rendering regression tests, each named after its reference image.
+const RENDER_TEST_SCENES: [(&str, TestScene); 3] = [
+    ("menu", TestScene::Menu),
+    ("level1", TestScene::Level(RENDER_TEST_LEVELS[0])),
+    ("level2", TestScene::Level(RENDER_TEST_LEVELS[1])),
+];
+
+/// Reference images the rendering regression tests compare against, one PNG per scene.
+const RENDER_TEST_REFERENCES_DIR: &str = "tests/render";
+
+/// Where the rendering regression tests write the images of scenes that failed, and
+/// where they differ.
+const RENDER_TEST_OUTPUT_DIR: &str = "target/render_tests";
+
+/// Size the rendering regression tests draw at, matching the default virtual resolution.
+const RENDER_TEST_SIZE: (u32, u32) = (800, 600);
+
+/// How far a color channel may stray from the reference before its pixel counts as
+/// differing, allowing for rounding differences between GPUs and drivers.
+const RENDER_TEST_CHANNEL_TOLERANCE: u8 = 8;
+
+/// Share of a scene's pixels that may differ before it fails, allowing for edges
+/// rasterized slightly differently.
+const RENDER_TEST_MAX_DIFFERING: f32 = 0.001;
+
+/// Seed of the random numbers in the rendering regression tests, such as the props' scattering.
+const RENDER_TEST_SEED: u32 = 1;
+
+/// Names the props' stream of the run's random numbers.
+const PROPS_STREAM: &str = "props";
+
+/// Names the particle effects' stream of the run's random numbers.
+const EFFECTS_STREAM: &str = "effects";
+
+/// Names the GPU particles' stream of the run's random numbers, seeding where they respawn.
+const GPU_PARTICLES_STREAM: &str = "gpu_particles";
+
+/// A scene drawn by the rendering regression tests.
+#[derive(Clone, Copy)]
+enum TestScene {
+    /// The level-select menu, over its backdrop.
+    Menu,
+    /// A level as it starts, with the player at its spawn point.
+    Level(&'static str),
+}
+
+/// The background layers, with an unscrolled instance of each sized to the window.
+struct Backgrounds {
+    layers: Vec<BackgroundLayer>,
+    /// The unscrolled instance of each layer, from `create_background_instances`.
+    instances: Vec<InstanceData>,
+}
+
+/// What the level-select menu offers: the levels to start, then the mods to switch
+/// on or off.
+struct MainMenu {
+    /// Paths of the game's levels, then those the enabled mods add.
+    levels: Vec<String>,
+    /// Every mod found, whether enabled or not.
+    mods: ModList,
+}
+
+impl MainMenu {
+    /// Returns the number of entries, levels and mods together.
+    fn entry_count(&self) -> usize {
+        self.levels.len() + self.mods.mods().len()
+    }
+
+    /// Returns what choosing an entry does: starting its level, or switching its mod.
+    ///
+    /// # Arguments
+    ///
+    /// * index - The entry, counting the levels and then the mods.
+    fn action(&self, index: usize) -> MenuAction {
+        match index.checked_sub(self.levels.len()) {
+            Some(mod_index) => MenuAction::ToggleMod(mod_index),
+            None => MenuAction::StartLevel(index),
+        }
+    }
+}
+
+/// The loaded level together with the features built from it.
+struct World {
+    level: Level,
+    room_manager: RoomManager,
+    secret_areas: SecretAreas,
+    water: Water,
+    /// The breath the player holds underwater, and the air bubbles refilling it.
+    oxygen: Oxygen,
+    air_bubbles: AirBubbles,
+    portals: Portals,
+    mounts: Mounts,
+    ropes: Ropes,
+    jelly_platforms: JellyPlatforms,
+    moving_platforms: MovingPlatforms,
+    slopes: Slopes,
+    camera_zones: CameraZones,
+    ambience_zones: AmbienceZones,
+    cutscenes: Cutscenes,
+    /// Scripts driving the level's triggers, scripted turrets, and cutscenes.
+    scripts: Scripts,
+    hazards: Hazards,
+    projectiles: Projectiles,
+    checkpoints: Checkpoints,
+    collectibles: Collectibles,
+    switches: Switches,
+    doors: Doors,
+    signs: Signs,
+    exits: LevelExits,
+    /// What the player has to do before the exits open.
+    objectives: Objectives,
+    lighting: Lighting,
+    /// Grass, bushes, and rocks scattered along the tops of the ground.
+    decorations: Decorations,
+    /// Announces the level as it starts, until it has faded out.
+    banner: Option<LevelBanner>,
+    /// The player's recent movement in the level, to rewind through.
+    rewind: Rewind,
+    /// The level's recent simulation states, to scrub through while paused.
+    #[cfg(debug_assertions)]
+    scrubber: Scrubber<SimulationState>,
+    /// Whether the player is in the world; the menu's backdrop leaves them out.
+    has_player: bool,
+}
+
+/// Everything that moves in the simulation at the end of one frame, recorded in
+/// debug builds to step back and forth through while paused.
+///
+/// Progress such as the score, collectibles, and switches isn't included, so
+/// looking through the states only moves things about.
+#[cfg(debug_assertions)]
+#[derive(Clone)]
+struct SimulationState {
+    player: PlayerSnapshot,
+    mounts: Mounts,
+    ropes: Ropes,
+    jelly_platforms: JellyPlatforms,
+    moving_platforms: MovingPlatforms,
+    projectiles: Projectiles,
+    camera: Camera,
+    /// Game time the frame took, in seconds.
+    delta_time: f32,
+}
+
+#[cfg(debug_assertions)]
+impl SimulationState {
+    /// Captures the simulation as a frame left it.
+    ///
+    /// # Arguments
+    ///
+    /// * world - The level's moving parts.
+    /// * game_state - The player.
+    /// * camera - The view of the world.
+    /// * delta_time - Game time the frame took.
+    fn capture(world: &World, game_state: &GameState, camera: &Camera, delta_time: f32) -> Self {
+        Self {
+            player: game_state.snapshot(),
+            mounts: world.mounts.clone(),
+            ropes: world.ropes.clone(),
+            jelly_platforms: world.jelly_platforms.clone(),
+            moving_platforms: world.moving_platforms.clone(),
+            projectiles: world.projectiles.clone(),
+            camera: camera.clone(),
+            delta_time,
+        }
+    }
+
+    /// Puts the simulation back as it was when the state was captured.
+    ///
+    /// # Arguments
+    ///
+    /// * world - The level whose moving parts are put back.
+    /// * game_state - The player to move back.
+    /// * camera - The view to move back.
+    fn restore(self, world: &mut World, game_state: &mut GameState, camera: &mut Camera) {
+        game_state.restore(&self.player);
+        world.mounts = self.mounts;
+        world.ropes = self.ropes;
+        world.jelly_platforms = self.jelly_platforms;
+        world.moving_platforms = self.moving_platforms;
+        world.projectiles = self.projectiles;
+        *camera = self.camera;
+    }
+}
 
 /// Runs the main game loop, initializing the window, handling events, and rendering frames.
-/// Runs the main game loop, initializing the window, handling events, and rendering frames.
-pub fn run() {
+///
+/// # Arguments
+///
+/// * app - The window to open, the scene to start in, the game to drive, which names
+///   the assets to load, and the systems run alongside it.
+pub fn run(app: App) {
+    let App {
+        window,
+        initial_scene,
+        seed,
+        game,
+        systems,
+    } = app;
+    let assets = game.assets();
+
+    // Find the mods before loading anything they may override
+    let mods = ModList::discover(MODS_DIR);
+    set_mods(mods.clone());
+    let main_menu = MainMenu {
+        levels: assets.levels.iter().cloned().chain(mods.levels()).collect(),
+        mods,
+    };
+
+    // Load the gameplay tuning before anything reads it, falling back to the built-in constants
+    match load_tuning(&assets.tuning) {
+        Ok(tuning) => set_tuning(tuning),
+        Err(e) => log::warn!("Could not load tuning '{}': {}", assets.tuning, e),
+    }
+
+    // Load the decorations before the renderer loads their sheet
+    match load_decorations(&assets.decorations) {
+        Ok(config) => set_decorations(config),
+        Err(e) => log::warn!("Could not load decorations '{}': {}", assets.decorations, e),
+    }
+
+    // Load the bullet patterns before any level's turrets look them up
+    match load_patterns(&assets.patterns) {
+        Ok(patterns) => set_patterns(patterns),
+        Err(e) => log::warn!("Could not load bullet patterns '{}': {}", assets.patterns, e),
+    }
+
+    // Load the events fired on the player's animation frames
+    match load_animation_events(&assets.animation_events) {
+        Ok(events) => set_animation_events(events),
+        Err(e) => log::warn!("Could not load animation events '{}': {}", assets.animation_events, e),
+    }
+
     // Create an event loop and a window
-    let event_loop = EventLoop::new();
+    let mut event_loop = EventLoop::new();
     let window = WindowBuilder::new()
-        .with_title("Rust Platformer Engine")
-        .with_inner_size(winit::dpi::PhysicalSize::new(800, 600))
+        .with_title(&window.title)
+        .with_inner_size(winit::dpi::PhysicalSize::new(window.width, window.height))
         .build(&event_loop)
         .expect("Failed to create window.");
 
-    // Initialize the renderer
-    let mut renderer = block_on(Renderer::new(&window));
+    let background_layers = load_background_layers(&assets.backgrounds);
+
+    // Save power on battery or when configured to, checking before picking the GPU
+    let power_saver = PowerSaver::new(tuning().low_power);
+
+    // Initialize the renderer behind the splash screen, stopping if the window is closed first
+    let Some(mut renderer) = warm_up(
+        &mut event_loop,
+        &window,
+        &assets.player_texture,
+        &background_layers,
+        power_saver.is_low_power(),
+    ) else {
+        return;
+    };
+    // Catch up with any resize while it was loading
+    renderer.resize(window.inner_size());
+
+    // Initialize the input handler with the configured bindings, writing out the
+    // defaults for the player to edit if there's no controls config yet
+    let action_map = match ActionMap::load(&assets.controls) {
+        Ok(action_map) => action_map,
+        Err(ConfigError::Io(e)) if e.kind() == std::io::ErrorKind::NotFound => {
+            let action_map = ActionMap::default();
+            match action_map.save(&assets.controls) {
+                Ok(()) => log::info!("Wrote the default controls to '{}'", assets.controls),
+                Err(e) => log::warn!("Could not write the default controls to '{}': {}", assets.controls, e),
+            }
+            action_map
+        }
+        Err(e) => {
+            log::warn!("Could not load controls '{}': {}", assets.controls, e);
+            ActionMap::default()
+        }
+    };
+    let input_handler = InputHandler::with_action_map(action_map);
+
+    // Initialize the game state with the player's animations
+    let player_sheet = load_player_sheet(&renderer, &assets.player_sheet);
+    let mut game_state = GameState::with_sprite_sheet(&player_sheet);
+    // Every game started this run draws its random numbers from the same seed
+    let seed = seed.unwrap_or_else(time_seed);
+    game_state.reseed(seed);
+    log::info!("Random seed: {}", seed);
+    for problem in player_sheet.validate(renderer.texture_size, game_state.animation_frames()) {
+        log::warn!("Sprite sheet '{}': {}", assets.player_sheet, problem);
+    }
+    for problem in animation_events().validate(&game_state.animation_frames()) {
+        log::warn!("Animation events '{}': {}", assets.animation_events, problem);
+    }
+
+    // Start in the first scene, fading in from black
+    let world = match &initial_scene {
+        Scene::Menu => menu_world(&renderer, &mut game_state),
+        Scene::Level(path) => start_level(path, &renderer, &mut game_state),
+    };
+    let scenes = SceneManager::new(initial_scene);
+    let camera = Camera::new();
+
+    // Start the clouds and birds drifting across the background
+    let props = Props::new(&camera, *game_state.stream(PROPS_STREAM));
+
+    // Load the particle effects, played by name
+    let presets = load_effects(&assets.effects).unwrap_or_else(|e| {
+        log::warn!("Could not load effects '{}': {}", assets.effects, e);
+        Default::default()
+    });
+    let effects = Effects::new(presets, renderer.particles.is_some(), *game_state.stream(EFFECTS_STREAM));
+    let particle_rng = *game_state.stream(GPU_PARTICLES_STREAM);
+
+    // Watch the assets so edits on disk show up without restarting
+    let asset_watcher = match AssetWatcher::new(&assets.assets_dir) {
+        Ok(watcher) => Some(watcher),
+        Err(e) => {
+            log::warn!("Could not watch '{}' for changes; hot-reloading is off: {}", assets.assets_dir, e);
+            None
+        }
+    };
+
+    // In debug builds, also watch the shaders so edits to the sprite shader show up
+    // without rebuilding
+    #[cfg(debug_assertions)]
+    let shader_watcher = match AssetWatcher::new(SHADERS_DIR) {
+        Ok(watcher) => Some(watcher),
+        Err(e) => {
+            log::warn!("Could not watch '{}' for changes; shader hot-reloading is off: {}", SHADERS_DIR, e);
+            None
+        }
+    };
+
+    // What's been done in each level, shown on the level-select menu
+    let level_records = LevelRecords::load(LEVEL_RECORDS_PATH).unwrap_or_else(|e| {
+        log::warn!("Could not load level records from '{}': {}", LEVEL_RECORDS_PATH, e);
+        LevelRecords::default()
+    });
+
+    // Frame rate and rendering stats, shown with the debug HUD action
+    let mut debug_hud = DebugHud::new();
+    debug_hud.set_adapter(&renderer.adapter_info);
+
+    // Start out on the menu with the first level selected, or playing the first
+    // level; while playing, the pause action toggles the pause overlay
+    let app_state = match scenes.current() {
+        Scene::Menu => AppState::Menu(0),
+        Scene::Level(_) => AppState::Playing,
+    };
+
+    let backgrounds = Backgrounds {
+        instances: create_background_instances(&renderer, &background_layers),
+        layers: background_layers,
+    };
+
+    // Start the music; sounds that fail to load are skipped with a warning
+    let audio = Audio::new();
+    if let Some(music) = assets.music.as_deref().and_then(load_sound) {
+        audio.play(&music, Bus::Music, 0.5, true);
+    }
+
+    let last_frame_time = std::time::Instant::now();
+    let frame_pacer = FramePacer::new(power_saver.target_fps(tuning().target_fps));
+    let mut session = Session {
+        renderer,
+        game,
+        systems,
+        seed,
+        player_sheet,
+        main_menu,
+        backgrounds,
+        asset_watcher,
+        #[cfg(debug_assertions)]
+        shader_watcher,
+        input_handler,
+        locked_input: InputHandler::new(),
+        gamepads: Gamepads::new(),
+        mouse: Mouse::new(),
+        clicked_action: None,
+        scenes,
+        app_state,
+        world,
+        game_state,
+        camera,
+        props,
+        effects,
+        particle_rng,
+        weather: None,
+        level_records,
+        attempt: LevelAttempt::default(),
+        next_scene: None,
+        events: Vec::new(),
+        debug_hud,
+        help_visible: false,
+        #[cfg(debug_assertions)]
+        dev_menu_visible: false,
+        #[cfg(debug_assertions)]
+        dev_action: None,
+        #[cfg(debug_assertions)]
+        console: Console::new(),
+        damage_flash: DamageFlash::new(),
+        flash_limiter: FlashLimiter::new(),
+        batcher: InstanceBatcher::new(),
+        audio,
+        ambience: Ambience::new(),
+        time_scale: TimeScale::new(),
+        power_saver,
+        frame_pacer,
+        last_frame_time,
+        start_time: last_frame_time,
+        frame_start: last_frame_time,
+    };
+
+    // Let the game and its systems set themselves up now that everything they can reach is ready
+    session.init();
+    if matches!(session.scenes.current(), Scene::Level(_)) {
+        session.start_attempt(false);
+    }
+
+    // Run the event loop
+    event_loop.run(move |event, _, control_flow| {
+        // Keep the event loop running, unless the game is paused while the window is hidden
+        let paused_hidden = !session.renderer.is_visible() && tuning().pause_when_hidden;
+        *control_flow = if paused_hidden { ControlFlow::Wait } else { ControlFlow::Poll };
+
+        match event {
+            Event::WindowEvent { event, .. } => handle_window_event(
+                event,
+                control_flow,
+                &mut session.input_handler,
+                &mut session.renderer,
+                &mut session.backgrounds,
+            ),
+            Event::MainEventsCleared => {
+                // Wait for the window to come back, restarting the frame clocks so game
+                // time doesn't jump forward by however long it was gone
+                if paused_hidden {
+                    session.last_frame_time = std::time::Instant::now();
+                    session.frame_start = session.last_frame_time;
+                    return;
+                }
+                session.frame();
+            }
+            _ => {}
+        }
+    });
+}
+
+/// The running game: everything the game loop keeps from one frame to the next, and
+/// the engine's systems updating and drawing it each frame.
+struct Session {
+    renderer: Renderer,
+    game: Box<dyn Game>,
+    /// Systems added to the app, run after the game in the order they were added.
+    systems: Vec<Box<dyn System>>,
+    /// Seed every game started this run draws its random numbers from.
+    seed: u32,
+    player_sheet: SpriteSheet,
+    main_menu: MainMenu,
+    backgrounds: Backgrounds,
+    /// Watches the assets so edits on disk show up without restarting.
+    asset_watcher: Option<AssetWatcher>,
+    /// Watches the shaders so edits to the sprite shader show up without rebuilding.
+    #[cfg(debug_assertions)]
+    shader_watcher: Option<AssetWatcher>,
+    input_handler: InputHandler,
+    /// Stands in for the keyboard while a cutscene has taken control of the player.
+    locked_input: InputHandler,
+    /// Feeds gamepad buttons into the input handler alongside the keyboard.
+    gamepads: Gamepads,
+    /// Tracks the mouse for the overlay's buttons.
+    mouse: Mouse,
+    /// The overlay button clicked while building last frame's overlay, acted on this frame.
+    clicked_action: Option<MenuAction>,
+    scenes: SceneManager,
+    app_state: AppState,
+    world: World,
+    game_state: GameState,
+    camera: Camera,
+    /// The clouds and birds drifting across the background.
+    props: Props,
+    /// The particle effects, played by name.
+    effects: Effects,
+    /// Seeds where the GPU particles respawn.
+    particle_rng: Rng,
+    /// The level's weather, simulated by the GPU particles; set on the first frame.
+    weather: Option<String>,
+    /// What's been done in each level, shown on the level-select menu.
+    level_records: LevelRecords,
+    /// The run through the current level.
+    attempt: LevelAttempt,
+    /// Once the level is finished, the scene its exit leads to, waiting for the player
+    /// to leave the level-complete screen.
+    next_scene: Option<Scene>,
+    /// What happened this frame, sent on to the game once the frame is updated.
+    events: Vec<GameEvent>,
+    debug_hud: DebugHud,
+    /// Whether the list of controls is shown, toggled with the help action.
+    help_visible: bool,
+    /// Whether the developer menu is shown, toggled with the dev menu action.
+    #[cfg(debug_assertions)]
+    dev_menu_visible: bool,
+    /// The developer menu's choice last frame, acted on this frame like the overlay's buttons.
+    #[cfg(debug_assertions)]
+    dev_action: Option<DevAction>,
+    /// Takes developer commands typed in, such as noclip, as developer menu choices.
+    #[cfg(debug_assertions)]
+    console: Console,
+    /// Flashes the screen edges when the player loses a life, held to a safe rate and
+    /// strength like every full-screen flash.
+    damage_flash: DamageFlash,
+    flash_limiter: FlashLimiter,
+    batcher: InstanceBatcher,
+    audio: Audio,
+    ambience: Ambience,
+    /// Game time runs at normal speed until hitstop or the speed keys change it.
+    time_scale: TimeScale,
+    power_saver: PowerSaver,
+    frame_pacer: FramePacer,
+    last_frame_time: std::time::Instant,
+    /// When the loop started, for material animation.
+    start_time: std::time::Instant,
+    frame_start: std::time::Instant,
+}
+
+impl Session {
+    /// Lets the game, then each of its systems, set itself up.
+    fn init(&mut self) {
+        let mut engine = Engine {
+            audio: &self.audio,
+            game_state: &mut self.game_state,
+        };
+        self.game.init(&mut engine);
+        for system in &mut self.systems {
+            system.init(&mut engine);
+        }
+    }
+
+    /// Runs a frame: reacts to the input, updates the scene, the simulation and the
+    /// game, then draws it all.
+    fn frame(&mut self) {
+        self.reload_assets();
+        self.gamepads.update(&mut self.input_handler);
+        self.update_scene();
+        self.update_saves();
+        self.update_menus();
+        let low_power_toggled = self.update_toggles();
+        #[cfg(debug_assertions)]
+        self.update_dev_tools();
+        let (entities_updated, particle_delta_time) = self.update_simulation();
+        self.send_events();
+        self.update_audio();
+        let overlay = self.render_overlay();
+        let (stats, timings) = self.render(overlay, particle_delta_time, entities_updated);
+        self.end_frame(stats, &timings, low_power_toggled);
+    }
+
+    /// Reloads the assets, and in debug builds the sprite shader, changed on disk.
+    fn reload_assets(&mut self) {
+        if let Some(watcher) = &self.asset_watcher {
+            reload_changed_assets(&watcher.changed_paths(), &self.renderer, &mut self.world, &mut self.game_state);
+        }
+        #[cfg(debug_assertions)]
+        if let Some(watcher) = &self.shader_watcher {
+            let changed = watcher.changed_paths();
+            if changed.iter().any(|path| std::path::Path::new(path).ends_with(SHADER_FILE)) {
+                self.renderer.reload_shader();
+            }
+        }
+    }
+
+    /// Loads the scene being switched to once the screen has faded out, unloading the
+    /// old scene's world by replacing it.
+    fn update_scene(&mut self) {
+        let Some(scene) = self.scenes.arrive() else {
+            return;
+        };
+        match scene {
+            Scene::Menu => {
+                self.app_state = AppState::Menu(0);
+                self.world = menu_world(&self.renderer, &mut self.game_state);
+            }
+            Scene::Level(path) => {
+                self.app_state = AppState::Playing;
+                self.world = start_level(&path, &self.renderer, &mut self.game_state);
+                self.start_attempt(false);
+            }
+        }
+        self.camera = Camera::new();
+    }
+
+    /// Starts tracking a new run through the level just loaded, and tells the game the
+    /// player has appeared in it.
+    ///
+    /// # Arguments
+    ///
+    /// * resumed - Whether the run picks up partway through from a save.
+    fn start_attempt(&mut self, resumed: bool) {
+        self.attempt = LevelAttempt {
+            resumed,
+            ..LevelAttempt::default()
+        };
+        self.level_records.start(&self.game_state.level, self.world.collectibles.collectibles.len());
+        self.events.push(GameEvent::Spawned {
+            position: (self.game_state.player_x, self.game_state.player_y),
+        });
+    }
+
+    /// Quicksaves and quickloads on the initial press of their actions, in levels only.
+    fn update_saves(&mut self) {
+        let in_level = matches!(self.scenes.current(), Scene::Level(_));
+        if in_level && self.input_handler.is_action_just_pressed(Action::QuickSave) {
+            // Save where the streams drawn from outside the game state had got to
+            *self.game_state.stream(PROPS_STREAM) = self.props.rng();
+            *self.game_state.stream(EFFECTS_STREAM) = self.effects.rng();
+            *self.game_state.stream(GPU_PARTICLES_STREAM) = self.particle_rng;
+            match self.game_state.save(SAVE_PATH) {
+                Ok(()) => log::info!("Saved the game to '{}'", SAVE_PATH),
+                Err(e) => log::warn!("Could not save the game to '{}': {}", SAVE_PATH, e),
+            }
+        }
+
+        if in_level && self.input_handler.is_action_just_pressed(Action::QuickLoad) {
+            match self.game_state.load(SAVE_PATH) {
+                Ok(()) => {
+                    // Rebuild the saved level around the restored player
+                    self.world = build_world(load_level(&self.game_state.level, &self.renderer), &mut self.game_state);
+                    self.camera = Camera::new();
+                    // Time and deaths count afresh from the save, not from the abandoned run
+                    self.start_attempt(true);
+                    // Carry on from the saved run's random numbers
+                    self.props.set_rng(*self.game_state.stream(PROPS_STREAM));
+                    self.effects.set_rng(*self.game_state.stream(EFFECTS_STREAM));
+                    self.particle_rng = *self.game_state.stream(GPU_PARTICLES_STREAM);
+                    log::info!("Loaded the game from '{}'", SAVE_PATH);
+                }
+                Err(e) => log::warn!("Could not load the game from '{}': {}", SAVE_PATH, e),
+            }
+        }
+    }
+
+    /// Toggles the pause overlay, and acts on the menu button clicked last frame or, on
+    /// the game-over screen and the level-select menu, moves the selection with the up
+    /// and down actions or the mouse wheel and acts on it with confirm.
+    fn update_menus(&mut self) {
+        let input = &self.input_handler;
+        if input.is_action_just_pressed(Action::Pause) {
+            self.app_state.toggle_pause();
+        }
+
+        let mut action = self.clicked_action.take();
+        let up = input.is_action_just_pressed(Action::Up) || input.scroll() > 0.0;
+        let down = input.is_action_just_pressed(Action::Down) || input.scroll() < 0.0;
+        let confirm = input.is_action_just_pressed(Action::Confirm);
+        // With no levels or mods to list, the menu has nothing to select
+        let menu_empty = self.main_menu.entry_count() == 0;
+        match self.app_state {
+            AppState::Menu(_) if menu_empty => {}
+            AppState::GameOver(choice) if up || down => self.app_state = AppState::GameOver(choice.toggle()),
+            AppState::GameOver(choice) if confirm => action = Some(MenuAction::GameOver(choice)),
+            AppState::Menu(selected) if up => {
+                let entries = self.main_menu.entry_count();
+                self.app_state = AppState::Menu((selected + entries - 1) % entries);
+            }
+            AppState::Menu(selected) if down => {
+                self.app_state = AppState::Menu((selected + 1) % self.main_menu.entry_count());
+            }
+            AppState::Menu(selected) if confirm => action = Some(self.main_menu.action(selected)),
+            AppState::LevelComplete(_) if confirm => action = Some(MenuAction::Continue),
+            _ => {}
+        }
+
+        match action {
+            Some(MenuAction::Resume) => self.app_state = AppState::Playing,
+            Some(MenuAction::GameOver(GameOverChoice::Retry)) if self.game_state.continues > 0 => {
+                self.game_state.continues -= 1;
+                self.game_state.lives = STARTING_LIVES;
+                self.world = respawn(&mut self.game_state, &self.renderer);
+                self.camera = Camera::new();
+                self.app_state = AppState::Playing;
+                self.events.push(GameEvent::Spawned {
+                    position: (self.game_state.player_x, self.game_state.player_y),
+                });
+            }
+            Some(MenuAction::GameOver(GameOverChoice::ReturnToStart)) => {
+                let level = self.game_state.level.clone();
+                self.game_state = GameState::with_sprite_sheet(&self.player_sheet);
+                self.game_state.reseed(self.seed);
+                self.world = start_level(&level, &self.renderer, &mut self.game_state);
+                self.camera = Camera::new();
+                self.app_state = AppState::Playing;
+                self.start_attempt(false);
+            }
+            // Out of continues, so only returning to the start is possible
+            Some(MenuAction::GameOver(GameOverChoice::Retry)) => {}
+            // Start a new game in the level picked from the menu
+            Some(MenuAction::StartLevel(index)) => {
+                self.game_state = GameState::with_sprite_sheet(&self.player_sheet);
+                self.game_state.reseed(self.seed);
+                self.scenes.switch_to(Scene::Level(self.main_menu.levels[index].clone()));
+            }
+            // Leave the finished level through its exit
+            Some(MenuAction::Continue) => {
+                if let Some(scene) = self.next_scene.take() {
+                    self.app_state = AppState::Playing;
+                    self.scenes.switch_to(scene);
+                }
+            }
+            // Switch a mod on or off from the next start
+            Some(MenuAction::ToggleMod(index)) => self.main_menu.mods.toggle(index),
+            // Leave the level, unpausing so it can fade out
+            Some(MenuAction::QuitToMenu) => {
+                self.app_state = AppState::Playing;
+                self.scenes.switch_to(Scene::Menu);
+            }
+            None => {}
+        }
+    }
+
+    /// Steps the game speed, and toggles the debug HUD, low-power mode and the list of
+    /// controls, on the initial press of their actions.
+    ///
+    /// # Returns
+    ///
+    /// Whether low-power mode was toggled, to apply once the frame is drawn.
+    fn update_toggles(&mut self) -> bool {
+        let input = &self.input_handler;
+        let step = if input.is_action_just_pressed(Action::SlowDown) {
+            -GAME_SPEED_STEP
+        } else if input.is_action_just_pressed(Action::SpeedUp) {
+            GAME_SPEED_STEP
+        } else {
+            0.0
+        };
+        if step != 0.0 {
+            let (min, max) = GAME_SPEED_RANGE;
+            self.time_scale.set_speed((self.time_scale.speed() + step).clamp(min, max));
+            log::info!("Game speed: {:.2}x", self.time_scale.speed());
+        }
+
+        if input.is_action_just_pressed(Action::DebugHud) {
+            self.debug_hud.toggle();
+            // Time passes on the GPU only while their times are shown
+            if let Some(timer) = &self.renderer.gpu_timer {
+                timer.set_enabled(self.debug_hud.visible());
+            }
+        }
+
+        let low_power_toggled = input.is_action_just_pressed(Action::LowPower);
+        if low_power_toggled {
+            self.power_saver.toggle();
+        }
+        if input.is_action_just_pressed(Action::Help) {
+            self.help_visible = !self.help_visible;
+        }
+        low_power_toggled
+    }
+
+    /// Opens the developer menu and the console and acts on their choices, steps
+    /// through the recorded simulation while paused, and picks what's under the cursor.
+    #[cfg(debug_assertions)]
+    fn update_dev_tools(&mut self) {
+        // The keyboard types into the console while it's open, rather than playing
+        let console_was_open = self.console.is_open();
+        if self.input_handler.is_action_just_pressed(Action::Console) {
+            self.console.toggle();
+        } else if let Some(action) = self.console.update(self.input_handler.typed()) {
+            self.dev_action = Some(action);
+        }
+        if self.console.is_open() != console_was_open {
+            self.input_handler.set_typing(self.console.is_open());
+        }
+
+        let input = &self.input_handler;
+        if input.is_action_just_pressed(Action::DevMenu) {
+            self.dev_menu_visible = !self.dev_menu_visible;
+        }
+        if input.is_action_just_pressed(Action::Noclip) {
+            self.dev_action = Some(DevAction::ToggleNoclip);
+        }
+        if let Some(action) = self.dev_action.take() {
+            apply_dev_action(action, &mut self.game_state, &self.world, &mut self.scenes);
+        }
+
+        // Step through the recorded simulation while paused, one frame a press
+        if self.app_state == AppState::Paused {
+            let step = if input.is_action_just_pressed(Action::ScrubBack) {
+                Some(-1)
+            } else if input.is_action_just_pressed(Action::ScrubForward) {
+                Some(1)
+            } else {
+                None
+            };
+            if let Some(state) = step.and_then(|step| self.world.scrubber.step(step)).cloned() {
+                let delta_time = state.delta_time;
+                state.restore(&mut self.world, &mut self.game_state, &mut self.camera);
+                if let Some((frame, frames)) = self.world.scrubber.position() {
+                    log::info!(
+                        "Frame {} of {}: player at ({:.3}, {:.3}), took {:.2} ms",
+                        frame,
+                        frames,
+                        self.game_state.player_x,
+                        self.game_state.player_y,
+                        delta_time * 1000.0
+                    );
+                }
+            }
+        }
+
+        // Right-click in a level to list what's under the cursor, and with noclip on,
+        // to move the player there
+        let in_level = matches!(self.scenes.current(), Scene::Level(_));
+        if in_level && input.is_mouse_just_pressed(winit::event::MouseButton::Right) {
+            if let Some(cursor) = input.cursor_position() {
+                let (x, y) = self.camera.screen_to_world(cursor);
+                log_picked(&self.world.level, x, y);
+                if self.game_state.cheats.noclip {
+                    self.game_state.teleport(x, y);
+                }
+            }
+        }
+    }
+
+    /// Moves the world on by the game time since the last frame while a level is being
+    /// played, or holding rewind, steps the player back through the last few seconds
+    /// while the rest of the world holds still. The world holds still as it fades out.
+    ///
+    /// # Returns
+    ///
+    /// How many entities were simulated, and the game time the GPU particles move on
+    /// by, which is zero while the game isn't playing.
+    fn update_simulation(&mut self) -> (usize, f32) {
+        if self.app_state != AppState::Playing || self.scenes.leaving() {
+            // Keep the clock current so resuming doesn't take one long step
+            self.last_frame_time = std::time::Instant::now();
+            return (0, 0.0);
+        }
+
+        // The player is moved by stand-in input while a cutscene has taken control,
+        // taken out of the session while the systems borrow the rest of it
+        let locked = self.world.cutscenes.input_locked();
+        let slot = if locked { &mut self.locked_input } else { &mut self.input_handler };
+        let mut input = std::mem::take(slot);
+
+        let rewinding = input.is_action_pressed(Action::Rewind) && self.world.rewind.can_rewind();
+        let mut updated = (0, 0.0);
+        if rewinding {
+            let (rewind, clock) = (&mut self.world.rewind, &mut self.last_frame_time);
+            rewind_game_state(rewind, &mut self.game_state, clock, &mut self.time_scale);
+            self.camera.follow(self.game_state.player_x);
+        } else {
+            let delta_time = advance_clock(&mut self.last_frame_time, &mut self.time_scale);
+            updated = (self.activate_entities(), delta_time);
+
+            // Step the player and the level's features in fixed steps, so they move the
+            // same at any frame rate, stopping once the level is left or the player loses a life
+            input.queue_step_presses();
+            for _ in 0..self.game_state.fixed_steps(delta_time) {
+                input.begin_step();
+                let stopped = self.step(&input);
+                input.end_step();
+                if stopped {
+                    break;
+                }
+            }
+
+            // Then the game's own logic, seeing what the engine has done this frame
+            self.update_game(&input, delta_time);
+            self.update_view(delta_time);
+        }
+        self.world.rewind.update_fade(rewinding);
+
+        *(if locked { &mut self.locked_input } else { &mut self.input_handler }) = input;
+        updated
+    }
+
+    /// Wakes the entities around the camera, leaving the rest of the level asleep.
+    ///
+    /// # Returns
+    ///
+    /// How many entities are awake.
+    fn activate_entities(&mut self) -> usize {
+        let range = ActivationRange::around(&self.camera);
+        let world = &mut self.world;
+        world.mounts.activate(&range)
+            + world.ropes.activate(&range)
+            + world.jelly_platforms.activate(&range)
+            + world.moving_platforms.activate(&range)
+            + world.projectiles.activate(&range)
+            + world.scripts.activate(&range)
+    }
+
+    /// Moves the player and the level's features on by one fixed simulation step.
+    ///
+    /// # Arguments
+    ///
+    /// * input - The input moving the player this step.
+    ///
+    /// # Returns
+    ///
+    /// Whether to stop stepping this frame, as the level was left or the player lost a life.
+    fn step(&mut self, input: &InputHandler) -> bool {
+        self.step_movement(input);
+        self.step_triggers();
+        self.step_pickups();
+        self.step_player_events();
+        let drowned = self.step_breath();
+        self.step_features(input);
+        self.attempt.time += SIMULATION_STEP;
+        let shot = self.step_projectiles();
+        self.step_objectives();
+        self.step_exits();
+        let life_lost = self.step_damage(shot || drowned);
+        self.world.rewind.record(&self.game_state, SIMULATION_STEP);
+        self.app_state != AppState::Playing || life_lost
+    }
+
+    /// Moves the player, and carries them along on whatever they're riding, hanging
+    /// from, or standing on.
+    fn step_movement(&mut self, input: &InputHandler) {
+        let (world, game_state) = (&mut self.world, &mut self.game_state);
+        // Swim rather than fall while in water
+        game_state.set_swimming(world.water.contains(game_state.player_x, game_state.player_y));
+        game_state.update(input, SIMULATION_STEP);
+        world.mounts.update(game_state, input, SIMULATION_STEP);
+        world.ropes.update(game_state, input, SIMULATION_STEP);
+        world.slopes.update(game_state);
+        world.moving_platforms.update(game_state, SIMULATION_STEP);
+        if world.jelly_platforms.update(game_state, SIMULATION_STEP) {
+            self.time_scale.hitstop(tuning().bounce_hitstop_time);
+            self.events.push(GameEvent::Bounced);
+        }
+        world.portals.update(game_state, SIMULATION_STEP);
+    }
+
+    /// Runs what the player's position sets off: rooms, camera zones, scripts,
+    /// cutscenes, and secret areas.
+    fn step_triggers(&mut self) {
+        let (world, game_state, camera) = (&mut self.world, &mut self.game_state, &mut self.camera);
+        world.room_manager.update(game_state, camera, SIMULATION_STEP);
+        world.camera_zones.update(game_state, camera);
+        world.scripts.update(game_state, camera, &mut self.effects, &mut world.projectiles, SIMULATION_STEP);
+        world.cutscenes.set_scripted(world.scripts.in_cutscene());
+        world.cutscenes.update(game_state, camera, SIMULATION_STEP);
+        world.secret_areas.update(game_state, SIMULATION_STEP);
+    }
+
+    /// Saves at the checkpoints the player reaches, and picks up the collectibles they touch.
+    fn step_pickups(&mut self) {
+        if let Some(checkpoint) = self.world.checkpoints.update(&mut self.game_state) {
+            save_checkpoint(&self.game_state);
+            let bounds = checkpoint.bounds;
+            let (x, y) = ((bounds.min_x + bounds.max_x) / 2.0, (bounds.min_y + bounds.max_y) / 2.0);
+            self.effects.play(&checkpoint.effect, x, y);
+        }
+        for collectible in self.world.collectibles.update(&mut self.game_state) {
+            let bounds = collectible.bounds;
+            let (x, y) = ((bounds.min_x + bounds.max_x) / 2.0, (bounds.min_y + bounds.max_y) / 2.0);
+            self.effects.play(&collectible.effect, x, y);
+            self.level_records.record_found(&self.game_state.level, collectible.name());
+        }
+    }
+
+    /// Fires the events attached to the animation frame the player moved onto, and
+    /// notes a swim-dash starting.
+    fn step_player_events(&mut self) {
+        let game_state = &self.game_state;
+        let position = (game_state.player_x, game_state.player_y);
+        if game_state.frame_entered() {
+            let frame = game_state.sprite_index;
+            for name in animation_events().at(game_state.current_action(), frame) {
+                self.events.push(GameEvent::AnimationEvent { name, frame, position });
+            }
+        }
+        if game_state.swim_dash_started() {
+            self.events.push(GameEvent::SwimDashed { position });
+        }
+    }
+
+    /// Holds the player's breath underwater, refilling it at the surface and from air
+    /// bubbles, until it runs out.
+    ///
+    /// # Returns
+    ///
+    /// Whether the player drowned.
+    fn step_breath(&mut self) -> bool {
+        let world = &mut self.world;
+        for (effect, x, y) in world.air_bubbles.update(SIMULATION_STEP) {
+            self.effects.play(effect, x, y);
+        }
+        let breath = world.oxygen.update(&self.game_state, &world.water, &world.air_bubbles, SIMULATION_STEP);
+        match breath {
+            Some(Breath::Low { seconds_left }) => self.events.push(GameEvent::OxygenLow { seconds_left }),
+            Some(Breath::Breathed) => self.events.push(GameEvent::Breathed {
+                position: (self.game_state.player_x, self.game_state.player_y),
+            }),
+            Some(Breath::Drowned) | None => {}
+        }
+        breath == Some(Breath::Drowned)
+    }
+
+    /// Animates the hazards and collectibles, and works the switches, doors, and signs.
+    fn step_features(&mut self, input: &InputHandler) {
+        let (world, game_state) = (&mut self.world, &mut self.game_state);
+        world.hazards.animate(SIMULATION_STEP);
+        world.collectibles.animate(SIMULATION_STEP);
+        world.switches.update(game_state);
+        world.doors.update(game_state);
+        if let Some(sign) = world.signs.update(game_state, input, SIMULATION_STEP) {
+            let bounds = world.signs.signs[sign].bounds;
+            let position = ((bounds.min_x + bounds.max_x) / 2.0, (bounds.min_y + bounds.max_y) / 2.0);
+            self.events.push(GameEvent::SignRead { sign, position });
+        }
+    }
+
+    /// Moves the projectiles, noting where they hit.
+    ///
+    /// # Returns
+    ///
+    /// Whether one struck the player.
+    fn step_projectiles(&mut self) -> bool {
+        let world = &mut self.world;
+        let mut shot = false;
+        for impact in world.projectiles.update(&mut self.game_state, &world.level.tile_map, SIMULATION_STEP) {
+            shot |= impact.target == Target::Player;
+            self.events.push(GameEvent::ProjectileHit {
+                position: impact.position,
+                target: impact.target,
+            });
+        }
+        shot
+    }
+
+    /// Counts progress on the level's objectives.
+    fn step_objectives(&mut self) {
+        let world = &mut self.world;
+        for update in world.objectives.update(&mut self.game_state, &world.collectibles, &world.projectiles) {
+            self.events.push(match update {
+                ObjectiveUpdate::Progressed { objective } => {
+                    let progress = &world.objectives.objectives()[objective];
+                    GameEvent::ObjectiveProgressed {
+                        objective,
+                        progress: progress.progress,
+                        required: progress.required,
+                    }
+                }
+                ObjectiveUpdate::Completed { objective } => GameEvent::ObjectiveCompleted { objective },
+            });
+        }
+    }
+
+    /// Finishes the level on reaching an exit once its objectives are done, showing how
+    /// the run went before leaving on to the next level or back to the menu.
+    fn step_exits(&mut self) {
+        let exit = self.world.exits.reached(&self.game_state).filter(|_| self.world.objectives.exit_open());
+        if let Some(exit) = exit {
+            let summary = self.level_records.record_completion(&self.game_state.level, &self.attempt);
+            save_level_records(&self.level_records);
+            self.next_scene = Some(exit.destination.clone().map_or(Scene::Menu, Scene::Level));
+            self.app_state = AppState::LevelComplete(summary);
+        }
+    }
+
+    /// Loses a life on touching a hazard, being shot, or drowning, returning to the last
+    /// checkpoint while any are left, unless god mode or noclip is on.
+    ///
+    /// # Arguments
+    ///
+    /// * hurt - Whether the player was shot or drowned this step.
+    ///
+    /// # Returns
+    ///
+    /// Whether a life was lost.
+    fn step_damage(&mut self, hurt: bool) -> bool {
+        let cheats = self.game_state.cheats;
+        let finished = matches!(self.app_state, AppState::LevelComplete(_));
+        let life_lost = !finished
+            && !cheats.god_mode
+            && !cheats.noclip
+            && (hurt || self.world.hazards.touching(&self.game_state));
+        if !life_lost {
+            return false;
+        }
+
+        self.damage_flash.trigger(&mut self.flash_limiter);
+        self.attempt.deaths += 1;
+        self.level_records.record_death(&self.game_state.level);
+        save_level_records(&self.level_records);
+        self.events.push(GameEvent::LifeLost {
+            position: (self.game_state.player_x, self.game_state.player_y),
+        });
+        self.game_state.lives = self.game_state.lives.saturating_sub(1);
+        if self.game_state.lives > 0 {
+            log::info!("Lost a life; {} left", self.game_state.lives);
+            self.world = respawn(&mut self.game_state, &self.renderer);
+            self.camera = Camera::new();
+            self.events.push(GameEvent::Spawned {
+                position: (self.game_state.player_x, self.game_state.player_y),
+            });
+        } else {
+            log::info!("Game over");
+            self.app_state = AppState::GameOver(if self.game_state.continues > 0 {
+                GameOverChoice::Retry
+            } else {
+                GameOverChoice::ReturnToStart
+            });
+        }
+        true
+    }
+
+    /// Updates the game, then its systems, by a frame while the level is still being played.
+    ///
+    /// # Arguments
+    ///
+    /// * input - The input the player was moved by this frame.
+    /// * delta_time - Game time the frame took.
+    fn update_game(&mut self, input: &InputHandler, delta_time: f32) {
+        if self.app_state != AppState::Playing {
+            return;
+        }
+        let mut ctx = Ctx {
+            level: &self.world.level,
+            game_state: &mut self.game_state,
+            input,
+            camera: &mut self.camera,
+            effects: &mut self.effects,
+            audio: &self.audio,
+        };
+        self.game.update(&mut ctx, delta_time);
+        for system in &mut self.systems {
+            system.update(&mut ctx, delta_time);
+        }
+    }
+
+    /// Moves the camera after the player, and the props, effects, and level banner on
+    /// by a frame, recording the frame to scrub through in debug builds.
+    ///
+    /// # Arguments
+    ///
+    /// * delta_time - Game time the frame took.
+    fn update_view(&mut self, delta_time: f32) {
+        self.camera.update(delta_time);
+        self.camera.follow(self.game_state.drawn_position().0);
+        self.props.update(&self.camera, delta_time);
+        self.effects.update(delta_time);
+        if let Some(banner) = self.world.banner.as_mut() {
+            banner.update(delta_time);
+        }
+
+        #[cfg(debug_assertions)]
+        if delta_time > 0.0 {
+            let state = SimulationState::capture(&self.world, &self.game_state, &self.camera, delta_time);
+            self.world.scrubber.record(state);
+        }
+    }
+
+    /// Lets the game, then its systems, react to what happened this frame, such as with
+    /// effects and sounds.
+    fn send_events(&mut self) {
+        let mut ctx = Ctx {
+            level: &self.world.level,
+            game_state: &mut self.game_state,
+            input: &self.input_handler,
+            camera: &mut self.camera,
+            effects: &mut self.effects,
+            audio: &self.audio,
+        };
+        for event in self.events.drain(..) {
+            self.game.on_event(&mut ctx, event);
+            for system in &mut self.systems {
+                system.on_event(&mut ctx, event);
+            }
+        }
+    }
+
+    /// Crossfades the ambient loops for where the player is, and shifts the pitch of
+    /// music and effects along with game time.
+    fn update_audio(&mut self) {
+        // The menu has no ambience, so the loops fade out there
+        self.ambience.update(&self.world.ambience_zones, &self.game_state.bounds(), &self.audio);
+        self.audio.set_time_scale(self.time_scale.scale());
+        self.audio.update();
+    }
+
+    /// Builds the overlay: the engine's HUD and menus, then the game's and its systems'
+    /// own, faded out while switching scenes. Buttons clicked on it are acted on next frame.
+    fn render_overlay(&mut self) -> Overlay {
+        self.mouse.update(&self.input_handler);
+        let mut ui = Ui::new(&self.renderer.text, &self.mouse);
+        let controls = self.help_visible.then(|| self.input_handler.action_map());
+        self.clicked_action = build_overlay(
+            &mut ui,
+            self.app_state,
+            &self.world,
+            &self.game_state,
+            self.damage_flash.alpha(&self.flash_limiter),
+            &self.debug_hud,
+            controls,
+        );
+        self.game.render(&mut ui, &self.game_state, &self.camera);
+        for system in &mut self.systems {
+            system.render(&mut ui, &self.game_state, &self.camera);
+        }
+        if let AppState::Menu(selected) = self.app_state {
+            self.clicked_action = build_main_menu(&mut ui, selected, &self.main_menu, &self.level_records);
+        }
+        #[cfg(debug_assertions)]
+        {
+            if self.dev_menu_visible {
+                self.dev_action = build_dev_menu(&mut ui, &self.game_state.cheats);
+            }
+            if self.console.is_open() {
+                build_console(&mut ui, &self.console);
+            }
+        }
+
+        // Fade the screen to black and back while switching scenes, in the
+        // post-processing pass if there is one, or else with a panel over everything
+        // but the debug HUD
+        let fade = self.scenes.fade_alpha();
+        if fade > 0.0 && !self.renderer.effects_active() {
+            let fade_z = RenderLayer::Fade.depth(0);
+            ui.panel(Rect::new(0.0, 0.0, 2.0, 2.0), fade_z, [0.0, 0.0, 0.0, fade]);
+        }
+        let (quads, text) = ui.finish();
+        Overlay { quads, text }
+    }
+
+    /// Draws the frame: the world, the particles, and the overlay over them.
+    ///
+    /// # Arguments
+    ///
+    /// * overlay - The overlay built this frame.
+    /// * particle_delta_time - Game time the GPU particles move on by.
+    /// * entities_updated - How many entities were simulated this frame.
+    ///
+    /// # Returns
+    ///
+    /// The frame's rendering stats, and how long each part of it took.
+    fn render(
+        &mut self,
+        overlay: Overlay,
+        particle_delta_time: f32,
+        entities_updated: usize,
+    ) -> (EngineStats, FrameTimings) {
+        let mut timings = FrameTimings::default();
+        let instances = timings.time("PREP", || {
+            let mut instances = prepare_instances(
+                &self.world,
+                &self.backgrounds,
+                &self.props,
+                &self.game_state,
+                &self.player_sheet,
+                &self.camera,
+                overlay,
+            );
+            instances.particles = particle_instances(&self.effects, &self.camera);
+            instances
+        });
+
+        let camera = &self.camera;
+        self.renderer.update_globals(
+            self.start_time.elapsed().as_secs_f32(),
+            camera.x,
+            camera.y,
+            camera.zoom,
+            self.world.rewind.saturation(),
+            self.scenes.fade_alpha(),
+        );
+
+        // Start the level's weather when it changes, then hand the GPU particles this
+        // frame's bursts and time step
+        if let Some(particles) = self.renderer.particles.as_mut() {
+            let level_weather = self.world.level.property("weather");
+            if level_weather != self.weather.as_deref() {
+                self.weather = level_weather.map(str::to_string);
+                let weather_particles = match level_weather {
+                    Some(name) => self.effects.weather(name, camera.x, camera.y, camera.zoom),
+                    None => Vec::new(),
+                };
+                particles.set_weather(&self.renderer.queue, &weather_particles);
+            }
+            particles.spawn(&self.renderer.queue, &self.effects.take_gpu_spawns());
+            particles.update(
+                &self.renderer.queue,
+                particle_delta_time,
+                self.particle_rng.next_u32(),
+                camera.x,
+                camera.y,
+                camera.zoom,
+            );
+        }
+
+        let (mesh_index_count, bytes_uploaded) =
+            timings.time("UPLOAD", || update_instance_buffers(&mut self.renderer, &instances, &mut self.batcher));
+
+        // Skip drawing while the window is hidden and the game runs on regardless
+        let draw_calls = if self.renderer.is_visible() {
+            timings.time("DRAW", || render_frame(&self.renderer, &self.batcher, mesh_index_count, instances.ambient))
+        } else {
+            0
+        };
+        // Pick up the GPU's times for an earlier frame, if they've come back
+        if let Some(gpu) = self.renderer.gpu_timer.as_ref().and_then(|timer| timer.collect(&self.renderer.device)) {
+            timings.gpu = gpu;
+        }
+
+        let stats = EngineStats {
+            entities_updated,
+            instances: instances.instance_count(),
+            draw_calls,
+            bytes_uploaded,
+            collision_tests: take_collision_tests(),
+            audio_voices: self.audio.voice_count(),
+        };
+        (stats, timings)
+    }
+
+    /// Holds to the target frame rate, then moves the flashes and scene fades on by the
+    /// time the frame took, records its stats, and switches low-power mode.
+    ///
+    /// # Arguments
+    ///
+    /// * stats - The frame's rendering stats.
+    /// * timings - How long each part of the frame took.
+    /// * low_power_toggled - Whether low-power mode was toggled this frame.
+    fn end_frame(&mut self, stats: EngineStats, timings: &FrameTimings, low_power_toggled: bool) {
+        self.frame_pacer.wait();
+
+        let now = std::time::Instant::now();
+        let frame_time = (now - self.frame_start).as_secs_f32();
+        self.damage_flash.update(frame_time);
+        self.flash_limiter.update(frame_time);
+        self.scenes.update(frame_time);
+        self.debug_hud.record_frame(frame_time, stats, timings);
+
+        // Lower the frame rate and skip post-processing from the next frame while
+        // saving power; the GPU stays the one picked at startup
+        if self.power_saver.update(frame_time) || low_power_toggled {
+            let low_power = self.power_saver.is_low_power();
+            self.renderer.low_power = low_power;
+            self.frame_pacer.set_target_fps(self.power_saver.target_fps(tuning().target_fps));
+            log::info!("Low-power mode {}", if low_power { "on" } else { "off" });
+        }
+        self.frame_start = now;
+        self.input_handler.end_frame();
+    }
+}
+
+/// Creates the renderer on a background thread while the window keeps handling events.
+///
+/// The window's surface is created here on the main thread, as macOS requires; the
+/// background thread then requests the adapter and device, shows the splash screen,
+/// compiles the pipelines and uploads the textures. Until it's done the window stays
+/// responsive and its title says it's loading. If the configured graphics APIs have
+/// no adapter, this tries again with a new surface on any available one.
+///
+/// # Arguments
+///
+/// * event_loop - The event loop to pump while waiting.
+/// * window - The window to render to.
+/// * player_texture - The player's texture, loaded by the renderer.
+/// * background_layers - The background layers whose textures the renderer loads.
+/// * low_power - Whether to start out saving power, preferring the integrated GPU.
+///
+/// # Returns
+///
+/// The renderer, or `None` if the window was closed before it was ready.
+fn warm_up(
+    event_loop: &mut EventLoop<()>,
+    window: &Window,
+    player_texture: &str,
+    background_layers: &[BackgroundLayer],
+    low_power: bool,
+) -> Option<Renderer> {
+    let title = window.title();
+    window.set_title(&format!("{} (loading...)", title));
+
+    let started = std::time::Instant::now();
+    let size = window.inner_size();
+    let mut closed = false;
+    let mut renderer = None;
+    for (attempt, backends) in backend_choices().into_iter().enumerate() {
+        if attempt > 0 {
+            log::warn!(
+                "No {:?} adapter found; falling back to any available backend",
+                tuning().graphics_backend
+            );
+        }
+        let Some((instance, surface)) = Renderer::create_surface(window, backends) else {
+            continue;
+        };
+        renderer = std::thread::scope(|scope| {
+            let warmup = scope.spawn(|| {
+                block_on(Renderer::new(
+                    &instance,
+                    surface,
+                    (size.width, size.height),
+                    player_texture,
+                    background_layers,
+                    low_power,
+                ))
+            });
+
+            event_loop.run_return(|event, _, control_flow| {
+                // Check back often without spinning while the renderer loads
+                *control_flow = ControlFlow::WaitUntil(std::time::Instant::now() + std::time::Duration::from_millis(10));
+                match event {
+                    Event::WindowEvent { event: WindowEvent::CloseRequested, .. } => {
+                        // Hide the window straight away; the loading still has to finish
+                        window.set_visible(false);
+                        closed = true;
+                        *control_flow = ControlFlow::Exit;
+                    }
+                    Event::MainEventsCleared if warmup.is_finished() => *control_flow = ControlFlow::Exit,
+                    _ => {}
+                }
+            });
+
+            warmup.join().expect("Renderer warmup panicked.")
+        });
+        if renderer.is_some() || closed {
+            break;
+        }
+    }
+
+    window.set_title(&title);
+    if closed {
+        return None;
+    }
+    let renderer = renderer.expect("No graphics adapter found.");
+    log::info!("Renderer ready after {:.2}s", started.elapsed().as_secs_f32());
+    Some(renderer)
+}
+
+/// How a run of the rendering regression tests went.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RenderTestResult {
+    /// Every scene matched its reference, or was written as one.
+    Passed,
+    /// Some scene strayed from its reference, or couldn't be compared or written.
+    Failed,
+    /// There's no graphics adapter, even headless, so no scene was drawn.
+    NoAdapter,
+}
+
+/// Draws a fixed set of scenes headlessly and compares them against reference images,
+/// guarding against shader and pipeline regressions.
+///
+/// Each scene is drawn once, without a window, into an offscreen target, with the
+/// built-in tuning and without mods so the result depends only on the game's code and
+/// assets. Scenes whose images stray from their references by more than the
+/// tolerances fail; their images and the differing pixels are written to
+/// `RENDER_TEST_OUTPUT_DIR` to look at.
+///
+/// # Arguments
+///
+/// * update_references - Whether to replace the reference images with this run's,
+///   after an intended change to how scenes look, rather than comparing.
+///
+/// # Returns
+///
+/// Whether every scene matched its reference, or was written as one, or that there
+/// was no adapter to draw them with.
+pub fn run_render_tests(update_references: bool) -> RenderTestResult {
+    let (player_sheet, player_texture) = RENDER_TEST_PLAYER;
+    let assets = AssetManifest {
+        levels: RENDER_TEST_LEVELS.iter().map(ToString::to_string).collect(),
+        ..AssetManifest::new(player_sheet, player_texture)
+    };
+    let background_layers = load_background_layers(&assets.backgrounds);
+    let Some(mut renderer) = block_on(Renderer::headless(RENDER_TEST_SIZE, player_texture, &background_layers)) else {
+        eprintln!("No graphics adapter found; the rendering tests need one, even headless");
+        return RenderTestResult::NoAdapter;
+    };
+    let target = OffscreenTarget::new(&renderer.device, &renderer.config);
+    let references = std::path::Path::new(RENDER_TEST_REFERENCES_DIR);
+    let output = std::path::Path::new(RENDER_TEST_OUTPUT_DIR);
+
+    let mut passed = 0;
+    for (name, scene) in RENDER_TEST_SCENES {
+        draw_test_scene(&mut renderer, &assets, &background_layers, scene, &target.view);
+        let image = target.read(&renderer.device, &renderer.queue);
+        let reference_path = references.join(format!("{}.png", name));
+
+        if update_references {
+            let _ = std::fs::create_dir_all(references);
+            match image.save(&reference_path) {
+                Ok(()) => {
+                    println!("{}: wrote '{}'", name, reference_path.display());
+                    passed += 1;
+                }
+                Err(e) => eprintln!("{}: could not write '{}': {}", name, reference_path.display(), e),
+            }
+            continue;
+        }
+
+        let failure = match image::open(&reference_path) {
+            Ok(reference) => match compare_images(&image, &reference.to_rgba8(), RENDER_TEST_CHANNEL_TOLERANCE) {
+                Some(difference) if difference.differing_share() <= RENDER_TEST_MAX_DIFFERING => {
+                    println!(
+                        "{}: ok ({} pixels differ, by at most {})",
+                        name, difference.differing_pixels, difference.max_difference
+                    );
+                    passed += 1;
+                    continue;
+                }
+                Some(difference) => {
+                    let diff_path = output.join(format!("{}.diff.png", name));
+                    let saved = std::fs::create_dir_all(output).is_ok() && difference.highlighted.save(&diff_path).is_ok();
+                    format!(
+                        "{:.2}% of pixels differ, by up to {}{}",
+                        difference.differing_share() * 100.0,
+                        difference.max_difference,
+                        if saved { format!("; marked in '{}'", diff_path.display()) } else { String::new() }
+                    )
+                }
+                None => format!("the image is {:?}, the reference {:?}", image.dimensions(), (reference.width(), reference.height())),
+            },
+            Err(e) => format!(
+                "could not load reference '{}' ({}); run with --update to write it",
+                reference_path.display(),
+                e
+            ),
+        };
+        let image_path = output.join(format!("{}.png", name));
+        if std::fs::create_dir_all(output).is_ok() && image.save(&image_path).is_ok() {
+            eprintln!("{}: FAILED: {}; drawn as '{}'", name, failure, image_path.display());
+        } else {
+            eprintln!("{}: FAILED: {}", name, failure);
+        }
+    }
+
+    println!("{} of {} scenes passed", passed, RENDER_TEST_SCENES.len());
+    if passed == RENDER_TEST_SCENES.len() {
+        RenderTestResult::Passed
+    } else {
+        RenderTestResult::Failed
+    }
+}
+
+/// Draws the first frame of a test scene into a target, with nothing random or timed
+/// in it, so the same code and assets always draw the same image.
+///
+/// # Arguments
+///
+/// * renderer - The renderer to draw with.
+/// * assets - The assets the scenes are drawn from.
+/// * background_layers - The background layers the renderer was created with.
+/// * scene - The scene to draw.
+/// * target - The view to draw into, the size of the renderer's configuration.
+fn draw_test_scene(
+    renderer: &mut Renderer,
+    assets: &AssetManifest,
+    background_layers: &[BackgroundLayer],
+    scene: TestScene,
+    target: &wgpu::TextureView,
+) {
+    let player_sheet = load_player_sheet(renderer, &assets.player_sheet);
+    let mut game_state = GameState::with_sprite_sheet(&player_sheet);
+    game_state.reseed(RENDER_TEST_SEED);
+    let (world, app_state) = match scene {
+        TestScene::Menu => (menu_world(renderer, &mut game_state), AppState::Menu(0)),
+        TestScene::Level(path) => (enter_level(path, renderer, &mut game_state), AppState::Playing),
+    };
+    let mut camera = Camera::new();
+    camera.follow(game_state.player_x);
+    let props = Props::new(&camera, Rng::new(RENDER_TEST_SEED));
+    let backgrounds = Backgrounds {
+        instances: create_background_instances(renderer, background_layers),
+        layers: background_layers.to_vec(),
+    };
+
+    // The overlay as the game draws it, with the mouse away from every button
+    let mouse = Mouse::new();
+    let mut ui = Ui::new(&renderer.text, &mouse);
+    build_overlay(&mut ui, app_state, &world, &game_state, 0.0, &DebugHud::new(), None);
+    if let AppState::Menu(selected) = app_state {
+        let main_menu = MainMenu {
+            levels: assets.levels.clone(),
+            mods: ModList::default(),
+        };
+        build_main_menu(&mut ui, selected, &main_menu, &LevelRecords::default());
+    }
+    let (quads, text) = ui.finish();
+    let instances = prepare_instances(
+        &world,
+        &backgrounds,
+        &props,
+        &game_state,
+        &player_sheet,
+        &camera,
+        Overlay { quads, text },
+    );
+
+    renderer.update_globals(0.0, camera.x, camera.y, camera.zoom, world.rewind.saturation(), 0.0);
+    let mut batcher = InstanceBatcher::new();
+    let (mesh_index_count, _) = update_instance_buffers(renderer, &instances, &mut batcher);
+    draw_frame(renderer, &batcher, mesh_index_count, instances.ambient, target);
+}
+
+/// Reads the background layers, slicing any banded image into its layers, falling
+/// back to the built-in layers.
+///
+/// # Arguments
+///
+/// * path - The backgrounds config to read.
+fn load_background_layers(path: &str) -> Vec<BackgroundLayer> {
+    load_backgrounds(path).unwrap_or_else(|e| {
+        log::warn!("Could not load backgrounds '{}': {}", path, e);
+        default_backgrounds()
+    })
+}
+
+/// Loads the player's sprite sheet, falling back to the built-in frame ranges on a
+/// strip of square frames as tall as the texture.
+///
+/// # Arguments
+///
+/// * renderer - Provides the size of the player's texture.
+/// * path - The Aseprite export of the sheet.
+fn load_player_sheet(renderer: &Renderer, path: &str) -> SpriteSheet {
+    load_aseprite_sheet(path).unwrap_or_else(|e| {
+        log::warn!("Could not load sprite sheet '{}': {}", path, e);
+        let (width, height) = renderer.texture_size;
+        SpriteSheet::grid((width / height.max(1)).max(1) as usize, 1, tuning().animation_speed)
+    })
+}
+
+/// Loads a level from its Tiled export, falling back to a flat ground strip.
+///
+/// # Arguments
+///
+/// * path - Path to the Tiled map.
+/// * renderer - Provides the tileset dimensions.
+fn load_level(path: &str, renderer: &Renderer) -> Level {
+    load_tiled_map(path, 0.3, 0.3, renderer.tileset_columns, renderer.tileset_rows).unwrap_or_else(|e| {
+        log::warn!("Could not load level '{}': {}", path, e);
+        Level::flat_ground(0.3, 0.3, renderer.tileset_columns, renderer.tileset_rows)
+    })
+}
+
+/// Reloads assets that changed on disk into the running game.
+///
+/// Textures are updated in place. When the current level's map changes, the world is
+/// rebuilt from it around the player, without replaying the level banner. A map that
+/// fails to load, such as one caught mid-save, leaves the current world in place.
+///
+/// # Arguments
+///
+/// * paths - The asset files that changed.
+/// * renderer - Provides the GPU queue and the tileset dimensions.
+/// * world - The world to rebuild if its level changed.
+/// * game_state - The player the world is rebuilt around.
+fn reload_changed_assets(paths: &[String], renderer: &Renderer, world: &mut World, game_state: &mut GameState) {
+    for path in paths {
+        if *path == game_state.level && world.has_player {
+            match load_tiled_map(path, 0.3, 0.3, renderer.tileset_columns, renderer.tileset_rows) {
+                Ok(level) => {
+                    *world = build_world(level, game_state);
+                    world.banner = None;
+                    log::info!("Reloaded level '{}'", path);
+                }
+                Err(e) => log::warn!("Could not reload level '{}': {}", path, e),
+            }
+        } else if path.ends_with(".png") {
+            // Sprite textures are drawn from their copies in the sprite array
+            if reload_texture(&renderer.queue, path) {
+                renderer.sprites.refresh(&renderer.device, &renderer.queue);
+            }
+        }
+    }
+}
+
+/// Starts a level from its spawn point.
+///
+/// The start of the level is saved as the first checkpoint, replacing any checkpoint
+/// left over from earlier play.
+///
+/// # Arguments
+///
+/// * path - Path to the Tiled map.
+/// * renderer - Provides the tileset dimensions.
+/// * game_state - The player to place in the level.
+fn start_level(path: &str, renderer: &Renderer, game_state: &mut GameState) -> World {
+    let world = enter_level(path, renderer, game_state);
+    save_checkpoint(game_state);
+    world
+}
+
+/// Loads a level and places the player at its spawn point, without saving a checkpoint.
+///
+/// # Arguments
+///
+/// * path - Path to the level's Tiled map.
+/// * renderer - Provides the tileset dimensions.
+/// * game_state - The player to place in the level.
+fn enter_level(path: &str, renderer: &Renderer, game_state: &mut GameState) -> World {
+    let level = load_level(path, renderer);
+    game_state.level = path.to_string();
+
+    // Stand the player on the level's spawn point, or on the ground at its start if it
+    // has none, rather than wherever they were before
+    let half_height = game_state.player_y - game_state.bounds().min_y;
+    match level.objects_of_kind("player_spawn").next() {
+        Some(spawn) => game_state.teleport(spawn.center().0, spawn.bounds.min_y + half_height),
+        None => game_state.teleport(0.0, GROUND_LEVEL + half_height),
+    }
+
+    build_world(level, game_state)
+}
+
+/// Saves every level's statistics, so they're kept whatever happens to the game after.
+///
+/// # Arguments
+///
+/// * records - The statistics to save.
+fn save_level_records(records: &LevelRecords) {
+    if let Err(e) = records.save(LEVEL_RECORDS_PATH) {
+        log::warn!("Could not save level records to '{}': {}", LEVEL_RECORDS_PATH, e);
+    }
+}
+
+/// Saves the player's progress as the point to return to after losing a life.
+///
+/// # Arguments
+///
+/// * game_state - The progress to save.
+fn save_checkpoint(game_state: &GameState) {
+    if let Err(e) = game_state.save(CHECKPOINT_SAVE_PATH) {
+        log::warn!("Could not save checkpoint to '{}': {}", CHECKPOINT_SAVE_PATH, e);
+    }
+}
+
+/// Returns the player to the last checkpoint, rebuilding its level.
+///
+/// Lives and continues carry over rather than being restored from the checkpoint.
+/// If the checkpoint can't be read, the level starts over from its spawn point.
+///
+/// # Arguments
+///
+/// * game_state - The player to return to the checkpoint.
+/// * renderer - Provides the tileset dimensions.
+fn respawn(game_state: &mut GameState, renderer: &Renderer) -> World {
+    let (lives, continues) = (game_state.lives, game_state.continues);
+    let world = match game_state.load(CHECKPOINT_SAVE_PATH) {
+        Ok(()) => {
+            let mut world = build_world(load_level(&game_state.level, renderer), game_state);
+            // The level is already underway, so don't announce it again
+            world.banner = None;
+            world
+        }
+        Err(e) => {
+            log::warn!("Could not load checkpoint from '{}': {}", CHECKPOINT_SAVE_PATH, e);
+            let level = game_state.level.clone();
+            start_level(&level, renderer, game_state)
+        }
+    };
+    game_state.lives = lives;
+    game_state.continues = continues;
+    world
+}
+
+/// Logs the tile and level objects at a world position, for picking things out with the mouse.
+///
+/// # Arguments
+///
+/// * level - The level to look in.
+/// * x, y - The world position picked.
+#[cfg(debug_assertions)]
+fn log_picked(level: &Level, x: f32, y: f32) {
+    let tile = level.tile_map.tile_at(x, y).map(|tile| tile.tile_index);
+    let objects: Vec<String> = level
+        .objects_at(x, y)
+        .map(|object| format!("{} '{}' (id {})", object.kind, object.name, object.id))
+        .collect();
+    log::info!("Picked ({:.2}, {:.2}): tile {:?}, objects [{}]", x, y, tile, objects.join(", "));
+}
+
+/// Acts on a choice made from the developer menu.
+///
+/// # Arguments
+///
+/// * action - The developer menu's choice.
+/// * game_state - The player whose cheats are toggled.
+/// * world - The level whose doors are unlocked.
+/// * scenes - Switches to the next level when skipping the level.
+#[cfg(debug_assertions)]
+fn apply_dev_action(action: DevAction, game_state: &mut GameState, world: &World, scenes: &mut SceneManager) {
+    let toggle = |name: &str, cheat: &mut bool| {
+        *cheat = !*cheat;
+        log::info!("{} {}", name, if *cheat { "on" } else { "off" });
+    };
+    match action {
+        DevAction::ToggleGodMode => toggle("God mode", &mut game_state.cheats.god_mode),
+        DevAction::ToggleNoclip => toggle("Noclip", &mut game_state.cheats.noclip),
+        DevAction::ToggleInfiniteJumps => toggle("Infinite jumps", &mut game_state.cheats.infinite_jumps),
+        DevAction::UnlockDoors => {
+            for door in &world.doors.doors {
+                game_state.set_object_on(&door.id, true);
+            }
+            log::info!("Unlocked {} doors", world.doors.doors.len());
+        }
+        DevAction::SkipLevel => match world.level.property("next_level") {
+            Some(next) => {
+                log::info!("Skipping to level '{}'", next);
+                scenes.switch_to(Scene::Level(next.to_string()));
+            }
+            None => log::info!("Level '{}' has no next_level to skip to", game_state.level),
+        },
+    }
+}
+
+/// Builds the level's features around the player, who takes on the level's physics.
+///
+/// # Arguments
+///
+/// * level - The level to build the world from.
+/// * game_state - The player, whose position decides the starting room.
+fn build_world(mut level: Level, game_state: &mut GameState) -> World {
+    // Shade the ground's depths and the space under its ceilings
+    shading::bake(&mut level.tile_map, tuning().tile_shading);
+    // Levels without physics of their own go back to the tuned values
+    game_state.set_physics(Physics::from_level(&level));
+    // Set up the level's turrets, with nothing yet in flight, and scatter its coins and gems
+    let projectiles = Projectiles::from_level(&level);
+    let collectibles = Collectibles::from_level(&level);
+    // Count what the objectives ask for among them
+    let objectives = Objectives::from_level(&level, game_state, &collectibles, &projectiles);
+    World {
+        // Initialize the rooms the camera follows the player through
+        room_manager: RoomManager::from_level(&level, game_state),
+        // Initialize the secret areas hidden behind foreground tiles
+        secret_areas: SecretAreas::from_level(&level),
+        // Find the water surfaces
+        water: Water::from_tile_map(&level.water),
+        // Start with a full breath, and find the air bubbles to breathe from underwater
+        oxygen: Oxygen::new(),
+        air_bubbles: AirBubbles::from_level(&level),
+        // Link up the level's portals
+        portals: Portals::from_level(&level),
+        // Park the level's mounts
+        mounts: Mounts::from_level(&level),
+        // Hang the level's ropes, chains, and bridges
+        ropes: Ropes::from_level(&level),
+        // Set up the level's jelly platforms
+        jelly_platforms: JellyPlatforms::from_level(&level),
+        // Start the level's moving platforms along their paths
+        moving_platforms: MovingPlatforms::from_level(&level),
+        // Find the slopes the player walks up and down
+        slopes: Slopes::from_level(&level),
+        // Mark out where the camera zooms and refocuses
+        camera_zones: CameraZones::from_level(&level),
+        // Mark out where ambient loops play under the music
+        ambience_zones: AmbienceZones::from_level(&level),
+        // Find the level's cutscene triggers
+        cutscenes: Cutscenes::from_level(&level),
+        // Load the scripts run by the level's triggers and turrets
+        scripts: Scripts::from_level(&level),
+        // Find the level's hazards and checkpoints
+        hazards: Hazards::from_level(&level),
+        checkpoints: Checkpoints::from_level(&level),
+        projectiles,
+        collectibles,
+        // Wire up the level's switches and the doors they open
+        switches: Switches::from_level(&level),
+        doors: Doors::from_level(&level),
+        // Put up the level's signs, to read
+        signs: Signs::from_level(&level),
+        // Find the ways out to the next level
+        exits: LevelExits::from_level(&level),
+        objectives,
+        // Find the level's ambient light and the lights in the dark
+        lighting: Lighting::from_level(&level),
+        // Dress the bare ground up with scattered decorations
+        decorations: Decorations::scatter(&level, decorations(), TILESET_PATH, game_state.seed()),
+        // Announce the level by name
+        banner: LevelBanner::from_level(&level),
+        // Start recording the player's movement afresh
+        rewind: Rewind::new(),
+        #[cfg(debug_assertions)]
+        scrubber: Scrubber::new(),
+        level,
+        has_player: true,
+    }
+}
+
+/// Builds the backdrop shown behind the level-select menu: flat ground, without the player.
+///
+/// # Arguments
+///
+/// * renderer - Provides the tileset dimensions.
+/// * game_state - The player, left out of the backdrop.
+fn menu_world(renderer: &Renderer, game_state: &mut GameState) -> World {
+    let level = Level::flat_ground(0.3, 0.3, renderer.tileset_columns, renderer.tileset_rows);
+    World {
+        has_player: false,
+        ..build_world(level, game_state)
+    }
+}
+
+/// Handles window-related events such as closing the application, resizing, and keyboard and mouse input.
+///
+/// # Arguments
+///
+/// * event - The event triggered by the window.
+/// * control_flow - Used to control the flow of the event loop.
+/// * input_handler - The input handler to update with keyboard and mouse inputs.
+/// * renderer - The renderer to reconfigure when the window is resized.
+/// * backgrounds - The background layers, whose instances are rescaled to the new window size.
+fn handle_window_event(
+    event: WindowEvent,
+    control_flow: &mut ControlFlow,
+    input_handler: &mut InputHandler,
+    renderer: &mut Renderer,
+    backgrounds: &mut Backgrounds,
+) {
+    match event {
+        WindowEvent::CloseRequested => *control_flow = ControlFlow::Exit,
+        WindowEvent::KeyboardInput { input, .. } => input_handler.handle_keyboard_input(input),
+        WindowEvent::ReceivedCharacter(character) => input_handler.handle_received_character(character),
+        WindowEvent::MouseInput { state, button, .. } => input_handler.handle_mouse_input(state, button),
+        WindowEvent::MouseWheel { delta, .. } => input_handler.handle_mouse_wheel(delta),
+        WindowEvent::CursorMoved { position, .. } => {
+            // Measure the cursor against the area drawn to, which letterboxing can shrink
+            let [x, y, width, height] = renderer.viewport();
+            let position = winit::dpi::PhysicalPosition::new(position.x - x as f64, position.y - y as f64);
+            let viewport_size = winit::dpi::PhysicalSize::new(width as u32, height as u32);
+            input_handler.handle_cursor_moved(position, viewport_size);
+        }
+        WindowEvent::CursorLeft { .. } => input_handler.handle_cursor_left(),
+        WindowEvent::Occluded(occluded) => renderer.set_occluded(occluded),
+        WindowEvent::Resized(new_size) => {
+            renderer.resize(new_size);
+            backgrounds.instances = create_background_instances(renderer, &backgrounds.layers);
+        }
+        WindowEvent::ScaleFactorChanged { new_inner_size, .. } => {
+            renderer.resize(*new_inner_size);
+            backgrounds.instances = create_background_instances(renderer, &backgrounds.layers);
+        }
+        _ => {}
+    }
+}
+
+/// Loads a sound, logging a warning and returning `None` if it can't be read.
+///
+/// # Arguments
+///
+/// * path - Path to the WAV file.
+fn load_sound(path: &str) -> Option<Arc<Sound>> {
+    match load_wav(path) {
+        Ok(sound) => Some(Arc::new(sound)),
+        Err(e) => {
+            log::warn!("Could not load sound '{}': {}", path, e);
+            None
+        }
+    }
+}
+
+/// Creates the instance data for the background layers, scaled to fill the window.
+///
+/// Each layer gets one unscrolled instance; `scroll_backgrounds` tiles and scrolls
+/// them with the camera every frame. Layers drawing a band of their image are
+/// placed where the band sits within the whole image.
+///
+/// # Arguments
+///
+/// * renderer - Provides the background textures and the size frames are drawn at.
+/// * layers - The background layers, from the farthest to the nearest.
+fn create_background_instances(renderer: &Renderer, layers: &[BackgroundLayer]) -> Vec<InstanceData> {
+    // Calculate scaling factors for each background layer based on their image sizes
+    let (window_width, window_height) = renderer.render_size();
+    let (window_width, window_height) = (window_width as f32, window_height as f32);
+
+    let mut background_instances = Vec::new();
+
+    for (i, (bg_texture, layer)) in renderer.background_textures.iter().zip(layers).enumerate() {
+        let background_scale_x = window_width / bg_texture.width as f32;
+        let background_scale_y = window_height / bg_texture.height as f32;
+
+        // Furthest layer at z=1.0, closer layers decreasing z down to 0.6
+        let z = 1.0 - 0.4 * i as f32 / (layers.len().max(2) - 1) as f32;
+        let [top, bottom] = layer.band;
+        let band_y = background_scale_y * (0.5 - (top + bottom) / 2.0);
+        let band_height = background_scale_y * (bottom - top);
+
+        background_instances.push(InstanceData {
+            transform: Renderer::create_transform_matrix(
+                0.0,                  // x position
+                band_y,               // y position of the band
+                z,                    // z depth
+                background_scale_x,   // scale_x to fill the window
+                band_height,          // scale_y of the band within the scaled image
+            ),
+            sprite_index: 0.0,
+            layer: 0.0,
+            sprite_size: [1.0, 1.0],
+            uv_offset: [0.0, top],
+            uv_scale: [1.0, bottom - top],
+            color: [1.0, 1.0, 1.0, 1.0],
+        });
+    }
+
+
+    background_instances
+}
+
+/// Scrolls the background layers with the camera, each by its parallax factor.
+///
+/// Layers wrap horizontally, so each is drawn as many times side by side as it
+/// takes to cover the screen wherever the camera is.
+///
+/// # Arguments
+///
+/// * backgrounds - The background layers and the unscrolled instance of each.
+/// * camera - The camera whose horizontal position scrolls the layers.
+///
+/// # Returns
+///
+/// The instances of each layer, from the farthest to the nearest.
+fn scroll_backgrounds(backgrounds: &Backgrounds, camera: &Camera) -> Vec<Vec<InstanceData>> {
+    backgrounds
+        .instances
+        .iter()
+        .zip(&backgrounds.layers)
+        .map(|(unscrolled, layer)| {
+            let width = unscrolled.transform[0][0];
+            // Enough copies to span the screen (2.0 in clip space) from any offset
+            let copies = (2.0 / width).ceil() as usize + 1;
+            let left = -1.0 - (camera.x * layer.parallax).rem_euclid(width);
+
+            (0..copies)
+                .map(|copy| {
+                    let mut instance = *unscrolled;
+                    instance.transform[3][0] = left + (copy as f32 + 0.5) * width;
+                    instance
+                })
+                .collect()
+        })
+        .collect()
+}
+
+/// Measures the game time the frame took, to run the simulation's fixed steps for.
+///
+/// # Arguments
+///
+/// * last_frame_time - Tracks the time of the last frame for calculating delta time.
+/// * time_scale - Scales the real time between frames into game time.
+///
+/// # Returns
+///
+/// The game time delta between the current and the last frame.
+fn advance_clock(last_frame_time: &mut std::time::Instant, time_scale: &mut TimeScale) -> f32 {
+    let now = std::time::Instant::now();
+    let delta_time = time_scale.update(now.duration_since(*last_frame_time).as_secs_f32());
+    *last_frame_time = now;
+    delta_time
+}
+
+/// Steps the player back through the rewind recording by the game time since the last frame.
+///
+/// # Arguments
+///
+/// * rewind - The recording of the player's recent movement.
+/// * game_state - The player to move back.
+/// * last_frame_time - Tracks the time of the last frame for calculating delta time.
+/// * time_scale - Scales the real time between frames into game time, so rewinding
+///   slows down in slow motion and stops during hitstop.
+fn rewind_game_state(
+    rewind: &mut Rewind,
+    game_state: &mut GameState,
+    last_frame_time: &mut std::time::Instant,
+    time_scale: &mut TimeScale,
+) {
+    let now = std::time::Instant::now();
+    let delta_time = time_scale.update(now.duration_since(*last_frame_time).as_secs_f32());
+    *last_frame_time = now;
+
+    rewind.rewind(game_state, delta_time);
+}
+
+/// Instance data for everything drawn in a frame, grouped by draw call.
+///
+/// Within a group, instances are emitted in the order their system keeps its
+/// entities (level file order, or spawn order for projectiles and particles),
+/// so sprites at the same depth overlap the same way every frame.
+struct FrameInstances {
+    /// The light reaching everywhere, or `None` if the level is fully lit.
+    ambient: Option<[f32; 3]>,
+    /// Lights added onto the light map, each a circle of light; empty if the level is fully lit.
+    lights: Vec<InstanceData>,
+    /// Background layers from the farthest to the nearest, each tiled across the screen.
+    backgrounds: Vec<Vec<InstanceData>>,
+    /// Decorative props drifting between the background layers.
+    props: Vec<InstanceData>,
+    /// Level tiles, drawn behind the player.
+    tiles: Vec<InstanceData>,
+    /// Decorations standing on the tiles, drawn from the decoration sheet.
+    decorations: Vec<InstanceData>,
+    /// The single instance drawing `jelly_mesh`, if there are any jelly platforms.
+    jelly: Vec<InstanceData>,
+    /// The deformed shapes of the jelly platforms, drawn from the tileset among the tiles.
+    jelly_mesh: Mesh,
+    /// Moving platforms, drawn from the tileset among the tiles.
+    platforms: Vec<InstanceData>,
+    /// Blob shadows on the ground under the player, mounts, and collectibles.
+    shadows: Vec<InstanceData>,
+    /// Portals, drawn with the portal material behind the player.
+    portals: Vec<InstanceData>,
+    /// Rope segments, drawn from the tileset behind mounts and the player.
+    ropes: Vec<InstanceData>,
+    /// Mounts, drawn from the tileset just behind the player.
+    mounts: Vec<InstanceData>,
+    /// Projectiles in flight, drawn from the projectile sprite strip behind the player.
+    projectiles: Vec<InstanceData>,
+    /// The player sprite.
+    player: Vec<InstanceData>,
+    /// Effect particles, drawn as solid squares just in front of the player.
+    particles: Vec<InstanceData>,
+    /// Water cells, drawn with the water material over the player.
+    water: Vec<InstanceData>,
+    /// Foreground tiles drawn in front of the player, such as secret-area covers.
+    foreground: Vec<InstanceData>,
+    /// Screen-space elements drawn over the whole scene, such as the pause screen.
+    overlay: Overlay,
+}
+
+impl FrameInstances {
+    /// Returns the instance groups in the order they're laid out in the instance buffer,
+    /// after the backgrounds, each with the sprite array layer it samples, if any.
+    fn groups(&self) -> [(&[InstanceData], Option<SpriteLayer>); 16] {
+        [
+            (&self.props, Some(SpriteLayer::Props)),
+            (&self.tiles, Some(SpriteLayer::Tileset)),
+            (&self.decorations, Some(SpriteLayer::Decorations)),
+            (&self.jelly, Some(SpriteLayer::Tileset)),
+            (&self.platforms, Some(SpriteLayer::Tileset)),
+            (&self.shadows, Some(SpriteLayer::Shadow)),
+            (&self.portals, None),
+            (&self.ropes, Some(SpriteLayer::Tileset)),
+            (&self.mounts, Some(SpriteLayer::Tileset)),
+            (&self.projectiles, Some(SpriteLayer::Projectiles)),
+            (&self.player, Some(SpriteLayer::Player)),
+            (&self.particles, Some(SpriteLayer::Solid)),
+            (&self.water, None),
+            (&self.foreground, Some(SpriteLayer::Tileset)),
+            (&self.overlay.quads, Some(SpriteLayer::Solid)),
+            (&self.overlay.text, Some(SpriteLayer::Font)),
+        ]
+    }
+
+    /// Pushes the lights, as the light map is drawn before the scene, then the backgrounds,
+    /// one batch each, then every group as a batch, in the order they're drawn, each with
+    /// the sprite array layer it samples.
+    ///
+    /// # Arguments
+    ///
+    /// * batcher - The batcher to push into, cleared first.
+    fn batch(&self, batcher: &mut InstanceBatcher) {
+        batcher.clear();
+        batcher.push(&self.lights, None);
+        for (index, layer) in self.backgrounds.iter().enumerate() {
+            batcher.push(layer, Some(SpriteLayer::Background(index)));
+        }
+        for (group, layer) in self.groups() {
+            batcher.push(group, layer);
+        }
+    }
+
+    /// Returns the total number of instances across the lights, the backgrounds, and all groups.
+    fn instance_count(&self) -> usize {
+        let backgrounds: usize = self.backgrounds.iter().map(Vec::len).sum();
+        self.lights.len() + backgrounds + self.groups().iter().map(|(group, _)| group.len()).sum::<usize>()
+    }
+}
+
+/// A choice made from a menu on the overlay.
+#[derive(Clone, Copy)]
+enum MenuAction {
+    /// Leave the pause menu.
+    Resume,
+    /// Take one of the game-over screen's choices.
+    GameOver(GameOverChoice),
+    /// Start a new game in the level picked from the level-select menu, by its index.
+    StartLevel(usize),
+    /// Switch a mod on or off from the level-select menu, by its index in the mod list.
+    ToggleMod(usize),
+    /// Leave the level for the level-select menu, from the pause menu.
+    QuitToMenu,
+    /// Leave the level-complete screen for where the level's exit leads.
+    Continue,
+}
+
+/// Draws the developer menu on the right of the screen, with a button for each
+/// cheat showing whether it is on and buttons to unlock the doors and skip the level.
+/// Unlocking the doors stands in for giving the player every key, as doors are opened
+/// by switches rather than keys.
+///
+/// # Arguments
+///
+/// * ui - The UI to draw the menu with.
+/// * cheats - The cheats currently on.
+///
+/// # Returns
+///
+/// The developer menu choice clicked this frame, if any.
+#[cfg(debug_assertions)]
+fn build_dev_menu(ui: &mut Ui, cheats: &Cheats) -> Option<DevAction> {
+    let panel_z = RenderLayer::Menu.depth(0);
+    let button_z = RenderLayer::Menu.depth(1);
+
+    let on_off = |on: bool| if on { "ON" } else { "OFF" };
+    let entries = [
+        (DevAction::ToggleGodMode, format!("GOD MODE {}", on_off(cheats.god_mode)), cheats.god_mode),
+        (DevAction::ToggleNoclip, format!("NOCLIP {}", on_off(cheats.noclip)), cheats.noclip),
+        (
+            DevAction::ToggleInfiniteJumps,
+            format!("INFINITE JUMPS {}", on_off(cheats.infinite_jumps)),
+            cheats.infinite_jumps,
+        ),
+        (DevAction::UnlockDoors, "UNLOCK ALL DOORS".to_string(), false),
+        (DevAction::SkipLevel, "SKIP LEVEL".to_string(), false),
+    ];
+
+    let (x, top, row_height) = (0.6, 0.55, 0.14);
+    let height = row_height * (entries.len() + 1) as f32 + 0.04;
+    ui.panel(Rect::new(x, top - height / 2.0, 0.7, height), panel_z, [0.05, 0.05, 0.1, 0.9]);
+
+    let title_style = TextStyle {
+        height: 0.05,
+        color: [1.0, 0.95, 0.8, 1.0],
+        z: button_z,
+        align: TextAlign::Center,
+    };
+    ui.label("DEV MENU", (x, top - row_height / 2.0), &title_style);
+
+    // Cheats that are on are highlighted
+    let mut clicked = None;
+    for (row, (action, label, on)) in entries.iter().enumerate() {
+        let button = Button {
+            label,
+            rect: Rect::new(x, top - (row as f32 + 1.5) * row_height, 0.62, 0.11),
+            z: button_z,
+            selected: *on,
+            enabled: true,
+        };
+        if ui.button(&button) {
+            clicked = Some(*action);
+        }
+    }
+    clicked
+}
+
+/// Draws the console along the bottom of the screen: the command being typed, under
+/// what the console said about the last one.
+///
+/// # Arguments
+///
+/// * ui - The UI to draw the console with.
+/// * console - The open console.
+#[cfg(debug_assertions)]
+fn build_console(ui: &mut Ui, console: &Console) {
+    let (left, bottom, row_height) = (-0.95, -0.95, 0.08);
+    let panel = Rect::new(0.0, bottom + row_height, 2.0, row_height * 2.0 + 0.04);
+    ui.panel(panel, RenderLayer::Menu.depth(0), [0.05, 0.05, 0.1, 0.9]);
+
+    let style = TextStyle {
+        height: 0.045,
+        color: [1.0, 0.95, 0.8, 1.0],
+        z: RenderLayer::Menu.depth(1),
+        align: TextAlign::Left,
+    };
+    ui.label(&console.reply().to_uppercase(), (left, bottom + row_height * 1.5), &style);
+    ui.label(&format!("> {}_", console.line().to_uppercase()), (left, bottom + row_height * 0.5), &style);
+}
+
+/// Screen-space instances drawn over the whole scene, grouped by texture.
+struct Overlay {
+    /// Solid colored quads, drawn with the white texture.
+    quads: Vec<InstanceData>,
+    /// Text glyphs, drawn with the font texture over the quads.
+    text: Vec<InstanceData>,
+}
+
+/// Builds the screen-space overlay for the current application state with the UI.
+///
+/// During cutscenes this draws letterbox bars across the top and bottom of the screen,
+/// and while playing it draws the score, the lives left and, while they are showing, the level banner
+/// and the sign being read.
+/// While paused, it dims the scene and draws a panel with a pause symbol and buttons to resume
+/// or quit to the menu instead, after a game over it draws the game-over screen with a button
+/// for each choice, on finishing a level it draws a summary of the run with a button to go on,
+/// and on the menu it draws the level select with a button for each level.
+/// A red vignette flashes over the scene when the player loses a life, and the list of
+/// controls and the debug HUD, when shown, are drawn over everything.
+///
+/// # Arguments
+///
+/// * ui - The UI to draw the overlay with.
+/// * app_state - The current application state.
+/// * world - Provides the cutscene letterbox, the level banner, and the level's signs.
+/// * game_state - Provides the score, lives, and continues left.
+/// * flash - Opacity of the damage flash drawn around the screen edges after losing a
+///   life, as capped by the flash limiter.
+/// * debug_hud - The debug HUD, drawn on the left below the lives when shown.
+/// * controls - The bindings to list while the controls help is shown.
+///
+/// # Returns
+///
+/// The menu action of the button clicked this frame, if any.
+fn build_overlay(
+    ui: &mut Ui,
+    app_state: AppState,
+    world: &World,
+    game_state: &GameState,
+    flash: f32,
+    debug_hud: &DebugHud,
+    controls: Option<&ActionMap>,
+) -> Option<MenuAction> {
+    // Nearest to the camera so the overlay covers everything, with the
+    // pause screen over the letterbox bars and banner
+    let letterbox_z = RenderLayer::Letterbox.depth(0);
+    let banner_z = RenderLayer::Hud.depth(0);
+    let banner_text_z = RenderLayer::Hud.depth(1);
+    let dim_z = RenderLayer::Pause.depth(0);
+    let panel_z = RenderLayer::Pause.depth(1);
+    let symbol_z = RenderLayer::Pause.depth(2);
+    let help_z = RenderLayer::Help.depth(0);
+    let help_text_z = RenderLayer::Help.depth(1);
+    let debug_z = RenderLayer::Debug.depth(0);
+    let debug_text_z = RenderLayer::Debug.depth(1);
+
+    let letterbox = world.cutscenes.letterbox();
+    if letterbox > 0.0 {
+        // Each bar covers up to an eighth of the screen height (2.0 in clip space)
+        let bar_height = 0.25 * letterbox;
+        let bar_y = 1.0 - bar_height / 2.0;
+        ui.panel(Rect::new(0.0, bar_y, 2.0, bar_height), letterbox_z, [0.0, 0.0, 0.0, 1.0]);
+        ui.panel(Rect::new(0.0, -bar_y, 2.0, bar_height), letterbox_z, [0.0, 0.0, 0.0, 1.0]);
+    }
+
+    if flash > 0.0 {
+        // Nested frames around the edges build up to a vignette, strongest at the edges.
+        // Each is drawn in front of the last, so where they overlap they all blend
+        // rather than failing the depth test
+        let rings = 4;
+        let color = [0.9, 0.05, 0.05, flash / rings as f32];
+        for ring in 1..=rings {
+            let flash_z = RenderLayer::Flash.depth(ring as i8);
+            let thickness = 0.08 * ring as f32;
+            let side_height = 2.0 - 2.0 * thickness;
+            let edge = 1.0 - thickness / 2.0;
+            ui.panel(Rect::new(0.0, edge, 2.0, thickness), flash_z, color);
+            ui.panel(Rect::new(0.0, -edge, 2.0, thickness), flash_z, color);
+            ui.panel(Rect::new(-edge, 0.0, thickness, side_height), flash_z, color);
+            ui.panel(Rect::new(edge, 0.0, thickness, side_height), flash_z, color);
+        }
+    }
+
+    if debug_hud.visible() {
+        let style = TextStyle {
+            height: 0.05,
+            color: [0.6, 1.0, 0.6, 1.0],
+            z: debug_text_z,
+            align: TextAlign::Left,
+        };
+        let lines = debug_hud.lines();
+        let hud = lines.join("\n");
+
+        // A backing panel sized to the text, on the left below the lives
+        let margin = 0.03;
+        let width = ui.text_renderer().width(&hud, style.height) + 2.0 * margin;
+        let height = ui.text_renderer().line_spacing(style.height) * lines.len() as f32 + margin;
+        let top = 0.82;
+        ui.panel(Rect::new(-1.0 + width / 2.0, top - height / 2.0, width, height), debug_z, [0.0, 0.0, 0.0, 0.6]);
+        ui.label(&hud, (-1.0 + margin, top - margin - style.height / 2.0), &style);
+    }
+
+    if let Some(action_map) = controls {
+        let style = TextStyle {
+            height: 0.045,
+            color: [0.9, 0.9, 0.9, 1.0],
+            z: help_text_z,
+            align: TextAlign::Left,
+        };
+
+        // One row per binding, in columns for the action, its keys, and its gamepad buttons
+        let rows: Vec<[String; 3]> = action_map
+            .bindings()
+            .iter()
+            .map(|binding| {
+                let keys: Vec<String> = binding.keys.iter().map(|&key| key_label(key)).collect();
+                let buttons: Vec<&str> = binding.buttons.iter().map(|button| button.label()).collect();
+                [binding.action.label().to_string(), keys.join(" / "), buttons.join(" / ")]
+            })
+            .collect();
+        let columns = [0, 1, 2].map(|column| {
+            std::iter::once(["ACTION", "KEYBOARD", "GAMEPAD"][column].to_string())
+                .chain(rows.iter().map(|row| row[column].clone()))
+                .collect::<Vec<_>>()
+                .join("\n")
+        });
+
+        let text = ui.text_renderer();
+        let gap = 0.08;
+        let widths = columns.clone().map(|column| text.width(&column, style.height));
+        let width = widths.iter().sum::<f32>() + 4.0 * gap;
+        // Room for the title, a blank line, the column headings, the rows, and a margin
+        let height = text.line_spacing(style.height) * (rows.len() + 4) as f32;
+        ui.panel(Rect::new(0.0, 0.0, width, height), help_z, [0.05, 0.05, 0.1, 0.9]);
+
+        let title_style = TextStyle {
+            color: [1.0, 0.95, 0.8, 1.0],
+            align: TextAlign::Center,
+            ..style
+        };
+        let top = height / 2.0 - ui.text_renderer().line_spacing(style.height);
+        ui.label("CONTROLS", (0.0, top), &title_style);
+
+        let mut x = -width / 2.0 + gap;
+        let first_row = top - 2.0 * ui.text_renderer().line_spacing(style.height);
+        for (column, column_width) in columns.iter().zip(widths) {
+            ui.label(column, (x, first_row), &style);
+            x += column_width + gap;
+        }
+    }
+
+    match app_state {
+        AppState::Playing => {
+            let lives_style = TextStyle {
+                height: 0.06,
+                color: [1.0, 1.0, 1.0, 1.0],
+                z: banner_text_z,
+                align: TextAlign::Left,
+            };
+            ui.label(&format!("LIVES {}", game_state.lives), (-0.95, 0.9), &lives_style);
+
+            // The breath left below the lives while it isn't full, flashing red once it runs low
+            let oxygen = &world.oxygen;
+            if oxygen.shown() {
+                let air_style = TextStyle {
+                    height: 0.05,
+                    ..lives_style
+                };
+                ui.label("AIR", (-0.95, 0.8), &air_style);
+                let (left, width, height) = (-0.78, 0.4, 0.035);
+                ui.panel(Rect::new(left + width / 2.0, 0.8, width, height), banner_z, [0.0, 0.0, 0.0, 0.6]);
+                let fill = width * oxygen.fraction();
+                let color = if !oxygen.is_low() {
+                    [0.55, 0.85, 1.0, 1.0]
+                } else if (oxygen.air_left() * 4.0).fract() < 0.5 {
+                    [1.0, 0.25, 0.2, 1.0]
+                } else {
+                    [0.5, 0.1, 0.1, 1.0]
+                };
+                ui.panel(Rect::new(left + fill / 2.0, 0.8, fill, height), banner_text_z, color);
+            }
+            let score_style = TextStyle {
+                align: TextAlign::Right,
+                ..lives_style
+            };
+            ui.label(&format!("SCORE {}", game_state.score), (0.95, 0.9), &score_style);
+
+            // The level's objectives below the score, turning green as they're done and
+            // dimmed while optional
+            let mut y = 0.8;
+            for objective in world.objectives.objectives() {
+                let color = match (objective.is_complete(), objective.optional) {
+                    (true, _) => [0.45, 1.0, 0.45, 1.0],
+                    (false, false) => [1.0, 1.0, 1.0, 1.0],
+                    (false, true) => [0.7, 0.7, 0.7, 0.8],
+                };
+                let objective_style = TextStyle {
+                    height: 0.045,
+                    color,
+                    ..score_style
+                };
+                let text = format!("{} {}/{}", objective.label, objective.progress, objective.required);
+                ui.label(&text, (0.95, y), &objective_style);
+                y -= ui.text_renderer().line_spacing(objective_style.height);
+            }
+
+            // A strip across the upper screen with the level number over its name
+            let banner = world.banner.as_ref().and_then(|banner| Some((banner, banner.frame()?)));
+            if let Some((banner, frame)) = banner {
+                let (x, y) = (frame.offset_x, 0.5);
+                ui.panel(Rect::new(x, y, 2.0, 0.32), banner_z, [0.05, 0.05, 0.1, 0.7 * frame.alpha]);
+
+                let title_style = TextStyle {
+                    height: 0.12,
+                    color: [1.0, 0.95, 0.8, frame.alpha],
+                    z: banner_text_z,
+                    align: TextAlign::Center,
+                };
+                let subtitle_style = TextStyle {
+                    height: 0.06,
+                    color: [0.8, 0.85, 0.9, frame.alpha],
+                    ..title_style
+                };
+                let title_y = if banner.subtitle.is_some() { y - 0.04 } else { y };
+                ui.label(&banner.title, (x, title_y), &title_style);
+                if let Some(subtitle) = &banner.subtitle {
+                    ui.label(subtitle, (x, y + 0.09), &subtitle_style);
+                }
+            }
+
+            // The sign being read on a panel in the upper screen, popping up from its middle,
+            // or in its place a hint to read the sign the player is by
+            if let Some(panel) = world.signs.panel() {
+                let text_style = TextStyle {
+                    height: 0.05 * panel.scale,
+                    color: [0.95, 0.95, 0.95, 1.0],
+                    z: banner_text_z,
+                    align: TextAlign::Center,
+                };
+                let title_style = TextStyle {
+                    color: [1.0, 0.95, 0.8, 1.0],
+                    ..text_style
+                };
+                let text = ui.text_renderer();
+                let spacing = text.line_spacing(text_style.height);
+                let lines = panel.text.lines().count() + panel.title.map_or(0, |_| 1);
+                let margin = 0.04 * panel.scale;
+                let width = panel
+                    .title
+                    .into_iter()
+                    .chain([panel.text])
+                    .map(|text_block| text.width(text_block, text_style.height))
+                    .fold(0.0, f32::max)
+                    + 2.0 * margin;
+                let height = spacing * lines as f32 + 2.0 * margin;
+                let center_y = 0.4;
+                ui.panel(Rect::new(0.0, center_y, width, height), banner_z, [0.05, 0.05, 0.1, 0.85]);
+
+                let mut y = center_y + height / 2.0 - margin - spacing / 2.0;
+                if let Some(title) = panel.title {
+                    ui.label(title, (0.0, y), &title_style);
+                    y -= spacing;
+                }
+                ui.label(panel.text, (0.0, y), &text_style);
+            } else if world.signs.can_read() {
+                let hint_style = TextStyle {
+                    height: 0.045,
+                    color: [1.0, 0.95, 0.8, 1.0],
+                    z: banner_text_z,
+                    align: TextAlign::Center,
+                };
+                let hint = "UP: READ";
+                let width = ui.text_renderer().width(hint, hint_style.height) + 0.06;
+                ui.panel(Rect::new(0.0, 0.4, width, 0.08), banner_z, [0.05, 0.05, 0.1, 0.7]);
+                ui.label(hint, (0.0, 0.4), &hint_style);
+            }
+            None
+        }
+        AppState::Paused => {
+            ui.panel(Rect::new(0.0, 0.0, 2.0, 2.0), dim_z, [0.0, 0.0, 0.0, 0.5]);
+            ui.panel(Rect::new(0.0, -0.05, 0.6, 0.7), panel_z, [0.1, 0.1, 0.15, 0.9]);
+            ui.panel(Rect::new(-0.07, 0.1, 0.08, 0.25), symbol_z, [0.95, 0.95, 0.95, 1.0]);
+            ui.panel(Rect::new(0.07, 0.1, 0.08, 0.25), symbol_z, [0.95, 0.95, 0.95, 1.0]);
+
+            let resume = Button {
+                label: "RESUME",
+                rect: Rect::new(0.0, -0.15, 0.5, 0.12),
+                z: symbol_z,
+                selected: false,
+                enabled: true,
+            };
+            let quit = Button {
+                label: "MAIN MENU",
+                rect: Rect::new(0.0, -0.3, 0.5, 0.12),
+                ..resume
+            };
+            let resumed = ui.button(&resume);
+            let quit = ui.button(&quit);
+
+            // Which recorded frame is shown, once scrubbing through them
+            #[cfg(debug_assertions)]
+            if let Some((frame, frames)) = world.scrubber.position() {
+                let style = TextStyle {
+                    height: 0.05,
+                    color: [0.6, 1.0, 0.6, 1.0],
+                    z: symbol_z,
+                    align: TextAlign::Center,
+                };
+                ui.label(&format!("FRAME {} OF {}", frame, frames), (0.0, 0.42), &style);
+            }
+            if resumed {
+                Some(MenuAction::Resume)
+            } else {
+                quit.then_some(MenuAction::QuitToMenu)
+            }
+        }
+        AppState::GameOver(choice) => {
+            ui.panel(Rect::new(0.0, 0.0, 2.0, 2.0), dim_z, [0.0, 0.0, 0.0, 0.7]);
+            ui.panel(Rect::new(0.0, 0.0, 1.4, 0.8), panel_z, [0.1, 0.1, 0.15, 0.9]);
+
+            let title_style = TextStyle {
+                height: 0.14,
+                color: [1.0, 0.4, 0.35, 1.0],
+                z: symbol_z,
+                align: TextAlign::Center,
+            };
+            ui.label("GAME OVER", (0.0, 0.2), &title_style);
+
+            // Highlight the keyboard selection, and grey out retrying without continues
+            let retry = if game_state.continues > 0 {
+                format!("RETRY FROM CHECKPOINT ({} LEFT)", game_state.continues)
+            } else {
+                "NO CONTINUES LEFT".to_string()
+            };
+            let choices = [
+                (GameOverChoice::Retry, retry.as_str(), game_state.continues > 0),
+                (GameOverChoice::ReturnToStart, "RETURN TO START", true),
+            ];
+            let mut clicked = None;
+            for (row, (option, label, enabled)) in choices.into_iter().enumerate() {
+                let button = Button {
+                    label,
+                    rect: Rect::new(0.0, -0.03 - row as f32 * 0.16, 1.2, 0.12),
+                    z: symbol_z,
+                    selected: option == choice,
+                    enabled,
+                };
+                if ui.button(&button) {
+                    clicked = Some(MenuAction::GameOver(option));
+                }
+            }
+            clicked
+        }
+        AppState::LevelComplete(summary) => {
+            ui.panel(Rect::new(0.0, 0.0, 2.0, 2.0), dim_z, [0.0, 0.0, 0.0, 0.5]);
+            ui.panel(Rect::new(0.0, 0.0, 1.2, 0.9), panel_z, [0.1, 0.1, 0.15, 0.9]);
+
+            let title_style = TextStyle {
+                height: 0.12,
+                color: [1.0, 0.95, 0.8, 1.0],
+                z: symbol_z,
+                align: TextAlign::Center,
+            };
+            ui.label("LEVEL COMPLETE", (0.0, 0.3), &title_style);
+
+            let stats_style = TextStyle {
+                height: 0.06,
+                color: [0.8, 0.85, 0.9, 1.0],
+                ..title_style
+            };
+            let best = if summary.new_best {
+                "NEW BEST".to_string()
+            } else {
+                format!("BEST {}", format_time(summary.best_time))
+            };
+            let lines = [
+                format!("TIME {}  {}", format_time(summary.time), best),
+                format!("DEATHS {}", summary.deaths),
+                format!("COLLECTIBLES {} OF {}", summary.found, summary.collectibles),
+                format!("{}% COMPLETE", (summary.completion * 100.0).round()),
+            ];
+            for (row, line) in lines.iter().enumerate() {
+                ui.label(line, (0.0, 0.15 - row as f32 * 0.09), &stats_style);
+            }
+
+            let button = Button {
+                label: "CONTINUE",
+                rect: Rect::new(0.0, -0.3, 0.5, 0.12),
+                z: symbol_z,
+                selected: true,
+                enabled: true,
+            };
+            ui.button(&button).then_some(MenuAction::Continue)
+        }
+        // Drawn by `build_main_menu`, which knows the levels and mods
+        AppState::Menu(_) => None,
+    }
+}
+
+/// Draws the level-select menu: a button for each level beside what's been done in it,
+/// and below them one for each mod showing whether it's on.
+///
+/// # Arguments
+///
+/// * ui - The UI to draw the menu with.
+/// * selected - The entry selected with the keyboard, counting the levels and then the mods.
+/// * menu - The levels and mods to list.
+/// * records - The statistics of the levels played.
+///
+/// # Returns
+///
+/// The menu action of the button clicked this frame, if any.
+fn build_main_menu(ui: &mut Ui, selected: usize, menu: &MainMenu, records: &LevelRecords) -> Option<MenuAction> {
+    let dim_z = RenderLayer::Pause.depth(0);
+    let panel_z = RenderLayer::Pause.depth(1);
+    let symbol_z = RenderLayer::Pause.depth(2);
 
-    // Initialize the input handler
-    let mut input_handler = InputHandler::new();
+    let mods = menu.mods.mods();
+    let row_height = 0.16;
+    // Room for the mods heading and the restart note, when there are mods to list
+    let mods_height = if mods.is_empty() { 0.0 } else { 0.24 };
+    let height = 0.5 + row_height * menu.entry_count() as f32 + mods_height;
+    ui.panel(Rect::new(0.0, 0.0, 2.0, 2.0), dim_z, [0.0, 0.0, 0.0, 0.4]);
+    ui.panel(Rect::new(0.0, 0.0, 1.6, height), panel_z, [0.1, 0.1, 0.15, 0.9]);
 
-    // Initialize the game state
-    let mut game_state = GameState::new();
+    let title_style = TextStyle {
+        height: 0.1,
+        color: [1.0, 0.95, 0.8, 1.0],
+        z: symbol_z,
+        align: TextAlign::Center,
+    };
+    let top = height / 2.0;
+    ui.label("SELECT LEVEL", (0.0, top - 0.15), &title_style);
 
-    // Create the TileMap
-    let tile_map = TileMap::new_ground(
-        0.3,
-        0.3,
-        renderer.tileset_columns,
-        renderer.tileset_rows,
+    // Highlight the keyboard selection
+    let mut labels: Vec<String> = (0..menu.levels.len()).map(|index| format!("LEVEL {}", index + 1)).collect();
+    labels.extend(
+        mods.iter()
+            .map(|mod_| format!("{} {}", mod_.name.to_uppercase(), if mod_.enabled { "ON" } else { "OFF" })),
     );
+    let stats_style = TextStyle {
+        height: 0.045,
+        color: [0.8, 0.85, 0.9, 1.0],
+        align: TextAlign::Left,
+        ..title_style
+    };
+    let mut clicked = None;
+    let mut y = top - 0.36;
+    for (index, label) in labels.iter().enumerate() {
+        if index == menu.levels.len() {
+            y -= 0.04;
+            ui.label("MODS", (0.0, y), &title_style);
+            y -= row_height;
+        }
+        let button = Button {
+            label,
+            rect: Rect::new(-0.35, y, 0.7, 0.12),
+            z: symbol_z,
+            selected: index == selected,
+            enabled: true,
+        };
+        if ui.button(&button) {
+            clicked = Some(menu.action(index));
+        }
 
-    // Calculate scaling factors for each background layer based on their image sizes
-    let window_width = window.inner_size().width as f32;
-    let window_height = window.inner_size().height as f32;
+        // The level's best time, deaths, and completion beside its button
+        if let Some(level) = menu.levels.get(index) {
+            let stats = match records.get(level) {
+                Some(stats) => format!(
+                    "{}  DEATHS {}  {}%",
+                    stats.best_time.map_or("--:--.-".to_string(), format_time),
+                    stats.deaths,
+                    (stats.completion() * 100.0).round()
+                ),
+                None => "NOT PLAYED".to_string(),
+            };
+            ui.label(&stats, (0.05, y), &stats_style);
+        }
+        y -= row_height;
+    }
 
-    let mut background_instances = Vec::new();
+    if !mods.is_empty() {
+        let note_style = TextStyle {
+            height: 0.045,
+            color: [0.8, 0.85, 0.9, 1.0],
+            ..title_style
+        };
+        ui.label("MOD CHANGES APPLY ON RESTART", (0.0, y + 0.02), &note_style);
+    }
+    clicked
+}
 
-    for (i, bg_texture) in renderer.background_textures.iter().enumerate() {
-        let background_scale_x = window_width / bg_texture.width as f32;
-        let background_scale_y = window_height / bg_texture.height as f32;
+/// Formats a time in seconds as minutes, seconds, and tenths, such as `1:05.3`.
+fn format_time(seconds: f32) -> String {
+    let tenths = (seconds * 10.0).round() as u32;
+    format!("{}:{:02}.{}", tenths / 600, tenths / 10 % 60, tenths % 10)
+}
+
+/// Prepares the instance data for the backgrounds, tiles, and the player for rendering.
+///
+/// # Arguments
+///
+/// * world - The level's tile maps, hazards, exits, collectibles, switches, doors, signs, water, portals, ropes, jelly and moving platforms, mounts,
+///   turrets, and projectiles to draw,
+///   and the secret areas fading its foreground tiles.
+/// * backgrounds - The background layers and the unscrolled instance of each.
+/// * props - The decorative props to draw among the background layers.
+/// * game_state - The current state of the game.
+/// * player_sheet - Frame layout of the player's sprite sheet.
+/// * camera - The camera whose position is subtracted from world positions and whose zoom scales them.
+/// * overlay - The screen-space overlay to draw over the scene.
+///
+/// # Returns
+///
+/// The instance data for each draw group.
+fn prepare_instances(
+    world: &World,
+    backgrounds: &Backgrounds,
+    props: &Props,
+    game_state: &GameState,
+    player_sheet: &SpriteSheet,
+    camera: &Camera,
+    overlay: Overlay,
+) -> FrameInstances {
+    let World {
+        level,
+        secret_areas,
+        water,
+        portals,
+        mounts,
+        ropes,
+        jelly_platforms,
+        moving_platforms,
+        hazards,
+        projectiles,
+        collectibles,
+        switches,
+        doors,
+        signs,
+        exits,
+        lighting,
+        decorations,
+        has_player,
+        ..
+    } = world;
 
-        let z = 1.0 - (i as f32 * 0.2); // Example: Furthest layer at z=1.0, closer layers decreasing z
+    let tile_z = RenderLayer::Tiles.depth(0);
+    let decoration_z = RenderLayer::Decorations.depth(0);
+    let jelly_z = RenderLayer::Jelly.depth(0);
+    let platform_z = RenderLayer::Platforms.depth(0);
+    let portal_z = RenderLayer::Portals.depth(0);
+    let rope_z = RenderLayer::Ropes.depth(0);
+    let mount_z = RenderLayer::Mounts.depth(0);
+    let projectile_z = RenderLayer::Projectiles.depth(0);
+    let player_z = RenderLayer::Player.depth(0);
+    let water_z = RenderLayer::Water.depth(0);
 
-        background_instances.push(InstanceData {
+    // Prepare tile instances
+    let mut tiles = tile_instances(&level.tile_map, camera, RenderLayer::Tiles, |_| 1.0);
+    let foreground = tile_instances(&level.foreground, camera, RenderLayer::Foreground, |tile| {
+        secret_areas.alpha_at(tile.position.0, tile.position.1)
+    });
+
+    // Prepare the decorations in view, mirrored by flipping their width
+    let view = camera.view_bounds();
+    let decorations = decorations
+        .in_view(&view)
+        .map(|decoration| {
+            let (width, height) = decoration.size;
+            InstanceData {
+                transform: Renderer::create_transform_matrix(
+                    decoration.position.0 - camera.x,
+                    decoration.position.1 - camera.y,
+                    decoration_z,
+                    if decoration.flipped { -width } else { width },
+                    height,
+                ),
+                sprite_index: 0.0,
+                layer: 0.0,
+                sprite_size: [0.0, 0.0],
+                uv_offset: decoration.uv.0,
+                uv_scale: decoration.uv.1,
+                color: [1.0, 1.0, 1.0, 1.0],
+            }
+        })
+        .collect();
+
+    // Prepare player instance
+    let scale_x = if game_state.facing_right { SPRITE_WIDTH } else { -SPRITE_WIDTH };
+    let scale_y = SPRITE_HEIGHT; // Non-zero scaling
+
+    // Look up the UV rect of the current animation frame
+    let (uv_offset, uv_scale) = player_sheet
+        .frames
+        .get(game_state.sprite_index)
+        .map_or(([0.0, 0.0], [1.0, 1.0]), |frame| (frame.uv_offset, frame.uv_scale));
+
+    // Tint the player when submerged; the water drawn on top adds to the effect
+    let (player_x, player_y) = mounts.rider_position(game_state);
+    let mut player_color = if water.contains(player_x, player_y) {
+        SUBMERGED_TINT
+    } else {
+        [1.0, 1.0, 1.0, 1.0]
+    };
+    let player_alpha = portals.player_alpha();
+    player_color[3] *= player_alpha;
+
+    let player = has_player
+        .then(|| InstanceData {
             transform: Renderer::create_transform_matrix(
-                0.0,                  // x position
-                0.0,                  // y position
-                z,                    // z depth
-                background_scale_x,   // scale_x to fill the window
-                background_scale_y,   // scale_y to fill the window
+                player_x - camera.x,
+                player_y - camera.y,
+                player_z,
+                scale_x,
+                scale_y,
+            ),
+            sprite_index: game_state.sprite_index as f32,
+            layer: 0.0,
+            sprite_size: [0.0, 0.0], // Frame rects are given directly as UVs
+            uv_offset,
+            uv_scale,
+            color: player_color,
+        })
+        .into_iter()
+        .collect();
+
+    // Prepare light instances, with the player's light following them, widened by the
+    // lanterns they've picked up, only in the dark. The light material reads how strongly
+    // each flickers from the color's alpha, and offsets their flickers by `sprite_index`
+    let player_light_radius = lighting.player_light + collectibles.light_gained(game_state);
+    let player_light = (*has_player && player_light_radius > 0.0).then_some(PointLight {
+        position: (player_x, player_y),
+        radius: player_light_radius,
+        color: [1.0, 1.0, 1.0],
+        flicker: 0.0,
+    });
+    let lights = lighting
+        .lights
+        .iter()
+        .chain(&player_light)
+        .filter(|_| lighting.ambient.is_some())
+        .enumerate()
+        .map(|(index, light)| InstanceData {
+            transform: Renderer::create_transform_matrix(
+                light.position.0 - camera.x,
+                light.position.1 - camera.y,
+                0.0,
+                light.radius * 2.0,
+                light.radius * 2.0,
+            ),
+            sprite_index: index as f32,
+            layer: 0.0,
+            sprite_size: [0.0, 0.0],
+            uv_offset: [0.0, 0.0],
+            uv_scale: [1.0, 1.0],
+            color: [light.color[0], light.color[1], light.color[2], light.flicker],
+        })
+        .collect();
+
+    // Prepare water instances; the material reads the surface flag from `sprite_index`
+    // and the height of the surface to reflect the scene about from `uv_offset`
+    let water = water
+        .cells
+        .iter()
+        .map(|cell| InstanceData {
+            transform: Renderer::create_transform_matrix(
+                cell.position.0 - camera.x,
+                cell.position.1 - camera.y,
+                water_z,
+                water.cell_width,
+                water.cell_height,
+            ),
+            sprite_index: if cell.surface { 1.0 } else { 0.0 },
+            layer: 0.0,
+            sprite_size: [0.0, 0.0],
+            uv_offset: [cell.surface_y, 0.0],
+            uv_scale: [1.0, 1.0],
+            color: WATER_TINT,
+        })
+        .collect();
+
+    // Prepare portal instances
+    let portals = portals
+        .portals
+        .iter()
+        .map(|portal| InstanceData {
+            transform: Renderer::create_transform_matrix(
+                (portal.bounds.min_x + portal.bounds.max_x) / 2.0 - camera.x,
+                (portal.bounds.min_y + portal.bounds.max_y) / 2.0 - camera.y,
+                portal_z,
+                portal.bounds.max_x - portal.bounds.min_x,
+                portal.bounds.max_y - portal.bounds.min_y,
             ),
             sprite_index: 0.0,
-            _padding1: 0.0,
-            sprite_size: [1.0, 1.0],
+            layer: 0.0,
+            sprite_size: [0.0, 0.0],
             uv_offset: [0.0, 0.0],
             uv_scale: [1.0, 1.0],
-        });
-    }
+            color: PORTAL_TINT,
+        })
+        .collect();
+
+    // Prepare the shadows on the ground below the player, unless riding, the mounts, and
+    // the collectibles not yet picked up, fainter the higher each is above it
+    let shadow_z = RenderLayer::Shadows.depth(0);
+    let player_shadow = (*has_player && !mounts.is_riding()).then(|| (game_state.drawn_bounds(), player_alpha));
+    let mount_shadows = mounts.mounts.iter().enumerate().map(|(index, mount)| {
+        let (x, y) = mounts.draw_position(index, game_state);
+        (Aabb::from_center(x, y, mount.size.0, mount.size.1), 1.0)
+    });
+    let collectible_shadows = collectibles
+        .collectibles
+        .iter()
+        .filter(|collectible| !collectible.is_collected(game_state))
+        .map(|collectible| (collectible.bounds, 1.0));
+    let shadows = player_shadow
+        .into_iter()
+        .chain(mount_shadows)
+        .chain(collectible_shadows)
+        .filter_map(|(bounds, alpha)| Some((Shadow::cast(&level.tile_map, &bounds)?, alpha)))
+        .map(|(shadow, alpha)| InstanceData {
+            transform: Renderer::create_transform_matrix(
+                shadow.position.0 - camera.x,
+                shadow.position.1 - camera.y,
+                shadow_z,
+                shadow.size.0,
+                shadow.size.1,
+            ),
+            sprite_index: 0.0,
+            layer: 0.0,
+            sprite_size: [0.0, 0.0],
+            uv_offset: [0.0, 0.0],
+            uv_scale: [1.0, 1.0],
+            color: [0.0, 0.0, 0.0, shadow.opacity * alpha],
+        })
+        .collect();
 
-    // Timing variables for frame timing
-    let mut last_frame_time = std::time::Instant::now();
+    // Prepare mount instances from their tileset tiles
+    let tile_size_u = 1.0 / level.tile_map.tileset_columns as f32;
+    let tile_size_v = 1.0 / level.tile_map.tileset_rows as f32;
+    let mounts = mounts
+        .mounts
+        .iter()
+        .enumerate()
+        .map(|(index, mount)| {
+            let (x, y) = mounts.draw_position(index, game_state);
+            let u = (mount.tile_index % level.tile_map.tileset_columns) as f32 * tile_size_u;
+            let v = (mount.tile_index / level.tile_map.tileset_columns) as f32 * tile_size_v;
+            InstanceData {
+                transform: Renderer::create_transform_matrix(
+                    x - camera.x,
+                    y - camera.y,
+                    mount_z,
+                    mount.size.0,
+                    mount.size.1,
+                ),
+                sprite_index: 0.0,
+                layer: 0.0,
+                sprite_size: [0.0, 0.0],
+                uv_offset: [u, v],
+                uv_scale: [tile_size_u, tile_size_v],
+                color: [1.0, 1.0, 1.0, 1.0],
+            }
+        })
+        .collect();
 
-    // Run the event loop
-    event_loop.run(move |event, _, control_flow| {
-        *control_flow = ControlFlow::Poll; // Keep the event loop running
+    // Prepare hazard instances, repeating their tile in squares along their length
+    tiles.extend(hazards.hazards.iter().flat_map(|hazard| {
+        strip_instances(&level.tile_map, hazard.bounds, hazard.tile_index, hazard.tile_span, camera, tile_z, 1.0)
+            .map(|instance| instance.rotated(hazard.angle))
+    }));
 
-        match event {
-            Event::WindowEvent { event, .. } => handle_window_event(event, control_flow, &mut input_handler),
-            Event::MainEventsCleared => {
-                let delta_time = update_game_state(&mut game_state, &input_handler, &mut last_frame_time);
+    // Prepare exit instances the same way
+    tiles.extend(exits.exits.iter().flat_map(|exit| {
+        strip_instances(&level.tile_map, exit.bounds, exit.tile_index, exit.tile_span, camera, tile_z, 1.0)
+    }));
 
-                let (tile_instances, player_instances) = prepare_instances(&tile_map, &game_state, &renderer);
+    // Prepare instances for the turrets not yet shot down
+    tiles.extend(
+        projectiles
+            .turrets
+            .iter()
+            .filter(|turret| !turret.is_destroyed(game_state))
+            .flat_map(|turret| {
+                strip_instances(&level.tile_map, turret.bounds, turret.tile_index, turret.tile_span, camera, tile_z, 1.0)
+            }),
+    );
 
-                update_instance_buffers(
-                    &renderer,
-                    &background_instances,
-                    &tile_instances,
-                    &player_instances,
-                );
+    // Prepare instances for the collectibles not yet picked up
+    tiles.extend(
+        collectibles
+            .collectibles
+            .iter()
+            .filter(|collectible| !collectible.is_collected(game_state))
+            .flat_map(|collectible| {
+                strip_instances(
+                    &level.tile_map,
+                    collectible.bounds,
+                    collectible.tile_index,
+                    collectible.tile_span,
+                    camera,
+                    tile_z,
+                    1.0,
+                )
+                .map(|instance| instance.rotated(collectible.angle))
+            }),
+    );
 
-                render_frame(&renderer, &tile_instances, &player_instances);
+    // Prepare switch instances, showing whether each is on
+    tiles.extend(switches.switches.iter().flat_map(|switch| {
+        let tile_index = if game_state.is_object_on(&switch.id) {
+            switch.tile_on_index
+        } else {
+            switch.tile_index
+        };
+        strip_instances(&level.tile_map, switch.bounds, tile_index, switch.tile_span, camera, tile_z, 1.0)
+    }));
 
-                // Frame limiting for consistent rendering (60 FPS)
-                let frame_duration = std::time::Duration::from_secs_f32(1.0 / 60.0);
-                std::thread::sleep(frame_duration.saturating_sub(last_frame_time.elapsed()));
-            }
-            _ => {}
-        }
-    });
-}
+    // Prepare door instances, faded out while open
+    tiles.extend(doors.doors.iter().flat_map(|door| {
+        let alpha = if game_state.is_object_on(&door.id) { 0.25 } else { 1.0 };
+        strip_instances(&level.tile_map, door.bounds, door.tile_index, door.tile_span, camera, tile_z, alpha)
+    }));
+
+    // Prepare sign instances
+    tiles.extend(signs.signs.iter().flat_map(|sign| {
+        strip_instances(&level.tile_map, sign.bounds, sign.tile_index, sign.tile_span, camera, tile_z, 1.0)
+    }));
+
+    // Prepare moving platform instances where the platforms are now
+    let platforms = moving_platforms
+        .platforms
+        .iter()
+        .flat_map(|platform| {
+            strip_instances(
+                &level.tile_map,
+                platform.bounds,
+                platform.tile_index,
+                platform.tile_span,
+                camera,
+                platform_z,
+                1.0,
+            )
+        })
+        .collect();
+
+    // Prepare rope instances, one rotated tile per segment
+    let ropes = ropes
+        .ropes
+        .iter()
+        .flat_map(|rope| {
+            let u = (rope.tile_index % level.tile_map.tileset_columns) as f32 * tile_size_u;
+            let v = (rope.tile_index / level.tile_map.tileset_columns) as f32 * tile_size_v;
+            rope.segments().map(move |(a, b)| {
+                let (dx, dy) = (b.0 - a.0, b.1 - a.1);
+                InstanceData {
+                    transform: Renderer::create_rotated_transform_matrix(
+                        (a.0 + b.0) / 2.0 - camera.x,
+                        (a.1 + b.1) / 2.0 - camera.y,
+                        rope_z,
+                        (dx * dx + dy * dy).sqrt(),
+                        rope.thickness(),
+                        dy.atan2(dx),
+                        (0.0, 0.0),
+                    ),
+                    sprite_index: 0.0,
+                    layer: 0.0,
+                    sprite_size: [0.0, 0.0],
+                    uv_offset: [u, v],
+                    uv_scale: [tile_size_u, tile_size_v],
+                    color: [1.0, 1.0, 1.0, 1.0],
+                }
+            })
+        })
+        .collect();
+
+    // Prepare projectile instances from their sprite strip, flipped to face their flight
+    let projectiles = projectiles
+        .projectiles
+        .iter()
+        .map(|projectile| InstanceData {
+            transform: Renderer::create_transform_matrix(
+                projectile.position.0 - camera.x,
+                projectile.position.1 - camera.y,
+                projectile_z,
+                if projectile.facing_right() { PROJECTILE_SIZE } else { -PROJECTILE_SIZE },
+                PROJECTILE_SIZE,
+            ),
+            sprite_index: projectile.frame() as f32,
+            layer: 0.0,
+            sprite_size: [1.0 / PROJECTILE_FRAMES as f32, 1.0],
+            uv_offset: [0.0, 0.0],
+            uv_scale: [1.0, 1.0],
+            color: [1.0, 1.0, 1.0, 1.0],
+        })
+        .collect();
+
+    // Build the jelly platforms' deformed shapes into one mesh, drawn with a single
+    // instance since the vertices are already in camera space
+    let mut jelly_mesh = Mesh::new();
+    for platform in &jelly_platforms.platforms {
+        let u = (platform.tile_index % level.tile_map.tileset_columns) as f32 * tile_size_u;
+        let v = (platform.tile_index / level.tile_map.tileset_columns) as f32 * tile_size_v;
+        let to_camera = |(x, y): (f32, f32)| (x - camera.x, y - camera.y);
+        let top: Vec<_> = platform.surface().map(to_camera).collect();
+        let bottom: Vec<_> = platform.base().map(to_camera).collect();
+        jelly_mesh.add_strip(&top, &bottom, [u, v], [tile_size_u, tile_size_v]);
+    }
+    let jelly = if jelly_mesh.indices.is_empty() {
+        Vec::new()
+    } else {
+        vec![InstanceData {
+            transform: Renderer::create_transform_matrix(0.0, 0.0, jelly_z, 1.0, 1.0),
+            sprite_index: 0.0,
+            layer: 0.0,
+            sprite_size: [0.0, 0.0],
+            uv_offset: [0.0, 0.0],
+            uv_scale: [1.0, 1.0],
+            color: [1.0, 1.0, 1.0, 1.0],
+        }]
+    };
+
+    // Props sit between the background layers, so they scroll with their
+    // layer's parallax instead of following the camera zoom
+    let props = props
+        .sprites(camera)
+        .map(|sprite| InstanceData {
+            transform: Renderer::create_transform_matrix(
+                sprite.position.0,
+                sprite.position.1,
+                sprite.depth,
+                if sprite.facing_right { sprite.size } else { -sprite.size },
+                sprite.size,
+            ),
+            sprite_index: sprite.frame as f32,
+            layer: 0.0,
+            sprite_size: [1.0 / PROP_FRAMES as f32, 1.0],
+            uv_offset: [0.0, 0.0],
+            uv_scale: [1.0, 1.0],
+            color: [1.0, 1.0, 1.0, 1.0],
+        })
+        .collect();
+
+    let mut frame = FrameInstances {
+        ambient: lighting.ambient,
+        lights,
+        backgrounds: scroll_backgrounds(backgrounds, camera),
+        props,
+        overlay,
+        jelly,
+        jelly_mesh,
+        platforms,
+        shadows,
+        tiles,
+        decorations,
+        portals,
+        ropes,
+        mounts,
+        projectiles,
+        player,
+        // Filled in separately by `particle_instances`
+        particles: Vec::new(),
+        water,
+        foreground,
+    };
+
+    // Zoom everything in the world about the center of the view; the overlay stays put
+    for group in [
+        &mut frame.lights,
+        &mut frame.tiles,
+        &mut frame.decorations,
+        &mut frame.jelly,
+        &mut frame.platforms,
+        &mut frame.shadows,
+        &mut frame.portals,
+        &mut frame.ropes,
+        &mut frame.mounts,
+        &mut frame.projectiles,
+        &mut frame.player,
+        &mut frame.water,
+        &mut frame.foreground,
+    ] {
+        apply_zoom(group, camera.zoom);
+    }
 
+    frame
+}
 
-/// Handles window-related events such as closing the application and keyboard input.
+/// Prepares the instance data for the effect particles, zoomed like the rest of the world.
 ///
 /// # Arguments
 ///
-/// * event - The event triggered by the window.
-/// * control_flow - Used to control the flow of the event loop.
-/// * input_handler - The input handler to update with keyboard inputs.
-fn handle_window_event(
-    event: WindowEvent,
-    control_flow: &mut ControlFlow,
-    input_handler: &mut InputHandler,
-) {
-    match event {
-        WindowEvent::CloseRequested => *control_flow = ControlFlow::Exit,
-        WindowEvent::KeyboardInput { input, .. } => input_handler.handle_keyboard_input(input),
-        _ => {}
-    }
+/// * effects - The effects whose particles to draw.
+/// * camera - The camera whose position is subtracted from world positions and whose zoom scales them.
+fn particle_instances(effects: &Effects, camera: &Camera) -> Vec<InstanceData> {
+    let particle_z = RenderLayer::Particles.depth(0);
+
+    let mut particles: Vec<InstanceData> = effects
+        .sprites()
+        .map(|particle| InstanceData {
+            transform: Renderer::create_transform_matrix(
+                particle.position.0 - camera.x,
+                particle.position.1 - camera.y,
+                particle_z,
+                particle.size.0,
+                particle.size.1,
+            ),
+            sprite_index: 0.0,
+            layer: 0.0,
+            sprite_size: [0.0, 0.0],
+            uv_offset: [0.0, 0.0],
+            uv_scale: [1.0, 1.0],
+            color: particle.color,
+        })
+        .collect();
+    apply_zoom(&mut particles, camera.zoom);
+    particles
 }
 
-/// Updates the game state, including handling input, physics, and animation.
+/// Scales instances' view-space positions and sizes by the camera zoom.
 ///
 /// # Arguments
 ///
-/// * game_state - The current state of the game.
-/// * input_handler - Provides the current input state.
-/// * last_frame_time - Tracks the time of the last frame for calculating delta time.
+/// * instances - Instances positioned relative to the camera.
+/// * zoom - The camera magnification.
+fn apply_zoom(instances: &mut [InstanceData], zoom: f32) {
+    for instance in instances {
+        // Scale the x and y rows of the transform, leaving depth untouched
+        for column in &mut instance.transform {
+            column[0] *= zoom;
+            column[1] *= zoom;
+        }
+    }
+}
+
+/// Builds instance data for the tiles of a tile map in the camera's view, layer by
+/// layer from the back, each at its own depth. Only the chunks of each layer that
+/// overlap the view are gone through, so large levels cost no more to draw.
 ///
-/// # Returns
+/// # Arguments
 ///
-/// The time delta between the current and the last frame.
-fn update_game_state(
-    game_state: &mut GameState,
-    input_handler: &InputHandler,
-    last_frame_time: &mut std::time::Instant,
-) -> f32 {
-    let now = std::time::Instant::now();
-    let delta_time = now.duration_since(*last_frame_time).as_secs_f32();
-    *last_frame_time = now;
+/// * tile_map - The tile map to draw.
+/// * camera - The camera whose view is drawn and whose position is subtracted from world positions.
+/// * render_layer - Layer of the map, which layers without a depth of their own are stacked on.
+/// * alpha - Returns the opacity of each tile.
+fn tile_instances(
+    tile_map: &TileMap,
+    camera: &Camera,
+    render_layer: RenderLayer,
+    alpha: impl Fn(&Tile) -> f32,
+) -> Vec<InstanceData> {
+    let tile_size_u = 1.0 / tile_map.tileset_columns as f32;
+    let tile_size_v = 1.0 / tile_map.tileset_rows as f32;
+    let view = camera.view_bounds();
 
-    game_state.update(input_handler, delta_time);
+    tile_map
+        .layer_depths(render_layer)
+        .flat_map(|(layer, z)| layer.tiles_in(&view).map(move |tile| (tile, z)))
+        .map(|(tile, z)| {
+            let u = (tile.tile_index % tile_map.tileset_columns) as f32 * tile_size_u;
+            let v = (tile.tile_index / tile_map.tileset_columns) as f32 * tile_size_v;
 
-    delta_time
+            InstanceData {
+                transform: Renderer::create_transform_matrix(
+                    tile.position.0 - camera.x,
+                    tile.position.1 - camera.y,
+                    z,
+                    tile.size.0,
+                    tile.size.1,
+                ),
+                sprite_index: 0.0,
+                layer: 0.0,
+                sprite_size: [0.0, 0.0],
+                uv_offset: [u, v],
+                uv_scale: [tile_size_u * tile.span.0 as f32, tile_size_v * tile.span.1 as f32],
+                color: [tile.shade, tile.shade, tile.shade, alpha(tile)],
+            }
+        })
+        .collect()
 }
 
-/// Prepares the instance data for tiles and the player for rendering.
-///
-/// # Arguments
+/// Builds instance data repeating a tileset tile in squares along an area's longer side,
+/// as used for hazards, turrets, collectibles, switches, doors, and moving platforms.
 ///
-/// * tile_map - The tile map containing all tiles.
-/// * game_state - The current state of the game.
-/// * renderer - The renderer for accessing tile and texture details.
+/// Art spanning several tileset tiles repeats as a block of the same shape instead,
+/// so a 3x1 platform tile on a platform three times as wide as it's tall draws once.
 ///
-/// # Returns
+/// # Arguments
 ///
-/// A tuple containing vectors of instance data for tiles and the player.
-fn prepare_instances(
+/// * tile_map - The tile map whose tileset the tile comes from.
+/// * bounds - The area to fill, in world coordinates.
+/// * tile_index - Index of the tileset tile to repeat, the top-left of multi-tile art.
+/// * span - Columns and rows of tileset tiles making up the art.
+/// * camera - The camera whose position is subtracted from world positions.
+/// * z - Depth at which the tiles are drawn.
+/// * alpha - Opacity of the tiles.
+fn strip_instances(
     tile_map: &TileMap,
-    game_state: &GameState,
-    renderer: &Renderer,
-) -> (Vec<InstanceData>, Vec<InstanceData>) {
-    let mut tile_instances = Vec::new();
-    let mut player_instances = Vec::new();
+    bounds: Aabb,
+    tile_index: usize,
+    span: (usize, usize),
+    camera: &Camera,
+    z: f32,
+    alpha: f32,
+) -> impl Iterator<Item = InstanceData> {
+    let tile_size_u = 1.0 / tile_map.tileset_columns as f32;
+    let tile_size_v = 1.0 / tile_map.tileset_rows as f32;
+    let u = (tile_index % tile_map.tileset_columns) as f32 * tile_size_u;
+    let v = (tile_index / tile_map.tileset_columns) as f32 * tile_size_v;
 
-    // Prepare tile instances
-    for tile in &tile_map.tiles {
-        let tile_size_u = 1.0 / renderer.tileset_columns as f32;
-        let tile_size_v = 1.0 / renderer.tileset_rows as f32;
-        let u = (tile.tile_index % renderer.tileset_columns) as f32 * tile_size_u;
-        let v = (tile.tile_index / renderer.tileset_columns) as f32 * tile_size_v;
-        let uv_offset = [u, v];
-        let uv_scale = [tile_size_u, tile_size_v];
-
-        let tile_z = 0.0; // Ground level
-        let tile_scale_x = tile_map.tile_width; // e.g., 1.0
-        let tile_scale_y = tile_map.tile_height; // e.g., 1.0
-
-        tile_instances.push(InstanceData {
-            transform: Renderer::create_transform_matrix(
-                tile.position.0,
-                tile.position.1,
-                tile_z,
-                tile_scale_x,
-                tile_scale_y,
-            ),
+    let (width, height) = (bounds.max_x - bounds.min_x, bounds.max_y - bounds.min_y);
+    let vertical = height > width;
+    let size = if vertical { width } else { height };
+    // Length of one repeat along the strip, keeping the art's shape
+    let (columns, rows) = (span.0 as f32, span.1 as f32);
+    let unit = if vertical { size * rows / columns } else { size * columns / rows };
+    let count = ((if vertical { height } else { width }) / unit).round().max(1.0) as usize;
+    let step = (if vertical { height } else { width }) / count as f32;
+    let (camera_x, camera_y) = (camera.x, camera.y);
+
+    (0..count).map(move |i| {
+        let offset = (i as f32 + 0.5) * step;
+        let (x, y, tile_width, tile_height) = if vertical {
+            ((bounds.min_x + bounds.max_x) / 2.0, bounds.min_y + offset, size, step)
+        } else {
+            (bounds.min_x + offset, (bounds.min_y + bounds.max_y) / 2.0, step, size)
+        };
+        InstanceData {
+            transform: Renderer::create_transform_matrix(x - camera_x, y - camera_y, z, tile_width, tile_height),
             sprite_index: 0.0,
-            _padding1: 0.0,
+            layer: 0.0,
             sprite_size: [0.0, 0.0],
-            uv_offset,
-            uv_scale,
-        });
-    }
+            uv_offset: [u, v],
+            uv_scale: [tile_size_u * columns, tile_size_v * rows],
+            color: [1.0, 1.0, 1.0, alpha],
+        }
+    })
+}
 
-    // Prepare player instance
-    let player_z = -0.5; // In front of tiles
-    let scale_x = if game_state.facing_right { 0.3 } else { -0.3 };
-    let scale_y = 0.3; // Non-zero scaling
-
-    // Calculate UV offset and scale for player
-    let sprite_width = 1.0 / 24.0; // Fixed sprite width (24 columns in the tileset)
-    let sprite_height = 1.0;      // Full height for a single sprite
-    let uv_offset = [0.0, 0.0];   // Hardcoded to match the working code
-    let uv_scale = [1.0, 1.0];    // Matches the entire texture dimensions
-
-    player_instances.push(InstanceData {
-        transform: Renderer::create_transform_matrix(
-            game_state.player_x,
-            game_state.player_y,
-            player_z,
-            scale_x,
-            scale_y,
-        ),
-        sprite_index: game_state.sprite_index as f32,
-        _padding1: 0.0,
-        sprite_size: [sprite_width, sprite_height],
-        uv_offset,
-        uv_scale,
-    });
+/// Updates the instance buffer data for the renderer.
+///
+/// Instances are laid out as backgrounds, props, tiles, decorations, jelly, moving platforms, shadows, portals, ropes, mounts, projectiles, player, particles, water,
+/// foreground tiles, then the overlay. The jelly platform mesh is uploaded alongside them.
+///
+/// # Arguments
+///
+/// * renderer - The renderer to update the buffers for, growing its instance buffer to fit.
+/// * instances - Instance data for the backgrounds, props, tiles, decorations, jelly, moving platforms, shadows, portals, ropes, mounts, projectiles, player, particles, water, foreground, and overlay.
+/// * batcher - Gathers the instances into one upload, leaving the range each group was written to.
+///
+/// # Returns
+///
+/// The number of indices of the jelly platform mesh to draw, and the bytes written to the buffers.
+fn update_instance_buffers(
+    renderer: &mut Renderer,
+    instances: &FrameInstances,
+    batcher: &mut InstanceBatcher,
+) -> (u32, u64) {
+    instances.batch(batcher);
+    renderer.reserve_instances(batcher.len());
+    let instance_bytes = batcher.upload(&renderer.queue, &renderer.instance_buffer);
 
-    (tile_instances, player_instances)
+    let mesh = &instances.jelly_mesh;
+    let index_count = renderer.upload_mesh(mesh);
+    let mesh_bytes = if index_count > 0 {
+        std::mem::size_of_val(mesh.vertices.as_slice()) + std::mem::size_of_val(mesh.indices.as_slice())
+    } else {
+        0
+    };
+    (index_count, instance_bytes + mesh_bytes as u64)
 }
 
+/// Draws a batch's range of the instance buffer with the given material.
+///
+/// # Arguments
+///
+/// * render_pass - The active render pass.
+/// * renderer - The renderer owning the buffers.
+/// * pipeline - The material pipeline to draw with.
+/// * bind_group - The bind group for the material (the sprite array, or the globals).
+/// * instances - The batch's range of the instance buffer.
+///
+/// # Returns
+///
+/// The number of draw calls issued: none if there are no instances, otherwise one.
+fn draw_instances<'a>(
+    render_pass: &mut wgpu::RenderPass<'a>,
+    renderer: &'a Renderer,
+    pipeline: &'a wgpu::RenderPipeline,
+    bind_group: &'a wgpu::BindGroup,
+    instances: Range<u32>,
+) -> usize {
+    if instances.is_empty() {
+        return 0;
+    }
 
+    let instance_size = std::mem::size_of::<InstanceData>() as wgpu::BufferAddress;
+    let start = instances.start as wgpu::BufferAddress * instance_size;
+    let end = instances.end as wgpu::BufferAddress * instance_size;
 
+    render_pass.set_pipeline(pipeline);
+    render_pass.set_bind_group(0, bind_group, &[]);
+    render_pass.set_vertex_buffer(0, renderer.vertex_buffer.slice(..));
+    render_pass.set_vertex_buffer(1, renderer.instance_buffer.slice(start..end));
+    render_pass.draw_indexed(0..renderer.num_indices, 0, 0..instances.len() as u32);
+    1
+}
 
-/// Updates the instance buffer data for the renderer.
+/// Takes the next batches, joined into the one range of the instance buffer they
+/// cover together, as batches are laid out back to back.
 ///
 /// # Arguments
 ///
-/// * renderer - The renderer to update the buffers for.
-/// * background_instances - Instance data for the background layers.
-/// * tile_instances - Instance data for tiles.
-/// * player_instances - Instance data for the player.
-fn update_instance_buffers(
-    renderer: &Renderer,
-    background_instances: &[InstanceData],
-    tile_instances: &[InstanceData],
-    player_instances: &[InstanceData],
-) {
-    let instance_size = std::mem::size_of::<InstanceData>() as wgpu::BufferAddress;
+/// * batches - The ranges of the batches still to draw, in order.
+/// * count - How many batches to take.
+fn take_run(batches: &mut impl Iterator<Item = Range<u32>>, count: usize) -> Range<u32> {
+    batches.take(count).reduce(|run, batch| run.start..batch.end).unwrap_or_default()
+}
 
-    // Calculate buffer offsets
-    let background_instances_size = background_instances.len() as wgpu::BufferAddress * instance_size;
-    let tile_instances_size = tile_instances.len() as wgpu::BufferAddress * instance_size;
-    let player_instances_size = player_instances.len() as wgpu::BufferAddress * instance_size;
-
-    // Write background instances
-    if !background_instances.is_empty() {
-        renderer.queue.write_buffer(
-            &renderer.instance_buffer,
-            0,
-            bytemuck::cast_slice(background_instances),
-        );
+/// Draws the mesh in the mesh buffers with a single instance.
+///
+/// # Arguments
+///
+/// * render_pass - The active render pass.
+/// * renderer - The renderer owning the buffers.
+/// * bind_group - The sprite array, with the instance's layer the texture the mesh's UVs refer to.
+/// * instance - Index of the instance in the instance buffer.
+/// * index_count - Number of mesh indices to draw.
+///
+/// # Returns
+///
+/// The number of draw calls issued: none if the mesh is empty, otherwise one.
+fn draw_mesh<'a>(
+    render_pass: &mut wgpu::RenderPass<'a>,
+    renderer: &'a Renderer,
+    bind_group: &'a wgpu::BindGroup,
+    instance: u32,
+    index_count: u32,
+) -> usize {
+    if index_count == 0 {
+        return 0;
     }
 
-    // Write tile instances
-    if !tile_instances.is_empty() {
-        renderer.queue.write_buffer(
-            &renderer.instance_buffer,
-            background_instances_size,
-            bytemuck::cast_slice(tile_instances),
-        );
-    }
+    let instance_size = std::mem::size_of::<InstanceData>() as wgpu::BufferAddress;
+    let start = instance as wgpu::BufferAddress * instance_size;
 
-    // Write player instances
-    if !player_instances.is_empty() {
-        renderer.queue.write_buffer(
-            &renderer.instance_buffer,
-            background_instances_size + tile_instances_size,
-            bytemuck::cast_slice(player_instances),
-        );
-    }
+    render_pass.set_pipeline(&renderer.pipeline);
+    render_pass.set_bind_group(0, bind_group, &[]);
+    render_pass.set_vertex_buffer(0, renderer.mesh_vertex_buffer.slice(..));
+    render_pass.set_vertex_buffer(1, renderer.instance_buffer.slice(start..start + instance_size));
+    render_pass.set_index_buffer(renderer.mesh_index_buffer.slice(..), wgpu::IndexFormat::Uint16);
+    render_pass.draw_indexed(0..index_count, 0, 0..1);
+
+    // Restore the quad indices for the instanced draws that follow
+    render_pass.set_index_buffer(renderer.index_buffer.slice(..), wgpu::IndexFormat::Uint16);
+    1
 }
 
+/// Begins a render pass drawing to a color target, with the quad's index buffer bound.
+///
+/// With MSAA on, the pass draws into the renderer's multisampled target instead, which
+/// is resolved into the color target as the pass ends.
+///
+/// # Arguments
+///
+/// * encoder - The command encoder to record the pass into.
+/// * renderer - The renderer whose index buffer and multisampled target to use.
+/// * view - The color target to draw to.
+/// * depth_view - The depth buffer.
+/// * clear - Whether to clear the depth buffer, rather than keep the previous pass's depth to draw over.
+///   The color target is cleared either way.
+fn begin_render_pass<'a>(
+    encoder: &'a mut wgpu::CommandEncoder,
+    renderer: &'a Renderer,
+    view: &'a wgpu::TextureView,
+    depth_view: &'a wgpu::TextureView,
+    clear: bool,
+) -> wgpu::RenderPass<'a> {
+    let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+        label: Some(if clear { "Render Pass" } else { "Screen Pass" }),
+        color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+            view: renderer.multisample_view.as_ref().unwrap_or(view),
+            resolve_target: renderer.multisample_view.as_ref().map(|_| view),
+            ops: wgpu::Operations {
+                load: wgpu::LoadOp::Clear(wgpu::Color {
+                    r: 0.1,
+                    g: 0.2,
+                    b: 0.3,
+                    a: 1.0,
+                }),
+                store: true,
+            },
+        })],
+        depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
+            view: depth_view,
+            depth_ops: Some(wgpu::Operations {
+                load: if clear { wgpu::LoadOp::Clear(1.0) } else { wgpu::LoadOp::Load },
+                store: true,
+            }),
+            stencil_ops: None,
+        }),
+    });
 
+    // Ensure index buffer is bound
+    render_pass.set_index_buffer(
+        renderer.index_buffer.slice(..),
+        wgpu::IndexFormat::Uint16,
+    );
+    render_pass
+}
 
-/// Renders a frame by issuing draw calls to the GPU.
+/// Renders a frame to the window's surface and presents it.
 ///
 /// # Arguments
 ///
 /// * renderer - The renderer to use for drawing.
-/// * tile_instances - Instance data for tiles.
-/// * player_instances - Instance data for the player.
+/// * batcher - The uploaded batches, as for `draw_frame`.
+/// * mesh_index_count - Number of indices of the uploaded jelly platform mesh.
+/// * ambient - The level's ambient light, or `None` if it's fully lit.
+///
+/// # Returns
+///
+/// The number of draw calls issued.
 fn render_frame(
     renderer: &Renderer,
-    tile_instances: &[InstanceData],
-    player_instances: &[InstanceData],
-) {
-    let output = match renderer.surface.get_current_texture() {
+    batcher: &InstanceBatcher,
+    mesh_index_count: u32,
+    ambient: Option<[f32; 3]>,
+) -> usize {
+    let Some(surface) = &renderer.surface else {
+        return 0;
+    };
+    // Skip the frame if the surface can't be drawn to, reconfiguring it if it went
+    // stale, such as after a display change
+    let output = match surface.get_current_texture() {
         Ok(output) => output,
+        Err(wgpu::SurfaceError::Lost | wgpu::SurfaceError::Outdated) => {
+            log::info!("Surface is out of date; reconfiguring it");
+            surface.configure(&renderer.device, &renderer.config);
+            return 0;
+        }
+        Err(wgpu::SurfaceError::Timeout) => {
+            log::debug!("Timed out acquiring the next frame; skipping it");
+            return 0;
+        }
         Err(e) => {
-            eprintln!("Failed to acquire next swap chain texture: {:?}", e);
-            return;
+            log::error!("Failed to acquire the next frame: {:?}", e);
+            return 0;
         }
     };
 
-    let view = output
+    let surface_view = output
         .texture
         .create_view(&wgpu::TextureViewDescriptor::default());
+    let draw_calls = draw_frame(renderer, batcher, mesh_index_count, ambient, &surface_view);
+    output.present();
+    draw_calls
+}
+
+/// Draws a frame by issuing draw calls to the GPU.
+///
+/// With water reflections on, everything behind the water is drawn into the
+/// offscreen scene texture, which is copied to the screen before the water,
+/// the foreground, and the overlay are drawn over it, so the water can reflect it.
+///
+/// In the dark, the lights are drawn onto the light map first, and everything but the
+/// overlay is multiplied by it once drawn.
+///
+/// # Arguments
+///
+/// * renderer - The renderer to use for drawing.
+/// * batcher - The uploaded batches: the lights, backgrounds, props, tiles, decorations, jelly, moving platforms, shadows, portals, ropes, mounts, projectiles, player, particles, water, foreground, and overlay.
+/// * mesh_index_count - Number of indices of the uploaded jelly platform mesh.
+/// * ambient - The level's ambient light, or `None` if it's fully lit.
+/// * target - Where the finished frame goes: the surface's texture, or an offscreen target.
+///
+/// # Returns
+///
+/// The number of draw calls issued.
+fn draw_frame(
+    renderer: &Renderer,
+    batcher: &InstanceBatcher,
+    mesh_index_count: u32,
+    ambient: Option<[f32; 3]>,
+    target: &wgpu::TextureView,
+) -> usize {
+    let mut draw_calls = 0;
+
+    // The frame is drawn offscreen, then copied onto the target through the post-processing
+    // effects: drawn to the canvas with a virtual resolution, scaled as it's copied
+    let view = match (&renderer.letterbox, renderer.active_effects()) {
+        (Some(letterbox), _) => letterbox.canvas_view(),
+        (None, Some(effects)) => effects.frame_view(),
+        (None, None) => target,
+    };
     let depth_view = renderer
         .depth_texture
         .create_view(&wgpu::TextureViewDescriptor::default());
@@ -304,101 +3785,104 @@ fn render_frame(
             label: Some("Render Encoder"),
         });
 
+    // Advance the GPU particles before drawing them
+    if let Some(particles) = &renderer.particles {
+        let span = renderer.begin_timing(&mut encoder, "PARTICLES");
+        particles.simulate(&mut encoder);
+        renderer.end_timing(&mut encoder, span);
+    }
+
+    // Batches come in the order they were pushed. Sprite batches all sample the
+    // sprite array, so each run of them between other materials is one draw
+    let mut batches = batcher.ranges().iter().cloned();
+
+    // Light the dark before drawing the scene it darkens, unless lighting is off
+    let lights = take_run(&mut batches, 1);
+    let light_map = renderer.light_map.as_ref().filter(|_| ambient.is_some());
+    if let (Some(light_map), Some(ambient)) = (light_map, ambient) {
+        let span = renderer.begin_timing(&mut encoder, "LIGHTS");
+        let mut light_pass = light_map.begin_pass(&mut encoder, ambient);
+        light_pass.set_index_buffer(renderer.index_buffer.slice(..), wgpu::IndexFormat::Uint16);
+        draw_calls += draw_instances(&mut light_pass, renderer, &light_map.light_pipeline, &renderer.globals_bind_group, lights);
+        drop(light_pass);
+        renderer.end_timing(&mut encoder, span);
+    }
+
+    let span = renderer.begin_timing(&mut encoder, "SCENE");
     {
-        let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
-            label: Some("Render Pass"),
-            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
-                view: &view,
-                resolve_target: None,
-                ops: wgpu::Operations {
-                    load: wgpu::LoadOp::Clear(wgpu::Color {
-                        r: 0.1,
-                        g: 0.2,
-                        b: 0.3,
-                        a: 1.0,
-                    }),
-                    store: true,
-                },
-            })],
-            depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
-                view: &depth_view,
-                depth_ops: Some(wgpu::Operations {
-                    load: wgpu::LoadOp::Clear(1.0),
-                    store: true,
-                }),
-                stencil_ops: None,
-            }),
-        });
+        let scene_view = renderer.active_post().map_or(view, |post| post.scene_view());
+        let mut render_pass = begin_render_pass(&mut encoder, renderer, scene_view, &depth_view, true);
+        let sprites = &renderer.sprites.bind_group;
 
-        // Ensure index buffer is bound
-        render_pass.set_index_buffer(
-            renderer.index_buffer.slice(..),
-            wgpu::IndexFormat::Uint16,
-        );
+        // Render background layers, each tiled across the screen, the props drifting
+        // between them, the tiles and their decorations, then the jelly platforms among them
+        let backdrop = take_run(&mut batches, renderer.background_textures.len() + 3);
+        draw_calls += draw_instances(&mut render_pass, renderer, &renderer.pipeline, sprites, backdrop);
+        let jelly = batches.next().unwrap_or_default();
+        draw_calls += draw_mesh(&mut render_pass, renderer, sprites, jelly.start, mesh_index_count);
 
-        // Render background layers
-        for (i, bind_group) in renderer.background_bind_groups.iter().enumerate() {
-            let offset = i as wgpu::BufferAddress * std::mem::size_of::<InstanceData>() as wgpu::BufferAddress;
-            render_pass.set_vertex_buffer(
-                1,
-                renderer.instance_buffer.slice(
-                    offset..offset + std::mem::size_of::<InstanceData>() as wgpu::BufferAddress,
-                ),
-            );
+        // Render moving platforms and the shadows on the ground, portals, then ropes, mounts,
+        // projectiles, the player, and particles
+        let platforms = take_run(&mut batches, 2);
+        draw_calls += draw_instances(&mut render_pass, renderer, &renderer.pipeline, sprites, platforms);
+        let portals = take_run(&mut batches, 1);
+        draw_calls += draw_instances(&mut render_pass, renderer, &renderer.portal_pipeline, &renderer.globals_bind_group, portals);
+        let actors = take_run(&mut batches, 5);
+        draw_calls += draw_instances(&mut render_pass, renderer, &renderer.pipeline, sprites, actors);
 
-            render_pass.set_pipeline(&renderer.pipeline);
-            render_pass.set_bind_group(0, bind_group, &[]);
-            render_pass.set_vertex_buffer(0, renderer.vertex_buffer.slice(..));
-            render_pass.set_vertex_buffer(1, renderer.instance_buffer.slice(offset..offset + std::mem::size_of::<InstanceData>() as wgpu::BufferAddress));
-            render_pass.draw_indexed(0..renderer.num_indices, 0, 0..1); // Ensure `num_indices` matches `INDICES`
-        }
-
-        // Render tiles
-        if !tile_instances.is_empty() {
-            let background_instances_size = renderer.background_bind_groups.len() as wgpu::BufferAddress
-                * std::mem::size_of::<InstanceData>() as wgpu::BufferAddress;
-
-            render_pass.set_pipeline(&renderer.pipeline);
-            render_pass.set_bind_group(0, &renderer.tileset_bind_group, &[]);
-            render_pass.set_vertex_buffer(0, renderer.vertex_buffer.slice(..));
-            render_pass.set_vertex_buffer(
-                1,
-                renderer
-                    .instance_buffer
-                    .slice(background_instances_size..background_instances_size + tile_instances.len() as wgpu::BufferAddress * std::mem::size_of::<InstanceData>() as wgpu::BufferAddress),
-            );
-            render_pass.draw_indexed(
-                0..renderer.num_indices,
-                0,
-                0..tile_instances.len() as u32,
-            );
+        // Render the GPU particles straight from their simulation buffer
+        if let Some(particles) = &renderer.particles {
+            particles.draw(&mut render_pass, &renderer.vertex_buffer, renderer.num_indices);
+            draw_calls += 1;
         }
 
-        // Render player
-        if !player_instances.is_empty() {
-            let background_instances_size = renderer.background_bind_groups.len() as wgpu::BufferAddress
-                * std::mem::size_of::<InstanceData>() as wgpu::BufferAddress;
-            let tile_instances_size = tile_instances.len() as wgpu::BufferAddress
-                * std::mem::size_of::<InstanceData>() as wgpu::BufferAddress;
-
-            render_pass.set_pipeline(&renderer.pipeline);
-            render_pass.set_bind_group(0, &renderer.texture_bind_group, &[]);
-            render_pass.set_vertex_buffer(0, renderer.vertex_buffer.slice(..));
-            render_pass.set_vertex_buffer(
-                1,
-                renderer
-                    .instance_buffer
-                    .slice(background_instances_size + tile_instances_size..background_instances_size + tile_instances_size + player_instances.len() as wgpu::BufferAddress * std::mem::size_of::<InstanceData>() as wgpu::BufferAddress),
-            );
-            render_pass.draw_indexed(
-                0..renderer.num_indices,
-                0,
-                0..player_instances.len() as u32,
-            );
+        // Copy the scene to the screen and carry on drawing there, reflecting the scene in
+        // the water. Without reflections the water samples a blank texture it ignores
+        let reflection_bind_group = match renderer.active_post() {
+            Some(post) => {
+                drop(render_pass);
+                render_pass = begin_render_pass(&mut encoder, renderer, view, &depth_view, false);
+                post.blit(&mut render_pass);
+                draw_calls += 1;
+                &post.scene_bind_group
+            }
+            None => &renderer.solid_bind_group,
+        };
+        render_pass.set_bind_group(1, reflection_bind_group, &[]);
+
+        // Render water, then foreground tiles over the player, darken everything so far
+        // outside the light, and draw the overlay over everything
+        let water = take_run(&mut batches, 1);
+        draw_calls += draw_instances(&mut render_pass, renderer, &renderer.water_pipeline, &renderer.globals_bind_group, water);
+        if let Some(light_map) = light_map {
+            let foreground = take_run(&mut batches, 1);
+            draw_calls += draw_instances(&mut render_pass, renderer, &renderer.pipeline, sprites, foreground);
+            light_map.composite(&mut render_pass);
+            draw_calls += 1;
         }
+        let front = take_run(&mut batches, 3);
+        draw_calls += draw_instances(&mut render_pass, renderer, &renderer.pipeline, sprites, front);
+    }
+    renderer.end_timing(&mut encoder, span);
+
+    let span = renderer.begin_timing(&mut encoder, "PRESENT");
+    if let Some(letterbox) = &renderer.letterbox {
+        letterbox.present(&mut encoder, target, &renderer.globals_bind_group);
+        draw_calls += 1;
+    } else if let Some(effects) = renderer.active_effects() {
+        effects.present(&mut encoder, target, &renderer.globals_bind_group);
+        draw_calls += 1;
     }
+    renderer.end_timing(&mut encoder, span);
 
+    // Read back the passes' times once the frame's done, if it was timed
+    if let Some(timer) = &renderer.gpu_timer {
+        timer.resolve(&mut encoder);
+    }
     renderer.queue.submit(Some(encoder.finish()));
-    output.present();
+    if let Some(timer) = &renderer.gpu_timer {
+        timer.read_back();
+    }
+    draw_calls
 }
 
@@ -0,0 +1,13 @@
+//! A 2D platformer engine built on wgpu and winit.
+//!
+//! `App` opens a window and runs the game loop, driving a `Game` of your own through
+//! its hooks, along with any `System`s added to it; the engine's modules are public to
+//! build them from.
+pub mod app;
+pub mod engine;
+pub mod game;
+mod game_loop;
+
+pub use app::{App, AssetManifest, WindowSettings};
+pub use game::{Ctx, Engine, Game, GameEvent, System};
+pub use game_loop::{run_render_tests, RenderTestResult};
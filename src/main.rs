@@ -1,11 +1,71 @@
-mod game_loop;
-mod engine;
+use rust_platformer_engine::engine::level::bundle::{LevelBundle, BUNDLE_EXTENSION};
+use rust_platformer_engine::engine::mods::{set_mods, ModList, MODS_DIR};
+use rust_platformer_engine::{run_render_tests, RenderTestResult};
+use std::path::{Path, PathBuf};
 
 fn main() {
     // Initialize the logger
     env_logger::init();
 
-    // Log that the game loop is starting
-    log::info!("Starting the game loop...");
-    game_loop::run();
+    // Level bundles are exported and imported, and the rendering tests run, from the
+    // command line without opening a window; the games themselves are the examples
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    match args.iter().map(String::as_str).collect::<Vec<_>>().as_slice() {
+        ["--export-level", level, output] => export_level(level, output),
+        ["--import-level", bundle] => import_level(bundle),
+        ["--render-tests"] => render_tests(false),
+        ["--render-tests", "--update"] => render_tests(true),
+        _ => {
+            eprintln!("Usage:");
+            eprintln!("  --export-level <level> <output>  Pack a level and its files into a bundle");
+            eprintln!("  --import-level <bundle>          Install a level bundle as a mod");
+            eprintln!("  --render-tests [--update]        Run the headless rendering tests");
+            eprintln!("To play the dino game, run `cargo run --example dino [-- --seed <seed>]`.");
+            std::process::exit(2);
+        }
+    }
+}
+
+/// Draws the test scenes and compares them against their references, exiting with
+/// an error status if any fail or there's no graphics adapter to draw them with.
+///
+/// # Arguments
+///
+/// * `update_references` - Whether to replace the reference images rather than compare.
+fn render_tests(update_references: bool) {
+    let passed = run_render_tests(update_references) == RenderTestResult::Passed;
+    std::process::exit(if passed { 0 } else { 1 });
+}
+
+/// Packs a level and the files it uses into a bundle to share, exiting with an
+/// error status if it can't.
+fn export_level(level: &str, output: &str) {
+    // Levels may use files from the enabled mods
+    set_mods(ModList::discover(MODS_DIR));
+    let mut output = PathBuf::from(output);
+    if output.extension().is_none() {
+        output.set_extension(BUNDLE_EXTENSION);
+    }
+    match LevelBundle::export(level).and_then(|bundle| bundle.write(&output)) {
+        Ok(()) => println!("Exported '{}' to '{}'", level, output.display()),
+        Err(e) => {
+            eprintln!("Could not export '{}': {}", level, e);
+            std::process::exit(1);
+        }
+    }
+}
+
+/// Checks a level bundle and installs it as a mod, exiting with an error status if
+/// it's invalid.
+fn import_level(bundle: &str) {
+    match LevelBundle::read(Path::new(bundle)).and_then(|level| {
+        let root = level.install(Path::new(MODS_DIR))?;
+        Ok((level.manifest.name, root))
+    }) {
+        Ok((name, root)) => println!("Installed '{}' into '{}'", name, root.display()),
+        Err(e) => {
+            eprintln!("Could not import '{}': {}", bundle, e);
+            std::process::exit(1);
+        }
+    }
 }
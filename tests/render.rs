@@ -0,0 +1,16 @@
+// render.rs
+use rust_platformer_engine::{run_render_tests, RenderTestResult};
+
+/// Draws the test scenes headlessly and compares them against the reference images in
+/// `tests/render`, skipping on machines without a graphics adapter.
+#[test]
+fn scenes_match_their_references() {
+    match run_render_tests(false) {
+        RenderTestResult::NoAdapter => eprintln!("No graphics adapter found; skipping the rendering tests"),
+        result => assert_eq!(
+            result,
+            RenderTestResult::Passed,
+            "scenes differ from their references; see target/render_tests"
+        ),
+    }
+}